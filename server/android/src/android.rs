@@ -9,7 +9,7 @@ pub mod android {
     use jni::sys::jchar;
     use repository::database_settings::DatabaseSettings;
     use server::{logging_init, start_server};
-    use service::settings::{LogMode, LoggingSettings, ServerSettings, Settings};
+    use service::settings::{LogMode, LoggingSettings, PdfRenderer, ServerSettings, Settings};
     use tokio::sync::mpsc;
 
     use self::jni::objects::{JClass, JString};
@@ -46,6 +46,12 @@ pub mod android {
                 cors_origins: vec!["http://localhost".to_string()],
                 base_dir: Some(files_dir.to_str().unwrap().to_string()),
                 machine_uid: Some(android_id),
+                request_limits: Default::default(),
+                certificates: None,
+                metrics: None,
+                // Chrome isn't available on Android
+                pdf_renderer: PdfRenderer::Native,
+                report_cache: None,
             },
             database: DatabaseSettings {
                 username: "n/a".to_string(),
@@ -63,6 +69,10 @@ pub mod android {
                 LoggingSettings::new(LogMode::File, service::settings::Level::Info)
                     .with_directory(files_dir.to_string_lossy().to_string()),
             ),
+            // Android servers don't register for DNS-SD discovery, and have no yaml configuration
+            peer_sync: None,
+            mail: None,
+            receipt_printer: None,
         };
 
         logging_init(settings.logging.clone(), None);
@@ -6,8 +6,8 @@ use cli::RefreshDatesRepository;
 use graphql::{Mutations, OperationalSchema, Queries};
 use log::info;
 use repository::{
-    get_storage_connection_manager, test_db, KeyType, KeyValueStoreRepository,
-    SyncBufferRowRepository,
+    get_storage_connection_manager, test_db, DatetimeFilter, EqualFilter, KeyType,
+    KeyValueStoreRepository, SyncBufferFilter, SyncBufferRepository, SyncBufferRowRepository,
 };
 use serde::{Deserialize, Serialize};
 use server::configuration;
@@ -19,8 +19,12 @@ use service::{
     service_provider::{ServiceContext, ServiceProvider},
     settings::Settings,
     sync::{
-        file_sync_driver::FileSyncDriver, settings::SyncSettings, sync_status::logger::SyncLogger,
-        synchroniser::integrate_and_translate_sync_buffer, synchroniser_driver::SynchroniserDriver,
+        file_sync_driver::FileSyncDriver,
+        integration_errors::{ignore_integration_errors, replay_integration_errors},
+        settings::SyncSettings,
+        sync_status::logger::SyncLogger,
+        synchroniser::integrate_and_translate_sync_buffer,
+        synchroniser_driver::SynchroniserDriver,
     },
     token_bucket::TokenBucket,
 };
@@ -85,6 +89,37 @@ enum Action {
         #[clap(short, long, parse(from_flag))]
         enable_sync: bool,
     },
+    /// Translate and integrate sync buffer records that failed integration again (e.g. after fixing data on central server or upgrading), or mark them as ignored.
+    /// Records are selected by id or by table and/or source site, ignored records are only replayed when selected by id
+    ReplayIntegrationErrors {
+        /// Sync buffer record ids, in format "id1,id2"
+        #[clap(short, long)]
+        record_ids: Option<String>,
+        /// Legacy (mSupply) table name
+        #[clap(short, long)]
+        table: Option<String>,
+        /// Source site id
+        #[clap(short, long)]
+        site_id: Option<i32>,
+        /// Mark records as ignored instead of replaying them (only hides them from integration error listings)
+        #[clap(short, long, parse(from_flag))]
+        ignore: bool,
+    },
+    /// Run mock mSupply central server for offline sync testing (v5 and v6 sync api), only available when built with `mock-central` feature.
+    /// Sites are named "site_1", "site_2", etc., with password "pass", more sites can be created with `/sync/v5/test/create_site` endpoint
+    #[cfg(feature = "mock-central")]
+    MockCentralServer {
+        /// Port to listen on (localhost)
+        #[clap(short, long, default_value = "2048")]
+        port: u16,
+        /// Number of sites to create, every site can see stores of all other sites
+        #[clap(short, long, default_value = "2")]
+        sites: u32,
+        /// Throwaway database used as open mSupply central database, it's dropped and recreated.
+        /// Must be different to the configured database
+        #[clap(short, long)]
+        database_name: String,
+    },
 
     SignPlugin {
         /// Path to the plugin.
@@ -356,12 +391,136 @@ async fn main() -> anyhow::Result<()> {
 
             info!("Refresh data result: {:#?}", result);
         }
+        Action::ReplayIntegrationErrors {
+            record_ids,
+            table,
+            site_id,
+            ignore,
+        } => {
+            let connection_manager = get_storage_connection_manager(&settings.database);
+            let app_data_folder = settings
+                .server
+                .base_dir
+                .ok_or(anyhow!("based dir not set in yaml configurations"))?;
+            let service_provider = Arc::new(ServiceProvider::new(
+                connection_manager.clone(),
+                &app_data_folder,
+            ));
+            let ctx = service_provider.basic_context()?;
+
+            let record_ids = match record_ids {
+                Some(record_ids) => record_ids.split(',').map(|id| id.to_string()).collect(),
+                None => {
+                    if table.is_none() && site_id.is_none() {
+                        return Err(anyhow!("Either record ids, table or site id is required"));
+                    }
+                    let filter = SyncBufferFilter {
+                        integration_error: Some(EqualFilter::is_null(false)),
+                        ignored_datetime: Some(DatetimeFilter::is_null(true)),
+                        table_name: table.as_deref().map(EqualFilter::equal_to),
+                        source_site_id: site_id.map(EqualFilter::equal_to_i32),
+                        ..Default::default()
+                    };
+                    SyncBufferRepository::new(&ctx.connection)
+                        .query_by_filter(filter)?
+                        .into_iter()
+                        .map(|row| row.record_id)
+                        .collect::<Vec<String>>()
+                }
+            };
+
+            if ignore {
+                let ignored_count = ignore_integration_errors(&ctx, &record_ids)?;
+                info!("Ignored {} records", ignored_count);
+            } else {
+                let result = replay_integration_errors(&ctx, &record_ids)?;
+                info!("Replay integration errors result: {:#?}", result);
+            }
+        }
+        #[cfg(feature = "mock-central")]
+        Action::MockCentralServer {
+            port,
+            sites,
+            database_name,
+        } => mock_central_server(settings, port, sites, database_name).await?,
         Action::SignPlugin { path, key, cert } => sign_plugin(&path, &key, &cert)?,
     }
 
     Ok(())
 }
 
+/// Runs mock central server with a throwaway database, configured database is not touched
+#[cfg(feature = "mock-central")]
+async fn mock_central_server(
+    settings: Settings,
+    port: u16,
+    sites: u32,
+    database_name: String,
+) -> anyhow::Result<()> {
+    use repository::database_settings::DatabaseSettings;
+    use service::sync::mock_central::{start_mock_central, MockCentral, MockSite};
+    use util::uuid::uuid;
+
+    if database_name == settings.database.database_name {
+        return Err(anyhow!(
+            "Mock central server drops its database, use a database other than the configured one"
+        ));
+    }
+    let database_settings = DatabaseSettings {
+        database_name,
+        ..settings.database.clone()
+    };
+    test_db::setup(&database_settings).await;
+    let connection_manager = get_storage_connection_manager(&database_settings);
+    let app_data_folder = settings
+        .server
+        .base_dir
+        .ok_or(anyhow!("based dir not set in yaml configurations"))?;
+    let service_provider = Arc::new(ServiceProvider::new(
+        connection_manager.clone(),
+        &app_data_folder,
+    ));
+
+    let mock_central = MockCentral::new(service_provider);
+    let mut created_sites: Vec<MockSite> = Vec::new();
+    for index in 1..=sites {
+        let visible_name_ids = created_sites
+            .iter()
+            .map(|site| site.name_id.clone())
+            .collect();
+        let site = mock_central.create_site(
+            Some(&format!("site_{}", index)),
+            Some("pass"),
+            visible_name_ids,
+        )?;
+        info!("Created site {:?}", site);
+        created_sites.push(site);
+    }
+    // Earlier sites should also see stores of later sites
+    let name_store_joins: Vec<serde_json::Value> = created_sites
+        .iter()
+        .enumerate()
+        .flat_map(|(index, site)| {
+            created_sites[index + 1..].iter().map(move |later_site| {
+                serde_json::json!({
+                    "ID": uuid(),
+                    "name_ID": later_site.name_id,
+                    "store_ID": site.store_id,
+                })
+            })
+        })
+        .collect();
+    mock_central.upsert_records(serde_json::json!({
+        "name_store_join": name_store_joins
+    }))?;
+
+    let (port, server) = start_mock_central(mock_central, port)?;
+    info!("Mock central server listening on http://127.0.0.1:{}", port);
+    server.await?;
+
+    Ok(())
+}
+
 fn export_paths(name: &str) -> (PathBuf, PathBuf, PathBuf) {
     let export_folder = Path::new(DATA_EXPORT_FOLDER).join(name);
     let export_file_path = export_folder.join("export.json");
@@ -61,8 +61,14 @@ fn get_timestamp_fields() -> Vec<TableAndFieldName> {
 #[cfg(feature = "postgres")]
 fn get_exclude_timestamp_fields() -> Vec<TableAndFieldName> {
     vec![
+        ("report_schedule", "next_run_datetime"),
+        ("report_schedule_run", "run_datetime"),
+        ("report_schedule_run", "emailed_datetime"),
         ("sync_buffer", "received_datetime"),
         ("sync_buffer", "integration_datetime"),
+        ("sync_buffer", "ignored_datetime"),
+        ("sync_conflict", "detected_datetime"),
+        ("sync_conflict", "resolved_datetime"),
         ("sync_log", "started_datetime"),
         ("sync_log", "finished_datetime"),
         ("sync_log", "prepare_initial_started_datetime"),
@@ -81,6 +87,8 @@ fn get_exclude_timestamp_fields() -> Vec<TableAndFieldName> {
         ("sync_log", "push_v6_finished_datetime"),
         ("user_account", "last_successful_sync"),
         ("activity_log", "datetime"),
+        ("audit_log", "datetime"),
+        ("audit_log_capture", "datetime"),
         ("asset_log", "log_datetime"),
         ("sync_file_reference", "retry_at"),
     ]
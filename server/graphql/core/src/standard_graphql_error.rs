@@ -4,6 +4,7 @@ use async_graphql::{Context, ErrorExtensions, Result};
 use repository::RepositoryError;
 use service::{
     auth::{AuthDeniedKind, AuthError, ResourceAccessRequest, ValidatedUser},
+    log_context::LogContext,
     ListError,
 };
 use thiserror::Error;
@@ -84,6 +85,14 @@ pub fn validate_auth(
         &ctx.get_auth_token(),
         access_request,
     );
+    if let Ok(validated_user) = &result {
+        LogContext::update(|context| {
+            context.user_id = Some(validated_user.user_id.clone());
+            if access_request.store_id.is_some() {
+                context.store_id = access_request.store_id.clone();
+            }
+        });
+    }
     result.map_err(|err| {
         let graphql_error = match err {
             AuthError::Denied(kind) => match kind {
@@ -11,7 +11,8 @@ use service::sync::CentralServerConfig;
 
 use crate::store_preference::store_preferences;
 use graphql_types::types::{
-    CurrenciesResponse, CurrencyFilterInput, CurrencySortInput, StorePreferenceNode,
+    AuditLogIntegrityNode, CurrenciesResponse, CurrencyFilterInput, CurrencySortInput,
+    StorePreferenceNode, SyncConflictNode,
 };
 use mutations::{
     barcode::{insert_barcode, BarcodeInput},
@@ -20,12 +21,26 @@ use mutations::{
         update_display_settings, DisplaySettingsInput, UpdateDisplaySettingsResponse,
     },
     initialise_site::{initialise_site, InitialiseSiteResponse},
+    integration_errors::{
+        ignore_integration_errors_mutation, replay_integration_errors_mutation,
+        IgnoreIntegrationErrorsNode, IntegrationErrorsInput, ReplayIntegrationErrorsNode,
+    },
     label_printer_settings::{
         update_label_printer_settings, LabelPrinterSettingsInput,
         UpdateLabelPrinterSettingsResponse,
     },
+    label_printer::{
+        cancel_print_job_mutation, delete_label_printer_mutation, retry_print_job_mutation,
+        upsert_label_printer_mutation, UpsertLabelPrinterInput,
+    },
+    label_template::{
+        delete_label_template_mutation, print_labels_mutation, upsert_label_template_mutation,
+        UpsertLabelTemplateInput,
+    },
     log::{update_log_level, LogLevelInput, UpsertLogLevelResponse},
     manual_sync::manual_sync,
+    receipt::{print_prescription_receipt_mutation, PrintPrescriptionReceiptInput},
+    sync_conflicts::{resolve_sync_conflict_mutation, ResolveSyncConflictInput},
     sync_settings::{update_sync_settings, UpdateSyncSettingsResponse},
     update_user,
 };
@@ -33,6 +48,11 @@ use queries::{
     currency::currencies,
     display_settings::{display_settings, DisplaySettingsHash, DisplaySettingsNode},
     initialisation_status::{initialisation_status, InitialisationStatusNode},
+    label_printer::{label_printers, print_jobs, LabelPrinterNode, PrintJobNode},
+    label_template::{
+        label_previews, label_templates, LabelTemplateNode, LabelTemplateTypeNode,
+        PrintLabelsInput,
+    },
     requisition_line_chart::{ConsumptionOptionsInput, StockEvolutionOptionsInput},
     sync_settings::{sync_settings, SyncSettingsNode},
 };
@@ -201,6 +221,45 @@ impl GeneralQueries {
         activity_logs(ctx, page, filter, sort)
     }
 
+    /// Field level change history of records that go through the changelog
+    pub async fn audit_logs(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Pagination option (first and offset)")] page: Option<PaginationInput>,
+        #[graphql(desc = "Filter option")] filter: Option<AuditLogFilterInput>,
+        #[graphql(desc = "Sort options (only first sort input is evaluated for this endpoint)")]
+        sort: Option<Vec<AuditLogSortInput>>,
+    ) -> Result<AuditLogResponse> {
+        audit_logs(ctx, page, filter, sort)
+    }
+
+    /// Recalculates the audit log hash chain to detect tampering
+    pub async fn audit_log_integrity(&self, ctx: &Context<'_>) -> Result<AuditLogIntegrityNode> {
+        audit_log_integrity(ctx).await
+    }
+
+    /// Sync buffer records that failed translation or integration
+    pub async fn integration_errors(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Pagination option (first and offset)")] page: Option<PaginationInput>,
+        #[graphql(desc = "Filter option")] filter: Option<IntegrationErrorFilterInput>,
+    ) -> Result<IntegrationErrorsResponse> {
+        integration_errors(ctx, page, filter)
+    }
+
+    /// Central records that were not integrated because the local record has unsynced changes
+    pub async fn sync_conflicts(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Pagination option (first and offset)")] page: Option<PaginationInput>,
+        #[graphql(desc = "Filter option")] filter: Option<SyncConflictFilterInput>,
+        #[graphql(desc = "Sort options (only first sort input is evaluated for this endpoint)")]
+        sort: Option<Vec<SyncConflictSortInput>>,
+    ) -> Result<SyncConflictsResponse> {
+        sync_conflicts(ctx, page, filter, sort)
+    }
+
     /// Available without authorisation in operational and initialisation states
     pub async fn initialisation_status(
         &self,
@@ -368,6 +427,46 @@ impl GeneralQueries {
     ) -> Result<Option<LabelPrinterSettingNode>> {
         label_printer_settings(ctx)
     }
+
+    /// Label templates of all types if the type is not set
+    pub async fn label_templates(
+        &self,
+        ctx: &Context<'_>,
+        store_id: String,
+        r#type: Option<LabelTemplateTypeNode>,
+    ) -> Result<Vec<LabelTemplateNode>> {
+        label_templates(ctx, store_id, r#type)
+    }
+
+    /// Renders the labels to png data urls, the label size of the printer the labels would be
+    /// printed on is used if one is configured. The preview is an approximation of the printed
+    /// labels.
+    pub async fn label_previews(
+        &self,
+        ctx: &Context<'_>,
+        store_id: String,
+        input: PrintLabelsInput,
+    ) -> Result<Vec<String>> {
+        label_previews(ctx, store_id, input)
+    }
+
+    /// Active and inactive label printers available to the store
+    pub async fn label_printers(
+        &self,
+        ctx: &Context<'_>,
+        store_id: String,
+    ) -> Result<Vec<LabelPrinterNode>> {
+        label_printers(ctx, store_id)
+    }
+
+    /// Latest print jobs of the store
+    pub async fn print_jobs(
+        &self,
+        ctx: &Context<'_>,
+        store_id: String,
+    ) -> Result<Vec<PrintJobNode>> {
+        print_jobs(ctx, store_id)
+    }
 }
 
 #[derive(Default, Clone)]
@@ -396,6 +495,34 @@ impl GeneralMutations {
         manual_sync(ctx, true)
     }
 
+    /// Translates and integrates sync buffer records with integration errors again
+    pub async fn replay_integration_errors(
+        &self,
+        ctx: &Context<'_>,
+        input: IntegrationErrorsInput,
+    ) -> Result<ReplayIntegrationErrorsNode> {
+        replay_integration_errors_mutation(ctx, input)
+    }
+
+    /// Marks sync buffer records with integration errors as ignored, so they can be excluded from
+    /// `integrationErrors` with the `isIgnored` filter. Records stay unintegrated and can be replayed
+    pub async fn ignore_integration_errors(
+        &self,
+        ctx: &Context<'_>,
+        input: IntegrationErrorsInput,
+    ) -> Result<IgnoreIntegrationErrorsNode> {
+        ignore_integration_errors_mutation(ctx, input)
+    }
+
+    /// Keeps the local record or integrates the central record of a sync conflict
+    pub async fn resolve_sync_conflict(
+        &self,
+        ctx: &Context<'_>,
+        input: ResolveSyncConflictInput,
+    ) -> Result<SyncConflictNode> {
+        resolve_sync_conflict_mutation(ctx, input)
+    }
+
     pub async fn update_display_settings(
         &self,
         ctx: &Context<'_>,
@@ -433,6 +560,72 @@ impl GeneralMutations {
     ) -> Result<UpdateLabelPrinterSettingsResponse> {
         update_label_printer_settings(ctx, input)
     }
+
+    pub async fn upsert_label_template(
+        &self,
+        ctx: &Context<'_>,
+        input: UpsertLabelTemplateInput,
+    ) -> Result<LabelTemplateNode> {
+        upsert_label_template_mutation(ctx, input)
+    }
+
+    pub async fn delete_label_template(&self, ctx: &Context<'_>, id: String) -> Result<String> {
+        delete_label_template_mutation(ctx, id)
+    }
+
+    /// Queues a label for each record for the label printer of the store
+    pub async fn print_labels(
+        &self,
+        ctx: &Context<'_>,
+        store_id: String,
+        input: PrintLabelsInput,
+    ) -> Result<PrintJobNode> {
+        print_labels_mutation(ctx, store_id, input)
+    }
+
+    pub async fn upsert_label_printer(
+        &self,
+        ctx: &Context<'_>,
+        input: UpsertLabelPrinterInput,
+    ) -> Result<LabelPrinterNode> {
+        upsert_label_printer_mutation(ctx, input)
+    }
+
+    /// Deletes the label printer and cancels its pending print jobs
+    pub async fn delete_label_printer(&self, ctx: &Context<'_>, id: String) -> Result<String> {
+        delete_label_printer_mutation(ctx, id)
+    }
+
+    /// Cancels a pending print job
+    pub async fn cancel_print_job(
+        &self,
+        ctx: &Context<'_>,
+        store_id: String,
+        id: String,
+    ) -> Result<PrintJobNode> {
+        cancel_print_job_mutation(ctx, store_id, id)
+    }
+
+    /// Queues a failed or cancelled print job again
+    pub async fn retry_print_job(
+        &self,
+        ctx: &Context<'_>,
+        store_id: String,
+        id: String,
+    ) -> Result<PrintJobNode> {
+        retry_print_job_mutation(ctx, store_id, id)
+    }
+
+    /// Prints the receipt of the prescription on the receipt printer of the server configuration,
+    /// returns the invoice id
+    pub async fn print_prescription_receipt(
+        &self,
+        ctx: &Context<'_>,
+        store_id: String,
+        input: PrintPrescriptionReceiptInput,
+    ) -> Result<String> {
+        print_prescription_receipt_mutation(ctx, store_id, input).await
+    }
 }
 
 /// Auth is not checked during initialisation stage
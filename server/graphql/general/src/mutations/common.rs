@@ -10,6 +10,11 @@ pub struct SyncSettingsInput {
     pub password: String,
     /// Sync interval
     pub interval_seconds: u64,
+    /// Stores to make operational first during initialisation (other stores on the site are
+    /// backfilled after initialisation)
+    pub priority_store_ids: Option<Vec<String>>,
+    /// Sync transactional records with omSupply central instead of legacy central
+    pub transactional_sync_v6: Option<bool>,
 }
 
 impl SyncSettingsInput {
@@ -20,6 +25,8 @@ impl SyncSettingsInput {
             password_sha256: sha256(&self.password),
             interval_seconds: self.interval_seconds,
             batch_size: Default::default(),
+            priority_store_ids: self.priority_store_ids.clone().unwrap_or_default(),
+            transactional_sync_v6: self.transactional_sync_v6.unwrap_or(false),
         }
     }
 }
@@ -0,0 +1,86 @@
+use async_graphql::*;
+use graphql_core::{
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    sync::integration_errors::{
+        ignore_integration_errors, replay_integration_errors, IntegrationErrorsError,
+        ReplayIntegrationResult,
+    },
+};
+
+#[derive(InputObject)]
+pub struct IntegrationErrorsInput {
+    /// Sync buffer record ids, all records must have an integration error
+    pub record_ids: Vec<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct ReplayIntegrationErrorsNode {
+    pub integrated_count: u32,
+    /// Records that failed integration again, see `integrationErrors` for the new errors
+    pub error_count: u32,
+}
+
+#[derive(SimpleObject)]
+pub struct IgnoreIntegrationErrorsNode {
+    pub ignored_count: u32,
+}
+
+pub fn replay_integration_errors_mutation(
+    ctx: &Context<'_>,
+    input: IntegrationErrorsInput,
+) -> Result<ReplayIntegrationErrorsNode> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+            store_id: None,
+        },
+    )?;
+
+    let service_context = ctx.service_provider().basic_context()?;
+    let ReplayIntegrationResult {
+        integrated_count,
+        error_count,
+    } = replay_integration_errors(&service_context, &input.record_ids).map_err(map_error)?;
+
+    Ok(ReplayIntegrationErrorsNode {
+        integrated_count,
+        error_count,
+    })
+}
+
+pub fn ignore_integration_errors_mutation(
+    ctx: &Context<'_>,
+    input: IntegrationErrorsInput,
+) -> Result<IgnoreIntegrationErrorsNode> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+            store_id: None,
+        },
+    )?;
+
+    let service_context = ctx.service_provider().basic_context()?;
+    let ignored_count =
+        ignore_integration_errors(&service_context, &input.record_ids).map_err(map_error)?;
+
+    Ok(IgnoreIntegrationErrorsNode { ignored_count })
+}
+
+fn map_error(error: IntegrationErrorsError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        IntegrationErrorsError::RecordDoesNotExist(_)
+        | IntegrationErrorsError::RecordHasNoIntegrationError(_) => BadUserInput(formatted_error),
+        IntegrationErrorsError::DatabaseError(_) => InternalError(formatted_error),
+    };
+
+    graphql_error.extend()
+}
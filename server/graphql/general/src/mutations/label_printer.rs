@@ -0,0 +1,170 @@
+use async_graphql::*;
+use graphql_core::{
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    print::{
+        printer::{
+            delete_label_printer, upsert_label_printer, DeleteLabelPrinterError,
+            UpsertLabelPrinter, UpsertLabelPrinterError,
+        },
+        queue::{cancel_print_job, retry_print_job, UpdatePrintJobError},
+    },
+};
+
+use crate::queries::{
+    label_printer::{LabelPrinterNode, PrintJobNode},
+    label_template::LabelTemplateTypeNode,
+};
+
+#[derive(InputObject)]
+pub struct UpsertLabelPrinterInput {
+    pub id: String,
+    pub name: String,
+    /// IP address of the printer
+    pub address: String,
+    pub port: u16,
+    /// Label width in dots
+    pub label_width: i32,
+    /// Label height in dots
+    pub label_height: i32,
+    /// Available to all stores when not set
+    pub store_id: Option<String>,
+    /// Prints all types of labels when not set
+    pub purpose: Option<LabelTemplateTypeNode>,
+    pub is_active: bool,
+}
+
+pub fn upsert_label_printer_mutation(
+    ctx: &Context<'_>,
+    input: UpsertLabelPrinterInput,
+) -> Result<LabelPrinterNode> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+            store_id: None,
+        },
+    )?;
+
+    let service_context = ctx.service_provider().basic_context()?;
+    let UpsertLabelPrinterInput {
+        id,
+        name,
+        address,
+        port,
+        label_width,
+        label_height,
+        store_id,
+        purpose,
+        is_active,
+    } = input;
+    let row = upsert_label_printer(
+        &service_context,
+        UpsertLabelPrinter {
+            id,
+            name,
+            address,
+            port,
+            label_width,
+            label_height,
+            store_id,
+            purpose: purpose.map(Into::into),
+            is_active,
+        },
+    )
+    .map_err(map_upsert_error)?;
+    Ok(LabelPrinterNode { row })
+}
+
+pub fn delete_label_printer_mutation(ctx: &Context<'_>, id: String) -> Result<String> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+            store_id: None,
+        },
+    )?;
+
+    let service_context = ctx.service_provider().basic_context()?;
+    delete_label_printer(&service_context, &id).map_err(map_delete_error)
+}
+
+pub fn cancel_print_job_mutation(
+    ctx: &Context<'_>,
+    store_id: String,
+    id: String,
+) -> Result<PrintJobNode> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::QueryStore,
+            store_id: Some(store_id.clone()),
+        },
+    )?;
+
+    let service_context = ctx.service_provider().context(store_id, user.user_id)?;
+    let row = cancel_print_job(&service_context, &id).map_err(map_update_print_job_error)?;
+    Ok(PrintJobNode { row })
+}
+
+pub fn retry_print_job_mutation(
+    ctx: &Context<'_>,
+    store_id: String,
+    id: String,
+) -> Result<PrintJobNode> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::QueryStore,
+            store_id: Some(store_id.clone()),
+        },
+    )?;
+
+    let service_context = ctx.service_provider().context(store_id, user.user_id)?;
+    let row = retry_print_job(&service_context, &id).map_err(map_update_print_job_error)?;
+    Ok(PrintJobNode { row })
+}
+
+fn map_upsert_error(error: UpsertLabelPrinterError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        UpsertLabelPrinterError::NameNotSpecified
+        | UpsertLabelPrinterError::InvalidAddress
+        | UpsertLabelPrinterError::InvalidLabelSize
+        | UpsertLabelPrinterError::StoreDoesNotExist => BadUserInput(formatted_error),
+        UpsertLabelPrinterError::DatabaseError(_) => InternalError(formatted_error),
+    };
+
+    graphql_error.extend()
+}
+
+fn map_delete_error(error: DeleteLabelPrinterError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        DeleteLabelPrinterError::LabelPrinterDoesNotExist => BadUserInput(formatted_error),
+        DeleteLabelPrinterError::DatabaseError(_) => InternalError(formatted_error),
+    };
+
+    graphql_error.extend()
+}
+
+fn map_update_print_job_error(error: UpdatePrintJobError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        UpdatePrintJobError::PrintJobDoesNotExist
+        | UpdatePrintJobError::PrintJobDoesNotBelongToStore
+        | UpdatePrintJobError::InvalidStatus => BadUserInput(formatted_error),
+        UpdatePrintJobError::DatabaseError(_) => InternalError(formatted_error),
+    };
+
+    graphql_error.extend()
+}
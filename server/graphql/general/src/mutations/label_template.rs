@@ -0,0 +1,123 @@
+use async_graphql::*;
+use graphql_core::{
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    print::label_template::{
+        delete_label_template, print_labels, upsert_label_template, DeleteLabelTemplateError,
+        UpsertLabelTemplate, UpsertLabelTemplateError,
+    },
+};
+
+use crate::queries::{
+    label_printer::PrintJobNode,
+    label_template::{
+        label_resource, map_print_labels_error, LabelTemplateNode, LabelTemplateTypeNode,
+        PrintLabelsInput,
+    },
+};
+
+#[derive(InputObject)]
+pub struct UpsertLabelTemplateInput {
+    pub id: String,
+    pub name: String,
+    pub r#type: LabelTemplateTypeNode,
+    /// ZPL with Tera variables, e.g. `^FD{{ item.name }}^FS`
+    pub template: String,
+}
+
+pub fn upsert_label_template_mutation(
+    ctx: &Context<'_>,
+    input: UpsertLabelTemplateInput,
+) -> Result<LabelTemplateNode> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+            store_id: None,
+        },
+    )?;
+
+    let service_context = ctx.service_provider().basic_context()?;
+    let UpsertLabelTemplateInput {
+        id,
+        name,
+        r#type,
+        template,
+    } = input;
+    let row = upsert_label_template(
+        &service_context,
+        UpsertLabelTemplate {
+            id,
+            name,
+            r#type: r#type.into(),
+            template,
+        },
+    )
+    .map_err(map_upsert_error)?;
+    Ok(LabelTemplateNode { row })
+}
+
+pub fn delete_label_template_mutation(ctx: &Context<'_>, id: String) -> Result<String> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+            store_id: None,
+        },
+    )?;
+
+    let service_context = ctx.service_provider().basic_context()?;
+    delete_label_template(&service_context, &id).map_err(map_delete_error)
+}
+
+pub fn print_labels_mutation(
+    ctx: &Context<'_>,
+    store_id: String,
+    input: PrintLabelsInput,
+) -> Result<PrintJobNode> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: label_resource(input.r#type),
+            store_id: Some(store_id.clone()),
+        },
+    )?;
+
+    let service_provider = ctx.service_provider();
+    let service_context = service_provider.context(store_id, user.user_id)?;
+    let settings = service_provider
+        .label_printer_settings_service
+        .label_printer_settings(&service_context)?;
+
+    let row = print_labels(&service_context, settings.as_ref(), &input.to_domain())
+        .map_err(map_print_labels_error)?;
+    Ok(PrintJobNode { row })
+}
+
+fn map_upsert_error(error: UpsertLabelTemplateError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        UpsertLabelTemplateError::NameNotSpecified
+        | UpsertLabelTemplateError::InvalidTemplate(_) => BadUserInput(formatted_error),
+        UpsertLabelTemplateError::DatabaseError(_) => InternalError(formatted_error),
+    };
+
+    graphql_error.extend()
+}
+
+fn map_delete_error(error: DeleteLabelTemplateError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        DeleteLabelTemplateError::LabelTemplateDoesNotExist => BadUserInput(formatted_error),
+        DeleteLabelTemplateError::DatabaseError(_) => InternalError(formatted_error),
+    };
+
+    graphql_error.extend()
+}
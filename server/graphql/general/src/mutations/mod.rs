@@ -2,8 +2,13 @@ pub mod barcode;
 pub mod common;
 pub mod display_settings;
 pub mod initialise_site;
+pub mod integration_errors;
 pub mod label_printer_settings;
+pub mod label_printer;
+pub mod label_template;
 pub mod log;
+pub mod receipt;
 pub mod manual_sync;
+pub mod sync_conflicts;
 pub mod sync_settings;
 pub mod update_user;
@@ -0,0 +1,88 @@
+use actix_web::web::{self, Data};
+use async_graphql::*;
+use graphql_core::{
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    print::receipt::{print_prescription_receipt, PrintReceipt, PrintReceiptError, ReceiptPayment},
+    service_provider::ServiceProvider,
+};
+
+#[derive(InputObject)]
+pub struct ReceiptPaymentInput {
+    /// E.g. cash or card
+    pub method: String,
+    pub amount_tendered: f64,
+}
+
+#[derive(InputObject)]
+pub struct PrintPrescriptionReceiptInput {
+    pub invoice_id: String,
+    /// Prints the amount paid and the change when set
+    pub payment: Option<ReceiptPaymentInput>,
+}
+
+pub async fn print_prescription_receipt_mutation(
+    ctx: &Context<'_>,
+    store_id: String,
+    input: PrintPrescriptionReceiptInput,
+) -> Result<String> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::MutatePrescription,
+            store_id: Some(store_id.clone()),
+        },
+    )?;
+
+    let PrintPrescriptionReceiptInput {
+        invoice_id,
+        payment,
+    } = input;
+    let input = PrintReceipt {
+        invoice_id,
+        payment: payment.map(
+            |ReceiptPaymentInput {
+                 method,
+                 amount_tendered,
+             }| ReceiptPayment {
+                method,
+                amount_tendered,
+            },
+        ),
+    };
+    let invoice_id = input.invoice_id.clone();
+
+    // Sending to the printer uses blocking sockets or device files, run it outside of the async
+    // executor
+    let service_provider = ctx.data_unchecked::<Data<ServiceProvider>>().clone();
+    let settings = ctx.get_settings().receipt_printer.clone();
+    web::block(move || {
+        let service_context = service_provider.context(store_id, user.user_id)?;
+        print_prescription_receipt(&service_context, settings.as_ref(), &input)
+    })
+    .await
+    .map_err(|error| StandardGraphqlError::InternalError(format!("{:?}", error)).extend())?
+    .map_err(map_error)?;
+    Ok(invoice_id)
+}
+
+fn map_error(error: PrintReceiptError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        PrintReceiptError::InvoiceDoesNotExist
+        | PrintReceiptError::NotThisStoreInvoice
+        | PrintReceiptError::NotAPrescriptionInvoice
+        | PrintReceiptError::InsufficientPayment
+        | PrintReceiptError::ReceiptPrinterNotConfigured => BadUserInput(formatted_error),
+        PrintReceiptError::PrinterError(_) | PrintReceiptError::DatabaseError(_) => {
+            InternalError(formatted_error)
+        }
+    };
+
+    graphql_error.extend()
+}
@@ -0,0 +1,54 @@
+use async_graphql::*;
+use graphql_core::{
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use graphql_types::types::{SyncConflictNode, SyncConflictResolutionNode};
+use repository::SyncConflictResolution;
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    sync::conflicts::{resolve_sync_conflict, ResolveSyncConflictError},
+};
+
+#[derive(InputObject)]
+pub struct ResolveSyncConflictInput {
+    pub id: String,
+    pub resolution: SyncConflictResolutionNode,
+}
+
+pub fn resolve_sync_conflict_mutation(
+    ctx: &Context<'_>,
+    input: ResolveSyncConflictInput,
+) -> Result<SyncConflictNode> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+            store_id: None,
+        },
+    )?;
+
+    let service_context = ctx.service_provider().basic_context()?;
+    let conflict = resolve_sync_conflict(
+        &service_context,
+        &input.id,
+        SyncConflictResolution::from(input.resolution),
+    )
+    .map_err(map_error)?;
+
+    Ok(SyncConflictNode::from_domain(conflict))
+}
+
+fn map_error(error: ResolveSyncConflictError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        ResolveSyncConflictError::ConflictDoesNotExist(_)
+        | ResolveSyncConflictError::ConflictAlreadyResolved(_) => BadUserInput(formatted_error),
+        ResolveSyncConflictError::IntegrationError(_)
+        | ResolveSyncConflictError::DatabaseError(_) => InternalError(formatted_error),
+    };
+
+    graphql_error.extend()
+}
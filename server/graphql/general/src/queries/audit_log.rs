@@ -0,0 +1,136 @@
+use actix_web::web::{self, Data};
+use async_graphql::*;
+use graphql_core::{
+    generic_filters::{DatetimeFilterInput, EqualFilterStringInput},
+    map_filter,
+    pagination::PaginationInput,
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use graphql_types::types::{AuditLogConnector, AuditLogIntegrityNode, ChangelogTableNameNode};
+use repository::audit_log::{AuditLogFilter, AuditLogSort, AuditLogSortField};
+use repository::{ChangelogTableName, DatetimeFilter, EqualFilter, PaginationOption};
+use service::{
+    audit_log::{get_audit_logs, verify_audit_log},
+    auth::{Resource, ResourceAccessRequest},
+    service_provider::ServiceProvider,
+};
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(remote = "repository::audit_log::AuditLogSortField")]
+#[graphql(rename_items = "camelCase")]
+pub enum AuditLogSortFieldInput {
+    Datetime,
+    ChangelogCursor,
+}
+
+#[derive(InputObject)]
+pub struct AuditLogSortInput {
+    /// Sort query result by `key`
+    key: AuditLogSortFieldInput,
+    /// Sort query result is sorted descending or ascending (if not provided the default is
+    /// ascending)
+    desc: Option<bool>,
+}
+
+#[derive(InputObject, Clone)]
+pub struct EqualFilterChangelogTableNameInput {
+    pub equal_to: Option<ChangelogTableNameNode>,
+    pub equal_any: Option<Vec<ChangelogTableNameNode>>,
+    pub not_equal_to: Option<ChangelogTableNameNode>,
+}
+
+#[derive(InputObject, Clone)]
+pub struct AuditLogFilterInput {
+    pub id: Option<EqualFilterStringInput>,
+    pub table_name: Option<EqualFilterChangelogTableNameInput>,
+    pub record_id: Option<EqualFilterStringInput>,
+    pub store_id: Option<EqualFilterStringInput>,
+    pub user_id: Option<EqualFilterStringInput>,
+    pub datetime: Option<DatetimeFilterInput>,
+}
+
+#[derive(Union)]
+pub enum AuditLogResponse {
+    Response(AuditLogConnector),
+}
+
+pub fn audit_logs(
+    ctx: &Context<'_>,
+    page: Option<PaginationInput>,
+    filter: Option<AuditLogFilterInput>,
+    sort: Option<Vec<AuditLogSortInput>>,
+) -> Result<AuditLogResponse> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::QueryLog,
+            store_id: None,
+        },
+    )?;
+
+    let connection_manager = ctx.get_connection_manager();
+    let items = get_audit_logs(
+        connection_manager,
+        page.map(PaginationOption::from),
+        filter.map(|filter| filter.to_domain()),
+        // Currently only one sort option is supported, use the first from the list.
+        sort.and_then(|mut sort_list| sort_list.pop())
+            .map(|sort| sort.to_domain()),
+    )
+    .map_err(StandardGraphqlError::from_list_error)?;
+
+    Ok(AuditLogResponse::Response(AuditLogConnector::from_domain(
+        items,
+    )))
+}
+
+pub async fn audit_log_integrity(ctx: &Context<'_>) -> Result<AuditLogIntegrityNode> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::QueryLog,
+            store_id: None,
+        },
+    )?;
+
+    // Walks the whole log, run it outside of the async executor
+    let service_provider = ctx.data_unchecked::<Data<ServiceProvider>>().clone();
+    let verification = web::block(move || verify_audit_log(&service_provider))
+        .await
+        .map_err(|error| StandardGraphqlError::InternalError(format!("{:?}", error)).extend())?
+        .map_err(|error| StandardGraphqlError::InternalError(format!("{:?}", error)).extend())?;
+
+    Ok(AuditLogIntegrityNode::from_domain(verification))
+}
+
+impl AuditLogFilterInput {
+    pub fn to_domain(self) -> AuditLogFilter {
+        let AuditLogFilterInput {
+            id,
+            table_name,
+            record_id,
+            store_id,
+            user_id,
+            datetime,
+        } = self;
+
+        AuditLogFilter {
+            id: id.map(EqualFilter::from),
+            table_name: table_name.map(|t| map_filter!(t, ChangelogTableName::from)),
+            record_id: record_id.map(EqualFilter::from),
+            store_id: store_id.map(EqualFilter::from),
+            user_id: user_id.map(EqualFilter::from),
+            datetime: datetime.map(DatetimeFilter::from),
+        }
+    }
+}
+
+impl AuditLogSortInput {
+    pub fn to_domain(&self) -> AuditLogSort {
+        AuditLogSort {
+            key: AuditLogSortField::from(self.key),
+            desc: self.desc,
+        }
+    }
+}
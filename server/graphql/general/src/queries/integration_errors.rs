@@ -0,0 +1,77 @@
+use async_graphql::*;
+use graphql_core::{
+    generic_filters::{EqualFilterNumberInput, EqualFilterStringInput, StringFilterInput},
+    pagination::PaginationInput,
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use graphql_types::types::SyncBufferRecordConnector;
+use repository::{DatetimeFilter, EqualFilter, PaginationOption, StringFilter, SyncBufferFilter};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    sync::integration_errors::get_integration_errors,
+};
+
+#[derive(InputObject, Clone)]
+pub struct IntegrationErrorFilterInput {
+    pub record_id: Option<EqualFilterStringInput>,
+    /// Legacy (mSupply) table name
+    pub table_name: Option<EqualFilterStringInput>,
+    pub source_site_id: Option<EqualFilterNumberInput>,
+    pub integration_error: Option<StringFilterInput>,
+    /// Records marked with `ignoreIntegrationErrors`, ignored records are listed unless this is
+    /// set to false
+    pub is_ignored: Option<bool>,
+}
+
+#[derive(Union)]
+pub enum IntegrationErrorsResponse {
+    Response(SyncBufferRecordConnector),
+}
+
+pub fn integration_errors(
+    ctx: &Context<'_>,
+    page: Option<PaginationInput>,
+    filter: Option<IntegrationErrorFilterInput>,
+) -> Result<IntegrationErrorsResponse> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+            store_id: None,
+        },
+    )?;
+
+    let service_context = ctx.service_provider().basic_context()?;
+    let items = get_integration_errors(
+        &service_context,
+        page.map(PaginationOption::from),
+        filter.map(|filter| filter.to_domain()),
+    )
+    .map_err(StandardGraphqlError::from_list_error)?;
+
+    Ok(IntegrationErrorsResponse::Response(
+        SyncBufferRecordConnector::from_domain(items),
+    ))
+}
+
+impl IntegrationErrorFilterInput {
+    pub fn to_domain(self) -> SyncBufferFilter {
+        let IntegrationErrorFilterInput {
+            record_id,
+            table_name,
+            source_site_id,
+            integration_error,
+            is_ignored,
+        } = self;
+
+        SyncBufferFilter {
+            record_id: record_id.map(EqualFilter::from),
+            table_name: table_name.map(EqualFilter::from),
+            source_site_id: source_site_id.map(EqualFilter::from),
+            integration_error_message: integration_error.map(StringFilter::from),
+            ignored_datetime: is_ignored.map(|is_ignored| DatetimeFilter::is_null(!is_ignored)),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use async_graphql::*;
+use chrono::{DateTime, Utc};
+use graphql_core::{standard_graphql_error::validate_auth, ContextExt};
+use repository::{LabelPrinterRow, PrintJobRow};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    print::{printer::get_label_printers, queue::get_print_jobs},
+};
+
+use super::label_template::LabelTemplateTypeNode;
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(remote = "repository::LabelPrinterStatus")]
+pub enum LabelPrinterStatusNode {
+    /// Not checked since the printer was added or its address changed
+    Unknown,
+    Ready,
+    /// Reachable but can't print, e.g. paper out or paused
+    Error,
+    Unreachable,
+}
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(remote = "repository::PrintJobStatus")]
+pub enum PrintJobStatusNode {
+    Pending,
+    Printed,
+    Failed,
+    Cancelled,
+}
+
+pub struct LabelPrinterNode {
+    pub row: LabelPrinterRow,
+}
+
+#[Object]
+impl LabelPrinterNode {
+    pub async fn id(&self) -> &str {
+        &self.row.id
+    }
+
+    pub async fn name(&self) -> &str {
+        &self.row.name
+    }
+
+    pub async fn address(&self) -> &str {
+        &self.row.address
+    }
+
+    pub async fn port(&self) -> i32 {
+        self.row.port
+    }
+
+    /// Label width in dots
+    pub async fn label_width(&self) -> i32 {
+        self.row.label_width
+    }
+
+    /// Label height in dots
+    pub async fn label_height(&self) -> i32 {
+        self.row.label_height
+    }
+
+    /// Available to all stores when not set
+    pub async fn store_id(&self) -> Option<&str> {
+        self.row.store_id.as_deref()
+    }
+
+    /// Prints all types of labels when not set
+    pub async fn purpose(&self) -> Option<LabelTemplateTypeNode> {
+        self.row.purpose.clone().map(LabelTemplateTypeNode::from)
+    }
+
+    pub async fn is_active(&self) -> bool {
+        self.row.is_active
+    }
+
+    pub async fn status(&self) -> LabelPrinterStatusNode {
+        LabelPrinterStatusNode::from(self.row.status.clone())
+    }
+
+    pub async fn status_message(&self) -> Option<&str> {
+        self.row.status_message.as_deref()
+    }
+
+    pub async fn status_datetime(&self) -> Option<DateTime<Utc>> {
+        self.row
+            .status_datetime
+            .map(|datetime| DateTime::<Utc>::from_naive_utc_and_offset(datetime, Utc))
+    }
+}
+
+pub struct PrintJobNode {
+    pub row: PrintJobRow,
+}
+
+#[Object]
+impl PrintJobNode {
+    pub async fn id(&self) -> &str {
+        &self.row.id
+    }
+
+    /// The printer of the label printer settings when not set
+    pub async fn label_printer_id(&self) -> Option<&str> {
+        self.row.label_printer_id.as_deref()
+    }
+
+    pub async fn label_count(&self) -> i32 {
+        self.row.label_count
+    }
+
+    pub async fn status(&self) -> PrintJobStatusNode {
+        PrintJobStatusNode::from(self.row.status.clone())
+    }
+
+    pub async fn attempts(&self) -> i32 {
+        self.row.attempts
+    }
+
+    /// Error of the last attempt
+    pub async fn error(&self) -> Option<&str> {
+        self.row.error.as_deref()
+    }
+
+    pub async fn created_datetime(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_naive_utc_and_offset(self.row.created_datetime, Utc)
+    }
+
+    pub async fn next_attempt_datetime(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_naive_utc_and_offset(self.row.next_attempt_datetime, Utc)
+    }
+
+    pub async fn printed_datetime(&self) -> Option<DateTime<Utc>> {
+        self.row
+            .printed_datetime
+            .map(|datetime| DateTime::<Utc>::from_naive_utc_and_offset(datetime, Utc))
+    }
+}
+
+pub fn label_printers(ctx: &Context<'_>, store_id: String) -> Result<Vec<LabelPrinterNode>> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::QueryStore,
+            store_id: Some(store_id.clone()),
+        },
+    )?;
+
+    let service_context = ctx.service_provider().context(store_id, user.user_id)?;
+    let printers = get_label_printers(&service_context)?;
+    Ok(printers
+        .into_iter()
+        .map(|row| LabelPrinterNode { row })
+        .collect())
+}
+
+pub fn print_jobs(ctx: &Context<'_>, store_id: String) -> Result<Vec<PrintJobNode>> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::QueryStore,
+            store_id: Some(store_id.clone()),
+        },
+    )?;
+
+    let service_context = ctx.service_provider().context(store_id, user.user_id)?;
+    let jobs = get_print_jobs(&service_context)?;
+    Ok(jobs.into_iter().map(|row| PrintJobNode { row }).collect())
+}
@@ -0,0 +1,151 @@
+use async_graphql::*;
+use graphql_core::{
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use repository::{LabelTemplateRow, LabelTemplateType};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    print::label_template::{get_label_templates, preview_labels, PrintLabels, PrintLabelsError},
+};
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(remote = "repository::LabelTemplateType")]
+pub enum LabelTemplateTypeNode {
+    StockLine,
+    Location,
+    Shipment,
+}
+
+pub struct LabelTemplateNode {
+    pub row: LabelTemplateRow,
+}
+
+#[Object]
+impl LabelTemplateNode {
+    pub async fn id(&self) -> &str {
+        &self.row.id
+    }
+
+    pub async fn name(&self) -> &str {
+        &self.row.name
+    }
+
+    pub async fn r#type(&self) -> LabelTemplateTypeNode {
+        LabelTemplateTypeNode::from(self.row.r#type.clone())
+    }
+
+    /// ZPL with Tera variables
+    pub async fn template(&self) -> &str {
+        &self.row.template
+    }
+}
+
+#[derive(InputObject)]
+pub struct PrintLabelsInput {
+    /// The built-in template of the type is used when not set
+    pub template_id: Option<String>,
+    pub r#type: LabelTemplateTypeNode,
+    /// Stock line, location or shipment (invoice) ids, depending on the type
+    pub record_ids: Vec<String>,
+    /// Labels per record, e.g. the number of boxes of a shipment. At most 100 labels (records
+    /// times copies) can be printed per request
+    pub copies: Option<u32>,
+    /// Named printer to print on, the printer for the store and type of labels is used when not
+    /// set
+    pub printer_id: Option<String>,
+}
+
+impl PrintLabelsInput {
+    pub fn to_domain(self) -> PrintLabels {
+        let PrintLabelsInput {
+            template_id,
+            r#type,
+            record_ids,
+            copies,
+            printer_id,
+        } = self;
+
+        PrintLabels {
+            template_id,
+            r#type: r#type.into(),
+            record_ids,
+            copies,
+            printer_id,
+        }
+    }
+}
+
+/// Permission to query the records printed on labels of the type
+pub(crate) fn label_resource(r#type: LabelTemplateTypeNode) -> Resource {
+    match LabelTemplateType::from(r#type) {
+        LabelTemplateType::StockLine => Resource::QueryStockLine,
+        LabelTemplateType::Location => Resource::QueryLocation,
+        LabelTemplateType::Shipment => Resource::QueryInvoice,
+    }
+}
+
+pub fn label_templates(
+    ctx: &Context<'_>,
+    store_id: String,
+    r#type: Option<LabelTemplateTypeNode>,
+) -> Result<Vec<LabelTemplateNode>> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::QueryStore,
+            store_id: Some(store_id.clone()),
+        },
+    )?;
+
+    let service_context = ctx.service_provider().context(store_id, user.user_id)?;
+    let templates = get_label_templates(&service_context, r#type.map(Into::into))?;
+    Ok(templates
+        .into_iter()
+        .map(|row| LabelTemplateNode { row })
+        .collect())
+}
+
+pub fn label_previews(
+    ctx: &Context<'_>,
+    store_id: String,
+    input: PrintLabelsInput,
+) -> Result<Vec<String>> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: label_resource(input.r#type),
+            store_id: Some(store_id.clone()),
+        },
+    )?;
+
+    let service_provider = ctx.service_provider();
+    let service_context = service_provider.context(store_id, user.user_id)?;
+    let settings = service_provider
+        .label_printer_settings_service
+        .label_printer_settings(&service_context)?;
+
+    preview_labels(&service_context, settings.as_ref(), &input.to_domain())
+        .map_err(map_print_labels_error)
+}
+
+pub(crate) fn map_print_labels_error(error: PrintLabelsError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        PrintLabelsError::LabelTemplateDoesNotExist
+        | PrintLabelsError::LabelTemplateTypeMismatch
+        | PrintLabelsError::RecordsDoNotExist(_)
+        | PrintLabelsError::InvalidCopies
+        | PrintLabelsError::TooManyLabels
+        | PrintLabelsError::TemplateError(_)
+        | PrintLabelsError::LabelPrinterDoesNotExist
+        | PrintLabelsError::NoLabelPrinter => BadUserInput(formatted_error),
+        PrintLabelsError::PreviewError(_) | PrintLabelsError::DatabaseError(_) => {
+            InternalError(formatted_error)
+        }
+    };
+
+    graphql_error.extend()
+}
@@ -26,6 +26,12 @@ pub mod store;
 pub use self::store::*;
 pub mod activity_log;
 pub use self::activity_log::*;
+pub mod audit_log;
+pub use self::audit_log::*;
+pub mod integration_errors;
+pub use self::integration_errors::*;
+pub mod sync_conflicts;
+pub use self::sync_conflicts::*;
 pub mod database_settings;
 pub use self::database_settings::*;
 pub mod display_settings;
@@ -52,6 +58,8 @@ pub use self::plugin::*;
 pub mod currency;
 pub mod label_printer_settings;
 pub use self::label_printer_settings::*;
+pub mod label_printer;
+pub mod label_template;
 
 pub mod generate_inbound_return_lines;
 pub use self::generate_inbound_return_lines::*;
@@ -0,0 +1,107 @@
+use async_graphql::*;
+use graphql_core::{
+    generic_filters::{DatetimeFilterInput, EqualFilterStringInput},
+    pagination::PaginationInput,
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use graphql_types::types::SyncConflictConnector;
+use repository::{
+    DatetimeFilter, EqualFilter, PaginationOption, SyncConflictFilter, SyncConflictSort,
+    SyncConflictSortField,
+};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    sync::conflicts::get_sync_conflicts,
+};
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(remote = "repository::SyncConflictSortField")]
+#[graphql(rename_items = "camelCase")]
+pub enum SyncConflictSortFieldInput {
+    DetectedDatetime,
+    TableName,
+}
+
+#[derive(InputObject)]
+pub struct SyncConflictSortInput {
+    /// Sort query result by `key`
+    key: SyncConflictSortFieldInput,
+    /// Sort query result is sorted descending or ascending (if not provided the default is
+    /// ascending)
+    desc: Option<bool>,
+}
+
+#[derive(InputObject, Clone)]
+pub struct SyncConflictFilterInput {
+    pub id: Option<EqualFilterStringInput>,
+    /// Legacy (mSupply) table name
+    pub table_name: Option<EqualFilterStringInput>,
+    pub record_id: Option<EqualFilterStringInput>,
+    pub detected_datetime: Option<DatetimeFilterInput>,
+    pub is_resolved: Option<bool>,
+}
+
+#[derive(Union)]
+pub enum SyncConflictsResponse {
+    Response(SyncConflictConnector),
+}
+
+pub fn sync_conflicts(
+    ctx: &Context<'_>,
+    page: Option<PaginationInput>,
+    filter: Option<SyncConflictFilterInput>,
+    sort: Option<Vec<SyncConflictSortInput>>,
+) -> Result<SyncConflictsResponse> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+            store_id: None,
+        },
+    )?;
+
+    let service_context = ctx.service_provider().basic_context()?;
+    let items = get_sync_conflicts(
+        &service_context,
+        page.map(PaginationOption::from),
+        filter.map(|filter| filter.to_domain()),
+        // Currently only one sort option is supported, use the first from the list.
+        sort.and_then(|mut sort_list| sort_list.pop())
+            .map(|sort| sort.to_domain()),
+    )
+    .map_err(StandardGraphqlError::from_list_error)?;
+
+    Ok(SyncConflictsResponse::Response(
+        SyncConflictConnector::from_domain(items),
+    ))
+}
+
+impl SyncConflictFilterInput {
+    pub fn to_domain(self) -> SyncConflictFilter {
+        let SyncConflictFilterInput {
+            id,
+            table_name,
+            record_id,
+            detected_datetime,
+            is_resolved,
+        } = self;
+
+        SyncConflictFilter {
+            id: id.map(EqualFilter::from),
+            table_name: table_name.map(EqualFilter::from),
+            record_id: record_id.map(EqualFilter::from),
+            detected_datetime: detected_datetime.map(DatetimeFilter::from),
+            resolved_datetime: is_resolved.map(|is_resolved| DatetimeFilter::is_null(!is_resolved)),
+        }
+    }
+}
+
+impl SyncConflictSortInput {
+    pub fn to_domain(&self) -> SyncConflictSort {
+        SyncConflictSort {
+            key: SyncConflictSortField::from(self.key),
+            desc: self.desc,
+        }
+    }
+}
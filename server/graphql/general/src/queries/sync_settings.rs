@@ -26,6 +26,16 @@ impl SyncSettingsNode {
     pub async fn interval_seconds(&self) -> u64 {
         self.settings.interval_seconds
     }
+
+    /// Stores that are made operational first during initialisation
+    pub async fn priority_store_ids(&self) -> &Vec<String> {
+        &self.settings.priority_store_ids
+    }
+
+    /// Transactional records are synced with omSupply central instead of legacy central
+    pub async fn transactional_sync_v6(&self) -> bool {
+        self.settings.transactional_sync_v6
+    }
 }
 
 pub(crate) fn sync_settings(
@@ -6,7 +6,11 @@ use graphql_core::{
 };
 use service::{
     auth::{Resource, ResourceAccessRequest},
-    sync::sync_status::status::FullSyncStatus,
+    sync::{
+        adaptive_sync::AdaptiveSyncStatus,
+        store_backfill::{store_sync_progress, StoreSyncProgress},
+        sync_status::status::FullSyncStatus,
+    },
 };
 
 use crate::sync_api_error::SyncErrorNode;
@@ -33,6 +37,7 @@ pub struct SyncStatusWithProgressNode {
     finished: Option<NaiveDateTime>,
     total: Option<u32>,
     done: Option<u32>,
+    transferred_bytes: Option<u64>,
 }
 
 #[Object]
@@ -53,6 +58,72 @@ impl SyncStatusWithProgressNode {
     async fn done(&self) -> &Option<u32> {
         &self.done
     }
+
+    /// Request and response body bytes sent over the network (i.e. compressed) during the step
+    async fn transferred_bytes(&self) -> &Option<u64> {
+        &self.transferred_bytes
+    }
+}
+
+pub struct AdaptiveSyncNode {
+    status: AdaptiveSyncStatus,
+}
+
+#[Object]
+impl AdaptiveSyncNode {
+    /// Batch sizes used by the next sync, reduced after timeouts or oversized requests
+    async fn remote_pull_batch_size(&self) -> u32 {
+        self.status.batch_size.remote_pull
+    }
+
+    async fn remote_push_batch_size(&self) -> u32 {
+        self.status.batch_size.remote_push
+    }
+
+    async fn central_pull_batch_size(&self) -> u32 {
+        self.status.batch_size.central_pull
+    }
+
+    async fn consecutive_failures(&self) -> u32 {
+        self.status.consecutive_failures
+    }
+
+    /// Next automatic sync after failed sync
+    async fn retry_datetime(&self) -> Option<DateTime<Utc>> {
+        self.status
+            .retry_datetime
+            .map(|v| DateTime::<Utc>::from_naive_utc_and_offset(v, Utc))
+    }
+}
+
+pub struct StoreSyncProgressNode {
+    progress: StoreSyncProgress,
+}
+
+#[Object]
+impl StoreSyncProgressNode {
+    async fn store_id(&self) -> &str {
+        &self.progress.store_id
+    }
+
+    async fn store_name(&self) -> &str {
+        &self.progress.store_name
+    }
+
+    /// Records of the store pulled from central server
+    async fn total(&self) -> u64 {
+        self.progress.total
+    }
+
+    /// Pulled records of the store that are not integrated yet
+    async fn remaining(&self) -> u64 {
+        self.progress.remaining
+    }
+
+    /// Store was not prioritised during initialisation and its records are still being integrated
+    async fn is_backfill_pending(&self) -> bool {
+        self.progress.is_backfill_pending
+    }
 }
 
 #[derive(SimpleObject)]
@@ -68,6 +139,8 @@ pub struct FullSyncStatusNode {
     push: Option<SyncStatusWithProgressNode>,
     push_v6: Option<SyncStatusWithProgressNode>,
     last_successful_sync: Option<SyncStatusNode>,
+    adaptive_sync: Option<AdaptiveSyncNode>,
+    store_progress: Vec<StoreSyncProgressNode>,
 }
 
 pub fn latest_sync_status(
@@ -92,6 +165,18 @@ pub fn latest_sync_status(
         .get_latest_successful_sync_status(&ctx)
         .unwrap_or(None);
 
+    let adaptive_sync = service_provider
+        .settings
+        .sync_settings(&ctx)?
+        .map(|settings| AdaptiveSyncNode {
+            status: service_provider.adaptive_sync.status(&settings.batch_size),
+        });
+
+    let store_progress = store_sync_progress(&ctx.connection)?
+        .into_iter()
+        .map(|progress| StoreSyncProgressNode { progress })
+        .collect();
+
     let FullSyncStatus {
         is_syncing,
         error,
@@ -121,24 +206,28 @@ pub fn latest_sync_status(
             finished: status.finished,
             total: status.total,
             done: status.done,
+            transferred_bytes: status.transferred_bytes,
         }),
         pull_central: pull_central.map(|status| SyncStatusWithProgressNode {
             started: status.started,
             finished: status.finished,
             total: status.total,
             done: status.done,
+            transferred_bytes: status.transferred_bytes,
         }),
         pull_remote: pull_remote.map(|status| SyncStatusWithProgressNode {
             started: status.started,
             finished: status.finished,
             total: status.total,
             done: status.done,
+            transferred_bytes: status.transferred_bytes,
         }),
         push: push.map(|status| SyncStatusWithProgressNode {
             started: status.started,
             finished: status.finished,
             total: status.total,
             done: status.done,
+            transferred_bytes: status.transferred_bytes,
         }),
         last_successful_sync: match last_successful_sync_status {
             None => None,
@@ -152,13 +241,17 @@ pub fn latest_sync_status(
             finished: status.finished,
             total: status.total,
             done: status.done,
+            transferred_bytes: status.transferred_bytes,
         }),
         push_v6: push_v6.map(|status| SyncStatusWithProgressNode {
             started: status.started,
             finished: status.finished,
             total: status.total,
             done: status.done,
+            transferred_bytes: status.transferred_bytes,
         }),
+        adaptive_sync,
+        store_progress,
     };
 
     Ok(Some(result))
@@ -2,12 +2,13 @@
 mod tests;
 
 use std::sync::Mutex;
+use std::time::Instant;
 
 use actix_web::web::{self, Data};
 use actix_web::HttpResponse;
 use actix_web::{guard, HttpRequest};
 
-use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SchemaBuilder};
 use async_graphql::{MergedObject, Response};
 use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
 use graphql_asset::property::AssetPropertiesQueries;
@@ -37,7 +38,7 @@ use graphql_pack_variant::{PackVariantMutations, PackVariantQueries};
 use graphql_plugin::{PluginMutations, PluginQueries};
 use graphql_programs::{ProgramsMutations, ProgramsQueries};
 use graphql_repack::{RepackMutations, RepackQueries};
-use graphql_reports::ReportQueries;
+use graphql_reports::{ReportMutations, ReportQueries};
 use graphql_requisition::{RequisitionMutations, RequisitionQueries};
 use graphql_requisition_line::RequisitionLineMutations;
 use graphql_stock_line::{StockLineMutations, StockLineQueries};
@@ -46,9 +47,10 @@ use graphql_stocktake_line::{StocktakeLineMutations, StocktakeLineQueries};
 
 use repository::StorageConnectionManager;
 use service::auth_data::AuthData;
+use service::log_context::LogContext;
 use service::plugin::validation::ValidatedPluginBucket;
 use service::service_provider::ServiceProvider;
-use service::settings::Settings;
+use service::settings::{RequestLimitSettings, Settings};
 use service::sync::CentralServerConfig;
 use tokio::sync::RwLock;
 
@@ -161,6 +163,7 @@ pub struct Mutations(
     pub AssetMutations,
     pub AssetLogMutations,
     pub InventoryAdjustmentMutations,
+    pub ReportMutations,
 );
 
 impl Mutations {
@@ -185,6 +188,7 @@ impl Mutations {
             AssetMutations,
             AssetLogMutations,
             InventoryAdjustmentMutations,
+            ReportMutations,
         )
     }
 }
@@ -197,6 +201,8 @@ pub struct GraphqlSchema {
     initialisation: InitialisationSchema,
     /// Set on startup based on InitialisationStatus and then updated via SiteIsInitialisedCallback after initialisation
     is_operational: RwLock<bool>,
+    /// For request metrics
+    service_provider: Data<ServiceProvider>,
 }
 
 pub struct GraphSchemaData {
@@ -230,7 +236,7 @@ impl GraphqlSchema {
                 .data(settings.clone())
                 .data(validated_plugins.clone())
                 .finish();
-        // Self requester does not need loggers
+        // Self requester does not need loggers, or query size limits (used for reports)
 
         // Operational schema
         let operational_builder =
@@ -243,6 +249,8 @@ impl GraphqlSchema {
                 .data(validated_plugins.clone())
                 // Add self requester to operational
                 .data(Data::new(SelfRequestImpl::new_boxed(self_requester_schema)));
+        let operational_builder =
+            limit_query_size(operational_builder, &settings.server.request_limits);
 
         // Initialisation schema should ony need service_provider
         let initialisiation_builder = InitialisationSchema::build(
@@ -256,6 +264,7 @@ impl GraphqlSchema {
             operational: operational_builder.finish(),
             initialisation: initialisiation_builder.finish(),
             is_operational: RwLock::new(is_operational),
+            service_provider,
         }
     }
 
@@ -265,13 +274,46 @@ impl GraphqlSchema {
 
     async fn execute(&self, http_req: HttpRequest, req: GraphQLRequest) -> Response {
         let req = req.into_inner();
-        if *self.is_operational.read().await {
+        // User and store are added to log context when request is authorised (validate_auth)
+        LogContext::update(|context| {
+            context.operation = Some(format!(
+                "graphql {}",
+                req.operation_name.as_deref().unwrap_or("unnamed")
+            ))
+        });
+        let operation_name = req.operation_name.clone();
+        let start = Instant::now();
+
+        let response = if *self.is_operational.read().await {
             // auth_data is only available in schema in operational mode
             let user_data = auth_data_from_request(&http_req);
             self.operational.execute(req.data(user_data)).await
         } else {
             self.initialisation.execute(req).await
-        }
+        };
+
+        self.service_provider.metrics.record_graphql_request(
+            operation_name.as_deref(),
+            start.elapsed(),
+            response.errors.is_empty(),
+        );
+        response
+    }
+}
+
+/// Query depth and complexity limits from settings, requests over the limits are rejected
+/// before execution with a validation error
+fn limit_query_size<Q, M, S>(
+    builder: SchemaBuilder<Q, M, S>,
+    limits: &RequestLimitSettings,
+) -> SchemaBuilder<Q, M, S> {
+    let builder = match limits.max_graphql_query_depth {
+        Some(depth) => builder.limit_depth(depth),
+        None => builder,
+    };
+    match limits.max_graphql_query_complexity {
+        Some(complexity) => builder.limit_complexity(complexity),
+        None => builder,
     }
 }
 
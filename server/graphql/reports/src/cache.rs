@@ -0,0 +1,92 @@
+use async_graphql::*;
+use chrono::{DateTime, Utc};
+use graphql_core::{standard_graphql_error::validate_auth, ContextExt};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    report::cache::ReportQueryDiagnostics,
+};
+
+pub struct ReportQueryDiagnosticsNode {
+    diagnostics: ReportQueryDiagnostics,
+}
+
+#[Object]
+impl ReportQueryDiagnosticsNode {
+    pub async fn report_id(&self) -> &str {
+        &self.diagnostics.report_id
+    }
+
+    pub async fn query_name(&self) -> &str {
+        &self.diagnostics.query_name
+    }
+
+    /// Number of times the SQL was run
+    pub async fn runs(&self) -> u64 {
+        self.diagnostics.runs
+    }
+
+    /// Number of times cached data was used instead of running the SQL
+    pub async fn cache_hits(&self) -> u64 {
+        self.diagnostics.cache_hits
+    }
+
+    pub async fn last_duration_ms(&self) -> f64 {
+        duration_ms(self.diagnostics.last_duration)
+    }
+
+    pub async fn max_duration_ms(&self) -> f64 {
+        duration_ms(self.diagnostics.max_duration)
+    }
+
+    pub async fn average_duration_ms(&self) -> f64 {
+        if self.diagnostics.runs == 0 {
+            return 0.0;
+        }
+        duration_ms(self.diagnostics.total_duration) / self.diagnostics.runs as f64
+    }
+
+    pub async fn last_row_count(&self) -> usize {
+        self.diagnostics.last_row_count
+    }
+
+    pub async fn last_run_datetime(&self) -> Option<DateTime<Utc>> {
+        self.diagnostics
+            .last_run_datetime
+            .map(|datetime| DateTime::<Utc>::from_naive_utc_and_offset(datetime, Utc))
+    }
+}
+
+fn duration_ms(duration: std::time::Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+pub fn report_query_diagnostics(ctx: &Context<'_>) -> Result<Vec<ReportQueryDiagnosticsNode>> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+            store_id: None,
+        },
+    )?;
+
+    Ok(ctx
+        .service_provider()
+        .report_data_cache
+        .query_diagnostics()
+        .into_iter()
+        .map(|diagnostics| ReportQueryDiagnosticsNode { diagnostics })
+        .collect())
+}
+
+pub fn clear_report_cache_mutation(ctx: &Context<'_>) -> Result<bool> {
+    validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+            store_id: None,
+        },
+    )?;
+
+    ctx.service_provider().report_data_cache.clear();
+    Ok(true)
+}
@@ -1,18 +1,44 @@
 use async_graphql::*;
+use cache::{clear_report_cache_mutation, report_query_diagnostics, ReportQueryDiagnosticsNode};
 use graphql_core::{generic_inputs::PrintReportSortInput, pagination::PaginationInput};
 use printing::{print_report, print_report_definition, PrintReportResponse};
 use reports::{reports, ReportFilterInput, ReportSortInput, ReportsResponse};
+use schedule::{
+    delete_report_schedule_mutation, report_schedules, upsert_report_schedule_mutation,
+    ReportScheduleNode, UpsertReportScheduleInput,
+};
+use service::report::report_service::PrintFormat as DomainPrintFormat;
 
+mod cache;
 mod printing;
 mod reports;
+mod schedule;
 
 #[derive(Default, Clone)]
 pub struct ReportQueries;
 
+#[derive(Default, Clone)]
+pub struct ReportMutations;
+
 #[derive(Enum, Copy, Clone, PartialEq, Eq, Debug)]
 pub enum PrintFormat {
     Pdf,
     Html,
+    /// Only for tabular reports
+    Csv,
+    /// Only for tabular reports
+    Xlsx,
+}
+
+impl PrintFormat {
+    fn to_domain(self) -> DomainPrintFormat {
+        match self {
+            PrintFormat::Pdf => DomainPrintFormat::Pdf,
+            PrintFormat::Html => DomainPrintFormat::Html,
+            PrintFormat::Csv => DomainPrintFormat::Csv,
+            PrintFormat::Xlsx => DomainPrintFormat::Xlsx,
+        }
+    }
 }
 
 #[Object]
@@ -45,22 +71,19 @@ impl ReportQueries {
         )]
         data_id: Option<String>,
         arguments: Option<serde_json::Value>,
+        #[graphql(
+            desc = "Defaults to pdf for html reports and xlsx for tabular reports, csv and xlsx are only supported for tabular reports"
+        )]
         format: Option<PrintFormat>,
         sort: Option<PrintReportSortInput>,
     ) -> Result<PrintReportResponse> {
-        let report_format = match format {
-            Some(PrintFormat::Html) => Some(service::report::report_service::PrintFormat::Html),
-            Some(PrintFormat::Pdf) | None => {
-                Some(service::report::report_service::PrintFormat::Pdf)
-            }
-        };
         print_report(
             ctx,
             store_id,
             report_id,
             data_id,
             arguments,
-            report_format,
+            format.map(PrintFormat::to_domain),
             sort,
         )
         .await
@@ -76,7 +99,62 @@ impl ReportQueries {
         #[graphql(desc = "The report definition to be printed")] report: serde_json::Value,
         data_id: Option<String>,
         arguments: Option<serde_json::Value>,
+        format: Option<PrintFormat>,
     ) -> Result<PrintReportResponse> {
-        print_report_definition(ctx, store_id, name, report, data_id, arguments).await
+        print_report_definition(
+            ctx,
+            store_id,
+            name,
+            report,
+            data_id,
+            arguments,
+            format.map(PrintFormat::to_domain),
+        )
+        .await
+    }
+
+    /// Reports that are printed periodically for the store
+    pub async fn report_schedules(
+        &self,
+        ctx: &Context<'_>,
+        store_id: String,
+    ) -> Result<Vec<ReportScheduleNode>> {
+        report_schedules(ctx, store_id)
+    }
+
+    /// Timing of the SQL report queries since the server started
+    pub async fn report_query_diagnostics(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Vec<ReportQueryDiagnosticsNode>> {
+        report_query_diagnostics(ctx)
+    }
+}
+
+#[Object]
+impl ReportMutations {
+    /// Only reports with SQL queries can be scheduled
+    pub async fn upsert_report_schedule(
+        &self,
+        ctx: &Context<'_>,
+        store_id: String,
+        input: UpsertReportScheduleInput,
+    ) -> Result<ReportScheduleNode> {
+        upsert_report_schedule_mutation(ctx, store_id, input)
+    }
+
+    /// Deletes the schedule and its printed reports
+    pub async fn delete_report_schedule(
+        &self,
+        ctx: &Context<'_>,
+        store_id: String,
+        id: String,
+    ) -> Result<String> {
+        delete_report_schedule_mutation(ctx, store_id, id)
+    }
+
+    /// Removes all cached report data, e.g. after changing data outside of the server
+    pub async fn clear_report_cache(&self, ctx: &Context<'_>) -> Result<bool> {
+        clear_report_cache_mutation(ctx)
     }
 }
@@ -3,7 +3,7 @@ use chrono::Utc;
 use graphql_core::generic_inputs::PrintReportSortInput;
 use graphql_core::standard_graphql_error::{validate_auth, StandardGraphqlError};
 use graphql_core::{ContextExt, RequestUserData};
-use repository::query_json;
+use repository::{query_json, RepositoryError};
 use service::auth::{Resource, ResourceAccessRequest};
 use service::report::definition::{GraphQlQuery, PrintReportSort, ReportDefinition, SQLQuery};
 use service::report::report_service::{PrintFormat, ReportError, ResolvedReportQuery};
@@ -88,6 +88,7 @@ pub async fn print_report(
     // fetch data required for the report
     let result = fetch_data(
         ctx,
+        Some(&report_id),
         &resolved_report.queries,
         &store_id,
         data_id,
@@ -110,6 +111,7 @@ pub async fn print_report(
     // print the report with the fetched data
     let file_id = match service.print_html_report(
         &ctx.get_settings().server.base_dir,
+        &ctx.get_settings().server.pdf_renderer,
         &resolved_report,
         report_data,
         arguments,
@@ -133,6 +135,7 @@ pub async fn print_report_definition(
     report: serde_json::Value,
     data_id: Option<String>,
     arguments: Option<serde_json::Value>,
+    format: Option<PrintFormat>,
 ) -> Result<PrintReportResponse> {
     let user = validate_auth(
         ctx,
@@ -165,6 +168,7 @@ pub async fn print_report_definition(
     // fetch data required for the report
     let result = fetch_data(
         ctx,
+        None,
         &resolved_report.queries,
         &store_id,
         data_id,
@@ -187,10 +191,11 @@ pub async fn print_report_definition(
     // print the report with the fetched data
     let file_id = match service.print_html_report(
         &ctx.get_settings().server.base_dir,
+        &ctx.get_settings().server.pdf_renderer,
         &resolved_report,
         report_data,
         arguments,
-        None,
+        format,
     ) {
         Ok(file_id) => file_id,
         Err(err) => {
@@ -265,8 +270,11 @@ fn query_variables(
     variables
 }
 
+/// `report_id` is not set for report definitions that are not stored (e.g. while developing a
+/// report), their data is not cached
 async fn fetch_data(
     ctx: &Context<'_>,
+    report_id: Option<&str>,
     queries: &Vec<ResolvedReportQuery>,
     store_id: &str,
     data_id: Option<String>,
@@ -301,8 +309,21 @@ async fn fetch_data(
         ResolvedReportQuery::GraphQlQuery(_) => None,
     }) {
         let variables = query_variables(store_id, &data_id, &arguments, &sort, &None);
-        let result = fetch_sql_data(ctx, sql, variables)?;
-        data.insert(sql.name.clone(), result);
+        let rows = match report_id {
+            Some(report_id) => {
+                let connection = ctx.get_connection_manager().connection()?;
+                ctx.service_provider().report_data_cache.query(
+                    &connection,
+                    ctx.get_settings().server.report_cache.as_ref(),
+                    report_id,
+                    sql,
+                    &variables,
+                    || fetch_sql_data(ctx, sql, &variables),
+                )?
+            }
+            None => fetch_sql_data(ctx, sql, &variables)?,
+        };
+        data.insert(sql.name.clone(), serde_json::Value::Array(rows));
     }
 
     Ok(FetchResult::Data(serde_json::Value::Object(data)))
@@ -312,25 +333,19 @@ async fn fetch_data(
 fn fetch_sql_data(
     ctx: &Context<'_>,
     query: &SQLQuery,
-    variables: serde_json::Map<String, serde_json::Value>,
-) -> anyhow::Result<serde_json::Value> {
-    let data = query_json(
-        &ctx.get_settings().database,
-        &query.query_sqlite,
-        &variables,
-    )?;
-    Ok(serde_json::Value::Array(data))
+    variables: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Vec<serde_json::Value>, RepositoryError> {
+    query_json(&ctx.get_settings().database, &query.query_sqlite, variables)
 }
 
 #[cfg(feature = "postgres")]
 fn fetch_sql_data(
     ctx: &Context<'_>,
     query: &SQLQuery,
-    variables: serde_json::Map<String, serde_json::Value>,
-) -> anyhow::Result<serde_json::Value> {
+    variables: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Vec<serde_json::Value>, RepositoryError> {
     let connection = ctx.get_connection_manager().connection()?;
-    let data = query_json(&connection, &query.query_postgres, &variables)?;
-    Ok(serde_json::Value::Array(data))
+    query_json(&connection, &query.query_postgres, variables)
 }
 
 async fn fetch_graphq_data(
@@ -369,6 +384,9 @@ fn map_error(error: ReportError) -> Result<PrintReportErrorInterface> {
         ReportError::MultipleGraphqlQueriesNotAllowed => {
             StandardGraphqlError::BadUserInput(formatted_error)
         }
+        ReportError::UnsupportedPrintFormat(_) => {
+            StandardGraphqlError::BadUserInput(formatted_error)
+        }
     };
 
     Err(graphql_error.extend())
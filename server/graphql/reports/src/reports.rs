@@ -12,7 +12,10 @@ use repository::{
     ContextType as ReportContextDomain, EqualFilter, PaginationOption, Report, ReportFilter,
     ReportSort, ReportSortField, StringFilter,
 };
-use service::auth::{Resource, ResourceAccessRequest};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    report::schedule::is_report_schedulable,
+};
 
 #[derive(Enum, Copy, Clone, PartialEq, Eq)]
 #[graphql(rename_items = "camelCase")]
@@ -75,6 +78,7 @@ pub struct ReportConnector {
 #[derive(PartialEq, Debug)]
 pub struct ReportNode {
     row: Report,
+    store_id: String,
 }
 
 #[Object]
@@ -96,12 +100,27 @@ impl ReportNode {
         &self.row.report_row.sub_context
     }
 
+    /// Semver version of the report definition
+    pub async fn version(&self) -> &Option<String> {
+        &self.row.report_row.version
+    }
+
     pub async fn argument_schema(&self) -> Option<FormSchemaNode> {
         self.row
             .argument_schema
             .clone()
             .map(|schema| FormSchemaNode { schema })
     }
+
+    /// Only reports with SQL queries can be scheduled, reports with GraphQL queries can only be
+    /// printed from the UI
+    pub async fn is_schedulable(&self, ctx: &Context<'_>) -> Result<bool> {
+        let mut service_context = ctx.service_provider().basic_context()?;
+        // Template references are resolved for the store
+        service_context.store_id = self.store_id.clone();
+        is_report_schedulable(&service_context, &self.row.report_row.id)
+            .map_err(|error| StandardGraphqlError::InternalError(format!("{:#?}", error)).extend())
+    }
 }
 
 pub fn reports(
@@ -120,7 +139,7 @@ pub fn reports(
     )?;
 
     let service_provider = ctx.service_provider();
-    let service_context = service_provider.context(store_id, user.user_id)?;
+    let service_context = service_provider.context(store_id.clone(), user.user_id)?;
 
     let reports = service_provider
         .report_service
@@ -134,7 +153,13 @@ pub fn reports(
         .map_err(StandardGraphqlError::from_list_error)?;
     Ok(ReportsResponse::Response(ReportConnector {
         total_count: reports.len() as u32,
-        nodes: reports.into_iter().map(|row| ReportNode { row }).collect(),
+        nodes: reports
+            .into_iter()
+            .map(|row| ReportNode {
+                row,
+                store_id: store_id.clone(),
+            })
+            .collect(),
     }))
 }
 
@@ -0,0 +1,265 @@
+use async_graphql::*;
+use chrono::{DateTime, Utc};
+use graphql_core::{
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use repository::{ReportScheduleRow, ReportScheduleRunRow};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    report::schedule::{
+        delete_report_schedule, get_report_schedule_runs, get_report_schedules,
+        upsert_report_schedule, DeleteReportScheduleError, UpsertReportSchedule,
+        UpsertReportScheduleError,
+    },
+};
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(remote = "repository::ReportScheduleFrequency")]
+pub enum ReportScheduleFrequencyNode {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(remote = "repository::ReportScheduleFormat")]
+pub enum ReportScheduleFormatNode {
+    Pdf,
+    Html,
+    Csv,
+    Xlsx,
+}
+
+pub struct ReportScheduleNode {
+    row: ReportScheduleRow,
+}
+
+pub struct ReportScheduleRunNode {
+    row: ReportScheduleRunRow,
+}
+
+#[Object]
+impl ReportScheduleNode {
+    pub async fn id(&self) -> &str {
+        &self.row.id
+    }
+
+    pub async fn report_id(&self) -> &str {
+        &self.row.report_id
+    }
+
+    pub async fn name(&self) -> &str {
+        &self.row.name
+    }
+
+    pub async fn arguments(&self) -> Option<serde_json::Value> {
+        self.row
+            .arguments
+            .as_ref()
+            .and_then(|arguments| serde_json::from_str(arguments).ok())
+    }
+
+    /// Default format of the report is used when not set
+    pub async fn format(&self) -> Option<ReportScheduleFormatNode> {
+        self.row.format.clone().map(ReportScheduleFormatNode::from)
+    }
+
+    pub async fn frequency(&self) -> ReportScheduleFrequencyNode {
+        ReportScheduleFrequencyNode::from(self.row.frequency.clone())
+    }
+
+    pub async fn next_run_datetime(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_naive_utc_and_offset(self.row.next_run_datetime, Utc)
+    }
+
+    /// Days printed reports are kept for
+    pub async fn retention_days(&self) -> i32 {
+        self.row.retention_days
+    }
+
+    pub async fn email_recipients(&self) -> Vec<String> {
+        self.row
+            .email_recipients
+            .iter()
+            .flat_map(|recipients| recipients.split(','))
+            .map(str::to_string)
+            .collect()
+    }
+
+    pub async fn is_active(&self) -> bool {
+        self.row.is_active
+    }
+
+    /// Latest run first
+    pub async fn runs(&self, ctx: &Context<'_>) -> Result<Vec<ReportScheduleRunNode>> {
+        let service_context = ctx.service_provider().basic_context()?;
+        let runs = get_report_schedule_runs(&service_context, &self.row.id)?;
+        Ok(runs
+            .into_iter()
+            .map(|row| ReportScheduleRunNode { row })
+            .collect())
+    }
+}
+
+#[Object]
+impl ReportScheduleRunNode {
+    /// Id of the printed report, can be retrieved from the `/files` endpoint
+    pub async fn id(&self) -> &str {
+        &self.row.id
+    }
+
+    pub async fn run_datetime(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_naive_utc_and_offset(self.row.run_datetime, Utc)
+    }
+
+    /// Not set if the report failed to print
+    pub async fn file_name(&self) -> Option<&str> {
+        self.row.file_name.as_deref()
+    }
+
+    pub async fn error(&self) -> Option<&str> {
+        self.row.error.as_deref()
+    }
+
+    pub async fn emailed_datetime(&self) -> Option<DateTime<Utc>> {
+        self.row
+            .emailed_datetime
+            .map(|datetime| DateTime::<Utc>::from_naive_utc_and_offset(datetime, Utc))
+    }
+}
+
+#[derive(InputObject)]
+pub struct UpsertReportScheduleInput {
+    pub id: String,
+    pub report_id: String,
+    pub name: String,
+    /// Validated against the argument schema of the report
+    pub arguments: Option<serde_json::Value>,
+    pub format: Option<ReportScheduleFormatNode>,
+    pub frequency: ReportScheduleFrequencyNode,
+    /// First run of a new schedule, following runs are calculated from this datetime
+    pub next_run_datetime: DateTime<Utc>,
+    pub retention_days: Option<i32>,
+    pub email_recipients: Option<Vec<String>>,
+    pub is_active: Option<bool>,
+}
+
+pub fn report_schedules(ctx: &Context<'_>, store_id: String) -> Result<Vec<ReportScheduleNode>> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::Report,
+            store_id: Some(store_id.clone()),
+        },
+    )?;
+
+    let service_context = ctx.service_provider().context(store_id, user.user_id)?;
+    let schedules = get_report_schedules(&service_context)?;
+    Ok(schedules
+        .into_iter()
+        .map(|row| ReportScheduleNode { row })
+        .collect())
+}
+
+pub fn upsert_report_schedule_mutation(
+    ctx: &Context<'_>,
+    store_id: String,
+    input: UpsertReportScheduleInput,
+) -> Result<ReportScheduleNode> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::Report,
+            store_id: Some(store_id.clone()),
+        },
+    )?;
+
+    let service_context = ctx.service_provider().context(store_id, user.user_id)?;
+    let row = upsert_report_schedule(&service_context, input.to_domain()).map_err(map_error)?;
+    Ok(ReportScheduleNode { row })
+}
+
+pub fn delete_report_schedule_mutation(
+    ctx: &Context<'_>,
+    store_id: String,
+    id: String,
+) -> Result<String> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::Report,
+            store_id: Some(store_id.clone()),
+        },
+    )?;
+
+    let service_context = ctx.service_provider().context(store_id, user.user_id)?;
+    delete_report_schedule(&service_context, &ctx.get_settings().server.base_dir, &id)
+        .map_err(map_delete_error)
+}
+
+impl UpsertReportScheduleInput {
+    fn to_domain(self) -> UpsertReportSchedule {
+        let UpsertReportScheduleInput {
+            id,
+            report_id,
+            name,
+            arguments,
+            format,
+            frequency,
+            next_run_datetime,
+            retention_days,
+            email_recipients,
+            is_active,
+        } = self;
+
+        UpsertReportSchedule {
+            id,
+            report_id,
+            name,
+            arguments,
+            format: format.map(Into::into),
+            frequency: frequency.into(),
+            next_run_datetime: next_run_datetime.naive_utc(),
+            retention_days,
+            email_recipients: email_recipients.unwrap_or_default(),
+            is_active,
+        }
+    }
+}
+
+fn map_error(error: UpsertReportScheduleError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        UpsertReportScheduleError::ReportScheduleDoesNotBelongToStore
+        | UpsertReportScheduleError::ReportDoesNotExist
+        | UpsertReportScheduleError::InvalidReport(_)
+        | UpsertReportScheduleError::InvalidArguments(_)
+        | UpsertReportScheduleError::InvalidEmailRecipient(_)
+        | UpsertReportScheduleError::InvalidRetentionDays => BadUserInput(formatted_error),
+        UpsertReportScheduleError::ReportQueryNotSupported => BadUserInput(
+            "Report uses a GraphQL query, only reports with SQL queries can be scheduled"
+                .to_string(),
+        ),
+        UpsertReportScheduleError::DatabaseError(_) => InternalError(formatted_error),
+    };
+
+    graphql_error.extend()
+}
+
+fn map_delete_error(error: DeleteReportScheduleError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        DeleteReportScheduleError::ReportScheduleDoesNotExist
+        | DeleteReportScheduleError::ReportScheduleDoesNotBelongToStore => {
+            BadUserInput(formatted_error)
+        }
+        DeleteReportScheduleError::DatabaseError(_) => InternalError(formatted_error),
+    };
+
+    graphql_error.extend()
+}
@@ -0,0 +1,211 @@
+use async_graphql::{dataloader::DataLoader, *};
+use chrono::{DateTime, Utc};
+use graphql_core::{
+    loader::{StoreByIdLoader, UserLoader},
+    standard_graphql_error::StandardGraphqlError,
+    ContextExt,
+};
+use repository::{audit_log::AuditLog, AuditLogRow, RowActionType};
+use serde_json::Value;
+use service::{audit_log::AuditLogVerification, ListResult};
+
+use super::{StoreNode, UserNode};
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq, Debug)]
+#[graphql(remote = "repository::ChangelogTableName")]
+pub enum ChangelogTableNameNode {
+    Number,
+    Location,
+    LocationMovement,
+    StockLine,
+    Invoice,
+    InvoiceLine,
+    Stocktake,
+    StocktakeLine,
+    Requisition,
+    RequisitionLine,
+    ActivityLog,
+    InventoryAdjustmentReason,
+    Barcode,
+    Clinician,
+    ClinicianStoreJoin,
+    Name,
+    NameStoreJoin,
+    Document,
+    Sensor,
+    TemperatureBreach,
+    TemperatureBreachConfig,
+    TemperatureLog,
+    PackVariant,
+    Currency,
+    AssetClass,
+    AssetCategory,
+    AssetCatalogueType,
+    AssetCatalogueItem,
+    AssetCatalogueItemProperty,
+    AssetCatalogueProperty,
+    SyncFileReference,
+    Asset,
+    AssetLog,
+    AssetLogReason,
+    AssetProperty,
+}
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AuditLogActionNode {
+    Upsert,
+    Delete,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct AuditLogNode {
+    audit_log: AuditLog,
+}
+
+#[derive(SimpleObject)]
+pub struct AuditLogConnector {
+    total_count: u32,
+    nodes: Vec<AuditLogNode>,
+}
+
+#[derive(SimpleObject)]
+pub struct AuditLogIntegrityNode {
+    pub is_valid: bool,
+    /// Number of entries that were verified before the first invalid entry (or all entries if valid)
+    pub checked_count: u32,
+    /// First entry that doesn't match its hash or doesn't link to the previous entry
+    pub first_invalid_id: Option<String>,
+    /// Chain ends at the signed head, false when the newest entries were deleted
+    pub matches_head: bool,
+}
+
+#[Object]
+impl AuditLogNode {
+    pub async fn id(&self) -> &str {
+        &self.row().id
+    }
+
+    pub async fn table_name(&self) -> ChangelogTableNameNode {
+        ChangelogTableNameNode::from(self.row().table_name.clone())
+    }
+
+    pub async fn record_id(&self) -> &str {
+        &self.row().record_id
+    }
+
+    pub async fn action(&self) -> AuditLogActionNode {
+        match self.row().row_action {
+            RowActionType::Upsert => AuditLogActionNode::Upsert,
+            RowActionType::Delete => AuditLogActionNode::Delete,
+        }
+    }
+
+    pub async fn store_id(&self) -> &Option<String> {
+        &self.row().store_id
+    }
+
+    pub async fn user_id(&self) -> &Option<String> {
+        &self.row().user_id
+    }
+
+    pub async fn datetime(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_naive_utc_and_offset(self.row().datetime, Utc)
+    }
+
+    /// JSON object of changed fields, in the shape of `{ "field": { "from": .., "to": .. } }`
+    pub async fn changed_fields(&self) -> Result<Option<Value>> {
+        parse_json(&self.row().changed_fields)
+    }
+
+    /// JSON of the record after the change, null for deletes
+    pub async fn record_snapshot(&self) -> Result<Option<Value>> {
+        parse_json(&self.row().record_snapshot)
+    }
+
+    pub async fn hash(&self) -> &str {
+        &self.row().hash
+    }
+
+    pub async fn previous_hash(&self) -> &str {
+        &self.row().previous_hash
+    }
+
+    pub async fn user(&self, ctx: &Context<'_>) -> Result<Option<UserNode>> {
+        let loader = ctx.get_loader::<DataLoader<UserLoader>>();
+
+        let user_id = match &self.row().user_id {
+            Some(user_id) => user_id,
+            None => return Ok(None),
+        };
+
+        let result = loader
+            .load_one(user_id.clone())
+            .await?
+            .map(UserNode::from_domain);
+
+        Ok(result)
+    }
+
+    pub async fn store(&self, ctx: &Context<'_>) -> Result<Option<StoreNode>> {
+        let loader = ctx.get_loader::<DataLoader<StoreByIdLoader>>();
+
+        let store_id = match &self.row().store_id {
+            Some(store_id) => store_id,
+            None => return Ok(None),
+        };
+
+        let result = loader
+            .load_one(store_id.clone())
+            .await?
+            .map(StoreNode::from_domain);
+
+        Ok(result)
+    }
+}
+
+fn parse_json(json: &Option<String>) -> Result<Option<Value>> {
+    let Some(json) = json else {
+        return Ok(None);
+    };
+
+    let value = serde_json::from_str(json).map_err(|error| {
+        StandardGraphqlError::InternalError(format!("Failed to parse audit log json: {error}"))
+            .extend()
+    })?;
+
+    Ok(Some(value))
+}
+
+impl AuditLogNode {
+    pub fn from_domain(audit_log: AuditLog) -> Self {
+        AuditLogNode { audit_log }
+    }
+
+    pub fn row(&self) -> &AuditLogRow {
+        &self.audit_log.audit_log_row
+    }
+}
+
+impl AuditLogConnector {
+    pub fn from_domain(audit_logs: ListResult<AuditLog>) -> AuditLogConnector {
+        AuditLogConnector {
+            total_count: audit_logs.count,
+            nodes: audit_logs
+                .rows
+                .into_iter()
+                .map(AuditLogNode::from_domain)
+                .collect(),
+        }
+    }
+}
+
+impl AuditLogIntegrityNode {
+    pub fn from_domain(verification: AuditLogVerification) -> AuditLogIntegrityNode {
+        AuditLogIntegrityNode {
+            is_valid: verification.is_valid(),
+            checked_count: verification.checked_count,
+            first_invalid_id: verification.first_invalid.map(|row| row.id),
+            matches_head: verification.matches_head,
+        }
+    }
+}
@@ -43,6 +43,9 @@ pub use self::user::*;
 pub mod activity_log;
 pub use self::activity_log::*;
 
+pub mod audit_log;
+pub use self::audit_log::*;
+
 pub mod period;
 pub use self::period::*;
 
@@ -91,6 +94,12 @@ pub use self::currency::*;
 pub mod sync_file_reference;
 pub use self::sync_file_reference::*;
 
+pub mod sync_buffer;
+pub use self::sync_buffer::*;
+
+pub mod sync_conflict;
+pub use self::sync_conflict::*;
+
 use async_graphql::*;
 pub struct DeleteResponse(pub String);
 #[Object]
@@ -0,0 +1,87 @@
+use async_graphql::*;
+use chrono::{DateTime, Utc};
+use repository::SyncBufferRow;
+use service::ListResult;
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq, Debug)]
+#[graphql(remote = "repository::SyncAction")]
+pub enum SyncBufferActionNode {
+    Upsert,
+    Delete,
+    Merge,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct SyncBufferRecordNode {
+    row: SyncBufferRow,
+}
+
+#[derive(SimpleObject)]
+pub struct SyncBufferRecordConnector {
+    total_count: u32,
+    nodes: Vec<SyncBufferRecordNode>,
+}
+
+#[Object]
+impl SyncBufferRecordNode {
+    pub async fn record_id(&self) -> &str {
+        &self.row.record_id
+    }
+
+    /// Legacy (mSupply) table name
+    pub async fn table_name(&self) -> &str {
+        &self.row.table_name
+    }
+
+    pub async fn action(&self) -> SyncBufferActionNode {
+        SyncBufferActionNode::from(self.row.action.clone())
+    }
+
+    /// Record as received from central server (or remote site for sync v6)
+    pub async fn data(&self) -> &str {
+        &self.row.data
+    }
+
+    pub async fn source_site_id(&self) -> Option<i32> {
+        self.row.source_site_id
+    }
+
+    pub async fn received_datetime(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_naive_utc_and_offset(self.row.received_datetime, Utc)
+    }
+
+    pub async fn integration_datetime(&self) -> Option<DateTime<Utc>> {
+        self.row
+            .integration_datetime
+            .map(|datetime| DateTime::<Utc>::from_naive_utc_and_offset(datetime, Utc))
+    }
+
+    pub async fn integration_error(&self) -> &Option<String> {
+        &self.row.integration_error
+    }
+
+    pub async fn ignored_datetime(&self) -> Option<DateTime<Utc>> {
+        self.row
+            .ignored_datetime
+            .map(|datetime| DateTime::<Utc>::from_naive_utc_and_offset(datetime, Utc))
+    }
+}
+
+impl SyncBufferRecordNode {
+    pub fn from_domain(row: SyncBufferRow) -> Self {
+        SyncBufferRecordNode { row }
+    }
+}
+
+impl SyncBufferRecordConnector {
+    pub fn from_domain(rows: ListResult<SyncBufferRow>) -> SyncBufferRecordConnector {
+        SyncBufferRecordConnector {
+            total_count: rows.count,
+            nodes: rows
+                .rows
+                .into_iter()
+                .map(SyncBufferRecordNode::from_domain)
+                .collect(),
+        }
+    }
+}
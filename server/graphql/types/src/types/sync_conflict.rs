@@ -0,0 +1,87 @@
+use async_graphql::*;
+use chrono::{DateTime, Utc};
+use repository::SyncConflictRow;
+use service::ListResult;
+
+use super::SyncBufferActionNode;
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq, Debug)]
+#[graphql(remote = "repository::SyncConflictResolution")]
+pub enum SyncConflictResolutionNode {
+    /// Local change is kept and pushed to central server again
+    KeepLocal,
+    /// Central record overwrites the local change
+    AcceptRemote,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct SyncConflictNode {
+    row: SyncConflictRow,
+}
+
+#[derive(SimpleObject)]
+pub struct SyncConflictConnector {
+    total_count: u32,
+    nodes: Vec<SyncConflictNode>,
+}
+
+#[Object]
+impl SyncConflictNode {
+    pub async fn id(&self) -> &str {
+        &self.row.id
+    }
+
+    /// Legacy (mSupply) table name
+    pub async fn table_name(&self) -> &str {
+        &self.row.table_name
+    }
+
+    pub async fn record_id(&self) -> &str {
+        &self.row.record_id
+    }
+
+    pub async fn action(&self) -> SyncBufferActionNode {
+        SyncBufferActionNode::from(self.row.action.clone())
+    }
+
+    /// Record as received from central server, the local record is not changed until resolved
+    pub async fn remote_data(&self) -> &str {
+        &self.row.remote_data
+    }
+
+    pub async fn detected_datetime(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_naive_utc_and_offset(self.row.detected_datetime, Utc)
+    }
+
+    pub async fn resolved_datetime(&self) -> Option<DateTime<Utc>> {
+        self.row
+            .resolved_datetime
+            .map(|datetime| DateTime::<Utc>::from_naive_utc_and_offset(datetime, Utc))
+    }
+
+    pub async fn resolution(&self) -> Option<SyncConflictResolutionNode> {
+        self.row
+            .resolution
+            .clone()
+            .map(SyncConflictResolutionNode::from)
+    }
+}
+
+impl SyncConflictNode {
+    pub fn from_domain(row: SyncConflictRow) -> Self {
+        SyncConflictNode { row }
+    }
+}
+
+impl SyncConflictConnector {
+    pub fn from_domain(rows: ListResult<SyncConflictRow>) -> SyncConflictConnector {
+        SyncConflictConnector {
+            total_count: rows.count,
+            nodes: rows
+                .rows
+                .into_iter()
+                .map(SyncConflictNode::from_domain)
+                .collect(),
+        }
+    }
+}
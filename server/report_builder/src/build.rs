@@ -1,7 +1,7 @@
 use anyhow::Result;
 use service::report::definition::{
     DefaultQuery, GraphQlQuery, ReportDefinition, ReportDefinitionEntry, ReportDefinitionIndex,
-    ReportOutputType, SQLQuery, TeraTemplate,
+    ReportOutputType, SQLQuery, TabularTemplate, TeraTemplate,
 };
 use std::{
     collections::HashMap,
@@ -11,6 +11,9 @@ use std::{
 
 use crate::BuildArgs;
 
+/// Main template files with this suffix are tabular templates (csv/xlsx output)
+const TABULAR_TEMPLATE_SUFFIX: &str = ".tabular.json";
+
 fn find_project_files(dir: &Path) -> anyhow::Result<HashMap<String, PathBuf>> {
     let mut map = HashMap::new();
     let paths = std::fs::read_dir(dir)?;
@@ -94,6 +97,11 @@ fn extract_sql_entry(
 }
 
 fn make_report(args: &BuildArgs, mut files: HashMap<String, PathBuf>) -> Result<ReportDefinition> {
+    if let Some(version) = &args.report_version {
+        semver::Version::parse(version).map_err(|err| {
+            anyhow::Error::msg(format!("Invalid report version {}: {}", version, err))
+        })?;
+    }
     let mut index = ReportDefinitionIndex {
         template: Some(args.template.clone()),
         header: None,
@@ -108,13 +116,23 @@ fn make_report(args: &BuildArgs, mut files: HashMap<String, PathBuf>) -> Result<
         .ok_or(anyhow::Error::msg("Template file does not exist"))?;
     let data = fs::read_to_string(template_file)
         .map_err(|err| anyhow::Error::msg(format!("Failed to load template file: {}", err)))?;
-    entries.insert(
-        args.template.clone(),
+    let template = if args.template.ends_with(TABULAR_TEMPLATE_SUFFIX) {
+        if args.header.is_some() || args.footer.is_some() {
+            return Err(anyhow::Error::msg(
+                "Header and footer are not supported for tabular templates",
+            ));
+        }
+        let tabular: TabularTemplate = serde_json::from_str(&data).map_err(|err| {
+            anyhow::Error::msg(format!("Failed to parse tabular template file: {}", err))
+        })?;
+        ReportDefinitionEntry::TabularTemplate(tabular)
+    } else {
         ReportDefinitionEntry::TeraTemplate(TeraTemplate {
             output: ReportOutputType::Html,
             template: data,
-        }),
-    );
+        })
+    };
+    entries.insert(args.template.clone(), template);
 
     // header
     if let Some(header) = &args.header {
@@ -229,7 +247,11 @@ fn make_report(args: &BuildArgs, mut files: HashMap<String, PathBuf>) -> Result<
         entries.insert(name, value);
     }
 
-    Ok(ReportDefinition { index, entries })
+    Ok(ReportDefinition {
+        index,
+        version: args.report_version.clone(),
+        entries,
+    })
 }
 
 pub fn build(args: BuildArgs) -> anyhow::Result<()> {
@@ -1,5 +1,5 @@
 use clap::Parser;
-use report_builder::{build::build, print::print_report, Action, Args};
+use report_builder::{build::build, preview::preview, print::print_report, Action, Args};
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
@@ -15,8 +15,12 @@ fn main() -> anyhow::Result<()> {
                 args.report,
                 args.data_id,
                 args.arguments_file,
+                args.format,
             )?;
         }
+        Action::Preview(args) => {
+            preview(args)?;
+        }
     };
 
     Ok(())
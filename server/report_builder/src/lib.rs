@@ -1,4 +1,5 @@
 pub mod build;
+pub mod preview;
 pub mod print;
 
 use clap::{Parser, Subcommand};
@@ -14,6 +15,7 @@ pub struct Args {
 pub enum Action {
     Build(BuildArgs),
     Print(PrintArgs),
+    Preview(PreviewArgs),
 }
 
 #[derive(clap::Args)]
@@ -24,7 +26,8 @@ pub struct BuildArgs {
     /// output path
     #[clap(short, long)]
     pub output: Option<String>,
-    /// Main template name
+    /// Main template name.
+    /// A main template ending with `.tabular.json` is a tabular template (csv/xlsx output)
     #[clap(long)]
     pub template: String,
     #[clap(long)]
@@ -49,6 +52,11 @@ pub struct BuildArgs {
     /// GraphQL query since otherwise data from the GraphQL query might get overwritten.
     #[clap(long, value_parser, value_delimiter = ' ')]
     pub query_sql: Option<Vec<String>>,
+
+    /// Semver version of the report definition, e.g. "1.2.0".
+    /// Sites don't replace an installed report with a lower version.
+    #[clap(long)]
+    pub report_version: Option<String>,
 }
 
 #[derive(clap::Args)]
@@ -66,6 +74,10 @@ pub struct PrintArgs {
     /// The output file path
     #[clap(long)]
     pub output: Option<String>,
+    /// Output format, one of: "pdf" | "html" | "csv" | "xlsx".
+    /// Defaults to pdf for html reports and xlsx for tabular reports.
+    #[clap(long)]
+    pub format: Option<String>,
     /// The YAML config data to connected to the remote server.
     /// Containing:
     /// - url
@@ -74,3 +86,21 @@ pub struct PrintArgs {
     #[clap(long)]
     pub config: String,
 }
+
+#[derive(clap::Args)]
+pub struct PreviewArgs {
+    /// Path to the report definition json file
+    #[clap(short, long)]
+    pub report: String,
+    /// Json file with sample report data, i.e. the `data` object in the templates
+    #[clap(long)]
+    pub data_file: String,
+    #[clap(long)]
+    pub arguments_file: Option<String>,
+    /// Locale of the translations and formatting, e.g. `fr`
+    #[clap(long, default_value = "en")]
+    pub locale: String,
+    /// The output html file path
+    #[clap(long)]
+    pub output: Option<String>,
+}
@@ -0,0 +1,53 @@
+use std::{fs, path::Path};
+
+use service::report::{definition::ReportDefinition, preview::preview_report};
+
+use crate::PreviewArgs;
+
+fn load_json<T: serde::de::DeserializeOwned>(path: &str, name: &str) -> anyhow::Result<T> {
+    println!("> Load {} from: {}", name, path);
+    let data = fs::read_to_string(path)
+        .map_err(|err| anyhow::Error::msg(format!("Failed to load {} file: {}", name, err)))?;
+    serde_json::from_str(&data)
+        .map_err(|err| anyhow::Error::msg(format!("Failed to parse {} file: {}", name, err)))
+}
+
+/// Renders a report definition locally against sample data and reports the variables that are
+/// used by the templates but missing in the data
+pub fn preview(args: PreviewArgs) -> anyhow::Result<()> {
+    let definition: ReportDefinition = load_json(&args.report, "report definition")?;
+    let data = load_json(&args.data_file, "report data")?;
+    let arguments = args
+        .arguments_file
+        .map(|path| load_json(&path, "arguments"))
+        .transpose()?;
+
+    let preview = preview_report(&definition, data, arguments, &args.locale)
+        .map_err(|err| anyhow::Error::msg(format!("Failed to render report: {:?}", err)))?;
+
+    let output_path = args
+        .output
+        .unwrap_or("./generated/preview.html".to_string());
+    let output_path = Path::new(&output_path);
+    fs::create_dir_all(output_path.parent().ok_or(anyhow::Error::msg(format!(
+        "Invalid output path: {:?}",
+        output_path
+    )))?)?;
+    println!("> Write preview to: {:?}", output_path);
+    fs::write(output_path, preview.html)?;
+
+    for reference in &preview.unresolved_references {
+        println!("> Reference not resolved (rendered empty): {}", reference);
+    }
+    for variable in &preview.missing_variables {
+        println!("> Missing variable: {}", variable);
+    }
+    if !preview.missing_variables.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "{} variable(s) missing in the sample data",
+            preview.missing_variables.len()
+        )));
+    }
+
+    Ok(())
+}
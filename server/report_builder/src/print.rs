@@ -22,8 +22,8 @@ query AuthToken($username: String!, $password: String) {
 "#;
 
 const PRINT_QUERY: &str = r#"
-query PrintReportDefinition($storeId: String!, $name: String, $report: JSON!, $dataId: String, $arguments: JSON) {
-  printReportDefinition(dataId: $dataId, name: $name, report: $report, storeId: $storeId, arguments: $arguments) {
+query PrintReportDefinition($storeId: String!, $name: String, $report: JSON!, $dataId: String, $arguments: JSON, $format: PrintFormat) {
+  printReportDefinition(dataId: $dataId, name: $name, report: $report, storeId: $storeId, arguments: $arguments, format: $format) {
     ... on PrintReportNode {
       __typename
       fileId
@@ -88,6 +88,7 @@ fn print_request(
     report: serde_json::Value,
     data_id: Option<String>,
     arguments: Option<serde_json::Value>,
+    format: Option<&str>,
 ) -> anyhow::Result<String> {
     let body = serde_json::json!({
       "query": PRINT_QUERY,
@@ -96,7 +97,8 @@ fn print_request(
         "dataId": data_id,
         "name": name,
         "report": report,
-        "arguments": arguments
+        "arguments": arguments,
+        "format": format
       }
     });
     let response = reqwest::blocking::Client::new()
@@ -118,6 +120,18 @@ fn print_request(
     Ok(file_id)
 }
 
+/// Maps the format argument to the graphql PrintFormat enum value
+fn parse_format(input: &str) -> anyhow::Result<&'static str> {
+    let format = match input {
+        "pdf" => "PDF",
+        "html" => "HTML",
+        "csv" => "CSV",
+        "xlsx" => "XLSX",
+        _ => return Err(anyhow::Error::msg(format!("Invalid format: {}", input))),
+    };
+    Ok(format)
+}
+
 fn fetch_file(
     url: Url,
     token: &str,
@@ -169,7 +183,10 @@ pub fn print_report(
     report_file: String,
     data_id: Option<String>,
     arguments_file: Option<String>,
+    format: Option<String>,
 ) -> anyhow::Result<()> {
+    let format = format.as_deref().map(parse_format).transpose()?;
+
     let arguments = if let Some(arguments_file) = arguments_file {
         println!("> Load arguments from: {}", arguments_file);
         let report_data = fs::read_to_string(arguments_file)
@@ -221,6 +238,7 @@ pub fn print_report(
         report,
         data_id,
         arguments,
+        format,
     )
     .map_err(|err| anyhow::Error::msg(format!("Failed to fetch report data: {}", err)))?;
 
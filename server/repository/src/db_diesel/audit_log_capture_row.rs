@@ -0,0 +1,65 @@
+use super::{
+    audit_log_capture_row::audit_log_capture::dsl as audit_log_capture_dsl, StorageConnection,
+};
+
+use crate::repository_error::RepositoryError;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+table! {
+    audit_log_capture (changelog_cursor) {
+        changelog_cursor -> BigInt,
+        user_id -> Nullable<Text>,
+        datetime -> Timestamp,
+        record_snapshot -> Nullable<Text>,
+    }
+}
+
+/// State of a changed record and the acting user, captured in the transaction of the change.
+/// The audit log processor adds it to the hash chained `audit_log` and deletes the capture
+#[derive(Clone, Queryable, Insertable, Debug, PartialEq, Default)]
+#[diesel(treat_none_as_null = true)]
+#[diesel(table_name = audit_log_capture)]
+pub struct AuditLogCaptureRow {
+    pub changelog_cursor: i64,
+    pub user_id: Option<String>,
+    pub datetime: NaiveDateTime,
+    /// JSON of the record after the change, None for deletes
+    pub record_snapshot: Option<String>,
+}
+
+pub struct AuditLogCaptureRowRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> AuditLogCaptureRowRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        AuditLogCaptureRowRepository { connection }
+    }
+
+    pub fn insert_one(&self, row: &AuditLogCaptureRow) -> Result<(), RepositoryError> {
+        diesel::insert_into(audit_log_capture_dsl::audit_log_capture)
+            .values(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    pub fn find_one_by_cursor(
+        &self,
+        changelog_cursor: i64,
+    ) -> Result<Option<AuditLogCaptureRow>, RepositoryError> {
+        let result = audit_log_capture_dsl::audit_log_capture
+            .filter(audit_log_capture_dsl::changelog_cursor.eq(changelog_cursor))
+            .first(self.connection.lock().connection())
+            .optional()?;
+        Ok(result)
+    }
+
+    pub fn delete(&self, changelog_cursor: i64) -> Result<(), RepositoryError> {
+        diesel::delete(audit_log_capture_dsl::audit_log_capture)
+            .filter(audit_log_capture_dsl::changelog_cursor.eq(changelog_cursor))
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+}
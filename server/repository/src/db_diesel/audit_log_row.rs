@@ -0,0 +1,117 @@
+use super::{audit_log_row::audit_log::dsl as audit_log_dsl, StorageConnection};
+
+use crate::{repository_error::RepositoryError, ChangelogTableName, RowActionType};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+table! {
+    audit_log (id) {
+        id -> Text,
+        changelog_cursor -> BigInt,
+        table_name -> crate::db_diesel::changelog::ChangelogTableNameMapping,
+        record_id -> Text,
+        row_action -> crate::db_diesel::changelog::RowActionTypeMapping,
+        store_id -> Nullable<Text>,
+        user_id -> Nullable<Text>,
+        datetime -> Timestamp,
+        changed_fields -> Nullable<Text>,
+        record_snapshot -> Nullable<Text>,
+        previous_hash -> Text,
+        hash -> Text,
+    }
+}
+
+/// Field level before/after record of a single changelog entry.
+///
+/// Rows form a hash chain in `changelog_cursor` order: `hash` covers the row content
+/// together with `previous_hash`, so altering or removing any row breaks every hash after it.
+#[derive(Clone, Queryable, Insertable, Debug, PartialEq, Default)]
+#[diesel(treat_none_as_null = true)]
+#[diesel(table_name = audit_log)]
+pub struct AuditLogRow {
+    pub id: String,
+    pub changelog_cursor: i64,
+    pub table_name: ChangelogTableName,
+    pub record_id: String,
+    pub row_action: RowActionType,
+    pub store_id: Option<String>,
+    pub user_id: Option<String>,
+    pub datetime: NaiveDateTime,
+    /// JSON object of changed fields, `{ "field": { "from": .., "to": .. } }`
+    pub changed_fields: Option<String>,
+    /// JSON of the record after the change, None for deletes
+    pub record_snapshot: Option<String>,
+    pub previous_hash: String,
+    pub hash: String,
+}
+
+pub struct AuditLogRowRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> AuditLogRowRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        AuditLogRowRepository { connection }
+    }
+
+    /// Audit log is append only, there is no update or delete
+    pub fn insert_one(&self, row: &AuditLogRow) -> Result<(), RepositoryError> {
+        diesel::insert_into(audit_log_dsl::audit_log)
+            .values(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    pub fn find_one_by_id(&self, id: &str) -> Result<Option<AuditLogRow>, RepositoryError> {
+        let result = audit_log_dsl::audit_log
+            .filter(audit_log_dsl::id.eq(id))
+            .first(self.connection.lock().connection())
+            .optional()?;
+        Ok(result)
+    }
+
+    /// Last entry in the hash chain
+    pub fn find_latest(&self) -> Result<Option<AuditLogRow>, RepositoryError> {
+        let result = audit_log_dsl::audit_log
+            .order(audit_log_dsl::changelog_cursor.desc())
+            .first(self.connection.lock().connection())
+            .optional()?;
+        Ok(result)
+    }
+
+    /// Last entry for a record with a known state (a snapshot or a delete), used to diff the next
+    /// change of the record against
+    pub fn find_latest_for_record(
+        &self,
+        table_name: &ChangelogTableName,
+        record_id: &str,
+    ) -> Result<Option<AuditLogRow>, RepositoryError> {
+        let result = audit_log_dsl::audit_log
+            .filter(audit_log_dsl::table_name.eq(table_name.clone()))
+            .filter(audit_log_dsl::record_id.eq(record_id))
+            .filter(
+                audit_log_dsl::record_snapshot
+                    .is_not_null()
+                    .or(audit_log_dsl::row_action.eq(RowActionType::Delete)),
+            )
+            .order(audit_log_dsl::changelog_cursor.desc())
+            .first(self.connection.lock().connection())
+            .optional()?;
+        Ok(result)
+    }
+
+    /// Entries in chain order, starting after `after_cursor`
+    pub fn find_chain(
+        &self,
+        after_cursor: i64,
+        limit: u32,
+    ) -> Result<Vec<AuditLogRow>, RepositoryError> {
+        let result = audit_log_dsl::audit_log
+            .filter(audit_log_dsl::changelog_cursor.gt(after_cursor))
+            .order(audit_log_dsl::changelog_cursor.asc())
+            .limit(limit.into())
+            .load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+}
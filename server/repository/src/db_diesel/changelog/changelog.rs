@@ -42,6 +42,8 @@ table! {
 
 joinable!(changelog_deduped -> name_link (name_link_id));
 allow_tables_to_appear_in_same_query!(changelog_deduped, name_link);
+joinable!(changelog -> name_link (name_link_id));
+allow_tables_to_appear_in_same_query!(changelog, name_link);
 
 #[cfg(not(feature = "postgres"))]
 sql_function!(
@@ -102,7 +104,11 @@ pub(crate) enum ChangeLogSyncStyle {
     Central,
     Remote,
     File,
-    // Transfer,
+    /// Synced with legacy central, or with omSupply central (like `Remote`) when the site uses
+    /// transactional v6 sync
+    Transactional,
+    /// Like `Transactional`, also sent to the site of the other party of a transfer (by name_id)
+    Transfer,
     // Patient??  etc
 }
 // When adding a new change log record type, specify how it should be synced
@@ -113,13 +119,13 @@ impl ChangelogTableName {
             ChangelogTableName::Number => ChangeLogSyncStyle::Legacy,
             ChangelogTableName::Location => ChangeLogSyncStyle::Legacy,
             ChangelogTableName::LocationMovement => ChangeLogSyncStyle::Legacy,
-            ChangelogTableName::StockLine => ChangeLogSyncStyle::Legacy,
-            ChangelogTableName::Invoice => ChangeLogSyncStyle::Legacy,
-            ChangelogTableName::InvoiceLine => ChangeLogSyncStyle::Legacy,
-            ChangelogTableName::Stocktake => ChangeLogSyncStyle::Legacy,
-            ChangelogTableName::StocktakeLine => ChangeLogSyncStyle::Legacy,
-            ChangelogTableName::Requisition => ChangeLogSyncStyle::Legacy,
-            ChangelogTableName::RequisitionLine => ChangeLogSyncStyle::Legacy,
+            ChangelogTableName::StockLine => ChangeLogSyncStyle::Transactional,
+            ChangelogTableName::Invoice => ChangeLogSyncStyle::Transfer,
+            ChangelogTableName::InvoiceLine => ChangeLogSyncStyle::Transfer,
+            ChangelogTableName::Stocktake => ChangeLogSyncStyle::Transactional,
+            ChangelogTableName::StocktakeLine => ChangeLogSyncStyle::Transactional,
+            ChangelogTableName::Requisition => ChangeLogSyncStyle::Transfer,
+            ChangelogTableName::RequisitionLine => ChangeLogSyncStyle::Transfer,
             ChangelogTableName::ActivityLog => ChangeLogSyncStyle::Legacy,
             ChangelogTableName::InventoryAdjustmentReason => ChangeLogSyncStyle::Legacy,
             ChangelogTableName::Barcode => ChangeLogSyncStyle::Legacy,
@@ -147,6 +153,26 @@ impl ChangelogTableName {
             ChangelogTableName::AssetProperty => ChangeLogSyncStyle::Central,
         }
     }
+
+    /// Tables synced with omSupply central (v6) instead of legacy central when transactional v6
+    /// sync is turned on
+    pub fn transactional_sync_tables() -> Vec<Self> {
+        ChangelogTableName::iter()
+            .filter(|table| {
+                matches!(
+                    table.sync_style(),
+                    ChangeLogSyncStyle::Transactional | ChangeLogSyncStyle::Transfer
+                )
+            })
+            .collect()
+    }
+
+    /// Tables that are also sent to the site of the other party of a transfer
+    pub fn transfer_sync_tables() -> Vec<Self> {
+        ChangelogTableName::iter()
+            .filter(|table| matches!(table.sync_style(), ChangeLogSyncStyle::Transfer))
+            .collect()
+    }
 }
 
 #[derive(Debug, PartialEq, Insertable, Default)]
@@ -254,11 +280,17 @@ impl<'a> ChangelogRepository<'a> {
         batch_size: u32,
         sync_site_id: i32,
         is_initialized: bool,
+        include_transactional: bool,
     ) -> Result<Vec<ChangelogRow>, RepositoryError> {
         let result = with_locked_changelog_table(self.connection, |locked_con| {
-            let query = create_filtered_outgoing_sync_query(earliest, sync_site_id, is_initialized)
-                .order(changelog_deduped::cursor.asc())
-                .limit(batch_size.into());
+            let query = create_filtered_outgoing_sync_query(
+                earliest,
+                sync_site_id,
+                is_initialized,
+                include_transactional,
+            )
+            .order(changelog_deduped::cursor.asc())
+            .limit(batch_size.into());
 
             // Debug diesel query
             // println!(
@@ -292,13 +324,98 @@ impl<'a> ChangelogRepository<'a> {
         earliest: u64,
         sync_site_id: i32,
         is_initialized: bool,
+        include_transactional: bool,
     ) -> Result<u64, RepositoryError> {
-        let result = create_filtered_outgoing_sync_query(earliest, sync_site_id, is_initialized)
-            .count()
-            .get_result::<i64>(self.connection.lock().connection())?;
+        let result = create_filtered_outgoing_sync_query(
+            earliest,
+            sync_site_id,
+            is_initialized,
+            include_transactional,
+        )
+        .count()
+        .get_result::<i64>(self.connection.lock().connection())?;
         Ok(result as u64)
     }
 
+    /// Latest change of a record (queries changelog directly, deduped view is slow for single records)
+    pub fn find_latest_for_record(
+        &self,
+        table_name: &ChangelogTableName,
+        record_id: &str,
+    ) -> Result<Option<ChangelogRow>, RepositoryError> {
+        let result: Option<(ChangelogRow, Option<NameLinkRow>)> = changelog::table
+            .left_join(name_link::table)
+            .filter(changelog::table_name.eq(table_name.clone()))
+            .filter(changelog::record_id.eq(record_id))
+            .order(changelog::cursor.desc())
+            .first(self.connection.lock().connection())
+            .optional()?;
+
+        Ok(result.map(|(change_log_row, name_link_row)| ChangelogRow {
+            name_id: name_link_row.map(|r| r.name_id),
+            ..change_log_row
+        }))
+    }
+
+    /// Like `changelogs` but from the changelog table instead of the deduped view, i.e. every
+    /// change of a record is returned
+    pub fn all_changelogs(
+        &self,
+        earliest: u64,
+        limit: u32,
+    ) -> Result<Vec<ChangelogRow>, RepositoryError> {
+        let result = with_locked_changelog_table(self.connection, |locked_con| {
+            let result: Vec<ChangelogJoin> = changelog::table
+                .left_join(name_link::table)
+                .filter(changelog::cursor.ge(earliest.try_into().unwrap_or(0)))
+                .order(changelog::cursor.asc())
+                .limit(limit.into())
+                .load(locked_con.connection())?;
+            Ok(result
+                .into_iter()
+                .map(|(change_log_row, name_link_row)| ChangelogRow {
+                    name_id: name_link_row.map(|r| r.name_id),
+                    ..change_log_row
+                })
+                .collect())
+        })?;
+        Ok(result)
+    }
+
+    /// Changelogs added by the current transaction, must be called in a transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `after_cursor` - Latest cursor when the transaction began
+    pub fn changelogs_in_transaction(
+        &self,
+        after_cursor: u64,
+    ) -> Result<Vec<ChangelogRow>, RepositoryError> {
+        let query: IntoBoxed<'static, LeftJoin<changelog::table, name_link::table>, DBType> =
+            changelog::table.left_join(name_link::table).into_boxed();
+        let query = query
+            .filter(changelog::cursor.gt(after_cursor as i64))
+            .order(changelog::cursor.asc());
+
+        // sqlite has a single writer, all changelogs after the transaction began are from the
+        // transaction. Postgres writers run concurrently, the rows have to be inserted by this
+        // transaction. `transaction_id` defaults to `txid_current()`, which is the id of the top
+        // level transaction, also for rows inserted in savepoints (unlike xmin)
+        #[cfg(feature = "postgres")]
+        let query = query.filter(diesel::dsl::sql::<diesel::sql_types::Bool>(
+            "changelog.transaction_id = txid_current()",
+        ));
+
+        let result: Vec<ChangelogJoin> = query.load(self.connection.lock().connection())?;
+        Ok(result
+            .into_iter()
+            .map(|(change_log_row, name_link_row)| ChangelogRow {
+                name_id: name_link_row.map(|r| r.name_id),
+                ..change_log_row
+            })
+            .collect())
+    }
+
     /// Returns latest change log
     /// After initial sync we use this method to get the latest cursor to make sure we don't try to push any records that were synced to this site on initialisation
     pub fn latest_cursor(&self) -> Result<u64, RepositoryError> {
@@ -308,6 +425,18 @@ impl<'a> ChangelogRepository<'a> {
         Ok(result.unwrap_or(0) as u64)
     }
 
+    /// Latest cursor of changes to the tables, 0 if the tables have no changes
+    pub fn latest_cursor_for_tables(
+        &self,
+        tables: &[ChangelogTableName],
+    ) -> Result<u64, RepositoryError> {
+        let result = changelog::table
+            .filter(changelog::table_name.eq_any(tables.to_vec()))
+            .select(diesel::dsl::max(changelog::cursor))
+            .first::<Option<i64>>(self.connection.lock().connection())?;
+        Ok(result.unwrap_or(0) as u64)
+    }
+
     // Delete all change logs with cursor greater-equal cursor_ge
     pub fn delete(&self, cursor_ge: i64) -> Result<(), RepositoryError> {
         diesel::delete(changelog::dsl::changelog)
@@ -424,6 +553,7 @@ fn create_filtered_outgoing_sync_query(
     earliest: u64,
     sync_site_id: i32,
     is_initialized: bool,
+    include_transactional: bool,
 ) -> BoxedChangelogQuery {
     let mut query = changelog_deduped::table
         .left_join(name_link::table)
@@ -449,9 +579,23 @@ fn create_filtered_outgoing_sync_query(
         .filter(|table| matches!(table.sync_style(), ChangeLogSyncStyle::Central))
         .collect();
 
-    // Remote Records
+    // Remote Records, transactional records are synced like remote records when the site uses
+    // transactional v6 sync
     let remote_sync_table_names: Vec<ChangelogTableName> = ChangelogTableName::iter()
-        .filter(|table| matches!(table.sync_style(), ChangeLogSyncStyle::Remote))
+        .filter(|table| match table.sync_style() {
+            ChangeLogSyncStyle::Remote => true,
+            ChangeLogSyncStyle::Transactional | ChangeLogSyncStyle::Transfer => {
+                include_transactional
+            }
+            _ => false,
+        })
+        .collect();
+
+    // Transfer Records, also sent to the site of the other party
+    let transfer_sync_table_names: Vec<ChangelogTableName> = ChangelogTableName::iter()
+        .filter(|table| {
+            include_transactional && matches!(table.sync_style(), ChangeLogSyncStyle::Transfer)
+        })
         .collect();
 
     let active_stores_for_site = store::table
@@ -459,6 +603,11 @@ fn create_filtered_outgoing_sync_query(
         .select(store::id.nullable())
         .into_boxed();
 
+    let store_name_ids_for_site = store::table
+        .filter(store::site_id.eq(sync_site_id))
+        .select(store::name_id)
+        .into_boxed();
+
     // Filter the query for the matching records for each type
     query = query.filter(
         changelog_deduped::table_name
@@ -466,7 +615,10 @@ fn create_filtered_outgoing_sync_query(
             .or(changelog_deduped::table_name.eq(ChangelogTableName::SyncFileReference)) // All sites get all sync file references (not necessarily files)
             .or(changelog_deduped::table_name
                 .eq_any(remote_sync_table_names)
-                .and(changelog_deduped::store_id.eq_any(active_stores_for_site))),
+                .and(changelog_deduped::store_id.eq_any(active_stores_for_site)))
+            .or(changelog_deduped::table_name
+                .eq_any(transfer_sync_table_names)
+                .and(name_link::name_id.eq_any(store_name_ids_for_site))),
         // Any other special cases could be handled here...
     );
 
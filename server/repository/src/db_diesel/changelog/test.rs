@@ -296,6 +296,23 @@ async fn test_changelog_filter() {
             .unwrap(),
         vec![log1.clone(), log2.clone(), log4.clone()]
     );
+
+    // Latest cursor of tables
+    assert_eq!(
+        changelog_repo.latest_cursor_for_tables(&[ChangelogTableName::Invoice]),
+        Ok(3)
+    );
+    assert_eq!(
+        changelog_repo.latest_cursor_for_tables(&[
+            ChangelogTableName::Requisition,
+            ChangelogTableName::StocktakeLine
+        ]),
+        Ok(4)
+    );
+    assert_eq!(
+        changelog_repo.latest_cursor_for_tables(&[ChangelogTableName::Location]),
+        Ok(0)
+    );
 }
 
 struct TestRecord<T> {
@@ -625,7 +642,7 @@ async fn test_changelog_outgoing_sync_records() {
     let repo = ChangelogRepository::new(&connection);
 
     let outgoing_results = repo
-        .outgoing_sync_records_from_central(0, 10, 1, true)
+        .outgoing_sync_records_from_central(0, 10, 1, true, false)
         .unwrap();
     assert_eq!(outgoing_results.len(), 0); // Nothing to send to the remote site yet...
 
@@ -645,7 +662,7 @@ async fn test_changelog_outgoing_sync_records() {
     let _result = row.upsert(&connection).unwrap();
 
     let outgoing_results = repo
-        .outgoing_sync_records_from_central(0, 1000, 1, true)
+        .outgoing_sync_records_from_central(0, 1000, 1, true, false)
         .unwrap();
     // outgoing_results should contain the changelog record for the asset class
     assert_eq!(outgoing_results.len(), 1);
@@ -670,7 +687,7 @@ async fn test_changelog_outgoing_sync_records() {
     // The asset class and the asset
 
     let outgoing_results = repo
-        .outgoing_sync_records_from_central(0, 1000, site1_id, false)
+        .outgoing_sync_records_from_central(0, 1000, site1_id, false, false)
         .unwrap();
     assert_eq!(outgoing_results.len(), 2);
     assert_eq!(outgoing_results[0].record_id, asset_class_id);
@@ -678,15 +695,113 @@ async fn test_changelog_outgoing_sync_records() {
 
     // If not during initialisation, we should only get the asset_class as the asset was synced from the site already
     let outgoing_results = repo
-        .outgoing_sync_records_from_central(0, 1000, site1_id, true)
+        .outgoing_sync_records_from_central(0, 1000, site1_id, true, false)
         .unwrap();
     assert_eq!(outgoing_results.len(), 1);
     assert_eq!(outgoing_results[0].record_id, asset_class_id);
 
     // Site 2 should only get the asset_class
     let outgoing_results = repo
-        .outgoing_sync_records_from_central(0, 1000, site2_id, true)
+        .outgoing_sync_records_from_central(0, 1000, site2_id, true, false)
         .unwrap();
     assert_eq!(outgoing_results.len(), 1);
     assert_eq!(outgoing_results[0].record_id, asset_class_id);
 }
+
+#[actix_rt::test]
+async fn test_changelog_outgoing_transactional_records() {
+    let (_, connection, _, _) = test_db::setup_all(
+        "test_changelog_outgoing_transactional_records",
+        MockDataInserts::none().names().stores(),
+    )
+    .await;
+
+    let repo = ChangelogRepository::new(&connection);
+    let site1_id = mock_store_a().site_id;
+    let site2_id = mock_store_b().site_id;
+    let site3_id = 3;
+
+    // Outbound shipment from store a (site 1) to store b (site 2)
+    let invoice = inline_init(|r: &mut InvoiceRow| {
+        r.id = "transfer_invoice".to_string();
+        r.store_id = mock_store_a().id;
+        r.name_link_id = mock_store_b().name_id;
+    });
+    InvoiceRowRepository::new(&connection)
+        .upsert_one(&invoice)
+        .unwrap();
+
+    let outgoing_invoice_ids = |site_id: i32, include_transactional: bool| -> Vec<String> {
+        repo.outgoing_sync_records_from_central(0, 1000, site_id, false, include_transactional)
+            .unwrap()
+            .into_iter()
+            .filter(|changelog| changelog.table_name == ChangelogTableName::Invoice)
+            .map(|changelog| changelog.record_id)
+            .collect()
+    };
+
+    // Transactional records are synced with legacy central by default
+    assert!(outgoing_invoice_ids(site1_id, false).is_empty());
+    assert!(outgoing_invoice_ids(site2_id, false).is_empty());
+
+    // Store site and other party of the transfer get the invoice
+    assert_eq!(
+        outgoing_invoice_ids(site1_id, true),
+        vec![invoice.id.clone()]
+    );
+    assert_eq!(
+        outgoing_invoice_ids(site2_id, true),
+        vec![invoice.id.clone()]
+    );
+    assert!(outgoing_invoice_ids(site3_id, true).is_empty());
+
+    assert!(ChangelogTableName::transactional_sync_tables().contains(&ChangelogTableName::Invoice));
+    assert!(!ChangelogTableName::transactional_sync_tables().contains(&ChangelogTableName::Name));
+}
+
+#[cfg(feature = "postgres")]
+#[actix_rt::test]
+async fn test_changelogs_in_transaction_savepoint() {
+    use crate::{RepositoryError, TransactionError};
+
+    let (_, connection, connection_manager, _) = test_db::setup_all(
+        "test_changelogs_in_transaction_savepoint",
+        MockDataInserts::none().names().stores(),
+    )
+    .await;
+    let other_connection = connection_manager.connection().unwrap();
+    let cursor = ChangelogRepository::new(&connection)
+        .latest_cursor()
+        .unwrap();
+
+    let result: Result<Vec<String>, TransactionError<RepositoryError>> = connection
+        .transaction_sync_etc(
+            |connection| {
+                LocationRowRepository::new(connection).upsert_one(&mock_location_1())?;
+                // Savepoint, rows get the xmin of the subtransaction
+                connection
+                    .transaction_sync_etc(
+                        |connection| {
+                            LocationRowRepository::new(connection).upsert_one(&mock_location_2())
+                        },
+                        false,
+                    )
+                    .map_err(RepositoryError::from)?;
+                // Committed by another transaction while this one is open
+                LocationRowRepository::new(&other_connection)
+                    .upsert_one(&mock_location_on_hold())?;
+
+                Ok(ChangelogRepository::new(connection)
+                    .changelogs_in_transaction(cursor)?
+                    .into_iter()
+                    .map(|log| log.record_id)
+                    .collect())
+            },
+            false,
+        );
+
+    assert_eq!(
+        result.unwrap(),
+        vec![mock_location_1().id, mock_location_2().id]
+    );
+}
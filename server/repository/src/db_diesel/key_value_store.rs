@@ -25,8 +25,15 @@ pub enum KeyType {
     SyncPullCursorV6,
     SyncPushCursorV6,
     RemoteSyncPushCursor,
+    SyncConflictPushCursor,
     ShipmentTransferProcessorCursor,
     RequisitionTransferProcessorCursor,
+    AuditLogProcessorCursor,
+    AuditLogHead,
+    SyncPackageCentralSequence,
+    SyncPackageAcknowledgedSyncIds,
+    SyncBackfillStoreIds,
+    PeerSyncCursors,
 
     SettingsSyncUrl,
     SettingsSyncUsername,
@@ -36,6 +43,8 @@ pub enum KeyType {
     SettingsSyncSiteId,
     SettingsSyncSiteUuid,
     SettingsSyncIsDisabled,
+    SettingsSyncPriorityStoreIds,
+    SettingsSyncTransactionalV6,
     SettingsTokenSecret,
 
     DatabaseVersion,
@@ -0,0 +1,144 @@
+use super::{
+    label_printer_row::label_printer::dsl as label_printer_dsl, LabelTemplateType,
+    StorageConnection,
+};
+
+use crate::repository_error::RepositoryError;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel_derive_enum::DbEnum;
+
+table! {
+    label_printer (id) {
+        id -> Text,
+        name -> Text,
+        address -> Text,
+        port -> Integer,
+        label_width -> Integer,
+        label_height -> Integer,
+        store_id -> Nullable<Text>,
+        purpose -> Nullable<crate::db_diesel::label_template_row::LabelTemplateTypeMapping>,
+        is_active -> Bool,
+        status -> crate::db_diesel::label_printer_row::LabelPrinterStatusMapping,
+        status_message -> Nullable<Text>,
+        status_datetime -> Nullable<Timestamp>,
+    }
+}
+
+#[derive(DbEnum, Debug, Clone, PartialEq, Eq)]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum LabelPrinterStatus {
+    /// Not polled yet
+    Unknown,
+    Ready,
+    /// The printer responded with an error, e.g. paper out
+    Error,
+    Unreachable,
+}
+
+/// Zebra label printer (local to the site, not synced)
+#[derive(Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq)]
+#[diesel(treat_none_as_null = true)]
+#[diesel(table_name = label_printer)]
+pub struct LabelPrinterRow {
+    pub id: String,
+    pub name: String,
+    pub address: String,
+    pub port: i32,
+    /// Label size in dots
+    pub label_width: i32,
+    pub label_height: i32,
+    /// Store the printer is assigned to, available to all stores if not set
+    pub store_id: Option<String>,
+    /// Type of labels the printer is used for, used for all labels if not set
+    pub purpose: Option<LabelTemplateType>,
+    pub is_active: bool,
+    /// Status of the last health check
+    pub status: LabelPrinterStatus,
+    pub status_message: Option<String>,
+    pub status_datetime: Option<NaiveDateTime>,
+}
+
+pub struct LabelPrinterRowRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> LabelPrinterRowRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        LabelPrinterRowRepository { connection }
+    }
+
+    #[cfg(feature = "postgres")]
+    pub fn upsert_one(&self, row: &LabelPrinterRow) -> Result<(), RepositoryError> {
+        diesel::insert_into(label_printer_dsl::label_printer)
+            .values(row)
+            .on_conflict(label_printer_dsl::id)
+            .do_update()
+            .set(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    pub fn upsert_one(&self, row: &LabelPrinterRow) -> Result<(), RepositoryError> {
+        diesel::replace_into(label_printer_dsl::label_printer)
+            .values(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    pub fn find_one_by_id(&self, id: &str) -> Result<Option<LabelPrinterRow>, RepositoryError> {
+        let result = label_printer_dsl::label_printer
+            .filter(label_printer_dsl::id.eq(id))
+            .first(self.connection.lock().connection())
+            .optional()?;
+        Ok(result)
+    }
+
+    /// Printers assigned to the store and printers available to all stores
+    pub fn find_many_by_store_id(
+        &self,
+        store_id: &str,
+    ) -> Result<Vec<LabelPrinterRow>, RepositoryError> {
+        let result = label_printer_dsl::label_printer
+            .filter(
+                label_printer_dsl::store_id
+                    .eq(store_id)
+                    .or(label_printer_dsl::store_id.is_null()),
+            )
+            .order(label_printer_dsl::name.asc())
+            .load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+
+    pub fn find_all(&self) -> Result<Vec<LabelPrinterRow>, RepositoryError> {
+        let result = label_printer_dsl::label_printer
+            .order(label_printer_dsl::name.asc())
+            .load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+
+    pub fn update_status(
+        &self,
+        id: &str,
+        status: LabelPrinterStatus,
+        status_message: Option<String>,
+        status_datetime: NaiveDateTime,
+    ) -> Result<(), RepositoryError> {
+        diesel::update(label_printer_dsl::label_printer.filter(label_printer_dsl::id.eq(id)))
+            .set((
+                label_printer_dsl::status.eq(status),
+                label_printer_dsl::status_message.eq(status_message),
+                label_printer_dsl::status_datetime.eq(status_datetime),
+            ))
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), RepositoryError> {
+        diesel::delete(label_printer_dsl::label_printer.filter(label_printer_dsl::id.eq(id)))
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use super::{label_template_row::label_template::dsl as label_template_dsl, StorageConnection};
+
+use crate::repository_error::RepositoryError;
+
+use diesel::prelude::*;
+use diesel_derive_enum::DbEnum;
+
+table! {
+    label_template (id) {
+        id -> Text,
+        name -> Text,
+        #[sql_name = "type"] type_ -> crate::db_diesel::label_template_row::LabelTemplateTypeMapping,
+        template -> Text,
+    }
+}
+
+/// Records a label template prints
+#[derive(DbEnum, Debug, Clone, PartialEq, Eq)]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum LabelTemplateType {
+    StockLine,
+    Location,
+    Shipment,
+}
+
+/// ZPL label template with Tera variables (local to the site, not synced)
+#[derive(Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq)]
+#[diesel(treat_none_as_null = true)]
+#[diesel(table_name = label_template)]
+pub struct LabelTemplateRow {
+    pub id: String,
+    pub name: String,
+    #[diesel(column_name = type_)]
+    pub r#type: LabelTemplateType,
+    pub template: String,
+}
+
+pub struct LabelTemplateRowRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> LabelTemplateRowRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        LabelTemplateRowRepository { connection }
+    }
+
+    #[cfg(feature = "postgres")]
+    pub fn upsert_one(&self, row: &LabelTemplateRow) -> Result<(), RepositoryError> {
+        diesel::insert_into(label_template_dsl::label_template)
+            .values(row)
+            .on_conflict(label_template_dsl::id)
+            .do_update()
+            .set(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    pub fn upsert_one(&self, row: &LabelTemplateRow) -> Result<(), RepositoryError> {
+        diesel::replace_into(label_template_dsl::label_template)
+            .values(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    pub fn find_one_by_id(&self, id: &str) -> Result<Option<LabelTemplateRow>, RepositoryError> {
+        let result = label_template_dsl::label_template
+            .filter(label_template_dsl::id.eq(id))
+            .first(self.connection.lock().connection())
+            .optional()?;
+        Ok(result)
+    }
+
+    pub fn find_many_by_type(
+        &self,
+        r#type: LabelTemplateType,
+    ) -> Result<Vec<LabelTemplateRow>, RepositoryError> {
+        let result = label_template_dsl::label_template
+            .filter(label_template_dsl::type_.eq(r#type))
+            .order(label_template_dsl::name.asc())
+            .load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+
+    pub fn find_all(&self) -> Result<Vec<LabelTemplateRow>, RepositoryError> {
+        let result = label_template_dsl::label_template
+            .order(label_template_dsl::name.asc())
+            .load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), RepositoryError> {
+        diesel::delete(label_template_dsl::label_template.filter(label_template_dsl::id.eq(id)))
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+}
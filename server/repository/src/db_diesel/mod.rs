@@ -3,6 +3,9 @@ use crate::repository_error::RepositoryError;
 pub mod activity_log;
 mod activity_log_row;
 pub mod assets;
+pub mod audit_log;
+mod audit_log_capture_row;
+mod audit_log_row;
 pub mod barcode;
 mod barcode_row;
 pub mod changelog;
@@ -43,6 +46,8 @@ pub mod item;
 mod item_link_row;
 mod item_row;
 pub mod key_value_store;
+mod label_printer_row;
+mod label_template_row;
 pub mod location;
 pub mod location_movement;
 mod location_movement_row;
@@ -65,6 +70,7 @@ mod patient;
 pub mod period;
 pub mod plugin_data;
 mod plugin_data_row;
+mod print_job_row;
 pub mod program_enrolment;
 mod program_enrolment_row;
 pub mod program_event;
@@ -72,6 +78,8 @@ mod program_event_row;
 mod program_requisition;
 pub mod report;
 mod report_row;
+mod report_schedule_row;
+mod report_schedule_run_row;
 pub mod requisition;
 pub mod requisition_line;
 pub mod return_reason;
@@ -91,6 +99,8 @@ pub mod store;
 mod store_preference_row;
 mod store_row;
 pub mod sync_buffer;
+pub mod sync_conflict;
+mod sync_conflict_row;
 pub mod sync_log;
 mod sync_log_row;
 pub mod temperature_breach;
@@ -109,6 +119,9 @@ mod user_store_join_row;
 
 pub use activity_log_row::*;
 pub use assets::*;
+pub use audit_log::*;
+pub use audit_log_capture_row::*;
+pub use audit_log_row::*;
 pub use barcode_row::*;
 pub use changelog::*;
 pub use clinician::*;
@@ -137,6 +150,8 @@ pub use item::*;
 pub use item_link_row::*;
 pub use item_row::*;
 pub use key_value_store::*;
+pub use label_printer_row::*;
+pub use label_template_row::*;
 pub use location_movement_row::*;
 pub use location_row::*;
 pub use master_list::*;
@@ -158,6 +173,7 @@ pub use patient::*;
 pub use period::*;
 pub use plugin_data::*;
 pub use plugin_data_row::*;
+pub use print_job_row::*;
 pub use program_enrolment::*;
 pub use program_enrolment_row::*;
 pub use program_event::*;
@@ -166,6 +182,8 @@ pub use program_requisition::*;
 pub use report::*;
 pub use report_query::*;
 pub use report_row::*;
+pub use report_schedule_row::*;
+pub use report_schedule_run_row::*;
 pub use requisition::*;
 pub use requisition_line::*;
 pub use return_reason_row::*;
@@ -184,6 +202,8 @@ pub use store::*;
 pub use store_preference_row::*;
 pub use store_row::*;
 pub use sync_buffer::*;
+pub use sync_conflict::*;
+pub use sync_conflict_row::*;
 pub use sync_file_reference::*;
 pub use sync_file_reference_row::*;
 pub use sync_log::*;
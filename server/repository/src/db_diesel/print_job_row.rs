@@ -0,0 +1,139 @@
+use super::{print_job_row::print_job::dsl as print_job_dsl, StorageConnection};
+
+use crate::repository_error::RepositoryError;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel_derive_enum::DbEnum;
+
+table! {
+    print_job (id) {
+        id -> Text,
+        label_printer_id -> Nullable<Text>,
+        store_id -> Nullable<Text>,
+        user_id -> Nullable<Text>,
+        payload -> Text,
+        label_count -> Integer,
+        status -> crate::db_diesel::print_job_row::PrintJobStatusMapping,
+        attempts -> Integer,
+        error -> Nullable<Text>,
+        created_datetime -> Timestamp,
+        next_attempt_datetime -> Timestamp,
+        printed_datetime -> Nullable<Timestamp>,
+    }
+}
+
+#[derive(DbEnum, Debug, Clone, PartialEq, Eq)]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum PrintJobStatus {
+    Pending,
+    Printed,
+    /// Sending to the printer failed after all attempts
+    Failed,
+    Cancelled,
+}
+
+/// Labels queued for a label printer (local to the site, not synced)
+#[derive(Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq)]
+#[diesel(treat_none_as_null = true)]
+#[diesel(table_name = print_job)]
+pub struct PrintJobRow {
+    pub id: String,
+    /// The printer of the label printer settings if not set
+    pub label_printer_id: Option<String>,
+    pub store_id: Option<String>,
+    pub user_id: Option<String>,
+    /// ZPL sent to the printer
+    pub payload: String,
+    pub label_count: i32,
+    pub status: PrintJobStatus,
+    pub attempts: i32,
+    /// Error of the last attempt
+    pub error: Option<String>,
+    pub created_datetime: NaiveDateTime,
+    /// Pending jobs are sent at or after this datetime
+    pub next_attempt_datetime: NaiveDateTime,
+    pub printed_datetime: Option<NaiveDateTime>,
+}
+
+pub struct PrintJobRowRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> PrintJobRowRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        PrintJobRowRepository { connection }
+    }
+
+    #[cfg(feature = "postgres")]
+    pub fn upsert_one(&self, row: &PrintJobRow) -> Result<(), RepositoryError> {
+        diesel::insert_into(print_job_dsl::print_job)
+            .values(row)
+            .on_conflict(print_job_dsl::id)
+            .do_update()
+            .set(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    pub fn upsert_one(&self, row: &PrintJobRow) -> Result<(), RepositoryError> {
+        diesel::replace_into(print_job_dsl::print_job)
+            .values(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    pub fn find_one_by_id(&self, id: &str) -> Result<Option<PrintJobRow>, RepositoryError> {
+        let result = print_job_dsl::print_job
+            .filter(print_job_dsl::id.eq(id))
+            .first(self.connection.lock().connection())
+            .optional()?;
+        Ok(result)
+    }
+
+    /// Latest jobs of the store first
+    pub fn find_many_by_store_id(
+        &self,
+        store_id: &str,
+        limit: i64,
+    ) -> Result<Vec<PrintJobRow>, RepositoryError> {
+        let result = print_job_dsl::print_job
+            .filter(print_job_dsl::store_id.eq(store_id))
+            .order(print_job_dsl::created_datetime.desc())
+            .limit(limit)
+            .load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+
+    pub fn find_many_by_label_printer_id(
+        &self,
+        label_printer_id: &str,
+    ) -> Result<Vec<PrintJobRow>, RepositoryError> {
+        let result = print_job_dsl::print_job
+            .filter(print_job_dsl::label_printer_id.eq(label_printer_id))
+            .load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+
+    /// Pending jobs, oldest first (including jobs waiting for their next attempt, so that later
+    /// jobs of the same printer can be held back)
+    pub fn find_pending(&self) -> Result<Vec<PrintJobRow>, RepositoryError> {
+        let result = print_job_dsl::print_job
+            .filter(print_job_dsl::status.eq(PrintJobStatus::Pending))
+            .order(print_job_dsl::created_datetime.asc())
+            .load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+
+    /// Deletes printed, failed and cancelled jobs created before `datetime`
+    pub fn delete_finished_before(&self, datetime: NaiveDateTime) -> Result<(), RepositoryError> {
+        diesel::delete(
+            print_job_dsl::print_job
+                .filter(print_job_dsl::status.ne(PrintJobStatus::Pending))
+                .filter(print_job_dsl::created_datetime.lt(datetime)),
+        )
+        .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+}
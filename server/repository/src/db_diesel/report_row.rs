@@ -42,6 +42,7 @@ table! {
       comment -> Nullable<Text>,
       sub_context -> Nullable<Text>,
       argument_schema_id -> Nullable<Text>,
+      version -> Nullable<Text>,
   }
 }
 
@@ -63,6 +64,8 @@ pub struct ReportRow {
     pub comment: Option<String>,
     pub sub_context: Option<String>,
     pub argument_schema_id: Option<String>,
+    /// Semver of the report definition, None for unversioned definitions
+    pub version: Option<String>,
 }
 
 impl Default for ReportRow {
@@ -76,6 +79,7 @@ impl Default for ReportRow {
             comment: Default::default(),
             sub_context: Default::default(),
             argument_schema_id: Default::default(),
+            version: Default::default(),
         }
     }
 }
@@ -0,0 +1,138 @@
+use super::{report_schedule_row::report_schedule::dsl as report_schedule_dsl, StorageConnection};
+
+use crate::repository_error::RepositoryError;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel_derive_enum::DbEnum;
+
+table! {
+    report_schedule (id) {
+        id -> Text,
+        store_id -> Text,
+        report_id -> Text,
+        user_id -> Text,
+        name -> Text,
+        arguments -> Nullable<Text>,
+        format -> Nullable<crate::db_diesel::report_schedule_row::ReportScheduleFormatMapping>,
+        frequency -> crate::db_diesel::report_schedule_row::ReportScheduleFrequencyMapping,
+        next_run_datetime -> Timestamp,
+        retention_days -> Integer,
+        email_recipients -> Nullable<Text>,
+        is_active -> Bool,
+    }
+}
+
+#[derive(DbEnum, Debug, Clone, PartialEq, Eq)]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum ReportScheduleFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(DbEnum, Debug, Clone, PartialEq, Eq)]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum ReportScheduleFormat {
+    Pdf,
+    Html,
+    Csv,
+    Xlsx,
+}
+
+/// Report that is printed periodically for a store (local to the site, not synced)
+#[derive(Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq)]
+#[diesel(treat_none_as_null = true)]
+#[diesel(table_name = report_schedule)]
+pub struct ReportScheduleRow {
+    pub id: String,
+    pub store_id: String,
+    pub report_id: String,
+    /// User that created the schedule
+    pub user_id: String,
+    pub name: String,
+    /// Report arguments (json)
+    pub arguments: Option<String>,
+    /// Default format of the report is used when not set
+    pub format: Option<ReportScheduleFormat>,
+    pub frequency: ReportScheduleFrequency,
+    pub next_run_datetime: NaiveDateTime,
+    /// Days printed reports are kept for
+    pub retention_days: i32,
+    /// Comma separated email addresses the printed report is sent to
+    pub email_recipients: Option<String>,
+    pub is_active: bool,
+}
+
+pub struct ReportScheduleRowRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> ReportScheduleRowRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        ReportScheduleRowRepository { connection }
+    }
+
+    #[cfg(feature = "postgres")]
+    pub fn upsert_one(&self, row: &ReportScheduleRow) -> Result<(), RepositoryError> {
+        diesel::insert_into(report_schedule_dsl::report_schedule)
+            .values(row)
+            .on_conflict(report_schedule_dsl::id)
+            .do_update()
+            .set(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    pub fn upsert_one(&self, row: &ReportScheduleRow) -> Result<(), RepositoryError> {
+        diesel::replace_into(report_schedule_dsl::report_schedule)
+            .values(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    pub fn find_one_by_id(&self, id: &str) -> Result<Option<ReportScheduleRow>, RepositoryError> {
+        let result = report_schedule_dsl::report_schedule
+            .filter(report_schedule_dsl::id.eq(id))
+            .first(self.connection.lock().connection())
+            .optional()?;
+        Ok(result)
+    }
+
+    pub fn find_many_by_store_id(
+        &self,
+        store_id: &str,
+    ) -> Result<Vec<ReportScheduleRow>, RepositoryError> {
+        let result = report_schedule_dsl::report_schedule
+            .filter(report_schedule_dsl::store_id.eq(store_id))
+            .order(report_schedule_dsl::name.asc())
+            .load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+
+    pub fn find_all(&self) -> Result<Vec<ReportScheduleRow>, RepositoryError> {
+        let result =
+            report_schedule_dsl::report_schedule.load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+
+    /// Active schedules with a next run at or before `datetime`
+    pub fn find_due(
+        &self,
+        datetime: NaiveDateTime,
+    ) -> Result<Vec<ReportScheduleRow>, RepositoryError> {
+        let result = report_schedule_dsl::report_schedule
+            .filter(report_schedule_dsl::is_active.eq(true))
+            .filter(report_schedule_dsl::next_run_datetime.le(datetime))
+            .order(report_schedule_dsl::next_run_datetime.asc())
+            .load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), RepositoryError> {
+        diesel::delete(report_schedule_dsl::report_schedule.filter(report_schedule_dsl::id.eq(id)))
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use super::{
+    report_schedule_run_row::report_schedule_run::dsl as report_schedule_run_dsl, StorageConnection,
+};
+
+use crate::repository_error::RepositoryError;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+table! {
+    report_schedule_run (id) {
+        id -> Text,
+        report_schedule_id -> Text,
+        run_datetime -> Timestamp,
+        file_name -> Nullable<Text>,
+        error -> Nullable<Text>,
+        emailed_datetime -> Nullable<Timestamp>,
+    }
+}
+
+/// Run of a report schedule, the id is the id of the printed report file
+#[derive(Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq)]
+#[diesel(treat_none_as_null = true)]
+#[diesel(table_name = report_schedule_run)]
+pub struct ReportScheduleRunRow {
+    pub id: String,
+    pub report_schedule_id: String,
+    pub run_datetime: NaiveDateTime,
+    /// Not set if the report failed to print
+    pub file_name: Option<String>,
+    /// Error when printing or emailing the report
+    pub error: Option<String>,
+    pub emailed_datetime: Option<NaiveDateTime>,
+}
+
+pub struct ReportScheduleRunRowRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> ReportScheduleRunRowRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        ReportScheduleRunRowRepository { connection }
+    }
+
+    #[cfg(feature = "postgres")]
+    pub fn upsert_one(&self, row: &ReportScheduleRunRow) -> Result<(), RepositoryError> {
+        diesel::insert_into(report_schedule_run_dsl::report_schedule_run)
+            .values(row)
+            .on_conflict(report_schedule_run_dsl::id)
+            .do_update()
+            .set(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    pub fn upsert_one(&self, row: &ReportScheduleRunRow) -> Result<(), RepositoryError> {
+        diesel::replace_into(report_schedule_run_dsl::report_schedule_run)
+            .values(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    pub fn find_one_by_id(
+        &self,
+        id: &str,
+    ) -> Result<Option<ReportScheduleRunRow>, RepositoryError> {
+        let result = report_schedule_run_dsl::report_schedule_run
+            .filter(report_schedule_run_dsl::id.eq(id))
+            .first(self.connection.lock().connection())
+            .optional()?;
+        Ok(result)
+    }
+
+    /// Runs of a schedule, latest first
+    pub fn find_many_by_report_schedule_id(
+        &self,
+        report_schedule_id: &str,
+    ) -> Result<Vec<ReportScheduleRunRow>, RepositoryError> {
+        let result = report_schedule_run_dsl::report_schedule_run
+            .filter(report_schedule_run_dsl::report_schedule_id.eq(report_schedule_id))
+            .order(report_schedule_run_dsl::run_datetime.desc())
+            .load(self.connection.lock().connection())?;
+        Ok(result)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), RepositoryError> {
+        diesel::delete(
+            report_schedule_run_dsl::report_schedule_run.filter(report_schedule_run_dsl::id.eq(id)),
+        )
+        .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+}
@@ -8,6 +8,7 @@ use diesel::prelude::*;
 use crate::{Delete, Upsert};
 
 use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
 
 table! {
     requisition_line (id) {
@@ -39,7 +40,9 @@ joinable!(requisition_line -> item_link (item_link_id));
 joinable!(requisition_line -> requisition (requisition_id));
 allow_tables_to_appear_in_same_query!(requisition_line, item_link);
 
-#[derive(Clone, Queryable, AsChangeset, Insertable, Debug, PartialEq, Default)]
+#[derive(
+    Clone, Queryable, AsChangeset, Insertable, Debug, PartialEq, Default, Serialize, Deserialize,
+)]
 #[diesel(table_name = requisition_line)]
 pub struct RequisitionLineRow {
     pub id: String,
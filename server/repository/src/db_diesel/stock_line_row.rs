@@ -8,6 +8,7 @@ use crate::{db_diesel::barcode_row::barcode, repository_error::RepositoryError,
 use diesel::prelude::*;
 
 use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
 
 table! {
     stock_line (id) {
@@ -37,7 +38,9 @@ joinable!(stock_line -> barcode (barcode_id));
 allow_tables_to_appear_in_same_query!(stock_line, item_link);
 allow_tables_to_appear_in_same_query!(stock_line, name_link);
 
-#[derive(Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq, Default)]
+#[derive(
+    Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq, Default, Serialize, Deserialize,
+)]
 #[diesel(treat_none_as_null = true)]
 #[diesel(table_name = stock_line)]
 pub struct StockLineRow {
@@ -10,6 +10,7 @@ use crate::{repository_error::RepositoryError, Delete, Upsert};
 use diesel::prelude::*;
 
 use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
 
 table! {
     stocktake_line (id) {
@@ -41,7 +42,9 @@ joinable!(stocktake_line -> stock_line (stock_line_id));
 joinable!(stocktake_line -> inventory_adjustment_reason (inventory_adjustment_reason_id));
 allow_tables_to_appear_in_same_query!(stocktake_line, item_link);
 
-#[derive(Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq, Default)]
+#[derive(
+    Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq, Default, Serialize, Deserialize,
+)]
 #[diesel(treat_none_as_null = true)]
 #[diesel(table_name = stocktake_line)]
 pub struct StocktakeLineRow {
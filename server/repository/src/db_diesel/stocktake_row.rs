@@ -8,6 +8,7 @@ use crate::Upsert;
 use chrono::{NaiveDate, NaiveDateTime};
 use diesel::{dsl::max, prelude::*};
 use diesel_derive_enum::DbEnum;
+use serde::{Deserialize, Serialize};
 use util::Defaults;
 
 table! {
@@ -30,14 +31,17 @@ table! {
 
 joinable!(stocktake -> user_account (user_id));
 
-#[derive(DbEnum, Debug, Clone, PartialEq, Eq)]
+#[derive(DbEnum, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
 pub enum StocktakeStatus {
     New,
     Finalised,
 }
 
-#[derive(Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq, Eq)]
+#[derive(
+    Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq, Eq, Serialize, Deserialize,
+)]
 #[diesel(table_name = stocktake)]
 pub struct StocktakeRow {
     pub id: String,
@@ -2,7 +2,7 @@ use std::sync::{Mutex, MutexGuard};
 
 use super::{get_connection, DBBackendConnection, DBConnection};
 
-use crate::repository_error::RepositoryError;
+use crate::{repository_error::RepositoryError, ChangelogRepository, ChangelogRow};
 
 use diesel::{
     connection::{AnsiTransactionManager, SimpleConnection, TransactionManager},
@@ -57,8 +57,19 @@ impl<'a> LockedConnection<'a> {
     }
 }
 
+/// Called with the changelogs of a transaction before it's committed, e.g. to capture the state
+/// of the changed records and the acting user for the audit log
+pub trait ChangelogHook: Send + Sync {
+    fn before_commit(
+        &self,
+        connection: &StorageConnection,
+        changelogs: Vec<ChangelogRow>,
+    ) -> Result<(), RepositoryError>;
+}
+
 pub struct StorageConnection {
     raw_connection: Mutex<DBConnection>,
+    changelog_hook: Option<Box<dyn ChangelogHook>>,
 }
 
 impl StorageConnection {
@@ -109,9 +120,16 @@ impl StorageConnection {
     pub fn new(connection: DBConnection) -> StorageConnection {
         StorageConnection {
             raw_connection: Mutex::new(connection),
+            changelog_hook: None,
         }
     }
 
+    /// Hook called before every top level transaction of the connection is committed, changes
+    /// made outside of a transaction are not passed to the hook
+    pub fn set_changelog_hook(&mut self, hook: Box<dyn ChangelogHook>) {
+        self.changelog_hook = Some(hook);
+    }
+
     /// Executes operations in transaction. A new transaction is only started if not already in a
     /// transaction.
     pub fn transaction_sync<'a, T, E, F>(&'a self, f: F) -> Result<T, TransactionError<E>>
@@ -155,7 +173,10 @@ impl StorageConnection {
             current_level
         };
 
-        let result = f(self);
+        let result = match (current_level, &self.changelog_hook) {
+            (0, Some(hook)) => self.run_with_changelog_hook(hook.as_ref(), f),
+            _ => f(self).map_err(TransactionError::Inner),
+        };
 
         match result {
             Ok(value) => {
@@ -180,9 +201,38 @@ impl StorageConnection {
                         level: current_level + 1,
                     }
                 })?;
-                Err(TransactionError::Inner(e))
+                Err(e)
+            }
+        }
+    }
+
+    fn run_with_changelog_hook<T, E, F>(
+        &self,
+        hook: &dyn ChangelogHook,
+        f: F,
+    ) -> Result<T, TransactionError<E>>
+    where
+        F: FnOnce(&StorageConnection) -> Result<T, E>,
+    {
+        let hook_error = |error: RepositoryError| {
+            error!("Changelog hook failed: {:?}", error);
+            TransactionError::Transaction {
+                msg: format!("Changelog hook failed: {:?}", error),
+                level: 1,
             }
+        };
+
+        let repository = ChangelogRepository::new(self);
+        let cursor = repository.latest_cursor().map_err(hook_error)?;
+        let value = f(self).map_err(TransactionError::Inner)?;
+
+        let changelogs = repository
+            .changelogs_in_transaction(cursor)
+            .map_err(hook_error)?;
+        if !changelogs.is_empty() {
+            hook.before_commit(self, changelogs).map_err(hook_error)?;
         }
+        Ok(value)
     }
 }
 
@@ -197,6 +247,15 @@ fn map_begin_transaction_error<T>(
     }
 }
 
+/// Connection pool usage, for monitoring
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionPoolState {
+    pub max_size: u32,
+    /// Open connections (idle and in use)
+    pub connections: u32,
+    pub idle_connections: u32,
+}
+
 #[derive(Clone)]
 pub struct StorageConnectionManager {
     pool: Pool<ConnectionManager<DBBackendConnection>>,
@@ -218,6 +277,15 @@ impl StorageConnectionManager {
         con.batch_execute(sql)?;
         Ok(())
     }
+
+    pub fn pool_state(&self) -> ConnectionPoolState {
+        let state = self.pool.state();
+        ConnectionPoolState {
+            max_size: self.pool.max_size(),
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+        }
+    }
 }
 
 #[cfg(test)]
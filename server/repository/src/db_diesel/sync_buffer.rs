@@ -1,8 +1,8 @@
 use super::StorageConnection;
 use crate::{
-    diesel_macros::{apply_date_time_filter, apply_equal_filter},
+    diesel_macros::{apply_date_time_filter, apply_equal_filter, apply_string_filter},
     repository_error::RepositoryError,
-    DBType, DatetimeFilter, EqualFilter,
+    DBType, DatetimeFilter, EqualFilter, Pagination, StringFilter,
 };
 use chrono::NaiveDateTime;
 use diesel::{dsl::IntoBoxed, prelude::*};
@@ -26,6 +26,8 @@ table! {
         action -> crate::SyncActionMapping,
         data -> Text,
         source_site_id -> Nullable<Integer>,
+        ignored_datetime -> Nullable<Timestamp>,
+        store_id -> Nullable<Text>,
     }
 }
 
@@ -43,6 +45,13 @@ pub struct SyncBufferRow {
     pub action: SyncAction,
     pub data: String,
     pub source_site_id: Option<i32>,
+    /// Set when a record that failed to integrate is ignored (integration is not retried)
+    #[serde(default)]
+    pub ignored_datetime: Option<NaiveDateTime>,
+    /// Store of remote record (for records of a store and their lines), used to integrate
+    /// records store by store
+    #[serde(default)]
+    pub store_id: Option<String>,
 }
 
 impl Default for SyncBufferRow {
@@ -56,6 +65,8 @@ impl Default for SyncBufferRow {
             action: SyncAction::Upsert,
             data: Default::default(),
             source_site_id: Default::default(),
+            ignored_datetime: Default::default(),
+            store_id: Default::default(),
         }
     }
 }
@@ -127,6 +138,9 @@ pub struct SyncBufferFilter {
     pub action: Option<EqualFilter<SyncAction>>,
     pub table_name: Option<EqualFilter<String>>,
     pub source_site_id: Option<EqualFilter<i32>>,
+    pub integration_error_message: Option<StringFilter>,
+    pub ignored_datetime: Option<DatetimeFilter>,
+    pub store_id: Option<EqualFilter<String>>,
 }
 
 impl SyncBufferFilter {
@@ -163,6 +177,21 @@ impl SyncBufferFilter {
         self.source_site_id = Some(filter);
         self
     }
+
+    pub fn integration_error_message(mut self, filter: StringFilter) -> Self {
+        self.integration_error_message = Some(filter);
+        self
+    }
+
+    pub fn ignored_datetime(mut self, filter: DatetimeFilter) -> Self {
+        self.ignored_datetime = Some(filter);
+        self
+    }
+
+    pub fn store_id(mut self, filter: EqualFilter<String>) -> Self {
+        self.store_id = Some(filter);
+        self
+    }
 }
 
 impl SyncAction {
@@ -199,6 +228,46 @@ impl<'a> SyncBufferRepository<'a> {
 
         Ok(result)
     }
+
+    pub fn count(&self, filter: Option<SyncBufferFilter>) -> Result<i64, RepositoryError> {
+        let query = create_filtered_query(filter);
+        Ok(query
+            .count()
+            .get_result(self.connection.lock().connection())?)
+    }
+
+    /// Most recently received records first
+    pub fn query_paginated(
+        &self,
+        pagination: Pagination,
+        filter: Option<SyncBufferFilter>,
+    ) -> Result<Vec<SyncBuffer>, RepositoryError> {
+        let result = create_filtered_query(filter)
+            .order((
+                sync_buffer_dsl::received_datetime.desc(),
+                sync_buffer_dsl::record_id.asc(),
+            ))
+            .offset(pagination.offset as i64)
+            .limit(pagination.limit as i64)
+            .load::<SyncBuffer>(self.connection.lock().connection())?;
+
+        Ok(result)
+    }
+
+    /// Distinct store ids of records matching the filter (records without store are skipped)
+    pub fn store_ids(
+        &self,
+        filter: Option<SyncBufferFilter>,
+    ) -> Result<Vec<String>, RepositoryError> {
+        let result = create_filtered_query(filter)
+            .filter(sync_buffer_dsl::store_id.is_not_null())
+            .select(sync_buffer_dsl::store_id)
+            .distinct()
+            .order(sync_buffer_dsl::store_id.asc())
+            .load::<Option<String>>(self.connection.lock().connection())?;
+
+        Ok(result.into_iter().flatten().collect())
+    }
 }
 
 type BoxedSyncBufferQuery = IntoBoxed<'static, sync_buffer::table, DBType>;
@@ -214,6 +283,9 @@ fn create_filtered_query(filter: Option<SyncBufferFilter>) -> BoxedSyncBufferQue
             table_name,
             record_id,
             source_site_id,
+            integration_error_message,
+            ignored_datetime,
+            store_id,
         } = f;
 
         apply_equal_filter!(query, record_id, sync_buffer_dsl::record_id);
@@ -226,6 +298,13 @@ fn create_filtered_query(filter: Option<SyncBufferFilter>) -> BoxedSyncBufferQue
         apply_equal_filter!(query, action, sync_buffer_dsl::action);
         apply_equal_filter!(query, table_name, sync_buffer_dsl::table_name);
         apply_equal_filter!(query, source_site_id, sync_buffer_dsl::source_site_id);
+        apply_string_filter!(
+            query,
+            integration_error_message,
+            sync_buffer_dsl::integration_error
+        );
+        apply_date_time_filter!(query, ignored_datetime, sync_buffer_dsl::ignored_datetime);
+        apply_equal_filter!(query, store_id, sync_buffer_dsl::store_id);
     }
 
     query
@@ -254,6 +333,7 @@ mod test {
             r.record_id = "store_b".to_string();
             r.integration_error = Some("error".to_string());
             r.action = SyncAction::Delete;
+            r.store_id = Some("store_1".to_string());
         })
     }
 
@@ -261,6 +341,7 @@ mod test {
         inline_init(|r: &mut SyncBufferRow| {
             r.record_id = "store_c".to_string();
             r.action = SyncAction::Upsert;
+            r.store_id = Some("store_1".to_string());
         })
     }
 
@@ -303,6 +384,22 @@ mod test {
                 .unwrap(),
             vec![row_b()]
         );
+
+        assert_eq!(
+            SyncBufferRepository::new(&connection)
+                .store_ids(None)
+                .unwrap(),
+            vec!["store_1".to_string()]
+        );
+        assert_eq!(
+            SyncBufferRepository::new(&connection)
+                .query_by_filter(
+                    SyncBufferFilter::new()
+                        .store_id(EqualFilter::equal_any_or_null(vec!["store_2".to_string()]))
+                )
+                .unwrap(),
+            vec![row_a()]
+        );
         // Test upsert overwrites integration_datetime
         let new_a = inline_edit(&row_a(), |mut r| {
             r.integration_datetime = None;
@@ -0,0 +1,143 @@
+use super::{
+    sync_conflict_row::{sync_conflict, sync_conflict::dsl as sync_conflict_dsl},
+    DBType, StorageConnection, SyncConflictRow,
+};
+use diesel::prelude::*;
+
+use crate::{
+    diesel_macros::{apply_date_time_filter, apply_equal_filter, apply_sort},
+    repository_error::RepositoryError,
+    DatetimeFilter, EqualFilter, Pagination, Sort,
+};
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct SyncConflictFilter {
+    pub id: Option<EqualFilter<String>>,
+    pub table_name: Option<EqualFilter<String>>,
+    pub record_id: Option<EqualFilter<String>>,
+    pub detected_datetime: Option<DatetimeFilter>,
+    pub resolved_datetime: Option<DatetimeFilter>,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum SyncConflictSortField {
+    DetectedDatetime,
+    TableName,
+}
+
+pub type SyncConflictSort = Sort<SyncConflictSortField>;
+
+pub struct SyncConflictRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> SyncConflictRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        SyncConflictRepository { connection }
+    }
+
+    pub fn count(&self, filter: Option<SyncConflictFilter>) -> Result<i64, RepositoryError> {
+        let query = create_filtered_query(filter);
+        Ok(query
+            .count()
+            .get_result(self.connection.lock().connection())?)
+    }
+
+    pub fn query_by_filter(
+        &self,
+        filter: SyncConflictFilter,
+    ) -> Result<Vec<SyncConflictRow>, RepositoryError> {
+        self.query(Pagination::all(), Some(filter), None)
+    }
+
+    pub fn query(
+        &self,
+        pagination: Pagination,
+        filter: Option<SyncConflictFilter>,
+        sort: Option<SyncConflictSort>,
+    ) -> Result<Vec<SyncConflictRow>, RepositoryError> {
+        let mut query = create_filtered_query(filter);
+        if let Some(sort) = sort {
+            match sort.key {
+                SyncConflictSortField::DetectedDatetime => {
+                    apply_sort!(query, sort, sync_conflict_dsl::detected_datetime)
+                }
+                SyncConflictSortField::TableName => {
+                    apply_sort!(query, sort, sync_conflict_dsl::table_name)
+                }
+            }
+        } else {
+            query = query.order(sync_conflict_dsl::detected_datetime.desc())
+        }
+
+        let result = query
+            .offset(pagination.offset as i64)
+            .limit(pagination.limit as i64)
+            .load::<SyncConflictRow>(self.connection.lock().connection())?;
+
+        Ok(result)
+    }
+}
+
+type BoxedSyncConflictQuery = sync_conflict::BoxedQuery<'static, DBType>;
+
+fn create_filtered_query(filter: Option<SyncConflictFilter>) -> BoxedSyncConflictQuery {
+    let mut query = sync_conflict::table.into_boxed();
+
+    if let Some(filter) = filter {
+        let SyncConflictFilter {
+            id,
+            table_name,
+            record_id,
+            detected_datetime,
+            resolved_datetime,
+        } = filter;
+
+        apply_equal_filter!(query, id, sync_conflict_dsl::id);
+        apply_equal_filter!(query, table_name, sync_conflict_dsl::table_name);
+        apply_equal_filter!(query, record_id, sync_conflict_dsl::record_id);
+        apply_date_time_filter!(
+            query,
+            detected_datetime,
+            sync_conflict_dsl::detected_datetime
+        );
+        apply_date_time_filter!(
+            query,
+            resolved_datetime,
+            sync_conflict_dsl::resolved_datetime
+        );
+    }
+
+    query
+}
+
+impl SyncConflictFilter {
+    pub fn new() -> SyncConflictFilter {
+        Self::default()
+    }
+
+    pub fn id(mut self, filter: EqualFilter<String>) -> Self {
+        self.id = Some(filter);
+        self
+    }
+
+    pub fn table_name(mut self, filter: EqualFilter<String>) -> Self {
+        self.table_name = Some(filter);
+        self
+    }
+
+    pub fn record_id(mut self, filter: EqualFilter<String>) -> Self {
+        self.record_id = Some(filter);
+        self
+    }
+
+    pub fn detected_datetime(mut self, filter: DatetimeFilter) -> Self {
+        self.detected_datetime = Some(filter);
+        self
+    }
+
+    pub fn resolved_datetime(mut self, filter: DatetimeFilter) -> Self {
+        self.resolved_datetime = Some(filter);
+        self
+    }
+}
@@ -0,0 +1,102 @@
+use super::{sync_conflict_row::sync_conflict::dsl as sync_conflict_dsl, StorageConnection};
+
+use crate::{repository_error::RepositoryError, SyncAction};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel_derive_enum::DbEnum;
+
+table! {
+    sync_conflict (id) {
+        id -> Text,
+        table_name -> Text,
+        record_id -> Text,
+        action -> crate::SyncActionMapping,
+        remote_data -> Text,
+        local_changelog_cursor -> BigInt,
+        detected_datetime -> Timestamp,
+        resolved_datetime -> Nullable<Timestamp>,
+        resolution -> Nullable<crate::db_diesel::sync_conflict_row::SyncConflictResolutionMapping>,
+    }
+}
+
+#[derive(DbEnum, Debug, Clone, PartialEq, Eq)]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum SyncConflictResolution {
+    /// Local change is kept (and pushed to central server with the next sync)
+    KeepLocal,
+    /// Central record is integrated, overwriting the local change
+    AcceptRemote,
+}
+
+/// Central server record that was not integrated because the local record has changes that
+/// were not synced yet
+#[derive(Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq)]
+#[diesel(treat_none_as_null = true)]
+#[diesel(table_name = sync_conflict)]
+pub struct SyncConflictRow {
+    pub id: String,
+    /// Legacy (mSupply) table name
+    pub table_name: String,
+    pub record_id: String,
+    pub action: SyncAction,
+    /// Record as received from central server
+    pub remote_data: String,
+    /// Changelog of the local change that conflicts with the central record
+    pub local_changelog_cursor: i64,
+    pub detected_datetime: NaiveDateTime,
+    pub resolved_datetime: Option<NaiveDateTime>,
+    pub resolution: Option<SyncConflictResolution>,
+}
+
+pub struct SyncConflictRowRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> SyncConflictRowRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        SyncConflictRowRepository { connection }
+    }
+
+    #[cfg(feature = "postgres")]
+    pub fn upsert_one(&self, row: &SyncConflictRow) -> Result<(), RepositoryError> {
+        diesel::insert_into(sync_conflict_dsl::sync_conflict)
+            .values(row)
+            .on_conflict(sync_conflict_dsl::id)
+            .do_update()
+            .set(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    pub fn upsert_one(&self, row: &SyncConflictRow) -> Result<(), RepositoryError> {
+        diesel::replace_into(sync_conflict_dsl::sync_conflict)
+            .values(row)
+            .execute(self.connection.lock().connection())?;
+        Ok(())
+    }
+
+    pub fn find_one_by_id(&self, id: &str) -> Result<Option<SyncConflictRow>, RepositoryError> {
+        let result = sync_conflict_dsl::sync_conflict
+            .filter(sync_conflict_dsl::id.eq(id))
+            .first(self.connection.lock().connection())
+            .optional()?;
+        Ok(result)
+    }
+
+    /// Conflict of the record that is waiting for resolution
+    pub fn find_unresolved_for_record(
+        &self,
+        table_name: &str,
+        record_id: &str,
+    ) -> Result<Option<SyncConflictRow>, RepositoryError> {
+        let result = sync_conflict_dsl::sync_conflict
+            .filter(sync_conflict_dsl::table_name.eq(table_name))
+            .filter(sync_conflict_dsl::record_id.eq(record_id))
+            .filter(sync_conflict_dsl::resolved_datetime.is_null())
+            .first(self.connection.lock().connection())
+            .optional()?;
+        Ok(result)
+    }
+}
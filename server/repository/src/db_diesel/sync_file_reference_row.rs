@@ -163,8 +163,8 @@ impl<'a> SyncFileReferenceRowRepository<'a> {
     }
 
     pub fn find_all_to_upload(&self) -> Result<Vec<SyncFileReferenceRow>, RepositoryError> {
-        // NOTE: InProgress status here as the behaviour is a bit undefined. We should either upload a whole file or get an error.
-        // It's included here in case the server is restarted with an Inprogress file, it will be re-tried.
+        // NOTE: InProgress is included in case the server is restarted while uploading a file,
+        // the upload is resumed from `uploaded_bytes`.
         let result = sync_file_reference
             .filter(deleted_datetime.is_null())
             .filter(direction.eq(SyncFileDirection::Upload))
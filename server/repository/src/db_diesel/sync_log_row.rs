@@ -56,6 +56,11 @@ table! {
         integration_progress_done -> Nullable<Integer>,
         error_message -> Nullable<Text>,
         error_code -> Nullable<crate::db_diesel::sync_log_row::SyncApiErrorCodeMapping>,
+        push_transferred_bytes -> Nullable<BigInt>,
+        pull_central_transferred_bytes -> Nullable<BigInt>,
+        pull_remote_transferred_bytes -> Nullable<BigInt>,
+        pull_v6_transferred_bytes -> Nullable<BigInt>,
+        push_v6_transferred_bytes -> Nullable<BigInt>,
     }
 }
 
@@ -94,6 +99,12 @@ pub struct SyncLogRow {
     pub integration_progress_done: Option<i32>,
     pub error_message: Option<String>,
     pub error_code: Option<SyncApiErrorCode>,
+    // Request and response body bytes sent over the network (i.e. compressed) during the step
+    pub push_transferred_bytes: Option<i64>,
+    pub pull_central_transferred_bytes: Option<i64>,
+    pub pull_remote_transferred_bytes: Option<i64>,
+    pub pull_v6_transferred_bytes: Option<i64>,
+    pub push_v6_transferred_bytes: Option<i64>,
 }
 
 impl Default for SyncLogRow {
@@ -130,6 +141,11 @@ impl Default for SyncLogRow {
             push_v6_finished_datetime: Default::default(),
             push_v6_progress_total: Default::default(),
             push_v6_progress_done: Default::default(),
+            push_transferred_bytes: Default::default(),
+            pull_central_transferred_bytes: Default::default(),
+            pull_remote_transferred_bytes: Default::default(),
+            pull_v6_transferred_bytes: Default::default(),
+            push_v6_transferred_bytes: Default::default(),
         }
     }
 }
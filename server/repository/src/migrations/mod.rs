@@ -19,6 +19,7 @@ mod v1_06_00;
 mod v1_07_00;
 mod v2_00_00;
 mod v2_01_00;
+mod v2_02_00;
 mod version;
 
 pub(crate) use self::types::*;
@@ -96,6 +97,7 @@ pub fn migrate(
         Box::new(v1_07_00::V1_07_00),
         Box::new(v2_00_00::V2_00_00),
         Box::new(v2_01_00::V2_01_00),
+        Box::new(v2_02_00::V2_02_00),
     ];
 
     // Historic diesel migrations
@@ -0,0 +1,53 @@
+use crate::migrations::*;
+
+pub(crate) fn migrate(connection: &StorageConnection) -> anyhow::Result<()> {
+    let (changelog_table_name, row_action_type) = if cfg!(feature = "postgres") {
+        ("changelog_table_name", "row_action_type")
+    } else {
+        ("TEXT", "TEXT")
+    };
+
+    sql!(
+        connection,
+        r#"
+        CREATE TABLE audit_log (
+            id TEXT NOT NULL PRIMARY KEY,
+            changelog_cursor BIGINT NOT NULL UNIQUE,
+            table_name {changelog_table_name} NOT NULL,
+            record_id TEXT NOT NULL,
+            row_action {row_action_type} NOT NULL,
+            store_id TEXT,
+            user_id TEXT,
+            datetime {DATETIME} NOT NULL,
+            changed_fields {JSON},
+            record_snapshot {JSON},
+            previous_hash TEXT NOT NULL,
+            hash TEXT NOT NULL
+        );
+        CREATE INDEX index_audit_log_table_name_record_id ON audit_log (table_name, record_id);
+        CREATE TABLE audit_log_capture (
+            changelog_cursor BIGINT NOT NULL PRIMARY KEY,
+            user_id TEXT,
+            datetime {DATETIME} NOT NULL,
+            record_snapshot {JSON}
+        );
+        "#
+    )?;
+
+    if cfg!(feature = "postgres") {
+        sql!(
+            connection,
+            r#"
+            ALTER TYPE key_type ADD VALUE IF NOT EXISTS 'AUDIT_LOG_PROCESSOR_CURSOR';
+            ALTER TYPE key_type ADD VALUE IF NOT EXISTS 'AUDIT_LOG_HEAD';
+            -- Top level transaction of the change (also in savepoints), to find the changes of
+            -- a transaction before it's committed. Default is set separately so that existing
+            -- rows are not rewritten
+            ALTER TABLE changelog ADD COLUMN transaction_id BIGINT;
+            ALTER TABLE changelog ALTER COLUMN transaction_id SET DEFAULT txid_current();
+            "#
+        )?;
+    }
+
+    Ok(())
+}
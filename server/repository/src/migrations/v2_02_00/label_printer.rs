@@ -0,0 +1,71 @@
+use crate::migrations::*;
+
+pub(crate) fn migrate(connection: &StorageConnection) -> anyhow::Result<()> {
+    #[cfg(feature = "postgres")]
+    sql!(
+        connection,
+        r#"
+        CREATE TYPE label_printer_status AS ENUM (
+            'UNKNOWN',
+            'READY',
+            'ERROR',
+            'UNREACHABLE'
+        );
+        CREATE TYPE print_job_status AS ENUM (
+            'PENDING',
+            'PRINTED',
+            'FAILED',
+            'CANCELLED'
+        );
+        "#,
+    )?;
+    let (label_template_type, label_printer_status, print_job_status) =
+        if cfg!(feature = "postgres") {
+            (
+                "label_template_type",
+                "label_printer_status",
+                "print_job_status",
+            )
+        } else {
+            ("TEXT", "TEXT", "TEXT")
+        };
+
+    sql!(
+        connection,
+        r#"
+        CREATE TABLE label_printer (
+            id TEXT NOT NULL PRIMARY KEY,
+            name TEXT NOT NULL,
+            address TEXT NOT NULL,
+            port INTEGER NOT NULL,
+            label_width INTEGER NOT NULL,
+            label_height INTEGER NOT NULL,
+            store_id TEXT REFERENCES store(id),
+            purpose {label_template_type},
+            is_active BOOLEAN NOT NULL,
+            status {label_printer_status} NOT NULL,
+            status_message TEXT,
+            status_datetime {DATETIME}
+        );
+
+        CREATE TABLE print_job (
+            id TEXT NOT NULL PRIMARY KEY,
+            label_printer_id TEXT,
+            store_id TEXT,
+            user_id TEXT,
+            payload TEXT NOT NULL,
+            label_count INTEGER NOT NULL,
+            status {print_job_status} NOT NULL,
+            attempts INTEGER NOT NULL,
+            error TEXT,
+            created_datetime {DATETIME} NOT NULL,
+            next_attempt_datetime {DATETIME} NOT NULL,
+            printed_datetime {DATETIME}
+        );
+        CREATE INDEX index_print_job_status ON print_job (status);
+        CREATE INDEX index_print_job_store_id ON print_job (store_id);
+        "#
+    )?;
+
+    Ok(())
+}
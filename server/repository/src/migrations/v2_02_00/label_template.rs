@@ -0,0 +1,34 @@
+use crate::migrations::*;
+
+pub(crate) fn migrate(connection: &StorageConnection) -> anyhow::Result<()> {
+    #[cfg(feature = "postgres")]
+    sql!(
+        connection,
+        r#"
+        CREATE TYPE label_template_type AS ENUM (
+            'STOCK_LINE',
+            'LOCATION',
+            'SHIPMENT'
+        );
+        "#,
+    )?;
+    let label_template_type = if cfg!(feature = "postgres") {
+        "label_template_type"
+    } else {
+        "TEXT"
+    };
+
+    sql!(
+        connection,
+        r#"
+        CREATE TABLE label_template (
+            id TEXT NOT NULL PRIMARY KEY,
+            name TEXT NOT NULL,
+            type {label_template_type} NOT NULL,
+            template TEXT NOT NULL
+        );
+        "#
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use super::{version::Version, Migration};
+
+use crate::StorageConnection;
+
+mod audit_log;
+mod label_printer;
+mod label_template;
+mod peer_sync;
+mod report_schedule;
+mod report_version;
+mod sync_buffer_ignored;
+mod sync_buffer_store;
+mod sync_conflict;
+mod sync_log_transferred_bytes;
+mod sync_package;
+mod sync_transactional_v6;
+
+pub(crate) struct V2_02_00;
+
+impl Migration for V2_02_00 {
+    fn version(&self) -> Version {
+        Version::from_str("2.2.0")
+    }
+
+    fn migrate(&self, connection: &StorageConnection) -> anyhow::Result<()> {
+        audit_log::migrate(connection)?;
+        sync_package::migrate(connection)?;
+        sync_buffer_ignored::migrate(connection)?;
+        sync_log_transferred_bytes::migrate(connection)?;
+        sync_buffer_store::migrate(connection)?;
+        sync_conflict::migrate(connection)?;
+        sync_transactional_v6::migrate(connection)?;
+        peer_sync::migrate(connection)?;
+        report_schedule::migrate(connection)?;
+        report_version::migrate(connection)?;
+        label_template::migrate(connection)?;
+        label_printer::migrate(connection)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[actix_rt::test]
+async fn migration_2_02_00() {
+    use crate::migrations::*;
+    use crate::test_db::*;
+
+    let version = V2_02_00.version();
+
+    // This test allows checking sql syntax
+    let SetupResult { connection, .. } = setup_test(SetupOption {
+        db_name: &format!("migration_{version}"),
+        version: Some(version.clone()),
+        ..Default::default()
+    })
+    .await;
+
+    assert_eq!(get_database_version(&connection), version);
+}
@@ -0,0 +1,59 @@
+use crate::migrations::*;
+
+pub(crate) fn migrate(connection: &StorageConnection) -> anyhow::Result<()> {
+    #[cfg(feature = "postgres")]
+    sql!(
+        connection,
+        r#"
+        CREATE TYPE report_schedule_frequency AS ENUM (
+            'DAILY',
+            'WEEKLY',
+            'MONTHLY'
+        );
+        CREATE TYPE report_schedule_format AS ENUM (
+            'PDF',
+            'HTML',
+            'CSV',
+            'XLSX'
+        );
+        "#,
+    )?;
+    let (frequency, format) = if cfg!(feature = "postgres") {
+        ("report_schedule_frequency", "report_schedule_format")
+    } else {
+        ("TEXT", "TEXT")
+    };
+
+    sql!(
+        connection,
+        r#"
+        CREATE TABLE report_schedule (
+            id TEXT NOT NULL PRIMARY KEY,
+            store_id TEXT NOT NULL REFERENCES store(id),
+            report_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            arguments TEXT,
+            format {format},
+            frequency {frequency} NOT NULL,
+            next_run_datetime {DATETIME} NOT NULL,
+            retention_days INTEGER NOT NULL,
+            email_recipients TEXT,
+            is_active BOOLEAN NOT NULL
+        );
+        CREATE INDEX index_report_schedule_store_id ON report_schedule (store_id);
+
+        CREATE TABLE report_schedule_run (
+            id TEXT NOT NULL PRIMARY KEY,
+            report_schedule_id TEXT NOT NULL REFERENCES report_schedule(id),
+            run_datetime {DATETIME} NOT NULL,
+            file_name TEXT,
+            error TEXT,
+            emailed_datetime {DATETIME}
+        );
+        CREATE INDEX index_report_schedule_run_report_schedule_id ON report_schedule_run (report_schedule_id);
+        "#
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use crate::migrations::*;
+
+pub(crate) fn migrate(connection: &StorageConnection) -> anyhow::Result<()> {
+    sql!(
+        connection,
+        r#"
+            ALTER TABLE report ADD COLUMN version TEXT;
+        "#
+    )?;
+
+    Ok(())
+}
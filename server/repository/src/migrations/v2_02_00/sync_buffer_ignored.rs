@@ -0,0 +1,12 @@
+use crate::migrations::*;
+
+pub(crate) fn migrate(connection: &StorageConnection) -> anyhow::Result<()> {
+    sql!(
+        connection,
+        r#"
+            ALTER TABLE sync_buffer ADD COLUMN ignored_datetime {DATETIME};
+        "#
+    )?;
+
+    Ok(())
+}
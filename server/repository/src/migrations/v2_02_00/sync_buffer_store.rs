@@ -0,0 +1,23 @@
+use crate::migrations::*;
+
+pub(crate) fn migrate(connection: &StorageConnection) -> anyhow::Result<()> {
+    sql!(
+        connection,
+        r#"
+            ALTER TABLE sync_buffer ADD COLUMN store_id TEXT;
+            CREATE INDEX sync_buffer_store_id ON sync_buffer (store_id);
+        "#
+    )?;
+
+    if cfg!(feature = "postgres") {
+        sql!(
+            connection,
+            r#"
+            ALTER TYPE key_type ADD VALUE IF NOT EXISTS 'SETTINGS_SYNC_PRIORITY_STORE_IDS';
+            ALTER TYPE key_type ADD VALUE IF NOT EXISTS 'SYNC_BACKFILL_STORE_IDS';
+            "#
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use crate::migrations::*;
+
+pub(crate) fn migrate(connection: &StorageConnection) -> anyhow::Result<()> {
+    #[cfg(feature = "postgres")]
+    sql!(
+        connection,
+        r#"
+        CREATE TYPE sync_conflict_resolution AS ENUM (
+            'KEEP_LOCAL',
+            'ACCEPT_REMOTE'
+        );
+        ALTER TYPE key_type ADD VALUE IF NOT EXISTS 'SYNC_CONFLICT_PUSH_CURSOR';
+        "#,
+    )?;
+    let (sync_action, sync_conflict_resolution) = if cfg!(feature = "postgres") {
+        ("sync_action", "sync_conflict_resolution")
+    } else {
+        ("TEXT", "TEXT")
+    };
+
+    sql!(
+        connection,
+        r#"
+        CREATE TABLE sync_conflict (
+            id TEXT NOT NULL PRIMARY KEY,
+            table_name TEXT NOT NULL, -- Legacy (mSupply) table name
+            record_id TEXT NOT NULL,
+            action {sync_action} NOT NULL,
+            remote_data TEXT NOT NULL,
+            local_changelog_cursor BIGINT NOT NULL,
+            detected_datetime {DATETIME} NOT NULL,
+            resolved_datetime {DATETIME},
+            resolution {sync_conflict_resolution}
+        );
+        CREATE INDEX index_sync_conflict_table_name_record_id ON sync_conflict (table_name, record_id);
+        "#
+    )?;
+
+    Ok(())
+}
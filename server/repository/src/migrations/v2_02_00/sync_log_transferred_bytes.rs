@@ -0,0 +1,16 @@
+use crate::migrations::*;
+
+pub(crate) fn migrate(connection: &StorageConnection) -> anyhow::Result<()> {
+    sql!(
+        connection,
+        r#"
+            ALTER TABLE sync_log ADD COLUMN push_transferred_bytes BIGINT;
+            ALTER TABLE sync_log ADD COLUMN pull_central_transferred_bytes BIGINT;
+            ALTER TABLE sync_log ADD COLUMN pull_remote_transferred_bytes BIGINT;
+            ALTER TABLE sync_log ADD COLUMN pull_v6_transferred_bytes BIGINT;
+            ALTER TABLE sync_log ADD COLUMN push_v6_transferred_bytes BIGINT;
+        "#
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use crate::migrations::*;
+
+pub(crate) fn migrate(connection: &StorageConnection) -> anyhow::Result<()> {
+    if cfg!(feature = "postgres") {
+        sql!(
+            connection,
+            r#"
+            ALTER TYPE key_type ADD VALUE IF NOT EXISTS 'SYNC_PACKAGE_CENTRAL_SEQUENCE';
+            ALTER TYPE key_type ADD VALUE IF NOT EXISTS 'SYNC_PACKAGE_ACKNOWLEDGED_SYNC_IDS';
+            "#
+        )?;
+    }
+
+    Ok(())
+}
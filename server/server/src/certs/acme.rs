@@ -0,0 +1,278 @@
+use std::{
+    collections::HashMap,
+    io::BufReader,
+    path::Path,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use actix_web::{
+    dev::ServerHandle,
+    web::{self, Data},
+    App, HttpResponse, HttpServer,
+};
+use anyhow::{anyhow, bail, Context};
+use instant_acme::{
+    Account, AccountCredentials, AuthorizationStatus, ChallengeType, HttpClient, Identifier,
+    NewAccount, NewOrder, Order, OrderStatus,
+};
+use log::info;
+use rcgen::{Certificate, CertificateParams, DistinguishedName};
+use rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore};
+use service::settings::AcmeSettings;
+
+use super::{save_cert_files, write_private_file, CertFiles};
+
+const ACCOUNT_FILE: &str = "acme_account.json";
+const MAX_POLL_ATTEMPTS: u32 = 10;
+const MAX_POLL_DELAY: Duration = Duration::from_secs(8);
+
+/// Key authorisations by token, served to the ACME server during http-01 validation
+#[derive(Clone, Default)]
+struct Challenges(Arc<RwLock<HashMap<String, String>>>);
+
+/// Orders a new certificate for `AcmeSettings.domains`
+pub(super) async fn order_certificate(
+    settings: &AcmeSettings,
+    cert_dir: &Path,
+) -> Result<CertFiles, anyhow::Error> {
+    info!("Ordering certificate for {:?}", settings.domains);
+    let account = account(settings, cert_dir).await?;
+
+    let identifiers: Vec<Identifier> = settings
+        .domains
+        .iter()
+        .map(|domain| Identifier::Dns(domain.clone()))
+        .collect();
+    let mut order = account
+        .new_order(&NewOrder {
+            identifiers: &identifiers,
+        })
+        .await?;
+
+    let challenges = Challenges::default();
+    let challenge_server = start_challenge_server(settings.challenge_port, challenges.clone())?;
+    let result = complete_order(&mut order, &settings.domains, &challenges).await;
+    challenge_server.stop(true).await;
+
+    let (private_key_pem, cert_chain_pem) = result?;
+    info!("Ordering certificate for {:?}..done", settings.domains);
+    save_cert_files(cert_dir, &private_key_pem, &cert_chain_pem)
+}
+
+/// Returns private key and certificate chain pem
+async fn complete_order(
+    order: &mut Order,
+    domains: &[String],
+    challenges: &Challenges,
+) -> Result<(String, String), anyhow::Error> {
+    let authorizations = order.authorizations().await?;
+    let mut challenge_urls = Vec::new();
+    for authorization in &authorizations {
+        match authorization.status {
+            AuthorizationStatus::Pending => {}
+            AuthorizationStatus::Valid => continue,
+            _ => bail!(
+                "Unexpected ACME authorization status {:?} for {:?}",
+                authorization.status,
+                authorization.identifier
+            ),
+        }
+
+        let challenge = authorization
+            .challenges
+            .iter()
+            .find(|challenge| challenge.r#type == ChallengeType::Http01)
+            .ok_or_else(|| {
+                anyhow!(
+                    "ACME server did not offer http-01 challenge for {:?}",
+                    authorization.identifier
+                )
+            })?;
+
+        challenges.0.write().unwrap().insert(
+            challenge.token.clone(),
+            order.key_authorization(challenge).as_str().to_string(),
+        );
+        challenge_urls.push(challenge.url.clone());
+    }
+
+    for url in &challenge_urls {
+        order.set_challenge_ready(url).await?;
+    }
+
+    let mut delay = Duration::from_millis(500);
+    let mut attempts = 0;
+    loop {
+        tokio::time::sleep(delay).await;
+        let state = order.refresh().await?;
+        match state.status {
+            OrderStatus::Ready => break,
+            OrderStatus::Invalid => bail!("ACME order is invalid {:?}", state.error),
+            _ => {}
+        }
+
+        attempts += 1;
+        if attempts >= MAX_POLL_ATTEMPTS {
+            bail!("Timed out waiting for ACME order to be ready");
+        }
+        delay = (delay * 2).min(MAX_POLL_DELAY);
+    }
+
+    let mut params = CertificateParams::new(domains.to_vec());
+    params.distinguished_name = DistinguishedName::new();
+    let cert = Certificate::from_params(params)?;
+    order.finalize(&cert.serialize_request_der()?).await?;
+
+    let mut attempts = 0;
+    let cert_chain_pem = loop {
+        if let Some(cert_chain_pem) = order.certificate().await? {
+            break cert_chain_pem;
+        }
+
+        attempts += 1;
+        if attempts >= MAX_POLL_ATTEMPTS {
+            bail!("Timed out waiting for ACME certificate");
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    };
+
+    Ok((cert.serialize_private_key_pem(), cert_chain_pem))
+}
+
+/// Existing account is re-used from `ACCOUNT_FILE`, or a new account is created
+async fn account(settings: &AcmeSettings, cert_dir: &Path) -> Result<Account, anyhow::Error> {
+    let account_file = cert_dir.join(ACCOUNT_FILE);
+    if let Ok(credentials) = std::fs::read_to_string(&account_file) {
+        let credentials: AccountCredentials =
+            serde_json::from_str(&credentials).context("Invalid ACME account file")?;
+        return Ok(Account::from_credentials_and_http(credentials, http_client(settings)?).await?);
+    }
+
+    let contact: Vec<String> = settings
+        .contact_email
+        .iter()
+        .map(|email| format!("mailto:{}", email))
+        .collect();
+    let contact: Vec<&str> = contact.iter().map(String::as_str).collect();
+    let (account, credentials) = Account::create_with_http(
+        &NewAccount {
+            contact: &contact,
+            terms_of_service_agreed: true,
+            only_return_existing: false,
+        },
+        &settings.directory_url,
+        None,
+        http_client(settings)?,
+    )
+    .await?;
+
+    std::fs::create_dir_all(cert_dir)?;
+    // Credentials include the account private key
+    write_private_file(&account_file, &serde_json::to_string(&credentials)?)?;
+    Ok(account)
+}
+
+/// Trusts public roots and `AcmeSettings.directory_root_cert_file` (for test ACME servers)
+fn http_client(settings: &AcmeSettings) -> Result<Box<dyn HttpClient>, anyhow::Error> {
+    let mut roots = RootCertStore::empty();
+    roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
+        )
+    }));
+
+    if let Some(root_cert_file) = &settings.directory_root_cert_file {
+        let file = std::fs::File::open(root_cert_file)
+            .with_context(|| format!("Cannot open ACME root certificate {}", root_cert_file))?;
+        for cert in rustls_pemfile::certs(&mut BufReader::new(file))? {
+            roots.add(&rustls::Certificate(cert))?;
+        }
+    }
+
+    let tls_config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_tls_config(tls_config)
+        .https_or_http()
+        .enable_http1()
+        .build();
+
+    Ok(Box::new(hyper::Client::builder().build(connector)))
+}
+
+/// Plain http server for http-01 validation, only running while an order is in progress
+fn start_challenge_server(
+    port: u16,
+    challenges: Challenges,
+) -> Result<ServerHandle, anyhow::Error> {
+    let server = HttpServer::new(move || {
+        App::new().app_data(Data::new(challenges.clone())).route(
+            "/.well-known/acme-challenge/{token}",
+            web::get().to(challenge),
+        )
+    })
+    .workers(1)
+    .disable_signals()
+    .bind(("0.0.0.0", port))
+    .with_context(|| format!("Cannot start ACME challenge server on port {}", port))?
+    .run();
+
+    let handle = server.handle();
+    actix_web::rt::spawn(server);
+    Ok(handle)
+}
+
+async fn challenge(token: web::Path<String>, challenges: Data<Challenges>) -> HttpResponse {
+    match challenges.0.read().unwrap().get(token.as_str()) {
+        Some(key_authorization) => HttpResponse::Ok().body(key_authorization.clone()),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+// Runs against a local Pebble ACME server, ignored by default:
+// docker run --rm -p 14000:14000 -e PEBBLE_VA_ALWAYS_VALID=1 ghcr.io/letsencrypt/pebble
+// curl -o /tmp/pebble.minica.pem \
+//   https://raw.githubusercontent.com/letsencrypt/pebble/main/test/certs/pebble.minica.pem
+// PEBBLE_ROOT_CERT=/tmp/pebble.minica.pem cargo test -p server acme_pebble -- --ignored
+// `PEBBLE_DIRECTORY_URL` overrides the default https://localhost:14000/dir
+#[cfg(test)]
+mod test {
+    use service::settings::AcmeSettings;
+
+    use crate::certs::{certificate_expires_within, load_certified_key};
+
+    use super::order_certificate;
+
+    #[actix_rt::test]
+    #[ignore]
+    async fn acme_pebble() {
+        let cert_dir = std::env::temp_dir().join("acme_pebble");
+        let _ = std::fs::remove_dir_all(&cert_dir);
+
+        let settings = AcmeSettings {
+            directory_url: std::env::var("PEBBLE_DIRECTORY_URL")
+                .unwrap_or("https://localhost:14000/dir".to_string()),
+            domains: vec!["omsupply.test".to_string()],
+            contact_email: None,
+            // Pebble default http-01 port
+            challenge_port: 5002,
+            directory_root_cert_file: Some(
+                std::env::var("PEBBLE_ROOT_CERT").expect("PEBBLE_ROOT_CERT is not set"),
+            ),
+            renew_before_days: 30,
+        };
+
+        let cert_files = order_certificate(&settings, &cert_dir).await.unwrap();
+        load_certified_key(&cert_files).unwrap();
+        assert!(!certificate_expires_within(&cert_files, 1).unwrap());
+
+        // Second order re-uses the stored account
+        assert!(cert_dir.join(super::ACCOUNT_FILE).exists());
+        order_certificate(&settings, &cert_dir).await.unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::{Datelike, Duration, Utc};
+use rcgen::{
+    date_time_ymd, Certificate, CertificateParams, DistinguishedName, DnType, KeyPair, SanType,
+};
+use service::settings::LocalCaSettings;
+
+use super::{save_cert_files, write_private_file, CertFiles};
+
+const CA_PATH: &str = "ca";
+const CA_CERT_FILE: &str = "ca_cert.pem";
+const CA_KEY_FILE: &str = "ca_key.pem";
+
+fn ca_files(cert_dir: &Path) -> (PathBuf, PathBuf) {
+    let ca_dir = cert_dir.join(CA_PATH);
+    (ca_dir.join(CA_CERT_FILE), ca_dir.join(CA_KEY_FILE))
+}
+
+fn load_ca(ca_cert_pem: &str, ca_key_pem: &str) -> Result<Certificate, anyhow::Error> {
+    let key_pair = KeyPair::from_pem(ca_key_pem).context("Invalid CA private key")?;
+    let params = CertificateParams::from_ca_cert_pem(ca_cert_pem, key_pair)
+        .context("Invalid CA certificate")?;
+    Ok(Certificate::from_params(params)?)
+}
+
+/// Validates and stores uploaded CA certificate and private key, replacing existing CA
+pub(super) fn save_ca(
+    cert_dir: &Path,
+    ca_cert_pem: &str,
+    ca_key_pem: &str,
+) -> Result<(), anyhow::Error> {
+    load_ca(ca_cert_pem, ca_key_pem)?;
+
+    let (ca_cert_file, ca_key_file) = ca_files(cert_dir);
+    std::fs::create_dir_all(cert_dir.join(CA_PATH))?;
+    write_private_file(&ca_key_file, ca_key_pem)?;
+    std::fs::write(ca_cert_file, ca_cert_pem)?;
+    Ok(())
+}
+
+pub(super) fn has_ca(cert_dir: &Path) -> bool {
+    let (ca_cert_file, ca_key_file) = ca_files(cert_dir);
+    ca_cert_file.exists() && ca_key_file.exists()
+}
+
+/// Issues server certificate signed by the stored CA
+pub(super) fn issue_certificate(
+    settings: &LocalCaSettings,
+    cert_dir: &Path,
+) -> Result<CertFiles, anyhow::Error> {
+    let (ca_cert_file, ca_key_file) = ca_files(cert_dir);
+    let ca_cert_pem = std::fs::read_to_string(&ca_cert_file)
+        .with_context(|| format!("Cannot read CA certificate {}", ca_cert_file.display()))?;
+    let ca_key_pem = std::fs::read_to_string(&ca_key_file)
+        .with_context(|| format!("Cannot read CA private key {}", ca_key_file.display()))?;
+    let ca = load_ca(&ca_cert_pem, &ca_key_pem)?;
+
+    let mut params = CertificateParams::default();
+    params.subject_alt_names = settings
+        .subject_alt_names
+        .iter()
+        .map(|name| match name.parse::<IpAddr>() {
+            Ok(ip) => SanType::IpAddress(ip),
+            Err(_) => SanType::DnsName(name.clone()),
+        })
+        .collect();
+    let mut distinguished_name = DistinguishedName::new();
+    if let Some(name) = settings.subject_alt_names.first() {
+        distinguished_name.push(DnType::CommonName, name.clone());
+    }
+    params.distinguished_name = distinguished_name;
+    // Allow for clock differences between server and devices
+    let not_before = Utc::now() - Duration::days(1);
+    let not_after = Utc::now() + Duration::days(settings.validity_days.into());
+    params.not_before = date_time_ymd(
+        not_before.year(),
+        not_before.month() as u8,
+        not_before.day() as u8,
+    );
+    params.not_after = date_time_ymd(
+        not_after.year(),
+        not_after.month() as u8,
+        not_after.day() as u8,
+    );
+
+    let cert = Certificate::from_params(params)?;
+    // Full chain, so that clients only need to trust the CA
+    let cert_chain_pem = format!("{}{}", cert.serialize_pem_with_signer(&ca)?, ca_cert_pem);
+
+    save_cert_files(cert_dir, &cert.serialize_private_key_pem(), &cert_chain_pem)
+}
+
+#[cfg(test)]
+mod test {
+    use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa};
+    use service::settings::LocalCaSettings;
+
+    use crate::certs::{certificate_expires_within, load_certified_key};
+
+    use super::{has_ca, issue_certificate, save_ca};
+
+    #[test]
+    fn local_ca_issue_certificate() {
+        let cert_dir = std::env::temp_dir().join("local_ca_issue_certificate");
+        let _ = std::fs::remove_dir_all(&cert_dir);
+
+        let mut ca_params = CertificateParams::new(vec!["Test CA".to_string()]);
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = Certificate::from_params(ca_params).unwrap();
+
+        // Invalid CA is rejected
+        assert!(save_ca(&cert_dir, "not a cert", &ca.serialize_private_key_pem()).is_err());
+        assert!(!has_ca(&cert_dir));
+
+        save_ca(
+            &cert_dir,
+            &ca.serialize_pem().unwrap(),
+            &ca.serialize_private_key_pem(),
+        )
+        .unwrap();
+        assert!(has_ca(&cert_dir));
+
+        let settings = LocalCaSettings {
+            subject_alt_names: vec!["omsupply.local".to_string(), "192.168.1.10".to_string()],
+            validity_days: 90,
+            renew_before_days: 30,
+        };
+        let cert_files = issue_certificate(&settings, &cert_dir).unwrap();
+
+        load_certified_key(&cert_files).unwrap();
+        assert!(!certificate_expires_within(&cert_files, 30).unwrap());
+        assert!(certificate_expires_within(&cert_files, 100).unwrap());
+
+        // Private keys are only readable by the owner
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let (_, ca_key_file) = super::ca_files(&cert_dir);
+            for file in [
+                ca_key_file.to_string_lossy().to_string(),
+                cert_files.private_cert_file,
+            ] {
+                let mode = std::fs::metadata(file).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use anyhow::Context;
+use log::{error, info, warn};
+use rcgen::generate_simple_self_signed;
+use rustls::{sign::CertifiedKey, ServerConfig};
+use service::settings::{is_develop, CertificateSettings, ServerSettings};
+use std::{
+    fmt::Display,
+    fs::OpenOptions,
+    io::{BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::sync::mpsc::{self, Receiver, Sender};
+
+mod acme;
+mod local_ca;
+mod reload;
+pub use reload::CertificateReloader;
+
+#[derive(Debug)]
+pub struct CertFiles {
+    pub private_cert_file: String,
+    pub public_cert_file: String,
+}
+
+const CERTS_PATH: &str = "certs";
+
+pub const PRIVATE_CERT_FILE: &str = "key.pem";
+pub const PUBLIC_CERT_FILE: &str = "cert.pem";
+
+/// How often certificates are checked for renewal and for changes on disk
+const CERTIFICATE_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+fn cert_dir(server_settings: &ServerSettings) -> PathBuf {
+    PathBuf::new()
+        .join(server_settings.base_dir.clone().unwrap_or(".".to_string()))
+        .join(CERTS_PATH)
+}
+
+pub fn find_certs(server_settings: &ServerSettings) -> Option<CertFiles> {
+    find_certs_in(&cert_dir(server_settings))
+}
+
+fn find_certs_in(cert_dir: &Path) -> Option<CertFiles> {
+    let key_file = cert_dir.join(PRIVATE_CERT_FILE);
+    let cert_file = cert_dir.join(PUBLIC_CERT_FILE);
+    if !key_file.exists() || !cert_file.exists() {
+        return None;
+    }
+    Some(CertFiles {
+        private_cert_file: key_file.to_string_lossy().to_string(),
+        public_cert_file: cert_file.to_string_lossy().to_string(),
+    })
+}
+
+/// Load rustls certificate chain and signing key
+pub fn load_certified_key(cert_files: &CertFiles) -> Result<CertifiedKey, anyhow::Error> {
+    let certfile = std::fs::File::open(&cert_files.public_cert_file)?;
+    let mut reader = BufReader::new(certfile);
+    let certs = rustls_pemfile::certs(&mut reader)?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+
+    let private_key = load_private_key_rusttls(&cert_files.private_cert_file)?;
+    let signing_key = rustls::sign::any_supported_type(&private_key)
+        .map_err(|_| anyhow::Error::msg("Unsupported private key type"))?;
+
+    Ok(CertifiedKey::new(certs, signing_key))
+}
+
+/// Helper to load a rustls::PrivateKey
+fn load_private_key_rusttls(filename: &str) -> Result<rustls::PrivateKey, anyhow::Error> {
+    let keyfile = std::fs::File::open(filename)?;
+    let mut reader = BufReader::new(keyfile);
+
+    loop {
+        match rustls_pemfile::read_one(&mut reader)? {
+            Some(rustls_pemfile::Item::RSAKey(key)) => return Ok(rustls::PrivateKey(key)),
+            Some(rustls_pemfile::Item::PKCS8Key(key)) => return Ok(rustls::PrivateKey(key)),
+            Some(rustls_pemfile::Item::ECKey(key)) => return Ok(rustls::PrivateKey(key)),
+            None => break,
+            _ => {}
+        }
+    }
+
+    Err(anyhow::Error::msg("No private key found"))
+}
+
+/// Writes a file that only the owner can read (on unix), for private keys and credentials.
+/// An existing file is replaced, since the mode is only applied when a file is created
+fn write_private_file(path: &Path, content: &str) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+        _ => {}
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(content.as_bytes())
+}
+
+/// Writes private key and certificate, each file is replaced in one step so that a reload
+/// never sees a partially written file
+fn save_cert_files(
+    cert_dir: &Path,
+    private_key_pem: &str,
+    public_cert_pem: &str,
+) -> Result<CertFiles, anyhow::Error> {
+    std::fs::create_dir_all(cert_dir)?;
+
+    let key_file = cert_dir.join(PRIVATE_CERT_FILE);
+    let cert_file = cert_dir.join(PUBLIC_CERT_FILE);
+    // Key first, reload is triggered by change of certificate file
+    let temp_key_file = key_file.with_extension("pem.tmp");
+    write_private_file(&temp_key_file, private_key_pem)?;
+    std::fs::rename(&temp_key_file, &key_file)?;
+    let temp_cert_file = cert_file.with_extension("pem.tmp");
+    std::fs::write(&temp_cert_file, public_cert_pem)?;
+    std::fs::rename(&temp_cert_file, &cert_file)?;
+
+    Ok(CertFiles {
+        private_cert_file: key_file.to_string_lossy().to_string(),
+        public_cert_file: cert_file.to_string_lossy().to_string(),
+    })
+}
+
+/// True if the (first) certificate in the public cert file expires within `days`
+pub fn certificate_expires_within(
+    cert_files: &CertFiles,
+    days: u32,
+) -> Result<bool, anyhow::Error> {
+    let pem = std::fs::read(&cert_files.public_cert_file)?;
+    let (_, pem) = x509_parser::pem::parse_x509_pem(&pem).context("Invalid certificate pem")?;
+    let cert = pem.parse_x509().context("Invalid certificate")?;
+
+    let not_after = cert.validity().not_after.timestamp();
+    let renew_at = chrono::Utc::now() + chrono::Duration::days(days.into());
+    Ok(not_after <= renew_at.timestamp())
+}
+
+/// Issues (or renews) certificate for ACME and local CA modes if it's missing, expiring
+/// or if `force` is set. Returns true if a new certificate was issued
+async fn renew_certificate(settings: &ServerSettings, force: bool) -> Result<bool, anyhow::Error> {
+    let cert_dir = cert_dir(settings);
+    let needs_renewal = |renew_before_days: u32| -> Result<bool, anyhow::Error> {
+        if force {
+            return Ok(true);
+        }
+        match find_certs_in(&cert_dir) {
+            Some(cert_files) => certificate_expires_within(&cert_files, renew_before_days),
+            None => Ok(true),
+        }
+    };
+
+    match &settings.certificates {
+        None => Ok(false),
+        Some(CertificateSettings::Acme(acme_settings)) => {
+            if !needs_renewal(acme_settings.renew_before_days)? {
+                return Ok(false);
+            }
+            acme::order_certificate(acme_settings, &cert_dir).await?;
+            Ok(true)
+        }
+        Some(CertificateSettings::LocalCa(local_ca_settings)) => {
+            if !local_ca::has_ca(&cert_dir) {
+                warn!("Local CA certificate has not been uploaded, can't issue server certificate");
+                return Ok(false);
+            }
+            if !needs_renewal(local_ca_settings.renew_before_days)? {
+                return Ok(false);
+            }
+            info!("Issuing certificate from local CA");
+            local_ca::issue_certificate(local_ca_settings, &cert_dir)?;
+            Ok(true)
+        }
+    }
+}
+
+/// Stores uploaded CA, a new server certificate is issued with the next certificate renewal run
+pub fn save_certificate_authority(
+    settings: &ServerSettings,
+    ca_cert_pem: &str,
+    ca_key_pem: &str,
+) -> Result<(), anyhow::Error> {
+    local_ca::save_ca(&cert_dir(settings), ca_cert_pem, ca_key_pem)
+}
+
+#[derive(Clone)]
+pub struct CertificateRenewalTrigger {
+    sender: Sender<()>,
+}
+
+impl CertificateRenewalTrigger {
+    /// Forces certificate to be re-issued (e.g. after new local CA upload)
+    pub fn trigger(&self) {
+        if let Err(error) = self.sender.try_send(()) {
+            error!("Problem triggering certificate renewal {:#?}", error)
+        }
+    }
+}
+
+/// Renews certificates (ACME and local CA modes) and hot reloads certificate files changed on disk.
+/// The first check runs when the task starts, so the server starts with the existing (or self
+/// signed) certificate and switches to the ACME or local CA certificate once it's issued
+pub struct CertificateRenewal {
+    settings: ServerSettings,
+    reloader: Option<CertificateReloader>,
+    receiver: Receiver<()>,
+}
+
+impl CertificateRenewal {
+    pub fn init(
+        settings: &ServerSettings,
+        certificates: &Certificates,
+    ) -> (CertificateRenewalTrigger, CertificateRenewal) {
+        let (sender, receiver) = mpsc::channel(1);
+        (
+            CertificateRenewalTrigger { sender },
+            CertificateRenewal {
+                settings: settings.clone(),
+                reloader: certificates.reloader.clone(),
+                receiver,
+            },
+        )
+    }
+
+    pub async fn run(mut self) {
+        let mut interval = tokio::time::interval(CERTIFICATE_CHECK_INTERVAL);
+        loop {
+            let force = tokio::select! {
+                Some(_) = self.receiver.recv() => true,
+                _ = interval.tick() => false,
+            };
+
+            if let Err(error) = renew_certificate(&self.settings, force).await {
+                error!("Failed to renew certificate: {:#}", error);
+            }
+
+            let Some(reloader) = &self.reloader else {
+                // Running in http mode, certificates will be used after restart
+                continue;
+            };
+            match reloader.reload_if_changed() {
+                Ok(true) => info!("Reloaded https certificate"),
+                Ok(false) => {}
+                Err(error) => error!("Failed to reload certificate: {:#}", error),
+            }
+        }
+    }
+}
+
+pub struct Certificates {
+    reloader: Option<CertificateReloader>,
+}
+
+impl Certificates {
+    ///Try to load ssl certificate, in production mode certificates are required unless danger_allow_http is set in the config
+    pub fn try_load(settings: &ServerSettings) -> std::io::Result<Self> {
+        let cert = find_certs(settings);
+
+        let reloader = match cert {
+            Some(cert_files) => Some(
+                CertificateReloader::new(cert_files).expect("Invalid self signed certificates"),
+            ),
+            None => {
+                if is_develop() || settings.danger_allow_http {
+                    warn!("No certificates found: Run in HTTP development mode");
+                    None
+                } else {
+                    warn!("No certificates found: Generating self signed certificates");
+                    let cert_path = cert_dir(settings);
+                    let cert_files = match Self::generate_certs(&cert_path) {
+                        Ok(cert_files) => cert_files,
+                        Err(e) => {
+                            warn!("Error generating self signed certificates: {}", e);
+                            error!("No certificates found");
+                            return Err(std::io::Error::new(
+                                ErrorKind::Other,
+                                "Certificate required",
+                            ));
+                        }
+                    };
+                    Some(
+                        CertificateReloader::new(cert_files)
+                            .expect("Invalid self signed certificates"),
+                    )
+                }
+            }
+        };
+
+        Ok(Certificates { reloader })
+    }
+
+    fn generate_certs(cert_dir: &Path) -> Result<CertFiles, anyhow::Error> {
+        let subject_alt_names = vec!["localhost".to_string()];
+        let cert = generate_simple_self_signed(subject_alt_names)?;
+        save_cert_files(
+            cert_dir,
+            &cert.serialize_private_key_pem(),
+            &cert.serialize_pem()?,
+        )
+    }
+
+    /// Server config that serves the latest certificate loaded by `CertificateRenewal`
+    pub fn config(&self) -> Option<ServerConfig> {
+        self.reloader
+            .as_ref()
+            .map(CertificateReloader::server_config)
+    }
+
+    /// None when running on http
+    pub fn reloader(&self) -> Option<CertificateReloader> {
+        self.reloader.clone()
+    }
+
+    pub fn is_https(&self) -> bool {
+        self.reloader.is_some()
+    }
+
+    pub fn protocol(&self) -> Protocol {
+        match self.reloader {
+            Some(_) => Protocol::Https,
+            None => Protocol::Http,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum Protocol {
+    Http,
+    Https,
+}
+
+impl Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Protocol::Http => "http",
+                Protocol::Https => "https",
+            }
+        )
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    sync::{Arc, Mutex, RwLock},
+    time::SystemTime,
+};
+
+use rustls::{
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    ServerConfig,
+};
+
+use super::{load_certified_key, CertFiles};
+
+/// Serves the currently loaded certificate, the certificate can be swapped while the server is running
+struct ReloadableCertResolver {
+    certified_key: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for ReloadableCertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.certified_key.read().unwrap().clone())
+    }
+}
+
+/// Hot reloads certificate files into the running https server
+#[derive(Clone)]
+pub struct CertificateReloader {
+    resolver: Arc<ReloadableCertResolver>,
+    cert_files: Arc<CertFiles>,
+    /// Modified time of the public certificate file when it was last loaded
+    loaded_modified: Arc<Mutex<Option<SystemTime>>>,
+}
+
+impl CertificateReloader {
+    pub fn new(cert_files: CertFiles) -> Result<Self, anyhow::Error> {
+        let loaded_modified = modified(&cert_files);
+        let certified_key = load_certified_key(&cert_files)?;
+
+        Ok(CertificateReloader {
+            resolver: Arc::new(ReloadableCertResolver {
+                certified_key: RwLock::new(Arc::new(certified_key)),
+            }),
+            cert_files: Arc::new(cert_files),
+            loaded_modified: Arc::new(Mutex::new(loaded_modified)),
+        })
+    }
+
+    pub fn server_config(&self) -> ServerConfig {
+        ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_cert_resolver(self.resolver.clone())
+    }
+
+    /// Currently served certificate (DER), signed by peer sync so that peers can pin it
+    pub fn certificate_der(&self) -> Option<Vec<u8>> {
+        let certified_key = self.resolver.certified_key.read().unwrap();
+        certified_key.cert.first().map(|cert| cert.0.clone())
+    }
+
+    /// Reloads certificate if the certificate file was changed since it was last loaded,
+    /// returns true if certificate was reloaded
+    pub fn reload_if_changed(&self) -> Result<bool, anyhow::Error> {
+        let current_modified = modified(&self.cert_files);
+        let mut loaded_modified = self.loaded_modified.lock().unwrap();
+        if current_modified == *loaded_modified {
+            return Ok(false);
+        }
+
+        // Keep serving the previous certificate if the new one can't be loaded
+        let certified_key = load_certified_key(&self.cert_files)?;
+        *self.resolver.certified_key.write().unwrap() = Arc::new(certified_key);
+        *loaded_modified = current_modified;
+        Ok(true)
+    }
+}
+
+fn modified(cert_files: &CertFiles) -> Option<SystemTime> {
+    std::fs::metadata(&cert_files.public_cert_file)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
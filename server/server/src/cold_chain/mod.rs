@@ -19,7 +19,7 @@ use temperature_breach::put_breaches;
 use temperature_log::put_logs;
 
 const URL_PATH: &str = "/coldchain/v1";
-const COOKIE_NAME: &str = "coldchain";
+pub(crate) const COOKIE_NAME: &str = "coldchain";
 
 pub fn config_cold_chain(cfg: &mut web::ServiceConfig) {
     cfg.route(&format!("{}/login", URL_PATH), web::post().to(post_login));
@@ -1,5 +1,10 @@
 use crate::certs::Protocol;
-use {astro_dnssd::DNSServiceBuilder, std::collections::HashMap};
+use service::sync::peer_sync::Peers;
+use std::time::Duration;
+use {
+    astro_dnssd::{DNSServiceBuilder, ServiceBrowserBuilder, ServiceEventType},
+    std::collections::HashMap,
+};
 
 const SERVICE_NAME: &str = "_omsupply._tcp";
 const NAME: &str = "omSupplyServer";
@@ -26,3 +31,48 @@ pub(crate) fn start_discovery(protocol: Protocol, port: u16, hardware_id: String
         }
     });
 }
+
+/// Browses for other omSupply servers on the network and keeps their urls in `peers` (for peer sync)
+pub(crate) fn start_peer_discovery(hardware_id: String, peers: Peers) {
+    // Browsing is blocking, run it on its own thread
+    std::thread::spawn(move || {
+        let browser = match ServiceBrowserBuilder::new(SERVICE_NAME).browse() {
+            Ok(browser) => browser,
+            Err(e) => {
+                log::error!("Error browsing for peers: {:?}", e);
+                return;
+            }
+        };
+
+        loop {
+            let Ok(service) = browser.recv_timeout(Duration::from_secs(5)) else {
+                continue;
+            };
+            let text_record = service.txt_record.unwrap_or_default();
+            let Some(peer_hardware_id) = text_record.get(HARDWARE_ID_KEY) else {
+                continue;
+            };
+            if *peer_hardware_id == hardware_id {
+                continue;
+            }
+
+            match service.event_type {
+                ServiceEventType::Added => {
+                    let protocol = text_record
+                        .get(PROTOCOL_KEY)
+                        .map(String::as_str)
+                        .unwrap_or("https");
+                    let url = format!(
+                        "{}://{}:{}",
+                        protocol,
+                        service.hostname.trim_end_matches('.'),
+                        service.port
+                    );
+                    log::info!("Discovered peer {}", url);
+                    peers.set(peer_hardware_id, &url);
+                }
+                ServiceEventType::Removed => peers.remove(peer_hardware_id),
+            }
+        }
+    });
+}
@@ -2,14 +2,26 @@
 extern crate machine_uid;
 
 use crate::{
-    certs::Certificates, cold_chain::config_cold_chain, configuration::get_or_create_token_secret,
-    cors::cors_policy, middleware::central_server_only, print::config_print,
-    serve_frontend::config_serve_frontend, static_files::config_static_files,
-    support::config_support, sync_on_central::config_sync_on_central,
+    certs::{CertificateRenewal, Certificates},
+    cold_chain::config_cold_chain,
+    configuration::get_or_create_token_secret,
+    cors::cors_policy,
+    metrics::config_metrics,
+    middleware::central_server_only,
+    peer_sync::config_peer_sync,
+    print::config_print,
+    serve_frontend::config_serve_frontend,
+    static_files::config_static_files,
+    support::config_support,
+    sync_on_central::config_sync_on_central,
     upload_fridge_tag::config_upload_fridge_tag,
 };
 
-use self::middleware::{compress as compress_middleware, logger as logger_middleware};
+use self::middleware::{
+    compress as compress_middleware, logger as logger_middleware,
+    rate_limit as rate_limit_middleware, rate_limit::RateLimiter,
+    request_log_context as request_log_context_middleware,
+};
 use actix_cors::Cors;
 use anyhow::Context;
 use graphql_core::loader::{get_loaders, LoaderRegistry};
@@ -23,11 +35,14 @@ use repository::{get_storage_connection_manager, migrations::migrate};
 use service::{
     auth_data::AuthData,
     plugin::validation::ValidatedPluginBucket,
+    print::queue::PrintQueueDriver,
     processors::Processors,
+    report::schedule::processor::ReportScheduleDriver,
     service_provider::ServiceProvider,
     settings::{is_develop, ServerSettings, Settings},
     sync::{
         file_sync_driver::FileSyncDriver,
+        peer_sync::{PeerSyncDriver, Peers},
         synchroniser_driver::{SiteIsInitialisedCallback, SynchroniserDriver},
     },
     token_bucket::TokenBucket,
@@ -43,7 +58,9 @@ pub mod configuration;
 pub mod cors;
 pub mod environment;
 mod logging;
+mod metrics;
 pub mod middleware;
+mod peer_sync;
 mod serve_frontend;
 pub mod static_files;
 pub mod support;
@@ -105,6 +122,8 @@ pub async fn start_server(
     ));
     let loaders = get_loaders(&connection_manager, service_provider.clone()).await;
     let certificates = Certificates::try_load(&settings.server).unwrap();
+    let (certificate_renewal_trigger, certificate_renewal) =
+        CertificateRenewal::init(&settings.server, &certificates);
     let token_bucket = Arc::new(RwLock::new(TokenBucket::new()));
     let token_secret = get_or_create_token_secret(&connection_manager.connection().unwrap());
     let auth = auth_data(&settings.server, token_bucket, token_secret, &certificates);
@@ -247,7 +266,11 @@ pub async fn start_server(
     #[cfg(not(target_os = "android"))]
     {
         info!("Starting server DNS-SD discovery",);
-        discovery::start_discovery(certificates.protocol(), settings.server.port, machine_uid);
+        discovery::start_discovery(
+            certificates.protocol(),
+            settings.server.port,
+            machine_uid.clone(),
+        );
     }
 
     info!("Starting discovery graphql server",);
@@ -285,27 +308,58 @@ pub async fn start_server(
         force_trigger_sync_on_startup,
     );
     let file_sync_task = file_sync_driver.run(service_provider.clone().into_inner());
+    actix_web::rt::spawn(certificate_renewal.run());
+
+    actix_web::rt::spawn(
+        ReportScheduleDriver::new(settings.clone()).run(service_provider.clone().into_inner()),
+    );
+    actix_web::rt::spawn(PrintQueueDriver::new().run(service_provider.clone().into_inner()));
+
+    // START PEER SYNC
+    if let Some(peer_sync_settings) = settings.peer_sync.clone() {
+        let peers = Peers::default();
+        #[cfg(not(target_os = "android"))]
+        {
+            info!("Starting DNS-SD discovery of peers",);
+            discovery::start_peer_discovery(machine_uid.clone(), peers.clone());
+        }
+        actix_web::rt::spawn(
+            PeerSyncDriver::new(peer_sync_settings, peers).run(service_provider.clone().into_inner()),
+        );
+    }
 
+    // Shared between http workers
+    let rate_limiter = Arc::new(RateLimiter::new(&settings.server.request_limits));
     let closure_settings = settings.clone();
+    let certificate_reloader = certificates.reloader();
     let mut http_server = HttpServer::new(move || {
         App::new()
             .app_data(Data::new(closure_settings.clone()))
             .wrap(logger_middleware())
             .wrap(cors_policy(&closure_settings))
             .wrap(compress_middleware())
+            .wrap(rate_limit_middleware(rate_limiter.clone()))
+            // Outermost, so that other middleware logs are within request log context
+            .wrap(request_log_context_middleware())
             // needed for static files service
             .app_data(Data::new(closure_settings.clone()))
             // needed for cold chain service
             .app_data(service_provider.clone())
             .app_data(auth.clone())
             .app_data(validated_plugins.clone())
+            // needed for certificate authority upload
+            .app_data(Data::new(certificate_renewal_trigger.clone()))
+            // needed for peer sync certificate pinning
+            .app_data(Data::new(certificate_reloader.clone()))
             .configure(attach_graphql_schema(graphql_schema.clone()))
             .configure(config_static_files)
             .configure(config_cold_chain)
             .configure(config_upload_fridge_tag)
             .configure(config_sync_on_central)
+            .configure(config_peer_sync)
             .configure(config_support)
             .configure(config_print)
+            .configure(config_metrics)
             // Needs to be last to capture all unmatches routes
             .configure(config_serve_frontend)
     })
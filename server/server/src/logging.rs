@@ -1,7 +1,26 @@
 #[cfg(not(target_os = "android"))]
 use std::env;
 
-use service::settings::{Level, LogMode, LoggingSettings};
+use log::{LevelFilter, Record};
+use log4rs::{
+    append::{
+        console::ConsoleAppender,
+        rolling_file::{
+            policy::compound::{
+                roll::fixed_window::FixedWindowRoller, trigger::size::SizeTrigger, CompoundPolicy,
+            },
+            RollingFileAppender,
+        },
+    },
+    config::{Appender, Root},
+    encode::Encode,
+    Config,
+};
+use serde::Serialize;
+use service::{
+    log_context::LogContext,
+    settings::{Level, LogFormat, LogMode, LoggingSettings},
+};
 use simple_log::LogConfigBuilder;
 
 // Can use log4rs to extend logging functionality beyond what is available in current
@@ -13,6 +32,11 @@ pub fn logging_init(settings: Option<LoggingSettings>, level: Option<Level>) {
     ));
 
     let log_level = level.unwrap_or(settings.level.clone());
+    if settings.format == LogFormat::Json {
+        json_logging_init(&settings, &log_level).expect("Unable to initialise logger");
+        return;
+    }
+
     let config = match settings.mode {
         LogMode::File => file_logger(&settings)
             .level(log_level.to_string())
@@ -28,7 +52,13 @@ pub fn logging_init(settings: Option<LoggingSettings>, level: Option<Level>) {
     simple_log::new(config).expect("Unable to initialise logger");
 }
 
-fn file_logger(settings: &LoggingSettings) -> LogConfigBuilder {
+struct LogFile {
+    path: String,
+    max_file_count: u32,
+    max_file_size_mb: u64,
+}
+
+fn log_file(settings: &LoggingSettings) -> LogFile {
     let default_log_file = "remote_server.log".to_string();
     let default_log_dir = "log".to_string();
     let default_max_file_count = 10;
@@ -43,12 +73,148 @@ fn file_logger(settings: &LoggingSettings) -> LogConfigBuilder {
     #[cfg(target_os = "android")]
     let log_path = std::path::PathBuf::from(&log_dir);
     let log_file = settings.filename.clone().unwrap_or(default_log_file);
-    let log_file = log_path.join(log_file).to_string_lossy().to_string();
-    let max_file_count = settings.max_file_count.unwrap_or(default_max_file_count);
-    let max_file_size = settings.max_file_size.unwrap_or(default_max_file_size);
+
+    LogFile {
+        path: log_path.join(log_file).to_string_lossy().to_string(),
+        max_file_count: settings.max_file_count.unwrap_or(default_max_file_count) as u32,
+        max_file_size_mb: settings.max_file_size.unwrap_or(default_max_file_size) as u64,
+    }
+}
+
+fn file_logger(settings: &LoggingSettings) -> LogConfigBuilder {
+    let LogFile {
+        path,
+        max_file_count,
+        max_file_size_mb,
+    } = log_file(settings);
 
     LogConfigBuilder::builder()
-        .path(log_file)
-        .size(max_file_size as u64)
-        .roll_count(max_file_count as u32)
+        .path(path)
+        .size(max_file_size_mb)
+        .roll_count(max_file_count)
+}
+
+/// simple-log doesn't support custom encoders, json logging is configured with log4rs directly
+fn json_logging_init(settings: &LoggingSettings, level: &Level) -> Result<(), anyhow::Error> {
+    let mut appenders = Vec::new();
+
+    if matches!(settings.mode, LogMode::Console | LogMode::All) {
+        let console = ConsoleAppender::builder()
+            .encoder(Box::new(JsonLogEncoder))
+            .build();
+        appenders.push(Appender::builder().build("console", Box::new(console)));
+    }
+
+    if matches!(settings.mode, LogMode::File | LogMode::All) {
+        let LogFile {
+            path,
+            max_file_count,
+            max_file_size_mb,
+        } = log_file(settings);
+        let roller =
+            FixedWindowRoller::builder().build(&format!("{}.{{}}", path), max_file_count)?;
+        let policy = CompoundPolicy::new(
+            Box::new(SizeTrigger::new(max_file_size_mb * 1024 * 1024)),
+            Box::new(roller),
+        );
+        let file = RollingFileAppender::builder()
+            .encoder(Box::new(JsonLogEncoder))
+            .build(path, Box::new(policy))?;
+        appenders.push(Appender::builder().build("file", Box::new(file)));
+    }
+
+    let level = level
+        .to_string()
+        .parse::<LevelFilter>()
+        .unwrap_or(LevelFilter::Info);
+    let root = appenders
+        .iter()
+        .fold(Root::builder(), |root, appender| {
+            root.appender(appender.name())
+        })
+        .build(level);
+
+    let config = Config::builder().appenders(appenders).build(root)?;
+    log4rs::init_config(config)?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct JsonLogEntry<'a> {
+    time: String,
+    level: &'a str,
+    target: &'a str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<u32>,
+    #[serde(flatten)]
+    context: Option<LogContext>,
+}
+
+/// One json object per line, with `LogContext` of the task that logged the record
+#[derive(Debug)]
+struct JsonLogEncoder;
+
+impl Encode for JsonLogEncoder {
+    fn encode(&self, w: &mut dyn log4rs::encode::Write, record: &Record) -> anyhow::Result<()> {
+        let entry = JsonLogEntry {
+            time: chrono::Utc::now().to_rfc3339(),
+            level: record.level().as_str(),
+            target: record.target(),
+            message: record.args().to_string(),
+            file: record.file(),
+            line: record.line(),
+            context: LogContext::current(),
+        };
+        serde_json::to_writer(&mut *w, &entry)?;
+        w.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use log::{Level, Record};
+    use log4rs::encode::{writer::simple::SimpleWriter, Encode};
+    use serde_json::{json, Value};
+    use service::log_context::LogContext;
+
+    use super::JsonLogEncoder;
+
+    fn encode(message: &str) -> Value {
+        let mut buffer = Vec::new();
+        JsonLogEncoder
+            .encode(
+                &mut SimpleWriter(&mut buffer),
+                &Record::builder()
+                    .level(Level::Warn)
+                    .target("service::sync")
+                    .args(format_args!("{}", message))
+                    .build(),
+            )
+            .unwrap();
+        serde_json::from_slice(&buffer).unwrap()
+    }
+
+    #[test]
+    fn json_log_encoder() {
+        let entry = encode("Sync step started Push");
+        assert_eq!(entry["level"], json!("WARN"));
+        assert_eq!(entry["message"], json!("Sync step started Push"));
+        assert_eq!(entry.get("correlation_id"), None);
+
+        let context = LogContext {
+            correlation_id: "correlation".to_string(),
+            operation: Some("sync".to_string()),
+            store_id: Some("store_a".to_string()),
+            ..Default::default()
+        };
+        let entry = context.sync_scope(|| encode("Sync step started Push"));
+        assert_eq!(entry["correlation_id"], json!("correlation"));
+        assert_eq!(entry["operation"], json!("sync"));
+        assert_eq!(entry["store_id"], json!("store_a"));
+        assert_eq!(entry.get("user_id"), None);
+    }
 }
@@ -0,0 +1,93 @@
+use actix_web::{
+    get,
+    http::header,
+    web::{self, Data},
+    HttpRequest, HttpResponse,
+};
+use service::{
+    service_provider::ServiceProvider,
+    settings::{MetricsSettings, Settings},
+};
+use util::hash::sha256;
+
+/// Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+pub fn config_metrics(cfg: &mut web::ServiceConfig) {
+    cfg.service(metrics);
+}
+
+#[get("/metrics")]
+async fn metrics(
+    request: HttpRequest,
+    settings: Data<Settings>,
+    service_provider: Data<ServiceProvider>,
+) -> HttpResponse {
+    let Some(metrics_settings) = &settings.server.metrics else {
+        return HttpResponse::NotFound().finish();
+    };
+    if !is_authorised(&request, metrics_settings) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match service_provider.metrics.export(&service_provider) {
+        Ok(metrics) => HttpResponse::Ok().content_type(CONTENT_TYPE).body(metrics),
+        Err(error) => {
+            log::error!("Failed to export metrics: {}", error);
+            HttpResponse::InternalServerError().body(error.to_string())
+        }
+    }
+}
+
+fn is_authorised(request: &HttpRequest, settings: &MetricsSettings) -> bool {
+    let Some(bearer_token) = &settings.bearer_token else {
+        return true;
+    };
+
+    let Some(request_token) = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+
+    // Comparing hashes, so that response timing doesn't reveal how much of the token matched
+    sha256(request_token) == sha256(bearer_token)
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{http::header, test::TestRequest};
+    use service::settings::MetricsSettings;
+
+    use super::is_authorised;
+
+    #[test]
+    fn metrics_bearer_token() {
+        let settings = MetricsSettings {
+            bearer_token: Some("secret".to_string()),
+        };
+
+        let request = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer secret"))
+            .to_http_request();
+        assert!(is_authorised(&request, &settings));
+
+        let request = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer wrong"))
+            .to_http_request();
+        assert!(!is_authorised(&request, &settings));
+        assert!(!is_authorised(
+            &TestRequest::default().to_http_request(),
+            &settings
+        ));
+
+        // No token configured
+        assert!(is_authorised(
+            &TestRequest::default().to_http_request(),
+            &MetricsSettings { bearer_token: None }
+        ));
+    }
+}
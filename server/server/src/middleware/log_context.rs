@@ -0,0 +1,81 @@
+use std::future::{ready, Ready};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use service::log_context::LogContext;
+
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+const MAX_CORRELATION_ID_LENGTH: usize = 64;
+
+/// Runs every request within a `LogContext`, so that all log entries of a request share a
+/// correlation id. Client can provide the correlation id with `X-Correlation-Id` header,
+/// it is returned in the response header either way
+#[derive(Debug, Default)]
+pub struct RequestLogContext;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestLogContext
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestLogContextMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestLogContextMiddleware { service }))
+    }
+}
+
+pub struct RequestLogContextMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestLogContextMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let context = LogContext::new(&format!("{} {}", req.method(), req.path()))
+            .correlation_id(client_correlation_id(&req));
+        let correlation_id = context.correlation_id.clone();
+
+        let fut = self.service.call(req);
+        Box::pin(context.scope(async move {
+            let mut response = fut.await?;
+            if let Ok(value) = HeaderValue::from_str(&correlation_id) {
+                response
+                    .headers_mut()
+                    .insert(HeaderName::from_static(CORRELATION_ID_HEADER), value);
+            }
+            Ok(response)
+        }))
+    }
+}
+
+/// Only accept reasonably sized ids made of characters that are safe to log and index
+fn client_correlation_id(req: &ServiceRequest) -> Option<String> {
+    let correlation_id = req.headers().get(CORRELATION_ID_HEADER)?.to_str().ok()?;
+    let is_valid = !correlation_id.is_empty()
+        && correlation_id.len() <= MAX_CORRELATION_ID_LENGTH
+        && correlation_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    is_valid.then(|| correlation_id.to_string())
+}
@@ -1,5 +1,7 @@
 pub mod content_length_limit;
+pub mod log_context;
 mod central_server_only;
+pub mod rate_limit;
 
 pub fn compress() -> actix_web::middleware::Compress {
     actix_web::middleware::Compress::default()
@@ -9,10 +11,18 @@ pub fn logger() -> actix_web::middleware::Logger {
     actix_web::middleware::Logger::default()
 }
 
+pub fn request_log_context() -> log_context::RequestLogContext {
+    log_context::RequestLogContext::default()
+}
+
 pub(crate) fn central_server_only() -> central_server_only::CentralServerOnly {
     central_server_only::CentralServerOnly::default()
 }
 
 pub fn limit_content_length() -> content_length_limit::ContentLengthLimit {
     content_length_limit::ContentLengthLimit::default()
-}
\ No newline at end of file
+}
+
+pub fn rate_limit(limiter: std::sync::Arc<rate_limit::RateLimiter>) -> rate_limit::RateLimit {
+    rate_limit::RateLimit::new(limiter)
+}
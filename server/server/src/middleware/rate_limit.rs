@@ -0,0 +1,526 @@
+use std::{
+    collections::HashMap,
+    future::{ready, Ready},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header, StatusCode},
+    web::Data,
+    Error, HttpResponse,
+};
+use futures_util::{future::LocalBoxFuture, TryStreamExt};
+use service::{auth::validate_auth, auth_data::AuthData, settings::RequestLimitSettings};
+
+use crate::cold_chain::COOKIE_NAME as COLD_CHAIN_COOKIE_NAME;
+
+const GRAPHQL_PATH_PREFIX: &str = "/graphql";
+/// Requests to other paths (static files, sync, frontend) are not rate limited
+const RATE_LIMITED_PATH_PREFIXES: [&str; 2] = [GRAPHQL_PATH_PREFIX, "/coldchain/"];
+
+/// Buckets that have been refilled are removed once there are more than this many
+const MAX_BUCKETS_BEFORE_CLEANUP: usize = 10_000;
+/// Cleanup iterates all buckets, so it runs at most once per interval rather than on every request
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Token bucket, `capacity` requests can be made in a burst, after which requests
+/// are allowed at `capacity` per minute
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+#[derive(Debug)]
+struct Limit {
+    capacity: f64,
+    buckets: HashMap<String, Bucket>,
+    last_cleanup: Option<Instant>,
+}
+
+impl Limit {
+    fn new(requests_per_minute: u32) -> Self {
+        Self {
+            capacity: requests_per_minute as f64,
+            buckets: HashMap::new(),
+            last_cleanup: None,
+        }
+    }
+
+    fn refill_per_second(&self) -> f64 {
+        self.capacity / 60.0
+    }
+
+    /// Takes a token for the key, or returns how long until the next token is available
+    fn take(&mut self, key: &str, now: Instant) -> Result<(), Duration> {
+        let capacity = self.capacity;
+        let refill_per_second = self.refill_per_second();
+
+        let is_cleanup_due = match self.last_cleanup {
+            Some(last_cleanup) => now.duration_since(last_cleanup) >= CLEANUP_INTERVAL,
+            None => true,
+        };
+        if self.buckets.len() > MAX_BUCKETS_BEFORE_CLEANUP && is_cleanup_due {
+            self.last_cleanup = Some(now);
+            self.buckets.retain(|_, bucket| {
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                bucket.tokens + elapsed * refill_per_second < capacity
+            });
+        }
+
+        let bucket = self.buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            last_refill: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_second).min(capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        let seconds_until_token = (1.0 - bucket.tokens) / refill_per_second;
+        Err(Duration::from_secs_f64(seconds_until_token))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RateLimitExceeded {
+    Ip { retry_after: Duration },
+    User { retry_after: Duration },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RequestBodyRejected {
+    TooLarge {
+        limit_bytes: u64,
+    },
+    /// Body without Content-Length (chunked), its size is unknown until it's read
+    LengthRequired,
+}
+
+/// In memory rate limiter, shared between all http workers
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    per_ip: Option<Mutex<Limit>>,
+    per_user: Option<Mutex<Limit>>,
+    max_request_body_bytes: Option<u64>,
+}
+
+impl RateLimiter {
+    pub fn new(settings: &RequestLimitSettings) -> Self {
+        Self {
+            per_ip: settings
+                .requests_per_minute_per_ip
+                .map(|limit| Mutex::new(Limit::new(limit))),
+            per_user: settings
+                .requests_per_minute_per_user
+                .map(|limit| Mutex::new(Limit::new(limit))),
+            max_request_body_bytes: settings.max_request_body_bytes,
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.per_ip.is_some() || self.per_user.is_some() || self.max_request_body_bytes.is_some()
+    }
+
+    /// `content_length` is None when the request has a body without Content-Length header
+    pub fn check_body_size(&self, content_length: Option<u64>) -> Result<(), RequestBodyRejected> {
+        let Some(limit_bytes) = self.max_request_body_bytes else {
+            return Ok(());
+        };
+        match content_length {
+            None => Err(RequestBodyRejected::LengthRequired),
+            Some(content_length) if content_length > limit_bytes => {
+                Err(RequestBodyRejected::TooLarge { limit_bytes })
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    pub fn check(
+        &self,
+        ip: Option<&str>,
+        user_id: Option<&str>,
+        now: Instant,
+    ) -> Result<(), RateLimitExceeded> {
+        if let (Some(limit), Some(ip)) = (&self.per_ip, ip) {
+            limit
+                .lock()
+                .unwrap()
+                .take(ip, now)
+                .map_err(|retry_after| RateLimitExceeded::Ip { retry_after })?;
+        }
+
+        if let (Some(limit), Some(user_id)) = (&self.per_user, user_id) {
+            limit
+                .lock()
+                .unwrap()
+                .take(user_id, now)
+                .map_err(|retry_after| RateLimitExceeded::User { retry_after })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl RateLimitExceeded {
+    fn retry_after_seconds(&self) -> u64 {
+        let retry_after = match self {
+            RateLimitExceeded::Ip { retry_after } | RateLimitExceeded::User { retry_after } => {
+                retry_after
+            }
+        };
+        // Round up, Retry-After of 0 would invite an immediate retry
+        retry_after.as_secs_f64().ceil().max(1.0) as u64
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            RateLimitExceeded::Ip { .. } => "Too many requests from this IP address",
+            RateLimitExceeded::User { .. } => "Too many requests for this user",
+        }
+    }
+
+    /// Graphql clients get a graphql error response, so they can handle it like other errors
+    fn response_body(&self, is_graphql: bool) -> serde_json::Value {
+        let retry_after_seconds = self.retry_after_seconds();
+        if is_graphql {
+            return serde_json::json!({
+                "data": null,
+                "errors": [{
+                    "message": "Too many requests",
+                    "extensions": {
+                        "details": self.message(),
+                        "retryAfterSeconds": retry_after_seconds,
+                    },
+                }],
+            });
+        }
+        serde_json::json!({
+            "error": "TooManyRequests",
+            "message": self.message(),
+            "retryAfterSeconds": retry_after_seconds,
+        })
+    }
+
+    fn into_response(self, is_graphql: bool) -> HttpResponse {
+        HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, self.retry_after_seconds().to_string()))
+            .json(self.response_body(is_graphql))
+    }
+}
+
+impl RequestBodyRejected {
+    fn message(&self) -> String {
+        match self {
+            RequestBodyRejected::TooLarge { limit_bytes } => {
+                format!("Request body is larger than {limit_bytes} bytes")
+            }
+            RequestBodyRejected::LengthRequired => {
+                "Request body must have a Content-Length header".to_string()
+            }
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            RequestBodyRejected::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            RequestBodyRejected::LengthRequired => StatusCode::LENGTH_REQUIRED,
+        }
+    }
+
+    fn response_body(&self, is_graphql: bool) -> serde_json::Value {
+        if is_graphql {
+            return serde_json::json!({
+                "data": null,
+                "errors": [{
+                    "message": "Request too large",
+                    "extensions": {
+                        "details": self.message(),
+                    },
+                }],
+            });
+        }
+        serde_json::json!({
+            "error": "RequestTooLarge",
+            "message": self.message(),
+        })
+    }
+
+    fn into_response(self, is_graphql: bool) -> HttpResponse {
+        HttpResponse::build(self.status()).json(self.response_body(is_graphql))
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit {
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimit {
+    pub fn new(limiter: Arc<RateLimiter>) -> Self {
+        Self { limiter }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service,
+            limiter: self.limiter.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: S,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let is_rate_limited_path = RATE_LIMITED_PATH_PREFIXES
+            .iter()
+            .any(|prefix| req.path().starts_with(prefix));
+
+        if !self.limiter.is_enabled() || !is_rate_limited_path {
+            let fut = self.service.call(req);
+            return Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) });
+        }
+
+        let is_graphql = req.path().starts_with(GRAPHQL_PATH_PREFIX);
+
+        if let Err(rejected) = self.limiter.check_body_size(request_content_length(&req)) {
+            log::warn!(
+                "Request body rejected for {} {}: {}",
+                req.method(),
+                req.path(),
+                rejected.message()
+            );
+            let response = rejected.into_response(is_graphql).map_into_right_body();
+            return Box::pin(async move {
+                // Drain the request body, see content_length_limit
+                let (_, payload) = req.parts_mut();
+                while let Ok(Some(_)) = payload.try_next().await {}
+
+                Ok(req.into_response(response))
+            });
+        }
+
+        let ip = req.peer_addr().map(|address| address.ip().to_string());
+        let user_id = request_user_id(&req);
+
+        if let Err(exceeded) = self
+            .limiter
+            .check(ip.as_deref(), user_id.as_deref(), Instant::now())
+        {
+            log::warn!(
+                "Rate limit exceeded for {} {}, ip: {:?}, user: {:?}",
+                req.method(),
+                req.path(),
+                ip,
+                user_id
+            );
+            let response = exceeded.into_response(is_graphql).map_into_right_body();
+            return Box::pin(async move { Ok(req.into_response(response)) });
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+/// Some(0) for requests without a body, None for a body without Content-Length (chunked)
+fn request_content_length(req: &ServiceRequest) -> Option<u64> {
+    if let Some(content_length) = req.headers().get(header::CONTENT_LENGTH) {
+        return content_length
+            .to_str()
+            .ok()
+            .and_then(|content_length| content_length.parse().ok());
+    }
+    match req.headers().contains_key(header::TRANSFER_ENCODING) {
+        true => None,
+        false => Some(0),
+    }
+}
+
+/// User of the api token (graphql) or cold chain cookie, None for unauthenticated requests
+fn request_user_id(req: &ServiceRequest) -> Option<String> {
+    let auth_data = req.app_data::<Data<AuthData>>()?;
+
+    let bearer_token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    let token = bearer_token.or_else(|| {
+        req.cookie(COLD_CHAIN_COOKIE_NAME)
+            .map(|cookie| cookie.value().to_string())
+    })?;
+
+    validate_auth(auth_data, &Some(token))
+        .ok()
+        .map(|validated_user| validated_user.user_id)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+    use service::settings::RequestLimitSettings;
+
+    use super::{
+        Limit, RateLimitExceeded, RateLimiter, RequestBodyRejected, CLEANUP_INTERVAL,
+        MAX_BUCKETS_BEFORE_CLEANUP,
+    };
+
+    #[test]
+    fn rate_limiter() {
+        let limiter = RateLimiter::new(&RequestLimitSettings {
+            requests_per_minute_per_ip: Some(3),
+            requests_per_minute_per_user: Some(2),
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        // Burst up to the ip limit
+        assert_eq!(limiter.check(Some("ip_a"), None, now), Ok(()));
+        assert_eq!(limiter.check(Some("ip_a"), None, now), Ok(()));
+        assert_eq!(limiter.check(Some("ip_a"), None, now), Ok(()));
+        let exceeded = limiter.check(Some("ip_a"), None, now).unwrap_err();
+        assert!(matches!(exceeded, RateLimitExceeded::Ip { .. }));
+        assert_eq!(exceeded.retry_after_seconds(), 20);
+        // Other ip is not affected
+        assert_eq!(limiter.check(Some("ip_b"), None, now), Ok(()));
+        // Token is refilled after 20 seconds (3 per minute)
+        let later = now + Duration::from_secs(20);
+        assert_eq!(limiter.check(Some("ip_a"), None, later), Ok(()));
+
+        // User limit applies across ips
+        assert_eq!(limiter.check(Some("ip_c"), Some("user"), now), Ok(()));
+        assert_eq!(limiter.check(Some("ip_d"), Some("user"), now), Ok(()));
+        let exceeded = limiter.check(Some("ip_e"), Some("user"), now).unwrap_err();
+        assert!(matches!(exceeded, RateLimitExceeded::User { .. }));
+        assert_eq!(exceeded.retry_after_seconds(), 30);
+
+        // Disabled limits
+        let limiter = RateLimiter::new(&RequestLimitSettings {
+            requests_per_minute_per_ip: None,
+            requests_per_minute_per_user: None,
+            ..Default::default()
+        });
+        assert!(!limiter.is_enabled());
+        assert_eq!(limiter.check(Some("ip_a"), Some("user"), now), Ok(()));
+
+        // Disabled by default
+        assert!(!RateLimiter::new(&RequestLimitSettings::default()).is_enabled());
+    }
+
+    #[test]
+    fn rate_limit_cleanup() {
+        let mut limit = Limit::new(60);
+        let now = Instant::now();
+        for key in 0..=MAX_BUCKETS_BEFORE_CLEANUP {
+            limit.take(&key.to_string(), now).unwrap();
+        }
+
+        // Buckets are refilled after a minute, first request over the threshold cleans up
+        let later = now + Duration::from_secs(60);
+        limit.take("new_key_1", later).unwrap();
+        assert_eq!(limit.buckets.len(), 1);
+
+        // Cleanup doesn't run again within the interval, even when over the threshold
+        for key in 0..=MAX_BUCKETS_BEFORE_CLEANUP {
+            limit.take(&key.to_string(), later).unwrap();
+        }
+        let even_later = later + Duration::from_secs(59);
+        limit.take("new_key_2", even_later).unwrap();
+        assert_eq!(limit.buckets.len(), MAX_BUCKETS_BEFORE_CLEANUP + 3);
+
+        // Runs once the interval has passed
+        limit.take("new_key_3", later + CLEANUP_INTERVAL).unwrap();
+        assert_eq!(limit.buckets.len(), 1);
+    }
+
+    #[test]
+    fn request_body_size() {
+        let limiter = RateLimiter::new(&RequestLimitSettings {
+            max_request_body_bytes: Some(100),
+            ..Default::default()
+        });
+        assert!(limiter.is_enabled());
+        assert_eq!(limiter.check_body_size(Some(0)), Ok(()));
+        assert_eq!(limiter.check_body_size(Some(100)), Ok(()));
+        assert_eq!(
+            limiter.check_body_size(Some(101)),
+            Err(RequestBodyRejected::TooLarge { limit_bytes: 100 })
+        );
+        assert_eq!(
+            limiter.check_body_size(None),
+            Err(RequestBodyRejected::LengthRequired)
+        );
+        assert_eq!(
+            RequestBodyRejected::TooLarge { limit_bytes: 100 }.response_body(false),
+            json!({
+                "error": "RequestTooLarge",
+                "message": "Request body is larger than 100 bytes",
+            })
+        );
+
+        // No limit by default
+        let limiter = RateLimiter::new(&RequestLimitSettings::default());
+        assert_eq!(limiter.check_body_size(Some(u64::MAX)), Ok(()));
+        assert_eq!(limiter.check_body_size(None), Ok(()));
+    }
+
+    #[test]
+    fn rate_limit_graphql_error() {
+        let exceeded = RateLimitExceeded::User {
+            retry_after: Duration::from_millis(1500),
+        };
+        assert_eq!(
+            exceeded.response_body(true),
+            json!({
+                "data": null,
+                "errors": [{
+                    "message": "Too many requests",
+                    "extensions": {
+                        "details": "Too many requests for this user",
+                        "retryAfterSeconds": 2,
+                    },
+                }],
+            })
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use actix_web::{
+    get, post,
+    web::{self, Data},
+    HttpResponse,
+};
+use service::{
+    service_provider::ServiceProvider,
+    settings::Settings,
+    sync::peer_sync::{serve_peer_certificate, serve_peer_pull, PeerSyncError},
+};
+use util::format_error;
+
+use crate::certs::CertificateReloader;
+
+pub fn config_peer_sync(cfg: &mut web::ServiceConfig) {
+    cfg.service(pull).service(certificate);
+}
+
+/// Transfer records for a peer on LAN (see `service::sync::peer_sync`), request and response
+/// are signed with the facility secret so the endpoint doesn't need user authentication
+#[post("/peer/sync/pull")]
+async fn pull(
+    body: String,
+    settings: Data<Settings>,
+    service_provider: Data<ServiceProvider>,
+) -> HttpResponse {
+    match serve_peer_pull(&service_provider, settings.peer_sync.as_ref(), &body) {
+        Ok(response) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(response),
+        Err(PeerSyncError::NotConfigured) => HttpResponse::NotFound().finish(),
+        Err(
+            error @ (PeerSyncError::InvalidSignature
+            | PeerSyncError::InvalidMessage(_)
+            | PeerSyncError::UnsupportedVersion(_)
+            | PeerSyncError::ExpiredMessage(_)
+            | PeerSyncError::ReplayedMessage),
+        ) => HttpResponse::Unauthorized().body(format_error(&error)),
+        Err(error) => HttpResponse::InternalServerError().body(format_error(&error)),
+    }
+}
+
+/// Signed fingerprint of the https certificate, peers pin it before sending pull requests
+#[get("/peer/sync/certificate")]
+async fn certificate(
+    settings: Data<Settings>,
+    certificate_reloader: Data<Option<CertificateReloader>>,
+) -> HttpResponse {
+    let Some(certificate_der) = certificate_reloader
+        .get_ref()
+        .as_ref()
+        .and_then(CertificateReloader::certificate_der)
+    else {
+        // Running on http
+        return HttpResponse::NotFound().finish();
+    };
+
+    match serve_peer_certificate(settings.peer_sync.as_ref(), &certificate_der) {
+        Ok(response) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(response),
+        Err(PeerSyncError::NotConfigured) => HttpResponse::NotFound().finish(),
+        Err(error) => HttpResponse::InternalServerError().body(format_error(&error)),
+    }
+}
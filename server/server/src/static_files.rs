@@ -71,11 +71,21 @@ async fn files(
     let service = StaticFileService::new(&settings.server.base_dir)
         .map_err(|err| InternalError::new(err, StatusCode::INTERNAL_SERVER_ERROR))?;
 
-    let static_file_category = StaticFileCategory::Temporary;
-    let file = service
-        .find_file(&query.id, static_file_category)
-        .map_err(|err| InternalError::new(err, StatusCode::INTERNAL_SERVER_ERROR))?
-        .ok_or_else(|| std::io::Error::new(ErrorKind::NotFound, "Static file not found"))?;
+    let mut file = None;
+    // Printed reports, scheduled reports are kept longer than temporary files
+    for static_file_category in [
+        StaticFileCategory::Temporary,
+        StaticFileCategory::ScheduledReport,
+    ] {
+        file = service
+            .find_file(&query.id, static_file_category)
+            .map_err(|err| InternalError::new(err, StatusCode::INTERNAL_SERVER_ERROR))?;
+        if file.is_some() {
+            break;
+        }
+    }
+    let file =
+        file.ok_or_else(|| std::io::Error::new(ErrorKind::NotFound, "Static file not found"))?;
 
     let response = fs::NamedFile::open(file.path)?
         .set_content_disposition(ContentDisposition {
@@ -0,0 +1,59 @@
+use actix_multipart::form::{tempfile::TempFile, MultipartForm};
+use actix_web::{web::Data, HttpRequest, HttpResponse};
+use service::{
+    auth_data::AuthData,
+    service_provider::ServiceProvider,
+    settings::{CertificateSettings, Settings},
+};
+
+use crate::certs::{save_certificate_authority, CertificateRenewalTrigger};
+
+#[derive(Debug, MultipartForm)]
+pub struct CertificateAuthorityForm {
+    /// CA certificate pem
+    ca_cert: TempFile,
+    /// CA private key pem
+    ca_key: TempFile,
+}
+
+/// Stores CA used to issue the server certificate in local CA mode, and re-issues
+/// the server certificate (new certificate is served without a restart)
+pub async fn upload_certificate_authority(
+    request: HttpRequest,
+    MultipartForm(form): MultipartForm<CertificateAuthorityForm>,
+    service_provider: Data<ServiceProvider>,
+    auth_data: Data<AuthData>,
+    settings: Data<Settings>,
+    certificate_renewal_trigger: Data<CertificateRenewalTrigger>,
+) -> HttpResponse {
+    use super::validate_request;
+
+    let auth_result = validate_request(request, &service_provider, &auth_data);
+    if auth_result.is_err() {
+        return HttpResponse::Unauthorized().body("Access Denied");
+    }
+
+    if !matches!(
+        settings.server.certificates,
+        Some(CertificateSettings::LocalCa(_))
+    ) {
+        return HttpResponse::BadRequest()
+            .body("Server is not configured to issue certificates from a local CA");
+    }
+
+    let ca_cert_pem = match std::fs::read_to_string(form.ca_cert.file.path()) {
+        Ok(pem) => pem,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid CA certificate: {}", e)),
+    };
+    let ca_key_pem = match std::fs::read_to_string(form.ca_key.file.path()) {
+        Ok(pem) => pem,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid CA private key: {}", e)),
+    };
+
+    if let Err(e) = save_certificate_authority(&settings.server, &ca_cert_pem, &ca_key_pem) {
+        return HttpResponse::BadRequest().body(format!("Error saving CA: {:#}", e));
+    }
+
+    certificate_renewal_trigger.trigger();
+    HttpResponse::Ok().body("Certificate authority uploaded, server certificate will be re-issued")
+}
@@ -12,9 +12,13 @@ use service::{
     settings::is_develop,
 };
 
+mod certificate_authority;
 mod database;
+mod sync_package;
+use certificate_authority::upload_certificate_authority;
 use database::get_database;
 use database::vacuum_database;
+use sync_package::{get_sync_package, upload_central_sync_package, upload_sync_package};
 
 const URL_PATH: &str = "/support";
 
@@ -27,6 +31,22 @@ pub fn config_support(cfg: &mut web::ServiceConfig) {
         &format!("{}{}", URL_PATH, "/vacuum"),
         web::post().to(vacuum_database),
     );
+    cfg.route(
+        &format!("{}{}", URL_PATH, "/certificate-authority"),
+        web::post().to(upload_certificate_authority),
+    );
+    cfg.route(
+        &format!("{}{}", URL_PATH, "/sync-package"),
+        web::get().to(get_sync_package),
+    );
+    cfg.route(
+        &format!("{}{}", URL_PATH, "/sync-package"),
+        web::post().to(upload_sync_package),
+    );
+    cfg.route(
+        &format!("{}{}", URL_PATH, "/central-sync-package"),
+        web::post().to(upload_central_sync_package),
+    );
 }
 
 fn validate_request(
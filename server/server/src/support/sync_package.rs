@@ -0,0 +1,151 @@
+use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
+use actix_web::{
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web::Data,
+    HttpRequest, HttpResponse,
+};
+use service::{
+    auth_data::AuthData,
+    service_provider::ServiceProvider,
+    sync::sync_package::{
+        export_central_sync_package, export_sync_package, import_sync_package,
+        ImportSyncPackageResult,
+    },
+};
+use util::format_error;
+
+#[derive(Debug, MultipartForm)]
+pub struct SyncPackageForm {
+    /// Central package produced for this site
+    package: TempFile,
+}
+
+#[derive(Debug, MultipartForm)]
+pub struct CentralSyncPackageForm {
+    /// Push package exported on the site
+    package: TempFile,
+    /// Sync password of the site
+    password: Text<String>,
+}
+
+/// Downloads sync package with records not yet acknowledged by central server (offline sync)
+pub async fn get_sync_package(
+    request: HttpRequest,
+    service_provider: Data<ServiceProvider>,
+    auth_data: Data<AuthData>,
+) -> HttpResponse {
+    use super::validate_request;
+
+    let auth_result = validate_request(request, &service_provider, &auth_data);
+    if auth_result.is_err() {
+        return HttpResponse::Unauthorized().body("Access Denied");
+    }
+
+    let result = service_provider
+        .basic_context()
+        .map_err(Into::into)
+        .and_then(|ctx| export_sync_package(&ctx, &service_provider));
+    let export = match result {
+        Ok(export) => export,
+        Err(e) => {
+            return HttpResponse::InternalServerError().body(format!(
+                "Error exporting sync package: {}",
+                format_error(&e)
+            ))
+        }
+    };
+
+    HttpResponse::Ok()
+        .content_type("application/gzip")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(export.file_name)],
+        })
+        .body(export.data)
+}
+
+/// Imports and integrates central sync package (offline sync)
+pub async fn upload_sync_package(
+    request: HttpRequest,
+    MultipartForm(form): MultipartForm<SyncPackageForm>,
+    service_provider: Data<ServiceProvider>,
+    auth_data: Data<AuthData>,
+) -> HttpResponse {
+    use super::validate_request;
+
+    let auth_result = validate_request(request, &service_provider, &auth_data);
+    if auth_result.is_err() {
+        return HttpResponse::Unauthorized().body("Access Denied");
+    }
+
+    let data = match std::fs::read(form.package.file.path()) {
+        Ok(data) => data,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid sync package: {}", e)),
+    };
+
+    let result = service_provider
+        .basic_context()
+        .map_err(Into::into)
+        .and_then(|ctx| import_sync_package(&ctx, &data));
+    match result {
+        Ok(ImportSyncPackageResult {
+            number_of_records,
+            push_cursor_advanced,
+        }) => HttpResponse::Ok().body(format!(
+            "Imported {} records{}",
+            number_of_records,
+            match push_cursor_advanced {
+                true => ", previously exported records were acknowledged",
+                false => "",
+            }
+        )),
+        Err(e) => HttpResponse::BadRequest().body(format!(
+            "Error importing sync package: {}",
+            format_error(&e)
+        )),
+    }
+}
+
+/// Pushes records of the site's push package to mSupply central server and downloads central
+/// package for the site (offline sync, on central server)
+pub async fn upload_central_sync_package(
+    request: HttpRequest,
+    MultipartForm(form): MultipartForm<CentralSyncPackageForm>,
+    service_provider: Data<ServiceProvider>,
+    auth_data: Data<AuthData>,
+) -> HttpResponse {
+    use super::validate_request;
+
+    let auth_result = validate_request(request, &service_provider, &auth_data);
+    if auth_result.is_err() {
+        return HttpResponse::Unauthorized().body("Access Denied");
+    }
+
+    let data = match std::fs::read(form.package.file.path()) {
+        Ok(data) => data,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid sync package: {}", e)),
+    };
+
+    let ctx = match service_provider.basic_context() {
+        Ok(ctx) => ctx,
+        Err(e) => return HttpResponse::InternalServerError().body(format_error(&e)),
+    };
+    let result = export_central_sync_package(&ctx, &service_provider, &data, &form.password).await;
+    let export = match result {
+        Ok(export) => export,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Error exporting central sync package: {}",
+                format_error(&e)
+            ))
+        }
+    };
+
+    HttpResponse::Ok()
+        .content_type("application/gzip")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(export.file_name)],
+        })
+        .body(export.data)
+}
@@ -2,7 +2,11 @@ use std::fmt::Display;
 
 use actix_multipart::form::{tempfile::TempFile, MultipartForm};
 use actix_web::{
-    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    http::header::{
+        ContentDisposition, DispositionParam, DispositionType, HeaderName, HeaderValue,
+        ACCEPT_ENCODING,
+    },
+    middleware::DefaultHeaders,
     post, put,
     web::{self, Data, Json},
     HttpRequest, Responder, ResponseError,
@@ -13,10 +17,11 @@ use service::{
     service_provider::ServiceProvider,
     settings::Settings,
     sync::{
+        api::SYNC_ACCEPT_ENCODING,
         api_v6::{
             SiteStatusRequestV6, SiteStatusResponseV6, SyncDownloadFileRequestV6, SyncParsedErrorV6, SyncPullRequestV6, SyncPullResponseV6,
             SyncPushRequestV6, SyncPushResponseV6, SyncUploadFileRequestV6,
-            SyncUploadFileResponseV6,
+            SyncUploadFileResponseV6, SYNC_FILE_SHA256_HEADER,
         },
         sync_on_central,
     },
@@ -26,6 +31,9 @@ pub fn config_sync_on_central(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("central")
             .wrap(central_server_only())
+            // Request bodies are decompressed by json extractor, this advertises encodings
+            // remote sites can use (responses are compressed by compress middleware)
+            .wrap(DefaultHeaders::new().add((ACCEPT_ENCODING, SYNC_ACCEPT_ENCODING)))
             .service(pull)
             .service(push)
             .service(site_status)
@@ -94,16 +102,23 @@ async fn download_file(
     settings: Data<Settings>,
 ) -> actix_web::Result<impl Responder> {
     log::info!("Sending a file via sync");
-    let (file, file_description) = sync_on_central::download_file(&settings, request.into_inner())
-        .await
-        .map_err(ToResponseError)?;
+    let (file, file_description, sha256) =
+        sync_on_central::download_file(&settings, request.into_inner())
+            .await
+            .map_err(ToResponseError)?;
 
-    let response = file
+    // Range header is handled by NamedFile, for resuming downloads
+    let mut response = file
         .set_content_disposition(ContentDisposition {
             disposition: DispositionType::Inline,
             parameters: vec![DispositionParam::Filename(file_description.name)],
         })
         .into_response(&req);
+    if let Ok(sha256) = HeaderValue::from_str(&sha256) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(SYNC_FILE_SHA256_HEADER), sha256);
+    }
 
     Ok(response)
 }
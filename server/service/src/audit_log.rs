@@ -0,0 +1,346 @@
+use std::{fs, io, path::Path};
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use repository::{
+    audit_log::{AuditLog, AuditLogFilter, AuditLogRepository, AuditLogSort},
+    AuditLogCaptureRow, AuditLogCaptureRowRepository, AuditLogRow, AuditLogRowRepository,
+    ChangelogHook, ChangelogRow, KeyType, KeyValueStoreRepository, PaginationOption,
+    RepositoryError, RowActionType, StorageConnection, StorageConnectionManager,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::Sha256;
+use thiserror::Error;
+use util::uuid::uuid;
+
+use crate::{
+    service_provider::ServiceProvider,
+    sync::translations::{translate_changelogs_to_sync_records, ToSyncRecordTranslationType},
+};
+
+use super::{get_default_pagination, i64_to_u32, ListError, ListResult};
+
+pub const MAX_LIMIT: u32 = 1000;
+pub const MIN_LIMIT: u32 = 1;
+
+/// `previous_hash` of the first entry in the chain
+pub const AUDIT_LOG_GENESIS_HASH: &str = "";
+
+const VERIFY_BATCH_SIZE: u32 = 500;
+/// Hashes are keyed with the key in this file of the app data folder, outside of the database,
+/// so that the chain can't be recalculated with access to the database only
+const AUDIT_LOG_KEY_FILE: &str = "audit_log.key";
+
+#[derive(Error, Debug)]
+pub enum AuditLogError {
+    #[error(transparent)]
+    DatabaseError(#[from] RepositoryError),
+    #[error("Failed to read or create the audit log key")]
+    KeyError(#[from] io::Error),
+    #[error("Invalid audit log head")]
+    InvalidHead(#[from] serde_json::Error),
+}
+
+/// Number of entries and hash of the last entry of the chain, signed with the audit log key and
+/// updated by the audit log processor with every entry. Deleting the newest entries leaves a
+/// chain that doesn't end at the head
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct AuditLogHead {
+    pub(crate) count: u64,
+    pub(crate) hash: String,
+    signature: String,
+}
+
+impl AuditLogHead {
+    pub(crate) fn new(key: &[u8], count: u64, hash: String) -> AuditLogHead {
+        AuditLogHead {
+            signature: hmac_sha256(key, &format!("{}:{}", count, hash)),
+            count,
+            hash,
+        }
+    }
+
+    pub(crate) fn is_signed_with(&self, key: &[u8]) -> bool {
+        self.signature == hmac_sha256(key, &format!("{}:{}", self.count, self.hash))
+    }
+
+    pub(crate) fn load(connection: &StorageConnection) -> Result<Option<Self>, AuditLogError> {
+        let head = KeyValueStoreRepository::new(connection).get_string(KeyType::AuditLogHead)?;
+        Ok(head.map(|head| serde_json::from_str(&head)).transpose()?)
+    }
+
+    pub(crate) fn save(&self, connection: &StorageConnection) -> Result<(), AuditLogError> {
+        KeyValueStoreRepository::new(connection)
+            .set_string(KeyType::AuditLogHead, Some(serde_json::to_string(self)?))?;
+        Ok(())
+    }
+}
+
+/// Key of the audit log hashes, created on first use
+pub(crate) fn audit_log_key(service_provider: &ServiceProvider) -> Result<Vec<u8>, io::Error> {
+    let directory = service_provider.app_data_service.get_app_data_directory()?;
+    let path = directory.join(AUDIT_LOG_KEY_FILE);
+    if !path.exists() {
+        // Linked from a complete temporary file, so that concurrent callers end up with one key
+        let temp_path = directory.join(format!("{}.{}", AUDIT_LOG_KEY_FILE, uuid()));
+        write_private_file(&temp_path, &hex::encode(rand::random::<[u8; 32]>()))?;
+        let result = fs::hard_link(&temp_path, &path);
+        fs::remove_file(&temp_path)?;
+        match result {
+            Err(error) if error.kind() != io::ErrorKind::AlreadyExists => return Err(error),
+            _ => {}
+        }
+    }
+
+    hex::decode(fs::read_to_string(&path)?.trim())
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Creates the file, readable by the owner only on unix
+fn write_private_file(path: &Path, content: &str) -> Result<(), io::Error> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(content.as_bytes())
+}
+
+fn hmac_sha256(key: &[u8], content: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(content.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+pub fn get_audit_logs(
+    connection_manager: &StorageConnectionManager,
+    pagination: Option<PaginationOption>,
+    filter: Option<AuditLogFilter>,
+    sort: Option<AuditLogSort>,
+) -> Result<ListResult<AuditLog>, ListError> {
+    let pagination = get_default_pagination(pagination, MAX_LIMIT, MIN_LIMIT)?;
+    let connection = connection_manager.connection()?;
+    let repository = AuditLogRepository::new(&connection);
+
+    Ok(ListResult {
+        rows: repository.query(pagination, filter.clone(), sort)?,
+        count: i64_to_u32(repository.count(filter)?),
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AuditLogVerification {
+    pub checked_count: u32,
+    /// First entry that doesn't match its hash or doesn't link to the previous entry
+    pub first_invalid: Option<AuditLogRow>,
+    /// Chain ends at the signed head, false when the newest entries were deleted or the head was
+    /// changed
+    pub matches_head: bool,
+}
+
+impl AuditLogVerification {
+    pub fn is_valid(&self) -> bool {
+        self.first_invalid.is_none() && self.matches_head
+    }
+}
+
+/// Walks the whole audit log in chain order, recalculating every hash, and checks that the chain
+/// ends at the signed head
+pub fn verify_audit_log(
+    service_provider: &ServiceProvider,
+) -> Result<AuditLogVerification, AuditLogError> {
+    let key = audit_log_key(service_provider)?;
+    let connection = service_provider.connection()?;
+    let repository = AuditLogRowRepository::new(&connection);
+
+    let mut previous_hash = AUDIT_LOG_GENESIS_HASH.to_string();
+    let mut after_cursor = -1;
+    let mut checked_count = 0;
+
+    loop {
+        let rows = repository.find_chain(after_cursor, VERIFY_BATCH_SIZE)?;
+        if rows.is_empty() {
+            break;
+        }
+
+        for row in rows {
+            if row.previous_hash != previous_hash || row.hash != audit_log_hash(&key, &row) {
+                return Ok(AuditLogVerification {
+                    checked_count,
+                    first_invalid: Some(row),
+                    matches_head: false,
+                });
+            }
+            checked_count += 1;
+            after_cursor = row.changelog_cursor;
+            previous_hash = row.hash;
+        }
+    }
+
+    let matches_head = match AuditLogHead::load(&connection)? {
+        Some(head) => {
+            head.is_signed_with(&key)
+                && head.count == checked_count as u64
+                && head.hash == previous_hash
+        }
+        None => checked_count == 0,
+    };
+
+    Ok(AuditLogVerification {
+        checked_count,
+        first_invalid: None,
+        matches_head,
+    })
+}
+
+/// Captures the state of the changed records and the acting user in the transaction of the change,
+/// set on the connection of every `ServiceContext`. Changes made outside of a transaction or
+/// without a `ServiceContext` (e.g. sync integration) are audited from the changelog by the audit
+/// log processor without an acting user
+pub(crate) struct AuditLogCapture {
+    pub(crate) user_id: Option<String>,
+}
+
+impl ChangelogHook for AuditLogCapture {
+    fn before_commit(
+        &self,
+        connection: &StorageConnection,
+        changelogs: Vec<ChangelogRow>,
+    ) -> Result<(), RepositoryError> {
+        let repo = AuditLogCaptureRowRepository::new(connection);
+        let datetime = Utc::now().naive_utc();
+
+        for changelog in changelogs {
+            let record_snapshot = match changelog.row_action {
+                RowActionType::Upsert => record_snapshot(connection, &changelog),
+                RowActionType::Delete => None,
+            };
+            repo.insert_one(&AuditLogCaptureRow {
+                changelog_cursor: changelog.cursor,
+                user_id: self.user_id.clone(),
+                datetime,
+                record_snapshot: record_snapshot.map(|snapshot| snapshot.to_string()),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialises the current state of a changelog record with the sync translators, this way every
+/// table that goes through the changelog is audited without table specific code.
+/// Legacy tables are serialised in legacy (mSupply central) format, other tables in omSupply format
+pub(crate) fn record_snapshot(
+    connection: &StorageConnection,
+    changelog: &ChangelogRow,
+) -> Option<Value> {
+    for r#type in [
+        ToSyncRecordTranslationType::PushToLegacyCentral,
+        ToSyncRecordTranslationType::PullFromOmSupplyCentral,
+    ] {
+        let records =
+            match translate_changelogs_to_sync_records(connection, vec![changelog.clone()], r#type)
+            {
+                Ok(records) => records,
+                Err(error) => {
+                    log::warn!("Audit log could not serialise record: {}", error);
+                    return None;
+                }
+            };
+
+        if let Some(record) = records.into_iter().next() {
+            return Some(record.record.record_data);
+        }
+    }
+
+    None
+}
+
+/// Keyed hash of all audited content of the row, chained with `previous_hash`
+pub(crate) fn audit_log_hash(key: &[u8], row: &AuditLogRow) -> String {
+    let AuditLogRow {
+        id,
+        changelog_cursor,
+        table_name,
+        record_id,
+        row_action,
+        store_id,
+        user_id,
+        datetime,
+        changed_fields,
+        record_snapshot,
+        previous_hash,
+        hash: _,
+    } = row;
+
+    let content = json!([
+        previous_hash,
+        id,
+        changelog_cursor,
+        format!("{:?}", table_name),
+        record_id,
+        format!("{:?}", row_action),
+        store_id,
+        user_id,
+        datetime.format("%Y-%m-%dT%H:%M:%S%.f").to_string(),
+        changed_fields,
+        record_snapshot,
+    ]);
+
+    hmac_sha256(key, &content.to_string())
+}
+
+/// Field level difference between two record snapshots, in the shape of:
+/// `{ "field": { "from": .., "to": .. } }`.
+/// Returns None if nothing changed
+pub(crate) fn changed_fields(before: Option<&Value>, after: Option<&Value>) -> Option<Value> {
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut result = Map::new();
+    for (field, from) in before.iter() {
+        let to = after.get(field).unwrap_or(&Value::Null);
+        if from != to {
+            result.insert(field.clone(), json!({ "from": from, "to": to }));
+        }
+    }
+    for (field, to) in after.iter() {
+        if !before.contains_key(field) && !to.is_null() {
+            result.insert(field.clone(), json!({ "from": Value::Null, "to": to }));
+        }
+    }
+
+    (!result.is_empty()).then(|| Value::Object(result))
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::changed_fields;
+
+    #[test]
+    fn audit_log_changed_fields() {
+        let before = json!({ "id": "1", "comment": "a", "status": "NEW", "on_hold": false });
+        let after = json!({ "id": "1", "comment": "b", "status": "NEW", "note": "new" });
+
+        assert_eq!(
+            changed_fields(Some(&before), Some(&after)),
+            Some(json!({
+                "comment": { "from": "a", "to": "b" },
+                "on_hold": { "from": false, "to": null },
+                "note": { "from": null, "to": "new" }
+            }))
+        );
+
+        assert_eq!(changed_fields(Some(&before), Some(&before)), None);
+
+        // Insert
+        assert_eq!(
+            changed_fields(None, Some(&json!({ "id": "1" }))),
+            Some(json!({ "id": { "from": null, "to": "1" } }))
+        );
+    }
+}
@@ -11,6 +11,7 @@ pub mod apis;
 pub mod app_data;
 
 pub mod asset;
+pub mod audit_log;
 pub mod auth;
 pub mod auth_data;
 pub mod barcode;
@@ -31,9 +32,12 @@ pub mod item_stats;
 pub mod label_printer_settings_service;
 pub mod ledger;
 pub mod location;
+pub mod log_context;
 pub mod log_service;
 pub mod login;
+pub mod mail;
 pub mod master_list;
+pub mod metrics;
 pub mod missing_program;
 pub mod name;
 pub mod number;
@@ -275,6 +279,18 @@ pub fn usize_to_u64(num: usize) -> u64 {
     num.try_into().unwrap_or(0)
 }
 
+pub fn i32_to_u64(num: i32) -> u64 {
+    num.try_into().unwrap_or(0)
+}
+
+pub fn u64_to_i32(num: u64) -> i32 {
+    num.try_into().unwrap_or(0)
+}
+
+pub fn u64_to_i64(num: u64) -> i64 {
+    num.try_into().unwrap_or(0)
+}
+
 #[derive(Debug, PartialEq)]
 pub struct InputWithResult<I, R> {
     pub input: I,
@@ -0,0 +1,117 @@
+use std::{cell::RefCell, future::Future};
+
+use serde::Serialize;
+use util::uuid::uuid;
+
+tokio::task_local! {
+    static LOG_CONTEXT: RefCell<LogContext>;
+}
+
+/// Context added to structured (json) log entries, so that all log entries of one
+/// graphql request, sync run or processor run can be found by `correlation_id`.
+///
+/// Context is task local, it's available to all code (sync or async) running within
+/// `scope` or `sync_scope` of the current task
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct LogContext {
+    pub correlation_id: String,
+    /// Correlation id of the request or run that caused this one,
+    /// e.g. mutation that triggered transfer processors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub triggered_by: Option<String>,
+    /// e.g. graphql operation name, `sync` or processor name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    /// Current `SyncStep` of a sync run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store_id: Option<String>,
+}
+
+impl LogContext {
+    pub fn new(operation: &str) -> Self {
+        LogContext {
+            correlation_id: uuid(),
+            operation: Some(operation.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Uses provided correlation id (e.g. from request header) if present
+    pub fn correlation_id(mut self, correlation_id: Option<String>) -> Self {
+        if let Some(correlation_id) = correlation_id {
+            self.correlation_id = correlation_id;
+        }
+        self
+    }
+
+    pub fn triggered_by(mut self, triggered_by: Option<String>) -> Self {
+        self.triggered_by = triggered_by;
+        self
+    }
+
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        LOG_CONTEXT.scope(RefCell::new(self), future).await
+    }
+
+    pub fn sync_scope<R>(self, f: impl FnOnce() -> R) -> R {
+        LOG_CONTEXT.sync_scope(RefCell::new(self), f)
+    }
+
+    /// None when called outside of a scope
+    pub fn current() -> Option<LogContext> {
+        LOG_CONTEXT
+            .try_with(|context| context.borrow().clone())
+            .ok()
+    }
+
+    pub fn current_correlation_id() -> Option<String> {
+        LOG_CONTEXT
+            .try_with(|context| context.borrow().correlation_id.clone())
+            .ok()
+    }
+
+    /// Updates context of the current scope, does nothing outside of a scope
+    pub fn update(f: impl FnOnce(&mut LogContext)) {
+        let _ = LOG_CONTEXT.try_with(|context| f(&mut context.borrow_mut()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LogContext;
+
+    #[actix_rt::test]
+    async fn log_context() {
+        assert_eq!(LogContext::current(), None);
+        // Update outside of scope is ignored
+        LogContext::update(|context| context.user_id = Some("user".to_string()));
+
+        let context = LogContext::new("request").correlation_id(Some("id".to_string()));
+        context
+            .scope(async {
+                LogContext::update(|context| context.user_id = Some("user".to_string()));
+                tokio::task::yield_now().await;
+
+                let current = LogContext::current().unwrap();
+                assert_eq!(current.correlation_id, "id");
+                assert_eq!(current.operation, Some("request".to_string()));
+                assert_eq!(current.user_id, Some("user".to_string()));
+
+                // Nested scope, e.g. processor run triggered by request
+                LogContext::new("processor")
+                    .triggered_by(LogContext::current_correlation_id())
+                    .sync_scope(|| {
+                        let current = LogContext::current().unwrap();
+                        assert_eq!(current.triggered_by, Some("id".to_string()));
+                        assert_eq!(current.user_id, None);
+                    });
+            })
+            .await;
+
+        assert_eq!(LogContext::current(), None);
+    }
+}
@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use lettre::{
+    message::{header::ContentType, Attachment, MultiPart, SinglePart},
+    transport::smtp::authentication::Credentials,
+    Message, SmtpTransport, Transport,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum MailSecurity {
+    /// Plain connection, e.g. for a local relay or test server
+    None,
+    #[default]
+    StartTls,
+    Tls,
+}
+
+/// SMTP server used to send emails, e.g. scheduled reports
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct MailSettings {
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub security: MailSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender address, e.g. `omSupply <reports@example.com>`
+    pub from: String,
+}
+
+pub struct MailAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Error, Debug)]
+pub enum MailError {
+    #[error("No recipients")]
+    NoRecipients,
+    #[error("Invalid address {0}")]
+    InvalidAddress(String),
+    #[error("Invalid content type {0}")]
+    InvalidContentType(String),
+    #[error(transparent)]
+    MessageError(#[from] lettre::error::Error),
+    #[error(transparent)]
+    SmtpError(#[from] lettre::transport::smtp::Error),
+}
+
+pub fn send_mail(
+    settings: &MailSettings,
+    recipients: &[String],
+    subject: &str,
+    body: &str,
+    attachment: Option<MailAttachment>,
+) -> Result<(), MailError> {
+    if recipients.is_empty() {
+        return Err(MailError::NoRecipients);
+    }
+
+    let mut builder = Message::builder()
+        .from(
+            settings
+                .from
+                .parse()
+                .map_err(|_| MailError::InvalidAddress(settings.from.clone()))?,
+        )
+        .subject(subject);
+    for recipient in recipients {
+        builder = builder.to(recipient
+            .parse()
+            .map_err(|_| MailError::InvalidAddress(recipient.clone()))?);
+    }
+
+    let mut content = MultiPart::mixed().singlepart(SinglePart::plain(body.to_string()));
+    if let Some(MailAttachment {
+        file_name,
+        content_type,
+        data,
+    }) = attachment
+    {
+        let content_type = ContentType::parse(&content_type)
+            .map_err(|_| MailError::InvalidContentType(content_type.clone()))?;
+        content = content.singlepart(Attachment::new(file_name).body(data, content_type));
+    }
+    let message = builder.multipart(content)?;
+
+    let mut transport = match settings.security {
+        MailSecurity::None => SmtpTransport::builder_dangerous(&settings.host),
+        MailSecurity::StartTls => SmtpTransport::starttls_relay(&settings.host)?,
+        MailSecurity::Tls => SmtpTransport::relay(&settings.host)?,
+    }
+    .port(settings.port)
+    .timeout(Some(SMTP_TIMEOUT));
+    if let Some(username) = &settings.username {
+        transport = transport.credentials(Credentials::new(
+            username.clone(),
+            settings.password.clone().unwrap_or_default(),
+        ));
+    }
+
+    transport.build().send(&message)?;
+    Ok(())
+}
+
+/// Minimal SMTP server that accepts every message, for testing mail delivery
+#[cfg(test)]
+pub(crate) mod test_smtp {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::mpsc::{self, Receiver},
+        thread,
+    };
+
+    use super::{MailSecurity, MailSettings};
+
+    /// Returns settings to connect to the server and a receiver for the DATA of received messages
+    pub(crate) fn start_test_smtp_server() -> (MailSettings, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { return };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut reply = |line: &str| stream.write_all(format!("{line}\r\n").as_bytes());
+                reply("220 localhost test smtp").unwrap();
+
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                    let command = line.trim_end().to_uppercase();
+                    if command.starts_with("DATA") {
+                        reply("354 End data with <CR><LF>.<CR><LF>").unwrap();
+                        let mut data = String::new();
+                        loop {
+                            let mut data_line = String::new();
+                            if reader.read_line(&mut data_line).unwrap_or(0) == 0
+                                || data_line == ".\r\n"
+                            {
+                                break;
+                            }
+                            data.push_str(&data_line);
+                        }
+                        let _ = sender.send(data);
+                        reply("250 OK").unwrap();
+                    } else if command.starts_with("QUIT") {
+                        let _ = reply("221 Bye");
+                        break;
+                    } else {
+                        // EHLO, MAIL FROM, RCPT TO, RSET, NOOP
+                        reply("250 OK").unwrap();
+                    }
+                    line.clear();
+                }
+            }
+        });
+
+        let settings = MailSettings {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: MailSecurity::None,
+            username: None,
+            password: None,
+            from: "omSupply <reports@example.com>".to_string(),
+        };
+        (settings, receiver)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{send_mail, test_smtp::start_test_smtp_server, MailAttachment, MailError};
+
+    #[test]
+    fn test_send_mail() {
+        let (settings, receiver) = start_test_smtp_server();
+
+        send_mail(
+            &settings,
+            &["manager@example.com".to_string()],
+            "Weekly expiry report",
+            "Report attached",
+            Some(MailAttachment {
+                file_name: "expiry.csv".to_string(),
+                content_type: "text/csv".to_string(),
+                data: b"Item,Expiry\r\n".to_vec(),
+            }),
+        )
+        .unwrap();
+
+        let data = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(data.contains("To: manager@example.com"));
+        assert!(data.contains("Subject: Weekly expiry report"));
+        assert!(data.contains("filename=\"expiry.csv\""));
+
+        assert!(matches!(
+            send_mail(&settings, &[], "subject", "body", None),
+            Err(MailError::NoRecipients)
+        ));
+        assert!(matches!(
+            send_mail(
+                &settings,
+                &["not an address".to_string()],
+                "subject",
+                "body",
+                None
+            ),
+            Err(MailError::InvalidAddress(_))
+        ));
+    }
+}
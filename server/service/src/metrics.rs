@@ -0,0 +1,433 @@
+use std::{collections::HashSet, sync::Mutex, time::Duration};
+
+use chrono::NaiveDateTime;
+use prometheus::{
+    Encoder, Gauge, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec,
+    Opts, Registry, TextEncoder,
+};
+
+use crate::{
+    service_provider::ServiceProvider,
+    sync::sync_status::status::{FullSyncStatus, SyncStatus, SyncStatusWithProgress},
+    u64_to_i64,
+};
+
+const NAMESPACE: &str = "omsupply";
+/// Operation names are chosen by clients, requests with new operation names are counted as
+/// `other` once this many operations have been seen
+const MAX_GRAPHQL_OPERATIONS: usize = 500;
+const MAX_GRAPHQL_OPERATION_NAME_LENGTH: usize = 100;
+
+/// Prometheus metrics of the server, request metrics are recorded as they happen, while
+/// database, processor and sync metrics are read when metrics are exported
+pub struct Metrics {
+    registry: Registry,
+    graphql_operations: Mutex<HashSet<String>>,
+    graphql_requests: IntCounterVec,
+    graphql_request_duration: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    processor_queue_length: IntGaugeVec,
+    changelog_backlog: IntGauge,
+    last_successful_sync: Gauge,
+    sync_duration: Gauge,
+    sync_step_duration: GaugeVec,
+    sync_step_records: IntGaugeVec,
+    sync_step_transferred_bytes: IntGaugeVec,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let opts = |name: &str, help: &str| Opts::new(name, help).namespace(NAMESPACE);
+
+        let graphql_requests = IntCounterVec::new(
+            opts("graphql_requests_total", "GraphQL requests by operation"),
+            &["operation", "status"],
+        )
+        .unwrap();
+        let graphql_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "graphql_request_duration_seconds",
+                "GraphQL request duration by operation",
+            )
+            .namespace(NAMESPACE),
+            &["operation"],
+        )
+        .unwrap();
+        let db_pool_connections = IntGaugeVec::new(
+            opts(
+                "db_pool_connections",
+                "Database connection pool connections by state (max, open, idle, in_use)",
+            ),
+            &["state"],
+        )
+        .unwrap();
+        let processor_queue_length = IntGaugeVec::new(
+            opts(
+                "processor_queue_length",
+                "Triggers waiting to be handled by processor",
+            ),
+            &["processor"],
+        )
+        .unwrap();
+        let changelog_backlog = IntGauge::with_opts(opts(
+            "changelog_backlog",
+            "Changelog records waiting to be pushed to central server",
+        ))
+        .unwrap();
+        let last_successful_sync = Gauge::with_opts(opts(
+            "last_successful_sync_timestamp_seconds",
+            "Unix time when last successful sync finished",
+        ))
+        .unwrap();
+        let sync_duration = Gauge::with_opts(opts(
+            "last_successful_sync_duration_seconds",
+            "Duration of last successful sync",
+        ))
+        .unwrap();
+        let sync_step_duration = GaugeVec::new(
+            opts(
+                "last_successful_sync_step_duration_seconds",
+                "Duration of sync steps in last successful sync",
+            ),
+            &["step"],
+        )
+        .unwrap();
+        let sync_step_records = IntGaugeVec::new(
+            opts(
+                "last_successful_sync_step_records",
+                "Records processed by sync steps in last successful sync",
+            ),
+            &["step"],
+        )
+        .unwrap();
+
+        let sync_step_transferred_bytes = IntGaugeVec::new(
+            opts(
+                "last_successful_sync_step_transferred_bytes",
+                "Request and response body bytes sent over the network by sync steps in last successful sync",
+            ),
+            &["step"],
+        )
+        .unwrap();
+
+        let registry = Registry::new();
+        registry
+            .register(Box::new(graphql_requests.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(graphql_request_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(db_pool_connections.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(processor_queue_length.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(changelog_backlog.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(last_successful_sync.clone()))
+            .unwrap();
+        registry.register(Box::new(sync_duration.clone())).unwrap();
+        registry
+            .register(Box::new(sync_step_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(sync_step_records.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(sync_step_transferred_bytes.clone()))
+            .unwrap();
+
+        Metrics {
+            registry,
+            graphql_operations: Mutex::new(HashSet::new()),
+            graphql_requests,
+            graphql_request_duration,
+            db_pool_connections,
+            processor_queue_length,
+            changelog_backlog,
+            last_successful_sync,
+            sync_duration,
+            sync_step_duration,
+            sync_step_records,
+            sync_step_transferred_bytes,
+        }
+    }
+
+    pub fn record_graphql_request(
+        &self,
+        operation_name: Option<&str>,
+        duration: Duration,
+        is_ok: bool,
+    ) {
+        let operation = self.graphql_operation_label(operation_name);
+        let status = if is_ok { "ok" } else { "error" };
+
+        self.graphql_requests
+            .with_label_values(&[&operation, status])
+            .inc();
+        self.graphql_request_duration
+            .with_label_values(&[&operation])
+            .observe(duration.as_secs_f64());
+    }
+
+    fn graphql_operation_label(&self, operation_name: Option<&str>) -> String {
+        let Some(operation_name) = operation_name else {
+            return "unnamed".to_string();
+        };
+        let is_valid = !operation_name.is_empty()
+            && operation_name.len() <= MAX_GRAPHQL_OPERATION_NAME_LENGTH
+            && operation_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_valid {
+            return "other".to_string();
+        }
+
+        let mut operations = self.graphql_operations.lock().unwrap();
+        if operations.contains(operation_name) {
+            return operation_name.to_string();
+        }
+        if operations.len() >= MAX_GRAPHQL_OPERATIONS {
+            return "other".to_string();
+        }
+        operations.insert(operation_name.to_string());
+        operation_name.to_string()
+    }
+
+    /// Updates metrics that are read from database and processors, and returns all metrics
+    /// in prometheus text format
+    pub fn export(&self, service_provider: &ServiceProvider) -> Result<String, prometheus::Error> {
+        self.update(service_provider);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        // Text encoder only writes valid utf8
+        Ok(String::from_utf8(buffer).unwrap_or_default())
+    }
+
+    fn update(&self, service_provider: &ServiceProvider) {
+        let pool_state = service_provider.connection_manager.pool_state();
+        for (state, value) in [
+            ("max", pool_state.max_size),
+            ("open", pool_state.connections),
+            ("idle", pool_state.idle_connections),
+            (
+                "in_use",
+                pool_state.connections - pool_state.idle_connections,
+            ),
+        ] {
+            self.db_pool_connections
+                .with_label_values(&[state])
+                .set(value.into());
+        }
+
+        for (processor, length) in service_provider.processors_trigger.queue_lengths() {
+            self.processor_queue_length
+                .with_label_values(&[processor])
+                .set(length as i64);
+        }
+
+        let ctx = match service_provider.basic_context() {
+            Ok(ctx) => ctx,
+            Err(error) => {
+                log::error!("Cannot read metrics from database: {:?}", error);
+                return;
+            }
+        };
+
+        // Fails when site is not initialised yet
+        match service_provider
+            .sync_status_service
+            .number_of_records_in_push_queue(&ctx)
+        {
+            Ok(backlog) => self.changelog_backlog.set(backlog as i64),
+            Err(error) => log::debug!("Cannot read changelog backlog: {:?}", error),
+        }
+
+        match service_provider
+            .sync_status_service
+            .get_latest_successful_sync_status(&ctx)
+        {
+            Ok(Some(sync_status)) => self.update_sync(&sync_status),
+            Ok(None) => {}
+            Err(error) => log::error!("Cannot read last successful sync: {:?}", error),
+        }
+    }
+
+    fn update_sync(&self, sync_status: &FullSyncStatus) {
+        let SyncStatus { started, finished } = &sync_status.summary;
+        if let Some(finished) = finished {
+            self.last_successful_sync
+                .set(finished.and_utc().timestamp_millis() as f64 / 1000.0);
+            self.sync_duration.set(duration_seconds(started, finished));
+        }
+
+        // Steps not in the last sync (e.g. v6 steps) are removed
+        self.sync_step_duration.reset();
+        self.sync_step_records.reset();
+        self.sync_step_transferred_bytes.reset();
+
+        // Labels match SyncStep names used in logs
+        if let Some(SyncStatus {
+            started,
+            finished: Some(finished),
+        }) = &sync_status.prepare_initial
+        {
+            self.sync_step_duration
+                .with_label_values(&["PrepareInitial"])
+                .set(duration_seconds(started, finished));
+        }
+
+        for (step, status) in [
+            ("Push", &sync_status.push),
+            ("PullCentral", &sync_status.pull_central),
+            ("PullRemote", &sync_status.pull_remote),
+            ("PullCentralV6", &sync_status.pull_v6),
+            ("PushCentralV6", &sync_status.push_v6),
+            ("Integrate", &sync_status.integration),
+        ] {
+            let Some(SyncStatusWithProgress {
+                started,
+                finished,
+                done,
+                transferred_bytes,
+                ..
+            }) = status
+            else {
+                continue;
+            };
+            if let Some(finished) = finished {
+                self.sync_step_duration
+                    .with_label_values(&[step])
+                    .set(duration_seconds(started, finished));
+            }
+            self.sync_step_records
+                .with_label_values(&[step])
+                .set(done.unwrap_or(0).into());
+            if let Some(transferred_bytes) = transferred_bytes {
+                self.sync_step_transferred_bytes
+                    .with_label_values(&[step])
+                    .set(u64_to_i64(*transferred_bytes));
+            }
+        }
+    }
+}
+
+fn duration_seconds(started: &NaiveDateTime, finished: &NaiveDateTime) -> f64 {
+    (*finished - *started).num_milliseconds() as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use chrono::NaiveDate;
+
+    use crate::sync::sync_status::status::{FullSyncStatus, SyncStatus, SyncStatusWithProgress};
+
+    use super::{Metrics, MAX_GRAPHQL_OPERATIONS};
+
+    #[test]
+    fn metrics_graphql_operation_label() {
+        let metrics = Metrics::new();
+
+        assert_eq!(metrics.graphql_operation_label(None), "unnamed");
+        assert_eq!(
+            metrics.graphql_operation_label(Some("invoices")),
+            "invoices"
+        );
+        assert_eq!(
+            metrics.graphql_operation_label(Some("invoices { id }")),
+            "other"
+        );
+
+        for index in 0..MAX_GRAPHQL_OPERATIONS {
+            metrics.graphql_operation_label(Some(&format!("operation{}", index)));
+        }
+        // Already seen operation is still labelled
+        assert_eq!(
+            metrics.graphql_operation_label(Some("invoices")),
+            "invoices"
+        );
+        assert_eq!(
+            metrics.graphql_operation_label(Some("newOperation")),
+            "other"
+        );
+
+        metrics.record_graphql_request(Some("invoices"), Duration::from_millis(20), true);
+        metrics.record_graphql_request(Some("invoices"), Duration::from_millis(40), false);
+        assert_eq!(
+            metrics
+                .graphql_requests
+                .with_label_values(&["invoices", "ok"])
+                .get(),
+            1
+        );
+        assert_eq!(
+            metrics
+                .graphql_request_duration
+                .with_label_values(&["invoices"])
+                .get_sample_count(),
+            2
+        );
+    }
+
+    #[test]
+    fn metrics_update_sync() {
+        let metrics = Metrics::new();
+        let datetime = |seconds| {
+            NaiveDate::from_ymd_opt(2024, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, seconds)
+                .unwrap()
+        };
+
+        metrics.update_sync(&FullSyncStatus {
+            summary: SyncStatus {
+                started: datetime(0),
+                finished: Some(datetime(30)),
+            },
+            push: Some(SyncStatusWithProgress {
+                started: datetime(1),
+                finished: Some(datetime(11)),
+                total: Some(10),
+                done: Some(10),
+                transferred_bytes: Some(2048),
+            }),
+            ..Default::default()
+        });
+
+        assert_eq!(metrics.sync_duration.get(), 30.0);
+        assert_eq!(
+            metrics.last_successful_sync.get(),
+            datetime(30).and_utc().timestamp() as f64
+        );
+        assert_eq!(
+            metrics
+                .sync_step_duration
+                .with_label_values(&["Push"])
+                .get(),
+            10.0
+        );
+        assert_eq!(
+            metrics.sync_step_records.with_label_values(&["Push"]).get(),
+            10
+        );
+        assert_eq!(
+            metrics
+                .sync_step_transferred_bytes
+                .with_label_values(&["Push"])
+                .get(),
+            2048
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use anyhow::Result;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::str::FromStr;
+use std::time::Duration;
+
+const PRINTER_CONNECTION_TIMEOUT: Duration = Duration::new(5, 0);
+const PRINTER_WRITE_TIMEOUT: Duration = Duration::new(10, 0);
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+const LF: u8 = 0x0a;
+/// WPC1252, matches unicode for all characters up to U+00FF except 0x80 - 0x9F
+const CODE_PAGE_WPC1252: u8 = 16;
+
+/// ESC/POS receipt printer, usually a thermal printer
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct ReceiptPrinterSettings {
+    pub target: ReceiptPrinterTarget,
+    /// Characters per line of the default font, e.g. 48 for 80mm and 32 for 58mm paper
+    #[serde(default = "default_line_width")]
+    pub line_width: usize,
+}
+
+fn default_line_width() -> usize {
+    48
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+pub enum ReceiptPrinterTarget {
+    /// Raw TCP, usually port 9100
+    Network { address: String, port: u16 },
+    /// Device or file the receipts are written to, e.g. `/dev/usb/lp0`, `/dev/ttyUSB0` or `COM3`.
+    /// Serial ports need to be configured (baud rate etc.) by the OS.
+    Device(String),
+}
+
+impl ReceiptPrinterTarget {
+    pub fn send(&self, data: &[u8]) -> Result<()> {
+        match self {
+            ReceiptPrinterTarget::Network { address, port } => {
+                let ip_addr = IpAddr::from_str(address)?;
+                let socket = SocketAddr::new(ip_addr, *port);
+                let mut stream = TcpStream::connect_timeout(&socket, PRINTER_CONNECTION_TIMEOUT)?;
+                stream.set_write_timeout(Some(PRINTER_WRITE_TIMEOUT))?;
+                stream.write_all(data)?;
+                stream.flush()?;
+            }
+            ReceiptPrinterTarget::Device(path) => {
+                let mut device = OpenOptions::new().create(true).append(true).open(path)?;
+                device.write_all(data)?;
+                device.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Builds ESC/POS commands, text is wrapped to the line width
+pub struct EscPos {
+    buffer: Vec<u8>,
+    line_width: usize,
+    double_size: bool,
+}
+
+impl EscPos {
+    /// Initialises the printer and selects the WPC1252 code page
+    pub fn new(line_width: usize) -> Self {
+        EscPos {
+            buffer: vec![ESC, b'@', ESC, b't', CODE_PAGE_WPC1252],
+            line_width: line_width.max(1),
+            double_size: false,
+        }
+    }
+
+    /// Characters per line for the current size
+    pub fn width(&self) -> usize {
+        if self.double_size {
+            (self.line_width / 2).max(1)
+        } else {
+            self.line_width
+        }
+    }
+
+    pub fn align(&mut self, align: Align) -> &mut Self {
+        let n = match align {
+            Align::Left => 0,
+            Align::Center => 1,
+            Align::Right => 2,
+        };
+        self.buffer.extend([ESC, b'a', n]);
+        self
+    }
+
+    pub fn bold(&mut self, bold: bool) -> &mut Self {
+        self.buffer.extend([ESC, b'E', bold as u8]);
+        self
+    }
+
+    /// Double width and height
+    pub fn double_size(&mut self, double_size: bool) -> &mut Self {
+        self.double_size = double_size;
+        self.buffer
+            .extend([GS, b'!', if double_size { 0x11 } else { 0x00 }]);
+        self
+    }
+
+    /// Prints the text, words are wrapped to the line width
+    pub fn line(&mut self, text: &str) -> &mut Self {
+        for line in wrap(text, self.width()) {
+            self.raw_line(&line);
+        }
+        self
+    }
+
+    /// Prints the left text and right aligns the right text, the left text is wrapped if both
+    /// don't fit on one line
+    pub fn columns(&mut self, left: &str, right: &str) -> &mut Self {
+        let width = self.width();
+        let right_width = right.chars().count();
+        let mut lines = wrap(left, width.saturating_sub(right_width + 1).max(1));
+        let last = lines.pop().unwrap_or_default();
+        for line in lines {
+            self.raw_line(&line);
+        }
+        let padding = width
+            .saturating_sub(last.chars().count() + right_width)
+            .max(1);
+        self.raw_line(&format!("{}{}{}", last, " ".repeat(padding), right));
+        self
+    }
+
+    pub fn separator(&mut self) -> &mut Self {
+        self.raw_line(&"-".repeat(self.width()))
+    }
+
+    pub fn feed(&mut self, lines: u8) -> &mut Self {
+        self.buffer.extend([ESC, b'd', lines]);
+        self
+    }
+
+    /// Feeds the paper to the cutter and cuts it
+    pub fn cut(&mut self) -> &mut Self {
+        self.buffer.extend([GS, b'V', 66, 3]);
+        self
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    fn raw_line(&mut self, text: &str) -> &mut Self {
+        self.buffer.extend(text.chars().map(encode));
+        self.buffer.push(LF);
+        self
+    }
+}
+
+/// Encodes the character in WPC1252, characters that are not in the code page (or control
+/// characters) are replaced with `?`
+fn encode(c: char) -> u8 {
+    match c as u32 {
+        code @ (0x20..=0x7e | 0xa0..=0xff) => code as u8,
+        _ => b'?',
+    }
+}
+
+/// Splits the text into lines of at most `width` characters, breaking at spaces where possible
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            let line_length = line.chars().count();
+            if line_length > 0 && line_length + 1 + word.len() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            // words longer than a line are split
+            while word.len() > width {
+                let rest = word.split_off(width);
+                lines.push(word.into_iter().collect());
+                word = rest;
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.extend(word);
+        }
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Read, net::TcpListener};
+
+    use util::uuid::uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("Take one tablet twice daily", 10),
+            vec!["Take one", "tablet", "twice", "daily"]
+        );
+        assert_eq!(wrap("Paracetamol500mg", 8), vec!["Paraceta", "mol500mg"]);
+        assert_eq!(wrap("a\nb", 10), vec!["a", "b"]);
+        assert_eq!(wrap("", 10), vec![""]);
+    }
+
+    #[test]
+    fn test_escpos() {
+        let mut receipt = EscPos::new(20);
+        receipt
+            .align(Align::Center)
+            .double_size(true)
+            .line("Café")
+            .double_size(false)
+            .align(Align::Left)
+            .columns("Amoxicillin 250mg", "12.50")
+            .separator()
+            .cut();
+        let mut expected = vec![ESC, b'@', ESC, b't', 16, ESC, b'a', 1, GS, b'!', 0x11];
+        expected.extend(b"Caf\xe9\n");
+        expected.extend([GS, b'!', 0, ESC, b'a', 0]);
+        expected.extend(b"Amoxicillin\n250mg          12.50\n");
+        expected.extend(b"--------------------\n");
+        expected.extend([GS, b'V', 66, 3]);
+        assert_eq!(receipt.into_bytes(), expected);
+    }
+
+    #[test]
+    fn test_send() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = std::thread::spawn(move || {
+            let mut data = Vec::new();
+            listener.accept().unwrap().0.read_to_end(&mut data).unwrap();
+            data
+        });
+        ReceiptPrinterTarget::Network {
+            address: "127.0.0.1".to_string(),
+            port,
+        }
+        .send(b"receipt")
+        .unwrap();
+        assert_eq!(sink.join().unwrap(), b"receipt");
+
+        let path = std::env::temp_dir().join(format!("receipt_{}", uuid()));
+        let device = ReceiptPrinterTarget::Device(path.to_string_lossy().to_string());
+        device.send(b"one").unwrap();
+        device.send(b"two").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"onetwo");
+        std::fs::remove_file(path).unwrap();
+    }
+}
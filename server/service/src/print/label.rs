@@ -1,18 +1,13 @@
 use anyhow::Result;
 
-use crate::settings::LabelPrinterSettingNode;
-
 use super::jetdirect::{Jetdirect, Mode};
 
 const LINE_HEIGHT_IN_DOTS: i32 = 50;
 
-pub fn print_qr_code(
-    settings: LabelPrinterSettingNode,
-    code: String,
-    message: Option<String>,
-) -> Result<String> {
+/// ZPL of a QR code label with optional text lines, printed through the print queue
+pub fn qr_code_label(label_height: i32, code: String, message: Option<String>) -> String {
     let qr_height = 133; // approx height in dots for the magnification factor of 4 when printing a uuid
-    let vertical_offset = (settings.label_height - qr_height) / 2;
+    let vertical_offset = (label_height - qr_height) / 2;
     let formatted_message = match message {
         Some(msg) => {
             // adding max to ensure that the y is not negative
@@ -29,7 +24,7 @@ pub fn print_qr_code(
         None => "".to_string(),
     };
 
-    let payload = format!(
+    format!(
         r#"
         ^XA
         ^FO50,{}
@@ -38,12 +33,150 @@ pub fn print_qr_code(
         {}
         ^XZ"#,
         vertical_offset, code, formatted_message
-    );
-    let printer = Jetdirect::new(settings.address, settings.port);
-    printer.send_string(payload, Mode::Print)
+    )
+}
+
+pub fn host_status(address: String, port: u16) -> Result<HostResponse> {
+    let printer = Jetdirect::new(address, port);
+    let response = printer.send_string("~HS".to_string(), Mode::Sgd)?;
+    Ok(HostResponse::parse(&response))
+}
+
+/**
+ * String 1 <STX>aaa,b,c,dddd,eee,f,g,h,iii,j,k,l<ETX><CR><LF>
+ * aaa = communication (interface) settings
+ * b = paper out flag (1 = paper out)
+ * c = pause flag (1 = pause active)
+ * dddd = label length (value in number of dots)
+ * eee = number of formats in receive buffer buffer
+ * f = full flag (1 = receive buffer full)
+ * g = communications diagnostic mode flag (1 = diagnostic mode active)
+ * h = partial format flag (1 = partial format in progress)
+ * iii = unused (always 000)
+ * j = corrupt RAM flag (1 = configuration data lost)
+ * k = temperature range (1 = under temperature)
+ * l = temperature range (1 = over temperature)
+ *
+ * String 2 <STX>mmm,n,o,p,q,r,s,t,uuuuuuuu,v,www<ETX><CR><LF>
+ * mmm =
+ * n = function settings
+ * o = unused
+ * p = head up flag (1 = head in up position)
+ * q = ribbon out flag (1 = ribbon out)
+ * r = print mode
+ * s = print mode width
+ * r = thermal transfer mode flag (1 = Thermal Transfer Mode selected)
+ * t = label waiting flag (1 = label waiting in Peel-off Mode)
+ * uuuuuuuu = labels remaining in batch
+ * v = format while printing flag (always 1)
+ * www = number of graphic images stored in memory
+ *
+ * String 3 <STX>xxxx,y<ETX><CR><LF>
+ * xxxx = password
+ * y = static RAM installed flag (1 = static RAM installed)
+ *
+ * e.g.
+ * 030,0,0,0290,000,0,0,0,000,0,0,0
+ * 001,0,0,0,1,2,4,0,00000000,1,000
+ * 1234,0
+ */
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub struct HostResponse {
+    pub is_valid: bool,
+    pub label_length: i32,
+    pub over_temperature: bool,
+    pub paper_out: bool,
+    pub pause: bool,
+    pub under_temperature: bool,
 }
 
-pub fn host_status(settings: LabelPrinterSettingNode) -> Result<String> {
-    let printer = Jetdirect::new(settings.address, settings.port);
-    printer.send_string("~HS".to_string(), Mode::Sgd)
+impl HostResponse {
+    pub fn parse(data: &str) -> HostResponse {
+        let invalid_response = HostResponse {
+            is_valid: false,
+            paper_out: false,
+            pause: false,
+            over_temperature: false,
+            under_temperature: false,
+            label_length: 0,
+        };
+        let lines: Vec<&str> = data.split('\n').collect();
+        if lines.len() < 3 {
+            return invalid_response;
+        }
+        let line1_parts: Vec<&str> = lines[0].split(',').collect();
+        // not testing for ends with \x03 to allow for line split of \r\n on windows
+        if line1_parts.len() != 12 || !line1_parts[0].starts_with('\x02') {
+            return invalid_response;
+        }
+
+        HostResponse {
+            paper_out: line1_parts[1] == "1",
+            pause: line1_parts[2] == "1",
+            over_temperature: line1_parts[10] == "1",
+            under_temperature: line1_parts[11] == "1",
+            label_length: line1_parts[3].parse().unwrap_or(0),
+            is_valid: true,
+        }
+    }
+
+    /// Conditions that stop the printer from printing
+    pub fn problems(&self) -> Vec<&'static str> {
+        if !self.is_valid {
+            return vec!["invalid host status response"];
+        }
+        [
+            (self.paper_out, "paper out"),
+            (self.pause, "paused"),
+            (self.over_temperature, "over temperature"),
+            (self.under_temperature, "under temperature"),
+        ]
+        .into_iter()
+        .filter(|(is_set, _)| *is_set)
+        .map(|(_, problem)| problem)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_response_parse() {
+        // Test valid response
+        let valid_response = r#"030,0,0,0290,000,0,0,0,000,0,0,0
+001,0,0,0,1,2,4,0,00000000,1,000
+1234,0"#;
+        let parsed_valid_response = HostResponse::parse(valid_response);
+        assert_eq!(parsed_valid_response.is_valid, true);
+        assert_eq!(parsed_valid_response.paper_out, false);
+        assert_eq!(parsed_valid_response.pause, false);
+        assert_eq!(parsed_valid_response.over_temperature, false);
+        assert_eq!(parsed_valid_response.under_temperature, false);
+        assert_eq!(parsed_valid_response.label_length, 290);
+
+        // Test invalid response with incorrect number of lines
+        let invalid_response1 = "030,0,0,0290,000,0,0,0,000,0,0,0\n";
+        let parsed_invalid_response1 = HostResponse::parse(invalid_response1);
+        assert_eq!(parsed_invalid_response1.is_valid, false);
+
+        // Test invalid response with incorrect line format
+        let invalid_response2 = "030,0,0,0290,000,0,0,0,000,0,0,0\n";
+        let parsed_invalid_response2 = HostResponse::parse(invalid_response2);
+        assert_eq!(parsed_invalid_response2.is_valid, false);
+        assert_eq!(
+            parsed_invalid_response2.problems(),
+            vec!["invalid host status response"]
+        );
+
+        // Test paused printer without paper
+        let paused_response = "\x02030,1,1,0290,000,0,0,0,000,0,0,0\x03
+001,0,0,0,1,2,4,0,00000000,1,000
+1234,0";
+        assert_eq!(
+            HostResponse::parse(paused_response).problems(),
+            vec!["paper out", "paused"]
+        );
+    }
 }
@@ -0,0 +1,683 @@
+//! Label templates are ZPL with Tera variables, rendered for each record and copy. Values are
+//! escaped, `^` and `~` are removed. Variables of all types: `label.width`, `label.height` (in
+//! dots), `copy` and `copies`. By type:
+//! - stock line: `stock_line` (`id`, `batch`, `expiry_date`, `pack_size`, `note`, ...), `item`
+//!   (`id`, `code`, `name`), `location`, `supplier` and `gs1`, the GS1 element string of the GTIN,
+//!   expiry date and batch for a GS1-128 barcode (`^BCN,,Y,N,N,D`)
+//! - location: `location` (`id`, `code`, `name`, `on_hold`)
+//! - shipment: `shipment` (invoice `id`, `invoice_number`, `their_reference`, ...), `name` (the
+//!   other party) and `store`
+use std::collections::HashMap;
+
+use repository::{
+    barcode::{BarcodeFilter, BarcodeRepository},
+    location::{LocationFilter, LocationRepository},
+    BarcodeRow, EqualFilter, InvoiceFilter, InvoiceRepository, LabelTemplateRow,
+    LabelTemplateRowRepository, LabelTemplateType, PrintJobRow, RepositoryError, StockLine,
+    StockLineFilter, StockLineRepository,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    report::validation::error_chain, service_provider::ServiceContext,
+    settings::LabelPrinterSettingNode,
+};
+
+use super::{
+    printer::{select_label_printer, SelectLabelPrinterError},
+    queue::enqueue_print_job,
+    zpl_preview::zpl_to_png,
+};
+
+/// Label size used for previews when no label printer is configured (4 x 2 inch at 203 dpi)
+const DEFAULT_LABEL_WIDTH: i32 = 812;
+const DEFAULT_LABEL_HEIGHT: i32 = 406;
+const TEMPLATE_NAME: &str = "label.zpl";
+/// Limit of records times copies, each label is rendered (and previewed) in the request
+pub const MAX_LABELS_PER_REQUEST: u32 = 100;
+
+/// Built-in template of each type, used when printing without a template id
+const DEFAULT_STOCK_LINE_TEMPLATE: &str = r#"^XA
+^CI28
+^PW{{ label.width }}
+^LL{{ label.height }}
+^FO30,30^A0N,36,36^FB{{ label.width - 60 }},2,0,L^FD{{ item.name }}^FS
+^FO30,115^A0N,28,28^FDCode: {{ item.code }}^FS
+^FO30,150^A0N,28,28^FDBatch: {{ stock_line.batch }}^FS
+^FO30,185^A0N,28,28^FDExpiry: {{ stock_line.expiry_date }}^FS
+{% if gs1 %}^FO30,230^BY2^BCN,70,Y,N,N,D^FD{{ gs1 }}^FS
+{% else %}^FO30,230^BQN,2,4^FDMA,{{ stock_line.id }}^FS
+{% endif %}^XZ
+"#;
+const DEFAULT_LOCATION_TEMPLATE: &str = r#"^XA
+^CI28
+^PW{{ label.width }}
+^LL{{ label.height }}
+^FO30,30^A0N,60,60^FD{{ location.code }}^FS
+^FO30,110^A0N,32,32^FB{{ label.width - 60 }},2,0,L^FD{{ location.name }}^FS
+^FO30,200^BY2^BCN,80,Y,N,N^FD{{ location.code }}^FS
+^XZ
+"#;
+const DEFAULT_SHIPMENT_TEMPLATE: &str = r#"^XA
+^CI28
+^PW{{ label.width }}
+^LL{{ label.height }}
+^FO30,30^A0N,32,32^FDFrom: {{ store.code }}^FS
+^FO30,75^A0N,40,40^FB{{ label.width - 60 }},2,0,L^FDTo: {{ name.name }}^FS
+^FO30,170^A0N,32,32^FDShipment {{ shipment.invoice_number }}^FS
+{% if shipment.their_reference %}^FO30,210^A0N,28,28^FDRef: {{ shipment.their_reference }}^FS
+{% endif %}^FO30,260^A0N,48,48^FDBox {{ copy }} of {{ copies }}^FS
+^FO{{ label.width - 200 }},170^BQN,2,5^FDMA,{{ shipment.id }}^FS
+^XZ
+"#;
+
+pub struct UpsertLabelTemplate {
+    pub id: String,
+    pub name: String,
+    pub r#type: LabelTemplateType,
+    pub template: String,
+}
+
+pub struct PrintLabels {
+    /// The built-in template of the type is used when not set
+    pub template_id: Option<String>,
+    pub r#type: LabelTemplateType,
+    /// Stock line, location or shipment ids (depending on the type), one label is printed for
+    /// each record in this order
+    pub record_ids: Vec<String>,
+    /// Labels printed per record, defaults to 1. At most `MAX_LABELS_PER_REQUEST` labels (records
+    /// times copies) are printed per request
+    pub copies: Option<u32>,
+    /// Named printer to print on, the printer for the store and type of labels is used when not
+    /// set
+    pub printer_id: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UpsertLabelTemplateError {
+    NameNotSpecified,
+    InvalidTemplate(String),
+    DatabaseError(RepositoryError),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DeleteLabelTemplateError {
+    LabelTemplateDoesNotExist,
+    DatabaseError(RepositoryError),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PrintLabelsError {
+    LabelTemplateDoesNotExist,
+    /// The template prints a different type of record
+    LabelTemplateTypeMismatch,
+    /// Records that don't exist or don't belong to the store
+    RecordsDoNotExist(Vec<String>),
+    InvalidCopies,
+    /// More than `MAX_LABELS_PER_REQUEST` labels (records times copies)
+    TooManyLabels,
+    TemplateError(String),
+    PreviewError(String),
+    /// The requested printer doesn't exist, is inactive or assigned to another store
+    LabelPrinterDoesNotExist,
+    /// No printer is assigned to the store and the label printer settings are not configured
+    NoLabelPrinter,
+    DatabaseError(RepositoryError),
+}
+
+impl From<RepositoryError> for UpsertLabelTemplateError {
+    fn from(error: RepositoryError) -> Self {
+        UpsertLabelTemplateError::DatabaseError(error)
+    }
+}
+
+impl From<RepositoryError> for DeleteLabelTemplateError {
+    fn from(error: RepositoryError) -> Self {
+        DeleteLabelTemplateError::DatabaseError(error)
+    }
+}
+
+impl From<RepositoryError> for PrintLabelsError {
+    fn from(error: RepositoryError) -> Self {
+        PrintLabelsError::DatabaseError(error)
+    }
+}
+
+impl From<SelectLabelPrinterError> for PrintLabelsError {
+    fn from(error: SelectLabelPrinterError) -> Self {
+        match error {
+            SelectLabelPrinterError::LabelPrinterDoesNotExist => {
+                PrintLabelsError::LabelPrinterDoesNotExist
+            }
+            SelectLabelPrinterError::NoLabelPrinter => PrintLabelsError::NoLabelPrinter,
+            SelectLabelPrinterError::DatabaseError(error) => PrintLabelsError::DatabaseError(error),
+        }
+    }
+}
+
+pub fn get_label_templates(
+    ctx: &ServiceContext,
+    r#type: Option<LabelTemplateType>,
+) -> Result<Vec<LabelTemplateRow>, RepositoryError> {
+    let repo = LabelTemplateRowRepository::new(&ctx.connection);
+    match r#type {
+        Some(r#type) => repo.find_many_by_type(r#type),
+        None => repo.find_all(),
+    }
+}
+
+pub fn default_label_template(r#type: &LabelTemplateType) -> &'static str {
+    match r#type {
+        LabelTemplateType::StockLine => DEFAULT_STOCK_LINE_TEMPLATE,
+        LabelTemplateType::Location => DEFAULT_LOCATION_TEMPLATE,
+        LabelTemplateType::Shipment => DEFAULT_SHIPMENT_TEMPLATE,
+    }
+}
+
+pub fn upsert_label_template(
+    ctx: &ServiceContext,
+    input: UpsertLabelTemplate,
+) -> Result<LabelTemplateRow, UpsertLabelTemplateError> {
+    if input.name.trim().is_empty() {
+        return Err(UpsertLabelTemplateError::NameNotSpecified);
+    }
+    compile(&input.template).map_err(UpsertLabelTemplateError::InvalidTemplate)?;
+
+    let UpsertLabelTemplate {
+        id,
+        name,
+        r#type,
+        template,
+    } = input;
+    let row = LabelTemplateRow {
+        id,
+        name,
+        r#type,
+        template,
+    };
+    LabelTemplateRowRepository::new(&ctx.connection).upsert_one(&row)?;
+    Ok(row)
+}
+
+pub fn delete_label_template(
+    ctx: &ServiceContext,
+    id: &str,
+) -> Result<String, DeleteLabelTemplateError> {
+    let repo = LabelTemplateRowRepository::new(&ctx.connection);
+    repo.find_one_by_id(id)?
+        .ok_or(DeleteLabelTemplateError::LabelTemplateDoesNotExist)?;
+    repo.delete(id)?;
+    Ok(id.to_string())
+}
+
+/// Renders the labels of the records to ZPL and queues them for the selected label printer
+pub fn print_labels(
+    ctx: &ServiceContext,
+    default_printer: Option<&LabelPrinterSettingNode>,
+    input: &PrintLabels,
+) -> Result<PrintJobRow, PrintLabelsError> {
+    let printer = select_label_printer(
+        ctx,
+        default_printer,
+        input.printer_id.as_deref(),
+        Some(&input.r#type),
+    )?;
+    let labels = generate_labels(ctx, printer.label_size(), input)?;
+    let job = enqueue_print_job(ctx, &printer, labels.concat(), labels.len())?;
+    Ok(job)
+}
+
+/// Renders the labels of the records to png data urls, the label size of the selected printer
+/// is used if one is configured
+pub fn preview_labels(
+    ctx: &ServiceContext,
+    default_printer: Option<&LabelPrinterSettingNode>,
+    input: &PrintLabels,
+) -> Result<Vec<String>, PrintLabelsError> {
+    let (width, height) = match select_label_printer(
+        ctx,
+        default_printer,
+        input.printer_id.as_deref(),
+        Some(&input.r#type),
+    ) {
+        Ok(printer) => printer.label_size(),
+        Err(SelectLabelPrinterError::NoLabelPrinter) => (DEFAULT_LABEL_WIDTH, DEFAULT_LABEL_HEIGHT),
+        Err(error) => return Err(error.into()),
+    };
+    let labels = generate_labels(ctx, (width, height), input)?;
+
+    let mut previews = Vec::new();
+    for label in labels {
+        let pngs = zpl_to_png(&label, width.max(1) as u32, height.max(1) as u32)
+            .map_err(|err| PrintLabelsError::PreviewError(err.to_string()))?;
+        previews.extend(pngs.into_iter().map(|png| {
+            format!(
+                "data:image/png;base64,{}",
+                base64::Engine::encode(&base64::engine::general_purpose::STANDARD, png)
+            )
+        }));
+    }
+    Ok(previews)
+}
+
+/// Renders the template for each record and copy, returns the ZPL of each label
+pub fn generate_labels(
+    ctx: &ServiceContext,
+    (width, height): (i32, i32),
+    input: &PrintLabels,
+) -> Result<Vec<String>, PrintLabelsError> {
+    let copies = input.copies.unwrap_or(1);
+    if copies < 1 {
+        return Err(PrintLabelsError::InvalidCopies);
+    }
+    let number_of_labels = (input.record_ids.len() as u64).saturating_mul(copies as u64);
+    if number_of_labels > MAX_LABELS_PER_REQUEST as u64 {
+        return Err(PrintLabelsError::TooManyLabels);
+    }
+    let template = match &input.template_id {
+        Some(template_id) => {
+            let row = LabelTemplateRowRepository::new(&ctx.connection)
+                .find_one_by_id(template_id)?
+                .ok_or(PrintLabelsError::LabelTemplateDoesNotExist)?;
+            if row.r#type != input.r#type {
+                return Err(PrintLabelsError::LabelTemplateTypeMismatch);
+            }
+            row.template
+        }
+        None => default_label_template(&input.r#type).to_string(),
+    };
+    let tera = compile(&template).map_err(PrintLabelsError::TemplateError)?;
+
+    let records = match input.r#type {
+        LabelTemplateType::StockLine => stock_line_contexts(ctx, &input.record_ids)?,
+        LabelTemplateType::Location => location_contexts(ctx, &input.record_ids)?,
+        LabelTemplateType::Shipment => shipment_contexts(ctx, &input.record_ids)?,
+    };
+    let missing: Vec<String> = input
+        .record_ids
+        .iter()
+        .filter(|id| !records.contains_key(*id))
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        return Err(PrintLabelsError::RecordsDoNotExist(missing));
+    }
+
+    let mut labels = Vec::new();
+    for id in &input.record_ids {
+        for copy in 1..=copies {
+            let mut context = records[id].clone();
+            if let Value::Object(context) = &mut context {
+                context.insert(
+                    "label".to_string(),
+                    json!({ "width": width, "height": height }),
+                );
+                context.insert("copy".to_string(), json!(copy));
+                context.insert("copies".to_string(), json!(copies));
+            }
+            let label = tera::Context::from_value(context)
+                .and_then(|context| tera.render(TEMPLATE_NAME, &context))
+                .map_err(|err| PrintLabelsError::TemplateError(error_chain(&err)))?;
+            labels.push(label);
+        }
+    }
+    Ok(labels)
+}
+
+fn compile(template: &str) -> Result<tera::Tera, String> {
+    let mut tera = tera::Tera::default();
+    tera.autoescape_on(vec![".zpl"]);
+    tera.set_escape_fn(escape_zpl);
+    tera.add_raw_template(TEMPLATE_NAME, template)
+        .map_err(|err| error_chain(&err))?;
+    Ok(tera)
+}
+
+/// Removes the command prefixes from values, so that record data (e.g. an item name) can't
+/// end a field or inject commands
+fn escape_zpl(value: &str) -> String {
+    value.replace(['^', '~'], "")
+}
+
+fn stock_line_contexts(
+    ctx: &ServiceContext,
+    ids: &[String],
+) -> Result<HashMap<String, Value>, RepositoryError> {
+    let stock_lines = StockLineRepository::new(&ctx.connection).query_by_filter(
+        StockLineFilter::new()
+            .id(EqualFilter::equal_any(ids.to_vec()))
+            .store_id(EqualFilter::equal_to(&ctx.store_id)),
+        Some(ctx.store_id.clone()),
+    )?;
+    let item_ids = stock_lines
+        .iter()
+        .map(|stock_line| stock_line.item_row.id.clone())
+        .collect();
+    let item_barcodes = BarcodeRepository::new(&ctx.connection)
+        .query_by_filter(BarcodeFilter {
+            item_id: Some(EqualFilter::equal_any(item_ids)),
+            ..Default::default()
+        })?
+        .into_iter()
+        .map(|barcode| barcode.barcode_row)
+        .collect::<Vec<_>>();
+
+    Ok(stock_lines
+        .into_iter()
+        .map(|stock_line| {
+            let gs1 = gs1(&stock_line, &item_barcodes);
+            let StockLine {
+                stock_line_row: row,
+                item_row,
+                location_row,
+                supplier_name_row,
+                ..
+            } = stock_line;
+            let context = json!({
+                "stock_line": {
+                    "id": row.id,
+                    "batch": row.batch,
+                    "expiry_date": row.expiry_date.map(|date| date.to_string()),
+                    "pack_size": row.pack_size,
+                    "available_number_of_packs": row.available_number_of_packs,
+                    "total_number_of_packs": row.total_number_of_packs,
+                    "on_hold": row.on_hold,
+                    "note": row.note,
+                },
+                "item": {
+                    "id": item_row.id,
+                    "code": item_row.code,
+                    "name": item_row.name,
+                },
+                "location": location_row.map(|location| json!({
+                    "id": location.id,
+                    "code": location.code,
+                    "name": location.name,
+                })),
+                "supplier": supplier_name_row.map(|supplier| json!({
+                    "code": supplier.code,
+                    "name": supplier.name,
+                })),
+                "gs1": gs1,
+            });
+            (row.id, context)
+        })
+        .collect())
+}
+
+/// GS1 element string with the GTIN, expiry date and batch of the stock line, e.g.
+/// `(01)09501101020917(17)250131(10)AB12`. The barcode of the stock line is used, otherwise a
+/// barcode of the item with the same pack size. None if there is no numeric GTIN.
+fn gs1(stock_line: &StockLine, item_barcodes: &[BarcodeRow]) -> Option<String> {
+    let row = &stock_line.stock_line_row;
+    let barcode = stock_line.barcode_row.as_ref().or_else(|| {
+        item_barcodes.iter().find(|barcode| {
+            barcode.item_id == stock_line.item_row.id && barcode.pack_size == Some(row.pack_size)
+        })
+    })?;
+    let gtin = barcode.gtin.trim();
+    if gtin.is_empty() || gtin.len() > 14 || !gtin.chars().all(|char| char.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut gs1 = format!("(01){:0>14}", gtin);
+    if let Some(expiry_date) = row.expiry_date {
+        gs1.push_str(&format!("(17){}", expiry_date.format("%y%m%d")));
+    }
+    if let Some(batch) = row.batch.as_ref().filter(|batch| !batch.is_empty()) {
+        // batch numbers are limited to 20 characters
+        gs1.push_str(&format!(
+            "(10){}",
+            batch.chars().take(20).collect::<String>()
+        ));
+    }
+    Some(gs1)
+}
+
+fn location_contexts(
+    ctx: &ServiceContext,
+    ids: &[String],
+) -> Result<HashMap<String, Value>, RepositoryError> {
+    let locations = LocationRepository::new(&ctx.connection).query_by_filter(
+        LocationFilter::new()
+            .id(EqualFilter::equal_any(ids.to_vec()))
+            .store_id(EqualFilter::equal_to(&ctx.store_id)),
+    )?;
+    Ok(locations
+        .into_iter()
+        .map(|location| {
+            let row = location.location_row;
+            let context = json!({
+                "location": {
+                    "id": row.id,
+                    "code": row.code,
+                    "name": row.name,
+                    "on_hold": row.on_hold,
+                }
+            });
+            (row.id, context)
+        })
+        .collect())
+}
+
+fn shipment_contexts(
+    ctx: &ServiceContext,
+    ids: &[String],
+) -> Result<HashMap<String, Value>, RepositoryError> {
+    let invoices = InvoiceRepository::new(&ctx.connection).query_by_filter(
+        InvoiceFilter::new()
+            .id(EqualFilter::equal_any(ids.to_vec()))
+            .store_id(EqualFilter::equal_to(&ctx.store_id)),
+    )?;
+    Ok(invoices
+        .into_iter()
+        .map(|invoice| {
+            let row = invoice.invoice_row;
+            let context = json!({
+                "shipment": {
+                    "id": row.id,
+                    "invoice_number": row.invoice_number,
+                    "type": row.r#type,
+                    "status": row.status,
+                    "their_reference": row.their_reference,
+                    "transport_reference": row.transport_reference,
+                    "comment": row.comment,
+                    "shipped_datetime": row.shipped_datetime.map(|datetime| datetime.to_string()),
+                },
+                "name": {
+                    "code": invoice.name_row.code,
+                    "name": invoice.name_row.name,
+                },
+                "store": {
+                    "code": invoice.store_row.code,
+                },
+            });
+            (row.id, context)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use repository::{
+        mock::{
+            mock_location_1, mock_outbound_shipment_e, mock_stock_line_a, mock_store_a,
+            mock_user_account_a, MockDataInserts,
+        },
+        test_db::setup_all,
+        BarcodeRow, BarcodeRowRepository, LabelTemplateType, StockLineRowRepository,
+    };
+
+    use crate::service_provider::ServiceProvider;
+
+    use super::*;
+
+    fn print_input(r#type: LabelTemplateType, ids: &[&str]) -> PrintLabels {
+        PrintLabels {
+            template_id: None,
+            r#type,
+            record_ids: ids.iter().map(|id| id.to_string()).collect(),
+            copies: None,
+            printer_id: None,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_label_templates() {
+        let (_, connection, connection_manager, _) =
+            setup_all("test_label_templates", MockDataInserts::all()).await;
+        let service_provider = ServiceProvider::new(connection_manager, "app_data");
+        let ctx = service_provider
+            .context(mock_store_a().id, mock_user_account_a().id)
+            .unwrap();
+
+        assert!(matches!(
+            upsert_label_template(
+                &ctx,
+                UpsertLabelTemplate {
+                    id: "template".to_string(),
+                    name: "Batch".to_string(),
+                    r#type: LabelTemplateType::StockLine,
+                    template: "^XA^FD{{ item.name ^FS^XZ".to_string(),
+                }
+            ),
+            Err(UpsertLabelTemplateError::InvalidTemplate(_))
+        ));
+        upsert_label_template(
+            &ctx,
+            UpsertLabelTemplate {
+                id: "template".to_string(),
+                name: "Batch".to_string(),
+                r#type: LabelTemplateType::StockLine,
+                template: "^XA^FD{{ item.name }} {{ stock_line.batch }}^FS^FD{{ gs1 }}^FS\
+                    ^FD{{ copy }}/{{ copies }}^FS^XZ"
+                    .to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            get_label_templates(&ctx, Some(LabelTemplateType::Location)),
+            Ok(vec![])
+        );
+
+        BarcodeRowRepository::new(&connection)
+            .upsert_one(&BarcodeRow {
+                id: "barcode".to_string(),
+                gtin: "9501101020917".to_string(),
+                item_id: "item_a".to_string(),
+                pack_size: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        let mut stock_line = mock_stock_line_a();
+        stock_line.batch = Some("B^1".to_string());
+        stock_line.expiry_date = chrono::NaiveDate::from_ymd_opt(2025, 1, 31);
+        stock_line.barcode_id = Some("barcode".to_string());
+        StockLineRowRepository::new(&connection)
+            .upsert_one(&stock_line)
+            .unwrap();
+
+        let input = PrintLabels {
+            template_id: Some("template".to_string()),
+            copies: Some(2),
+            ..print_input(LabelTemplateType::StockLine, &[&stock_line.id])
+        };
+        let labels = generate_labels(&ctx, (400, 200), &input).unwrap();
+        assert_eq!(
+            labels,
+            vec![
+                "^XA^FDItem A B1^FS^FD(01)09501101020917(17)250131(10)B1^FS^FD1/2^FS^XZ",
+                "^XA^FDItem A B1^FS^FD(01)09501101020917(17)250131(10)B1^FS^FD2/2^FS^XZ",
+            ]
+        );
+
+        // limit of labels per request
+        assert_eq!(
+            generate_labels(
+                &ctx,
+                (400, 200),
+                &PrintLabels {
+                    copies: Some(MAX_LABELS_PER_REQUEST / 2 + 1),
+                    ..print_input(
+                        LabelTemplateType::StockLine,
+                        &[&stock_line.id, &stock_line.id]
+                    )
+                }
+            ),
+            Err(PrintLabelsError::TooManyLabels)
+        );
+
+        // template of another type
+        assert_eq!(
+            generate_labels(
+                &ctx,
+                (400, 200),
+                &PrintLabels {
+                    r#type: LabelTemplateType::Location,
+                    ..input
+                }
+            ),
+            Err(PrintLabelsError::LabelTemplateTypeMismatch)
+        );
+        // location of another store
+        assert_eq!(
+            generate_labels(
+                &ctx,
+                (400, 200),
+                &print_input(
+                    LabelTemplateType::Location,
+                    &[
+                        &mock_location_1().id,
+                        "location_on_hold",
+                        "location_in_another_store"
+                    ]
+                )
+            ),
+            Err(PrintLabelsError::RecordsDoNotExist(vec![
+                "location_in_another_store".to_string()
+            ]))
+        );
+
+        // built-in templates
+        for (r#type, id) in [
+            (LabelTemplateType::StockLine, stock_line.id),
+            (LabelTemplateType::Location, mock_location_1().id),
+            (LabelTemplateType::Shipment, mock_outbound_shipment_e().id),
+        ] {
+            let previews = preview_labels(&ctx, None, &print_input(r#type, &[&id])).unwrap();
+            assert_eq!(previews.len(), 1);
+            assert!(previews[0].starts_with("data:image/png;base64,"));
+        }
+
+        // labels are queued for the printer of the settings
+        let input = print_input(LabelTemplateType::Location, &[&mock_location_1().id]);
+        assert_eq!(
+            print_labels(&ctx, None, &input),
+            Err(PrintLabelsError::NoLabelPrinter)
+        );
+        let job = print_labels(
+            &ctx,
+            Some(&LabelPrinterSettingNode {
+                address: "127.0.0.1".to_string(),
+                label_height: 406,
+                label_width: 812,
+                port: 9100,
+            }),
+            &input,
+        )
+        .unwrap();
+        assert_eq!(
+            (job.label_printer_id, job.label_count, job.status),
+            (None, 1, repository::PrintJobStatus::Pending)
+        );
+
+        assert_eq!(
+            delete_label_template(&ctx, "template"),
+            Ok("template".to_string())
+        );
+        assert_eq!(
+            delete_label_template(&ctx, "template"),
+            Err(DeleteLabelTemplateError::LabelTemplateDoesNotExist)
+        );
+    }
+}
@@ -1,2 +1,8 @@
+pub mod escpos;
 pub mod jetdirect;
 pub mod label;
+pub mod label_template;
+pub mod printer;
+pub mod queue;
+pub mod receipt;
+mod zpl_preview;
@@ -0,0 +1,357 @@
+use std::net::IpAddr;
+
+use repository::{
+    LabelPrinterRow, LabelPrinterRowRepository, LabelPrinterStatus, LabelTemplateType,
+    PrintJobRowRepository, PrintJobStatus, RepositoryError, StoreRowRepository,
+};
+
+use crate::{service_provider::ServiceContext, settings::LabelPrinterSettingNode};
+
+/// Printer labels are sent to, a named printer or the printer of the label printer settings
+#[derive(Clone, Debug, PartialEq)]
+pub enum LabelPrinter {
+    Named(LabelPrinterRow),
+    Default(LabelPrinterSettingNode),
+}
+
+impl LabelPrinter {
+    /// Id of a named printer
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            LabelPrinter::Named(row) => Some(&row.id),
+            LabelPrinter::Default(_) => None,
+        }
+    }
+
+    pub fn address(&self) -> (String, u16) {
+        match self {
+            LabelPrinter::Named(row) => (row.address.clone(), row.port as u16),
+            LabelPrinter::Default(settings) => (settings.address.clone(), settings.port),
+        }
+    }
+
+    /// Label width and height in dots
+    pub fn label_size(&self) -> (i32, i32) {
+        match self {
+            LabelPrinter::Named(row) => (row.label_width, row.label_height),
+            LabelPrinter::Default(settings) => (settings.label_width, settings.label_height),
+        }
+    }
+}
+
+pub struct UpsertLabelPrinter {
+    pub id: String,
+    pub name: String,
+    pub address: String,
+    pub port: u16,
+    pub label_width: i32,
+    pub label_height: i32,
+    pub store_id: Option<String>,
+    pub purpose: Option<LabelTemplateType>,
+    pub is_active: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UpsertLabelPrinterError {
+    NameNotSpecified,
+    InvalidAddress,
+    InvalidLabelSize,
+    StoreDoesNotExist,
+    DatabaseError(RepositoryError),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DeleteLabelPrinterError {
+    LabelPrinterDoesNotExist,
+    DatabaseError(RepositoryError),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SelectLabelPrinterError {
+    /// The printer doesn't exist, is inactive or assigned to another store
+    LabelPrinterDoesNotExist,
+    /// No printer is assigned to the store and the label printer settings are not configured
+    NoLabelPrinter,
+    DatabaseError(RepositoryError),
+}
+
+impl From<RepositoryError> for UpsertLabelPrinterError {
+    fn from(error: RepositoryError) -> Self {
+        UpsertLabelPrinterError::DatabaseError(error)
+    }
+}
+
+impl From<RepositoryError> for DeleteLabelPrinterError {
+    fn from(error: RepositoryError) -> Self {
+        DeleteLabelPrinterError::DatabaseError(error)
+    }
+}
+
+impl From<RepositoryError> for SelectLabelPrinterError {
+    fn from(error: RepositoryError) -> Self {
+        SelectLabelPrinterError::DatabaseError(error)
+    }
+}
+
+/// Printers available to the store of the context
+pub fn get_label_printers(ctx: &ServiceContext) -> Result<Vec<LabelPrinterRow>, RepositoryError> {
+    LabelPrinterRowRepository::new(&ctx.connection).find_many_by_store_id(&ctx.store_id)
+}
+
+pub fn upsert_label_printer(
+    ctx: &ServiceContext,
+    input: UpsertLabelPrinter,
+) -> Result<LabelPrinterRow, UpsertLabelPrinterError> {
+    if input.name.trim().is_empty() {
+        return Err(UpsertLabelPrinterError::NameNotSpecified);
+    }
+    if input.address.parse::<IpAddr>().is_err() {
+        return Err(UpsertLabelPrinterError::InvalidAddress);
+    }
+    if input.label_width < 1 || input.label_height < 1 {
+        return Err(UpsertLabelPrinterError::InvalidLabelSize);
+    }
+    if let Some(store_id) = &input.store_id {
+        StoreRowRepository::new(&ctx.connection)
+            .find_one_by_id(store_id)?
+            .ok_or(UpsertLabelPrinterError::StoreDoesNotExist)?;
+    }
+
+    let repo = LabelPrinterRowRepository::new(&ctx.connection);
+    // the status is kept until the next health check
+    let existing = repo.find_one_by_id(&input.id)?;
+    let (status, status_message, status_datetime) = match existing {
+        Some(existing)
+            if existing.address == input.address && existing.port == input.port as i32 =>
+        {
+            (
+                existing.status,
+                existing.status_message,
+                existing.status_datetime,
+            )
+        }
+        _ => (LabelPrinterStatus::Unknown, None, None),
+    };
+
+    let UpsertLabelPrinter {
+        id,
+        name,
+        address,
+        port,
+        label_width,
+        label_height,
+        store_id,
+        purpose,
+        is_active,
+    } = input;
+    let row = LabelPrinterRow {
+        id,
+        name,
+        address,
+        port: port as i32,
+        label_width,
+        label_height,
+        store_id,
+        purpose,
+        is_active,
+        status,
+        status_message,
+        status_datetime,
+    };
+    repo.upsert_one(&row)?;
+    Ok(row)
+}
+
+/// Deletes the printer and cancels its pending print jobs
+pub fn delete_label_printer(
+    ctx: &ServiceContext,
+    id: &str,
+) -> Result<String, DeleteLabelPrinterError> {
+    LabelPrinterRowRepository::new(&ctx.connection)
+        .find_one_by_id(id)?
+        .ok_or(DeleteLabelPrinterError::LabelPrinterDoesNotExist)?;
+
+    ctx.connection
+        .transaction_sync(|connection| {
+            let job_repo = PrintJobRowRepository::new(connection);
+            for mut job in job_repo.find_many_by_label_printer_id(id)? {
+                if job.status == PrintJobStatus::Pending {
+                    job.status = PrintJobStatus::Cancelled;
+                    job_repo.upsert_one(&job)?;
+                }
+            }
+            LabelPrinterRowRepository::new(connection).delete(id)
+        })
+        .map_err(|error| error.to_inner_error())?;
+
+    Ok(id.to_string())
+}
+
+/// Printer for labels of the store of the context. Unless a printer is requested, the active
+/// printer assigned to the store is preferred over printers available to all stores, and a
+/// printer for the type of labels over printers for all labels. The printer of the label
+/// printer settings is used if no printer matches.
+pub fn select_label_printer(
+    ctx: &ServiceContext,
+    default_printer: Option<&LabelPrinterSettingNode>,
+    printer_id: Option<&str>,
+    purpose: Option<&LabelTemplateType>,
+) -> Result<LabelPrinter, SelectLabelPrinterError> {
+    let printers: Vec<LabelPrinterRow> = get_label_printers(ctx)?
+        .into_iter()
+        .filter(|printer| printer.is_active)
+        .collect();
+
+    if let Some(printer_id) = printer_id {
+        return printers
+            .into_iter()
+            .find(|printer| printer.id == printer_id)
+            .map(LabelPrinter::Named)
+            .ok_or(SelectLabelPrinterError::LabelPrinterDoesNotExist);
+    }
+
+    let rank = |printer: &LabelPrinterRow| {
+        let store_rank = match &printer.store_id {
+            Some(_) => 0,
+            None => 1,
+        };
+        let purpose_rank = match (&printer.purpose, purpose) {
+            (Some(printer_purpose), Some(purpose)) if printer_purpose == purpose => Some(0),
+            (Some(_), _) => None,
+            (None, _) => Some(1),
+        };
+        purpose_rank.map(|purpose_rank| (purpose_rank, store_rank))
+    };
+    // printers are sorted by name, the first of equally ranked printers is used
+    let selected = printers
+        .into_iter()
+        .filter_map(|printer| rank(&printer).map(|rank| (rank, printer)))
+        .min_by_key(|(rank, _)| *rank)
+        .map(|(_, printer)| printer);
+
+    match (selected, default_printer) {
+        (Some(printer), _) => Ok(LabelPrinter::Named(printer)),
+        (None, Some(settings)) => Ok(LabelPrinter::Default(settings.clone())),
+        (None, None) => Err(SelectLabelPrinterError::NoLabelPrinter),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use repository::{
+        mock::{mock_store_a, mock_store_b, mock_user_account_a, MockDataInserts},
+        test_db::setup_all,
+        LabelTemplateType,
+    };
+
+    use crate::{service_provider::ServiceProvider, settings::LabelPrinterSettingNode};
+
+    use super::*;
+
+    fn printer(id: &str) -> UpsertLabelPrinter {
+        UpsertLabelPrinter {
+            id: id.to_string(),
+            name: id.to_string(),
+            address: "127.0.0.1".to_string(),
+            port: 9100,
+            label_width: 812,
+            label_height: 406,
+            store_id: None,
+            purpose: None,
+            is_active: true,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_select_label_printer() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_select_label_printer",
+            MockDataInserts::none().names().stores(),
+        )
+        .await;
+        let service_provider = ServiceProvider::new(connection_manager, "app_data");
+        let ctx = service_provider
+            .context(mock_store_a().id, mock_user_account_a().id)
+            .unwrap();
+        let default_printer = LabelPrinterSettingNode {
+            address: "127.0.0.1".to_string(),
+            label_height: 200,
+            label_width: 400,
+            port: 9100,
+        };
+        let selected_id = |printer_id: Option<&str>, purpose: Option<&LabelTemplateType>| {
+            select_label_printer(&ctx, Some(&default_printer), printer_id, purpose)
+                .map(|printer| printer.id().map(str::to_string))
+        };
+
+        assert_eq!(
+            select_label_printer(&ctx, None, None, None),
+            Err(SelectLabelPrinterError::NoLabelPrinter)
+        );
+        assert_eq!(selected_id(None, None), Ok(None));
+        assert_eq!(
+            upsert_label_printer(
+                &ctx,
+                UpsertLabelPrinter {
+                    address: "printer.local".to_string(),
+                    ..printer("invalid")
+                }
+            ),
+            Err(UpsertLabelPrinterError::InvalidAddress)
+        );
+
+        upsert_label_printer(&ctx, printer("all_stores")).unwrap();
+        upsert_label_printer(
+            &ctx,
+            UpsertLabelPrinter {
+                store_id: Some(mock_store_a().id),
+                purpose: Some(LabelTemplateType::Shipment),
+                ..printer("store_a_shipments")
+            },
+        )
+        .unwrap();
+        upsert_label_printer(
+            &ctx,
+            UpsertLabelPrinter {
+                store_id: Some(mock_store_b().id),
+                ..printer("store_b")
+            },
+        )
+        .unwrap();
+        upsert_label_printer(
+            &ctx,
+            UpsertLabelPrinter {
+                store_id: Some(mock_store_a().id),
+                is_active: false,
+                ..printer("store_a_inactive")
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            selected_id(None, Some(&LabelTemplateType::Shipment)),
+            Ok(Some("store_a_shipments".to_string()))
+        );
+        assert_eq!(
+            selected_id(None, Some(&LabelTemplateType::StockLine)),
+            Ok(Some("all_stores".to_string()))
+        );
+        assert_eq!(
+            selected_id(Some("store_b"), None),
+            Err(SelectLabelPrinterError::LabelPrinterDoesNotExist)
+        );
+        assert_eq!(
+            selected_id(Some("store_a_inactive"), None),
+            Err(SelectLabelPrinterError::LabelPrinterDoesNotExist)
+        );
+
+        assert_eq!(
+            delete_label_printer(&ctx, "all_stores"),
+            Ok("all_stores".to_string())
+        );
+        assert_eq!(
+            selected_id(None, Some(&LabelTemplateType::StockLine)),
+            Ok(None)
+        );
+    }
+}
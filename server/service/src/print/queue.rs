@@ -0,0 +1,475 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use chrono::{NaiveDateTime, Utc};
+use repository::{
+    LabelPrinterRowRepository, LabelPrinterStatus, PrintJobRow, PrintJobRowRepository,
+    PrintJobStatus, RepositoryError, StorageConnection,
+};
+use util::uuid::uuid;
+
+use crate::{
+    log_context::LogContext,
+    service_provider::{ServiceContext, ServiceProvider},
+    settings::LabelPrinterSettingNode,
+};
+
+use super::{
+    jetdirect::{Jetdirect, Mode},
+    label::host_status,
+    printer::LabelPrinter,
+};
+
+const PRINT_QUEUE_INTERVAL_SECONDS: u64 = 5;
+/// Printer health is checked every this many queue intervals
+const HEALTH_CHECK_INTERVALS: u32 = 12;
+/// Attempts to send a job before it fails
+const MAX_ATTEMPTS: i32 = 5;
+/// Delay before the first retry, doubled for each following retry
+const RETRY_DELAY_SECONDS: i64 = 30;
+const PRINT_JOB_RETENTION_DAYS: i64 = 7;
+/// Latest jobs of a store returned by `get_print_jobs`
+const PRINT_JOB_LIMIT: i64 = 100;
+
+#[derive(Debug, PartialEq)]
+pub enum UpdatePrintJobError {
+    PrintJobDoesNotExist,
+    PrintJobDoesNotBelongToStore,
+    /// Only pending jobs can be cancelled and only failed or cancelled jobs retried
+    InvalidStatus,
+    DatabaseError(RepositoryError),
+}
+
+impl From<RepositoryError> for UpdatePrintJobError {
+    fn from(error: RepositoryError) -> Self {
+        UpdatePrintJobError::DatabaseError(error)
+    }
+}
+
+/// Queues the ZPL for the printer, it's sent by the `PrintQueueDriver`
+pub fn enqueue_print_job(
+    ctx: &ServiceContext,
+    printer: &LabelPrinter,
+    payload: String,
+    label_count: usize,
+) -> Result<PrintJobRow, RepositoryError> {
+    let now = Utc::now().naive_utc();
+    let non_empty = |value: &str| Some(value.to_string()).filter(|value| !value.is_empty());
+    let job = PrintJobRow {
+        id: uuid(),
+        label_printer_id: printer.id().map(str::to_string),
+        store_id: non_empty(&ctx.store_id),
+        user_id: non_empty(&ctx.user_id),
+        payload,
+        label_count: label_count as i32,
+        status: PrintJobStatus::Pending,
+        attempts: 0,
+        error: None,
+        created_datetime: now,
+        next_attempt_datetime: now,
+        printed_datetime: None,
+    };
+    PrintJobRowRepository::new(&ctx.connection).upsert_one(&job)?;
+    Ok(job)
+}
+
+/// Latest print jobs of the store of the context
+pub fn get_print_jobs(ctx: &ServiceContext) -> Result<Vec<PrintJobRow>, RepositoryError> {
+    PrintJobRowRepository::new(&ctx.connection)
+        .find_many_by_store_id(&ctx.store_id, PRINT_JOB_LIMIT)
+}
+
+pub fn cancel_print_job(
+    ctx: &ServiceContext,
+    id: &str,
+) -> Result<PrintJobRow, UpdatePrintJobError> {
+    let mut job = find_store_print_job(ctx, id)?;
+    if job.status != PrintJobStatus::Pending {
+        return Err(UpdatePrintJobError::InvalidStatus);
+    }
+    job.status = PrintJobStatus::Cancelled;
+    PrintJobRowRepository::new(&ctx.connection).upsert_one(&job)?;
+    Ok(job)
+}
+
+/// Queues a failed or cancelled job again, with all attempts
+pub fn retry_print_job(ctx: &ServiceContext, id: &str) -> Result<PrintJobRow, UpdatePrintJobError> {
+    let mut job = find_store_print_job(ctx, id)?;
+    if !matches!(
+        job.status,
+        PrintJobStatus::Failed | PrintJobStatus::Cancelled
+    ) {
+        return Err(UpdatePrintJobError::InvalidStatus);
+    }
+    job.status = PrintJobStatus::Pending;
+    job.attempts = 0;
+    job.error = None;
+    job.next_attempt_datetime = Utc::now().naive_utc();
+    PrintJobRowRepository::new(&ctx.connection).upsert_one(&job)?;
+    Ok(job)
+}
+
+fn find_store_print_job(
+    ctx: &ServiceContext,
+    id: &str,
+) -> Result<PrintJobRow, UpdatePrintJobError> {
+    let job = PrintJobRowRepository::new(&ctx.connection)
+        .find_one_by_id(id)?
+        .ok_or(UpdatePrintJobError::PrintJobDoesNotExist)?;
+    if job.store_id.as_deref() != Some(ctx.store_id.as_str()) {
+        return Err(UpdatePrintJobError::PrintJobDoesNotBelongToStore);
+    }
+    Ok(job)
+}
+
+/// Sends queued print jobs every few seconds and checks the health of the label printers
+/// every minute
+#[derive(Default)]
+pub struct PrintQueueDriver;
+
+impl PrintQueueDriver {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn run(self, service_provider: Arc<ServiceProvider>) {
+        let mut interval = tokio::time::interval(Duration::from_secs(PRINT_QUEUE_INTERVAL_SECONDS));
+        let mut intervals = 0;
+        loop {
+            interval.tick().await;
+
+            let check_health = intervals % HEALTH_CHECK_INTERVALS == 0;
+            intervals = intervals.wrapping_add(1);
+            // Sending jobs and checking printers uses blocking sockets, run it outside of the
+            // async executor
+            let service_provider = service_provider.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                LogContext::new("print_queue").sync_scope(|| {
+                    let ctx = service_provider.basic_context()?;
+                    let now = Utc::now().naive_utc();
+                    if check_health {
+                        check_label_printers(&ctx.connection, now)?;
+                    }
+                    let default_printer = service_provider
+                        .label_printer_settings_service
+                        .label_printer_settings(&ctx)?;
+                    process_print_queue(&ctx.connection, default_printer.as_ref(), now)
+                })
+            })
+            .await;
+            match result {
+                Ok(Ok(_)) => {}
+                Ok(Err(error)) => log::error!("Error in print queue ({})", error),
+                Err(error) => log::error!("Print queue panicked ({})", error),
+            }
+        }
+    }
+}
+
+/// Sends the due jobs to their printers and deletes finished jobs after the retention period,
+/// returns the number of printed jobs. Labels are printed in order, later jobs of a printer are
+/// held while an earlier job of the printer waits for its next attempt or failed in this run.
+pub(crate) fn process_print_queue(
+    connection: &StorageConnection,
+    default_printer: Option<&LabelPrinterSettingNode>,
+    now: NaiveDateTime,
+) -> Result<usize, RepositoryError> {
+    let job_repo = PrintJobRowRepository::new(connection);
+    let printer_repo = LabelPrinterRowRepository::new(connection);
+
+    let mut held_printers = HashSet::new();
+    let mut printed = 0;
+    for mut job in job_repo.find_pending()? {
+        if held_printers.contains(&job.label_printer_id) {
+            continue;
+        }
+        if job.next_attempt_datetime > now {
+            held_printers.insert(job.label_printer_id.clone());
+            continue;
+        }
+        let printer = match &job.label_printer_id {
+            Some(id) => printer_repo.find_one_by_id(id)?.map(LabelPrinter::Named),
+            None => default_printer.cloned().map(LabelPrinter::Default),
+        };
+        let printer = match printer {
+            // jobs of inactive printers are held until the printer is activated
+            Some(LabelPrinter::Named(row)) if !row.is_active => continue,
+            Some(printer) => printer,
+            None => {
+                job.status = PrintJobStatus::Failed;
+                job.error = Some("Label printer not found".to_string());
+                job_repo.upsert_one(&job)?;
+                continue;
+            }
+        };
+
+        let (address, port) = printer.address();
+        job.attempts += 1;
+        match Jetdirect::new(address, port).send_string(job.payload.clone(), Mode::Print) {
+            Ok(_) => {
+                job.status = PrintJobStatus::Printed;
+                job.error = None;
+                job.printed_datetime = Some(now);
+                printed += 1;
+            }
+            Err(error) => {
+                log::error!("Failed to send print job {}: {}", job.id, error);
+                job.error = Some(error.to_string());
+                if job.attempts >= MAX_ATTEMPTS {
+                    job.status = PrintJobStatus::Failed;
+                } else {
+                    let delay = RETRY_DELAY_SECONDS * 2_i64.pow(job.attempts as u32 - 1);
+                    job.next_attempt_datetime = now + chrono::Duration::seconds(delay);
+                }
+                held_printers.insert(job.label_printer_id.clone());
+            }
+        }
+        job_repo.upsert_one(&job)?;
+    }
+
+    job_repo.delete_finished_before(now - chrono::Duration::days(PRINT_JOB_RETENTION_DAYS))?;
+    Ok(printed)
+}
+
+/// Updates the status of the active printers from their host status
+pub(crate) fn check_label_printers(
+    connection: &StorageConnection,
+    now: NaiveDateTime,
+) -> Result<(), RepositoryError> {
+    let repo = LabelPrinterRowRepository::new(connection);
+    for printer in repo.find_all()? {
+        if !printer.is_active {
+            continue;
+        }
+        let (status, message) = match host_status(printer.address.clone(), printer.port as u16) {
+            Ok(response) if response.problems().is_empty() => (LabelPrinterStatus::Ready, None),
+            Ok(response) => (
+                LabelPrinterStatus::Error,
+                Some(response.problems().join(", ")),
+            ),
+            Err(error) => (LabelPrinterStatus::Unreachable, Some(error.to_string())),
+        };
+        repo.update_status(&printer.id, status, message, now)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::Read,
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    use repository::{
+        mock::{mock_store_a, mock_user_account_a, MockDataInserts},
+        test_db::setup_all,
+    };
+
+    use crate::{
+        print::printer::{upsert_label_printer, UpsertLabelPrinter},
+        service_provider::ServiceProvider,
+    };
+
+    use super::*;
+
+    /// Accepts connections on a local port and collects the received data
+    fn printer_sink() -> (u16, Arc<Mutex<Vec<u8>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut data = Vec::new();
+                let _ = stream.unwrap().read_to_end(&mut data);
+                sink.lock().unwrap().extend(data);
+            }
+        });
+        (port, received)
+    }
+
+    /// Port without a listener, connections are refused
+    fn closed_port() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    #[actix_rt::test]
+    async fn test_print_queue() {
+        let (_, connection, connection_manager, _) =
+            setup_all("test_print_queue", MockDataInserts::none().names().stores()).await;
+        let service_provider = ServiceProvider::new(connection_manager, "app_data");
+        let ctx = service_provider
+            .context(mock_store_a().id, mock_user_account_a().id)
+            .unwrap();
+
+        let (port, received) = printer_sink();
+        let printer = |id: &str, port: u16| UpsertLabelPrinter {
+            id: id.to_string(),
+            name: id.to_string(),
+            address: "127.0.0.1".to_string(),
+            port,
+            label_width: 812,
+            label_height: 406,
+            store_id: None,
+            purpose: None,
+            is_active: true,
+        };
+        let online = upsert_label_printer(&ctx, printer("online", port)).unwrap();
+        let offline = upsert_label_printer(&ctx, printer("offline", closed_port())).unwrap();
+
+        let online_job = enqueue_print_job(
+            &ctx,
+            &LabelPrinter::Named(online),
+            "^XA^FDLabel^FS^XZ".to_string(),
+            1,
+        )
+        .unwrap();
+        let offline_job = enqueue_print_job(
+            &ctx,
+            &LabelPrinter::Named(offline.clone()),
+            "^XA^XZ".to_string(),
+            1,
+        )
+        .unwrap();
+        let default_job = enqueue_print_job(
+            &ctx,
+            &LabelPrinter::Default(LabelPrinterSettingNode {
+                address: "127.0.0.1".to_string(),
+                label_height: 406,
+                label_width: 812,
+                port,
+            }),
+            "".to_string(),
+            1,
+        )
+        .unwrap();
+
+        let now = Utc::now().naive_utc();
+        assert_eq!(process_print_queue(&connection, None, now), Ok(1));
+
+        let job_repo = PrintJobRowRepository::new(&connection);
+        let job = |id: &str| job_repo.find_one_by_id(id).unwrap().unwrap();
+        assert_eq!(job(&online_job.id).status, PrintJobStatus::Printed);
+        // the sink receives the data once the connection is closed
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(&*received.lock().unwrap(), b"^XA^FDLabel^FS^XZ");
+
+        // retried later
+        let failed = job(&offline_job.id);
+        assert_eq!(failed.status, PrintJobStatus::Pending);
+        assert_eq!(failed.attempts, 1);
+        assert!(failed.error.is_some());
+        assert_eq!(
+            failed.next_attempt_datetime,
+            now + chrono::Duration::seconds(RETRY_DELAY_SECONDS)
+        );
+        assert_eq!(process_print_queue(&connection, None, now), Ok(0));
+        assert_eq!(job(&offline_job.id).attempts, 1);
+
+        // no label printer settings
+        assert_eq!(job(&default_job.id).status, PrintJobStatus::Failed);
+
+        let mut later = now;
+        for _ in 1..MAX_ATTEMPTS {
+            later += chrono::Duration::days(1);
+            process_print_queue(&connection, None, later).unwrap();
+        }
+        let failed = job(&offline_job.id);
+        assert_eq!(failed.status, PrintJobStatus::Failed);
+        assert_eq!(failed.attempts, MAX_ATTEMPTS);
+
+        assert_eq!(
+            cancel_print_job(&ctx, &offline_job.id),
+            Err(UpdatePrintJobError::InvalidStatus)
+        );
+        let retried = retry_print_job(&ctx, &offline_job.id).unwrap();
+        assert_eq!(
+            (retried.status, retried.attempts),
+            (PrintJobStatus::Pending, 0)
+        );
+        assert_eq!(
+            cancel_print_job(&ctx, &offline_job.id).map(|job| job.status),
+            Ok(PrintJobStatus::Cancelled)
+        );
+
+        check_label_printers(&connection, now).unwrap();
+        let printer_repo = LabelPrinterRowRepository::new(&connection);
+        assert_eq!(
+            printer_repo
+                .find_one_by_id(&offline.id)
+                .unwrap()
+                .unwrap()
+                .status,
+            LabelPrinterStatus::Unreachable
+        );
+
+        // finished jobs are deleted after the retention period
+        process_print_queue(&connection, None, later + chrono::Duration::days(8)).unwrap();
+        assert_eq!(get_print_jobs(&ctx), Ok(vec![]));
+    }
+
+    #[actix_rt::test]
+    async fn test_print_queue_order() {
+        let (_, connection, connection_manager, _) = setup_all(
+            "test_print_queue_order",
+            MockDataInserts::none().names().stores(),
+        )
+        .await;
+        let service_provider = ServiceProvider::new(connection_manager, "app_data");
+        let ctx = service_provider
+            .context(mock_store_a().id, mock_user_account_a().id)
+            .unwrap();
+
+        let printer = |port: u16| UpsertLabelPrinter {
+            id: "printer".to_string(),
+            name: "printer".to_string(),
+            address: "127.0.0.1".to_string(),
+            port,
+            label_width: 812,
+            label_height: 406,
+            store_id: None,
+            purpose: None,
+            is_active: true,
+        };
+        let offline = upsert_label_printer(&ctx, printer(closed_port())).unwrap();
+        let first_job = enqueue_print_job(
+            &ctx,
+            &LabelPrinter::Named(offline),
+            "^XA^FDFirst^FS^XZ".to_string(),
+            1,
+        )
+        .unwrap();
+        let now = Utc::now().naive_utc();
+        assert_eq!(process_print_queue(&connection, None, now), Ok(0));
+
+        // printer back online, job queued while the first job waits for its next attempt
+        let (port, received) = printer_sink();
+        let online = upsert_label_printer(&ctx, printer(port)).unwrap();
+        let second_job = enqueue_print_job(
+            &ctx,
+            &LabelPrinter::Named(online),
+            "^XA^FDSecond^FS^XZ".to_string(),
+            1,
+        )
+        .unwrap();
+        let now = Utc::now().naive_utc();
+        assert_eq!(process_print_queue(&connection, None, now), Ok(0));
+
+        let job_repo = PrintJobRowRepository::new(&connection);
+        let job = |id: &str| job_repo.find_one_by_id(id).unwrap().unwrap();
+        let held = job(&second_job.id);
+        assert_eq!((held.status, held.attempts), (PrintJobStatus::Pending, 0));
+
+        let later = now + chrono::Duration::seconds(RETRY_DELAY_SECONDS);
+        assert_eq!(process_print_queue(&connection, None, later), Ok(2));
+        assert_eq!(job(&first_job.id).status, PrintJobStatus::Printed);
+        assert_eq!(job(&second_job.id).status, PrintJobStatus::Printed);
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(
+            &*received.lock().unwrap(),
+            b"^XA^FDFirst^FS^XZ^XA^FDSecond^FS^XZ"
+        );
+    }
+}
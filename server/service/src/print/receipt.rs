@@ -0,0 +1,290 @@
+//! Prescription receipts for ESC/POS printers, with a store and patient/prescriber header, the
+//! dispensed items with their directions (the line note), quantity and prices including tax, and
+//! optionally the payment
+use chrono::{Local, NaiveDateTime, TimeZone};
+use repository::{
+    EqualFilter, Invoice, InvoiceFilter, InvoiceLine, InvoiceLineFilter, InvoiceLineRepository,
+    InvoiceLineType, InvoiceRepository, InvoiceType, NameRowRepository, RepositoryError,
+};
+
+use crate::service_provider::ServiceContext;
+
+use super::escpos::{Align, EscPos, ReceiptPrinterSettings};
+
+pub struct PrintReceipt {
+    pub invoice_id: String,
+    /// Prints the amount paid and the change when set
+    pub payment: Option<ReceiptPayment>,
+}
+
+pub struct ReceiptPayment {
+    /// E.g. cash or card
+    pub method: String,
+    pub amount_tendered: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PrintReceiptError {
+    InvoiceDoesNotExist,
+    NotThisStoreInvoice,
+    NotAPrescriptionInvoice,
+    /// The amount tendered is less than the total
+    InsufficientPayment,
+    ReceiptPrinterNotConfigured,
+    PrinterError(String),
+    DatabaseError(RepositoryError),
+}
+
+impl From<RepositoryError> for PrintReceiptError {
+    fn from(error: RepositoryError) -> Self {
+        PrintReceiptError::DatabaseError(error)
+    }
+}
+
+/// Renders the receipt of the prescription and sends it to the receipt printer
+pub fn print_prescription_receipt(
+    ctx: &ServiceContext,
+    settings: Option<&ReceiptPrinterSettings>,
+    input: &PrintReceipt,
+) -> Result<(), PrintReceiptError> {
+    let settings = settings.ok_or(PrintReceiptError::ReceiptPrinterNotConfigured)?;
+    let receipt = generate_prescription_receipt(ctx, settings.line_width, input)?;
+    settings
+        .target
+        .send(&receipt)
+        .map_err(|err| PrintReceiptError::PrinterError(err.to_string()))
+}
+
+/// ESC/POS commands of the receipt, `line_width` is the number of characters per line
+pub fn generate_prescription_receipt(
+    ctx: &ServiceContext,
+    line_width: usize,
+    input: &PrintReceipt,
+) -> Result<Vec<u8>, PrintReceiptError> {
+    let invoice = InvoiceRepository::new(&ctx.connection)
+        .query_one(InvoiceFilter::new().id(EqualFilter::equal_to(&input.invoice_id)))?
+        .ok_or(PrintReceiptError::InvoiceDoesNotExist)?;
+    if invoice.invoice_row.store_id != ctx.store_id {
+        return Err(PrintReceiptError::NotThisStoreInvoice);
+    }
+    if invoice.invoice_row.r#type != InvoiceType::Prescription {
+        return Err(PrintReceiptError::NotAPrescriptionInvoice);
+    }
+    let lines = InvoiceLineRepository::new(&ctx.connection).query_by_filter(
+        InvoiceLineFilter::new()
+            .invoice_id(EqualFilter::equal_to(&input.invoice_id))
+            .r#type(InvoiceLineType::StockOut.equal_to()),
+    )?;
+    let store_name = NameRowRepository::new(&ctx.connection)
+        .find_one_by_id(&invoice.store_row.name_id)?
+        .map(|name| name.name)
+        .unwrap_or_else(|| invoice.store_row.code.clone());
+
+    let total_before_tax: f64 = lines
+        .iter()
+        .map(|line| line.invoice_line_row.total_before_tax)
+        .sum();
+    let total: f64 = lines
+        .iter()
+        .map(|line| line.invoice_line_row.total_after_tax)
+        .sum();
+    if let Some(payment) = &input.payment {
+        // rounded to cents, so that a payment of the printed total is sufficient
+        if (payment.amount_tendered * 100.0).round() < (total * 100.0).round() {
+            return Err(PrintReceiptError::InsufficientPayment);
+        }
+    }
+
+    let mut receipt = EscPos::new(line_width);
+    header(&mut receipt, &store_name, &invoice);
+    for line in &lines {
+        item(&mut receipt, line);
+    }
+    receipt
+        .separator()
+        .columns("Subtotal", &money(total_before_tax))
+        .columns("Tax", &money(total - total_before_tax))
+        .bold(true)
+        .columns("Total", &money(total))
+        .bold(false);
+    if let Some(payment) = &input.payment {
+        receipt
+            .columns(
+                &format!("Paid ({})", payment.method),
+                &money(payment.amount_tendered),
+            )
+            .columns("Change", &money(payment.amount_tendered - total));
+    }
+    receipt.feed(3).cut();
+
+    Ok(receipt.into_bytes())
+}
+
+fn header(receipt: &mut EscPos, store_name: &str, invoice: &Invoice) {
+    let row = &invoice.invoice_row;
+    let datetime = row.picked_datetime.unwrap_or(row.created_datetime);
+    receipt
+        .align(Align::Center)
+        .bold(true)
+        .double_size(true)
+        .line(store_name)
+        .double_size(false)
+        .bold(false)
+        .line(&format!("Prescription {}", row.invoice_number))
+        .line(&local_datetime(datetime))
+        .align(Align::Left)
+        .separator();
+
+    let patient = &invoice.name_row;
+    receipt.line(&format!("Patient: {} ({})", patient.name, patient.code));
+    if let Some(date_of_birth) = patient.date_of_birth {
+        receipt.line(&format!(
+            "Date of birth: {}",
+            date_of_birth.format("%Y-%m-%d")
+        ));
+    }
+    if let Some(clinician) = &invoice.clinician_row {
+        let name = match &clinician.first_name {
+            Some(first_name) => format!("{} {}", first_name, clinician.last_name),
+            None => clinician.last_name.clone(),
+        };
+        receipt.line(&format!("Prescriber: {} ({})", name, clinician.code));
+    }
+    receipt.separator();
+}
+
+fn item(receipt: &mut EscPos, line: &InvoiceLine) {
+    let row = &line.invoice_line_row;
+    let name = if row.item_name.is_empty() {
+        &line.item_row.name
+    } else {
+        &row.item_name
+    };
+    receipt.bold(true).line(name).bold(false);
+    if let Some(directions) = row.note.as_deref().filter(|note| !note.trim().is_empty()) {
+        receipt.line(directions);
+    }
+    let units = row.number_of_packs * row.pack_size as f64;
+    let mut quantity = format!(
+        "{} x {}",
+        number(units),
+        money(row.sell_price_per_pack / row.pack_size.max(1) as f64)
+    );
+    if let Some(tax_percentage) = row.tax_percentage.filter(|tax| *tax != 0.0) {
+        quantity.push_str(&format!(" +{}% tax", number(tax_percentage)));
+    }
+    receipt.columns(&format!("  {}", quantity), &money(row.total_after_tax));
+}
+
+fn local_datetime(datetime: NaiveDateTime) -> String {
+    Local
+        .from_utc_datetime(&datetime)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+fn money(amount: f64) -> String {
+    format!("{:.2}", amount)
+}
+
+/// Number without trailing zeros, e.g. 2.5 instead of 2.50
+fn number(value: f64) -> String {
+    let formatted = format!("{:.3}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use repository::{
+        mock::{
+            mock_outbound_shipment_a, mock_prescription_a, mock_prescription_a_invoice_line_a,
+            mock_store_a, mock_store_b, mock_user_account_a, MockDataInserts,
+        },
+        test_db::setup_all,
+        InvoiceLineRowRepository,
+    };
+
+    use crate::service_provider::ServiceProvider;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_prescription_receipt() {
+        let (_, connection, connection_manager, _) =
+            setup_all("test_prescription_receipt", MockDataInserts::all()).await;
+        let service_provider = ServiceProvider::new(connection_manager, "app_data");
+        let ctx = service_provider
+            .context(mock_store_a().id, mock_user_account_a().id)
+            .unwrap();
+        let receipt = |invoice_id: &str, payment: Option<ReceiptPayment>| {
+            generate_prescription_receipt(
+                &ctx,
+                40,
+                &PrintReceipt {
+                    invoice_id: invoice_id.to_string(),
+                    payment,
+                },
+            )
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        };
+
+        assert_eq!(
+            receipt("invalid", None),
+            Err(PrintReceiptError::InvoiceDoesNotExist)
+        );
+        assert_eq!(
+            receipt(&mock_outbound_shipment_a().id, None),
+            Err(PrintReceiptError::NotAPrescriptionInvoice)
+        );
+
+        let mut line = mock_prescription_a_invoice_line_a();
+        line.note = Some("Take one tablet twice daily".to_string());
+        line.tax_percentage = Some(10.0);
+        line.total_after_tax = 11.0;
+        InvoiceLineRowRepository::new(&connection)
+            .upsert_one(&line)
+            .unwrap();
+
+        let payment = |amount_tendered| ReceiptPayment {
+            method: "Cash".to_string(),
+            amount_tendered,
+        };
+        assert_eq!(
+            receipt(&mock_prescription_a().id, Some(payment(60.99))),
+            Err(PrintReceiptError::InsufficientPayment)
+        );
+        let text = receipt(&mock_prescription_a().id, Some(payment(100.0))).unwrap();
+        for expected in [
+            "Prescription 1\n",
+            "Patient: testId (testId)\n",
+            "Item A\n",
+            "Take one tablet twice daily\n",
+            "  5 x 18.00 +10% tax               11.00\n",
+            "Subtotal                           60.00\n",
+            "Tax                                 1.00\n",
+            "Total                              61.00\n",
+            "Paid (Cash)                       100.00\n",
+            "Change                             39.00\n",
+        ] {
+            assert!(text.contains(expected), "{} not in {}", expected, text);
+        }
+
+        let ctx = service_provider
+            .context(mock_store_b().id, mock_user_account_a().id)
+            .unwrap();
+        assert_eq!(
+            generate_prescription_receipt(
+                &ctx,
+                40,
+                &PrintReceipt {
+                    invoice_id: mock_prescription_a().id,
+                    payment: None,
+                }
+            ),
+            Err(PrintReceiptError::NotThisStoreInvoice)
+        );
+    }
+}
@@ -0,0 +1,717 @@
+//! Renders ZPL labels to png for print previews.
+//!
+//! Only the commands used by label templates are supported: field origin and label home
+//! (`^FO`, `^LH`), scalable font text (`^A0`, `^CF`) with field blocks (`^FB`), boxes and lines
+//! (`^GB`), QR codes (`^BQ`) and Code 128 barcodes (`^BC`, `^BY`), including GS1-128 (mode D).
+//! Other commands are ignored, the preview is an approximation of the printed label.
+use std::convert::Infallible;
+
+use anyhow::anyhow;
+use embedded_graphics::{
+    mono_font::{iso_8859_1::FONT_10X20, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{Baseline, Text},
+};
+use qrcode::{Color, EcLevel, QrCode};
+
+/// Glyph size of the font used for all ZPL fonts
+const GLYPH_WIDTH: u32 = 10;
+const GLYPH_HEIGHT: u32 = 20;
+/// Default font height of the printer (font 0 is used for all fonts)
+const DEFAULT_FONT_HEIGHT: u32 = 30;
+/// Height of the interpretation line printed below Code 128 barcodes
+const INTERPRETATION_LINE_HEIGHT: u32 = 24;
+
+/// Renders each `^XA ... ^XZ` label of the ZPL to a png of the given size (in dots), `^PW` and
+/// `^LL` of the label change the size
+pub fn zpl_to_png(zpl: &str, width: u32, height: u32) -> Result<Vec<Vec<u8>>, anyhow::Error> {
+    let mut labels = Vec::new();
+    let mut renderer: Option<Renderer> = None;
+    for command in parse(zpl) {
+        match (command.name.as_str(), renderer.as_mut()) {
+            ("XA", _) => renderer = Some(Renderer::new(width, height)),
+            ("XZ", Some(_)) => {
+                if let Some(renderer) = renderer.take() {
+                    labels.push(renderer.canvas.to_png()?);
+                }
+            }
+            (_, Some(renderer)) => renderer.apply(&command)?,
+            // commands outside of a label, e.g. host status requests
+            (_, None) => {}
+        }
+    }
+    Ok(labels)
+}
+
+#[derive(Debug, PartialEq)]
+struct Command {
+    /// Command name without the prefix, e.g. `FO`
+    name: String,
+    params: String,
+}
+
+impl Command {
+    /// Comma separated parameter, None if empty
+    fn param(&self, index: usize) -> Option<&str> {
+        self.params
+            .split(',')
+            .nth(index)
+            .map(str::trim)
+            .filter(|param| !param.is_empty())
+    }
+
+    fn number(&self, index: usize) -> Option<u32> {
+        self.param(index).and_then(|param| param.parse().ok())
+    }
+}
+
+fn parse(zpl: &str) -> Vec<Command> {
+    let mut commands = Vec::new();
+    let mut chars = zpl.chars().peekable();
+    while let Some(char) = chars.next() {
+        if char != '^' && char != '~' {
+            continue;
+        }
+        let name: String = chars.by_ref().take(2).collect::<String>().to_uppercase();
+        // the font command is followed by the font name, e.g. `^A0N,30`, which is dropped
+        let name = match name.strip_prefix('A') {
+            Some(font) if font != "@" => "A".to_string(),
+            _ => name,
+        };
+        let mut params = String::new();
+        while let Some(char) = chars.peek() {
+            if *char == '^' || *char == '~' {
+                break;
+            }
+            params.push(*char);
+            chars.next();
+        }
+        let params = params.trim_end_matches(['\r', '\n']).to_string();
+        commands.push(Command { name, params });
+    }
+    commands
+}
+
+#[derive(Clone, Copy)]
+struct Font {
+    height: u32,
+    width: u32,
+}
+
+enum FieldType {
+    Text,
+    Qr {
+        magnification: u32,
+    },
+    Code128 {
+        height: u32,
+        interpretation: bool,
+        gs1: bool,
+    },
+    Box {
+        width: u32,
+        height: u32,
+        thickness: u32,
+    },
+}
+
+struct FieldBlock {
+    width: u32,
+    lines: u32,
+    justification: char,
+}
+
+struct Renderer {
+    canvas: Canvas,
+    home: (u32, u32),
+    origin: (u32, u32),
+    default_font: Font,
+    font: Option<Font>,
+    module_width: u32,
+    bar_height: u32,
+    field_type: FieldType,
+    field_block: Option<FieldBlock>,
+    data: Option<String>,
+}
+
+impl Renderer {
+    fn new(width: u32, height: u32) -> Renderer {
+        Renderer {
+            canvas: Canvas::new(width, height),
+            home: (0, 0),
+            origin: (0, 0),
+            default_font: Font {
+                height: DEFAULT_FONT_HEIGHT,
+                width: DEFAULT_FONT_HEIGHT,
+            },
+            font: None,
+            module_width: 2,
+            bar_height: 10,
+            field_type: FieldType::Text,
+            field_block: None,
+            data: None,
+        }
+    }
+
+    fn apply(&mut self, command: &Command) -> Result<(), anyhow::Error> {
+        match command.name.as_str() {
+            "PW" => {
+                if let Some(width) = command.number(0) {
+                    self.canvas.resize(width, self.canvas.height)
+                }
+            }
+            "LL" => {
+                if let Some(height) = command.number(0) {
+                    self.canvas.resize(self.canvas.width, height)
+                }
+            }
+            "LH" => {
+                self.home = (
+                    command.number(0).unwrap_or(0),
+                    command.number(1).unwrap_or(0),
+                )
+            }
+            "FO" | "FT" => {
+                self.origin = (
+                    self.home.0 + command.number(0).unwrap_or(0),
+                    self.home.1 + command.number(1).unwrap_or(0),
+                )
+            }
+            "CF" => {
+                let height = command.number(1).unwrap_or(self.default_font.height);
+                self.default_font = Font {
+                    height,
+                    width: command.number(2).unwrap_or(height),
+                };
+            }
+            "A" => {
+                // orientation, height and width, e.g. `^A0N,30,25`
+                let height = command.number(1).unwrap_or(self.default_font.height);
+                self.font = Some(Font {
+                    height,
+                    width: command.number(2).unwrap_or(height),
+                });
+            }
+            "FB" => {
+                self.field_block = Some(FieldBlock {
+                    width: command.number(0).unwrap_or(0),
+                    lines: command.number(1).unwrap_or(1).max(1),
+                    justification: command
+                        .param(3)
+                        .and_then(|param| param.chars().next())
+                        .unwrap_or('L'),
+                })
+            }
+            "BY" => {
+                self.module_width = command.number(0).unwrap_or(self.module_width).max(1);
+                self.bar_height = command.number(2).unwrap_or(self.bar_height);
+            }
+            "BQ" => {
+                self.field_type = FieldType::Qr {
+                    magnification: command.number(2).unwrap_or(2).clamp(1, 10),
+                }
+            }
+            "BC" => {
+                self.field_type = FieldType::Code128 {
+                    height: command.number(1).unwrap_or(self.bar_height),
+                    interpretation: command.param(2) != Some("N"),
+                    gs1: command.param(5) == Some("D"),
+                }
+            }
+            "GB" => {
+                let thickness = command.number(2).unwrap_or(1).max(1);
+                self.field_type = FieldType::Box {
+                    width: command.number(0).unwrap_or(thickness),
+                    height: command.number(1).unwrap_or(thickness),
+                    thickness,
+                }
+            }
+            "FD" => self.data = Some(command.params.clone()),
+            "FS" => self.field()?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Draws the field at `^FS` and resets the field settings
+    fn field(&mut self) -> Result<(), anyhow::Error> {
+        let field_type = std::mem::replace(&mut self.field_type, FieldType::Text);
+        let field_block = self.field_block.take();
+        let font = self.font.take().unwrap_or(self.default_font);
+        let data = self.data.take().unwrap_or_default();
+        let (x, y) = self.origin;
+
+        match field_type {
+            FieldType::Text => self.text(x, y, &data, font, field_block),
+            FieldType::Qr { magnification } => {
+                // the data starts with the error correction level and input mode, e.g. `MA,`
+                let (ec_level, data) = match data.split_once(',') {
+                    Some((options, data)) => (options.chars().next(), data),
+                    None => (None, data.as_str()),
+                };
+                let ec_level = match ec_level {
+                    Some('H') => EcLevel::H,
+                    Some('Q') => EcLevel::Q,
+                    Some('L') => EcLevel::L,
+                    _ => EcLevel::M,
+                };
+                let code = QrCode::with_error_correction_level(data, ec_level)
+                    .map_err(|err| anyhow!("Failed to encode QR code: {}", err))?;
+                let size = code.width();
+                for row in 0..size {
+                    for column in 0..size {
+                        if code[(column, row)] == Color::Dark {
+                            self.canvas.fill(
+                                x + column as u32 * magnification,
+                                y + row as u32 * magnification,
+                                magnification,
+                                magnification,
+                            );
+                        }
+                    }
+                }
+            }
+            FieldType::Code128 {
+                height,
+                interpretation,
+                gs1,
+            } => {
+                let (symbols, text) = code128(&data, gs1)?;
+                let mut bar_x = x;
+                for symbol in symbols {
+                    for (index, width) in CODE128_PATTERNS[symbol].bytes().enumerate() {
+                        let width = (width - b'0') as u32 * self.module_width;
+                        // patterns start with a bar and alternate between bars and spaces
+                        if index % 2 == 0 {
+                            self.canvas.fill(bar_x, y, width, height);
+                        }
+                        bar_x += width;
+                    }
+                }
+                if interpretation {
+                    let font = Font {
+                        height: INTERPRETATION_LINE_HEIGHT,
+                        width: INTERPRETATION_LINE_HEIGHT,
+                    };
+                    let block = FieldBlock {
+                        width: bar_x - x,
+                        lines: 1,
+                        justification: 'C',
+                    };
+                    self.text(x, y + height + 2, &text, font, Some(block));
+                }
+            }
+            FieldType::Box {
+                width,
+                height,
+                thickness,
+            } => {
+                let thickness_x = thickness.min(width);
+                let thickness_y = thickness.min(height);
+                self.canvas.fill(x, y, width, thickness_y);
+                self.canvas.fill(
+                    x,
+                    (y + height).saturating_sub(thickness_y),
+                    width,
+                    thickness_y,
+                );
+                self.canvas.fill(x, y, thickness_x, height);
+                self.canvas.fill(
+                    (x + width).saturating_sub(thickness_x),
+                    y,
+                    thickness_x,
+                    height,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, x: u32, y: u32, text: &str, font: Font, block: Option<FieldBlock>) {
+        let advance = (font.width / 2).max(1);
+        let lines = match &block {
+            Some(block) if block.width > 0 => {
+                let mut lines = wrap(text, (block.width / advance).max(1) as usize);
+                lines.truncate(block.lines as usize);
+                lines
+            }
+            _ => vec![text.to_string()],
+        };
+        for (index, line) in lines.iter().enumerate() {
+            let line_width = line.chars().count() as u32 * advance;
+            let offset = match &block {
+                Some(block) if block.justification == 'C' => {
+                    block.width.saturating_sub(line_width) / 2
+                }
+                Some(block) if block.justification == 'R' => block.width.saturating_sub(line_width),
+                _ => 0,
+            };
+            self.canvas.text(
+                x + offset,
+                y + index as u32 * font.height,
+                line,
+                advance,
+                font.height,
+            );
+        }
+    }
+}
+
+/// Splits the text to lines of at most `columns` characters, at spaces if possible. `\&` is the
+/// line break of field blocks.
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split("\\&") {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let mut word = word.to_string();
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > columns {
+                lines.push(std::mem::take(&mut line));
+            }
+            while word.chars().count() > columns {
+                let rest = word.split_off(word.char_indices().nth(columns).unwrap().0);
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                lines.push(word);
+                word = rest;
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+const CODE128_START_B: usize = 104;
+const CODE128_START_C: usize = 105;
+const CODE128_CODE_B: usize = 100;
+const CODE128_CODE_C: usize = 99;
+const CODE128_FNC1: usize = 102;
+const CODE128_STOP: usize = 106;
+
+/// Bar and space widths (in modules) of the Code 128 symbols
+const CODE128_PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212",
+    "221213", "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221",
+    "223211", "221132", "221231", "213212", "223112", "312131", "311222", "321122", "321221",
+    "312212", "322112", "322211", "212123", "212321", "232121", "111323", "131123", "131321",
+    "112313", "132113", "132311", "211313", "231113", "231311", "112133", "112331", "132131",
+    "113123", "113321", "133121", "313121", "211331", "231131", "213113", "213311", "213131",
+    "311123", "311321", "331121", "312113", "312311", "332111", "314111", "221411", "431111",
+    "111224", "111422", "121124", "121421", "141122", "141221", "112214", "112412", "122114",
+    "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111", "111242",
+    "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311",
+    "113141", "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+
+/// GS1 application identifiers with a fixed length value, that aren't followed by FNC1
+const GS1_FIXED_LENGTH_IDENTIFIERS: [&str; 11] = [
+    "00", "01", "02", "11", "12", "13", "15", "16", "17", "20", "41",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Code128Token {
+    Char(char),
+    Fnc1,
+}
+
+/// Encodes the field data to Code 128 symbols (including the start, check and stop symbols)
+/// and returns the interpretation line. In GS1 mode the data contains application identifiers
+/// in parentheses, e.g. `(01)09501101020917(10)ABC`.
+fn code128(data: &str, gs1: bool) -> Result<(Vec<usize>, String), anyhow::Error> {
+    let mut tokens = Vec::new();
+    if gs1 {
+        let mut identifier: Option<String> = None;
+        let mut chars = data.chars();
+        while let Some(char) = chars.next() {
+            if char == '(' {
+                let next: String = chars.by_ref().take_while(|char| *char != ')').collect();
+                let previous_is_variable = identifier
+                    .as_ref()
+                    .map(|identifier| !GS1_FIXED_LENGTH_IDENTIFIERS.contains(&identifier.as_str()))
+                    .unwrap_or(true);
+                if previous_is_variable {
+                    tokens.push(Code128Token::Fnc1);
+                }
+                tokens.extend(next.chars().map(Code128Token::Char));
+                identifier = Some(next);
+            } else {
+                tokens.push(Code128Token::Char(char));
+            }
+        }
+    } else {
+        let mut chars = data.chars().peekable();
+        while let Some(char) = chars.next() {
+            // invocation codes, e.g. `>;` (start C), are replaced by automatic code sets
+            match (char, chars.peek()) {
+                ('>', Some('8')) => {
+                    chars.next();
+                    tokens.push(Code128Token::Fnc1);
+                }
+                ('>', Some(';' | ':' | '9' | '5' | '6' | '7')) => {
+                    chars.next();
+                }
+                (char, _) => tokens.push(Code128Token::Char(char)),
+            }
+        }
+    }
+
+    let is_digit =
+        |token: &Code128Token| matches!(token, Code128Token::Char(char) if char.is_ascii_digit());
+    // number of digits from the index, code set C is used for runs of at least 4 digits
+    let digits = |index: usize| {
+        tokens[index..]
+            .iter()
+            .take_while(|token| is_digit(token))
+            .count()
+    };
+
+    let mut symbols = Vec::new();
+    let leading_fnc1 = tokens
+        .iter()
+        .take_while(|token| **token == Code128Token::Fnc1)
+        .count();
+    let run = digits(leading_fnc1);
+    let mut code_c = run >= 4 || (run >= 2 && leading_fnc1 + run == tokens.len());
+    symbols.push(if code_c {
+        CODE128_START_C
+    } else {
+        CODE128_START_B
+    });
+    let mut index = 0;
+    while index < tokens.len() {
+        let run = digits(index);
+        if code_c && run < 2 && tokens[index] != Code128Token::Fnc1 {
+            symbols.push(CODE128_CODE_B);
+            code_c = false;
+        } else if !code_c && run >= 4 && run % 2 == 0 {
+            // the first digit of odd runs is encoded in code set B
+            symbols.push(CODE128_CODE_C);
+            code_c = true;
+        }
+
+        match tokens[index] {
+            Code128Token::Fnc1 => {
+                symbols.push(CODE128_FNC1);
+                index += 1;
+            }
+            Code128Token::Char(first) if code_c => {
+                let second = match tokens[index + 1] {
+                    Code128Token::Char(second) => second,
+                    Code128Token::Fnc1 => unreachable!(),
+                };
+                let value = format!("{}{}", first, second).parse::<usize>()?;
+                symbols.push(value);
+                index += 2;
+            }
+            Code128Token::Char(char) => {
+                let value = char as usize;
+                if !(32..=126).contains(&value) {
+                    return Err(anyhow!("Character {:?} can't be encoded in Code 128", char));
+                }
+                symbols.push(value - 32);
+                index += 1;
+            }
+        }
+    }
+
+    let checksum = symbols
+        .iter()
+        .enumerate()
+        .map(|(index, symbol)| index.max(1) * symbol)
+        .sum::<usize>()
+        % 103;
+    symbols.push(checksum);
+    symbols.push(CODE128_STOP);
+
+    let text = if gs1 {
+        data.to_string()
+    } else {
+        tokens
+            .iter()
+            .filter_map(|token| match token {
+                Code128Token::Char(char) => Some(*char),
+                Code128Token::Fnc1 => None,
+            })
+            .collect()
+    };
+    Ok((symbols, text))
+}
+
+/// Monochrome image, true is a printed dot
+struct Canvas {
+    width: u32,
+    height: u32,
+    dots: Vec<bool>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Canvas {
+        Canvas {
+            width,
+            height,
+            dots: vec![false; (width * height) as usize],
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height.min(self.height) {
+            for x in 0..width.min(self.width) {
+                canvas.dots[(y * width + x) as usize] = self.dots[(y * self.width + x) as usize];
+            }
+        }
+        *self = canvas;
+    }
+
+    fn set(&mut self, x: u32, y: u32) {
+        if x < self.width && y < self.height {
+            self.dots[(y * self.width + x) as usize] = true;
+        }
+    }
+
+    fn fill(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        for dot_y in y..(y + height).min(self.height) {
+            for dot_x in x..(x + width).min(self.width) {
+                self.set(dot_x, dot_y);
+            }
+        }
+    }
+
+    /// Draws the text with the bitmap font, scaled to the character width and height
+    fn text(&mut self, x: u32, y: u32, text: &str, char_width: u32, char_height: u32) {
+        let length = text.chars().count() as u32;
+        if length == 0 {
+            return;
+        }
+        let mut glyphs = Canvas::new(length * GLYPH_WIDTH, GLYPH_HEIGHT);
+        let style = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+        let _ = Text::with_baseline(text, Point::zero(), style, Baseline::Top).draw(&mut glyphs);
+
+        for dot_y in 0..char_height {
+            let glyph_y = dot_y * GLYPH_HEIGHT / char_height;
+            for dot_x in 0..length * char_width {
+                let glyph_x = dot_x * GLYPH_WIDTH / char_width;
+                if glyphs.dots[(glyph_y * glyphs.width + glyph_x) as usize] {
+                    self.set(x + dot_x, y + dot_y);
+                }
+            }
+        }
+    }
+
+    fn to_png(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let pixels: Vec<u8> = self
+            .dots
+            .iter()
+            .map(|dot| if *dot { 0 } else { 255 })
+            .collect();
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&pixels)?;
+        writer.finish()?;
+        Ok(png)
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if color == BinaryColor::On && point.x >= 0 && point.y >= 0 {
+                self.set(point.x as u32, point.y as u32);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_code128() {
+        for pattern in CODE128_PATTERNS.iter().take(106) {
+            assert_eq!(
+                pattern
+                    .bytes()
+                    .map(|width| (width - b'0') as u32)
+                    .sum::<u32>(),
+                11
+            );
+        }
+
+        // start B, "A", "B", check, stop
+        assert_eq!(code128("AB", false).unwrap().0, vec![104, 33, 34, 102, 106]);
+        // digits use code set C
+        let (symbols, text) = code128("ABC123456", false).unwrap();
+        assert_eq!(symbols[..7], [104, 33, 34, 35, 99, 12, 34]);
+        assert_eq!(text, "ABC123456");
+        // FNC1 before the first application identifier and after variable length values
+        let (symbols, text) = code128("(01)09501101020917(10)A1(21)2", true).unwrap();
+        assert_eq!(symbols[..3], [105, 102, 1]);
+        assert_eq!(symbols[10..16], [10, 100, 33, 17, 102, 18]);
+        assert_eq!(text, "(01)09501101020917(10)A1(21)2");
+        assert!(code128("é", false).is_err());
+    }
+
+    #[test]
+    fn test_zpl_to_png() {
+        let zpl = "^XA^PW400^LL200^CI28
+            ^FO10,10^A0N,30,30^FDBatch A1^FS
+            ^FO10,50^BQN,2,3^FDMA,stock_line_id^FS
+            ^FO120,50^BY2^BCN,60,Y,N,N,D^FD(01)09501101020917(17)250131(10)A1^FS
+            ^FO0,0^GB400,200,2^FS
+            ^XZ
+            ^XA^FO10,10^FB200,2,0,C^FDSecond label with a long text^FS^XZ";
+        let labels = zpl_to_png(zpl, 300, 100).unwrap();
+        assert_eq!(labels.len(), 2);
+        for label in &labels {
+            assert!(label.starts_with(b"\x89PNG"));
+        }
+
+        let mut renderer = Renderer::new(300, 100);
+        for command in parse("^FO10,10^A0N,30,30^FDBatch^FS^FO0,90^GB300,10,10^FS") {
+            renderer.apply(&command).unwrap();
+        }
+        let canvas = renderer.canvas;
+        let dots = |y: u32| {
+            (0..canvas.width)
+                .filter(|x| canvas.dots[(y * 300 + x) as usize])
+                .count()
+        };
+        assert!(dots(25) > 0);
+        assert_eq!(dots(5), 0);
+        assert_eq!(dots(95), 300);
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("Item name with words", 10),
+            vec!["Item name", "with words"]
+        );
+        assert_eq!(wrap("Abcdefghijkl", 5), vec!["Abcde", "fghij", "kl"]);
+        assert_eq!(wrap("One\\&Two", 10), vec!["One", "Two"]);
+    }
+}
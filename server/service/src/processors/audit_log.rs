@@ -0,0 +1,352 @@
+use chrono::Utc;
+use repository::{
+    AuditLogCaptureRowRepository, AuditLogRow, AuditLogRowRepository, ChangelogRepository,
+    ChangelogRow, KeyType, RepositoryError, RowActionType, StorageConnection,
+};
+use serde_json::Value;
+use thiserror::Error;
+use util::uuid::uuid;
+
+use crate::{
+    audit_log::{
+        audit_log_hash, audit_log_key, changed_fields, record_snapshot, AuditLogError,
+        AuditLogHead, AUDIT_LOG_GENESIS_HASH,
+    },
+    cursor_controller::CursorController,
+    service_provider::ServiceProvider,
+};
+
+const CHANGELOG_BATCH_SIZE: u32 = 100;
+/// Audit processor shares the processor task with transfer processors, limit how much
+/// of the changelog is processed per run so a large backlog (e.g. after upgrade) doesn't block transfers
+const MAX_CHANGELOGS_PER_RUN: u32 = 2000;
+
+#[derive(Error, Debug)]
+pub(crate) enum ProcessAuditLogError {
+    #[error("{0:?}")]
+    DatabaseError(#[from] RepositoryError),
+    #[error("Problem parsing audit log snapshot {0:?}")]
+    SnapshotParseError(serde_json::Error),
+    #[error("{0}")]
+    AuditLogError(#[from] AuditLogError),
+    #[error("Audit log head is missing or not signed with the audit log key, the log was changed")]
+    InvalidHead,
+}
+
+/// Adds every changelog entry to the hash chained audit log, in changelog order.
+/// Returns true if there are more changelogs to process
+pub(crate) fn process_audit_log(
+    service_provider: &ServiceProvider,
+) -> Result<bool, ProcessAuditLogError> {
+    use ProcessAuditLogError as Error;
+
+    // Plain connection, changes of the processor itself aren't captured
+    let connection = service_provider
+        .connection()
+        .map_err(Error::DatabaseError)?;
+
+    let key = audit_log_key(service_provider).map_err(AuditLogError::from)?;
+    let changelog_repo = ChangelogRepository::new(&connection);
+    let cursor_controller = CursorController::new(KeyType::AuditLogProcessorCursor);
+
+    let mut processed = 0;
+    while processed < MAX_CHANGELOGS_PER_RUN {
+        let cursor = cursor_controller
+            .get(&connection)
+            .map_err(Error::DatabaseError)?;
+
+        // Not deduped, every change of a record gets an entry
+        let logs = changelog_repo
+            .all_changelogs(cursor, CHANGELOG_BATCH_SIZE)
+            .map_err(Error::DatabaseError)?;
+
+        if logs.is_empty() {
+            return Ok(false);
+        }
+
+        for log in logs {
+            connection
+                .transaction_sync(|connection| {
+                    audit_changelog(connection, &key, &log)?;
+                    cursor_controller
+                        .update(connection, (log.cursor + 1) as u64)
+                        .map_err(Error::DatabaseError)
+                })
+                .map_err(|error| error.to_inner_error())?;
+            processed += 1;
+        }
+    }
+
+    Ok(true)
+}
+
+fn audit_changelog(
+    connection: &StorageConnection,
+    key: &[u8],
+    changelog: &ChangelogRow,
+) -> Result<(), ProcessAuditLogError> {
+    use ProcessAuditLogError as Error;
+    let repo = AuditLogRowRepository::new(connection);
+    let capture_repo = AuditLogCaptureRowRepository::new(connection);
+
+    // Extending a changed head would sign the change
+    let head = AuditLogHead::load(connection)?;
+    match &head {
+        Some(head) if !head.is_signed_with(key) => return Err(Error::InvalidHead),
+        None if repo.find_latest().map_err(Error::DatabaseError)?.is_some() => {
+            return Err(Error::InvalidHead)
+        }
+        _ => {}
+    }
+    let (count, previous_hash) = match head {
+        Some(head) => (head.count, head.hash),
+        None => (0, AUDIT_LOG_GENESIS_HASH.to_string()),
+    };
+
+    let before = repo
+        .find_latest_for_record(&changelog.table_name, &changelog.record_id)
+        .map_err(Error::DatabaseError)?
+        .and_then(|row| row.record_snapshot)
+        .map(|snapshot| serde_json::from_str::<Value>(&snapshot))
+        .transpose()
+        .map_err(Error::SnapshotParseError)?;
+
+    let capture = capture_repo
+        .find_one_by_cursor(changelog.cursor)
+        .map_err(Error::DatabaseError)?;
+
+    let (user_id, datetime, after) = match capture {
+        Some(capture) => {
+            capture_repo
+                .delete(capture.changelog_cursor)
+                .map_err(Error::DatabaseError)?;
+            let after = capture
+                .record_snapshot
+                .map(|snapshot| serde_json::from_str::<Value>(&snapshot))
+                .transpose()
+                .map_err(Error::SnapshotParseError)?;
+            (capture.user_id, capture.datetime, after)
+        }
+        // Changed without a ServiceContext transaction, e.g. by sync, the acting user is unknown.
+        // The current state is only the state of this change if the record wasn't changed since
+        None => {
+            let is_latest_change = ChangelogRepository::new(connection)
+                .find_latest_for_record(&changelog.table_name, &changelog.record_id)
+                .map_err(Error::DatabaseError)?
+                .map(|latest| latest.cursor == changelog.cursor)
+                .unwrap_or(false);
+            let after = match changelog.row_action {
+                RowActionType::Upsert if is_latest_change => record_snapshot(connection, changelog),
+                _ => None,
+            };
+            (None, Utc::now().naive_utc(), after)
+        }
+    };
+
+    // State of an intermediate change that wasn't captured is unknown, don't diff it
+    let changes = match (&changelog.row_action, &after) {
+        (RowActionType::Upsert, None) => None,
+        _ => changed_fields(before.as_ref(), after.as_ref()),
+    };
+
+    let mut row = AuditLogRow {
+        id: uuid(),
+        changelog_cursor: changelog.cursor,
+        table_name: changelog.table_name.clone(),
+        record_id: changelog.record_id.clone(),
+        row_action: changelog.row_action.clone(),
+        store_id: changelog.store_id.clone(),
+        user_id,
+        datetime,
+        changed_fields: changes.map(|changed| changed.to_string()),
+        record_snapshot: after.map(|snapshot| snapshot.to_string()),
+        previous_hash,
+        hash: String::new(),
+    };
+    row.hash = audit_log_hash(key, &row);
+
+    repo.insert_one(&row).map_err(Error::DatabaseError)?;
+    AuditLogHead::new(key, count + 1, row.hash).save(connection)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use repository::{
+        audit_log::{AuditLogFilter, AuditLogRepository},
+        mock::{mock_store_a, MockDataInserts},
+        test_db::setup_all,
+        ChangelogTableName, EqualFilter, LocationRow, LocationRowRepository, RowActionType,
+    };
+    use serde_json::json;
+
+    use crate::{audit_log::verify_audit_log, service_provider::ServiceProvider};
+
+    use super::{process_audit_log, AuditLogHead, ProcessAuditLogError};
+
+    #[actix_rt::test]
+    async fn audit_log_processor() {
+        let (_, connection, connection_manager, _) = setup_all(
+            "audit_log_processor",
+            MockDataInserts::none().names().stores(),
+        )
+        .await;
+        // Without processors task, so that processor is only run explicitly
+        let service_provider = ServiceProvider::new(connection_manager.clone(), "app_data");
+        // Mock data changelogs
+        process_audit_log(&service_provider).unwrap();
+
+        let location_repo = LocationRowRepository::new(&connection);
+        let mut location = LocationRow {
+            id: "audit_location".to_string(),
+            name: "before".to_string(),
+            code: "code".to_string(),
+            on_hold: false,
+            store_id: mock_store_a().id,
+        };
+        location_repo.upsert_one(&location).unwrap();
+        // All changelogs processed
+        assert!(!process_audit_log(&service_provider).unwrap());
+
+        location.name = "after".to_string();
+        location_repo.upsert_one(&location).unwrap();
+        process_audit_log(&service_provider).unwrap();
+
+        location_repo.delete(&location.id).unwrap();
+        process_audit_log(&service_provider).unwrap();
+
+        let logs = AuditLogRepository::new(&connection)
+            .query_by_filter(
+                AuditLogFilter::new()
+                    .table_name(ChangelogTableName::Location.equal_to())
+                    .record_id(EqualFilter::equal_to("audit_location")),
+            )
+            .unwrap();
+        assert_eq!(logs.len(), 3);
+
+        let updated = &logs[1].audit_log_row;
+        assert_eq!(
+            updated.changed_fields,
+            Some(json!({ "Description": { "from": "before", "to": "after" } }).to_string())
+        );
+        assert_eq!(updated.previous_hash, logs[0].audit_log_row.hash);
+
+        let deleted = &logs[2].audit_log_row;
+        assert_eq!(deleted.row_action, RowActionType::Delete);
+        assert_eq!(deleted.record_snapshot, None);
+
+        let verification = verify_audit_log(&service_provider).unwrap();
+        assert!(verification.is_valid());
+    }
+
+    #[actix_rt::test]
+    async fn audit_log_capture() {
+        let (_, connection, connection_manager, _) = setup_all(
+            "audit_log_capture",
+            MockDataInserts::none().names().stores(),
+        )
+        .await;
+        let service_provider = ServiceProvider::new(connection_manager.clone(), "app_data");
+        process_audit_log(&service_provider).unwrap();
+
+        let ctx = service_provider
+            .context(mock_store_a().id, "audit_user".to_string())
+            .unwrap();
+        let mut location = LocationRow {
+            id: "audit_capture_location".to_string(),
+            name: "first".to_string(),
+            code: "code".to_string(),
+            on_hold: false,
+            store_id: mock_store_a().id,
+        };
+        // Several changes between processor runs, in transactions of a ServiceContext
+        for name in ["first", "second", "third"] {
+            location.name = name.to_string();
+            ctx.connection
+                .transaction_sync(|connection| {
+                    LocationRowRepository::new(connection).upsert_one(&location)
+                })
+                .unwrap();
+        }
+        // Changes without a ServiceContext, e.g. by sync
+        let location_repo = LocationRowRepository::new(&connection);
+        for name in ["fourth", "fifth"] {
+            location.name = name.to_string();
+            location_repo.upsert_one(&location).unwrap();
+        }
+        process_audit_log(&service_provider).unwrap();
+
+        let logs: Vec<_> = AuditLogRepository::new(&connection)
+            .query_by_filter(
+                AuditLogFilter::new().record_id(EqualFilter::equal_to("audit_capture_location")),
+            )
+            .unwrap()
+            .into_iter()
+            .map(|log| log.audit_log_row)
+            .collect();
+        assert_eq!(logs.len(), 5);
+
+        let description = |from: &str, to: &str| {
+            Some(json!({ "Description": { "from": from, "to": to } }).to_string())
+        };
+        assert_eq!(logs[0].user_id, Some("audit_user".to_string()));
+        assert_eq!(logs[1].user_id, Some("audit_user".to_string()));
+        assert_eq!(logs[1].changed_fields, description("first", "second"));
+        assert_eq!(logs[2].changed_fields, description("second", "third"));
+        // State of the fourth change is unknown, the fifth is the current state
+        assert_eq!(logs[3].user_id, None);
+        assert_eq!(logs[3].record_snapshot, None);
+        assert_eq!(logs[3].changed_fields, None);
+        assert_eq!(logs[4].changed_fields, description("third", "fifth"));
+
+        let verification = verify_audit_log(&service_provider).unwrap();
+        assert!(verification.is_valid());
+    }
+
+    #[actix_rt::test]
+    async fn audit_log_truncation() {
+        let (_, connection, connection_manager, _) = setup_all(
+            "audit_log_truncation",
+            MockDataInserts::none().names().stores(),
+        )
+        .await;
+        let service_provider = ServiceProvider::new(connection_manager.clone(), "app_data");
+
+        let location_repo = LocationRowRepository::new(&connection);
+        let mut location = LocationRow {
+            id: "audit_truncation_location".to_string(),
+            name: "first".to_string(),
+            code: "code".to_string(),
+            on_hold: false,
+            store_id: mock_store_a().id,
+        };
+        location_repo.upsert_one(&location).unwrap();
+        location.name = "second".to_string();
+        location_repo.upsert_one(&location).unwrap();
+        process_audit_log(&service_provider).unwrap();
+        assert!(verify_audit_log(&service_provider).unwrap().is_valid());
+
+        // Deleting the newest entry leaves a chain that is valid up to its end
+        connection_manager
+            .execute(
+                "DELETE FROM audit_log WHERE changelog_cursor = \
+                (SELECT MAX(changelog_cursor) FROM audit_log);",
+            )
+            .unwrap();
+        let verification = verify_audit_log(&service_provider).unwrap();
+        assert_eq!(verification.first_invalid, None);
+        assert!(!verification.matches_head);
+        assert!(!verification.is_valid());
+
+        // Head without a valid signature isn't extended
+        AuditLogHead::new(b"other key", 1, "hash".to_string())
+            .save(&connection)
+            .unwrap();
+        location.name = "third".to_string();
+        location_repo.upsert_one(&location).unwrap();
+        assert!(matches!(
+            process_audit_log(&service_provider),
+            Err(ProcessAuditLogError::InvalidHead)
+        ));
+    }
+}
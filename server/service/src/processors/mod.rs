@@ -1,33 +1,45 @@
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
 
+use crate::log_context::LogContext;
 use crate::service_provider::ServiceProvider;
 
+use self::audit_log::{process_audit_log, ProcessAuditLogError};
 use self::transfer::invoice::ProcessInvoiceTransfersError;
 use self::transfer::requisition::ProcessRequisitionTransfersError;
 use self::transfer::{
     invoice::process_invoice_transfers, requisition::process_requisition_transfers,
 };
 
+pub(crate) mod audit_log;
 #[cfg(test)]
 mod test_helpers;
 pub(crate) mod transfer;
 
 const CHANNEL_BUFFER_SIZE: usize = 30;
+/// Audit log is also processed periodically, since most changelog entries are created by
+/// services that don't trigger processors
+const AUDIT_LOG_INTERVAL_SECONDS: u64 = 10;
+
+/// Processors are triggered with the log correlation id of the triggering request or run (if any)
+type TriggeredBy = Option<String>;
 
 #[derive(Clone)]
 pub struct ProcessorsTrigger {
-    requisition_transfer: Sender<()>,
-    invoice_transfer: Sender<()>,
+    requisition_transfer: Sender<TriggeredBy>,
+    invoice_transfer: Sender<TriggeredBy>,
+    audit_log: Sender<TriggeredBy>,
     await_process_queue: Sender<oneshot::Sender<()>>,
 }
 
 pub struct Processors {
-    requisition_transfer: Receiver<()>,
-    invoice_transfer: Receiver<()>,
+    requisition_transfer: Receiver<TriggeredBy>,
+    invoice_transfer: Receiver<TriggeredBy>,
+    audit_log: Receiver<TriggeredBy>,
     await_process_queue: Receiver<oneshot::Sender<()>>,
 }
 
@@ -37,6 +49,8 @@ enum ProcessorsError {
     InvoiceTransfer(ProcessInvoiceTransfersError),
     #[error("Error in requisition transfer processor ({0})")]
     RequisitionTransfer(ProcessRequisitionTransfersError),
+    #[error("Error in audit log processor ({0})")]
+    AuditLog(ProcessAuditLogError),
     #[error("Error when waiting for the process queue to be processed")]
     AwaitProcessQueue(()),
 }
@@ -49,17 +63,21 @@ impl Processors {
         let (invoice_transfer_sender, invoice_transfer_receiver) =
             mpsc::channel(CHANNEL_BUFFER_SIZE);
 
+        let (audit_log_sender, audit_log_receiver) = mpsc::channel(CHANNEL_BUFFER_SIZE);
+
         let (request_check_sender, request_check_receiver) = mpsc::channel(CHANNEL_BUFFER_SIZE);
 
         (
             ProcessorsTrigger {
                 requisition_transfer: requisition_transfer_sender,
                 invoice_transfer: invoice_transfer_sender,
+                audit_log: audit_log_sender,
                 await_process_queue: request_check_sender,
             },
             Processors {
                 requisition_transfer: requisition_transfer_receiver,
                 invoice_transfer: invoice_transfer_receiver,
+                audit_log: audit_log_receiver,
                 await_process_queue: request_check_receiver,
             },
         )
@@ -69,10 +87,13 @@ impl Processors {
         let Processors {
             mut requisition_transfer,
             mut invoice_transfer,
+            mut audit_log,
             mut await_process_queue,
         } = self;
 
         tokio::spawn(async move {
+            let mut audit_log_interval =
+                tokio::time::interval(Duration::from_secs(AUDIT_LOG_INTERVAL_SECONDS));
             loop {
                 // See test below for reasoning behind biased, even though there is no foreseen use case where
                 // requisition must be processed before shipment, it easy to reason about future use cases if
@@ -80,11 +101,25 @@ impl Processors {
                 // The biased flag also makes sure that `await_process_queue` is only called after all other channels are empty.
                 let result = tokio::select! {
                     biased;
-                    Some(_) = requisition_transfer.recv() => {
-                        process_requisition_transfers(&service_provider).map_err(ProcessorsError::RequisitionTransfer)
+                    Some(triggered_by) = requisition_transfer.recv() => {
+                        LogContext::new("requisition_transfer_processor").triggered_by(triggered_by).sync_scope(|| {
+                            process_requisition_transfers(&service_provider).map_err(ProcessorsError::RequisitionTransfer)
+                        })
+                    },
+                    Some(triggered_by) = invoice_transfer.recv() => {
+                        LogContext::new("invoice_transfer_processor").triggered_by(triggered_by).sync_scope(|| {
+                            process_invoice_transfers(&service_provider).map_err(ProcessorsError::InvoiceTransfer)
+                        })
                     },
-                    Some(_) = invoice_transfer.recv() => {
-                        process_invoice_transfers(&service_provider).map_err(ProcessorsError::InvoiceTransfer)
+                    Some(triggered_by) = audit_log.recv() => {
+                        LogContext::new("audit_log_processor").triggered_by(triggered_by).sync_scope(|| {
+                            run_audit_log_processor(&service_provider)
+                        })
+                    },
+                    _ = audit_log_interval.tick() => {
+                        LogContext::new("audit_log_processor").sync_scope(|| {
+                            run_audit_log_processor(&service_provider)
+                        })
                     },
                     Some(sender) = await_process_queue.recv() => {
                         sender.send(()).map_err(ProcessorsError::AwaitProcessQueue)
@@ -101,9 +136,24 @@ impl Processors {
     }
 }
 
+/// Audit log processor only processes part of a large backlog per run, re-trigger it
+/// for the remaining changelogs (other processors get a chance to run in between)
+fn run_audit_log_processor(service_provider: &Arc<ServiceProvider>) -> Result<(), ProcessorsError> {
+    let has_more = process_audit_log(service_provider).map_err(ProcessorsError::AuditLog)?;
+    if has_more {
+        service_provider
+            .processors_trigger
+            .trigger_audit_log_processor();
+    }
+    Ok(())
+}
+
 impl ProcessorsTrigger {
     pub(crate) fn trigger_requisition_transfer_processors(&self) {
-        if let Err(error) = self.requisition_transfer.try_send(()) {
+        if let Err(error) = self
+            .requisition_transfer
+            .try_send(LogContext::current_correlation_id())
+        {
             log::error!(
                 "Problem triggering requisition transfer processor {:#?}",
                 error
@@ -112,11 +162,23 @@ impl ProcessorsTrigger {
     }
 
     pub(crate) fn trigger_invoice_transfer_processors(&self) {
-        if let Err(error) = self.invoice_transfer.try_send(()) {
+        if let Err(error) = self
+            .invoice_transfer
+            .try_send(LogContext::current_correlation_id())
+        {
             log::error!("Problem triggering invoice transfer processor {:#?}", error)
         }
     }
 
+    pub(crate) fn trigger_audit_log_processor(&self) {
+        if let Err(error) = self
+            .audit_log
+            .try_send(LogContext::current_correlation_id())
+        {
+            log::error!("Problem triggering audit log processor {:#?}", error)
+        }
+    }
+
     /// Waits till all current events in the processor queue are handled.
     /// Its guaranteed that all queued processor events that where in the queue before calling
     /// this method are handled when this method returns.
@@ -140,11 +202,25 @@ impl ProcessorsTrigger {
         }
     }
 
+    /// Number of triggers waiting in each processor queue
+    pub fn queue_lengths(&self) -> Vec<(&'static str, usize)> {
+        let queue_length = |sender: &Sender<TriggeredBy>| sender.max_capacity() - sender.capacity();
+        vec![
+            (
+                "requisition_transfer",
+                queue_length(&self.requisition_transfer),
+            ),
+            ("invoice_transfer", queue_length(&self.invoice_transfer)),
+            ("audit_log", queue_length(&self.audit_log)),
+        ]
+    }
+
     /// Empty processor triggers for test that don't use processors but require processors for construction of ServiceContext and ServiceProvider
     pub(crate) fn new_void() -> ProcessorsTrigger {
         ProcessorsTrigger {
             requisition_transfer: mpsc::channel(1).0,
             invoice_transfer: mpsc::channel(1).0,
+            audit_log: mpsc::channel(1).0,
             await_process_queue: mpsc::channel(1).0,
         }
     }
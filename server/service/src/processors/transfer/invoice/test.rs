@@ -133,7 +133,7 @@ async fn invoice_transfers() {
             // and we want to check that shipment is not created when processors runs
             ctx.processors_trigger
                 .invoice_transfer
-                .try_send(())
+                .try_send(None)
                 .unwrap();
             ctx.processors_trigger.await_events_processed().await;
             tester.check_inbound_shipment_not_created(&ctx.connection);
@@ -159,7 +159,7 @@ async fn invoice_transfers() {
             // and we want to check that shipment is not created when processors runs
             ctx.processors_trigger
                 .invoice_transfer
-                .try_send(())
+                .try_send(None)
                 .unwrap();
             ctx.processors_trigger.await_events_processed().await;
             tester.check_inbound_return_not_created(&ctx.connection);
@@ -357,7 +357,7 @@ async fn invoice_transfers_with_merged_name() {
             // and we want to check that shipment is not created when processors runs
             ctx.processors_trigger
                 .invoice_transfer
-                .try_send(())
+                .try_send(None)
                 .unwrap();
             ctx.processors_trigger.await_events_processed().await;
 
@@ -384,7 +384,7 @@ async fn invoice_transfers_with_merged_name() {
             // and we want to check that shipment is not created when processors runs
             ctx.processors_trigger
                 .invoice_transfer
-                .try_send(())
+                .try_send(None)
                 .unwrap();
             ctx.processors_trigger.await_events_processed().await;
             tester.check_inbound_return_not_created(&ctx.connection);
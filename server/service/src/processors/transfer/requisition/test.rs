@@ -102,7 +102,7 @@ async fn requisition_transfer() {
             // manually trigger because inserting the requisition doesn't trigger the processor
             ctx.processors_trigger
                 .requisition_transfer
-                .try_send(())
+                .try_send(None)
                 .unwrap();
             ctx.processors_trigger.await_events_processed().await;
             tester.check_response_requisition_not_created(&ctx.connection);
@@ -190,7 +190,7 @@ async fn stock_on_deleted_requisitions() {
 
         ctx.processors_trigger
             .requisition_transfer
-            .try_send(())
+            .try_send(None)
             .unwrap();
 
         ctx.processors_trigger.await_events_processed().await;
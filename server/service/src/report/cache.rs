@@ -0,0 +1,433 @@
+//! Optional cache for the data of SQL report queries. Cached data is keyed by report id, query
+//! and variables (without `now`), and is reused until one of the tables read by the query changes
+//! (the latest changelog cursor of the tables, views are mapped to their tables) or it's older
+//! than the max age. The max age also refreshes data of tables without changelog, e.g. item.
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveDateTime, Utc};
+use repository::{ChangelogRepository, ChangelogTableName, RepositoryError, StorageConnection};
+use serde_json::{Map, Value};
+use strum::IntoEnumIterator;
+
+use super::definition::SQLQuery;
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct ReportCacheSettings {
+    /// Cached data is queried again after this time, even if no table read by the query changed
+    #[serde(default = "default_max_age_seconds")]
+    pub max_age_seconds: u64,
+    /// The oldest data is removed when the cache is full
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_max_age_seconds() -> u64 {
+    3600
+}
+
+fn default_max_entries() -> usize {
+    100
+}
+
+/// Tables of views used in report queries
+const VIEW_TABLES: &[(&str, &[ChangelogTableName])] = &[
+    (
+        "stock_movement",
+        &[ChangelogTableName::Invoice, ChangelogTableName::InvoiceLine],
+    ),
+    (
+        "inbound_shipment_stock_movement",
+        &[ChangelogTableName::Invoice, ChangelogTableName::InvoiceLine],
+    ),
+    (
+        "outbound_shipment_stock_movement",
+        &[ChangelogTableName::Invoice, ChangelogTableName::InvoiceLine],
+    ),
+    (
+        "inventory_adjustment_stock_movement",
+        &[ChangelogTableName::Invoice, ChangelogTableName::InvoiceLine],
+    ),
+    (
+        "invoice_line_stock_movement",
+        &[ChangelogTableName::Invoice, ChangelogTableName::InvoiceLine],
+    ),
+    (
+        "consumption",
+        &[ChangelogTableName::Invoice, ChangelogTableName::InvoiceLine],
+    ),
+    (
+        "invoice_stats",
+        &[ChangelogTableName::Invoice, ChangelogTableName::InvoiceLine],
+    ),
+    ("stock_on_hand", &[ChangelogTableName::StockLine]),
+    ("store_items", &[ChangelogTableName::StockLine]),
+    (
+        "requisitions_in_period",
+        &[
+            ChangelogTableName::Requisition,
+            ChangelogTableName::RequisitionLine,
+        ],
+    ),
+    ("latest_asset_log", &[ChangelogTableName::AssetLog]),
+    ("latest_document", &[ChangelogTableName::Document]),
+    (
+        "report_document",
+        &[ChangelogTableName::Document, ChangelogTableName::Name],
+    ),
+    (
+        "report_encounter",
+        &[ChangelogTableName::Document, ChangelogTableName::Name],
+    ),
+    (
+        "report_patient",
+        &[ChangelogTableName::Document, ChangelogTableName::Name],
+    ),
+    (
+        "report_program_enrolment",
+        &[ChangelogTableName::Document, ChangelogTableName::Name],
+    ),
+    (
+        "report_program_event",
+        &[ChangelogTableName::Document, ChangelogTableName::Name],
+    ),
+];
+
+/// Timing of a query of a report, since the server started
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct ReportQueryDiagnostics {
+    pub report_id: String,
+    pub query_name: String,
+    /// Number of times the SQL was run
+    pub runs: u64,
+    pub cache_hits: u64,
+    pub last_duration: Duration,
+    pub max_duration: Duration,
+    pub total_duration: Duration,
+    pub last_row_count: usize,
+    pub last_run_datetime: Option<NaiveDateTime>,
+}
+
+struct CacheEntry {
+    watermark: u64,
+    created: Instant,
+    rows: Vec<Value>,
+}
+
+#[derive(Default)]
+pub struct ReportDataCache {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    diagnostics: Mutex<HashMap<(String, String), ReportQueryDiagnostics>>,
+}
+
+impl ReportDataCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached data of the query if it's still valid, otherwise runs the query with
+    /// `run`. Data is not cached when `settings` is not set, the timing is recorded in both cases.
+    pub fn query(
+        &self,
+        connection: &StorageConnection,
+        settings: Option<&ReportCacheSettings>,
+        report_id: &str,
+        query: &SQLQuery,
+        variables: &Map<String, Value>,
+        run: impl FnOnce() -> Result<Vec<Value>, RepositoryError>,
+    ) -> Result<Vec<Value>, RepositoryError> {
+        let Some(settings) = settings else {
+            return self.run_query(report_id, query, run);
+        };
+
+        let key = cache_key(report_id, query, variables);
+        let watermark = data_watermark(connection, query)?;
+        let max_age = Duration::from_secs(settings.max_age_seconds);
+        if let Some(entry) = self.entries.lock().unwrap().get(&key) {
+            if entry.watermark == watermark && entry.created.elapsed() < max_age {
+                self.diagnostics(report_id, query, |diagnostics| diagnostics.cache_hits += 1);
+                return Ok(entry.rows.clone());
+            }
+        }
+
+        let rows = self.run_query(report_id, query, run)?;
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(
+            key,
+            CacheEntry {
+                watermark,
+                created: Instant::now(),
+                rows: rows.clone(),
+            },
+        );
+        while entries.len() > settings.max_entries {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.created)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+        Ok(rows)
+    }
+
+    /// Timing of all report queries run since the server started, by report and query name
+    pub fn query_diagnostics(&self) -> Vec<ReportQueryDiagnostics> {
+        let mut diagnostics: Vec<ReportQueryDiagnostics> =
+            self.diagnostics.lock().unwrap().values().cloned().collect();
+        diagnostics
+            .sort_by(|a, b| (&a.report_id, &a.query_name).cmp(&(&b.report_id, &b.query_name)));
+        diagnostics
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn run_query(
+        &self,
+        report_id: &str,
+        query: &SQLQuery,
+        run: impl FnOnce() -> Result<Vec<Value>, RepositoryError>,
+    ) -> Result<Vec<Value>, RepositoryError> {
+        let start = Instant::now();
+        let rows = run()?;
+        let duration = start.elapsed();
+        log::info!(
+            "Report {} query {} took {:?} ({} rows)",
+            report_id,
+            query.name,
+            duration,
+            rows.len()
+        );
+        self.diagnostics(report_id, query, |diagnostics| {
+            diagnostics.runs += 1;
+            diagnostics.last_duration = duration;
+            diagnostics.max_duration = diagnostics.max_duration.max(duration);
+            diagnostics.total_duration += duration;
+            diagnostics.last_row_count = rows.len();
+            diagnostics.last_run_datetime = Some(Utc::now().naive_utc());
+        });
+        Ok(rows)
+    }
+
+    fn diagnostics(
+        &self,
+        report_id: &str,
+        query: &SQLQuery,
+        update: impl FnOnce(&mut ReportQueryDiagnostics),
+    ) {
+        let mut diagnostics = self.diagnostics.lock().unwrap();
+        let entry = diagnostics
+            .entry((report_id.to_string(), query.name.clone()))
+            .or_insert_with(|| ReportQueryDiagnostics {
+                report_id: report_id.to_string(),
+                query_name: query.name.clone(),
+                ..Default::default()
+            });
+        update(entry);
+    }
+}
+
+fn cache_key(report_id: &str, query: &SQLQuery, variables: &Map<String, Value>) -> String {
+    // sorted, so that the order of the arguments doesn't matter
+    let variables: BTreeMap<&String, &Value> = variables
+        .iter()
+        .filter(|(name, _)| name.as_str() != "now")
+        .collect();
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        report_id,
+        query.name,
+        query.query_sqlite,
+        query.query_postgres,
+        serde_json::to_string(&variables).unwrap_or_default()
+    )
+}
+
+/// Latest changelog cursor of the tables read by the query, or of all tables if the query
+/// doesn't read any table with changelog
+fn data_watermark(
+    connection: &StorageConnection,
+    query: &SQLQuery,
+) -> Result<u64, RepositoryError> {
+    let repo = ChangelogRepository::new(connection);
+    let tables = query_tables(&format!("{}\n{}", query.query_sqlite, query.query_postgres));
+    if tables.is_empty() {
+        repo.latest_cursor()
+    } else {
+        repo.latest_cursor_for_tables(&tables)
+    }
+}
+
+/// Tables with changelog read by the SQL, directly or through views
+pub(crate) fn query_tables(sql: &str) -> Vec<ChangelogTableName> {
+    let identifiers: HashSet<String> = sql
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .map(str::to_ascii_lowercase)
+        .collect();
+
+    let mut tables: Vec<ChangelogTableName> = ChangelogTableName::iter()
+        .filter(|table| identifiers.contains(&table_name(table)))
+        .collect();
+    for (view, view_tables) in VIEW_TABLES {
+        if identifiers.contains(*view) {
+            tables.extend(view_tables.iter().cloned());
+        }
+    }
+    let mut unique = Vec::new();
+    for table in tables {
+        if !unique.contains(&table) {
+            unique.push(table);
+        }
+    }
+    unique
+}
+
+/// Name of the DB table, e.g. `invoice_line` for `InvoiceLine`
+fn table_name(table: &ChangelogTableName) -> String {
+    let mut name = String::new();
+    for (index, c) in format!("{:?}", table).chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if index > 0 {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use repository::{
+        mock::{mock_store_a, MockDataInserts},
+        test_db::setup_all,
+        LocationRow, LocationRowRepository,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    fn sql_query(name: &str, sql: &str) -> SQLQuery {
+        SQLQuery {
+            name: name.to_string(),
+            query_sqlite: sql.to_string(),
+            query_postgres: sql.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_query_tables() {
+        assert_eq!(
+            query_tables("SELECT * FROM stock_movement sm JOIN item ON item.id = sm.item_id"),
+            vec![ChangelogTableName::Invoice, ChangelogTableName::InvoiceLine]
+        );
+        assert_eq!(
+            query_tables("select l.code from LOCATION l join stock_line s on s.location_id = l.id"),
+            vec![ChangelogTableName::Location, ChangelogTableName::StockLine]
+        );
+        assert_eq!(query_tables("SELECT id FROM item"), vec![]);
+    }
+
+    #[actix_rt::test]
+    async fn test_report_data_cache() {
+        let (_, connection, _, _) = setup_all(
+            "test_report_data_cache",
+            MockDataInserts::none().names().stores(),
+        )
+        .await;
+        let cache = ReportDataCache::new();
+        let settings = ReportCacheSettings {
+            max_age_seconds: 3600,
+            max_entries: 2,
+        };
+        let locations = sql_query("locations", "SELECT * FROM location");
+        let movements = sql_query("movements", "SELECT * FROM stock_movement");
+        let runs = Cell::new(0);
+        let query = |settings: Option<&ReportCacheSettings>, sql: &SQLQuery, variables: Value| {
+            let variables = variables.as_object().unwrap();
+            cache
+                .query(&connection, settings, "report", sql, variables, || {
+                    runs.set(runs.get() + 1);
+                    Ok(vec![json!(runs.get())])
+                })
+                .unwrap()
+        };
+
+        assert_eq!(
+            query(Some(&settings), &locations, json!({"now": "1"})),
+            vec![json!(1)]
+        );
+        // now is ignored
+        assert_eq!(
+            query(Some(&settings), &locations, json!({"now": "2"})),
+            vec![json!(1)]
+        );
+        assert_eq!(
+            query(Some(&settings), &locations, json!({"storeId": "store"})),
+            vec![json!(2)]
+        );
+        assert_eq!(
+            query(Some(&settings), &movements, json!({})),
+            vec![json!(3)]
+        );
+        assert_eq!(query(None, &movements, json!({})), vec![json!(4)]);
+
+        // changes of other tables don't invalidate the data
+        LocationRowRepository::new(&connection)
+            .upsert_one(&LocationRow {
+                id: "location".to_string(),
+                name: "Location".to_string(),
+                code: "L1".to_string(),
+                on_hold: false,
+                store_id: mock_store_a().id,
+            })
+            .unwrap();
+        assert_eq!(
+            query(Some(&settings), &movements, json!({})),
+            vec![json!(3)]
+        );
+        assert_eq!(
+            query(Some(&settings), &locations, json!({"storeId": "store"})),
+            vec![json!(5)]
+        );
+        // evicted (max 2 entries)
+        assert_eq!(
+            query(Some(&settings), &locations, json!({"now": "3"})),
+            vec![json!(6)]
+        );
+
+        let max_age = ReportCacheSettings {
+            max_age_seconds: 0,
+            ..settings
+        };
+        assert_eq!(
+            query(Some(&max_age), &locations, json!({"now": "3"})),
+            vec![json!(7)]
+        );
+
+        let diagnostics = cache.query_diagnostics();
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| (
+                    d.query_name.as_str(),
+                    d.runs,
+                    d.cache_hits,
+                    d.last_row_count
+                ))
+                .collect::<Vec<_>>(),
+            vec![("locations", 5, 1, 1), ("movements", 2, 1, 1)]
+        );
+    }
+}
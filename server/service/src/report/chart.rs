@@ -0,0 +1,412 @@
+//! Tera functions rendering line and bar charts of report data to inline svg, e.g.
+//! `{{ line_chart(data=data.consumption, x="date", y=["consumption", "averageMonthlyConsumption"],
+//! labels=["Consumption", "AMC"], title="Consumption") }}`.
+//!
+//! Arguments:
+//! - `data`: list of rows (objects)
+//! - `x`: path of the category of a row (dot separated, like tabular columns)
+//! - `y`: path or list of paths of the values of the series, rows without a value are skipped
+//! - `labels`: legend labels of the series, defaults to `y` (the legend is shown for more than
+//!   one series or when labels are set)
+//! - `title`, `width` and `height` (px, default 600 x 300) and `colors` (list of css colors)
+//!
+//! Only rect, line, polyline and text elements are used, which the native pdf renderer supports.
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{string_or_vec::string_or_vec, tabular::value_at_path};
+
+const COLORS: [&str; 6] = [
+    "#3e7bfa", "#e95c30", "#33a02c", "#ff7f00", "#6a3d9a", "#b15928",
+];
+const FONT_SIZE: f64 = 11.0;
+const TITLE_FONT_SIZE: f64 = 13.0;
+const Y_TICKS: f64 = 4.0;
+/// Minimum space for a label on the x axis, labels are skipped when there are too many rows
+const X_LABEL_WIDTH: f64 = 60.0;
+const GRID_COLOR: &str = "#dddddd";
+const AXIS_COLOR: &str = "#000000";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChartType {
+    Line,
+    Bar,
+}
+
+#[derive(Deserialize, Debug)]
+struct ChartArgs {
+    data: Vec<Value>,
+    x: String,
+    #[serde(deserialize_with = "string_or_vec")]
+    y: Vec<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    title: Option<String>,
+    #[serde(default = "default_width")]
+    width: f64,
+    #[serde(default = "default_height")]
+    height: f64,
+    #[serde(default)]
+    colors: Vec<String>,
+}
+
+fn default_width() -> f64 {
+    600.0
+}
+
+fn default_height() -> f64 {
+    300.0
+}
+
+struct ChartFunction(ChartType);
+
+impl tera::Function for ChartFunction {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let name = match self.0 {
+            ChartType::Line => "line_chart",
+            ChartType::Bar => "bar_chart",
+        };
+        let args: ChartArgs =
+            serde_json::from_value(Value::Object(args.clone().into_iter().collect()))
+                .map_err(|err| tera::Error::msg(format!("Function `{}`: {}", name, err)))?;
+        Ok(Value::String(chart_svg(self.0, &args)))
+    }
+
+    // all text from the data is escaped
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// Registers the `line_chart` and `bar_chart` functions
+pub(crate) fn register_chart_functions(tera: &mut tera::Tera) {
+    tera.register_function("line_chart", ChartFunction(ChartType::Line));
+    tera.register_function("bar_chart", ChartFunction(ChartType::Bar));
+}
+
+fn chart_svg(chart_type: ChartType, args: &ChartArgs) -> String {
+    let categories: Vec<String> = args
+        .data
+        .iter()
+        .map(|row| value_at_path(row, &args.x).map(label).unwrap_or_default())
+        .collect();
+    let series: Vec<Vec<Option<f64>>> = args
+        .y
+        .iter()
+        .map(|path| {
+            args.data
+                .iter()
+                .map(|row| value_at_path(row, path).and_then(number))
+                .collect()
+        })
+        .collect();
+    let color = |index: usize| {
+        args.colors
+            .get(index)
+            .map(String::as_str)
+            .unwrap_or(COLORS[index % COLORS.len()])
+    };
+
+    let show_legend = series.len() > 1 || !args.labels.is_empty();
+    let left = 50.0;
+    let right = f64::max(args.width - 10.0, left + 1.0);
+    let top = if args.title.is_some() { 30.0 } else { 10.0 };
+    let bottom = f64::max(
+        args.height - if show_legend { 45.0 } else { 25.0 },
+        top + 1.0,
+    );
+
+    // the y axis always includes 0
+    let (min, max) = series
+        .iter()
+        .flatten()
+        .flatten()
+        .fold((0.0_f64, 0.0_f64), |(min, max), value| {
+            (min.min(*value), max.max(*value))
+        });
+    let step = nice_step(if max > min { max - min } else { 1.0 });
+    let axis_min = (min / step).floor() * step;
+    let axis_max = f64::max((max / step).ceil() * step, axis_min + step);
+    let y_position =
+        |value: f64| bottom - (value - axis_min) / (axis_max - axis_min) * (bottom - top);
+    let band = (right - left) / categories.len().max(1) as f64;
+    let x_position = |index: usize| left + band * (index as f64 + 0.5);
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="Helvetica, Arial, sans-serif" font-size="{font_size}">"#,
+        width = format_number(args.width),
+        height = format_number(args.height),
+        font_size = format_number(FONT_SIZE),
+    );
+    if let Some(title) = &args.title {
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" text-anchor="middle" font-size="{}" font-weight="bold">{}</text>"#,
+            format_number(args.width / 2.0),
+            format_number(TITLE_FONT_SIZE + 4.0),
+            format_number(TITLE_FONT_SIZE),
+            escape(title)
+        ));
+    }
+
+    // y axis grid and labels
+    let ticks = ((axis_max - axis_min) / step).round() as usize;
+    for tick in 0..=ticks {
+        let value = axis_min + tick as f64 * step;
+        let y = y_position(value);
+        line(&mut svg, (left, y), (right, y), GRID_COLOR);
+        text(
+            &mut svg,
+            (left - 4.0, y + FONT_SIZE / 3.0),
+            "end",
+            &format_number(value),
+        );
+    }
+
+    // x axis labels
+    let label_step = ((X_LABEL_WIDTH / band).ceil() as usize).max(1);
+    for (index, category) in categories.iter().enumerate().step_by(label_step) {
+        text(
+            &mut svg,
+            (x_position(index), bottom + FONT_SIZE + 4.0),
+            "middle",
+            category,
+        );
+    }
+
+    match chart_type {
+        ChartType::Line => {
+            for (index, values) in series.iter().enumerate() {
+                // rows without a value split the line
+                let mut segments: Vec<Vec<(f64, f64)>> = vec![vec![]];
+                for (row, value) in values.iter().enumerate() {
+                    match value {
+                        Some(value) => segments
+                            .last_mut()
+                            .unwrap()
+                            .push((x_position(row), y_position(*value))),
+                        None => segments.push(vec![]),
+                    }
+                }
+                for segment in segments {
+                    match segment.as_slice() {
+                        [] => {}
+                        [(x, y)] => rect(&mut svg, (x - 2.0, y - 2.0), (4.0, 4.0), color(index)),
+                        points => polyline(&mut svg, points, color(index)),
+                    }
+                }
+            }
+        }
+        ChartType::Bar => {
+            let group_width = band * 0.8;
+            let bar_width = group_width / series.len().max(1) as f64;
+            let zero = y_position(0.0);
+            for (index, values) in series.iter().enumerate() {
+                for (row, value) in values.iter().enumerate() {
+                    let Some(value) = value else {
+                        continue;
+                    };
+                    let x = x_position(row) - group_width / 2.0 + bar_width * index as f64;
+                    let y = y_position(*value);
+                    rect(
+                        &mut svg,
+                        (x, y.min(zero)),
+                        (bar_width, (y - zero).abs()),
+                        color(index),
+                    );
+                }
+            }
+        }
+    }
+
+    // axes, drawn over the grid and the bars
+    line(&mut svg, (left, top), (left, bottom), AXIS_COLOR);
+    let zero = y_position(0.0);
+    line(&mut svg, (left, zero), (right, zero), AXIS_COLOR);
+
+    if show_legend {
+        let y = args.height - 10.0;
+        let mut x = left;
+        for index in 0..series.len() {
+            let label = args.labels.get(index).unwrap_or(&args.y[index]);
+            rect(
+                &mut svg,
+                (x, y - FONT_SIZE + 1.0),
+                (10.0, 10.0),
+                color(index),
+            );
+            text(&mut svg, (x + 14.0, y), "start", label);
+            // approximate text width, svg text can't be measured
+            x += 30.0 + label.chars().count() as f64 * FONT_SIZE * 0.6;
+        }
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Rounds the step between the y axis ticks to 1, 2 or 5 times a power of 10
+fn nice_step(range: f64) -> f64 {
+    let step = range / Y_TICKS;
+    let magnitude = 10_f64.powf(step.log10().floor());
+    let nice = match step / magnitude {
+        normalized if normalized <= 1.0 => 1.0,
+        normalized if normalized <= 2.0 => 2.0,
+        normalized if normalized <= 5.0 => 5.0,
+        _ => 10.0,
+    };
+    nice * magnitude
+}
+
+fn line(svg: &mut String, (x1, y1): (f64, f64), (x2, y2): (f64, f64), color: &str) {
+    svg.push_str(&format!(
+        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1"/>"#,
+        format_number(x1),
+        format_number(y1),
+        format_number(x2),
+        format_number(y2),
+        escape(color)
+    ));
+}
+
+fn polyline(svg: &mut String, points: &[(f64, f64)], color: &str) {
+    let points: Vec<String> = points
+        .iter()
+        .map(|(x, y)| format!("{},{}", format_number(*x), format_number(*y)))
+        .collect();
+    svg.push_str(&format!(
+        r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+        points.join(" "),
+        escape(color)
+    ));
+}
+
+fn rect(svg: &mut String, (x, y): (f64, f64), (width, height): (f64, f64), color: &str) {
+    svg.push_str(&format!(
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+        format_number(x),
+        format_number(y),
+        format_number(width),
+        format_number(height),
+        escape(color)
+    ));
+}
+
+fn text(svg: &mut String, (x, y): (f64, f64), anchor: &str, text: &str) {
+    svg.push_str(&format!(
+        r#"<text x="{}" y="{}" text-anchor="{}">{}</text>"#,
+        format_number(x),
+        format_number(y),
+        anchor,
+        escape(text)
+    ));
+}
+
+fn label(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Number(number) => number
+            .as_f64()
+            .map(format_number)
+            .unwrap_or_else(|| number.to_string()),
+        Value::Bool(bool) => bool.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Numbers and numeric strings, e.g. decimals from SQL queries
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.trim().parse().ok(),
+        _ => None,
+    }
+    .filter(|number| number.is_finite())
+}
+
+/// Number with at most 2 decimals and without trailing zeros
+fn format_number(value: f64) -> String {
+    let formatted = format!("{:.2}", value);
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+    match formatted {
+        "-0" | "" => "0".to_string(),
+        formatted => formatted.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(template: &str, data: Value) -> tera::Result<String> {
+        let mut tera = tera::Tera::default();
+        register_chart_functions(&mut tera);
+        tera.add_raw_template("template.html", template).unwrap();
+        let mut context = tera::Context::new();
+        context.insert("data", &data);
+        tera.render("template.html", &context)
+    }
+
+    #[test]
+    fn test_nice_step() {
+        assert_eq!(nice_step(40.0), 10.0);
+        assert_eq!(nice_step(7.0), 2.0);
+        assert_eq!(nice_step(1200.0), 500.0);
+    }
+
+    #[test]
+    fn test_line_chart() {
+        let svg = render(
+            r#"{{ line_chart(data=data.rows, x="month", y=["consumption", "stock.total"], title="Stock & consumption") }}"#,
+            json!({"rows": [
+                {"month": "Jan", "consumption": 10, "stock": {"total": 20}},
+                {"month": "Feb", "consumption": null, "stock": {"total": "40"}},
+            ]}),
+        )
+        .unwrap();
+
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="600" height="300""#)
+        );
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains(">Stock &amp; consumption</text>"));
+        // y axis from 0 to 40 in steps of 10, plot between y 30 and 255
+        assert!(svg.contains(r#"text-anchor="end">40</text>"#));
+        assert!(svg.contains(r#"<text x="185" y="270" text-anchor="middle">Jan</text>"#));
+        assert!(
+            svg.contains(r##"<polyline points="185,142.5 455,30" fill="none" stroke="#e95c30""##)
+        );
+        // single point of the consumption
+        assert!(svg.contains(r##"<rect x="183" y="196.75" width="4" height="4" fill="#3e7bfa"/>"##));
+        // legend
+        assert!(svg.contains(">stock.total</text>"));
+    }
+
+    #[test]
+    fn test_bar_chart() {
+        let svg = render(
+            r#"{{ bar_chart(data=data, x="name", y="value", labels=["Value"], colors=["red"], height=200) }}"#,
+            json!([{"name": "<b>A</b>", "value": 5}, {"name": "B", "value": -5}]),
+        )
+        .unwrap();
+
+        // escaped, but the svg isn't
+        assert!(svg.contains(">&lt;b&gt;A&lt;/b&gt;</text>"));
+        // y axis from -5 to 5, plot between y 10 and 155
+        assert!(svg.contains(r#"<rect x="77" y="10" width="216" height="72.5" fill="red"/>"#));
+        assert!(svg.contains(r#"<rect x="347" y="82.5" width="216" height="72.5" fill="red"/>"#));
+        assert!(svg.contains(">Value</text>"));
+
+        assert!(render(r#"{{ bar_chart(data=data, y="value") }}"#, json!([])).is_err());
+    }
+}
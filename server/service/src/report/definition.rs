@@ -54,12 +54,46 @@ pub struct TeraTemplate {
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum ReportOutputType {
     Html,
+    /// Rows and columns of a `TabularTemplate`, printed to csv or xlsx (or as html table)
+    Tabular,
+}
+
+/// Table of report data, columns are mapped onto the rows of a query result
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TabularTemplate {
+    /// Dot separated path of the rows array in the report data, e.g. `stockLines.nodes` for a
+    /// GraphQL query or the name of a SQL query
+    pub rows: String,
+    pub columns: Vec<TabularColumn>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TabularColumn {
+    pub header: String,
+    /// Dot separated path of the value in a row, e.g. `item.name`
+    pub field: String,
+    /// Cell type in xlsx output
+    #[serde(default)]
+    pub r#type: TabularColumnType,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub enum TabularColumnType {
+    #[default]
+    String,
+    Number,
+    Boolean,
+    /// Date string, e.g. `2024-01-31`
+    Date,
+    /// Date time string, e.g. `2024-01-31T13:30:00`
+    DateTime,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 #[serde(tag = "type", content = "data")]
 pub enum ReportDefinitionEntry {
     TeraTemplate(TeraTemplate),
+    TabularTemplate(TabularTemplate),
     /// Custom http query
     GraphGLQuery(GraphQlQuery),
     /// Use default predefined query
@@ -83,6 +117,10 @@ pub struct ReportDefinitionIndex {
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct ReportDefinition {
     pub index: ReportDefinitionIndex,
+    /// Semver of the report definition, e.g. `1.2.0`. A synced definition doesn't replace an
+    /// installed definition with a higher version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
     pub entries: HashMap<String, ReportDefinitionEntry>,
 }
 
@@ -150,6 +188,7 @@ mod report_dsl_test {
                     footer: Some("local_footer.html".to_string()),
                     query: vec!["query".to_string()],
                 },
+                version: None,
                 entries: HashMap::from([
                     (
                         "local_footer.html".to_string(),
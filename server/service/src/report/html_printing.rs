@@ -1,8 +1,22 @@
 use std::{fs, path::PathBuf, str::FromStr};
 
-use headless_chrome::{types::PrintToPdfOptions, Browser, LaunchOptionsBuilder};
+use headless_chrome::{
+    browser::default_executable, types::PrintToPdfOptions, Browser, LaunchOptionsBuilder,
+};
+
+/// Chrome is found through the `CHROME` environment variable or in its default install locations
+pub fn is_chrome_available() -> bool {
+    default_executable().is_ok()
+}
+
+/// Launches a headless Chrome browser, fails when Chrome is not installed or can't be started
+pub fn launch_chrome() -> Result<Browser, anyhow::Error> {
+    let launch_options = LaunchOptionsBuilder::default().headless(true).build()?;
+    Browser::new(launch_options)
+}
 
 pub fn html_to_pdf(
+    browser: &Browser,
     temp_dir: &Option<String>,
     document: &str,
     document_id: &str,
@@ -44,9 +58,8 @@ pub fn html_to_pdf(
     let temp_html_doc_path = temp_dir.join(document_name);
     fs::write(&temp_html_doc_path, document)?;
 
-    // create a new browser and a tab in that browser using headless-chrome
-    let launch_options = LaunchOptionsBuilder::default().headless(true).build()?;
-    let local_pdf = Browser::new(launch_options)?
+    // create a tab in the browser using headless-chrome
+    let local_pdf = browser
         .new_tab()?
         .navigate_to(&format!("file:{}", temp_html_doc_path.to_string_lossy()))?
         .wait_until_navigated()?
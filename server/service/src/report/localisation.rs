@@ -0,0 +1,578 @@
+//! Localised report rendering. Report definitions can contain translations as `Resource` entries
+//! named `translations.<locale>` (e.g. `translations.fr`, built from `translations.fr.json`),
+//! with the strings of the locale as (nested) object. Templates are rendered in the locale of the
+//! user's language, with these Tera helpers:
+//!
+//! - `t(key="stock.title")`: the translation of the key (dot separated path) in the locale,
+//!   falling back to English and then to the key. Other arguments replace `{name}` placeholders,
+//!   e.g. `t(key="page", number=1)` for `"Page {number}"`.
+//! - `value | format_number(decimals=2)`: number with the decimal and group separators of the
+//!   locale, at most 2 decimals without trailing zeros when `decimals` isn't set
+//! - `value | format_currency(symbol="$", decimals=2)`: amount with the symbol placed for the locale
+//! - `value | format_date(format="short")`: date or datetime string as `short` (e.g. 31/01/2024),
+//!   `long` (31 January 2024), `month` (January 2024) or `datetime` (31/01/2024 13:30)
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime};
+use repository::{LanguageType, RepositoryError, StorageConnection, UserAccountRowRepository};
+use serde_json::Value;
+
+use super::tabular::value_at_path;
+
+pub const DEFAULT_LOCALE: &str = "en";
+const TRANSLATIONS_PREFIX: &str = "translations.";
+const NBSP: &str = "\u{a0}";
+
+struct Locale {
+    code: &'static str,
+    decimal_separator: &'static str,
+    group_separator: &'static str,
+    /// Currency symbol after the amount, e.g. `12,50 €`
+    currency_suffix: bool,
+    /// strftime format
+    short_date: &'static str,
+    /// `{day}`, `{month}` and `{year}` placeholders
+    long_date: &'static str,
+    month_year: &'static str,
+    /// Month names in dates, i.e. genitive in Russian
+    months: [&'static str; 12],
+    /// Month names without a day, if different
+    standalone_months: Option<[&'static str; 12]>,
+}
+
+static LOCALES: [Locale; 8] = [
+    Locale {
+        code: "en",
+        decimal_separator: ".",
+        group_separator: ",",
+        currency_suffix: false,
+        short_date: "%d/%m/%Y",
+        long_date: "{day} {month} {year}",
+        month_year: "{month} {year}",
+        months: [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ],
+        standalone_months: None,
+    },
+    Locale {
+        code: "fr",
+        decimal_separator: ",",
+        group_separator: NBSP,
+        currency_suffix: true,
+        short_date: "%d/%m/%Y",
+        long_date: "{day} {month} {year}",
+        month_year: "{month} {year}",
+        months: [
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+        standalone_months: None,
+    },
+    Locale {
+        code: "es",
+        decimal_separator: ",",
+        group_separator: ".",
+        currency_suffix: true,
+        short_date: "%d/%m/%Y",
+        long_date: "{day} de {month} de {year}",
+        month_year: "{month} de {year}",
+        months: [
+            "enero",
+            "febrero",
+            "marzo",
+            "abril",
+            "mayo",
+            "junio",
+            "julio",
+            "agosto",
+            "septiembre",
+            "octubre",
+            "noviembre",
+            "diciembre",
+        ],
+        standalone_months: None,
+    },
+    Locale {
+        code: "lo",
+        decimal_separator: ".",
+        group_separator: ",",
+        currency_suffix: false,
+        short_date: "%d/%m/%Y",
+        long_date: "{day} {month} {year}",
+        month_year: "{month} {year}",
+        months: [
+            "ມັງກອນ",
+            "ກຸມພາ",
+            "ມີນາ",
+            "ເມສາ",
+            "ພຶດສະພາ",
+            "ມິຖຸນາ",
+            "ກໍລະກົດ",
+            "ສິງຫາ",
+            "ກັນຍາ",
+            "ຕຸລາ",
+            "ພະຈິກ",
+            "ທັນວາ",
+        ],
+        standalone_months: None,
+    },
+    Locale {
+        code: "km",
+        decimal_separator: ",",
+        group_separator: ".",
+        currency_suffix: false,
+        short_date: "%d/%m/%Y",
+        long_date: "{day} {month} {year}",
+        month_year: "{month} {year}",
+        months: [
+            "មករា",
+            "កុម្ភៈ",
+            "មីនា",
+            "មេសា",
+            "ឧសភា",
+            "មិថុនា",
+            "កក្កដា",
+            "សីហា",
+            "កញ្ញា",
+            "តុលា",
+            "វិច្ឆិកា",
+            "ធ្នូ",
+        ],
+        standalone_months: None,
+    },
+    Locale {
+        code: "pt",
+        decimal_separator: ",",
+        group_separator: ".",
+        currency_suffix: false,
+        short_date: "%d/%m/%Y",
+        long_date: "{day} de {month} de {year}",
+        month_year: "{month} de {year}",
+        months: [
+            "janeiro",
+            "fevereiro",
+            "março",
+            "abril",
+            "maio",
+            "junho",
+            "julho",
+            "agosto",
+            "setembro",
+            "outubro",
+            "novembro",
+            "dezembro",
+        ],
+        standalone_months: None,
+    },
+    Locale {
+        code: "ru",
+        decimal_separator: ",",
+        group_separator: NBSP,
+        currency_suffix: true,
+        short_date: "%d.%m.%Y",
+        long_date: "{day} {month} {year}",
+        month_year: "{month} {year}",
+        months: [
+            "января",
+            "февраля",
+            "марта",
+            "апреля",
+            "мая",
+            "июня",
+            "июля",
+            "августа",
+            "сентября",
+            "октября",
+            "ноября",
+            "декабря",
+        ],
+        standalone_months: Some([
+            "январь",
+            "февраль",
+            "март",
+            "апрель",
+            "май",
+            "июнь",
+            "июль",
+            "август",
+            "сентябрь",
+            "октябрь",
+            "ноябрь",
+            "декабрь",
+        ]),
+    },
+    Locale {
+        code: "tet",
+        decimal_separator: ",",
+        group_separator: ".",
+        currency_suffix: false,
+        short_date: "%d/%m/%Y",
+        long_date: "{day} {month} {year}",
+        month_year: "{month} {year}",
+        months: [
+            "Janeiru",
+            "Fevereiru",
+            "Marsu",
+            "Abríl",
+            "Maiu",
+            "Juñu",
+            "Jullu",
+            "Agostu",
+            "Setembru",
+            "Outubru",
+            "Novembru",
+            "Dezembru",
+        ],
+        standalone_months: None,
+    },
+];
+
+/// Locale code of the user's language
+pub fn language_locale(language: &LanguageType) -> &'static str {
+    match language {
+        LanguageType::English => "en",
+        LanguageType::French => "fr",
+        LanguageType::Spanish => "es",
+        LanguageType::Laos => "lo",
+        LanguageType::Khmer => "km",
+        LanguageType::Portuguese => "pt",
+        LanguageType::Russian => "ru",
+        LanguageType::Tetum => "tet",
+    }
+}
+
+/// Locale of the user's language, the default locale if the user doesn't exist (e.g. for system
+/// contexts)
+pub(crate) fn user_locale(
+    connection: &StorageConnection,
+    user_id: &str,
+) -> Result<String, RepositoryError> {
+    let user = UserAccountRowRepository::new(connection).find_one_by_id(user_id)?;
+    Ok(user
+        .map(|user| language_locale(&user.language))
+        .unwrap_or(DEFAULT_LOCALE)
+        .to_string())
+}
+
+fn find_locale(code: &str) -> &'static Locale {
+    // e.g. `fr-CA` uses `fr`
+    let language = code.split(['-', '_']).next().unwrap_or_default();
+    LOCALES
+        .iter()
+        .find(|locale| locale.code.eq_ignore_ascii_case(language))
+        .unwrap_or(&LOCALES[0])
+}
+
+/// Registers the `t` function and the formatting filters for the locale, translations are read
+/// from the `translations.<locale>` resources
+pub(crate) fn register_localisation(
+    tera: &mut tera::Tera,
+    locale: &str,
+    resources: &HashMap<String, Value>,
+) {
+    let translations = |locale: &str| {
+        resources
+            .get(&format!("{}{}", TRANSLATIONS_PREFIX, locale))
+            .cloned()
+    };
+    // the full locale (e.g. `fr-CA`), its language and then English
+    let mut tables = Vec::new();
+    let language = locale.split(['-', '_']).next().unwrap_or_default();
+    for code in [locale, language, DEFAULT_LOCALE] {
+        if let Some(table) = translations(code) {
+            if !tables.contains(&table) {
+                tables.push(table);
+            }
+        }
+    }
+    tera.register_function("t", Translate { tables });
+
+    let locale = find_locale(locale);
+    tera.register_filter(
+        "format_number",
+        move |value: &Value, args: &HashMap<String, Value>| {
+            let number = number(value, "format_number")?;
+            let decimals = decimals_arg(args, "format_number")?;
+            Ok(Value::String(format_number(locale, number, decimals)))
+        },
+    );
+    tera.register_filter(
+        "format_currency",
+        move |value: &Value, args: &HashMap<String, Value>| {
+            let number = number(value, "format_currency")?;
+            let decimals = decimals_arg(args, "format_currency")?.unwrap_or(2);
+            let amount = format_number(locale, number, Some(decimals));
+            let symbol = args.get("symbol").and_then(Value::as_str).unwrap_or("");
+            let formatted = match (symbol, locale.currency_suffix) {
+                ("", _) => amount,
+                (symbol, true) => format!("{}{}{}", amount, NBSP, symbol),
+                (symbol, false) => format!("{}{}", symbol, amount),
+            };
+            Ok(Value::String(formatted))
+        },
+    );
+    tera.register_filter("format_date", FormatDate { locale });
+}
+
+struct FormatDate {
+    locale: &'static Locale,
+}
+
+impl tera::Filter for FormatDate {
+    fn filter(&self, value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let format = args
+            .get("format")
+            .and_then(Value::as_str)
+            .unwrap_or("short");
+        let Some(datetime) = value.as_str().and_then(parse_datetime) else {
+            return Err(tera::Error::msg(format!(
+                "Filter `format_date` received an invalid date: {}",
+                value
+            )));
+        };
+        format_date(self.locale, datetime, format).map(Value::String)
+    }
+
+    // only digits, separators and month names, i.e. `/` isn't escaped
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+struct Translate {
+    /// Translations of the locale and the fallback locales, in lookup order
+    tables: Vec<Value>,
+}
+
+impl tera::Function for Translate {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let Some(key) = args.get("key").and_then(Value::as_str) else {
+            return Err(tera::Error::msg(
+                "Function `t` requires a `key` string argument",
+            ));
+        };
+        let mut text = self
+            .tables
+            .iter()
+            .find_map(|table| value_at_path(table, key).and_then(Value::as_str))
+            .unwrap_or(key)
+            .to_string();
+        for (name, value) in args.iter().filter(|(name, _)| name.as_str() != "key") {
+            let value = match value {
+                Value::String(string) => string.clone(),
+                value => value.to_string(),
+            };
+            text = text.replace(&format!("{{{}}}", name), &value);
+        }
+        Ok(Value::String(text))
+    }
+}
+
+/// Numbers and numeric strings, e.g. decimals from SQL queries
+fn number(value: &Value, filter: &str) -> tera::Result<f64> {
+    let number = match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.trim().parse().ok(),
+        _ => None,
+    };
+    number.ok_or_else(|| {
+        tera::Error::msg(format!(
+            "Filter `{}` received a value that is not a number: {}",
+            filter, value
+        ))
+    })
+}
+
+fn decimals_arg(args: &HashMap<String, Value>, filter: &str) -> tera::Result<Option<usize>> {
+    match args.get("decimals") {
+        None => Ok(None),
+        Some(decimals) => decimals
+            .as_u64()
+            .map(|decimals| Some(decimals.min(10) as usize))
+            .ok_or_else(|| {
+                tera::Error::msg(format!(
+                    "Filter `{}`: `decimals` must be a positive integer",
+                    filter
+                ))
+            }),
+    }
+}
+
+/// With `decimals` or otherwise at most 2 decimals without trailing zeros
+fn format_number(locale: &Locale, number: f64, decimals: Option<usize>) -> String {
+    let formatted = format!("{:.*}", decimals.unwrap_or(2), number.abs());
+    let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+    let fraction = match decimals {
+        Some(_) => fraction,
+        None => fraction.trim_end_matches('0'),
+    };
+
+    let mut grouped = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            grouped.push_str(locale.group_separator);
+        }
+        grouped.push(digit);
+    }
+    let is_zero = formatted.chars().all(|c| matches!(c, '0' | '.'));
+    let sign = if number < 0.0 && !is_zero { "-" } else { "" };
+    if fraction.is_empty() {
+        format!("{}{}", sign, grouped)
+    } else {
+        format!(
+            "{}{}{}{}",
+            sign, grouped, locale.decimal_separator, fraction
+        )
+    }
+}
+
+/// Date (`2024-01-31`), naive datetime (`2024-01-31T13:30:00`) or RFC 3339 datetime, the
+/// datetime is kept in its offset
+fn parse_datetime(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.naive_local());
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(datetime);
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+}
+
+fn format_date(locale: &Locale, datetime: NaiveDateTime, format: &str) -> tera::Result<String> {
+    let month = datetime.month0() as usize;
+    let formatted = match format {
+        "short" => datetime.format(locale.short_date).to_string(),
+        "datetime" => format!(
+            "{} {}",
+            datetime.format(locale.short_date),
+            datetime.format("%H:%M")
+        ),
+        "long" => locale
+            .long_date
+            .replace("{day}", &datetime.day().to_string())
+            .replace("{month}", locale.months[month])
+            .replace("{year}", &datetime.year().to_string()),
+        "month" => locale
+            .month_year
+            .replace(
+                "{month}",
+                locale.standalone_months.unwrap_or(locale.months)[month],
+            )
+            .replace("{year}", &datetime.year().to_string()),
+        format => {
+            return Err(tera::Error::msg(format!(
+                "Filter `format_date`: unknown format {} (short, long, month or datetime)",
+                format
+            )))
+        }
+    };
+    Ok(formatted)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(locale: &str, template: &str, data: Value) -> tera::Result<String> {
+        let resources = HashMap::from([
+            (
+                "translations.en".to_string(),
+                json!({"title": "Stock", "page": "Page {number} of {total}", "only_en": "English"}),
+            ),
+            (
+                "translations.fr".to_string(),
+                json!({"title": "Stock", "page": "Page {number} sur {total}", "item": {"name": "Nom de l'article"}}),
+            ),
+        ]);
+        let mut tera = tera::Tera::default();
+        register_localisation(&mut tera, locale, &resources);
+        tera.add_raw_template("template.html", template).unwrap();
+        let mut context = tera::Context::new();
+        context.insert("data", &data);
+        tera.render("template.html", &context)
+    }
+
+    #[test]
+    fn test_translate() {
+        let template = r#"{{ t(key="page", number=1, total=data.total) }}|{{ t(key="item.name") }}|{{ t(key="only_en") }}|{{ t(key="missing") }}"#;
+        assert_eq!(
+            render("fr", template, json!({"total": 3})).unwrap(),
+            "Page 1 sur 3|Nom de l&#x27;article|English|missing"
+        );
+        assert_eq!(
+            render("fr-CA", template, json!({"total": 3})).unwrap(),
+            "Page 1 sur 3|Nom de l&#x27;article|English|missing"
+        );
+        assert_eq!(
+            render("ru", template, json!({"total": 3})).unwrap(),
+            "Page 1 of 3|item.name|English|missing"
+        );
+        assert!(render("en", r#"{{ t() }}"#, json!({})).is_err());
+    }
+
+    #[test]
+    fn test_format_number() {
+        let template = r#"{{ data.a | format_number }}|{{ data.b | format_number(decimals=2) }}|{{ data.c | format_currency(symbol="$") }}|{{ data.d | format_number }}"#;
+        let data = json!({"a": 1234567.5, "b": "-1234", "c": 0.5, "d": -0.001});
+        assert_eq!(
+            render("en", template, data.clone()).unwrap(),
+            "1,234,567.5|-1,234.00|$0.50|0"
+        );
+        assert_eq!(
+            render("fr", template, data.clone()).unwrap(),
+            "1\u{a0}234\u{a0}567,5|-1\u{a0}234,00|0,50\u{a0}$|0"
+        );
+        assert_eq!(
+            render("es", template, data).unwrap(),
+            "1.234.567,5|-1.234,00|0,50\u{a0}$|0"
+        );
+        assert!(render("en", r#"{{ "abc" | format_number }}"#, json!({})).is_err());
+    }
+
+    #[test]
+    fn test_format_date() {
+        let template = r#"{{ data.date | format_date }}|{{ data.date | format_date(format="long") }}|{{ data.date | format_date(format="month") }}|{{ data.datetime | format_date(format="datetime") }}"#;
+        let data = json!({"date": "2024-03-05", "datetime": "2024-03-05T13:30:00+07:00"});
+        assert_eq!(
+            render("en", template, data.clone()).unwrap(),
+            "05/03/2024|5 March 2024|March 2024|05/03/2024 13:30"
+        );
+        assert_eq!(
+            render("pt", template, data.clone()).unwrap(),
+            "05/03/2024|5 de março de 2024|março de 2024|05/03/2024 13:30"
+        );
+        assert_eq!(
+            render("ru", template, data).unwrap(),
+            "05.03.2024|5 марта 2024|март 2024|05.03.2024 13:30"
+        );
+        assert!(render("en", r#"{{ "2024-13-01" | format_date }}"#, json!({})).is_err());
+    }
+}
@@ -1,5 +1,13 @@
+pub mod cache;
+mod chart;
 pub mod default_queries;
 pub mod definition;
 mod html_printing;
+pub mod localisation;
+mod native_pdf;
+pub mod preview;
 pub mod report_service;
+pub mod schedule;
 mod string_or_vec;
+mod tabular;
+pub mod validation;
@@ -0,0 +1,131 @@
+/// Standard pdf fonts, they don't need to be embedded and only support the WinAnsi (Latin-1)
+/// character set. Characters outside of it are printed as `?`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum Font {
+    Helvetica,
+    HelveticaBold,
+    HelveticaOblique,
+    HelveticaBoldOblique,
+    Courier,
+    CourierBold,
+}
+
+pub(super) const FONTS: [Font; 6] = [
+    Font::Helvetica,
+    Font::HelveticaBold,
+    Font::HelveticaOblique,
+    Font::HelveticaBoldOblique,
+    Font::Courier,
+    Font::CourierBold,
+];
+
+/// Widths of the characters 32 to 126 in 1/1000 of the font size (from the Adobe font metrics)
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+const COURIER_WIDTH: u16 = 600;
+
+impl Font {
+    pub(super) fn new(bold: bool, italic: bool, monospace: bool) -> Font {
+        match (monospace, bold, italic) {
+            (true, false, _) => Font::Courier,
+            (true, true, _) => Font::CourierBold,
+            (false, false, false) => Font::Helvetica,
+            (false, true, false) => Font::HelveticaBold,
+            (false, false, true) => Font::HelveticaOblique,
+            (false, true, true) => Font::HelveticaBoldOblique,
+        }
+    }
+
+    pub(super) fn base_font(&self) -> &'static str {
+        match self {
+            Font::Helvetica => "Helvetica",
+            Font::HelveticaBold => "Helvetica-Bold",
+            Font::HelveticaOblique => "Helvetica-Oblique",
+            Font::HelveticaBoldOblique => "Helvetica-BoldOblique",
+            Font::Courier => "Courier",
+            Font::CourierBold => "Courier-Bold",
+        }
+    }
+
+    /// Name of the font in the page resources
+    pub(super) fn resource_name(&self) -> String {
+        let index = FONTS.iter().position(|font| font == self).unwrap_or(0);
+        format!("F{}", index + 1)
+    }
+
+    /// Width of the text in pt
+    pub(super) fn text_width(&self, text: &str, size: f32) -> f32 {
+        let width: u32 = encode(text)
+            .into_iter()
+            .map(|byte| self.char_width(byte) as u32)
+            .sum();
+        width as f32 * size / 1000.0
+    }
+
+    fn char_width(&self, byte: u8) -> u16 {
+        let widths = match self {
+            Font::Courier | Font::CourierBold => return COURIER_WIDTH,
+            Font::Helvetica | Font::HelveticaOblique => &HELVETICA_WIDTHS,
+            Font::HelveticaBold | Font::HelveticaBoldOblique => &HELVETICA_BOLD_WIDTHS,
+        };
+        match byte {
+            32..=126 => widths[(byte - 32) as usize],
+            // nbsp
+            160 => widths[0],
+            // approximation for the remaining latin characters
+            _ => 556,
+        }
+    }
+}
+
+/// Encodes text as WinAnsiEncoding
+pub(super) fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            32..=126 | 160..=255 => c as u8,
+            _ => match c {
+                '€' => 0x80,
+                '‚' => 0x82,
+                'ƒ' => 0x83,
+                '„' => 0x84,
+                '…' => 0x85,
+                '†' => 0x86,
+                '‡' => 0x87,
+                'ˆ' => 0x88,
+                '‰' => 0x89,
+                'Š' => 0x8a,
+                '‹' => 0x8b,
+                'Œ' => 0x8c,
+                'Ž' => 0x8e,
+                '‘' => 0x91,
+                '’' => 0x92,
+                '“' => 0x93,
+                '”' => 0x94,
+                '•' => 0x95,
+                '–' => 0x96,
+                '—' => 0x97,
+                '˜' => 0x98,
+                '™' => 0x99,
+                'š' => 0x9a,
+                '›' => 0x9b,
+                'œ' => 0x9c,
+                'ž' => 0x9e,
+                'Ÿ' => 0x9f,
+                '\t' => b' ',
+                _ => b'?',
+            },
+        })
+        .collect()
+}
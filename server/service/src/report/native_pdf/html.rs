@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+
+/// Elements without content or closing tag
+const VOID_ELEMENTS: [&str; 12] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "wbr",
+];
+/// Elements whose content is not parsed as html
+const RAW_TEXT_ELEMENTS: [&str; 3] = ["script", "style", "title"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Element {
+    pub(super) name: String,
+    pub(super) attributes: HashMap<String, String>,
+    pub(super) children: Vec<Node>,
+}
+
+impl Element {
+    fn new(name: &str, attributes: HashMap<String, String>) -> Element {
+        Element {
+            name: name.to_string(),
+            attributes,
+            children: Vec::new(),
+        }
+    }
+
+    pub(super) fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    pub(super) fn has_class(&self, class: &str) -> bool {
+        self.attribute("class")
+            .map(|classes| classes.split_whitespace().any(|c| c == class))
+            .unwrap_or(false)
+    }
+
+    /// Text content of the element and all its descendants
+    pub(super) fn text(&self) -> String {
+        let mut text = String::new();
+        for child in &self.children {
+            match child {
+                Node::Text(t) => text.push_str(t),
+                Node::Element(element) => text.push_str(&element.text()),
+            }
+        }
+        text
+    }
+
+    /// Calls f for this element and all descendant elements
+    pub(super) fn visit<'a>(&'a self, f: &mut impl FnMut(&'a Element)) {
+        f(self);
+        for child in &self.children {
+            if let Node::Element(element) = child {
+                element.visit(f);
+            }
+        }
+    }
+}
+
+/// Tolerant html parser for the documents generated by report templates.
+/// Unclosed elements are closed by the parent's end tag, unexpected end tags are ignored and
+/// cells, rows, list items and paragraphs are implicitly closed like in a browser.
+pub(super) fn parse(html: &str) -> Element {
+    let mut stack = vec![Element::new("#root", HashMap::new())];
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment
+                .find("-->")
+                .map(|end| &comment[end + 3..])
+                .unwrap_or("");
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            // doctype or processing instruction
+            rest = rest.find('>').map(|end| &rest[end + 1..]).unwrap_or("");
+        } else if let Some(end_tag) = rest.strip_prefix("</") {
+            let end = end_tag.find('>').unwrap_or(end_tag.len());
+            let name = end_tag[..end].trim().to_lowercase();
+            rest = end_tag.get(end + 1..).unwrap_or("");
+            close_element(&mut stack, &name);
+        } else if rest.starts_with('<') && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic())
+        {
+            let (name, attributes, self_closing, remaining) = parse_start_tag(&rest[1..]);
+            rest = remaining;
+            implicitly_close(&mut stack, &name);
+
+            let mut element = Element::new(&name, attributes);
+            if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                let end_tag = format!("</{}", name);
+                let end = rest
+                    .to_ascii_lowercase()
+                    .find(&end_tag)
+                    .unwrap_or(rest.len());
+                element.children.push(Node::Text(rest[..end].to_string()));
+                rest = &rest[end..];
+                rest = rest.find('>').map(|end| &rest[end + 1..]).unwrap_or("");
+                push_node(&mut stack, Node::Element(element));
+            } else if self_closing || VOID_ELEMENTS.contains(&name.as_str()) {
+                push_node(&mut stack, Node::Element(element));
+            } else {
+                stack.push(element);
+            }
+        } else {
+            let first = rest.chars().next().map(char::len_utf8).unwrap_or(1);
+            let end = rest[first..]
+                .find('<')
+                .map(|end| end + first)
+                .unwrap_or(rest.len());
+            push_node(&mut stack, Node::Text(decode_entities(&rest[..end])));
+            rest = &rest[end..];
+        }
+    }
+
+    while stack.len() > 1 {
+        let element = stack.pop().unwrap();
+        push_node(&mut stack, Node::Element(element));
+    }
+    stack.pop().unwrap()
+}
+
+fn push_node(stack: &mut [Element], node: Node) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(node);
+    }
+}
+
+/// Pops elements up to and including the last open element with the name
+fn close_element(stack: &mut Vec<Element>, name: &str) {
+    let Some(position) = stack.iter().rposition(|element| element.name == name) else {
+        return;
+    };
+    // don't close the root
+    if position == 0 {
+        return;
+    }
+    while stack.len() > position {
+        let element = stack.pop().unwrap();
+        push_node(stack, Node::Element(element));
+    }
+}
+
+fn implicitly_close(stack: &mut Vec<Element>, name: &str) {
+    let closes: &[&str] = match name {
+        "td" | "th" => &["td", "th"],
+        "tr" => &["td", "th", "tr"],
+        "thead" | "tbody" | "tfoot" => &["td", "th", "tr", "thead", "tbody", "tfoot"],
+        "li" => &["li"],
+        "p" | "div" | "table" | "ul" | "ol" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => &["p"],
+        _ => return,
+    };
+    while stack.len() > 1 && closes.contains(&stack.last().unwrap().name.as_str()) {
+        let element = stack.pop().unwrap();
+        push_node(stack, Node::Element(element));
+    }
+}
+
+/// Parses `name attr="value" ...>` and returns the remaining input after the tag
+fn parse_start_tag(input: &str) -> (String, HashMap<String, String>, bool, &str) {
+    let name_end = input
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(input.len());
+    let name = input[..name_end].to_lowercase();
+    let mut rest = &input[name_end..];
+    let mut attributes = HashMap::new();
+
+    loop {
+        rest = rest.trim_start();
+        if let Some(remaining) = rest.strip_prefix("/>") {
+            return (name, attributes, true, remaining);
+        }
+        if let Some(remaining) = rest.strip_prefix('>') {
+            return (name, attributes, false, remaining);
+        }
+        if rest.is_empty() {
+            return (name, attributes, false, rest);
+        }
+        if let Some(remaining) = rest.strip_prefix('/') {
+            rest = remaining;
+            continue;
+        }
+
+        let attribute_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')
+            .unwrap_or(rest.len())
+            .max(1);
+        let attribute = rest[..attribute_end].to_lowercase();
+        rest = rest[attribute_end..].trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            Some(value) => {
+                let value = value.trim_start();
+                let (value, remaining) = match value.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let end = value[1..].find(quote).map(|end| end + 1);
+                        match end {
+                            Some(end) => (&value[1..end], &value[end + 1..]),
+                            None => (&value[1..], ""),
+                        }
+                    }
+                    _ => {
+                        let end = value
+                            .find(|c: char| c.is_whitespace() || c == '>')
+                            .unwrap_or(value.len());
+                        (&value[..end], &value[end..])
+                    }
+                };
+                rest = remaining;
+                decode_entities(value)
+            }
+            None => String::new(),
+        };
+        attributes.insert(attribute, value);
+    }
+}
+
+pub(super) fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end]).map(|c| (c, end)));
+        match decoded {
+            Some((c, end)) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(number) = entity.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    let c = match entity {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "copy" => '©',
+        "reg" => '®',
+        "deg" => '°',
+        "euro" => '€',
+        "pound" => '£',
+        "times" => '×',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "bull" => '•',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// Parses css declarations like `font-size: 10px; text-align: center`
+pub(super) fn parse_declarations(css: &str) -> Vec<(String, String)> {
+    css.split(';')
+        .filter_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            let value = value.trim().trim_end_matches("!important").trim();
+            Some((property.trim().to_lowercase(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Simple selector, e.g. `td`, `.total` or `td.total`
+#[derive(Debug, PartialEq)]
+struct Selector {
+    tag: Option<String>,
+    class: Option<String>,
+}
+
+impl Selector {
+    fn parse(selector: &str) -> Option<Selector> {
+        let selector = selector.trim();
+        // descendant, child, attribute and pseudo class selectors are not supported
+        if selector.is_empty() || selector.contains([' ', '>', '[', ':', '#', '+', '~', '*']) {
+            return None;
+        }
+        let (tag, class) = match selector.split_once('.') {
+            Some((tag, class)) => (tag, Some(class)),
+            None => (selector, None),
+        };
+        // multiple classes are not supported
+        if class.map(|class| class.contains('.')).unwrap_or(false) {
+            return None;
+        }
+        Some(Selector {
+            tag: (!tag.is_empty()).then(|| tag.to_lowercase()),
+            class: class.map(str::to_string),
+        })
+    }
+
+    fn matches(&self, element: &Element) -> bool {
+        self.tag.iter().all(|tag| *tag == element.name)
+            && self.class.iter().all(|class| element.has_class(class))
+    }
+}
+
+/// Rules of all `<style>` elements, only simple tag and class selectors are supported
+#[derive(Debug, Default)]
+pub(super) struct Stylesheet {
+    rules: Vec<(Selector, Vec<(String, String)>)>,
+    /// Declarations of `@page` rules
+    page: Vec<(String, String)>,
+}
+
+impl Stylesheet {
+    pub(super) fn add(&mut self, css: &str) {
+        let css = strip_css_comments(css);
+        let mut rest = css.as_str();
+        while let Some(block_start) = rest.find('{') {
+            let selectors = rest[..block_start].trim();
+            let Some(block_end) = find_block_end(&rest[block_start..]) else {
+                break;
+            };
+            let block = &rest[block_start + 1..block_start + block_end];
+            rest = &rest[block_start + block_end + 1..];
+
+            if selectors.starts_with("@page") {
+                self.page.extend(parse_declarations(block));
+                continue;
+            }
+            // other at-rules like @media
+            if selectors.starts_with('@') {
+                continue;
+            }
+            let declarations = parse_declarations(block);
+            for selector in selectors.split(',').filter_map(Selector::parse) {
+                self.rules.push((selector, declarations.clone()));
+            }
+        }
+    }
+
+    pub(super) fn page_declarations(&self) -> &[(String, String)] {
+        &self.page
+    }
+
+    /// Declarations that apply to the element, in order of precedence (last wins)
+    pub(super) fn declarations(&self, element: &Element) -> Vec<(String, String)> {
+        let mut matching: Vec<_> = self
+            .rules
+            .iter()
+            .filter(|(selector, _)| selector.matches(element))
+            .collect();
+        // class selectors are more specific than tag selectors
+        matching.sort_by_key(|(selector, _)| selector.class.is_some() as u8);
+
+        let mut declarations: Vec<_> = matching
+            .into_iter()
+            .flat_map(|(_, declarations)| declarations.iter().cloned())
+            .collect();
+        if let Some(style) = element.attribute("style") {
+            declarations.extend(parse_declarations(style));
+        }
+        declarations
+    }
+}
+
+fn strip_css_comments(css: &str) -> String {
+    let mut result = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        result.push_str(&rest[..start]);
+        rest = rest[start + 2..]
+            .find("*/")
+            .map(|end| &rest[start + 2 + end + 2..])
+            .unwrap_or("");
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Index of the `}` matching the `{` at the start of the input
+fn find_block_end(input: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in input.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn element(node: &Node) -> &Element {
+        match node {
+            Node::Element(element) => element,
+            Node::Text(text) => panic!("Expected element, got text {}", text),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let root = parse(
+            r#"<!DOCTYPE html><html><body class="main">
+            <!-- comment --><p>Tom &amp; Jerry&nbsp;&#8364;<br/>second<p>third
+            <table border=1><tr><td>a<td>b<tr><td colspan='2'>c</table>
+            <style>td > b { color: red }</style></body></html>"#,
+        );
+
+        let html = element(&root.children[0]);
+        let body = element(&html.children[0]);
+        assert!(body.has_class("main"));
+
+        let paragraphs: Vec<_> = body
+            .children
+            .iter()
+            .filter_map(|node| match node {
+                Node::Element(element) if element.name == "p" => Some(element),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(paragraphs.len(), 2);
+        assert_eq!(paragraphs[0].text(), "Tom & Jerry\u{a0}€second");
+
+        let mut cells = Vec::new();
+        let mut rows = 0;
+        let mut style = String::new();
+        root.visit(&mut |element| match element.name.as_str() {
+            "td" => cells.push((element.text(), element.attribute("colspan"))),
+            "tr" => rows += 1,
+            "style" => style = element.text(),
+            _ => {}
+        });
+        assert_eq!(rows, 2);
+        assert_eq!(
+            cells,
+            vec![
+                ("a".to_string(), None),
+                ("b".to_string(), None),
+                ("c".to_string(), Some("2"))
+            ]
+        );
+        assert_eq!(style, "td > b { color: red }");
+    }
+
+    #[test]
+    fn test_stylesheet() {
+        let mut stylesheet = Stylesheet::default();
+        stylesheet.add(
+            "/* comment */ @page { size: A4 } td, .total { font-weight: bold; } \
+            td.total { text-align: right } td b { color: red }",
+        );
+        let root = parse(r#"<td class="total" style="font-size: 8pt">1</td>"#);
+        let td = element(&root.children[0]);
+        assert_eq!(
+            stylesheet.page_declarations(),
+            &[("size".to_string(), "A4".to_string())]
+        );
+        assert_eq!(
+            stylesheet.declarations(td),
+            vec![
+                ("font-weight".to_string(), "bold".to_string()),
+                ("font-weight".to_string(), "bold".to_string()),
+                ("text-align".to_string(), "right".to_string()),
+                ("font-size".to_string(), "8pt".to_string()),
+            ]
+        );
+    }
+}
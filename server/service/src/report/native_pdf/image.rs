@@ -0,0 +1,342 @@
+use std::{
+    convert::TryInto,
+    io::{Read, Write},
+};
+
+use anyhow::{anyhow, bail};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum ColorSpace {
+    Gray,
+    Rgb,
+    Cmyk,
+    /// Palette of rgb colors
+    Indexed(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum ImageFilter {
+    /// Jpeg data
+    Dct,
+    /// Zlib data, with png predictors if colors is set
+    Flate { png_predictor_colors: Option<u8> },
+}
+
+/// Image in a format that can be embedded in a pdf without re-encoding the colors
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Image {
+    pub(super) width: u32,
+    pub(super) height: u32,
+    pub(super) color_space: ColorSpace,
+    pub(super) bits_per_component: u8,
+    pub(super) filter: ImageFilter,
+    pub(super) data: Vec<u8>,
+    /// Zlib compressed alpha channel
+    pub(super) alpha: Option<Vec<u8>>,
+}
+
+/// Loads a png or jpeg image from a data url (`data:image/png;base64,...`), images from other
+/// urls are not supported
+pub(super) fn load_image(src: &str) -> Result<Image, anyhow::Error> {
+    let Some(data_url) = src.trim().strip_prefix("data:") else {
+        bail!("Only data urls are supported");
+    };
+    let (media_type, data) = data_url
+        .split_once(',')
+        .ok_or_else(|| anyhow!("Invalid data url"))?;
+    let Some(media_type) = media_type.strip_suffix(";base64") else {
+        bail!("Only base64 encoded data urls are supported");
+    };
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    let data = STANDARD.decode(data)?;
+
+    match media_type {
+        "image/png" => load_png(&data),
+        "image/jpeg" | "image/jpg" => load_jpeg(data),
+        _ => bail!("Unsupported image type {}", media_type),
+    }
+}
+
+fn load_jpeg(data: Vec<u8>) -> Result<Image, anyhow::Error> {
+    if !data.starts_with(&[0xff, 0xd8]) {
+        bail!("Invalid jpeg");
+    }
+
+    let mut position = 2;
+    while position + 4 <= data.len() {
+        if data[position] != 0xff {
+            bail!("Invalid jpeg marker");
+        }
+        let marker = data[position + 1];
+        // padding
+        if marker == 0xff {
+            position += 1;
+            continue;
+        }
+        let length = u16::from_be_bytes([data[position + 2], data[position + 3]]) as usize;
+        let is_start_of_frame =
+            matches!(marker, 0xc0..=0xcf) && ![0xc4, 0xc8, 0xcc].contains(&marker);
+        if is_start_of_frame {
+            let frame = data
+                .get(position + 4..position + 10)
+                .ok_or_else(|| anyhow!("Invalid jpeg frame"))?;
+            let height = u16::from_be_bytes([frame[1], frame[2]]) as u32;
+            let width = u16::from_be_bytes([frame[3], frame[4]]) as u32;
+            let color_space = match frame[5] {
+                1 => ColorSpace::Gray,
+                3 => ColorSpace::Rgb,
+                4 => ColorSpace::Cmyk,
+                components => bail!("Unsupported jpeg with {} components", components),
+            };
+            return Ok(Image {
+                width,
+                height,
+                color_space,
+                bits_per_component: frame[0],
+                filter: ImageFilter::Dct,
+                data,
+                alpha: None,
+            });
+        }
+        position += 2 + length;
+    }
+    bail!("Jpeg frame not found")
+}
+
+fn load_png(data: &[u8]) -> Result<Image, anyhow::Error> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+    if !data.starts_with(&SIGNATURE) {
+        bail!("Invalid png");
+    }
+
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut compressed = Vec::new();
+    let mut position = SIGNATURE.len();
+    while position + 8 <= data.len() {
+        let length = u32::from_be_bytes(data[position..position + 4].try_into()?) as usize;
+        let chunk_type = &data[position + 4..position + 8];
+        let chunk = data
+            .get(position + 8..position + 8 + length)
+            .ok_or_else(|| anyhow!("Invalid png chunk"))?;
+        match chunk_type {
+            b"IHDR" => header = Some(chunk.to_vec()),
+            b"PLTE" => palette = chunk.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(chunk),
+            b"IEND" => break,
+            _ => {}
+        }
+        // chunk length, type and crc
+        position += length + 12;
+    }
+
+    let header = header.filter(|header| header.len() == 13);
+    let Some(header) = header else {
+        bail!("Png header not found");
+    };
+    let width = u32::from_be_bytes(header[0..4].try_into()?);
+    let height = u32::from_be_bytes(header[4..8].try_into()?);
+    let bit_depth = header[8];
+    let color_type = header[9];
+    if header[12] != 0 {
+        bail!("Interlaced png images are not supported");
+    }
+
+    let (color_space, colors, has_alpha) = match color_type {
+        0 => (ColorSpace::Gray, 1, false),
+        2 => (ColorSpace::Rgb, 3, false),
+        3 => (ColorSpace::Indexed(palette), 1, false),
+        4 => (ColorSpace::Gray, 1, true),
+        6 => (ColorSpace::Rgb, 3, true),
+        _ => bail!("Invalid png color type {}", color_type),
+    };
+    // png only allows 8 or 16 bits per sample with alpha, samples are split by byte below
+    if has_alpha && !matches!(bit_depth, 8 | 16) {
+        bail!(
+            "Invalid png bit depth {} for color type {}",
+            bit_depth,
+            color_type
+        );
+    }
+
+    if !has_alpha {
+        // png image data can be used directly with the png predictor
+        return Ok(Image {
+            width,
+            height,
+            color_space,
+            bits_per_component: bit_depth,
+            filter: ImageFilter::Flate {
+                png_predictor_colors: Some(colors),
+            },
+            data: compressed,
+            alpha: None,
+        });
+    }
+
+    // the alpha channel has to be split from the color channels for the pdf soft mask
+    let mut filtered = Vec::new();
+    ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut filtered)?;
+    let sample_size = (bit_depth / 8) as usize;
+    let pixel_size = (colors as usize + 1) * sample_size;
+    let pixels = unfilter(&filtered, width as usize, height as usize, pixel_size)?;
+
+    let color_size = colors as usize * sample_size;
+    let mut color = Vec::with_capacity(pixels.len() / (colors as usize + 1) * colors as usize);
+    let mut alpha = Vec::with_capacity(pixels.len() / (colors as usize + 1));
+    for pixel in pixels.chunks_exact(pixel_size) {
+        color.extend_from_slice(&pixel[..color_size]);
+        alpha.extend_from_slice(&pixel[color_size..]);
+    }
+
+    Ok(Image {
+        width,
+        height,
+        color_space,
+        bits_per_component: bit_depth,
+        filter: ImageFilter::Flate {
+            png_predictor_colors: None,
+        },
+        data: compress(&color)?,
+        alpha: Some(compress(&alpha)?),
+    })
+}
+
+/// Reverts the png filters of each row (for images with at least 8 bits per sample)
+fn unfilter(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    pixel_size: usize,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let stride = width * pixel_size;
+    if data.len() < height * (stride + 1) {
+        bail!("Png image data is too short");
+    }
+
+    let mut pixels = vec![0u8; height * stride];
+    for row in 0..height {
+        let filter = data[row * (stride + 1)];
+        let source = &data[row * (stride + 1) + 1..(row + 1) * (stride + 1)];
+        let (previous_rows, current_rows) = pixels.split_at_mut(row * stride);
+        let previous = (row > 0).then(|| &previous_rows[(row - 1) * stride..]);
+        let current = &mut current_rows[..stride];
+
+        for i in 0..stride {
+            let left = if i >= pixel_size {
+                current[i - pixel_size]
+            } else {
+                0
+            };
+            let up = previous.map(|previous| previous[i]).unwrap_or(0);
+            let up_left = match previous {
+                Some(previous) if i >= pixel_size => previous[i - pixel_size],
+                _ => 0,
+            };
+            let predicted = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => bail!("Invalid png filter {}", filter),
+            };
+            current[i] = source[i].wrapping_add(predicted);
+        }
+    }
+    Ok(pixels)
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let distance_left = (estimate - left as i16).abs();
+    let distance_up = (estimate - up as i16).abs();
+    let distance_up_left = (estimate - up_left as i16).abs();
+    if distance_left <= distance_up && distance_left <= distance_up_left {
+        left
+    } else if distance_up <= distance_up_left {
+        up
+    } else {
+        up_left
+    }
+}
+
+pub(super) fn compress(data: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// 2x2 rgba png, pixels (red, transparent), (yellow, blue) with sub and up filters
+    fn rgba_png(bit_depth: u8) -> Vec<u8> {
+        let filtered = [
+            1, 255, 0, 0, 255, 1, 0, 0, 1, //
+            2, 0, 255, 0, 0, 0, 0, 255, 255,
+        ];
+        let mut header = Vec::new();
+        header.extend_from_slice(&2u32.to_be_bytes());
+        header.extend_from_slice(&2u32.to_be_bytes());
+        header.extend_from_slice(&[bit_depth, 6, 0, 0, 0]);
+
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+        for (chunk_type, chunk) in [
+            (b"IHDR", header),
+            (b"IDAT", compress(&filtered).unwrap()),
+            (b"IEND", Vec::new()),
+        ] {
+            png.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+            png.extend_from_slice(chunk_type);
+            png.extend_from_slice(&chunk);
+            // crc is not checked
+            png.extend_from_slice(&[0, 0, 0, 0]);
+        }
+        png
+    }
+
+    fn decompress(data: &[u8]) -> Vec<u8> {
+        let mut result = Vec::new();
+        ZlibDecoder::new(data).read_to_end(&mut result).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_load_png() {
+        let src = format!("data:image/png;base64,{}", STANDARD.encode(rgba_png(8)));
+        let image = load_image(&src).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.color_space, ColorSpace::Rgb);
+        assert_eq!(
+            decompress(&image.data),
+            vec![255, 0, 0, 0, 0, 0, 255, 255, 0, 0, 0, 255]
+        );
+        assert_eq!(decompress(&image.alpha.unwrap()), vec![255, 0, 255, 255]);
+
+        // Bit depth below 8 is invalid with alpha
+        let src = format!("data:image/png;base64,{}", STANDARD.encode(rgba_png(4)));
+        assert!(load_image(&src).is_err());
+
+        assert!(load_image("https://example.com/logo.png").is_err());
+        assert!(load_image("data:image/gif;base64,R0lGODlh").is_err());
+    }
+
+    #[test]
+    fn test_load_jpeg() {
+        // SOI, APP0 segment and SOF0 of a 3 component 16x8 image
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, 0xff, 0xc0, 0x00, 0x11, 0x08, 0x00,
+            0x08, 0x00, 0x10, 0x03,
+        ];
+        let image =
+            load_image(&format!("data:image/jpeg;base64,{}", STANDARD.encode(jpeg))).unwrap();
+        assert_eq!((image.width, image.height), (16, 8));
+        assert_eq!(image.color_space, ColorSpace::Rgb);
+        assert_eq!(image.filter, ImageFilter::Dct);
+    }
+}
@@ -0,0 +1,1431 @@
+use std::collections::HashMap;
+
+use super::{
+    font::Font,
+    html::{Element, Node, Stylesheet},
+    image::{load_image, Image},
+    svg::{svg_content, svg_size},
+};
+
+/// pt per css px
+pub(super) const PX: f32 = 0.75;
+const DEFAULT_FONT_SIZE: f32 = 16.0 * PX;
+const LINE_HEIGHT: f32 = 1.2;
+/// Distance of the baseline to the bottom of a line relative to the font size
+const DESCENT: f32 = 0.3;
+const CELL_PADDING: f32 = 2.0;
+const LIST_INDENT: f32 = 18.0;
+pub(super) const BORDER_WIDTH: f32 = 0.5;
+/// Tolerance for rounding errors when checking if content fits into a width
+const TOLERANCE: f32 = 0.01;
+
+/// Rgb components between 0 and 1
+pub(super) type Color = [f32; 3];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum DrawOp {
+    /// y is the baseline of the text
+    Text {
+        x: f32,
+        y: f32,
+        font: Font,
+        size: f32,
+        text: String,
+    },
+    Line {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+    },
+    /// y is the top of the image, image is the index in the loaded images
+    Image {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        image: usize,
+    },
+    /// Filled rectangle, y is the top
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        color: Color,
+    },
+    Polyline {
+        points: Vec<(f32, f32)>,
+        width: f32,
+        color: Color,
+    },
+}
+
+impl DrawOp {
+    pub(super) fn translate(&mut self, dx: f32, dy: f32) {
+        match self {
+            DrawOp::Text { x, y, .. } | DrawOp::Image { x, y, .. } | DrawOp::Rect { x, y, .. } => {
+                *x += dx;
+                *y += dy;
+            }
+            DrawOp::Line { x1, y1, x2, y2 } => {
+                *x1 += dx;
+                *x2 += dx;
+                *y1 += dy;
+                *y2 += dy;
+            }
+            DrawOp::Polyline { points, .. } => points.iter_mut().for_each(|(x, y)| {
+                *x += dx;
+                *y += dy;
+            }),
+        }
+    }
+}
+
+/// Content that is kept together on one page, positions are relative to the top left corner
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct Content {
+    pub(super) height: f32,
+    pub(super) ops: Vec<DrawOp>,
+}
+
+impl Content {
+    pub(super) fn translated(mut self, dx: f32, dy: f32) -> Content {
+        self.ops.iter_mut().for_each(|op| op.translate(dx, dy));
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Block {
+    Content(Content),
+    /// Vertical space between blocks, dropped at the top of a page
+    Space(f32),
+    PageBreak,
+    /// Header rows are repeated on every page the table continues on
+    Table {
+        header: Vec<Content>,
+        rows: Vec<Content>,
+    },
+}
+
+impl Block {
+    fn translate(&mut self, dx: f32) {
+        match self {
+            Block::Content(content) => content.ops.iter_mut().for_each(|op| op.translate(dx, 0.0)),
+            Block::Table { header, rows } => header
+                .iter_mut()
+                .chain(rows.iter_mut())
+                .flat_map(|row| row.ops.iter_mut())
+                .for_each(|op| op.translate(dx, 0.0)),
+            Block::Space(_) | Block::PageBreak => {}
+        }
+    }
+}
+
+/// Stacks blocks into one content, ignoring page breaks
+pub(super) fn stack(blocks: Vec<Block>) -> Content {
+    let mut result = Content::default();
+    let mut pending_space = 0.0;
+    let add = |result: &mut Content, content: Content, pending_space: &mut f32| {
+        let y = result.height + *pending_space;
+        result.height = y + content.height;
+        result.ops.extend(content.translated(0.0, y).ops);
+        *pending_space = 0.0;
+    };
+
+    for block in blocks {
+        match block {
+            Block::Content(content) => add(&mut result, content, &mut pending_space),
+            Block::Space(space) => {
+                if result.height > 0.0 {
+                    pending_space = f32::max(pending_space, space)
+                }
+            }
+            Block::PageBreak => {}
+            Block::Table { header, rows } => {
+                for row in header.into_iter().chain(rows) {
+                    add(&mut result, row, &mut pending_space);
+                }
+            }
+        }
+    }
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Length {
+    Pt(f32),
+    Percent(f32),
+    Em(f32),
+}
+
+impl Length {
+    pub(super) fn parse(value: &str) -> Option<Length> {
+        let value = value.trim().to_lowercase();
+        let unit_start = value
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(unit_start);
+        let number: f32 = number.parse().ok()?;
+        let length = match unit.trim() {
+            // unitless lengths, e.g. in width attributes, are px
+            "" | "px" => Length::Pt(number * PX),
+            "pt" => Length::Pt(number),
+            "%" => Length::Percent(number),
+            "em" => Length::Em(number),
+            "rem" => Length::Pt(number * DEFAULT_FONT_SIZE),
+            "cm" => Length::Pt(number * 72.0 / 2.54),
+            "mm" => Length::Pt(number * 72.0 / 25.4),
+            "in" => Length::Pt(number * 72.0),
+            _ => return None,
+        };
+        Some(length)
+    }
+
+    pub(super) fn resolve(&self, reference: f32, font_size: f32) -> f32 {
+        match self {
+            Length::Pt(pt) => *pt,
+            Length::Percent(percent) => reference * percent / 100.0,
+            Length::Em(em) => font_size * em,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    fn parse(value: &str) -> Option<Align> {
+        match value.trim().to_lowercase().as_str() {
+            "left" | "start" | "justify" => Some(Align::Left),
+            "center" | "middle" => Some(Align::Center),
+            "right" | "end" => Some(Align::Right),
+            _ => None,
+        }
+    }
+
+    /// Top, middle and bottom are mapped to left, center and right
+    fn parse_vertical(value: &str) -> Option<Align> {
+        match value.trim().to_lowercase().as_str() {
+            "top" => Some(Align::Left),
+            "middle" => Some(Align::Center),
+            "bottom" => Some(Align::Right),
+            _ => None,
+        }
+    }
+
+    fn offset(&self, free_space: f32) -> f32 {
+        match self {
+            Align::Left => 0.0,
+            Align::Center => free_space / 2.0,
+            Align::Right => free_space,
+        }
+    }
+}
+
+/// Inherited style properties
+#[derive(Debug, Clone)]
+struct Style {
+    font_size: f32,
+    bold: bool,
+    italic: bool,
+    monospace: bool,
+    preformatted: bool,
+    align: Align,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            font_size: DEFAULT_FONT_SIZE,
+            bold: false,
+            italic: false,
+            monospace: false,
+            preformatted: false,
+            align: Align::Left,
+        }
+    }
+}
+
+impl Style {
+    fn font(&self) -> Font {
+        Font::new(self.bold, self.italic, self.monospace)
+    }
+
+    fn line_height(&self) -> f32 {
+        self.font_size * LINE_HEIGHT
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Display {
+    Block,
+    Inline,
+    None,
+}
+
+/// Style properties that are not inherited
+#[derive(Debug, Clone, Default)]
+struct BoxStyle {
+    display: Option<Display>,
+    page_break_before: bool,
+    page_break_after: bool,
+    border: bool,
+    width: Option<Length>,
+    height: Option<Length>,
+    vertical_align: Option<Align>,
+}
+
+fn is_block_element(name: &str) -> bool {
+    matches!(
+        name,
+        "html"
+            | "body"
+            | "div"
+            | "p"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "ul"
+            | "ol"
+            | "li"
+            | "header"
+            | "footer"
+            | "section"
+            | "article"
+            | "main"
+            | "nav"
+            | "aside"
+            | "center"
+            | "blockquote"
+            | "pre"
+            | "address"
+            | "figure"
+            | "figcaption"
+            | "form"
+            | "fieldset"
+            | "dl"
+            | "dt"
+            | "dd"
+            | "caption"
+    )
+}
+
+fn is_page_break(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "always" | "page" | "left" | "right"
+    )
+}
+
+enum Inline {
+    Word {
+        text: String,
+        font: Font,
+        size: f32,
+        width: f32,
+    },
+    Space {
+        width: f32,
+    },
+    Break {
+        height: f32,
+    },
+    Image {
+        image: usize,
+        width: f32,
+        height: f32,
+    },
+}
+
+/// Collects the inline content of a block and breaks it into lines
+struct Flow {
+    width: f32,
+    align: Align,
+    items: Vec<Inline>,
+    blocks: Vec<Block>,
+}
+
+impl Flow {
+    fn new(width: f32, align: Align) -> Flow {
+        Flow {
+            width,
+            align,
+            items: Vec::new(),
+            blocks: Vec::new(),
+        }
+    }
+
+    fn push_space(&mut self, style: &Style) {
+        if matches!(
+            self.items.last(),
+            Some(Inline::Word { .. } | Inline::Image { .. })
+        ) {
+            let width = style.font().text_width(" ", style.font_size);
+            self.items.push(Inline::Space { width });
+        }
+    }
+
+    fn push_word(&mut self, text: &str, style: &Style) {
+        let font = style.font();
+        self.items.push(Inline::Word {
+            text: text.to_string(),
+            font,
+            size: style.font_size,
+            width: font.text_width(text, style.font_size),
+        });
+    }
+
+    fn push_text(&mut self, text: &str, style: &Style) {
+        if style.preformatted {
+            for (index, line) in text.split('\n').enumerate() {
+                if index > 0 {
+                    self.items.push(Inline::Break {
+                        height: style.line_height(),
+                    });
+                }
+                let line = line.trim_end_matches('\r').replace('\t', "    ");
+                for (index, word) in line.split(' ').enumerate() {
+                    if index > 0 {
+                        let width = style.font().text_width(" ", style.font_size);
+                        self.items.push(Inline::Space { width });
+                    }
+                    if !word.is_empty() {
+                        self.push_word(word, style);
+                    }
+                }
+            }
+            return;
+        }
+
+        // collapse white space, but keep non breaking spaces
+        if text.starts_with(|c: char| c.is_ascii_whitespace()) {
+            self.push_space(style);
+        }
+        let mut words = text
+            .split(|c: char| c.is_ascii_whitespace())
+            .filter(|word| !word.is_empty())
+            .peekable();
+        while let Some(word) = words.next() {
+            self.push_word(word, style);
+            if words.peek().is_some() {
+                self.push_space(style);
+            }
+        }
+        if text.ends_with(|c: char| c.is_ascii_whitespace()) {
+            self.push_space(style);
+        }
+    }
+
+    fn push_block(&mut self, block: Block) {
+        self.flush();
+        self.blocks.push(block);
+    }
+
+    /// Collapses with the previous space
+    fn push_vertical_space(&mut self, space: f32) {
+        self.flush();
+        if let Some(Block::Space(previous)) = self.blocks.last_mut() {
+            *previous = f32::max(*previous, space);
+        } else if space > 0.0 {
+            self.blocks.push(Block::Space(space));
+        }
+    }
+
+    /// Breaks the collected inline content into lines
+    fn flush(&mut self) {
+        let items = std::mem::take(&mut self.items);
+        let mut line = Line::default();
+        let mut pending_space = 0.0;
+
+        for item in items {
+            match item {
+                Inline::Break { height } => {
+                    line.break_height = height;
+                    self.finish_line(std::mem::take(&mut line));
+                    pending_space = 0.0;
+                }
+                Inline::Space { width } => {
+                    if !line.ops.is_empty() {
+                        pending_space = width;
+                    }
+                }
+                Inline::Word {
+                    text,
+                    font,
+                    size,
+                    width,
+                } => {
+                    for (text, width) in split_word(&text, font, size, width, self.width) {
+                        if !line.ops.is_empty()
+                            && line.width + pending_space + width > self.width + TOLERANCE
+                        {
+                            self.finish_line(std::mem::take(&mut line));
+                            pending_space = 0.0;
+                        }
+                        line.add_text(text, font, size, width, pending_space);
+                        pending_space = 0.0;
+                    }
+                }
+                Inline::Image {
+                    image,
+                    width,
+                    height,
+                } => {
+                    if !line.ops.is_empty()
+                        && line.width + pending_space + width > self.width + TOLERANCE
+                    {
+                        self.finish_line(std::mem::take(&mut line));
+                        pending_space = 0.0;
+                    }
+                    line.add_image(image, width, height, pending_space);
+                    pending_space = 0.0;
+                }
+            }
+        }
+        if !line.ops.is_empty() {
+            self.finish_line(line);
+        }
+    }
+
+    fn finish_line(&mut self, line: Line) {
+        let descent = line.max_font_size * DESCENT;
+        let height = f32::max(
+            f32::max(
+                line.max_font_size * LINE_HEIGHT,
+                line.max_image_height + descent,
+            ),
+            line.break_height,
+        );
+        let baseline = height - descent;
+        let offset = self.align.offset(f32::max(self.width - line.width, 0.0));
+
+        let ops = line
+            .ops
+            .into_iter()
+            .map(|mut op| {
+                match &mut op {
+                    DrawOp::Text { y, .. } => *y = baseline,
+                    DrawOp::Image { y, height, .. } => *y = baseline - *height,
+                    DrawOp::Line { .. } | DrawOp::Rect { .. } | DrawOp::Polyline { .. } => {}
+                }
+                op.translate(offset, 0.0);
+                op
+            })
+            .collect();
+        self.blocks.push(Block::Content(Content { height, ops }));
+    }
+}
+
+#[derive(Default)]
+struct Line {
+    width: f32,
+    max_font_size: f32,
+    max_image_height: f32,
+    /// Height of a line only containing a line break
+    break_height: f32,
+    ops: Vec<DrawOp>,
+}
+
+impl Line {
+    fn add_text(&mut self, text: String, font: Font, size: f32, width: f32, space: f32) {
+        self.max_font_size = f32::max(self.max_font_size, size);
+        // merge words of the same font into one text op
+        if let Some(DrawOp::Text {
+            font: previous_font,
+            size: previous_size,
+            text: previous_text,
+            ..
+        }) = self.ops.last_mut()
+        {
+            if *previous_font == font && *previous_size == size {
+                if space > 0.0 {
+                    previous_text.push(' ');
+                }
+                previous_text.push_str(&text);
+                self.width += space + width;
+                return;
+            }
+        }
+        self.ops.push(DrawOp::Text {
+            x: self.width + space,
+            y: 0.0,
+            font,
+            size,
+            text,
+        });
+        self.width += space + width;
+    }
+
+    fn add_image(&mut self, image: usize, width: f32, height: f32, space: f32) {
+        self.max_image_height = f32::max(self.max_image_height, height);
+        self.ops.push(DrawOp::Image {
+            x: self.width + space,
+            y: 0.0,
+            width,
+            height,
+            image,
+        });
+        self.width += space + width;
+    }
+}
+
+/// Splits words that are wider than the available width
+fn split_word(
+    text: &str,
+    font: Font,
+    size: f32,
+    width: f32,
+    available_width: f32,
+) -> Vec<(String, f32)> {
+    if width <= available_width + TOLERANCE {
+        return vec![(text.to_string(), width)];
+    }
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    for c in text.chars() {
+        let mut extended = part.clone();
+        extended.push(c);
+        if !part.is_empty() && font.text_width(&extended, size) > available_width + TOLERANCE {
+            let width = font.text_width(&part, size);
+            parts.push((std::mem::take(&mut part), width));
+            part.push(c);
+        } else {
+            part = extended;
+        }
+    }
+    if !part.is_empty() {
+        let width = font.text_width(&part, size);
+        parts.push((part, width));
+    }
+    parts
+}
+
+/// Minimum and maximum content width of an element, used for the column widths of tables
+#[derive(Default)]
+struct Measure {
+    min: f32,
+    max: f32,
+    line: f32,
+}
+
+impl Measure {
+    fn add(&mut self, width: f32) {
+        self.min = f32::max(self.min, width);
+        self.line += width;
+        self.max = f32::max(self.max, self.line);
+    }
+
+    fn new_line(&mut self) {
+        self.line = 0.0;
+    }
+}
+
+/// Lays out html documents into blocks of draw operations
+pub(super) struct Layout<'a> {
+    stylesheet: &'a Stylesheet,
+    pub(super) images: Vec<Image>,
+    image_indexes: HashMap<String, Option<usize>>,
+    /// Current page and total pages for `pageNumber` and `totalPages` elements in headers and
+    /// footers
+    pub(super) page_numbers: Option<(usize, usize)>,
+}
+
+impl<'a> Layout<'a> {
+    pub(super) fn new(stylesheet: &'a Stylesheet) -> Layout<'a> {
+        Layout {
+            stylesheet,
+            images: Vec::new(),
+            image_indexes: HashMap::new(),
+            page_numbers: None,
+        }
+    }
+
+    pub(super) fn layout(&mut self, root: &Element, width: f32) -> Vec<Block> {
+        let style = Style::default();
+        let mut flow = Flow::new(width, style.align);
+        self.walk_children(root, &style, &mut flow);
+        flow.flush();
+        flow.blocks
+    }
+
+    fn compute_style(&self, element: &Element, parent: &Style) -> (Style, BoxStyle) {
+        let mut style = parent.clone();
+        let mut box_style = BoxStyle::default();
+
+        let heading_size = match element.name.as_str() {
+            "h1" => Some(2.0),
+            "h2" => Some(1.5),
+            "h3" => Some(1.17),
+            "h4" => Some(1.0),
+            "h5" => Some(0.83),
+            "h6" => Some(0.67),
+            _ => None,
+        };
+        if let Some(size) = heading_size {
+            style.font_size *= size;
+            style.bold = true;
+        }
+        match element.name.as_str() {
+            "b" | "strong" => style.bold = true,
+            "i" | "em" | "cite" | "var" => style.italic = true,
+            "code" | "tt" | "kbd" | "samp" => style.monospace = true,
+            "pre" => {
+                style.monospace = true;
+                style.preformatted = true;
+            }
+            "small" | "sub" | "sup" => style.font_size *= 0.83,
+            "center" => style.align = Align::Center,
+            "th" => {
+                style.bold = true;
+                style.align = Align::Center;
+            }
+            "td" => style.align = Align::Left,
+            _ => {}
+        }
+
+        // presentational attributes
+        if let Some(align) = element.attribute("align").and_then(Align::parse) {
+            style.align = align;
+        }
+        box_style.vertical_align = element.attribute("valign").and_then(Align::parse_vertical);
+        box_style.width = element.attribute("width").and_then(Length::parse);
+        box_style.height = element.attribute("height").and_then(Length::parse);
+        box_style.border = element
+            .attribute("border")
+            .map(|border| !matches!(border.trim(), "0" | ""))
+            .unwrap_or(false);
+
+        for (property, value) in self.stylesheet.declarations(element) {
+            let lowercase_value = value.to_lowercase();
+            match property.as_str() {
+                "font-size" => {
+                    let size = match lowercase_value.as_str() {
+                        "xx-small" => Some(9.0 * PX),
+                        "x-small" => Some(10.0 * PX),
+                        "small" => Some(13.0 * PX),
+                        "medium" => Some(16.0 * PX),
+                        "large" => Some(18.0 * PX),
+                        "x-large" => Some(24.0 * PX),
+                        "xx-large" => Some(32.0 * PX),
+                        "smaller" => Some(parent.font_size * 0.83),
+                        "larger" => Some(parent.font_size * 1.2),
+                        _ => Length::parse(&value)
+                            .map(|size| size.resolve(parent.font_size, parent.font_size)),
+                    };
+                    if let Some(size) = size.filter(|size| *size > 0.0) {
+                        style.font_size = size;
+                    }
+                }
+                "font-weight" => {
+                    style.bold = match lowercase_value.as_str() {
+                        "bold" | "bolder" => true,
+                        "normal" | "lighter" => false,
+                        weight => weight
+                            .parse::<u32>()
+                            .map(|weight| weight >= 600)
+                            .unwrap_or(style.bold),
+                    }
+                }
+                "font-style" => style.italic = lowercase_value != "normal",
+                "font-family" => style.monospace = lowercase_value.contains("monospace"),
+                "white-space" => style.preformatted = lowercase_value.starts_with("pre"),
+                "text-align" => {
+                    if let Some(align) = Align::parse(&value) {
+                        style.align = align
+                    }
+                }
+                "vertical-align" => box_style.vertical_align = Align::parse_vertical(&value),
+                "display" => {
+                    box_style.display = match lowercase_value.as_str() {
+                        "none" => Some(Display::None),
+                        "inline" | "inline-block" => Some(Display::Inline),
+                        "block" | "flex" | "grid" => Some(Display::Block),
+                        _ => None,
+                    }
+                }
+                "page-break-before" | "break-before" => {
+                    box_style.page_break_before = is_page_break(&value)
+                }
+                "page-break-after" | "break-after" => {
+                    box_style.page_break_after = is_page_break(&value)
+                }
+                "border" | "border-style" | "border-width" => {
+                    box_style.border = !lowercase_value.contains("none")
+                        && !lowercase_value.contains("hidden")
+                        && !lowercase_value.starts_with('0')
+                }
+                "width" => box_style.width = Length::parse(&value),
+                "height" => box_style.height = Length::parse(&value),
+                _ => {}
+            }
+        }
+        (style, box_style)
+    }
+
+    fn walk_children(&mut self, element: &Element, style: &Style, flow: &mut Flow) {
+        for child in &element.children {
+            match child {
+                Node::Text(text) => flow.push_text(text, style),
+                Node::Element(child) => self.walk_element(child, style, flow),
+            }
+        }
+    }
+
+    fn walk_element(&mut self, element: &Element, parent_style: &Style, flow: &mut Flow) {
+        let name = element.name.as_str();
+        if matches!(
+            name,
+            "head" | "script" | "style" | "title" | "meta" | "link"
+        ) {
+            return;
+        }
+        let (style, box_style) = self.compute_style(element, parent_style);
+        let display = box_style.display.unwrap_or(if is_block_element(name) {
+            Display::Block
+        } else {
+            Display::Inline
+        });
+        if display == Display::None {
+            return;
+        }
+
+        if let Some((page, total)) = self.page_numbers {
+            if element.has_class("pageNumber") || element.has_class("totalPages") {
+                let number = if element.has_class("pageNumber") {
+                    page
+                } else {
+                    total
+                };
+                flow.push_word(&number.to_string(), &style);
+                return;
+            }
+        }
+
+        match name {
+            "br" => flow.items.push(Inline::Break {
+                height: style.line_height(),
+            }),
+            "img" => {
+                let Some((image, width, height)) = self.image(element, &box_style, flow.width)
+                else {
+                    return;
+                };
+                if display == Display::Block {
+                    let offset = style.align.offset(f32::max(flow.width - width, 0.0));
+                    flow.push_block(Block::Content(Content {
+                        height,
+                        ops: vec![DrawOp::Image {
+                            x: offset,
+                            y: 0.0,
+                            width,
+                            height,
+                            image,
+                        }],
+                    }));
+                } else {
+                    flow.items.push(Inline::Image {
+                        image,
+                        width,
+                        height,
+                    });
+                }
+            }
+            // laid out like a block, e.g. a chart
+            "svg" => {
+                let (Some(content), Some((width, _))) =
+                    (svg_content(element, flow.width), svg_size(element))
+                else {
+                    return;
+                };
+                let width = f32::min(width, flow.width);
+                let offset = style.align.offset(f32::max(flow.width - width, 0.0));
+                flow.push_block(Block::Content(content.translated(offset, 0.0)));
+            }
+            "hr" => {
+                flow.push_vertical_space(style.font_size * 0.5);
+                flow.push_block(Block::Content(Content {
+                    height: 1.0,
+                    ops: vec![DrawOp::Line {
+                        x1: 0.0,
+                        y1: 0.5,
+                        x2: flow.width,
+                        y2: 0.5,
+                    }],
+                }));
+                flow.push_vertical_space(style.font_size * 0.5);
+            }
+            "table" => {
+                flow.flush();
+                if box_style.page_break_before {
+                    flow.blocks.push(Block::PageBreak);
+                }
+                let blocks = self.table(element, &style, &box_style, flow.width);
+                flow.blocks.extend(blocks);
+                if box_style.page_break_after {
+                    flow.blocks.push(Block::PageBreak);
+                }
+            }
+            _ if display == Display::Block => {
+                flow.flush();
+                if box_style.page_break_before {
+                    flow.blocks.push(Block::PageBreak);
+                }
+                let space = match name {
+                    "p" => style.font_size * 0.75,
+                    "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => style.font_size * 0.67,
+                    "ul" | "ol" | "pre" | "blockquote" | "dl" => style.font_size * 0.5,
+                    _ => 0.0,
+                };
+                flow.push_vertical_space(space);
+
+                let indent = match name {
+                    "ul" | "ol" => LIST_INDENT,
+                    "blockquote" | "dd" => 2.0 * LIST_INDENT,
+                    _ => 0.0,
+                };
+                let mut block_flow = Flow::new(f32::max(flow.width - indent, 1.0), style.align);
+                if matches!(name, "ul" | "ol") {
+                    self.walk_list(element, &style, &mut block_flow);
+                } else {
+                    // the leading newline of a pre element is ignored
+                    match (name, element.children.first()) {
+                        ("pre", Some(Node::Text(text))) if text.starts_with('\n') => {
+                            block_flow.push_text(&text[1..], &style);
+                            let rest = Element {
+                                children: element.children[1..].to_vec(),
+                                ..element.clone()
+                            };
+                            self.walk_children(&rest, &style, &mut block_flow);
+                        }
+                        _ => self.walk_children(element, &style, &mut block_flow),
+                    }
+                }
+                block_flow.flush();
+                for mut block in block_flow.blocks {
+                    block.translate(indent);
+                    flow.blocks.push(block);
+                }
+
+                flow.push_vertical_space(space);
+                if box_style.page_break_after {
+                    flow.blocks.push(Block::PageBreak);
+                }
+            }
+            _ => self.walk_children(element, &style, flow),
+        }
+    }
+
+    fn walk_list(&mut self, list: &Element, style: &Style, flow: &mut Flow) {
+        let mut number = list
+            .attribute("start")
+            .and_then(|start| start.parse().ok())
+            .unwrap_or(1);
+        for child in &list.children {
+            match child {
+                Node::Element(item) if item.name == "li" => {
+                    let (item_style, _) = self.compute_style(item, style);
+                    flow.flush();
+                    let marker = match list.name.as_str() {
+                        "ol" => format!("{}.", number),
+                        _ => "•".to_string(),
+                    };
+                    number += 1;
+                    flow.push_word(&marker, &item_style);
+                    flow.push_space(&item_style);
+                    self.walk_children(item, &item_style, flow);
+                    flow.flush();
+                }
+                Node::Element(child) => self.walk_element(child, style, flow),
+                Node::Text(text) => flow.push_text(text, style),
+            }
+        }
+    }
+
+    /// Lays out the content of an element without page breaks, e.g. a table cell or header
+    pub(super) fn layout_content(&mut self, element: &Element, width: f32) -> Content {
+        stack(self.layout(element, width))
+    }
+
+    fn image(
+        &mut self,
+        element: &Element,
+        box_style: &BoxStyle,
+        available_width: f32,
+    ) -> Option<(usize, f32, f32)> {
+        let src = element.attribute("src")?;
+        let index = match self.image_indexes.get(src) {
+            Some(index) => *index,
+            None => {
+                let index = match load_image(src) {
+                    Ok(image) => {
+                        self.images.push(image);
+                        Some(self.images.len() - 1)
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to load image for pdf: {}", err);
+                        None
+                    }
+                };
+                self.image_indexes.insert(src.to_string(), index);
+                index
+            }
+        }?;
+
+        let image = &self.images[index];
+        let natural_width = image.width as f32 * PX;
+        let natural_height = image.height as f32 * PX;
+        let width = box_style
+            .width
+            .map(|width| width.resolve(available_width, DEFAULT_FONT_SIZE));
+        // percentage heights depend on the containing block height which is not known
+        let height = box_style
+            .height
+            .filter(|height| !matches!(height, Length::Percent(_)))
+            .map(|height| height.resolve(0.0, DEFAULT_FONT_SIZE));
+        let (mut width, mut height) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            (Some(width), None) => (width, width * natural_height / natural_width),
+            (None, Some(height)) => (height * natural_width / natural_height, height),
+            (None, None) => (natural_width, natural_height),
+        };
+        if width > available_width {
+            height *= available_width / width;
+            width = available_width;
+        }
+        if !(width > 0.0 && height > 0.0) {
+            return None;
+        }
+        Some((index, width, height))
+    }
+
+    fn measure(&mut self, element: &Element, style: &Style, measure: &mut Measure) {
+        for child in &element.children {
+            match child {
+                Node::Text(text) => {
+                    let font = style.font();
+                    let space = font.text_width(" ", style.font_size);
+                    for (index, line) in text.split('\n').enumerate() {
+                        if index > 0 && style.preformatted {
+                            measure.new_line();
+                        }
+                        for word in line.split(|c: char| c.is_ascii_whitespace()) {
+                            if !word.is_empty() {
+                                measure.add(font.text_width(word, style.font_size));
+                                measure.line += space;
+                            }
+                        }
+                    }
+                }
+                Node::Element(child) => {
+                    let (child_style, box_style) = self.compute_style(child, style);
+                    match child.name.as_str() {
+                        "br" => measure.new_line(),
+                        "img" => {
+                            if let Some((_, width, _)) = self.image(child, &box_style, f32::MAX) {
+                                measure.add(width);
+                            }
+                        }
+                        "svg" => {
+                            if let Some((width, _)) = svg_size(child) {
+                                measure.new_line();
+                                measure.add(width);
+                                measure.new_line();
+                            }
+                        }
+                        name if is_block_element(name) || name == "table" || name == "tr" => {
+                            measure.new_line();
+                            self.measure(child, &child_style, measure);
+                            measure.new_line();
+                        }
+                        _ => self.measure(child, &child_style, measure),
+                    }
+                }
+            }
+        }
+    }
+
+    fn table(
+        &mut self,
+        table: &Element,
+        style: &Style,
+        box_style: &BoxStyle,
+        available_width: f32,
+    ) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut header_rows = Vec::new();
+        let mut body_rows = Vec::new();
+        let mut footer_rows = Vec::new();
+        for child in &table.children {
+            let Node::Element(child) = child else {
+                continue;
+            };
+            let (child_style, _) = self.compute_style(child, style);
+            match child.name.as_str() {
+                "caption" => {
+                    let mut flow = Flow::new(available_width, Align::Center);
+                    self.walk_children(child, &child_style, &mut flow);
+                    flow.flush();
+                    blocks.extend(flow.blocks);
+                }
+                "tr" => body_rows.push((child, style.clone())),
+                "thead" | "tbody" | "tfoot" => {
+                    let rows = match child.name.as_str() {
+                        "thead" => &mut header_rows,
+                        "tfoot" => &mut footer_rows,
+                        _ => &mut body_rows,
+                    };
+                    for row in &child.children {
+                        if let Node::Element(row) = row {
+                            if row.name == "tr" {
+                                rows.push((row, child_style.clone()));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        body_rows.extend(footer_rows);
+
+        // cells of each row with their style and column span
+        let mut rows = Vec::new();
+        for (row, section_style) in header_rows.iter().chain(body_rows.iter()) {
+            let (row_style, _) = self.compute_style(row, section_style);
+            let cells: Vec<_> = row
+                .children
+                .iter()
+                .filter_map(|cell| match cell {
+                    Node::Element(cell) if matches!(cell.name.as_str(), "td" | "th") => {
+                        let (cell_style, cell_box) = self.compute_style(cell, &row_style);
+                        let span = cell
+                            .attribute("colspan")
+                            .and_then(|span| span.trim().parse().ok())
+                            .unwrap_or(1usize)
+                            .max(1);
+                        Some((cell, cell_style, cell_box, span))
+                    }
+                    _ => None,
+                })
+                .collect();
+            rows.push(cells);
+        }
+
+        let columns = rows
+            .iter()
+            .map(|cells| cells.iter().map(|(_, _, _, span)| span).sum())
+            .max()
+            .unwrap_or(0);
+        if columns == 0 {
+            return blocks;
+        }
+
+        let table_width = box_style
+            .width
+            .map(|width| width.resolve(available_width, style.font_size))
+            .map(|width| f32::min(width, available_width));
+        let reference_width = table_width.unwrap_or(available_width);
+
+        let mut min_widths = vec![0.0f32; columns];
+        let mut max_widths = vec![0.0f32; columns];
+        let mut fixed_widths: Vec<Option<f32>> = vec![None; columns];
+        for cells in &rows {
+            let mut column = 0;
+            for (cell, cell_style, cell_box, span) in cells {
+                if *span == 1 {
+                    let mut measure = Measure::default();
+                    self.measure(cell, cell_style, &mut measure);
+                    min_widths[column] =
+                        f32::max(min_widths[column], measure.min + 2.0 * CELL_PADDING);
+                    max_widths[column] =
+                        f32::max(max_widths[column], measure.max + 2.0 * CELL_PADDING);
+                    if let Some(width) = cell_box.width {
+                        fixed_widths[column] =
+                            Some(width.resolve(reference_width, cell_style.font_size));
+                    }
+                }
+                column += span;
+            }
+        }
+        let widths = column_widths(
+            &min_widths,
+            &max_widths,
+            &fixed_widths,
+            table_width,
+            available_width,
+        );
+
+        let bordered = box_style.border;
+        let mut layouted_rows = Vec::new();
+        for cells in rows {
+            let mut column = 0;
+            let mut cell_contents = Vec::new();
+            for (cell, cell_style, cell_box, span) in cells {
+                let x: f32 = widths[..column.min(columns)].iter().sum();
+                let width: f32 = widths[column.min(columns)..(column + span).min(columns)]
+                    .iter()
+                    .sum();
+                column += span;
+
+                let mut flow =
+                    Flow::new(f32::max(width - 2.0 * CELL_PADDING, 1.0), cell_style.align);
+                self.walk_children(cell, &cell_style, &mut flow);
+                flow.flush();
+                let content = stack(flow.blocks);
+                cell_contents.push((x, width, content, cell_box));
+            }
+
+            let height = cell_contents
+                .iter()
+                .map(|(_, _, content, cell_box)| {
+                    let min_height = cell_box
+                        .height
+                        .filter(|height| !matches!(height, Length::Percent(_)))
+                        .map(|height| height.resolve(0.0, style.font_size))
+                        .unwrap_or(0.0);
+                    f32::max(content.height + 2.0 * CELL_PADDING, min_height)
+                })
+                .fold(0.0, f32::max);
+
+            let mut ops = Vec::new();
+            for (x, width, content, cell_box) in cell_contents {
+                let free_space = height - content.height - 2.0 * CELL_PADDING;
+                let y = CELL_PADDING
+                    + cell_box
+                        .vertical_align
+                        .unwrap_or(Align::Center)
+                        .offset(free_space);
+                ops.extend(content.translated(x + CELL_PADDING, y).ops);
+                if bordered || cell_box.border {
+                    ops.extend(rectangle(x, 0.0, width, height));
+                }
+            }
+            layouted_rows.push(Content { height, ops });
+        }
+
+        let rows = layouted_rows.split_off(header_rows.len());
+        blocks.push(Block::Table {
+            header: layouted_rows,
+            rows,
+        });
+        blocks
+    }
+}
+
+fn rectangle(x: f32, y: f32, width: f32, height: f32) -> Vec<DrawOp> {
+    let (left, top, right, bottom) = (x, y, x + width, y + height);
+    vec![
+        DrawOp::Line {
+            x1: left,
+            y1: top,
+            x2: right,
+            y2: top,
+        },
+        DrawOp::Line {
+            x1: right,
+            y1: top,
+            x2: right,
+            y2: bottom,
+        },
+        DrawOp::Line {
+            x1: right,
+            y1: bottom,
+            x2: left,
+            y2: bottom,
+        },
+        DrawOp::Line {
+            x1: left,
+            y1: bottom,
+            x2: left,
+            y2: top,
+        },
+    ]
+}
+
+/// Distributes the table width to the columns similar to the browser auto table layout:
+/// columns get at least their minimum content width and the remaining width is distributed
+/// relative to their maximum content width
+fn column_widths(
+    min_widths: &[f32],
+    max_widths: &[f32],
+    fixed_widths: &[Option<f32>],
+    table_width: Option<f32>,
+    available_width: f32,
+) -> Vec<f32> {
+    let min_widths: Vec<f32> = min_widths
+        .iter()
+        .zip(fixed_widths)
+        .map(|(min, fixed)| fixed.map_or(*min, |fixed| f32::max(fixed, *min)))
+        .collect();
+    let max_widths: Vec<f32> = max_widths
+        .iter()
+        .zip(&min_widths)
+        .zip(fixed_widths)
+        .map(|((max, min), fixed)| fixed.unwrap_or(f32::max(*max, *min)))
+        .collect();
+    let min_total: f32 = min_widths.iter().sum();
+    let max_total: f32 = max_widths.iter().sum();
+    let width = table_width.unwrap_or(f32::min(max_total, available_width));
+
+    if min_total >= width {
+        // scale down, content will overflow the cells
+        return min_widths
+            .iter()
+            .map(|min| min * width / min_total.max(f32::EPSILON))
+            .collect();
+    }
+    if max_total <= width {
+        // distribute the remaining space to the columns without fixed width
+        let flexible_total: f32 = max_widths
+            .iter()
+            .zip(fixed_widths)
+            .filter(|(_, fixed)| fixed.is_none())
+            .map(|(max, _)| max)
+            .sum();
+        let remaining = width - max_total;
+        return max_widths
+            .iter()
+            .zip(fixed_widths)
+            .map(|(max, fixed)| match fixed {
+                Some(_) if flexible_total > 0.0 => *max,
+                _ if flexible_total > 0.0 => max + remaining * max / flexible_total,
+                _ => max + remaining * max / max_total.max(f32::EPSILON),
+            })
+            .collect();
+    }
+    let flexible_total = max_total - min_total;
+    min_widths
+        .iter()
+        .zip(&max_widths)
+        .map(|(min, max)| min + (width - min_total) * (max - min) / flexible_total)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::report::native_pdf::html::parse;
+
+    fn texts(content: &Content) -> Vec<(f32, f32, String)> {
+        content
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                DrawOp::Text { x, y, text, .. } => Some((*x, *y, text.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        let stylesheet = Stylesheet::default();
+        let mut layout = Layout::new(&stylesheet);
+        let root =
+            parse("<p>one two <b>three</b>four five</p><p style='text-align: right'>six</p>");
+        // "one two three" fits, "four" doesn't
+        let width = Font::Helvetica.text_width("one two ", DEFAULT_FONT_SIZE)
+            + Font::HelveticaBold.text_width("three", DEFAULT_FONT_SIZE)
+            + 1.0;
+        let content = layout.layout_content(&root, width);
+        let texts = texts(&content);
+
+        let line_height = DEFAULT_FONT_SIZE * LINE_HEIGHT;
+        let baseline = line_height - DEFAULT_FONT_SIZE * DESCENT;
+        assert_eq!(
+            texts
+                .iter()
+                .map(|(_, _, text)| text.as_str())
+                .collect::<Vec<_>>(),
+            vec!["one two", "three", "four five", "six"]
+        );
+        assert_eq!(texts[0].1, baseline);
+        assert_eq!(texts[1].1, baseline);
+        assert_eq!((texts[2].0, texts[2].1), (0.0, baseline + line_height));
+        // paragraph spacing
+        assert_eq!(
+            texts[3].1,
+            baseline + 2.0 * line_height + DEFAULT_FONT_SIZE * 0.75
+        );
+        let six_width = Font::Helvetica.text_width("six", DEFAULT_FONT_SIZE);
+        assert!((texts[3].0 - (width - six_width)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_table() {
+        let mut stylesheet = Stylesheet::default();
+        stylesheet.add("td { border: 1px solid black }");
+        let mut layout = Layout::new(&stylesheet);
+        let root = parse(
+            "<table style='width: 100%'><thead><tr><th>Item</th><th>Quantity</th></thead>
+            <tr><td>Amoxicillin</td><td>10</td></tr>
+            <tr><td>Paracetamol 500mg tablets</td><td>20</td></tr></table>",
+        );
+        let blocks = layout.layout(&root, 400.0);
+        let Block::Table { header, rows } = &blocks[0] else {
+            panic!("Expected table, got {:?}", blocks[0]);
+        };
+        assert_eq!(header.len(), 1);
+        assert_eq!(rows.len(), 2);
+
+        let header_texts = texts(&header[0]);
+        // full width table, columns relative to their content width
+        assert!(header_texts[1].0 > 200.0);
+        // borders of the two cells
+        let lines = rows[0]
+            .ops
+            .iter()
+            .filter(|op| matches!(op, DrawOp::Line { .. }))
+            .count();
+        assert_eq!(lines, 8);
+        // no border on the header
+        assert!(header[0]
+            .ops
+            .iter()
+            .all(|op| !matches!(op, DrawOp::Line { .. })));
+    }
+
+    #[test]
+    fn test_column_widths() {
+        // auto width table that fits
+        assert_eq!(
+            column_widths(&[10.0, 20.0], &[50.0, 100.0], &[None, None], None, 300.0),
+            vec![50.0, 100.0]
+        );
+        // full width table
+        assert_eq!(
+            column_widths(
+                &[10.0, 20.0],
+                &[50.0, 100.0],
+                &[None, None],
+                Some(300.0),
+                300.0
+            ),
+            vec![100.0, 200.0]
+        );
+        // fixed column
+        assert_eq!(
+            column_widths(
+                &[10.0, 20.0],
+                &[50.0, 100.0],
+                &[Some(100.0), None],
+                Some(300.0),
+                300.0
+            ),
+            vec![100.0, 200.0]
+        );
+        // content doesn't fit
+        assert_eq!(
+            column_widths(&[10.0, 20.0], &[110.0, 220.0], &[None, None], None, 130.0),
+            vec![10.0 + 100.0 * 100.0 / 300.0, 20.0 + 100.0 * 200.0 / 300.0]
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! Pdf renderer for the html generated by report templates that doesn't require a browser.
+//!
+//! Supports the subset of html and css used by reports: text with the standard pdf fonts,
+//! headings, paragraphs, lists, tables (with repeated header rows), png and jpeg images from
+//! data urls, inline svg charts, page breaks and a header and footer on every page (including the `pageNumber`
+//! and `totalPages` placeholders). Only simple tag and class css selectors are supported.
+mod font;
+mod html;
+mod image;
+mod layout;
+mod svg;
+mod writer;
+
+use html::{parse, Element, Stylesheet};
+use layout::{stack, Block, Content, DrawOp, Layout, Length};
+
+/// Gap between the header or footer and the page content
+const HEADER_GAP: f32 = 6.0;
+const DEFAULT_MARGIN: f32 = 36.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PageSetup {
+    width: f32,
+    height: f32,
+    margin: f32,
+}
+
+impl PageSetup {
+    /// Reads the `@page { size: A4 landscape; margin: 1cm }` rule
+    fn from_stylesheet(stylesheet: &Stylesheet) -> PageSetup {
+        let mut setup = PageSetup {
+            width: 595.28,
+            height: 841.89,
+            margin: DEFAULT_MARGIN,
+        };
+        for (property, value) in stylesheet.page_declarations() {
+            match property.as_str() {
+                "size" => {
+                    let mut landscape = false;
+                    for part in value.to_lowercase().split_whitespace() {
+                        let size = match part {
+                            "a3" => Some((841.89, 1190.55)),
+                            "a4" => Some((595.28, 841.89)),
+                            "a5" => Some((419.53, 595.28)),
+                            "letter" => Some((612.0, 792.0)),
+                            "legal" => Some((612.0, 1008.0)),
+                            "landscape" => {
+                                landscape = true;
+                                None
+                            }
+                            _ => None,
+                        };
+                        if let Some((width, height)) = size {
+                            setup.width = width;
+                            setup.height = height;
+                        }
+                    }
+                    if landscape && setup.width < setup.height {
+                        std::mem::swap(&mut setup.width, &mut setup.height);
+                    }
+                }
+                "margin" => {
+                    // only a single margin for all sides is supported
+                    if let Some(margin) = value.split_whitespace().next().and_then(Length::parse) {
+                        setup.margin = margin.resolve(setup.width, 12.0);
+                    }
+                }
+                _ => {}
+            }
+        }
+        setup
+    }
+}
+
+/// Renders a report document with an optional header and footer, that are repeated on every
+/// page, to pdf
+pub fn html_to_pdf(
+    document: &str,
+    header: Option<&str>,
+    footer: Option<&str>,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let document = parse(document);
+    let header = header.map(parse);
+    let footer = footer.map(parse);
+
+    let mut stylesheet = Stylesheet::default();
+    for root in [Some(&document), header.as_ref(), footer.as_ref()]
+        .iter()
+        .flatten()
+    {
+        root.visit(&mut |element| {
+            if element.name == "style" {
+                stylesheet.add(&element.text())
+            }
+        });
+    }
+    let setup = PageSetup::from_stylesheet(&stylesheet);
+    let width = setup.width - 2.0 * setup.margin;
+
+    let mut layout = Layout::new(&stylesheet);
+    // page numbers are only known after the page breaks, the header and footer height is
+    // measured with placeholder numbers
+    layout.page_numbers = Some((1, 1));
+    let mut measure = |element: Option<&Element>| {
+        element
+            .map(|element| layout.layout_content(element, width).height)
+            .filter(|height| *height > 0.0)
+            .map(|height| height + HEADER_GAP)
+            .unwrap_or(0.0)
+    };
+    let header_height = measure(header.as_ref());
+    let footer_height = measure(footer.as_ref());
+    layout.page_numbers = None;
+
+    let blocks = layout.layout(&document, width);
+    let body_height = f32::max(
+        setup.height - 2.0 * setup.margin - header_height - footer_height,
+        1.0,
+    );
+    let mut pages = paginate(blocks, body_height);
+
+    let total_pages = pages.len();
+    for (index, ops) in pages.iter_mut().enumerate() {
+        ops.iter_mut()
+            .for_each(|op| op.translate(setup.margin, setup.margin + header_height));
+
+        layout.page_numbers = Some((index + 1, total_pages));
+        if let Some(header) = &header {
+            let content = layout.layout_content(header, width);
+            ops.extend(content.translated(setup.margin, setup.margin).ops);
+        }
+        if let Some(footer) = &footer {
+            let content = layout.layout_content(footer, width);
+            let y = setup.height - setup.margin - footer_height + HEADER_GAP;
+            ops.extend(content.translated(setup.margin, y).ops);
+        }
+    }
+
+    writer::write_pdf(setup.width, setup.height, &pages, &layout.images)
+}
+
+/// Distributes the blocks to pages of the given height, returns the draw operations of each
+/// page relative to the top of the page content
+fn paginate(blocks: Vec<Block>, page_height: f32) -> Vec<Vec<DrawOp>> {
+    let mut paginator = Paginator {
+        page_height,
+        pages: vec![Vec::new()],
+        y: 0.0,
+        pending_space: 0.0,
+    };
+
+    for block in blocks {
+        match block {
+            Block::Content(content) => paginator.place(content),
+            Block::Space(space) => {
+                if paginator.y > 0.0 {
+                    paginator.pending_space = f32::max(paginator.pending_space, space);
+                }
+            }
+            Block::PageBreak => {
+                if paginator.y > 0.0 {
+                    paginator.new_page();
+                }
+            }
+            Block::Table { header, rows } => {
+                let header = stack(header.into_iter().map(Block::Content).collect());
+                let mut rows = rows.into_iter().peekable();
+                // keep the header together with the first row
+                let first_row_height = rows.peek().map(|row| row.height).unwrap_or(0.0);
+                if !paginator.fits(header.height + first_row_height) {
+                    paginator.new_page();
+                }
+                if header.height > 0.0 {
+                    paginator.place(header.clone());
+                }
+                for row in rows {
+                    if !paginator.fits(row.height) {
+                        paginator.new_page();
+                        if header.height > 0.0 {
+                            paginator.place(header.clone());
+                        }
+                    }
+                    paginator.place(row);
+                }
+            }
+        }
+    }
+    paginator.pages
+}
+
+struct Paginator {
+    page_height: f32,
+    pages: Vec<Vec<DrawOp>>,
+    y: f32,
+    pending_space: f32,
+}
+
+impl Paginator {
+    /// Content higher than a page always fits on an empty page (and is cut off)
+    fn fits(&self, height: f32) -> bool {
+        self.y == 0.0 || self.y + self.pending_space + height <= self.page_height
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Vec::new());
+        self.y = 0.0;
+        self.pending_space = 0.0;
+    }
+
+    fn place(&mut self, content: Content) {
+        if !self.fits(content.height) {
+            self.new_page();
+        }
+        let y = self.y + self.pending_space;
+        self.y = y + content.height;
+        self.pending_space = 0.0;
+        if let Some(page) = self.pages.last_mut() {
+            page.extend(content.translated(0.0, y).ops);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn page_count(pdf: &[u8]) -> usize {
+        String::from_utf8_lossy(pdf).matches("/Type /Page ").count()
+    }
+
+    #[test]
+    fn test_html_to_pdf() {
+        let rows: String = (1..=100)
+            .map(|row| format!("<tr><td>Item {}</td><td>{}</td></tr>", row, row * 10))
+            .collect();
+        let document = format!(
+            "<style>.break {{ page-break-after: always }}</style>
+            <h1>Stock report</h1><p class=\"break\">Summary</p>
+            <table border=\"1\"><thead><tr><th>Item</th><th>Quantity</th></tr></thead>{}</table>",
+            rows
+        );
+        let pdf = html_to_pdf(
+            &document,
+            Some("<div>Header</div>"),
+            Some("Page <span class=\"pageNumber\"></span> of <span class=\"totalPages\"></span>"),
+        )
+        .unwrap();
+
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        // summary page and table over multiple pages
+        let pages = page_count(&pdf);
+        assert!(pages >= 3, "{} pages", pages);
+    }
+
+    #[test]
+    fn test_paginate() {
+        let line = |text: &str| {
+            Block::Content(Content {
+                height: 40.0,
+                ops: vec![DrawOp::Text {
+                    x: 0.0,
+                    y: 30.0,
+                    font: font::Font::Helvetica,
+                    size: 12.0,
+                    text: text.to_string(),
+                }],
+            })
+        };
+        let row = |text: &str| match line(text) {
+            Block::Content(content) => content,
+            _ => unreachable!(),
+        };
+        let blocks = vec![
+            line("first"),
+            Block::Space(10.0),
+            line("second"),
+            Block::PageBreak,
+            Block::Space(10.0),
+            Block::Table {
+                header: vec![row("header")],
+                rows: vec![row("row 1"), row("row 2"), row("row 3")],
+            },
+        ];
+        let pages: Vec<Vec<(f32, String)>> = paginate(blocks, 100.0)
+            .into_iter()
+            .map(|ops| {
+                ops.into_iter()
+                    .filter_map(|op| match op {
+                        DrawOp::Text { y, text, .. } => Some((y, text)),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        assert_eq!(
+            pages,
+            vec![
+                vec![(30.0, "first".to_string()), (80.0, "second".to_string())],
+                vec![(30.0, "header".to_string()), (70.0, "row 1".to_string())],
+                vec![(30.0, "header".to_string()), (70.0, "row 2".to_string())],
+                vec![(30.0, "header".to_string()), (70.0, "row 3".to_string())],
+            ]
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! Subset of inline svg used by report charts: rect, line, polyline and text elements (also in g
+//! elements) with solid colors. Transforms, paths, other shapes and text colors are not supported.
+use super::{
+    font::Font,
+    html::{Element, Node},
+    layout::{Color, Content, DrawOp, Length, PX},
+};
+
+const DEFAULT_FONT_SIZE: f32 = 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// Inherited presentation attributes, lengths in svg user units
+#[derive(Debug, Clone, PartialEq)]
+struct SvgStyle {
+    fill: Option<Color>,
+    stroke: Option<Color>,
+    stroke_width: f32,
+    font_size: f32,
+    bold: bool,
+    anchor: Anchor,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        SvgStyle {
+            fill: Some([0.0, 0.0, 0.0]),
+            stroke: None,
+            stroke_width: 1.0,
+            font_size: DEFAULT_FONT_SIZE,
+            bold: false,
+            anchor: Anchor::Start,
+        }
+    }
+}
+
+impl SvgStyle {
+    fn apply(&self, element: &Element) -> SvgStyle {
+        let mut style = self.clone();
+        if let Some(fill) = element.attribute("fill").and_then(parse_color) {
+            style.fill = fill;
+        }
+        if let Some(stroke) = element.attribute("stroke").and_then(parse_color) {
+            style.stroke = stroke;
+        }
+        if let Some(stroke_width) = number_attribute(element, "stroke-width") {
+            style.stroke_width = stroke_width;
+        }
+        if let Some(font_size) = number_attribute(element, "font-size") {
+            style.font_size = font_size;
+        }
+        if let Some(font_weight) = element.attribute("font-weight") {
+            style.bold = matches!(
+                font_weight.trim(),
+                "bold" | "bolder" | "600" | "700" | "800" | "900"
+            );
+        }
+        match element.attribute("text-anchor").map(str::trim) {
+            Some("start") => style.anchor = Anchor::Start,
+            Some("middle") => style.anchor = Anchor::Middle,
+            Some("end") => style.anchor = Anchor::End,
+            _ => {}
+        }
+        style
+    }
+}
+
+/// Maps svg user units to pt relative to the top left corner of the svg
+struct Transform {
+    min_x: f32,
+    min_y: f32,
+    scale_x: f32,
+    scale_y: f32,
+}
+
+impl Transform {
+    fn point(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            (x - self.min_x) * self.scale_x,
+            (y - self.min_y) * self.scale_y,
+        )
+    }
+}
+
+/// Size of the svg in pt, from the width and height attributes or the view box
+pub(super) fn svg_size(element: &Element) -> Option<(f32, f32)> {
+    let view_box = view_box(element);
+    let length = |name: &str| match element.attribute(name).and_then(Length::parse) {
+        Some(Length::Pt(length)) => Some(length),
+        _ => None,
+    };
+    let width = length("width").or(view_box.map(|(_, _, width, _)| width * PX))?;
+    let height = length("height").or(view_box.map(|(_, _, _, height)| height * PX))?;
+    (width > 0.0 && height > 0.0).then_some((width, height))
+}
+
+/// Draws the svg, scaled down to `max_width` if it's wider
+pub(super) fn svg_content(element: &Element, max_width: f32) -> Option<Content> {
+    let (natural_width, natural_height) = svg_size(element)?;
+    let (width, height) = if natural_width > max_width {
+        (max_width, natural_height * max_width / natural_width)
+    } else {
+        (natural_width, natural_height)
+    };
+    let (min_x, min_y, view_width, view_height) =
+        view_box(element).unwrap_or((0.0, 0.0, natural_width / PX, natural_height / PX));
+    let transform = Transform {
+        min_x,
+        min_y,
+        scale_x: width / view_width,
+        scale_y: height / view_height,
+    };
+
+    let mut ops = Vec::new();
+    draw_children(
+        element,
+        &SvgStyle::default().apply(element),
+        &transform,
+        &mut ops,
+    );
+    Some(Content { height, ops })
+}
+
+fn draw_children(
+    element: &Element,
+    style: &SvgStyle,
+    transform: &Transform,
+    ops: &mut Vec<DrawOp>,
+) {
+    for child in &element.children {
+        let Node::Element(child) = child else {
+            continue;
+        };
+        let style = style.apply(child);
+        let number = |name: &str| number_attribute(child, name).unwrap_or(0.0);
+        match child.name.as_str() {
+            "g" => draw_children(child, &style, transform, ops),
+            "rect" => {
+                let (x, y) = transform.point(number("x"), number("y"));
+                let width = number("width") * transform.scale_x;
+                let height = number("height") * transform.scale_y;
+                if !(width > 0.0 && height > 0.0) {
+                    continue;
+                }
+                if let Some(color) = style.fill {
+                    ops.push(DrawOp::Rect {
+                        x,
+                        y,
+                        width,
+                        height,
+                        color,
+                    });
+                }
+                if let Some(color) = style.stroke {
+                    ops.push(DrawOp::Polyline {
+                        points: vec![
+                            (x, y),
+                            (x + width, y),
+                            (x + width, y + height),
+                            (x, y + height),
+                            (x, y),
+                        ],
+                        width: style.stroke_width * transform.scale_x,
+                        color,
+                    });
+                }
+            }
+            "line" | "polyline" => {
+                let Some(color) = style.stroke else {
+                    continue;
+                };
+                let points = if child.name == "line" {
+                    vec![
+                        transform.point(number("x1"), number("y1")),
+                        transform.point(number("x2"), number("y2")),
+                    ]
+                } else {
+                    parse_points(child.attribute("points").unwrap_or_default())
+                        .into_iter()
+                        .map(|(x, y)| transform.point(x, y))
+                        .collect()
+                };
+                if points.len() < 2 {
+                    continue;
+                }
+                ops.push(DrawOp::Polyline {
+                    points,
+                    width: style.stroke_width * transform.scale_x,
+                    color,
+                });
+            }
+            "text" => {
+                let text = child
+                    .text()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if text.is_empty() {
+                    continue;
+                }
+                let (x, y) = transform.point(number("x"), number("y"));
+                let font = Font::new(style.bold, false, false);
+                let size = style.font_size * transform.scale_y;
+                let x = match style.anchor {
+                    Anchor::Start => x,
+                    Anchor::Middle => x - font.text_width(&text, size) / 2.0,
+                    Anchor::End => x - font.text_width(&text, size),
+                };
+                ops.push(DrawOp::Text {
+                    x,
+                    y,
+                    font,
+                    size,
+                    text,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn view_box(element: &Element) -> Option<(f32, f32, f32, f32)> {
+    let values: Vec<f32> = element
+        .attribute("viewbox")?
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse().ok())
+        .collect::<Option<_>>()?;
+    match values.as_slice() {
+        [min_x, min_y, width, height] if *width > 0.0 && *height > 0.0 => {
+            Some((*min_x, *min_y, *width, *height))
+        }
+        _ => None,
+    }
+}
+
+fn number_attribute(element: &Element, name: &str) -> Option<f32> {
+    element
+        .attribute(name)?
+        .trim()
+        .trim_end_matches("px")
+        .parse()
+        .ok()
+}
+
+/// `x1,y1 x2,y2 ...`, commas and whitespace are interchangeable
+fn parse_points(points: &str) -> Vec<(f32, f32)> {
+    let numbers: Vec<f32> = points
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter_map(|value| value.parse().ok())
+        .collect();
+    numbers
+        .chunks_exact(2)
+        .map(|point| (point[0], point[1]))
+        .collect()
+}
+
+/// `Some(None)` for `none`, `None` for unsupported values (the inherited color is used)
+fn parse_color(value: &str) -> Option<Option<Color>> {
+    let value = value.trim().to_lowercase();
+    let hex = |digits: &str| {
+        u8::from_str_radix(digits, 16)
+            .ok()
+            .map(|c| c as f32 / 255.0)
+    };
+    let color = match value.as_str() {
+        "none" | "transparent" => return Some(None),
+        "black" => [0.0, 0.0, 0.0],
+        "white" => [1.0, 1.0, 1.0],
+        "red" => [1.0, 0.0, 0.0],
+        "green" => [0.0, 128.0 / 255.0, 0.0],
+        "blue" => [0.0, 0.0, 1.0],
+        "gray" | "grey" => [128.0 / 255.0; 3],
+        value => {
+            let digits = value.strip_prefix('#')?;
+            if !digits.is_ascii() {
+                return None;
+            }
+            match digits.len() {
+                3 => {
+                    // e.g. #f00 is #ff0000
+                    let digit = |index: usize| {
+                        u8::from_str_radix(&digits[index..index + 1], 16)
+                            .ok()
+                            .map(|c| (c * 17) as f32 / 255.0)
+                    };
+                    [digit(0)?, digit(1)?, digit(2)?]
+                }
+                6 => [
+                    hex(&digits[0..2])?,
+                    hex(&digits[2..4])?,
+                    hex(&digits[4..6])?,
+                ],
+                _ => return None,
+            }
+        }
+    };
+    Some(Some(color))
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::html::parse;
+    use super::*;
+
+    fn svg_element(html: &str) -> Element {
+        let root = parse(html);
+        let Some(Node::Element(svg)) = root.children.into_iter().next() else {
+            panic!("Expected svg element");
+        };
+        svg
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#ff0000"), Some(Some([1.0, 0.0, 0.0])));
+        assert_eq!(parse_color("#FFF"), Some(Some([1.0, 1.0, 1.0])));
+        assert_eq!(parse_color("none"), Some(None));
+        assert_eq!(parse_color("rgb(1, 2, 3)"), None);
+    }
+
+    #[test]
+    fn test_svg_content() {
+        let svg = svg_element(
+            r##"<svg width="200" height="100" viewBox="0 0 400 200" font-size="20">
+            <g stroke="#ff0000"><line x1="0" y1="0" x2="400" y2="200"/></g>
+            <rect x="100" y="100" width="40" height="100" fill="#0000ff"/>
+            <polyline points="0,200 200,0 400,200" fill="none" stroke="black" stroke-width="2"/>
+            <text x="200" y="40" text-anchor="middle" font-weight="bold">Title</text>
+            </svg>"##,
+        );
+        assert_eq!(svg_size(&svg), Some((150.0, 75.0)));
+
+        // scaled to 100pt wide, i.e. 0.25 pt per user unit
+        let content = svg_content(&svg, 100.0).unwrap();
+        assert_eq!(content.height, 50.0);
+        let bold = Font::HelveticaBold;
+        assert_eq!(
+            content.ops,
+            vec![
+                DrawOp::Polyline {
+                    points: vec![(0.0, 0.0), (100.0, 50.0)],
+                    width: 0.25,
+                    color: [1.0, 0.0, 0.0],
+                },
+                DrawOp::Rect {
+                    x: 25.0,
+                    y: 25.0,
+                    width: 10.0,
+                    height: 25.0,
+                    color: [0.0, 0.0, 1.0],
+                },
+                DrawOp::Polyline {
+                    points: vec![(0.0, 50.0), (50.0, 0.0), (100.0, 50.0)],
+                    width: 0.5,
+                    color: [0.0, 0.0, 0.0],
+                },
+                DrawOp::Text {
+                    x: 50.0 - bold.text_width("Title", 5.0) / 2.0,
+                    y: 10.0,
+                    font: bold,
+                    size: 5.0,
+                    text: "Title".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,344 @@
+use std::fmt::Write;
+
+use super::{
+    font::{encode, FONTS},
+    image::{compress, ColorSpace, Image, ImageFilter},
+    layout::{Color, DrawOp, BORDER_WIDTH},
+};
+
+/// Writes pages of draw operations (top left origin, in pt) as a pdf document
+pub(super) fn write_pdf(
+    page_width: f32,
+    page_height: f32,
+    pages: &[Vec<DrawOp>],
+    images: &[Image],
+) -> Result<Vec<u8>, anyhow::Error> {
+    let mut writer = PdfWriter::default();
+
+    // object ids: catalog, page tree, resources, fonts, images (and their masks), pages
+    let catalog_id = 1;
+    let pages_id = 2;
+    let resources_id = 3;
+    let first_font_id = 4;
+    let mut next_id = first_font_id + FONTS.len();
+    let image_ids: Vec<(usize, Option<usize>)> = images
+        .iter()
+        .map(|image| {
+            let id = next_id;
+            let mask_id = image.alpha.as_ref().map(|_| id + 1);
+            next_id += 1 + mask_id.is_some() as usize;
+            (id, mask_id)
+        })
+        .collect();
+    let page_ids: Vec<(usize, usize)> = pages
+        .iter()
+        .enumerate()
+        .map(|(index, _)| (next_id + 2 * index, next_id + 2 * index + 1))
+        .collect();
+
+    writer.object(
+        catalog_id,
+        format!("<< /Type /Catalog /Pages {} 0 R >>", pages_id).as_bytes(),
+    );
+    let kids: Vec<String> = page_ids
+        .iter()
+        .map(|(page_id, _)| format!("{} 0 R", page_id))
+        .collect();
+    writer.object(
+        pages_id,
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            page_ids.len()
+        )
+        .as_bytes(),
+    );
+
+    let fonts: String = FONTS
+        .iter()
+        .enumerate()
+        .map(|(index, font)| format!("/{} {} 0 R ", font.resource_name(), first_font_id + index))
+        .collect();
+    let xobjects: String = image_ids
+        .iter()
+        .enumerate()
+        .map(|(index, (id, _))| format!("/Im{} {} 0 R ", index + 1, id))
+        .collect();
+    writer.object(
+        resources_id,
+        format!("<< /Font << {}>> /XObject << {}>> >>", fonts, xobjects).as_bytes(),
+    );
+
+    for (index, font) in FONTS.iter().enumerate() {
+        writer.object(
+            first_font_id + index,
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                font.base_font()
+            )
+            .as_bytes(),
+        );
+    }
+
+    for (image, (id, mask_id)) in images.iter().zip(&image_ids) {
+        let color_space = match &image.color_space {
+            ColorSpace::Gray => "/DeviceGray".to_string(),
+            ColorSpace::Rgb => "/DeviceRGB".to_string(),
+            ColorSpace::Cmyk => "/DeviceCMYK".to_string(),
+            ColorSpace::Indexed(palette) => format!(
+                "[/Indexed /DeviceRGB {} <{}>]",
+                (palette.len() / 3).max(1) - 1,
+                palette
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<String>()
+            ),
+        };
+        let filter = match image.filter {
+            ImageFilter::Dct => "/Filter /DCTDecode".to_string(),
+            ImageFilter::Flate {
+                png_predictor_colors: None,
+            } => "/Filter /FlateDecode".to_string(),
+            ImageFilter::Flate {
+                png_predictor_colors: Some(colors),
+            } => format!(
+                "/Filter /FlateDecode /DecodeParms << /Predictor 15 /Colors {} \
+                /BitsPerComponent {} /Columns {} >>",
+                colors, image.bits_per_component, image.width
+            ),
+        };
+        let mask = mask_id
+            .map(|mask_id| format!(" /SMask {} 0 R", mask_id))
+            .unwrap_or_default();
+        writer.stream(
+            *id,
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace {} \
+                /BitsPerComponent {} {}{}",
+                image.width, image.height, color_space, image.bits_per_component, filter, mask
+            ),
+            &image.data,
+        );
+
+        if let (Some(mask_id), Some(alpha)) = (mask_id, &image.alpha) {
+            writer.stream(
+                *mask_id,
+                &format!(
+                    "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceGray \
+                    /BitsPerComponent {} /Filter /FlateDecode",
+                    image.width, image.height, image.bits_per_component
+                ),
+                alpha,
+            );
+        }
+    }
+
+    for (ops, (page_id, content_id)) in pages.iter().zip(&page_ids) {
+        writer.object(
+            *page_id,
+            format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources {} 0 R \
+                /Contents {} 0 R >>",
+                pages_id,
+                number(page_width),
+                number(page_height),
+                resources_id,
+                content_id
+            )
+            .as_bytes(),
+        );
+        let content = compress(&content_stream(ops, page_height))?;
+        writer.stream(*content_id, "/Filter /FlateDecode", &content);
+    }
+
+    Ok(writer.finish(catalog_id))
+}
+
+fn content_stream(ops: &[DrawOp], page_height: f32) -> Vec<u8> {
+    let mut content = Vec::new();
+    let mut line_width_set = false;
+    for op in ops {
+        match op {
+            DrawOp::Text {
+                x,
+                y,
+                font,
+                size,
+                text,
+            } => {
+                content.extend_from_slice(
+                    format!(
+                        "BT /{} {} Tf {} {} Td ",
+                        font.resource_name(),
+                        number(*size),
+                        number(*x),
+                        number(page_height - y)
+                    )
+                    .as_bytes(),
+                );
+                content.extend_from_slice(&string(&encode(text)));
+                content.extend_from_slice(b" Tj ET\n");
+            }
+            DrawOp::Line { x1, y1, x2, y2 } => {
+                if !line_width_set {
+                    content.extend_from_slice(format!("{} w\n", number(BORDER_WIDTH)).as_bytes());
+                    line_width_set = true;
+                }
+                content.extend_from_slice(
+                    format!(
+                        "{} {} m {} {} l S\n",
+                        number(*x1),
+                        number(page_height - y1),
+                        number(*x2),
+                        number(page_height - y2)
+                    )
+                    .as_bytes(),
+                );
+            }
+            DrawOp::Image {
+                x,
+                y,
+                width,
+                height,
+                image,
+            } => {
+                content.extend_from_slice(
+                    format!(
+                        "q {} 0 0 {} {} {} cm /Im{} Do Q\n",
+                        number(*width),
+                        number(*height),
+                        number(*x),
+                        number(page_height - y - height),
+                        image + 1
+                    )
+                    .as_bytes(),
+                );
+            }
+            DrawOp::Rect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => {
+                content.extend_from_slice(
+                    format!(
+                        "q {} rg {} {} {} {} re f Q\n",
+                        rgb(color),
+                        number(*x),
+                        number(page_height - y - height),
+                        number(*width),
+                        number(*height)
+                    )
+                    .as_bytes(),
+                );
+            }
+            DrawOp::Polyline {
+                points,
+                width,
+                color,
+            } => {
+                let mut path = format!("q {} RG {} w", rgb(color), number(*width));
+                for (index, (x, y)) in points.iter().enumerate() {
+                    let operator = if index == 0 { "m" } else { "l" };
+                    path.push_str(&format!(
+                        " {} {} {}",
+                        number(*x),
+                        number(page_height - y),
+                        operator
+                    ));
+                }
+                path.push_str(" S Q\n");
+                content.extend_from_slice(path.as_bytes());
+            }
+        }
+    }
+    content
+}
+
+fn rgb(color: &Color) -> String {
+    color
+        .iter()
+        .map(|component| number(*component))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn number(value: f32) -> String {
+    let formatted = format!("{:.2}", value);
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+    match formatted {
+        "-0" | "" => "0".to_string(),
+        formatted => formatted.to_string(),
+    }
+}
+
+/// Pdf literal string
+fn string(bytes: &[u8]) -> Vec<u8> {
+    let mut result = vec![b'('];
+    for byte in bytes {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                result.push(b'\\');
+                result.push(*byte);
+            }
+            32..=126 => result.push(*byte),
+            _ => result.extend_from_slice(format!("\\{:03o}", byte).as_bytes()),
+        }
+    }
+    result.push(b')');
+    result
+}
+
+#[derive(Default)]
+struct PdfWriter {
+    data: Vec<u8>,
+    /// Object id and its offset in data
+    offsets: Vec<(usize, usize)>,
+}
+
+impl PdfWriter {
+    fn object(&mut self, id: usize, content: &[u8]) {
+        if self.data.is_empty() {
+            // binary comment marks the file as binary for transfer programs
+            self.data
+                .extend_from_slice(b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n");
+        }
+        self.offsets.push((id, self.data.len()));
+        self.data
+            .extend_from_slice(format!("{} 0 obj\n", id).as_bytes());
+        self.data.extend_from_slice(content);
+        self.data.extend_from_slice(b"\nendobj\n");
+    }
+
+    fn stream(&mut self, id: usize, dictionary: &str, data: &[u8]) {
+        let mut content =
+            format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+        content.extend_from_slice(data);
+        content.extend_from_slice(b"\nendstream");
+        self.object(id, &content);
+    }
+
+    fn finish(mut self, root_id: usize) -> Vec<u8> {
+        self.offsets.sort_by_key(|(id, _)| *id);
+        let xref_offset = self.data.len();
+        let mut xref = String::new();
+        let _ = writeln!(
+            xref,
+            "xref\n0 {}\n0000000000 65535 f ",
+            self.offsets.len() + 1
+        );
+        for (_, offset) in &self.offsets {
+            let _ = writeln!(xref, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            xref,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            root_id,
+            xref_offset
+        );
+        self.data.extend_from_slice(xref.as_bytes());
+        self.data
+    }
+}
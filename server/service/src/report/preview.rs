@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use super::{
+    chart::register_chart_functions,
+    definition::{ReportDefinition, ReportDefinitionEntry},
+    localisation::register_localisation,
+    report_service::{format_html_document, GeneratedReport, ReportError},
+    tabular,
+    validation::error_chain,
+};
+
+/// Stop replacing missing variables after this many, e.g. if a template loops forever
+const MAX_MISSING_VARIABLES: usize = 500;
+
+pub struct ReportPreview {
+    /// Html document including the header and footer
+    pub html: String,
+    /// Variables used by the templates that are not in the sample data, the arguments or the
+    /// resources. They are rendered as `[variable.path]` placeholders.
+    pub missing_variables: Vec<String>,
+    /// Ref entries, they can't be resolved without a database and are rendered as empty templates
+    pub unresolved_references: Vec<String>,
+}
+
+/// Renders a report definition against sample data, without a database.
+/// Unlike printing, missing variables don't fail the rendering but are replaced by placeholders
+/// and reported. `locale` is the locale of the translations and formatting, e.g. `fr`.
+pub fn preview_report(
+    definition: &ReportDefinition,
+    data: Value,
+    arguments: Option<Value>,
+    locale: &str,
+) -> Result<ReportPreview, ReportError> {
+    let template = definition
+        .index
+        .template
+        .clone()
+        .ok_or(ReportError::TemplateNotSpecified)?;
+
+    let mut templates = HashMap::new();
+    let mut resources = Map::new();
+    let mut unresolved_references = Vec::new();
+    for (name, entry) in &definition.entries {
+        match entry {
+            ReportDefinitionEntry::TeraTemplate(template) => {
+                templates.insert(name.clone(), template.template.clone());
+            }
+            ReportDefinitionEntry::Resource(resource) => {
+                let template = match resource {
+                    Value::String(string) => string.clone(),
+                    resource => resource.to_string(),
+                };
+                templates.insert(name.clone(), template);
+                resources.insert(name.clone(), resource.clone());
+            }
+            ReportDefinitionEntry::Ref(_) => {
+                unresolved_references.push(name.clone());
+                templates.insert(name.clone(), String::new());
+            }
+            _ => {}
+        }
+    }
+    unresolved_references.sort();
+
+    if let Some(ReportDefinitionEntry::TabularTemplate(tabular)) = definition.entries.get(&template)
+    {
+        let document = GeneratedReport {
+            document: tabular::to_html(tabular, &data)?,
+            header: None,
+            footer: None,
+        };
+        return Ok(ReportPreview {
+            html: format_html_document(document),
+            missing_variables: vec![],
+            unresolved_references,
+        });
+    }
+
+    let mut tera = tera::Tera::default();
+    register_chart_functions(&mut tera);
+    register_localisation(&mut tera, locale, &resources.clone().into_iter().collect());
+    tera.add_raw_templates(templates).map_err(|err| {
+        ReportError::DocGenerationError(format!("Failed to add templates: {}", error_chain(&err)))
+    })?;
+
+    let mut context = Map::new();
+    context.insert("data".to_string(), data);
+    context.insert("res".to_string(), Value::Object(resources));
+    context.insert("locale".to_string(), Value::String(locale.to_string()));
+    if let Some(arguments) = arguments {
+        context.insert("arguments".to_string(), arguments);
+    }
+    let mut context = Value::Object(context);
+
+    let mut missing_variables = Vec::new();
+    loop {
+        let err = match render(&tera, definition, &template, &context) {
+            Ok(document) => {
+                return Ok(ReportPreview {
+                    html: format_html_document(document),
+                    missing_variables,
+                    unresolved_references,
+                })
+            }
+            Err(err) => error_chain(&err),
+        };
+        // a variable that is still missing after adding its placeholder can't be replaced
+        let variable = missing_variable(&err).filter(|variable| {
+            !missing_variables.contains(variable) && missing_variables.len() < MAX_MISSING_VARIABLES
+        });
+        match variable {
+            Some(variable) if insert_placeholder(&mut context, &variable) => {
+                missing_variables.push(variable)
+            }
+            _ if missing_variables.is_empty() => return Err(ReportError::DocGenerationError(err)),
+            _ => {
+                return Err(ReportError::DocGenerationError(format!(
+                    "{} (missing variables: {})",
+                    err,
+                    missing_variables.join(", ")
+                )))
+            }
+        }
+    }
+}
+
+fn render(
+    tera: &tera::Tera,
+    definition: &ReportDefinition,
+    template: &str,
+    context: &Value,
+) -> Result<GeneratedReport, tera::Error> {
+    let context = tera::Context::from_value(context.clone())?;
+    let render = |name: &Option<String>| {
+        name.as_ref()
+            .map(|name| tera.render(name, &context))
+            .transpose()
+    };
+    Ok(GeneratedReport {
+        document: tera.render(template, &context)?,
+        header: render(&definition.index.header)?,
+        footer: render(&definition.index.footer)?,
+    })
+}
+
+/// Extracts the variable from Tera's "Variable `data.name` not found in context" error
+fn missing_variable(err: &str) -> Option<String> {
+    let (_, rest) = err.split_once("Variable `")?;
+    let (variable, rest) = rest.split_once('`')?;
+    rest.starts_with(" not found in context")
+        .then(|| variable.to_string())
+}
+
+/// Sets the dot separated path in the context to a `[path]` placeholder, returns false if the
+/// path can't be set
+fn insert_placeholder(context: &mut Value, path: &str) -> bool {
+    if path.contains('[') {
+        return false;
+    }
+    let keys: Vec<&str> = path.split('.').collect();
+    let mut value = context;
+    for (index, key) in keys.iter().enumerate() {
+        match value {
+            // array items can't be added without changing the number of items
+            Value::Array(_) => return false,
+            Value::Object(_) => {}
+            // placeholders of parent paths are replaced
+            _ => *value = Value::Object(Map::new()),
+        }
+        let Value::Object(object) = value else {
+            return false;
+        };
+        if index == keys.len() - 1 {
+            object.insert(key.to_string(), Value::String(format!("[{}]", path)));
+            return true;
+        }
+        value = object.entry(key.to_string()).or_insert(Value::Null);
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::preview_report;
+
+    #[test]
+    fn test_preview_report() {
+        let definition = serde_json::from_value(json!({
+            "index": {
+                "template": "template.html",
+                "footer": "footer.html",
+                "query": "query"
+            },
+            "entries": {
+                "template.html": {
+                    "type": "TeraTemplate",
+                    "data": {
+                        "output": "Html",
+                        "template": "{{ data.invoice.name }}: {{ data.invoice.store.name }} \
+                            {% for line in data.invoice.lines %}{{ line.item }}{% endfor %} \
+                            {{ arguments.comment }} {{ res.title }}"
+                    }
+                },
+                "footer.html": {
+                    "type": "Ref",
+                    "data": { "source": "base" }
+                },
+                "title": { "type": "Resource", "data": "Title" },
+                "query": { "type": "DefaultQuery", "data": "Invoice" }
+            }
+        }))
+        .unwrap();
+
+        let preview = preview_report(
+            &definition,
+            json!({ "invoice": { "name": "Invoice 1", "lines": [{ "item": "Item A" }] } }),
+            None,
+            "en",
+        )
+        .unwrap();
+        assert_eq!(
+            preview.missing_variables,
+            vec!["data.invoice.store.name", "arguments.comment"]
+        );
+        assert_eq!(preview.unresolved_references, vec!["footer.html"]);
+        assert!(preview
+            .html
+            .contains("Invoice 1: [data.invoice.store.name] Item A [arguments.comment] Title"));
+
+        // fields of loop variables fall back to the context
+        let preview = preview_report(
+            &definition,
+            json!({ "invoice": { "name": "Invoice 1", "lines": [{ "name": "Item A" }] } }),
+            Some(json!({ "comment": "Comment" })),
+            "en",
+        )
+        .unwrap();
+        assert_eq!(
+            preview.missing_variables,
+            vec!["data.invoice.store.name", "line.item"]
+        );
+        assert!(preview
+            .html
+            .contains("Invoice 1: [data.invoice.store.name] [line.item] Comment Title"));
+    }
+}
@@ -9,27 +9,39 @@ use util::uuid::uuid;
 use crate::{
     get_default_pagination,
     service_provider::ServiceContext,
+    settings::PdfRenderer,
     static_files::{StaticFileCategory, StaticFileService},
     ListError,
 };
 
 use super::{
+    chart::register_chart_functions,
     default_queries::get_default_gql_query,
     definition::{
-        GraphQlQuery, ReportDefinition, ReportDefinitionEntry, ReportRef, SQLQuery, TeraTemplate,
+        GraphQlQuery, ReportDefinition, ReportDefinitionEntry, ReportOutputType, ReportRef,
+        SQLQuery, TabularTemplate, TeraTemplate,
     },
-    html_printing::html_to_pdf,
+    html_printing::{html_to_pdf, is_chrome_available, launch_chrome},
+    localisation::{register_localisation, user_locale},
+    native_pdf, tabular,
 };
 
 pub enum PrintFormat {
     Pdf,
     Html,
+    /// Only for tabular reports
+    Csv,
+    /// Only for tabular reports
+    Xlsx,
 }
 
 #[derive(Debug)]
 pub enum ReportError {
     RepositoryError(RepositoryError),
-    ReportDefinitionNotFound { report_id: String, msg: String },
+    ReportDefinitionNotFound {
+        report_id: String,
+        msg: String,
+    },
     TemplateNotSpecified,
     QueryNotSpecified,
     MultipleGraphqlQueriesNotAllowed,
@@ -37,6 +49,8 @@ pub enum ReportError {
     QueryError(String),
     DocGenerationError(String),
     HTMLToPDFError(String),
+    /// E.g. csv for a html report
+    UnsupportedPrintFormat(String),
 }
 
 #[derive(Debug, Clone)]
@@ -58,8 +72,21 @@ pub struct ResolvedReportDefinition {
     pub footer: Option<String>,
     /// Map of all found Tera templates in the report definition
     pub templates: HashMap<String, TeraTemplate>,
+    /// Set when the main template is a tabular template
+    pub tabular: Option<TabularTemplate>,
     pub queries: Vec<ResolvedReportQuery>,
     pub resources: HashMap<String, serde_json::Value>,
+    /// Locale the report is rendered in, from the language of the user
+    pub locale: String,
+}
+
+impl ResolvedReportDefinition {
+    pub fn output_type(&self) -> ReportOutputType {
+        match self.tabular {
+            Some(_) => ReportOutputType::Tabular,
+            None => ReportOutputType::Html,
+        }
+    }
 }
 
 pub struct GeneratedReport {
@@ -98,23 +125,57 @@ pub trait ReportServiceTrait: Sync + Send {
         resolve_report_definition(ctx, name, report_definition)
     }
 
-    /// Converts a HTML report to a file for the target PrintFormat and returns file id
+    /// Converts a report to a file for the target PrintFormat and returns file id.
+    /// HTML reports default to pdf, tabular reports default to xlsx (and are printed as a html
+    /// table for html and pdf)
     fn print_html_report(
         &self,
         base_dir: &Option<String>,
+        pdf_renderer: &PdfRenderer,
         report: &ResolvedReportDefinition,
         report_data: serde_json::Value,
         arguments: Option<serde_json::Value>,
         format: Option<PrintFormat>,
     ) -> Result<String, ReportError> {
+        let report_name = report.name.clone();
+
+        if let Some(template) = &report.tabular {
+            let table = || -> Result<GeneratedReport, ReportError> {
+                Ok(GeneratedReport {
+                    document: tabular::to_html(template, &report_data)?,
+                    header: None,
+                    footer: None,
+                })
+            };
+            return match format {
+                Some(PrintFormat::Csv) => {
+                    let csv = tabular::to_csv(template, &report_data)?;
+                    store_report_file(base_dir, &report_name, "csv", csv.as_bytes())
+                }
+                Some(PrintFormat::Xlsx) | None => {
+                    let xlsx = tabular::to_xlsx(template, &report_name, &report_data)?;
+                    store_report_file(base_dir, &report_name, "xlsx", &xlsx)
+                }
+                Some(PrintFormat::Html) => {
+                    print_html_report_to_html(base_dir, table()?, report_name)
+                }
+                Some(PrintFormat::Pdf) => {
+                    print_html_report_to_pdf(base_dir, pdf_renderer, table()?, report_name)
+                }
+            };
+        }
+
         let document = generate_report(report, report_data, arguments)?;
 
         match format {
-            Some(PrintFormat::Html) => {
-                print_html_report_to_html(base_dir, document, report.name.clone())
-            }
+            Some(PrintFormat::Html) => print_html_report_to_html(base_dir, document, report_name),
             Some(PrintFormat::Pdf) | None => {
-                print_html_report_to_pdf(base_dir, document, report.name.clone())
+                print_html_report_to_pdf(base_dir, pdf_renderer, document, report_name)
+            }
+            Some(PrintFormat::Csv) | Some(PrintFormat::Xlsx) => {
+                Err(ReportError::UnsupportedPrintFormat(
+                    "Csv and xlsx are only supported for tabular reports".to_string(),
+                ))
             }
         }
     }
@@ -123,25 +184,42 @@ pub trait ReportServiceTrait: Sync + Send {
 /// Converts a HTML report to a pdf file and returns the file id
 fn print_html_report_to_pdf(
     base_dir: &Option<String>,
+    pdf_renderer: &PdfRenderer,
     document: GeneratedReport,
     report_name: String,
 ) -> Result<String, ReportError> {
-    let id = uuid();
-    // TODO use a proper tmp dir here instead of base_dir?
-    let pdf = html_to_pdf(base_dir, &format_html_document(document), &id)
-        .map_err(|err| ReportError::HTMLToPDFError(format!("{}", err)))?;
-
-    let file_service = StaticFileService::new(base_dir)
-        .map_err(|err| ReportError::DocGenerationError(format!("{}", err)))?;
-    let now: DateTime<Utc> = SystemTime::now().into();
-    let file = file_service
-        .store_file(
-            &format!("{}_{}.pdf", now.format("%Y%m%d_%H%M%S"), report_name),
-            StaticFileCategory::Temporary,
-            &pdf,
+    let chrome = match pdf_renderer {
+        PdfRenderer::Chrome => {
+            Some(launch_chrome().map_err(|err| ReportError::HTMLToPDFError(format!("{}", err)))?)
+        }
+        PdfRenderer::Native => None,
+        PdfRenderer::Auto if !is_chrome_available() => None,
+        // Chrome can be installed but fail to start (e.g. missing libraries or sandbox issues)
+        PdfRenderer::Auto => match launch_chrome() {
+            Ok(browser) => Some(browser),
+            Err(err) => {
+                log::warn!(
+                    "Failed to launch Chrome, using native pdf renderer instead: {}",
+                    err
+                );
+                None
+            }
+        },
+    };
+    let pdf = if let Some(browser) = chrome {
+        let id = uuid();
+        // TODO use a proper tmp dir here instead of base_dir?
+        html_to_pdf(&browser, base_dir, &format_html_document(document), &id)
+    } else {
+        native_pdf::html_to_pdf(
+            &document.document,
+            document.header.as_deref(),
+            document.footer.as_deref(),
         )
-        .map_err(|err| ReportError::DocGenerationError(format!("{}", err)))?;
-    Ok(file.id)
+    }
+    .map_err(|err| ReportError::HTMLToPDFError(format!("{}", err)))?;
+
+    store_report_file(base_dir, &report_name, "pdf", &pdf)
 }
 
 /// Converts the report to a HTML file and returns the file id
@@ -149,15 +227,35 @@ fn print_html_report_to_html(
     base_dir: &Option<String>,
     document: GeneratedReport,
     report_name: String,
+) -> Result<String, ReportError> {
+    store_report_file(
+        base_dir,
+        &report_name,
+        "html",
+        format_html_document(document).as_bytes(),
+    )
+}
+
+/// Stores the printed report as temporary static file and returns the file id
+fn store_report_file(
+    base_dir: &Option<String>,
+    report_name: &str,
+    extension: &str,
+    data: &[u8],
 ) -> Result<String, ReportError> {
     let file_service = StaticFileService::new(base_dir)
         .map_err(|err| ReportError::DocGenerationError(format!("{}", err)))?;
     let now: DateTime<Utc> = SystemTime::now().into();
     let file = file_service
         .store_file(
-            &format!("{}_{}.html", now.format("%Y%m%d_%H%M%S"), report_name),
+            &format!(
+                "{}_{}.{}",
+                now.format("%Y%m%d_%H%M%S"),
+                report_name,
+                extension
+            ),
             StaticFileCategory::Temporary,
-            format_html_document(document).as_bytes(),
+            data,
         )
         .map_err(|err| ReportError::DocGenerationError(format!("{}", err)))?;
     Ok(file.id)
@@ -165,7 +263,7 @@ fn print_html_report_to_html(
 
 /// Puts the document content, header and footer into a <html> template.
 /// This assumes that the document contains the html body.
-fn format_html_document(document: GeneratedReport) -> String {
+pub(super) fn format_html_document(document: GeneratedReport) -> String {
     // ensure that <html> is at the start of the text
     // if not, the cordova printer plugin renders as text not HTML!
     format!(
@@ -216,7 +314,7 @@ fn query_reports(
     Ok(repo.query(pagination, Some(filter.clone()), sort)?)
 }
 
-fn resolve_report(
+pub(crate) fn resolve_report(
     ctx: &ServiceContext,
     report_id: &str,
 ) -> Result<ResolvedReportDefinition, ReportError> {
@@ -236,6 +334,7 @@ fn resolve_report_definition(
 
     let templates = tera_templates_from_resolved_template(&fully_loaded_report)
         .ok_or(ReportError::TemplateNotSpecified)?;
+    let mut tabular_templates = tabular_templates_from_resolved_template(&fully_loaded_report);
 
     // validate index entries are present
     let template =
@@ -246,7 +345,8 @@ fn resolve_report_definition(
             .ok_or(ReportError::InvalidReportDefinition(
                 "Template reference missing".to_string(),
             ))?;
-    if !templates.contains_key(&template) {
+    let tabular = tabular_templates.remove(&template);
+    if !templates.contains_key(&template) && tabular.is_none() {
         return Err(ReportError::InvalidReportDefinition(format!(
             "Invalid template reference: {}",
             template
@@ -287,14 +387,17 @@ fn resolve_report_definition(
     let queries = query_from_resolved_template(query_entry)?;
 
     let resources = resources_from_resolved_template(&fully_loaded_report);
+    let locale = user_locale(&ctx.connection, &ctx.user_id)?;
     Ok(ResolvedReportDefinition {
         name,
         template,
         header: fully_loaded_report.index.header.clone(),
         footer: fully_loaded_report.index.footer.clone(),
         templates,
+        tabular,
         queries,
         resources,
+        locale,
     })
 }
 
@@ -306,10 +409,13 @@ fn generate_report(
     let mut context = tera::Context::new();
     context.insert("data", &report_data);
     context.insert("res", &report.resources);
+    context.insert("locale", &report.locale);
     if let Some(arguments) = arguments {
         context.insert("arguments", &arguments);
     }
     let mut tera = tera::Tera::default();
+    register_chart_functions(&mut tera);
+    register_localisation(&mut tera, &report.locale, &report.resources);
     let mut templates: HashMap<String, String> = report
         .templates
         .iter()
@@ -377,6 +483,21 @@ fn tera_templates_from_resolved_template(
     Some(templates)
 }
 
+fn tabular_templates_from_resolved_template(
+    report: &ReportDefinition,
+) -> HashMap<String, TabularTemplate> {
+    report
+        .entries
+        .iter()
+        .filter_map(|(name, entry)| match entry {
+            ReportDefinitionEntry::TabularTemplate(template) => {
+                Some((name.clone(), template.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 fn query_from_resolved_template(
     query_entries: Vec<&ReportDefinitionEntry>,
 ) -> Result<Vec<ResolvedReportQuery>, ReportError> {
@@ -449,6 +570,7 @@ fn load_template_references(
 ) -> Result<ReportDefinition, ReportError> {
     let mut out = ReportDefinition {
         index: report.index.clone(),
+        version: report.version.clone(),
         entries: HashMap::new(),
     };
     for (name, entry) in report.entries {
@@ -508,9 +630,11 @@ mod report_service_test {
                 DefaultQuery, ReportDefinition, ReportDefinitionEntry, ReportDefinitionIndex,
                 ReportOutputType, ReportRef, TeraTemplate,
             },
-            report_service::generate_report,
+            report_service::{generate_report, PrintFormat},
         },
         service_provider::ServiceProvider,
+        settings::PdfRenderer,
+        static_files::{StaticFileCategory, StaticFileService},
     };
 
     #[actix_rt::test]
@@ -522,6 +646,7 @@ mod report_service_test {
                 footer: Some("footer.html".to_string()),
                 query: vec!["query".to_string()],
             },
+            version: None,
             entries: HashMap::from([
                 (
                     "template.html".to_string(),
@@ -551,6 +676,7 @@ mod report_service_test {
                 footer: Some("footer.html".to_string()),
                 query: vec![],
             },
+            version: None,
             entries: HashMap::from([(
                 "footer.html".to_string(),
                 ReportDefinitionEntry::TeraTemplate(TeraTemplate {
@@ -572,6 +698,7 @@ mod report_service_test {
             comment: None,
             sub_context: None,
             argument_schema_id: None,
+            version: None,
         })
         .unwrap();
         repo.upsert_one(&ReportRow {
@@ -583,6 +710,7 @@ mod report_service_test {
             comment: None,
             sub_context: None,
             argument_schema_id: None,
+            version: None,
         })
         .unwrap();
 
@@ -602,5 +730,28 @@ mod report_service_test {
         )
         .unwrap();
         assert_eq!(doc.document, "Template: Hello Footer");
+
+        // print with the native renderer, i.e. without Chrome
+        let base_dir = tempfile::tempdir().unwrap();
+        let base_dir = Some(base_dir.path().to_string_lossy().to_string());
+        let file_id = service
+            .print_html_report(
+                &base_dir,
+                &PdfRenderer::Native,
+                &resolved_def,
+                serde_json::json!({
+                    "test": "Hello"
+                }),
+                None,
+                Some(PrintFormat::Pdf),
+            )
+            .unwrap();
+        let file = StaticFileService::new(&base_dir)
+            .unwrap()
+            .find_file(&file_id, StaticFileCategory::Temporary)
+            .unwrap()
+            .unwrap();
+        assert!(file.name.ends_with("Report 1.pdf"));
+        assert!(std::fs::read(&file.path).unwrap().starts_with(b"%PDF"));
     }
 }
@@ -0,0 +1,517 @@
+use chrono::{Duration, Months, NaiveDateTime};
+use jsonschema::JSONSchema;
+use repository::{
+    FormSchemaRowRepository, ReportRowRepository, ReportScheduleFormat, ReportScheduleFrequency,
+    ReportScheduleRow, ReportScheduleRowRepository, ReportScheduleRunRow,
+    ReportScheduleRunRowRepository, RepositoryError, StorageConnection,
+};
+use serde_json::Value;
+
+use crate::{
+    service_provider::ServiceContext,
+    static_files::{StaticFileCategory, StaticFileService},
+};
+
+use super::report_service::{
+    resolve_report, ReportError, ResolvedReportDefinition, ResolvedReportQuery,
+};
+
+pub mod processor;
+
+const DEFAULT_RETENTION_DAYS: i32 = 90;
+
+pub struct UpsertReportSchedule {
+    pub id: String,
+    pub report_id: String,
+    pub name: String,
+    pub arguments: Option<Value>,
+    pub format: Option<ReportScheduleFormat>,
+    pub frequency: ReportScheduleFrequency,
+    /// First run when inserting, the next run is calculated from this datetime after each run
+    pub next_run_datetime: NaiveDateTime,
+    pub retention_days: Option<i32>,
+    pub email_recipients: Vec<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UpsertReportScheduleError {
+    ReportScheduleDoesNotBelongToStore,
+    ReportDoesNotExist,
+    /// Report uses a GraphQL query, see `is_report_schedulable`
+    ReportQueryNotSupported,
+    InvalidReport(String),
+    InvalidArguments(Vec<String>),
+    InvalidEmailRecipient(String),
+    InvalidRetentionDays,
+    DatabaseError(RepositoryError),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DeleteReportScheduleError {
+    ReportScheduleDoesNotExist,
+    ReportScheduleDoesNotBelongToStore,
+    DatabaseError(RepositoryError),
+}
+
+impl From<RepositoryError> for UpsertReportScheduleError {
+    fn from(error: RepositoryError) -> Self {
+        UpsertReportScheduleError::DatabaseError(error)
+    }
+}
+
+impl From<RepositoryError> for DeleteReportScheduleError {
+    fn from(error: RepositoryError) -> Self {
+        DeleteReportScheduleError::DatabaseError(error)
+    }
+}
+
+pub fn get_report_schedules(
+    ctx: &ServiceContext,
+) -> Result<Vec<ReportScheduleRow>, RepositoryError> {
+    ReportScheduleRowRepository::new(&ctx.connection).find_many_by_store_id(&ctx.store_id)
+}
+
+/// Runs of a schedule, latest first
+pub fn get_report_schedule_runs(
+    ctx: &ServiceContext,
+    report_schedule_id: &str,
+) -> Result<Vec<ReportScheduleRunRow>, RepositoryError> {
+    ReportScheduleRunRowRepository::new(&ctx.connection)
+        .find_many_by_report_schedule_id(report_schedule_id)
+}
+
+/// Scheduled reports are printed by the server without a GraphQL request (and user session), so
+/// only reports with SQL queries can be scheduled. Reports with GraphQL queries can still be
+/// printed from the UI
+pub fn is_report_schedulable(ctx: &ServiceContext, report_id: &str) -> Result<bool, ReportError> {
+    Ok(is_schedulable(&resolve_report(ctx, report_id)?))
+}
+
+fn is_schedulable(report: &ResolvedReportDefinition) -> bool {
+    !report
+        .queries
+        .iter()
+        .any(|query| matches!(query, ResolvedReportQuery::GraphQlQuery(_)))
+}
+
+pub fn upsert_report_schedule(
+    ctx: &ServiceContext,
+    input: UpsertReportSchedule,
+) -> Result<ReportScheduleRow, UpsertReportScheduleError> {
+    let existing = ReportScheduleRowRepository::new(&ctx.connection).find_one_by_id(&input.id)?;
+    validate(ctx, &input, existing.as_ref())?;
+
+    let row = generate(ctx, input, existing)?;
+    ReportScheduleRowRepository::new(&ctx.connection).upsert_one(&row)?;
+    Ok(row)
+}
+
+/// Deletes the schedule, its runs and printed reports
+pub fn delete_report_schedule(
+    ctx: &ServiceContext,
+    base_dir: &Option<String>,
+    id: &str,
+) -> Result<String, DeleteReportScheduleError> {
+    let schedule = ReportScheduleRowRepository::new(&ctx.connection)
+        .find_one_by_id(id)?
+        .ok_or(DeleteReportScheduleError::ReportScheduleDoesNotExist)?;
+    if schedule.store_id != ctx.store_id {
+        return Err(DeleteReportScheduleError::ReportScheduleDoesNotBelongToStore);
+    }
+
+    ctx.connection
+        .transaction_sync(|connection| {
+            for run in ReportScheduleRunRowRepository::new(connection)
+                .find_many_by_report_schedule_id(id)?
+            {
+                delete_report_schedule_run(connection, base_dir, &run)?;
+            }
+            ReportScheduleRowRepository::new(connection).delete(id)
+        })
+        .map_err(|error| error.to_inner_error())?;
+
+    Ok(id.to_string())
+}
+
+fn validate(
+    ctx: &ServiceContext,
+    input: &UpsertReportSchedule,
+    existing: Option<&ReportScheduleRow>,
+) -> Result<(), UpsertReportScheduleError> {
+    if let Some(existing) = existing {
+        if existing.store_id != ctx.store_id {
+            return Err(UpsertReportScheduleError::ReportScheduleDoesNotBelongToStore);
+        }
+    }
+
+    if input.retention_days.map(|days| days < 1).unwrap_or(false) {
+        return Err(UpsertReportScheduleError::InvalidRetentionDays);
+    }
+
+    if let Some(recipient) = input
+        .email_recipients
+        .iter()
+        .find(|recipient| recipient.parse::<lettre::Address>().is_err())
+    {
+        return Err(UpsertReportScheduleError::InvalidEmailRecipient(
+            recipient.clone(),
+        ));
+    }
+
+    let report = match resolve_report(ctx, &input.report_id) {
+        Ok(report) => report,
+        Err(ReportError::ReportDefinitionNotFound { .. }) => {
+            return Err(UpsertReportScheduleError::ReportDoesNotExist)
+        }
+        Err(ReportError::RepositoryError(error)) => return Err(error.into()),
+        Err(error) => {
+            return Err(UpsertReportScheduleError::InvalidReport(format!(
+                "{:?}",
+                error
+            )))
+        }
+    };
+    if !is_schedulable(&report) {
+        return Err(UpsertReportScheduleError::ReportQueryNotSupported);
+    }
+
+    validate_arguments(&ctx.connection, &input.report_id, &input.arguments)
+}
+
+/// Validates arguments against the argument schema of the report (if it has one)
+fn validate_arguments(
+    connection: &StorageConnection,
+    report_id: &str,
+    arguments: &Option<Value>,
+) -> Result<(), UpsertReportScheduleError> {
+    let Some(schema_id) = ReportRowRepository::new(connection)
+        .find_one_by_id(report_id)?
+        .and_then(|report| report.argument_schema_id)
+    else {
+        return Ok(());
+    };
+    let schema = FormSchemaRowRepository::new(connection)
+        .find_one_by_id(&schema_id)?
+        .ok_or_else(|| {
+            UpsertReportScheduleError::InvalidReport(format!(
+                "Argument schema does not exist: {}",
+                schema_id
+            ))
+        })?;
+    let validator = JSONSchema::compile(&schema.json_schema).map_err(|err| {
+        UpsertReportScheduleError::InvalidReport(format!("Invalid argument schema: {}", err))
+    })?;
+
+    let arguments = arguments
+        .clone()
+        .unwrap_or(Value::Object(Default::default()));
+    validator.validate(&arguments).map_err(|errors| {
+        UpsertReportScheduleError::InvalidArguments(errors.map(|err| format!("{}", err)).collect())
+    })
+}
+
+fn generate(
+    ctx: &ServiceContext,
+    UpsertReportSchedule {
+        id,
+        report_id,
+        name,
+        arguments,
+        format,
+        frequency,
+        next_run_datetime,
+        retention_days,
+        email_recipients,
+        is_active,
+    }: UpsertReportSchedule,
+    existing: Option<ReportScheduleRow>,
+) -> Result<ReportScheduleRow, RepositoryError> {
+    let arguments = arguments
+        .map(|arguments| serde_json::to_string(&arguments))
+        .transpose()
+        .map_err(|err| RepositoryError::DBError {
+            msg: "Can't serialize report arguments".to_string(),
+            extra: format!("{}", err),
+        })?;
+
+    Ok(ReportScheduleRow {
+        id,
+        store_id: ctx.store_id.clone(),
+        report_id,
+        // Runs with the permissions of the user that created the schedule
+        user_id: existing
+            .as_ref()
+            .map(|existing| existing.user_id.clone())
+            .unwrap_or(ctx.user_id.clone()),
+        name,
+        arguments,
+        format,
+        frequency,
+        next_run_datetime,
+        retention_days: retention_days
+            .or(existing.as_ref().map(|existing| existing.retention_days))
+            .unwrap_or(DEFAULT_RETENTION_DAYS),
+        email_recipients: (!email_recipients.is_empty()).then(|| email_recipients.join(",")),
+        is_active: is_active
+            .or(existing.as_ref().map(|existing| existing.is_active))
+            .unwrap_or(true),
+    })
+}
+
+/// Next run after `now`, runs missed while the server was offline are skipped
+pub(crate) fn next_run_datetime(
+    frequency: &ReportScheduleFrequency,
+    previous: NaiveDateTime,
+    now: NaiveDateTime,
+) -> NaiveDateTime {
+    let mut next = previous;
+    while next <= now {
+        next = match frequency {
+            ReportScheduleFrequency::Daily => next + Duration::days(1),
+            ReportScheduleFrequency::Weekly => next + Duration::weeks(1),
+            ReportScheduleFrequency::Monthly => next
+                .checked_add_months(Months::new(1))
+                .unwrap_or(next + Duration::days(30)),
+        };
+    }
+    next
+}
+
+pub(crate) fn delete_report_schedule_run(
+    connection: &StorageConnection,
+    base_dir: &Option<String>,
+    run: &ReportScheduleRunRow,
+) -> Result<(), RepositoryError> {
+    if run.file_name.is_some() {
+        let result = StaticFileService::new(base_dir)
+            .and_then(|service| service.delete_file(&run.id, StaticFileCategory::ScheduledReport));
+        if let Err(error) = result {
+            log::error!(
+                "Failed to delete scheduled report file {}: {}",
+                run.id,
+                error
+            );
+        }
+    }
+    ReportScheduleRunRowRepository::new(connection).delete(&run.id)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDate;
+    use repository::{
+        mock::{mock_store_a, mock_user_account_a, MockDataInserts},
+        test_db::setup_all,
+        ContextType, FormSchemaJson, FormSchemaRowRepository, ReportRow, ReportRowRepository,
+        ReportScheduleFrequency, ReportType,
+    };
+    use serde_json::json;
+
+    use crate::{
+        report::definition::{
+            GraphQlQuery, ReportDefinition, ReportDefinitionEntry, ReportDefinitionIndex,
+            ReportOutputType, SQLQuery, TeraTemplate,
+        },
+        service_provider::ServiceProvider,
+    };
+
+    use super::*;
+
+    fn report_row(id: &str, query: ReportDefinitionEntry) -> ReportRow {
+        let definition = ReportDefinition {
+            index: ReportDefinitionIndex {
+                template: Some("template.html".to_string()),
+                header: None,
+                footer: None,
+                query: vec!["query".to_string()],
+            },
+            version: None,
+            entries: HashMap::from([
+                (
+                    "template.html".to_string(),
+                    ReportDefinitionEntry::TeraTemplate(TeraTemplate {
+                        output: ReportOutputType::Html,
+                        template: "{{data.query | length}} items".to_string(),
+                    }),
+                ),
+                ("query".to_string(), query),
+            ]),
+        };
+        ReportRow {
+            id: id.to_string(),
+            name: id.to_string(),
+            r#type: ReportType::OmSupply,
+            template: serde_json::to_string(&definition).unwrap(),
+            context: ContextType::Stocktake,
+            ..Default::default()
+        }
+    }
+
+    fn upsert_input(report_id: &str) -> UpsertReportSchedule {
+        UpsertReportSchedule {
+            id: "schedule".to_string(),
+            report_id: report_id.to_string(),
+            name: "Monthly stock".to_string(),
+            arguments: Some(json!({ "monthsOverstock": 6 })),
+            format: None,
+            frequency: ReportScheduleFrequency::Monthly,
+            next_run_datetime: NaiveDate::from_ymd_opt(2024, 1, 1)
+                .unwrap()
+                .and_hms_opt(6, 0, 0)
+                .unwrap(),
+            retention_days: None,
+            email_recipients: vec!["manager@example.com".to_string()],
+            is_active: None,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_upsert_report_schedule() {
+        let (_, connection, connection_manager, _) =
+            setup_all("test_upsert_report_schedule", MockDataInserts::all()).await;
+
+        FormSchemaRowRepository::new(&connection)
+            .upsert_one(&FormSchemaJson {
+                id: "arguments_schema".to_string(),
+                r#type: "ReportArguments".to_string(),
+                json_schema: json!({
+                    "type": "object",
+                    "properties": { "monthsOverstock": { "type": "number" } },
+                    "required": ["monthsOverstock"]
+                }),
+                ui_schema: json!({}),
+            })
+            .unwrap();
+        let report_repo = ReportRowRepository::new(&connection);
+        report_repo
+            .upsert_one(&ReportRow {
+                argument_schema_id: Some("arguments_schema".to_string()),
+                ..report_row(
+                    "sql_report",
+                    ReportDefinitionEntry::SQLQuery(SQLQuery {
+                        name: "query".to_string(),
+                        query_sqlite: "SELECT id FROM item".to_string(),
+                        query_postgres: "SELECT id FROM item".to_string(),
+                    }),
+                )
+            })
+            .unwrap();
+        report_repo
+            .upsert_one(&report_row(
+                "graphql_report",
+                ReportDefinitionEntry::GraphGLQuery(GraphQlQuery {
+                    query: "query { items { totalCount } }".to_string(),
+                    variables: None,
+                }),
+            ))
+            .unwrap();
+
+        let service_provider = ServiceProvider::new(connection_manager, "app_data");
+        let ctx = service_provider
+            .context(mock_store_a().id, mock_user_account_a().id)
+            .unwrap();
+
+        assert_eq!(
+            upsert_report_schedule(&ctx, upsert_input("does_not_exist")),
+            Err(UpsertReportScheduleError::ReportDoesNotExist)
+        );
+        assert_eq!(
+            upsert_report_schedule(&ctx, upsert_input("graphql_report")),
+            Err(UpsertReportScheduleError::ReportQueryNotSupported)
+        );
+        assert!(matches!(
+            is_report_schedulable(&ctx, "graphql_report"),
+            Ok(false)
+        ));
+        assert!(matches!(
+            is_report_schedulable(&ctx, "sql_report"),
+            Ok(true)
+        ));
+        assert!(matches!(
+            upsert_report_schedule(
+                &ctx,
+                UpsertReportSchedule {
+                    arguments: Some(json!({ "monthsOverstock": "six" })),
+                    ..upsert_input("sql_report")
+                }
+            ),
+            Err(UpsertReportScheduleError::InvalidArguments(_))
+        ));
+        assert_eq!(
+            upsert_report_schedule(
+                &ctx,
+                UpsertReportSchedule {
+                    email_recipients: vec!["manager".to_string()],
+                    ..upsert_input("sql_report")
+                }
+            ),
+            Err(UpsertReportScheduleError::InvalidEmailRecipient(
+                "manager".to_string()
+            ))
+        );
+
+        let schedule = upsert_report_schedule(&ctx, upsert_input("sql_report")).unwrap();
+        assert_eq!(schedule.store_id, mock_store_a().id);
+        assert_eq!(schedule.user_id, mock_user_account_a().id);
+        assert_eq!(schedule.retention_days, DEFAULT_RETENTION_DAYS);
+        assert_eq!(
+            schedule.email_recipients,
+            Some("manager@example.com".to_string())
+        );
+        assert!(schedule.is_active);
+        assert_eq!(get_report_schedules(&ctx).unwrap(), vec![schedule]);
+
+        let other_store_ctx = service_provider
+            .context("store_b".to_string(), mock_user_account_a().id)
+            .unwrap();
+        assert_eq!(
+            upsert_report_schedule(&other_store_ctx, upsert_input("sql_report")),
+            Err(UpsertReportScheduleError::ReportScheduleDoesNotBelongToStore)
+        );
+
+        assert_eq!(
+            delete_report_schedule(&ctx, &None, "schedule"),
+            Ok("schedule".to_string())
+        );
+        assert_eq!(get_report_schedules(&ctx).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_next_run_datetime() {
+        let datetime = |month, day, hour| {
+            NaiveDate::from_ymd_opt(2024, month, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap()
+        };
+
+        assert_eq!(
+            next_run_datetime(
+                &ReportScheduleFrequency::Daily,
+                datetime(1, 1, 6),
+                datetime(1, 1, 6)
+            ),
+            datetime(1, 2, 6)
+        );
+        // Missed runs are skipped
+        assert_eq!(
+            next_run_datetime(
+                &ReportScheduleFrequency::Weekly,
+                datetime(1, 1, 6),
+                datetime(1, 20, 0)
+            ),
+            datetime(1, 22, 6)
+        );
+        assert_eq!(
+            next_run_datetime(
+                &ReportScheduleFrequency::Monthly,
+                datetime(1, 31, 6),
+                datetime(2, 1, 0)
+            ),
+            datetime(2, 29, 6)
+        );
+    }
+}
@@ -0,0 +1,468 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{NaiveDateTime, Utc};
+use repository::{
+    query_json, ReportScheduleFormat, ReportScheduleRow, ReportScheduleRowRepository,
+    ReportScheduleRunRow, ReportScheduleRunRowRepository, RepositoryError, StorageConnection,
+};
+use serde_json::{Map, Value};
+use thiserror::Error;
+use util::{format_error, uuid::uuid};
+
+use crate::{
+    log_context::LogContext,
+    mail::{send_mail, MailAttachment, MailError},
+    report::{
+        definition::SQLQuery,
+        report_service::{resolve_report, PrintFormat, ReportError, ResolvedReportQuery},
+    },
+    service_provider::ServiceProvider,
+    settings::Settings,
+    static_files::{StaticFile, StaticFileCategory, StaticFileService},
+};
+
+use super::{delete_report_schedule_run, next_run_datetime};
+
+const REPORT_SCHEDULE_INTERVAL_SECONDS: u64 = 60;
+
+#[derive(Error, Debug)]
+enum RunReportScheduleError {
+    #[error("Failed to print report ({0:?})")]
+    ReportError(ReportError),
+    #[error("Report uses a GraphQL query, only SQL queries are supported for scheduled reports")]
+    ReportQueryNotSupported,
+    #[error("Invalid report arguments")]
+    InvalidArguments(#[from] serde_json::Error),
+    #[error("Failed to query report data")]
+    QueryError(#[from] RepositoryError),
+    #[error("Failed to store printed report")]
+    FileError(#[from] anyhow::Error),
+    #[error("Failed to email report")]
+    MailError(#[from] MailError),
+    #[error("Can't email report, mail is not configured")]
+    MailNotConfigured,
+}
+
+/// Prints due report schedules every minute, stores them as static files and emails them to the
+/// recipients of the schedule
+pub struct ReportScheduleDriver {
+    settings: Settings,
+}
+
+impl ReportScheduleDriver {
+    pub fn new(settings: Settings) -> Self {
+        Self { settings }
+    }
+
+    pub async fn run(self, service_provider: Arc<ServiceProvider>) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(REPORT_SCHEDULE_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+
+            // Printing and emailing reports is blocking, run it outside of the async executor
+            let service_provider = service_provider.clone();
+            let settings = self.settings.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                LogContext::new("report_schedule_processor").sync_scope(|| {
+                    run_report_schedules(&service_provider, &settings, Utc::now().naive_utc())
+                })
+            })
+            .await;
+            match result {
+                Ok(Ok(_)) => {}
+                Ok(Err(error)) => log::error!("Error in report schedule processor ({})", error),
+                Err(error) => log::error!("Report schedule processor panicked ({})", error),
+            }
+        }
+    }
+}
+
+/// Runs due schedules and deletes runs older than the retention period, returns number of runs
+pub(crate) fn run_report_schedules(
+    service_provider: &ServiceProvider,
+    settings: &Settings,
+    now: NaiveDateTime,
+) -> Result<usize, RepositoryError> {
+    let ctx = service_provider.basic_context()?;
+    let schedule_repo = ReportScheduleRowRepository::new(&ctx.connection);
+    let run_repo = ReportScheduleRunRowRepository::new(&ctx.connection);
+
+    let due_schedules = schedule_repo.find_due(now)?;
+    for schedule in &due_schedules {
+        let run = run_report_schedule(service_provider, settings, schedule, now);
+        if let Some(error) = &run.error {
+            log::error!("Report schedule {} failed: {}", schedule.id, error);
+        }
+        run_repo.upsert_one(&run)?;
+        schedule_repo.upsert_one(&ReportScheduleRow {
+            next_run_datetime: next_run_datetime(
+                &schedule.frequency,
+                schedule.next_run_datetime,
+                now,
+            ),
+            ..schedule.clone()
+        })?;
+    }
+
+    for schedule in schedule_repo.find_all()? {
+        let expiry = now - chrono::Duration::days(schedule.retention_days as i64);
+        for run in run_repo.find_many_by_report_schedule_id(&schedule.id)? {
+            if run.run_datetime < expiry {
+                delete_report_schedule_run(&ctx.connection, &settings.server.base_dir, &run)?;
+            }
+        }
+    }
+
+    Ok(due_schedules.len())
+}
+
+fn run_report_schedule(
+    service_provider: &ServiceProvider,
+    settings: &Settings,
+    schedule: &ReportScheduleRow,
+    now: NaiveDateTime,
+) -> ReportScheduleRunRow {
+    let mut run = ReportScheduleRunRow {
+        id: uuid(),
+        report_schedule_id: schedule.id.clone(),
+        run_datetime: now,
+        file_name: None,
+        error: None,
+        emailed_datetime: None,
+    };
+
+    let file = match print_report_schedule(service_provider, settings, schedule) {
+        Ok(file) => file,
+        Err(error) => {
+            run.error = Some(format_error(&error));
+            return run;
+        }
+    };
+    run.id = file.id.clone();
+    run.file_name = Some(file.name.clone());
+
+    let recipients: Vec<String> = schedule
+        .email_recipients
+        .iter()
+        .flat_map(|recipients| recipients.split(','))
+        .map(str::to_string)
+        .collect();
+    if recipients.is_empty() {
+        return run;
+    }
+    match email_report(settings, schedule, &recipients, &file) {
+        Ok(()) => run.emailed_datetime = Some(Utc::now().naive_utc()),
+        Err(error) => run.error = Some(format_error(&error)),
+    }
+
+    run
+}
+
+fn print_report_schedule(
+    service_provider: &ServiceProvider,
+    settings: &Settings,
+    schedule: &ReportScheduleRow,
+) -> Result<StaticFile, RunReportScheduleError> {
+    let ctx = service_provider.context(schedule.store_id.clone(), schedule.user_id.clone())?;
+    let report =
+        resolve_report(&ctx, &schedule.report_id).map_err(RunReportScheduleError::ReportError)?;
+    let arguments: Option<Value> = schedule
+        .arguments
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()?;
+
+    let mut data = Map::new();
+    for query in &report.queries {
+        let ResolvedReportQuery::SQLQuery(query) = query else {
+            return Err(RunReportScheduleError::ReportQueryNotSupported);
+        };
+        let variables = query_variables(&schedule.store_id, &arguments);
+        let rows = service_provider.report_data_cache.query(
+            &ctx.connection,
+            settings.server.report_cache.as_ref(),
+            &schedule.report_id,
+            query,
+            &variables,
+            || query_sql(settings, &ctx.connection, query, &variables),
+        )?;
+        data.insert(query.name.clone(), Value::Array(rows));
+    }
+
+    let format = schedule.format.as_ref().map(|format| match format {
+        ReportScheduleFormat::Pdf => PrintFormat::Pdf,
+        ReportScheduleFormat::Html => PrintFormat::Html,
+        ReportScheduleFormat::Csv => PrintFormat::Csv,
+        ReportScheduleFormat::Xlsx => PrintFormat::Xlsx,
+    });
+    let file_id = service_provider
+        .report_service
+        .print_html_report(
+            &settings.server.base_dir,
+            &settings.server.pdf_renderer,
+            &report,
+            Value::Object(data),
+            arguments,
+            format,
+        )
+        .map_err(RunReportScheduleError::ReportError)?;
+
+    // Printed reports are temporary files, keep them for the retention period of the schedule
+    let file = StaticFileService::new(&settings.server.base_dir)?
+        .move_file_to_category(
+            &file_id,
+            StaticFileCategory::Temporary,
+            &StaticFileCategory::ScheduledReport,
+        )?
+        .ok_or_else(|| anyhow::anyhow!("Printed report not found"))?;
+    Ok(file)
+}
+
+/// Same variables as for SQL queries of reports printed through GraphQL (without dataId)
+fn query_variables(store_id: &str, arguments: &Option<Value>) -> Map<String, Value> {
+    let mut variables = match arguments {
+        Some(Value::Object(arguments)) => arguments.clone(),
+        _ => Map::new(),
+    };
+    variables.insert("storeId".to_string(), Value::String(store_id.to_string()));
+    variables.insert("now".to_string(), Value::String(Utc::now().to_rfc3339()));
+    variables
+}
+
+#[cfg(not(feature = "postgres"))]
+fn query_sql(
+    settings: &Settings,
+    _: &StorageConnection,
+    query: &SQLQuery,
+    variables: &Map<String, Value>,
+) -> Result<Vec<Value>, RepositoryError> {
+    query_json(&settings.database, &query.query_sqlite, variables)
+}
+
+#[cfg(feature = "postgres")]
+fn query_sql(
+    _: &Settings,
+    connection: &StorageConnection,
+    query: &SQLQuery,
+    variables: &Map<String, Value>,
+) -> Result<Vec<Value>, RepositoryError> {
+    query_json(connection, &query.query_postgres, variables)
+}
+
+fn email_report(
+    settings: &Settings,
+    schedule: &ReportScheduleRow,
+    recipients: &[String],
+    file: &StaticFile,
+) -> Result<(), RunReportScheduleError> {
+    let mail_settings = settings
+        .mail
+        .as_ref()
+        .ok_or(RunReportScheduleError::MailNotConfigured)?;
+    let data = std::fs::read(&file.path).map_err(anyhow::Error::from)?;
+    let content_type = match file.name.rsplit('.').next() {
+        Some("pdf") => "application/pdf",
+        Some("html") => "text/html",
+        Some("csv") => "text/csv",
+        Some("xlsx") => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        _ => "application/octet-stream",
+    };
+
+    send_mail(
+        mail_settings,
+        recipients,
+        &schedule.name,
+        &format!("Scheduled report \"{}\" is attached.", schedule.name),
+        Some(MailAttachment {
+            file_name: file.name.clone(),
+            content_type: content_type.to_string(),
+            data,
+        }),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, time::Duration};
+
+    use chrono::NaiveDate;
+    use repository::{
+        mock::{mock_store_a, mock_user_account_a, MockDataInserts},
+        test_db::setup_all,
+        ContextType, ReportRow, ReportRowRepository, ReportScheduleFormat, ReportScheduleFrequency,
+        ReportScheduleRow, ReportScheduleRowRepository, ReportScheduleRunRow,
+        ReportScheduleRunRowRepository, ReportType,
+    };
+    use util::uuid::uuid;
+
+    use crate::{
+        mail::test_smtp::start_test_smtp_server,
+        report::definition::{
+            ReportDefinition, ReportDefinitionEntry, ReportDefinitionIndex, SQLQuery,
+            TabularColumn, TabularColumnType, TabularTemplate,
+        },
+        service_provider::ServiceProvider,
+        static_files::{StaticFileCategory, StaticFileService},
+        test_helpers::test_settings,
+    };
+
+    use super::run_report_schedules;
+
+    #[actix_rt::test]
+    async fn test_run_report_schedules() {
+        let (_, connection, connection_manager, db_settings) =
+            setup_all("test_run_report_schedules", MockDataInserts::all()).await;
+        let base_dir = tempfile::tempdir().unwrap();
+        let (mail_settings, received_mail) = start_test_smtp_server();
+        let mut settings = test_settings(db_settings);
+        settings.server.base_dir = Some(base_dir.path().to_string_lossy().to_string());
+        settings.mail = Some(mail_settings);
+
+        let definition = ReportDefinition {
+            index: ReportDefinitionIndex {
+                template: Some("items.tabular.json".to_string()),
+                header: None,
+                footer: None,
+                query: vec!["items".to_string()],
+            },
+            version: None,
+            entries: HashMap::from([
+                (
+                    "items.tabular.json".to_string(),
+                    ReportDefinitionEntry::TabularTemplate(TabularTemplate {
+                        rows: "items".to_string(),
+                        columns: vec![TabularColumn {
+                            header: "Code".to_string(),
+                            field: "code".to_string(),
+                            r#type: TabularColumnType::String,
+                        }],
+                    }),
+                ),
+                (
+                    "items".to_string(),
+                    ReportDefinitionEntry::SQLQuery(SQLQuery {
+                        name: "items".to_string(),
+                        query_sqlite: "SELECT code FROM item WHERE code = $code".to_string(),
+                        query_postgres: "SELECT code FROM item WHERE code = $code".to_string(),
+                    }),
+                ),
+            ]),
+        };
+        ReportRowRepository::new(&connection)
+            .upsert_one(&ReportRow {
+                id: "item_report".to_string(),
+                name: "Items".to_string(),
+                r#type: ReportType::OmSupply,
+                template: serde_json::to_string(&definition).unwrap(),
+                context: ContextType::Stocktake,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let datetime = |day, hour| {
+            NaiveDate::from_ymd_opt(2024, 1, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap()
+        };
+        let schedule = ReportScheduleRow {
+            id: "schedule".to_string(),
+            store_id: mock_store_a().id,
+            report_id: "item_report".to_string(),
+            user_id: mock_user_account_a().id,
+            name: "Daily items".to_string(),
+            arguments: Some(r#"{"code": "item_a_code"}"#.to_string()),
+            format: Some(ReportScheduleFormat::Csv),
+            frequency: ReportScheduleFrequency::Daily,
+            next_run_datetime: datetime(10, 6),
+            retention_days: 7,
+            email_recipients: Some("manager@example.com".to_string()),
+            is_active: true,
+        };
+        let schedule_repo = ReportScheduleRowRepository::new(&connection);
+        schedule_repo.upsert_one(&schedule).unwrap();
+
+        // Run that is older than the retention period
+        let file_service = StaticFileService::new(&settings.server.base_dir).unwrap();
+        let old_file = file_service
+            .store_file("old.csv", StaticFileCategory::ScheduledReport, b"")
+            .unwrap();
+        let run_repo = ReportScheduleRunRowRepository::new(&connection);
+        run_repo
+            .upsert_one(&ReportScheduleRunRow {
+                id: old_file.id.clone(),
+                report_schedule_id: schedule.id.clone(),
+                run_datetime: datetime(1, 6),
+                file_name: Some(old_file.name.clone()),
+                error: None,
+                emailed_datetime: None,
+            })
+            .unwrap();
+
+        let service_provider = ServiceProvider::new(connection_manager, "app_data");
+
+        // Not due yet
+        assert_eq!(
+            run_report_schedules(&service_provider, &settings, datetime(10, 5)).unwrap(),
+            0
+        );
+
+        assert_eq!(
+            run_report_schedules(&service_provider, &settings, datetime(10, 7)).unwrap(),
+            1
+        );
+
+        let runs = run_repo
+            .find_many_by_report_schedule_id(&schedule.id)
+            .unwrap();
+        assert_eq!(runs.len(), 1, "old run is deleted");
+        let run = &runs[0];
+        assert_eq!(run.error, None);
+        assert!(run.emailed_datetime.is_some());
+        let file = file_service
+            .find_file(&run.id, StaticFileCategory::ScheduledReport)
+            .unwrap()
+            .unwrap();
+        assert!(file.name.ends_with("Items.csv"));
+        assert_eq!(
+            std::fs::read_to_string(&file.path).unwrap(),
+            "Code\r\nitem_a_code\r\n"
+        );
+        assert_eq!(
+            file_service
+                .find_file(&old_file.id, StaticFileCategory::ScheduledReport)
+                .unwrap(),
+            None
+        );
+
+        let mail = received_mail.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(mail.contains("To: manager@example.com"));
+        assert!(mail.contains("Subject: Daily items"));
+        assert!(mail.contains(&file.name));
+
+        assert_eq!(
+            schedule_repo
+                .find_one_by_id(&schedule.id)
+                .unwrap()
+                .unwrap()
+                .next_run_datetime,
+            datetime(11, 6)
+        );
+
+        // Failed run is recorded, schedule continues
+        schedule_repo
+            .upsert_one(&ReportScheduleRow {
+                id: uuid(),
+                report_id: "does_not_exist".to_string(),
+                next_run_datetime: datetime(11, 6),
+                ..schedule.clone()
+            })
+            .unwrap();
+        assert_eq!(
+            run_report_schedules(&service_provider, &settings, datetime(11, 7)).unwrap(),
+            2
+        );
+    }
+}
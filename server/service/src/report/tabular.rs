@@ -0,0 +1,266 @@
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, XlsxError};
+use serde_json::Value;
+
+use super::{
+    definition::{TabularColumn, TabularColumnType, TabularTemplate},
+    report_service::ReportError,
+};
+
+/// Value of `path` (dot separated keys or array indexes, e.g. `invoice.lines.nodes`) in `value`
+pub(super) fn value_at_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|key| !key.is_empty())
+        .try_fold(value, |value, key| match value {
+            Value::Object(object) => object.get(key),
+            Value::Array(array) => array.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+fn rows<'a>(template: &TabularTemplate, data: &'a Value) -> Result<&'a Vec<Value>, ReportError> {
+    match value_at_path(data, &template.rows) {
+        Some(Value::Array(rows)) => Ok(rows),
+        Some(Value::Null) | None => Err(ReportError::DocGenerationError(format!(
+            "Tabular rows not found in report data: {}",
+            template.rows
+        ))),
+        Some(_) => Err(ReportError::DocGenerationError(format!(
+            "Tabular rows are not an array: {}",
+            template.rows
+        ))),
+    }
+}
+
+/// Cell text, as written to csv and html
+fn cell_text(column: &TabularColumn, row: &Value) -> String {
+    match value_at_path(row, &column.field) {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(string)) => string.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Csv with a header line (RFC 4180)
+pub(crate) fn to_csv(template: &TabularTemplate, data: &Value) -> Result<String, ReportError> {
+    let line = |fields: Vec<String>| {
+        fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    let mut lines = vec![line(
+        template.columns.iter().map(|c| c.header.clone()).collect(),
+    )];
+    for row in rows(template, data)? {
+        lines.push(line(
+            template.columns.iter().map(|c| cell_text(c, row)).collect(),
+        ));
+    }
+
+    Ok(lines.join("\r\n") + "\r\n")
+}
+
+/// Html table, used when a tabular report is printed to html or pdf
+pub(crate) fn to_html(template: &TabularTemplate, data: &Value) -> Result<String, ReportError> {
+    let escape = |text: &str| {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+
+    let header: String = template
+        .columns
+        .iter()
+        .map(|c| format!("<th>{}</th>", escape(&c.header)))
+        .collect();
+    let body: String = rows(template, data)?
+        .iter()
+        .map(|row| {
+            let cells: String = template
+                .columns
+                .iter()
+                .map(|c| format!("<td>{}</td>", escape(&cell_text(c, row))))
+                .collect();
+            format!("<tr>{}</tr>", cells)
+        })
+        .collect();
+
+    Ok(format!(
+        "<table><thead><tr>{}</tr></thead><tbody>{}</tbody></table>",
+        header, body
+    ))
+}
+
+/// Xlsx workbook with one worksheet, cells are written with the column type (values that don't
+/// match the type are written as text)
+pub(crate) fn to_xlsx(
+    template: &TabularTemplate,
+    sheet_name: &str,
+    data: &Value,
+) -> Result<Vec<u8>, ReportError> {
+    let rows = rows(template, data)?;
+    write_xlsx(template, sheet_name, rows)
+        .map_err(|err| ReportError::DocGenerationError(format!("Xlsx generation: {}", err)))
+}
+
+fn write_xlsx(
+    template: &TabularTemplate,
+    sheet_name: &str,
+    rows: &[Value],
+) -> Result<Vec<u8>, XlsxError> {
+    let header_format = Format::new().set_bold();
+    let date_format = Format::new().set_num_format("yyyy-mm-dd");
+    let datetime_format = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    // Sheet names are limited to 31 characters and can't contain []:*?/\
+    let sheet_name: String = sheet_name
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\'))
+        .take(31)
+        .collect();
+    if !sheet_name.is_empty() {
+        worksheet.set_name(sheet_name)?;
+    }
+
+    for (col, column) in template.columns.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, &column.header, &header_format)?;
+    }
+
+    for (index, row) in rows.iter().enumerate() {
+        let row_number = index as u32 + 1;
+        for (col, column) in template.columns.iter().enumerate() {
+            let col = col as u16;
+            let value = match value_at_path(row, &column.field) {
+                None | Some(Value::Null) => continue,
+                Some(value) => value,
+            };
+
+            match (&column.r#type, value) {
+                (TabularColumnType::Number, Value::Number(number)) => {
+                    worksheet.write_number(row_number, col, number.as_f64().unwrap_or_default())?;
+                }
+                (TabularColumnType::Boolean, Value::Bool(boolean)) => {
+                    worksheet.write_boolean(row_number, col, *boolean)?;
+                }
+                (TabularColumnType::Date, Value::String(date)) => {
+                    match ExcelDateTime::parse_from_str(date) {
+                        Ok(date) => {
+                            worksheet.write_datetime_with_format(
+                                row_number,
+                                col,
+                                &date,
+                                &date_format,
+                            )?;
+                        }
+                        Err(_) => {
+                            worksheet.write_string(row_number, col, date)?;
+                        }
+                    }
+                }
+                (TabularColumnType::DateTime, Value::String(datetime)) => {
+                    match ExcelDateTime::parse_from_str(datetime) {
+                        Ok(datetime) => {
+                            worksheet.write_datetime_with_format(
+                                row_number,
+                                col,
+                                &datetime,
+                                &datetime_format,
+                            )?;
+                        }
+                        Err(_) => {
+                            worksheet.write_string(row_number, col, datetime)?;
+                        }
+                    }
+                }
+                _ => {
+                    worksheet.write_string(row_number, col, &cell_text(column, row))?;
+                }
+            };
+        }
+    }
+
+    workbook.save_to_buffer()
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn template() -> TabularTemplate {
+        TabularTemplate {
+            rows: "stockLines.nodes".to_string(),
+            columns: vec![
+                TabularColumn {
+                    header: "Item".to_string(),
+                    field: "item.name".to_string(),
+                    r#type: TabularColumnType::String,
+                },
+                TabularColumn {
+                    header: "Packs".to_string(),
+                    field: "totalNumberOfPacks".to_string(),
+                    r#type: TabularColumnType::Number,
+                },
+                TabularColumn {
+                    header: "Expiry".to_string(),
+                    field: "expiryDate".to_string(),
+                    r#type: TabularColumnType::Date,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_tabular_csv() {
+        let data = json!({
+            "stockLines": {
+                "nodes": [
+                    { "item": { "name": "Paracetamol, 500mg" }, "totalNumberOfPacks": 10.5, "expiryDate": "2025-01-31" },
+                    { "item": { "name": "Say \"ah\"" }, "totalNumberOfPacks": 2, "expiryDate": null }
+                ]
+            }
+        });
+
+        assert_eq!(
+            to_csv(&template(), &data).unwrap(),
+            "Item,Packs,Expiry\r\n\
+            \"Paracetamol, 500mg\",10.5,2025-01-31\r\n\
+            \"Say \"\"ah\"\"\",2,\r\n"
+        );
+
+        assert!(matches!(
+            to_csv(&template(), &json!({ "stockLines": {} })),
+            Err(ReportError::DocGenerationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_tabular_xlsx() {
+        let data = json!({
+            "stockLines": {
+                "nodes": [
+                    { "item": { "name": "Paracetamol" }, "totalNumberOfPacks": 10, "expiryDate": "2025-01-31" },
+                    { "item": { "name": "Amoxicillin" }, "totalNumberOfPacks": "n/a", "expiryDate": "unknown" }
+                ]
+            }
+        });
+
+        let xlsx = to_xlsx(&template(), "Stock: [all]", &data).unwrap();
+        // Xlsx is a zip archive
+        assert_eq!(&xlsx[0..2], b"PK");
+    }
+}
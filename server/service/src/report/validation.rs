@@ -0,0 +1,342 @@
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+use repository::{
+    ContextType, FormSchemaRowRepository, ReportRow, ReportRowRepository, RepositoryError,
+    StorageConnection,
+};
+
+use super::definition::{ReportDefinition, ReportDefinitionEntry, ReportRef};
+
+#[derive(Debug, PartialEq)]
+pub enum ReportValidationError {
+    /// The report template can't be parsed as report definition
+    InvalidDefinition(String),
+    /// The definition version is not a semver version
+    InvalidVersion(String),
+    TemplateNotSpecified,
+    QueryNotSpecified,
+    /// A template, header, footer or query of the index is not in the definition entries
+    EntryNotFound(String),
+    /// A Tera template (or a resource that is used as template) doesn't compile
+    TemplateError(String),
+    /// A `ReportRef` entry refers to a report or report entry that doesn't exist
+    ReferenceNotFound {
+        name: String,
+        source: String,
+    },
+    ArgumentSchemaNotFound(String),
+}
+
+impl fmt::Display for ReportValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportValidationError::InvalidDefinition(err) => {
+                write!(f, "Invalid report definition: {}", err)
+            }
+            ReportValidationError::InvalidVersion(err) => write!(f, "Invalid version: {}", err),
+            ReportValidationError::TemplateNotSpecified => write!(f, "Template not specified"),
+            ReportValidationError::QueryNotSpecified => write!(f, "Query not specified"),
+            ReportValidationError::EntryNotFound(name) => write!(f, "Entry not found: {}", name),
+            ReportValidationError::TemplateError(err) => write!(f, "Template error: {}", err),
+            ReportValidationError::ReferenceNotFound { name, source } => write!(
+                f,
+                "Reference {} to report {} can't be resolved",
+                name, source
+            ),
+            ReportValidationError::ArgumentSchemaNotFound(id) => {
+                write!(f, "Argument schema not found: {}", id)
+            }
+        }
+    }
+}
+
+/// Checks that a report can be printed, i.e. that its definition can be parsed, has a semver
+/// version (if set), that the index template and queries are present, that the Tera templates
+/// compile, that `ReportRef` entries resolve and that the argument schema exists.
+/// Returns all problems found, an empty list means the report is valid.
+pub fn validate_report(
+    connection: &StorageConnection,
+    report: &ReportRow,
+) -> Result<Vec<ReportValidationError>, RepositoryError> {
+    let definition = match serde_json::from_str::<ReportDefinition>(&report.template) {
+        Ok(definition) => definition,
+        Err(err) => {
+            return Ok(vec![ReportValidationError::InvalidDefinition(
+                err.to_string(),
+            )])
+        }
+    };
+    let mut errors = Vec::new();
+
+    if let Some(version) = &definition.version {
+        if let Err(err) = semver::Version::parse(version) {
+            errors.push(ReportValidationError::InvalidVersion(format!(
+                "{} ({})",
+                version, err
+            )));
+        }
+    }
+
+    // resource reports only provide entries for other reports
+    if report.context != ContextType::Resource {
+        errors.extend(validate_index(&definition));
+    }
+
+    // templates are compiled together, as they are when the report is generated
+    let repo = ReportRowRepository::new(connection);
+    let mut templates = HashMap::new();
+    for (name, entry) in &definition.entries {
+        let entry = match entry {
+            ReportDefinitionEntry::Ref(reference) => {
+                match resolve_reference(&repo, name, reference)? {
+                    Some(entry) => entry,
+                    None => {
+                        errors.push(ReportValidationError::ReferenceNotFound {
+                            name: name.clone(),
+                            source: reference.source.clone(),
+                        });
+                        // an empty stand-in avoids follow up errors, e.g. when extending it
+                        templates.insert(name.clone(), String::new());
+                        continue;
+                    }
+                }
+            }
+            entry => entry.clone(),
+        };
+        match entry {
+            ReportDefinitionEntry::TeraTemplate(template) => {
+                templates.insert(name.clone(), template.template);
+            }
+            ReportDefinitionEntry::Resource(serde_json::Value::String(resource)) => {
+                templates.insert(name.clone(), resource);
+            }
+            ReportDefinitionEntry::Resource(resource) => {
+                templates.insert(name.clone(), resource.to_string());
+            }
+            _ => {}
+        }
+    }
+    if let Err(err) = tera::Tera::default().add_raw_templates(templates) {
+        errors.push(ReportValidationError::TemplateError(error_chain(&err)));
+    }
+
+    if let Some(schema_id) = &report.argument_schema_id {
+        if FormSchemaRowRepository::new(connection)
+            .find_one_by_id(schema_id)?
+            .is_none()
+        {
+            errors.push(ReportValidationError::ArgumentSchemaNotFound(
+                schema_id.clone(),
+            ));
+        }
+    }
+
+    Ok(errors)
+}
+
+fn validate_index(definition: &ReportDefinition) -> Vec<ReportValidationError> {
+    let mut errors = Vec::new();
+    let index = &definition.index;
+    if index.template.is_none() {
+        errors.push(ReportValidationError::TemplateNotSpecified);
+    }
+    if index.query.is_empty() {
+        errors.push(ReportValidationError::QueryNotSpecified);
+    }
+    let entries = index
+        .template
+        .iter()
+        .chain(index.header.iter())
+        .chain(index.footer.iter())
+        .chain(index.query.iter());
+    for name in entries {
+        if !definition.entries.contains_key(name) {
+            errors.push(ReportValidationError::EntryNotFound(name.clone()));
+        }
+    }
+    errors
+}
+
+fn resolve_reference(
+    repo: &ReportRowRepository,
+    name: &str,
+    reference: &ReportRef,
+) -> Result<Option<ReportDefinitionEntry>, RepositoryError> {
+    let Some(row) = repo.find_one_by_id(&reference.source)? else {
+        return Ok(None);
+    };
+    let Ok(mut definition) = serde_json::from_str::<ReportDefinition>(&row.template) else {
+        return Ok(None);
+    };
+    let source_name = reference.source_name.as_deref().unwrap_or(name);
+    Ok(definition.entries.remove(source_name))
+}
+
+/// Error message including all its sources, e.g. the parse error of a template
+pub(crate) fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message = format!("{}: {}", message, err);
+        source = err.source();
+    }
+    message
+}
+
+/// Compares report definition versions, None when a version is missing (unversioned) or invalid
+/// and the versions can't be compared
+pub fn compare_versions(a: &Option<String>, b: &Option<String>) -> Option<Ordering> {
+    let parse = |version: &Option<String>| {
+        version
+            .as_ref()
+            .and_then(|version| semver::Version::parse(version).ok())
+    };
+    Some(parse(a)?.cmp(&parse(b)?))
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+
+    use repository::{
+        mock::{mock_form_schema_empty, MockDataInserts},
+        test_db::setup_all,
+        ContextType, ReportRow, ReportRowRepository,
+    };
+    use serde_json::json;
+
+    use super::{compare_versions, validate_report, ReportValidationError};
+
+    fn report_row(id: &str, template: serde_json::Value) -> ReportRow {
+        ReportRow {
+            id: id.to_string(),
+            name: id.to_string(),
+            template: template.to_string(),
+            context: ContextType::Stocktake,
+            ..Default::default()
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_validate_report() {
+        let (_, connection, _, _) = setup_all(
+            "test_validate_report",
+            MockDataInserts::none().form_schemas(),
+        )
+        .await;
+
+        ReportRowRepository::new(&connection)
+            .upsert_one(&ReportRow {
+                context: ContextType::Resource,
+                ..report_row(
+                    "base",
+                    json!({
+                        "index": { "query": [] },
+                        "entries": {
+                            "footer.html": {
+                                "type": "TeraTemplate",
+                                "data": { "output": "Html", "template": "Page footer" }
+                            }
+                        }
+                    }),
+                )
+            })
+            .unwrap();
+
+        let valid = ReportRow {
+            argument_schema_id: Some(mock_form_schema_empty().id),
+            ..report_row(
+                "valid",
+                json!({
+                    "index": {
+                        "template": "template.html",
+                        "footer": "footer.html",
+                        "query": "query"
+                    },
+                    "version": "1.2.0",
+                    "entries": {
+                        "template.html": {
+                            "type": "TeraTemplate",
+                            "data": {
+                                "output": "Html",
+                                "template": "{% for line in data.lines %}{{ line.name }}{% endfor %}"
+                            }
+                        },
+                        "footer.html": {
+                            "type": "Ref",
+                            "data": { "source": "base" }
+                        },
+                        "query": { "type": "DefaultQuery", "data": "Invoice" }
+                    }
+                }),
+            )
+        };
+        assert_eq!(validate_report(&connection, &valid), Ok(vec![]));
+
+        let invalid = ReportRow {
+            argument_schema_id: Some("missing_schema".to_string()),
+            ..report_row(
+                "invalid",
+                json!({
+                    "index": {
+                        "template": "template.html",
+                        "header": "header.html",
+                        "footer": "footer.html",
+                        "query": []
+                    },
+                    "version": "1.2",
+                    "entries": {
+                        "template.html": {
+                            "type": "TeraTemplate",
+                            "data": { "output": "Html", "template": "{% if data.name %}" }
+                        },
+                        "footer.html": {
+                            "type": "Ref",
+                            "data": { "source": "missing_report" }
+                        }
+                    }
+                }),
+            )
+        };
+        let errors = validate_report(&connection, &invalid).unwrap();
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                ReportValidationError::InvalidVersion(_),
+                ReportValidationError::QueryNotSpecified,
+                ReportValidationError::EntryNotFound(header),
+                ReportValidationError::ReferenceNotFound { name, source },
+                ReportValidationError::TemplateError(_),
+                ReportValidationError::ArgumentSchemaNotFound(schema),
+            ] if header == "header.html"
+                && name == "footer.html"
+                && source == "missing_report"
+                && schema == "missing_schema"
+        ));
+
+        let errors =
+            validate_report(&connection, &report_row("not_json", json!("template data"))).unwrap();
+        assert!(matches!(
+            errors.as_slice(),
+            [ReportValidationError::InvalidDefinition(_)]
+        ));
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        let version = |version: &str| Some(version.to_string());
+        assert_eq!(
+            compare_versions(&version("1.10.0"), &version("1.9.1")),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            compare_versions(&version("1.0.0-rc1"), &version("1.0.0")),
+            Some(Ordering::Less)
+        );
+        assert_eq!(compare_versions(&None, &version("0.0.1")), None);
+        assert_eq!(
+            compare_versions(&version("0.0.1"), &version("invalid")),
+            None
+        );
+    }
+}
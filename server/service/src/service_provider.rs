@@ -1,6 +1,7 @@
 use crate::{
     app_data::{AppDataService, AppDataServiceTrait},
     asset::AssetServiceTrait,
+    audit_log::AuditLogCapture,
     auth::{AuthService, AuthServiceTrait},
     barcode::{BarcodeService, BarcodeServiceTrait},
     catalogue::{AssetCatalogueServiceTrait, CatalogueService},
@@ -26,6 +27,7 @@ use crate::{
     location::{LocationService, LocationServiceTrait},
     log_service::{LogService, LogServiceTrait},
     master_list::{MasterListService, MasterListServiceTrait},
+    metrics::Metrics,
     missing_program::create_missing_master_list_and_program,
     name::get_names,
     pack_variant::PackVariantServiceTrait,
@@ -39,7 +41,10 @@ use crate::{
         program_event::{ProgramEventService, ProgramEventServiceTrait},
     },
     repack::{RepackService, RepackServiceTrait},
-    report::report_service::{ReportService, ReportServiceTrait},
+    report::{
+        cache::ReportDataCache,
+        report_service::{ReportService, ReportServiceTrait},
+    },
     requisition::{RequisitionService, RequisitionServiceTrait},
     requisition_line::{RequisitionLineService, RequisitionLineServiceTrait},
     sensor::{SensorService, SensorServiceTrait},
@@ -49,6 +54,8 @@ use crate::{
     stocktake_line::{StocktakeLineService, StocktakeLineServiceTrait},
     store::{get_store, get_stores},
     sync::{
+        adaptive_sync::AdaptiveSync,
+        peer_sync::PeerSyncNonces,
         site_info::{SiteInfoService, SiteInfoTrait},
         sync_status::status::{SyncStatusService, SyncStatusTrait},
         synchroniser_driver::{SiteIsInitialisedTrigger, SyncTrigger},
@@ -112,8 +119,10 @@ pub struct ServiceProvider {
     // Sync
     pub site_info_service: Box<dyn SiteInfoTrait>,
     pub sync_status_service: Box<dyn SyncStatusTrait>,
+    pub adaptive_sync: AdaptiveSync,
+    pub peer_sync_nonces: PeerSyncNonces,
     // Triggers
-    processors_trigger: ProcessorsTrigger,
+    pub(crate) processors_trigger: ProcessorsTrigger,
     pub sync_trigger: SyncTrigger,
     pub site_is_initialised_trigger: SiteIsInitialisedTrigger,
     pub display_settings_service: Box<dyn DisplaySettingsServiceTrait>,
@@ -132,6 +141,10 @@ pub struct ServiceProvider {
     pub asset_service: Box<dyn AssetServiceTrait>,
     // Label Printer
     pub label_printer_settings_service: Box<dyn LabelPrinterSettingsServiceTrait>,
+    // Metrics
+    pub metrics: Metrics,
+    // Report data cache
+    pub report_data_cache: ReportDataCache,
 }
 
 pub struct ServiceContext {
@@ -194,6 +207,8 @@ impl ServiceProvider {
             app_data_service: Box::new(AppDataService::new(app_data_folder)),
             site_info_service: Box::new(SiteInfoService),
             sync_status_service: Box::new(SyncStatusService),
+            adaptive_sync: AdaptiveSync::default(),
+            peer_sync_nonces: PeerSyncNonces::default(),
             processors_trigger,
             sync_trigger,
             site_is_initialised_trigger,
@@ -212,13 +227,15 @@ impl ServiceProvider {
             label_printer_settings_service: Box::new(
                 crate::label_printer_settings_service::LabelPrinterSettingsService {},
             ),
+            metrics: Metrics::new(),
+            report_data_cache: ReportDataCache::new(),
         }
     }
 
     /// Creates a new service context with a new DB connection
     pub fn basic_context(&self) -> Result<ServiceContext, RepositoryError> {
         Ok(ServiceContext {
-            connection: self.connection()?,
+            connection: self.audited_connection(None)?,
             processors_trigger: self.processors_trigger.clone(),
             user_id: "".to_string(),
             store_id: "".to_string(),
@@ -231,7 +248,7 @@ impl ServiceProvider {
         user_id: String,
     ) -> Result<ServiceContext, RepositoryError> {
         Ok(ServiceContext {
-            connection: self.connection()?,
+            connection: self.audited_connection(Some(user_id.clone()))?,
             processors_trigger: self.processors_trigger.clone(),
             user_id,
             store_id,
@@ -242,6 +259,18 @@ impl ServiceProvider {
     pub fn connection(&self) -> Result<StorageConnection, RepositoryError> {
         self.connection_manager.connection()
     }
+
+    /// Connection that captures the changes of its transactions for the audit log
+    fn audited_connection(
+        &self,
+        user_id: Option<String>,
+    ) -> Result<StorageConnection, RepositoryError> {
+        let mut connection = self.connection()?;
+        connection.set_changelog_hook(Box::new(AuditLogCapture {
+            user_id: user_id.filter(|user_id| !user_id.is_empty()),
+        }));
+        Ok(connection)
+    }
 }
 
 impl ServiceContext {
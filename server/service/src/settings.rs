@@ -2,7 +2,12 @@ use std::fmt::{Display, Formatter, Result};
 
 use repository::database_settings::DatabaseSettings;
 
-use crate::sync::settings::SyncSettings;
+use crate::{
+    mail::MailSettings,
+    print::escpos::ReceiptPrinterSettings,
+    report::cache::ReportCacheSettings,
+    sync::settings::{PeerSyncSettings, SyncSettings},
+};
 
 #[derive(serde::Deserialize, Clone)]
 pub struct Settings {
@@ -10,6 +15,12 @@ pub struct Settings {
     pub database: DatabaseSettings,
     pub sync: Option<SyncSettings>,
     pub logging: Option<LoggingSettings>,
+    /// LAN sync of transfers between servers of one facility, disabled when not set
+    pub peer_sync: Option<PeerSyncSettings>,
+    /// SMTP server for sending emails (e.g. scheduled reports), emails are not sent when not set
+    pub mail: Option<MailSettings>,
+    /// ESC/POS printer for prescription receipts, receipts can't be printed when not set
+    pub receipt_printer: Option<ReceiptPrinterSettings>,
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -27,6 +38,106 @@ pub struct ServerSettings {
     pub base_dir: Option<String>,
     /// Option to set the machine id of the device for an OS that isn't supported by machine_uid
     pub machine_uid: Option<String>,
+    /// Rate limits and graphql query limits, disabled when not configured
+    #[serde(default)]
+    pub request_limits: RequestLimitSettings,
+    /// How https certificates are obtained, when not set certificates are loaded from
+    /// `base_dir/certs` (a self signed certificate is generated if they don't exist)
+    pub certificates: Option<CertificateSettings>,
+    /// Prometheus `/metrics` endpoint, disabled when not configured
+    pub metrics: Option<MetricsSettings>,
+    /// Renderer used to print pdf reports
+    #[serde(default)]
+    pub pdf_renderer: PdfRenderer,
+    /// Caches the data of SQL report queries when set
+    pub report_cache: Option<ReportCacheSettings>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq, Default)]
+pub enum PdfRenderer {
+    /// Headless Chrome when it's installed and starts, otherwise the native renderer
+    #[default]
+    Auto,
+    /// Headless Chrome, printing fails when Chrome can't be launched
+    Chrome,
+    /// Built-in renderer for the html subset used by report templates (tables, headers and
+    /// footers, page breaks and images), doesn't need a browser
+    Native,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct MetricsSettings {
+    /// Scrapers have to send `Authorization: Bearer <token>`, the endpoint is unauthenticated
+    /// when not set (e.g. when the server is only reachable from a monitoring network)
+    pub bearer_token: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "mode")]
+pub enum CertificateSettings {
+    /// Certificate is ordered and renewed from an ACME server (e.g. Let's Encrypt)
+    Acme(AcmeSettings),
+    /// Certificate is issued and renewed with a CA certificate and key provided by the user,
+    /// the CA certificate can then be installed on devices accessing the server
+    LocalCa(LocalCaSettings),
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct AcmeSettings {
+    /// ACME directory, e.g. https://acme-v02.api.letsencrypt.org/directory
+    pub directory_url: String,
+    /// Domains the certificate is ordered for, validated with the http-01 challenge
+    pub domains: Vec<String>,
+    pub contact_email: Option<String>,
+    /// Port for the http-01 challenge server, must be reachable by the ACME server on port 80
+    /// (can be changed for testing against a local ACME server like Pebble)
+    #[serde(default = "default_acme_challenge_port")]
+    pub challenge_port: u16,
+    /// Additional root certificate (pem) to trust when connecting to the ACME directory,
+    /// e.g. the Pebble test CA
+    pub directory_root_cert_file: Option<String>,
+    #[serde(default = "default_renew_before_days")]
+    pub renew_before_days: u32,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct LocalCaSettings {
+    /// Host names and IP addresses the server certificate is issued for
+    pub subject_alt_names: Vec<String>,
+    #[serde(default = "default_local_ca_validity_days")]
+    pub validity_days: u32,
+    #[serde(default = "default_renew_before_days")]
+    pub renew_before_days: u32,
+}
+
+fn default_acme_challenge_port() -> u16 {
+    80
+}
+
+fn default_renew_before_days() -> u32 {
+    30
+}
+
+fn default_local_ca_validity_days() -> u32 {
+    365
+}
+
+/// Limits are disabled when set to None, which is the default (limits are opt-in)
+#[derive(serde::Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct RequestLimitSettings {
+    /// Requests per minute allowed from one IP address to rate limited endpoints
+    /// (graphql and cold chain api)
+    pub requests_per_minute_per_ip: Option<u32>,
+    /// Requests per minute allowed for one authenticated user to rate limited endpoints
+    pub requests_per_minute_per_user: Option<u32>,
+    /// Maximum nesting depth of a graphql query
+    pub max_graphql_query_depth: Option<usize>,
+    /// Maximum complexity (number of requested fields) of a graphql query
+    pub max_graphql_query_complexity: Option<usize>,
+    /// Maximum request body size in bytes for rate limited endpoints, larger requests get
+    /// a 413 response and requests without Content-Length a 411 response
+    pub max_request_body_bytes: Option<u64>,
 }
 
 impl ServerSettings {
@@ -86,6 +197,18 @@ pub struct LoggingSettings {
     pub max_file_count: Option<i64>,
     /// Max logfile size in MB
     pub max_file_size: Option<usize>,
+    /// Text (default) | Json
+    #[serde(default)]
+    pub format: LogFormat,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One json object per line, including request/run correlation id, user and store
+    /// (see `log_context::LogContext`)
+    Json,
 }
 
 impl LoggingSettings {
@@ -97,6 +220,7 @@ impl LoggingSettings {
             filename: None,
             max_file_count: None,
             max_file_size: None,
+            format: LogFormat::Text,
         }
     }
 
@@ -124,7 +248,7 @@ pub struct DisplaySettingsInput {
     pub custom_theme: Option<String>,
 }
 
-#[derive(serde::Deserialize, Clone, serde::Serialize)]
+#[derive(serde::Deserialize, Clone, serde::Serialize, Debug, PartialEq)]
 pub struct LabelPrinterSettingNode {
     pub address: String,
     pub label_height: i32,
@@ -2,7 +2,10 @@ use repository::{KeyType, KeyValueStoreRepository, RepositoryError};
 use reqwest::Url;
 use thiserror::Error;
 
-use crate::{service_provider::ServiceContext, sync::settings::SyncSettings};
+use crate::{
+    service_provider::ServiceContext,
+    sync::{settings::SyncSettings, store_backfill::parse_store_ids},
+};
 
 #[derive(Debug, Error)]
 pub enum UpdateSettingsError {
@@ -38,6 +41,13 @@ pub trait SettingsServiceTrait: Sync + Send {
         let username = key_value_store.get_string(KeyType::SettingsSyncUsername)?;
         let password_sha256 = key_value_store.get_string(KeyType::SettingsSyncPasswordSha256)?;
         let interval_seconds = key_value_store.get_i64(KeyType::SettingsSyncIntervalSeconds)?;
+        let priority_store_ids = key_value_store
+            .get_string(KeyType::SettingsSyncPriorityStoreIds)?
+            .map(|store_ids| parse_store_ids(&store_ids))
+            .unwrap_or_default();
+        let transactional_sync_v6 = key_value_store
+            .get_bool(KeyType::SettingsSyncTransactionalV6)?
+            .unwrap_or(false);
 
         // `?` inside this closure would result in closure returning `None`
         let make_settings = || {
@@ -47,6 +57,8 @@ pub trait SettingsServiceTrait: Sync + Send {
                 password_sha256: password_sha256?,
                 interval_seconds: interval_seconds? as u64,
                 batch_size: Default::default(),
+                priority_store_ids,
+                transactional_sync_v6,
             })
         };
 
@@ -77,6 +89,14 @@ pub trait SettingsServiceTrait: Sync + Send {
                     KeyType::SettingsSyncIntervalSeconds,
                     Some(settings.interval_seconds as i64),
                 )?;
+                key_value_store.set_string(
+                    KeyType::SettingsSyncPriorityStoreIds,
+                    Some(settings.priority_store_ids.join(",")),
+                )?;
+                key_value_store.set_bool(
+                    KeyType::SettingsSyncTransactionalV6,
+                    Some(settings.transactional_sync_v6),
+                )?;
                 Ok(())
             })
             .map_err(|err| UpdateSettingsError::RepositoryError(err.to_inner_error()))?;
@@ -1,15 +1,19 @@
 use actix_multipart::form::tempfile::TempFile;
 use anyhow::Context;
 use repository::sync_file_reference_row::SyncFileReferenceRow;
-use reqwest::Response;
+use reqwest::{Response, StatusCode};
+use sha2::{Digest, Sha256};
 use std::io::Error;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::{Duration, SystemTime};
-use tokio::fs::File;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
 use util::uuid::uuid;
 use util::{move_file, sanitize_filename};
+
+use crate::sync::api_v6::SYNC_FILE_SHA256_HEADER;
 #[derive(Debug, PartialEq)]
 pub struct StaticFile {
     pub id: String,
@@ -23,6 +27,8 @@ const STATIC_FILE_DIR: &str = "static_files";
 pub enum StaticFileCategory {
     Temporary,
     SyncFile(String, String), // Files to be synced (Table Name, Record Id)
+    PartialSyncFile(String, String), // Sync files still being uploaded or downloaded (Table Name, Record Id)
+    ScheduledReport, // Printed scheduled reports, deleted after the retention period of the schedule
 }
 
 impl StaticFileCategory {
@@ -32,6 +38,12 @@ impl StaticFileCategory {
             StaticFileCategory::SyncFile(table_name, record_id) => {
                 PathBuf::from("sync_files").join(table_name).join(record_id)
             }
+            StaticFileCategory::PartialSyncFile(table_name, record_id) => {
+                PathBuf::from("partial_sync_files")
+                    .join(table_name)
+                    .join(record_id)
+            }
+            StaticFileCategory::ScheduledReport => PathBuf::from("scheduled_reports"),
         }
     }
 }
@@ -48,6 +60,16 @@ impl StaticFile {
 ///
 /// Old files are deleted automatically.
 
+#[derive(thiserror::Error, Debug)]
+pub enum PartialSyncFileError {
+    #[error("File hash mismatch, expected {expected}, got {actual}")]
+    HashMismatch { expected: String, actual: String },
+    #[error("File system error")]
+    FileSystemError(#[from] std::io::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
 #[derive(Debug, Clone)]
 pub struct StaticFileService {
     pub dir: PathBuf,
@@ -174,35 +196,185 @@ impl StaticFileService {
         }))
     }
 
-    pub async fn download_file_in_chunks(
+    /// Moves a file to another category, keeping its id
+    pub fn move_file_to_category(
+        &self,
+        id: &str,
+        from: StaticFileCategory,
+        to: &StaticFileCategory,
+    ) -> anyhow::Result<Option<StaticFile>> {
+        let Some(file) = self.find_file(id, from)? else {
+            return Ok(None);
+        };
+        let moved_file = self.reserve_file(&file.name, to, Some(file.id))?;
+        move_file(Path::new(&file.path), Path::new(&moved_file.path))
+            .context("Problem moving file")?;
+        Ok(Some(moved_file))
+    }
+
+    pub fn delete_file(&self, id: &str, category: StaticFileCategory) -> anyhow::Result<()> {
+        if let Some(file) = self.find_file(id, category)? {
+            std::fs::remove_file(file.path)?;
+        }
+        Ok(())
+    }
+
+    /// Sync files are transferred in chunks into a partial file, which is moved to
+    /// `StaticFileCategory::SyncFile` once complete (see `complete_partial_sync_file`)
+    pub fn partial_sync_file(
         &self,
         sync_file: &SyncFileReferenceRow,
-        mut download_response: Response,
     ) -> anyhow::Result<StaticFile> {
+        self.reserve_file(
+            &sanitize_filename(sync_file.file_name.clone()),
+            &StaticFileCategory::PartialSyncFile(
+                sync_file.table_name.clone(),
+                sync_file.record_id.clone(),
+            ),
+            Some(sync_file.id.clone()),
+        )
+    }
+
+    /// Number of bytes already transferred, 0 if transfer hasn't started
+    pub fn partial_sync_file_length(
+        &self,
+        sync_file: &SyncFileReferenceRow,
+    ) -> anyhow::Result<u64> {
+        let partial_file = self.partial_sync_file(sync_file)?;
+        match std::fs::metadata(&partial_file.path) {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn remove_partial_sync_file(&self, sync_file: &SyncFileReferenceRow) -> anyhow::Result<()> {
+        let partial_file = self.partial_sync_file(sync_file)?;
+        match std::fs::remove_file(&partial_file.path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Appends `chunk` file to partial sync file. Chunk is ignored if `offset` doesn't match length
+    /// of the partial file (e.g. chunk is re-sent after a dropped response), returns length of the
+    /// partial file, which is where the sender should continue from
+    pub fn append_partial_sync_file(
+        &self,
+        sync_file: &SyncFileReferenceRow,
+        offset: u64,
+        total_bytes: u64,
+        chunk: &Path,
+    ) -> anyhow::Result<u64> {
+        let partial_file = self.partial_sync_file(sync_file)?;
+        let mut length = self.partial_sync_file_length(sync_file)?;
+        // Left over from a different version of the file, start again
+        if length > total_bytes {
+            self.remove_partial_sync_file(sync_file)?;
+            length = 0;
+        }
+
+        if offset != length {
+            return Ok(length);
+        }
+
+        let mut file_handle = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&partial_file.path)?;
+        let appended = std::io::copy(&mut std::fs::File::open(chunk)?, &mut file_handle)?;
+
+        Ok(length + appended)
+    }
+
+    /// Checks hash of fully transferred partial sync file and moves it to its sync file location.
+    /// On hash mismatch the partial file is removed, so that the transfer starts from the beginning
+    pub fn complete_partial_sync_file(
+        &self,
+        sync_file: &SyncFileReferenceRow,
+        expected_sha256: Option<&str>,
+    ) -> Result<StaticFile, PartialSyncFileError> {
+        let partial_file = self.partial_sync_file(sync_file)?;
+
+        if let Some(expected) = expected_sha256 {
+            let actual = file_sha256(Path::new(&partial_file.path))?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                self.remove_partial_sync_file(sync_file)?;
+                return Err(PartialSyncFileError::HashMismatch {
+                    expected: expected.to_string(),
+                    actual,
+                });
+            }
+        }
+
         let category =
             StaticFileCategory::SyncFile(sync_file.table_name.clone(), sync_file.record_id.clone());
+        let file = self.reserve_file(&partial_file.name, &category, Some(sync_file.id.clone()))?;
+        move_file(Path::new(&partial_file.path), Path::new(&file.path))
+            .context("Problem moving file")?;
+
+        Ok(file)
+    }
 
-        let file =
-            self.reserve_file(&sync_file.file_name, &category, Some(sync_file.id.clone()))?;
-        let mut file_handle = File::create(&file.path).await?;
+    /// Downloads sync file into partial sync file, appending to it for range (resumed) responses.
+    /// `on_progress` is called with the number of bytes downloaded so far after each chunk
+    pub async fn download_file_in_chunks(
+        &self,
+        sync_file: &SyncFileReferenceRow,
+        mut download_response: Response,
+        mut on_progress: impl FnMut(u64),
+    ) -> Result<StaticFile, PartialSyncFileError> {
+        let partial_file = self.partial_sync_file(sync_file)?;
+        let is_resumed = download_response.status() == StatusCode::PARTIAL_CONTENT;
+        let expected_sha256 = download_response
+            .headers()
+            .get(SYNC_FILE_SHA256_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let mut file_handle = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(is_resumed)
+            .truncate(!is_resumed)
+            .open(&partial_file.path)
+            .await?;
+        let mut downloaded_bytes = file_handle.metadata().await?.len();
 
         loop {
             log::info!("Downloading chunk");
-            let Some(bytes) = download_response.chunk().await? else {
+            let Some(bytes) = download_response
+                .chunk()
+                .await
+                .map_err(anyhow::Error::from)?
+            else {
                 break;
             };
 
             tokio::io::copy(&mut bytes.deref(), &mut file_handle).await?;
+            downloaded_bytes += bytes.len() as u64;
+            on_progress(downloaded_bytes);
         }
-
-        Ok(StaticFile {
-            id: sync_file.id.clone(),
-            name: sync_file.file_name.clone(),
-            path: file.path.to_string(),
-        })
+        file_handle.flush().await?;
+        drop(file_handle);
+
+        if expected_sha256.is_none() {
+            log::warn!(
+                "Central server didn't send hash for file {}, skipping integrity check",
+                sync_file.id
+            );
+        }
+        self.complete_partial_sync_file(sync_file, expected_sha256.as_deref())
     }
 }
 
+/// Hex encoded sha256 of file content
+pub fn file_sha256(path: &Path) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
 /// Returns the file name part of the path like:
 /// `./static_file_path/{uuid}_{file_name};
 fn parse_original_file_name(id: &str, file_path: &Path) -> Option<String> {
@@ -267,9 +439,11 @@ fn delete_temporary_files(file_dir: &PathBuf, max_life_time_millis: u64) -> Resu
 mod test {
     use std::{fs, path::PathBuf, str::FromStr, time::Duration};
 
+    use repository::SyncFileReferenceRow;
+
     use crate::static_files::StaticFileCategory;
 
-    use super::StaticFileService;
+    use super::{file_sha256, PartialSyncFileError, StaticFileService};
 
     const TEST_DIR: &str = "test_static_files";
 
@@ -335,4 +509,63 @@ mod test {
         // Clean up
         fs::remove_dir_all(&test_dir).unwrap();
     }
+
+    #[test]
+    fn test_partial_sync_file() {
+        let test_dir = std::env::temp_dir().join("test_partial_sync_file");
+        let _ = fs::remove_dir_all(&test_dir);
+        let mut service = StaticFileService::new(&None).unwrap();
+        service.dir = test_dir.clone();
+        fs::create_dir_all(&test_dir).unwrap();
+
+        let sync_file = SyncFileReferenceRow {
+            id: "sync_file_id".to_string(),
+            table_name: "asset".to_string(),
+            record_id: "asset_id".to_string(),
+            file_name: "photo.jpg".to_string(),
+            ..Default::default()
+        };
+        let data = "first chunk,second chunk".as_bytes();
+        let chunk_file = test_dir.join("chunk");
+        let append = |offset: u64, chunk: &[u8]| {
+            fs::write(&chunk_file, chunk).unwrap();
+            service
+                .append_partial_sync_file(&sync_file, offset, data.len() as u64, &chunk_file)
+                .unwrap()
+        };
+
+        assert_eq!(service.partial_sync_file_length(&sync_file).unwrap(), 0);
+        assert_eq!(append(0, &data[..12]), 12);
+        // Re-sent chunk is ignored
+        assert_eq!(append(0, &data[..12]), 12);
+        // Sender is ahead of partial file, continues from partial file length
+        assert_eq!(append(20, &data[20..]), 12);
+        assert_eq!(append(12, &data[12..]), data.len() as u64);
+
+        // Wrong hash removes partial file
+        let error = service
+            .complete_partial_sync_file(&sync_file, Some("wrong"))
+            .unwrap_err();
+        assert!(matches!(error, PartialSyncFileError::HashMismatch { .. }));
+        assert_eq!(service.partial_sync_file_length(&sync_file).unwrap(), 0);
+
+        assert_eq!(append(0, data), data.len() as u64);
+        let expected_sha256 = file_sha256(&chunk_file).unwrap();
+        let file = service
+            .complete_partial_sync_file(&sync_file, Some(&expected_sha256))
+            .unwrap();
+        assert_eq!(fs::read(&file.path).unwrap(), data);
+        assert_eq!(
+            service
+                .find_file(
+                    &sync_file.id,
+                    StaticFileCategory::SyncFile("asset".to_string(), "asset_id".to_string())
+                )
+                .unwrap(),
+            Some(file)
+        );
+        assert_eq!(service.partial_sync_file_length(&sync_file).unwrap(), 0);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
 }
@@ -0,0 +1,222 @@
+use std::sync::Mutex;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use log::{info, warn};
+use rand::Rng;
+
+use super::settings::BatchSize;
+
+/// Batch sizes are not reduced below this
+const MIN_BATCH_SIZE: u32 = 10;
+/// Upper limit of retry interval after consecutive failed syncs (unless sync interval is longer)
+const MAX_RETRY_SECONDS: u64 = 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SyncBatch {
+    RemotePull,
+    RemotePush,
+    CentralPull,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveSyncStatus {
+    /// Batch sizes used by the next sync, at most the configured batch sizes
+    pub batch_size: BatchSize,
+    pub consecutive_failures: u32,
+    /// Next automatic sync after failed sync, None if last sync succeeded
+    pub retry_datetime: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Default)]
+struct AdaptiveSyncState {
+    /// None when configured batch sizes are used
+    batch_size: Option<BatchSize>,
+    consecutive_failures: u32,
+    retry_datetime: Option<NaiveDateTime>,
+}
+
+/// Reduces sync batch sizes after timeouts or oversized requests and grows them back after
+/// successful syncs, and backs off exponentially (with jitter) between failed syncs.
+/// State is kept in memory, configured batch sizes are used again after restart
+#[derive(Debug, Default)]
+pub struct AdaptiveSync {
+    state: Mutex<AdaptiveSyncState>,
+}
+
+impl AdaptiveSync {
+    pub fn status(&self, configured: &BatchSize) -> AdaptiveSyncStatus {
+        let state = self.state.lock().unwrap();
+        AdaptiveSyncStatus {
+            batch_size: effective_batch_size(&state, configured),
+            consecutive_failures: state.consecutive_failures,
+            retry_datetime: state.retry_datetime,
+        }
+    }
+
+    pub(crate) fn batch_size(&self, configured: &BatchSize) -> BatchSize {
+        effective_batch_size(&self.state.lock().unwrap(), configured)
+    }
+
+    /// Time to wait before next automatic sync, None if last sync succeeded
+    pub(crate) fn retry_in(&self) -> Option<std::time::Duration> {
+        let retry_datetime = self.state.lock().unwrap().retry_datetime?;
+        let retry_in = retry_datetime - Utc::now().naive_utc();
+        Some(retry_in.to_std().unwrap_or_default())
+    }
+
+    /// Grows reduced batch sizes back towards configured batch sizes
+    pub(crate) fn sync_succeeded(&self, configured: &BatchSize) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        state.retry_datetime = None;
+
+        let Some(current) = state.batch_size.take() else {
+            return;
+        };
+        let grown = BatchSize {
+            remote_pull: grow(current.remote_pull, configured.remote_pull),
+            remote_push: grow(current.remote_push, configured.remote_push),
+            central_pull: grow(current.central_pull, configured.central_pull),
+        };
+        info!("Increasing sync batch sizes to {:?}", grown);
+        if &grown != configured {
+            state.batch_size = Some(grown);
+        }
+    }
+
+    /// Halves the batch size of `oversized_batch` and schedules next automatic sync
+    pub(crate) fn sync_failed(
+        &self,
+        configured: &BatchSize,
+        oversized_batch: Option<SyncBatch>,
+        interval_seconds: u64,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+
+        if let Some(batch) = oversized_batch {
+            let mut batch_size = effective_batch_size(&state, configured);
+            let size = match batch {
+                SyncBatch::RemotePull => &mut batch_size.remote_pull,
+                SyncBatch::RemotePush => &mut batch_size.remote_push,
+                SyncBatch::CentralPull => &mut batch_size.central_pull,
+            };
+            *size = (*size / 2).max(MIN_BATCH_SIZE);
+            warn!("Reducing {:?} sync batch size to {}", batch, size);
+            state.batch_size = Some(batch_size);
+        }
+
+        let retry_seconds = retry_seconds(
+            interval_seconds,
+            state.consecutive_failures,
+            rand::thread_rng().gen(),
+        );
+        info!(
+            "Sync failed {} times in a row, retrying in {} seconds",
+            state.consecutive_failures, retry_seconds
+        );
+        state.retry_datetime =
+            Some(Utc::now().naive_utc() + Duration::seconds(retry_seconds as i64));
+    }
+}
+
+/// Configured batch sizes may have changed since batch sizes were reduced
+fn effective_batch_size(state: &AdaptiveSyncState, configured: &BatchSize) -> BatchSize {
+    match &state.batch_size {
+        None => configured.clone(),
+        Some(current) => BatchSize {
+            remote_pull: current.remote_pull.min(configured.remote_pull),
+            remote_push: current.remote_push.min(configured.remote_push),
+            central_pull: current.central_pull.min(configured.central_pull),
+        },
+    }
+}
+
+fn grow(current: u32, configured: u32) -> u32 {
+    current.saturating_mul(2).min(configured)
+}
+
+/// Sync interval doubled for every consecutive failure, with equal jitter (`jitter` between 0 and 1)
+/// so that sites that failed at the same time (e.g. central server outage) don't retry together
+fn retry_seconds(interval_seconds: u64, consecutive_failures: u32, jitter: f64) -> u64 {
+    let max_seconds = MAX_RETRY_SECONDS.max(interval_seconds);
+    let exponent = consecutive_failures.saturating_sub(1).min(16);
+    let backoff = interval_seconds
+        .max(1)
+        .saturating_mul(1 << exponent)
+        .min(max_seconds);
+    let half = backoff / 2;
+
+    backoff - half + (half as f64 * jitter) as u64
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sync::settings::BatchSize;
+
+    use super::{retry_seconds, AdaptiveSync, SyncBatch, MIN_BATCH_SIZE};
+
+    #[test]
+    fn adaptive_sync_batch_size() {
+        let configured = BatchSize::default();
+        let adaptive_sync = AdaptiveSync::default();
+        assert_eq!(adaptive_sync.batch_size(&configured), configured);
+
+        adaptive_sync.sync_failed(&configured, Some(SyncBatch::CentralPull), 60);
+        adaptive_sync.sync_failed(&configured, Some(SyncBatch::CentralPull), 60);
+        adaptive_sync.sync_failed(&configured, None, 60);
+        let status = adaptive_sync.status(&configured);
+        assert_eq!(
+            status.batch_size,
+            BatchSize {
+                central_pull: 125,
+                ..configured.clone()
+            }
+        );
+        assert_eq!(status.consecutive_failures, 3);
+        assert!(status.retry_datetime.is_some());
+        assert!(adaptive_sync.retry_in().is_some());
+
+        // Lower configured batch size takes precedence
+        let lower = BatchSize {
+            central_pull: 100,
+            remote_push: 100,
+            ..configured.clone()
+        };
+        assert_eq!(adaptive_sync.batch_size(&lower), lower);
+
+        // Grows back after success
+        adaptive_sync.sync_succeeded(&configured);
+        let status = adaptive_sync.status(&configured);
+        assert_eq!(status.batch_size.central_pull, 250);
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(status.retry_datetime, None);
+        assert_eq!(adaptive_sync.retry_in(), None);
+
+        adaptive_sync.sync_succeeded(&configured);
+        adaptive_sync.sync_succeeded(&configured);
+        assert_eq!(adaptive_sync.batch_size(&configured), configured);
+
+        // Minimum batch size
+        for _ in 0..20 {
+            adaptive_sync.sync_failed(&configured, Some(SyncBatch::RemotePush), 60);
+        }
+        assert_eq!(
+            adaptive_sync.batch_size(&configured).remote_push,
+            MIN_BATCH_SIZE
+        );
+    }
+
+    #[test]
+    fn adaptive_sync_retry_seconds() {
+        assert_eq!(retry_seconds(60, 1, 0.0), 30);
+        assert_eq!(retry_seconds(60, 1, 1.0), 60);
+        assert_eq!(retry_seconds(60, 3, 0.0), 120);
+        assert_eq!(retry_seconds(60, 3, 1.0), 240);
+        // Capped
+        assert_eq!(retry_seconds(60, 100, 1.0), 60 * 60);
+        assert_eq!(retry_seconds(60, 100, 0.0), 30 * 60);
+        // Sync interval longer than cap
+        assert_eq!(retry_seconds(2 * 60 * 60, 5, 1.0), 2 * 60 * 60);
+    }
+}
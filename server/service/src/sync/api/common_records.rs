@@ -98,8 +98,21 @@ impl CommonSyncRecord {
             integration_datetime: None,
             integration_error: None,
             source_site_id,
+            ignored_datetime: None,
+            store_id: None,
         })
     }
+
+    /// Store of remote record (`store_ID` in legacy records, `store_id` in omSupply records), lines
+    /// get the store of their parent before integration (when records are integrated store by store)
+    pub(crate) fn store_id(&self) -> Option<String> {
+        self.record_data
+            .get("store_ID")
+            .or_else(|| self.record_data.get("store_id"))?
+            .as_str()
+            .filter(|store_id| !store_id.is_empty())
+            .map(str::to_string)
+    }
 }
 
 impl RemoteSyncBatchV5 {
@@ -110,7 +123,13 @@ impl RemoteSyncBatchV5 {
     pub(crate) fn to_sync_buffer_rows(self) -> Result<Vec<SyncBufferRow>, ParsingSyncRecordError> {
         self.data
             .into_iter()
-            .map(|r| Ok(r.record.to_buffer_row(None)?))
+            .map(|r| {
+                let store_id = r.record.store_id();
+                Ok(SyncBufferRow {
+                    store_id,
+                    ..r.record.to_buffer_row(None)?
+                })
+            })
             .collect()
     }
 }
@@ -152,6 +171,36 @@ mod tests {
         assert_eq!(row.data, "{}");
     }
 
+    #[test]
+    fn test_remote_sync_batch_v5_store_id() {
+        let record = |table_name: &str, record_data: serde_json::Value| RemoteSyncRecordV5 {
+            sync_id: table_name.to_string(),
+            record: CommonSyncRecord {
+                table_name: table_name.to_string(),
+                record_id: table_name.to_string(),
+                action: SyncAction::Insert,
+                record_data,
+            },
+        };
+
+        let rows = RemoteSyncBatchV5 {
+            queue_length: 0,
+            data: vec![
+                record("transact", json!({"ID": "transact", "store_ID": "store_a"})),
+                record(
+                    "trans_line",
+                    json!({"ID": "trans_line", "transaction_ID": "transact"}),
+                ),
+                record("item_line", json!({"ID": "item_line", "store_ID": ""})),
+            ],
+        }
+        .to_sync_buffer_rows()
+        .unwrap();
+
+        let store_ids: Vec<Option<String>> = rows.into_iter().map(|row| row.store_id).collect();
+        assert_eq!(store_ids, vec![Some("store_a".to_string()), None, None]);
+    }
+
     #[actix_rt::test]
     async fn test_remote_sync_batch_v5_to_sync_buffer_rows() {
         let batch = RemoteSyncBatchV5 {
@@ -0,0 +1,211 @@
+use std::{
+    io::{self, Read, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use reqwest::{header::CONTENT_ENCODING, RequestBuilder, Response};
+
+use crate::usize_to_u64;
+
+use super::ParsingResponseError;
+
+/// Encodings accepted for sync request and response bodies, in order of preference.
+/// Sent as `accept-encoding` request header by sync api clients, and as `accept-encoding`
+/// response header by central server to advertise encodings it accepts for request bodies
+pub const SYNC_ACCEPT_ENCODING: &str = "zstd, gzip";
+
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncEncoding {
+    Zstd,
+    Gzip,
+}
+
+impl SyncEncoding {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "zstd" => Some(SyncEncoding::Zstd),
+            "gzip" | "x-gzip" => Some(SyncEncoding::Gzip),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SyncEncoding::Zstd => "zstd",
+            SyncEncoding::Gzip => "gzip",
+        }
+    }
+
+    /// Preferred encoding out of encodings listed in `accept-encoding` header value,
+    /// encodings with `q=0` are not acceptable
+    pub fn from_accept_encoding(value: &str) -> Option<Self> {
+        let accepted: Vec<SyncEncoding> = value
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let encoding = SyncEncoding::from_name(parts.next()?)?;
+                let is_rejected = parts.any(|parameter| {
+                    parameter
+                        .trim()
+                        .strip_prefix("q=")
+                        .and_then(|quality| quality.parse::<f32>().ok())
+                        == Some(0.0)
+                });
+                (!is_rejected).then_some(encoding)
+            })
+            .collect();
+
+        [SyncEncoding::Zstd, SyncEncoding::Gzip]
+            .into_iter()
+            .find(|encoding| accepted.contains(encoding))
+    }
+
+    pub fn encode(&self, body: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            SyncEncoding::Zstd => zstd::encode_all(body, ZSTD_LEVEL),
+            SyncEncoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+        }
+    }
+
+    pub fn decode(&self, body: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            SyncEncoding::Zstd => zstd::decode_all(body),
+            SyncEncoding::Gzip => {
+                let mut decoded = Vec::new();
+                GzDecoder::new(body).read_to_end(&mut decoded)?;
+                Ok(decoded)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SyncTrafficCount {
+    /// Request and response body bytes sent over the network
+    pub transferred_bytes: u64,
+    /// Request and response body bytes before compression
+    pub uncompressed_bytes: u64,
+}
+
+/// Counts request and response body bytes of sync api calls, clones share the count so that
+/// traffic of all sync api instances used in a sync step can be collected
+#[derive(Debug, Clone, Default)]
+pub struct SyncTraffic {
+    transferred_bytes: Arc<AtomicU64>,
+    uncompressed_bytes: Arc<AtomicU64>,
+}
+
+impl SyncTraffic {
+    fn add(&self, transferred_bytes: usize, uncompressed_bytes: usize) {
+        self.transferred_bytes
+            .fetch_add(usize_to_u64(transferred_bytes), Ordering::Relaxed);
+        self.uncompressed_bytes
+            .fetch_add(usize_to_u64(uncompressed_bytes), Ordering::Relaxed);
+    }
+
+    /// Returns traffic since the last call
+    pub(crate) fn take(&self) -> SyncTrafficCount {
+        SyncTrafficCount {
+            transferred_bytes: self.transferred_bytes.swap(0, Ordering::Relaxed),
+            uncompressed_bytes: self.uncompressed_bytes.swap(0, Ordering::Relaxed),
+        }
+    }
+
+    /// Sets request body, compressed with `encoding` if provided
+    pub(crate) fn request_body(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+        encoding: Option<SyncEncoding>,
+    ) -> io::Result<RequestBuilder> {
+        let uncompressed_bytes = body.len();
+        let (request, body) = match encoding {
+            Some(encoding) => (
+                request.header(CONTENT_ENCODING, encoding.name()),
+                encoding.encode(&body)?,
+            ),
+            None => (request, body),
+        };
+
+        self.add(body.len(), uncompressed_bytes);
+        Ok(request.body(body))
+    }
+
+    /// Reads response body, decompressing it according to `content-encoding` header
+    pub(crate) async fn response_body(
+        &self,
+        response: Response,
+    ) -> Result<Vec<u8>, ParsingResponseError> {
+        let content_encoding = response
+            .headers()
+            .get(CONTENT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default();
+        let body = response.bytes().await?;
+
+        let decoded = match content_encoding.as_str() {
+            "" | "identity" => body.to_vec(),
+            name => SyncEncoding::from_name(name)
+                .ok_or_else(|| ParsingResponseError::UnsupportedContentEncoding(name.to_string()))?
+                .decode(&body)
+                .map_err(ParsingResponseError::CannotDecompressResponse)?,
+        };
+
+        self.add(body.len(), decoded.len());
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SyncEncoding, SyncTraffic, SyncTrafficCount};
+
+    #[test]
+    fn sync_encoding() {
+        assert_eq!(
+            SyncEncoding::from_accept_encoding("gzip, deflate, br, zstd"),
+            Some(SyncEncoding::Zstd)
+        );
+        assert_eq!(
+            SyncEncoding::from_accept_encoding("gzip;q=0.5, zstd;q=0"),
+            Some(SyncEncoding::Gzip)
+        );
+        assert_eq!(SyncEncoding::from_accept_encoding("br, deflate"), None);
+        assert_eq!(SyncEncoding::from_accept_encoding(""), None);
+
+        let body = r#"{"data":"records"}"#.repeat(100);
+        for encoding in [SyncEncoding::Zstd, SyncEncoding::Gzip] {
+            let encoded = encoding.encode(body.as_bytes()).unwrap();
+            assert!(encoded.len() < body.len());
+            assert_eq!(encoding.decode(&encoded).unwrap(), body.as_bytes());
+        }
+    }
+
+    #[test]
+    fn sync_traffic() {
+        let traffic = SyncTraffic::default();
+        let shared = traffic.clone();
+
+        traffic.add(10, 100);
+        shared.add(5, 50);
+        assert_eq!(
+            traffic.take(),
+            SyncTrafficCount {
+                transferred_bytes: 15,
+                uncompressed_bytes: 150
+            }
+        );
+        assert_eq!(shared.take(), SyncTrafficCount::default());
+    }
+}
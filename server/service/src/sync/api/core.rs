@@ -1,8 +1,16 @@
-use std::{collections::HashMap, convert::TryInto};
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use crate::{service_provider::ServiceProvider, sync::settings::SyncSettings};
 use repository::migrations::Version;
-use reqwest::{header::HeaderMap, Client, Response, Url};
+use reqwest::{
+    header::{HeaderMap, ACCEPT_ENCODING},
+    Client, RequestBuilder, StatusCode, Url,
+};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
 use thiserror::Error;
@@ -16,6 +24,11 @@ const APP_NAME: &str = "Open mSupply Android";
 #[cfg(not(target_os = "android"))]
 const APP_NAME: &str = "Open mSupply Desktop";
 
+/// Batch pull and push requests taking longer than this are treated as connection errors, the
+/// batch size is reduced for the next sync (see `AdaptiveSync`). Other requests (e.g. initialise,
+/// which can take a long time on central server) have no timeout
+pub(crate) const SYNC_BATCH_REQUEST_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SyncApiSettings {
@@ -32,6 +45,16 @@ pub struct SyncApiSettings {
 pub struct SyncApiV5 {
     pub url: Url,
     pub settings: SyncApiSettings,
+    pub(crate) traffic: SyncTraffic,
+    // Request bodies are only compressed once server advertised accepted encodings in
+    // `accept-encoding` response header (legacy mSupply server may not support it)
+    request_encoding: Arc<Mutex<Option<SyncEncoding>>>,
+}
+
+/// Successful or error response, with decompressed body
+pub(crate) struct SyncApiResponse {
+    pub(crate) status: StatusCode,
+    pub(crate) body: Vec<u8>,
 }
 
 fn tuple_vec_to_header(tuple_vec: Vec<(&str, &str)>) -> HeaderMap {
@@ -86,9 +109,19 @@ impl SyncApiV5 {
                 SyncApiV5CreatingError::CannotParseSyncUrl(settings.server_url.clone(), error)
             })?,
             settings,
+            traffic: SyncTraffic::default(),
+            request_encoding: Arc::new(Mutex::new(None)),
         })
     }
 
+    /// Traffic of this instance (and its clones) is counted in `traffic`
+    pub(crate) fn with_traffic(self, traffic: &SyncTraffic) -> Self {
+        Self {
+            traffic: traffic.clone(),
+            ..self
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn new_test(url: &str, site_name: &str, password: &str, hardware_id: &str) -> Self {
         use crate::sync::settings::SYNC_VERSION;
@@ -105,10 +138,41 @@ impl SyncApiV5 {
                 app_version: Version::from_package_json().to_string(),
                 app_name: APP_NAME.to_string(),
             },
+            traffic: SyncTraffic::default(),
+            request_encoding: Arc::new(Mutex::new(None)),
         }
     }
 
-    pub(crate) async fn do_get<T>(&self, route: &str, query: &T) -> Result<Response, SyncApiError>
+    pub(crate) async fn do_get<T>(
+        &self,
+        route: &str,
+        query: &T,
+    ) -> Result<SyncApiResponse, SyncApiError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.get(route, query, None).await
+    }
+
+    /// Get of a pull batch, with `SYNC_BATCH_REQUEST_TIMEOUT`
+    pub(crate) async fn do_get_batch<T>(
+        &self,
+        route: &str,
+        query: &T,
+    ) -> Result<SyncApiResponse, SyncApiError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.get(route, query, Some(SYNC_BATCH_REQUEST_TIMEOUT))
+            .await
+    }
+
+    async fn get<T>(
+        &self,
+        route: &str,
+        query: &T,
+        timeout: Option<Duration>,
+    ) -> Result<SyncApiResponse, SyncApiError>
     where
         T: Serialize + ?Sized,
     {
@@ -127,7 +191,7 @@ impl SyncApiV5 {
             .join(route)
             .map_err(|error| self.api_error(route, error.into()))?;
 
-        let result = Client::new()
+        let request = Client::new()
             .get(url.clone())
             .headers(tuple_vec_to_header(vec![
                 ("msupply-site-uuid", site_uuid),
@@ -136,16 +200,47 @@ impl SyncApiV5 {
                 ("version", sync_version),
             ]))
             .basic_auth(username, Some(password_sha256))
-            .query(query)
-            .send()
-            .await;
+            .query(query);
+        let request = match timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        };
 
-        response_or_err(result)
+        self.send(request, None)
             .await
             .map_err(|error| self.api_error(route, error))
     }
 
-    pub(crate) async fn do_post<T>(&self, route: &str, body: &T) -> Result<Response, SyncApiError>
+    pub(crate) async fn do_post<T>(
+        &self,
+        route: &str,
+        body: &T,
+    ) -> Result<SyncApiResponse, SyncApiError>
+    where
+        T: Serialize,
+    {
+        self.post(route, body, None).await
+    }
+
+    /// Post of a push batch, with `SYNC_BATCH_REQUEST_TIMEOUT`
+    pub(crate) async fn do_post_batch<T>(
+        &self,
+        route: &str,
+        body: &T,
+    ) -> Result<SyncApiResponse, SyncApiError>
+    where
+        T: Serialize,
+    {
+        self.post(route, body, Some(SYNC_BATCH_REQUEST_TIMEOUT))
+            .await
+    }
+
+    async fn post<T>(
+        &self,
+        route: &str,
+        body: &T,
+        timeout: Option<Duration>,
+    ) -> Result<SyncApiResponse, SyncApiError>
     where
         T: Serialize,
     {
@@ -164,7 +259,7 @@ impl SyncApiV5 {
             .join(route)
             .map_err(|error| self.api_error(route, error.into()))?;
 
-        let result = Client::new()
+        let request = Client::new()
             .post(url.clone())
             .headers(tuple_vec_to_header(vec![
                 ("msupply-site-uuid", site_uuid),
@@ -172,27 +267,80 @@ impl SyncApiV5 {
                 ("app-name", app_name),
                 ("version", sync_version),
             ]))
-            .basic_auth(username, Some(password_sha256))
-            // Re unwrap, from to_string documentation:
-            // Serialization can fail if T's implementation of Serialize decides to fail, or if T contains a map with non-string keys.
-            .body(serde_json::to_string(&body).unwrap())
-            .send()
-            .await;
-
-        response_or_err(result)
+            .basic_auth(username, Some(password_sha256));
+        let request = match timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        };
+        // Re unwrap, from to_vec documentation:
+        // Serialization can fail if T's implementation of Serialize decides to fail, or if T contains a map with non-string keys.
+        let body = serde_json::to_vec(&body).unwrap();
+
+        self.send(request, Some(body))
             .await
             .map_err(|error| self.api_error(route, error))
     }
 
-    pub(crate) async fn do_empty_post(&self, route: &str) -> Result<Response, SyncApiError> {
+    pub(crate) async fn do_empty_post(&self, route: &str) -> Result<SyncApiResponse, SyncApiError> {
         self.do_post(route, &json!({})).await
     }
+
+    async fn send(
+        &self,
+        request: RequestBuilder,
+        body: Option<Vec<u8>>,
+    ) -> Result<SyncApiResponse, SyncApiErrorVariantV5> {
+        let mut request = request.header(ACCEPT_ENCODING, SYNC_ACCEPT_ENCODING);
+        if let Some(body) = body {
+            let encoding = *self.request_encoding.lock().unwrap();
+            request = self
+                .traffic
+                .request_body(request, body, encoding)
+                .map_err(|error| SyncApiErrorVariantV5::Other(error.into()))?;
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(error) => {
+                if error.is_connect() || error.is_timeout() {
+                    return Err(SyncApiErrorVariantV5::ConnectionError(error));
+                } else {
+                    return Err(SyncApiErrorVariantV5::Other(error.into()));
+                }
+            }
+        };
+
+        if let Some(accept_encoding) = response
+            .headers()
+            .get(ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+        {
+            *self.request_encoding.lock().unwrap() =
+                SyncEncoding::from_accept_encoding(accept_encoding);
+        }
+
+        let status = response.status();
+        let response = SyncApiResponse {
+            status,
+            body: self.traffic.response_body(response).await?,
+        };
+
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        Err(SyncApiErrorVariantV5::from_response(response))
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum ParsingResponseError {
     #[error("Cannot retrieve response body")]
     CannotGetTextResponse(#[from] reqwest::Error),
+    #[error("Unsupported response content encoding '{0}'")]
+    UnsupportedContentEncoding(String),
+    #[error("Cannot decompress response body")]
+    CannotDecompressResponse(#[source] std::io::Error),
     #[error("Could not parse response body, response: '{response_text}'")]
     ParseError {
         source: serde_json::Error,
@@ -200,39 +348,13 @@ pub enum ParsingResponseError {
     },
 }
 
-pub(crate) async fn to_json<T: DeserializeOwned>(
-    response: Response,
+pub(crate) fn to_json<T: DeserializeOwned>(
+    response: SyncApiResponse,
 ) -> Result<T, ParsingResponseError> {
-    // TODO not owned (to avoid double parsing)
-    let response_text = response.text().await?;
-    let result = serde_json::from_str(&response_text).map_err(|source| {
-        ParsingResponseError::ParseError {
-            source,
-            response_text,
-        }
-    })?;
-    Ok(result)
-}
-
-async fn response_or_err(
-    result: Result<Response, reqwest::Error>,
-) -> Result<Response, SyncApiErrorVariantV5> {
-    let response = match result {
-        Ok(result) => result,
-        Err(error) => {
-            if error.is_connect() {
-                return Err(SyncApiErrorVariantV5::ConnectionError(error));
-            } else {
-                return Err(SyncApiErrorVariantV5::Other(error.into()));
-            }
-        }
-    };
-
-    if response.status().is_success() {
-        return Ok(response);
-    }
-
-    Err(SyncApiErrorVariantV5::from_response_and_status(response.status(), response).await)
+    serde_json::from_slice(&response.body).map_err(|source| ParsingResponseError::ParseError {
+        source,
+        response_text: String::from_utf8_lossy(&response.body).to_string(),
+    })
 }
 
 #[cfg(test)]
@@ -297,4 +419,59 @@ mod tests {
 
         assert_matches!(result_with_auth, Err(_));
     }
+
+    #[actix_rt::test]
+    async fn test_compression() {
+        let mock_server = MockServer::start();
+        let url = mock_server.base_url();
+        let response_body = SyncEncoding::Gzip
+            .encode(r#"{"result":"ok"}"#.as_bytes())
+            .unwrap();
+
+        // Server that doesn't advertise accepted encodings gets uncompressed requests
+        let mut mock = mock_server.mock(|when, then| {
+            when.method(POST)
+                .header("accept-encoding", SYNC_ACCEPT_ENCODING)
+                .header_missing("content-encoding")
+                .body(r#"{"request":1}"#)
+                .path("/sync/v5/test");
+            then.status(200)
+                .header("accept-encoding", "gzip")
+                .header("content-encoding", "gzip")
+                .body(&response_body);
+        });
+
+        let api = SyncApiV5::new_test(&url, "", "", "site_id");
+        let response = api
+            .do_post("/sync/v5/test", &json!({"request": 1}))
+            .await
+            .unwrap();
+        mock.assert();
+        mock.delete();
+        assert_eq!(
+            to_json::<serde_json::Value>(response).unwrap(),
+            json!({"result": "ok"})
+        );
+
+        // Compressed with encoding advertised in previous response
+        let mock = mock_server.mock(|when, then| {
+            when.method(POST)
+                .header("content-encoding", "gzip")
+                .path("/sync/v5/test");
+            then.status(200).body(r#"{"result":"ok"}"#);
+        });
+        let request = json!({ "request": "records".repeat(100) });
+        api.do_post("/sync/v5/test", &request).await.unwrap();
+        mock.assert();
+
+        let request_bytes = serde_json::to_vec(&json!({"request": 1})).unwrap().len()
+            + serde_json::to_vec(&request).unwrap().len();
+        let response_bytes = r#"{"result":"ok"}"#.len() * 2;
+        let traffic = api.traffic.take();
+        assert_eq!(
+            traffic.uncompressed_bytes as usize,
+            request_bytes + response_bytes
+        );
+        assert!(traffic.transferred_bytes < traffic.uncompressed_bytes);
+    }
 }
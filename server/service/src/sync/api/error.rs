@@ -1,5 +1,5 @@
 use super::*;
-use reqwest::{Response, StatusCode, Url};
+use reqwest::{StatusCode, Url};
 use serde::{
     de::{value::StrDeserializer, IntoDeserializer},
     Deserialize, Deserializer, Serialize, Serializer,
@@ -24,11 +24,6 @@ pub enum SyncApiErrorVariantV5 {
     },
     #[error("status: '{status}' text: '{text}'")]
     AsText { status: StatusCode, text: String },
-    #[error("Cannot parse error, status: '{status}'")]
-    ErrorParsingError {
-        status: StatusCode,
-        source: reqwest::Error,
-    },
     #[error("Connection problem")]
     ConnectionError(#[from] reqwest::Error),
     #[error("Could not parse response")]
@@ -86,23 +81,21 @@ struct ErrorWrapper {
 }
 
 impl SyncApiErrorVariantV5 {
-    pub(crate) async fn from_response_and_status(status: StatusCode, response: Response) -> Self {
-        let error = match to_json::<ErrorWrapper>(response).await {
+    pub(crate) fn from_response(response: SyncApiResponse) -> Self {
+        let status = response.status;
+        let error = match to_json::<ErrorWrapper>(response) {
             Ok(ErrorWrapper { error: source }) => {
                 return SyncApiErrorVariantV5::ParsedError { source, status }
             }
             Err(error) => error,
         };
 
-        use ParsingResponseError::*;
         match error {
-            CannotGetTextResponse(source) => {
-                SyncApiErrorVariantV5::ErrorParsingError { status, source }
-            }
-            ParseError {
+            ParsingResponseError::ParseError {
                 response_text: text,
                 ..
             } => SyncApiErrorVariantV5::AsText { status, text },
+            error => SyncApiErrorVariantV5::ResponseParsingError(error),
         }
     }
 }
@@ -133,6 +126,25 @@ impl SyncApiError {
     pub(crate) fn is_unknown(&self) -> bool {
         matches!(self.source, SyncApiErrorVariantV5::Other(_))
     }
+
+    /// Request timed out or was rejected by the server (or a proxy) as too large,
+    /// batch is too large to be pushed or pulled in one request
+    pub(crate) fn is_oversized_batch(&self) -> bool {
+        match &self.source {
+            SyncApiErrorVariantV5::ConnectionError(error) => error.is_timeout(),
+            SyncApiErrorVariantV5::ResponseParsingError(
+                ParsingResponseError::CannotGetTextResponse(error),
+            ) => error.is_timeout(),
+            SyncApiErrorVariantV5::ParsedError { status, .. }
+            | SyncApiErrorVariantV5::AsText { status, .. } => matches!(
+                *status,
+                StatusCode::PAYLOAD_TOO_LARGE
+                    | StatusCode::REQUEST_TIMEOUT
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            _ => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -245,6 +257,23 @@ mod test {
                 ..
             }
         );
+        assert!(!result.is_oversized_batch());
+
+        // Payload too large (from proxy)
+        let mock_server = MockServer::start();
+        let url = mock_server.base_url();
+
+        mock_server.mock(|when, then| {
+            when.method(POST).path("/sync/v5/initialise");
+            then.status(413)
+                .body("<html>413 Request Entity Too Large</html>");
+        });
+
+        let result = create_api(&url, "", "")
+            .post_initialise()
+            .await
+            .expect_err("Should result in error");
+        assert!(result.is_oversized_batch());
 
         // Incorrect hardware id
         let mock_server = MockServer::start();
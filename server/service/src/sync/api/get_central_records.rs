@@ -31,11 +31,9 @@ impl SyncApiV5 {
             ("cursor", &cursor.to_string()),
             ("limit", &limit.to_string()),
         ];
-        let response = self.do_get(route, &query).await?;
+        let response = self.do_get_batch(route, &query).await?;
 
-        to_json(response)
-            .await
-            .map_err(|error| self.api_error(route, error.into()))
+        to_json(response).map_err(|error| self.api_error(route, error.into()))
     }
 }
 
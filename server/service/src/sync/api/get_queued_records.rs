@@ -8,11 +8,9 @@ impl SyncApiV5 {
     ) -> Result<RemoteSyncBatchV5, SyncApiError> {
         let route = "/sync/v5/queued_records";
         let query = [("limit", &batch_size.to_string())];
-        let response = self.do_get(route, &query).await?;
+        let response = self.do_get_batch(route, &query).await?;
 
-        to_json(response)
-            .await
-            .map_err(|error| self.api_error(route, error.into()))
+        to_json(response).map_err(|error| self.api_error(route, error.into()))
     }
 }
 
@@ -30,9 +30,7 @@ impl SyncApiV5 {
         let route = "/sync/v5/site";
         let response = self.do_get(route, &()).await?;
 
-        to_json(response)
-            .await
-            .map_err(|error| self.api_error(route, error.into()))
+        to_json(response).map_err(|error| self.api_error(route, error.into()))
     }
 }
 
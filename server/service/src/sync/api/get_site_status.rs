@@ -24,9 +24,7 @@ impl SyncApiV5 {
         let route = "/sync/v5/site_status";
         let response = self.do_get(route, &()).await?;
 
-        to_json(response)
-            .await
-            .map_err(|error| self.api_error(route, error.into()))
+        to_json(response).map_err(|error| self.api_error(route, error.into()))
     }
 }
 
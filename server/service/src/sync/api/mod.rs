@@ -1,4 +1,5 @@
 mod common_records;
+mod compression;
 mod core;
 mod error;
 mod get_central_records;
@@ -10,12 +11,13 @@ mod post_initialise;
 mod post_queued_records;
 
 pub(crate) use self::common_records::*;
+pub use self::compression::*;
 pub use self::core::*;
 pub use self::error::*;
 pub(crate) use get_central_records::*;
 pub(crate) use get_site_info::*;
 pub(crate) use get_site_status::*;
-#[cfg(test)]
+pub(crate) use post_acknowledged_records::*;
 pub(crate) use post_queued_records::*;
 
 #[cfg(test)]
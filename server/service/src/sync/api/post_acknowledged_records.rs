@@ -1,8 +1,8 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use super::*;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct RemoteSyncPullAcknowledgementV5 {
     #[serde(rename = "syncIDs")]
     pub(crate) sync_ids: Vec<String>,
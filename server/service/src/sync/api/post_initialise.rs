@@ -7,9 +7,7 @@ impl SyncApiV5 {
         let route = "/sync/v5/initialise";
         let response = self.do_empty_post(route).await?;
 
-        to_json(response)
-            .await
-            .map_err(|error| self.api_error(route, error.into()))
+        to_json(response).map_err(|error| self.api_error(route, error.into()))
     }
 }
 
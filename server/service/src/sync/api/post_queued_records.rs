@@ -21,11 +21,9 @@ impl SyncApiV5 {
             data: records,
         };
 
-        let response = self.do_post(route, &body).await?;
+        let response = self.do_post_batch(route, &body).await?;
 
-        to_json(response)
-            .await
-            .map_err(|error| self.api_error(route, error.into()))
+        to_json(response).map_err(|error| self.api_error(route, error.into()))
     }
 }
 
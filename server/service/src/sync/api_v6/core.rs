@@ -1,4 +1,12 @@
-use reqwest::Client;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::{
+    header::{ACCEPT_ENCODING, CONTENT_TYPE},
+    Client,
+};
 use thiserror::Error;
 use url::ParseError;
 
@@ -8,6 +16,10 @@ use super::*;
 pub(crate) struct SyncApiV6 {
     pub(crate) url: Url,
     pub(crate) sync_v5_settings: SyncApiSettings,
+    pub(crate) traffic: SyncTraffic,
+    // Request bodies are only compressed once central server advertised accepted encodings in
+    // `accept-encoding` response header (older central servers don't decompress requests)
+    request_encoding: Arc<Mutex<Option<SyncEncoding>>>,
 }
 
 #[derive(Error, Debug)]
@@ -31,18 +43,68 @@ impl SyncApiV6 {
         Ok(Self {
             url,
             sync_v5_settings: sync_v5_settings.clone(),
+            traffic: SyncTraffic::default(),
+            request_encoding: Arc::new(Mutex::new(None)),
         })
     }
 
+    /// Traffic of this instance is counted in `traffic`
+    pub(crate) fn with_traffic(self, traffic: &SyncTraffic) -> Self {
+        Self {
+            traffic: traffic.clone(),
+            ..self
+        }
+    }
+
+    /// Posts json request, compressed with encoding advertised by central server in a previous
+    /// response, central server decompresses request bodies according to `content-encoding`
+    /// header. Response is compressed by central server if it's in `accept-encoding` header
+    async fn post_json<T: Serialize, R: DeserializeOwned>(
+        &self,
+        url: &Url,
+        request: &T,
+        timeout: Option<Duration>,
+    ) -> Result<R, SyncApiErrorVariantV6> {
+        let body = serde_json::to_vec(request).map_err(anyhow::Error::from)?;
+        let request = Client::new()
+            .post(url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT_ENCODING, SYNC_ACCEPT_ENCODING);
+        let request = match timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        };
+        let encoding = *self.request_encoding.lock().unwrap();
+        let request = self
+            .traffic
+            .request_body(request, body, encoding)
+            .map_err(anyhow::Error::from)?;
+
+        let result = request.send().await;
+        if let Some(accept_encoding) = result
+            .as_ref()
+            .ok()
+            .and_then(|response| response.headers().get(ACCEPT_ENCODING))
+            .and_then(|value| value.to_str().ok())
+        {
+            *self.request_encoding.lock().unwrap() =
+                SyncEncoding::from_accept_encoding(accept_encoding);
+        }
+
+        response_or_err(result, &self.traffic).await
+    }
+
     pub async fn pull(
         &self,
         cursor: u64,
         batch_size: u32,
         is_initialised: bool,
+        transactional_sync_v6: bool,
     ) -> Result<SyncBatchV6, SyncApiErrorV6> {
         let Self {
             sync_v5_settings,
             url,
+            ..
         } = self;
 
         let route = "pull";
@@ -53,11 +115,13 @@ impl SyncApiV6 {
             batch_size,
             sync_v5_settings: sync_v5_settings.clone(),
             is_initialised,
+            transactional_sync_v6,
         };
 
-        let result = Client::new().post(url.clone()).json(&request).send().await;
-
-        let error = match response_or_err(result).await {
+        let error = match self
+            .post_json(&url, &request, Some(SYNC_BATCH_REQUEST_TIMEOUT))
+            .await
+        {
             Ok(SyncPullResponseV6::Data(data)) => return Ok(data),
             Ok(SyncPullResponseV6::Error(error)) => error.into(),
             Err(error) => error,
@@ -74,6 +138,7 @@ impl SyncApiV6 {
         let Self {
             sync_v5_settings,
             url,
+            ..
         } = self;
 
         let route = "push";
@@ -84,9 +149,10 @@ impl SyncApiV6 {
             sync_v5_settings: sync_v5_settings.clone(),
         };
 
-        let result = Client::new().post(url.clone()).json(&request).send().await;
-
-        let error = match response_or_err(result).await {
+        let error = match self
+            .post_json(&url, &request, Some(SYNC_BATCH_REQUEST_TIMEOUT))
+            .await
+        {
             Ok(SyncPushResponseV6::Data(data)) => return Ok(data),
             Ok(SyncPushResponseV6::Error(error)) => error.into(),
             Err(error) => error.into(),
@@ -103,6 +169,7 @@ impl SyncApiV6 {
         let Self {
             sync_v5_settings,
             url,
+            ..
         } = self;
 
         let route = "site_status";
@@ -112,9 +179,7 @@ impl SyncApiV6 {
             sync_v5_settings: sync_v5_settings.clone(),
         };
 
-        let result = Client::new().post(url.clone()).json(&request).send().await;
-
-        let error = match response_or_err(result).await {
+        let error = match self.post_json(&url, &request, None).await {
             Ok(SiteStatusResponseV6::Data(data)) => return Ok(data),
             Ok(SiteStatusResponseV6::Error(error)) => error.into(),
             Err(error) => error.into(),
@@ -127,3 +192,52 @@ impl SyncApiV6 {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use httpmock::{Method::POST, MockServer};
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_request_encoding() {
+        let mock_server = MockServer::start();
+        let api = SyncApiV6::new(
+            &mock_server.base_url(),
+            &SyncApiSettings {
+                server_url: mock_server.base_url(),
+                username: "site".to_string(),
+                password_sha256: "".to_string(),
+                site_uuid: "".to_string(),
+                app_version: "".to_string(),
+                app_name: "".to_string(),
+                sync_version: "".to_string(),
+            },
+        )
+        .unwrap();
+
+        // Central server that didn't advertise accepted encodings gets uncompressed requests
+        let mut mock = mock_server.mock(|when, then| {
+            when.method(POST)
+                .header_missing("content-encoding")
+                .path("/central/sync/site_status");
+            then.status(200)
+                .header("accept-encoding", "zstd")
+                .body(r#"{"data":{"is_integrating":false}}"#);
+        });
+        api.get_site_status().await.unwrap();
+        mock.assert();
+        mock.delete();
+
+        // Compressed with encoding advertised in previous response
+        let mock = mock_server.mock(|when, then| {
+            when.method(POST)
+                .header("content-encoding", "zstd")
+                .path("/central/sync/site_status");
+            then.status(200)
+                .body(r#"{"data":{"is_integrating":false}}"#);
+        });
+        api.get_site_status().await.unwrap();
+        mock.assert();
+    }
+}
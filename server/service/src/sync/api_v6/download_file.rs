@@ -1,17 +1,22 @@
 use super::*;
 use crate::static_files::{StaticFile, StaticFileService};
 use repository::sync_file_reference_row::SyncFileReferenceRow;
-use reqwest::{Client, Response};
+use reqwest::{header::RANGE, Client, Response};
 
 impl SyncApiV6 {
+    /// Downloads file from `offset`, appending to partially downloaded file if central server
+    /// responds with the requested range
     pub async fn download_file(
         &self,
         static_file_service: &StaticFileService,
         sync_file: &SyncFileReferenceRow,
+        offset: u64,
+        on_progress: impl FnMut(u64),
     ) -> Result<StaticFile, SyncApiErrorV6> {
         let Self {
             sync_v5_settings,
             url,
+            ..
         } = self;
 
         let route = "download_file";
@@ -24,15 +29,18 @@ impl SyncApiV6 {
             sync_v5_settings: sync_v5_settings.clone(),
         };
 
-        let request = Client::new().post(url.clone()).json(&request);
+        let mut request = Client::new().post(url.clone()).json(&request);
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
         let result = request.send().await;
 
         let downloaded_file = match download_response_or_err(result).await {
             Err(error) => Err(error),
             Ok(download_response) => static_file_service
-                .download_file_in_chunks(&sync_file, download_response)
+                .download_file_in_chunks(&sync_file, download_response, on_progress)
                 .await
-                .map_err(SyncApiErrorVariantV6::Other),
+                .map_err(|error| SyncApiErrorVariantV6::Other(error.into())),
         }
         .map_err(|source| SyncApiErrorV6 {
             url,
@@ -14,7 +14,8 @@ pub use self::core::*;
 use super::{
     api::{
         CommonSyncRecord, ParsedError, ParsingResponseError, SyncApiError, SyncApiErrorVariantV5,
-        SyncApiSettings,
+        SyncApiSettings, SyncEncoding, SyncTraffic, SYNC_ACCEPT_ENCODING,
+        SYNC_BATCH_REQUEST_TIMEOUT,
     },
     translations::PushSyncRecord,
 };
@@ -39,6 +40,8 @@ pub enum SyncParsedErrorV6 {
     IntegrationInProgress,
     #[error("Sync file not found, file_id: {0}")]
     SyncFileNotFound(String),
+    #[error("Sync file hash mismatch, file_id: {0}")]
+    SyncFileHashMismatch(String),
 }
 
 impl From<anyhow::Error> for SyncParsedErrorV6 {
@@ -125,6 +128,19 @@ pub enum SyncApiErrorVariantV6 {
     Other(#[from] anyhow::Error),
 }
 
+impl SyncApiErrorV6 {
+    /// Request timed out, batch is too large to be pushed or pulled in one request
+    pub(crate) fn is_oversized_batch(&self) -> bool {
+        match &self.source {
+            SyncApiErrorVariantV6::ConnectionError(error) => error.is_timeout(),
+            SyncApiErrorVariantV6::ParsingResponseError(
+                ParsingResponseError::CannotGetTextResponse(error),
+            ) => error.is_timeout(),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Serialize)]
 pub(crate) struct SyncRecordV6 {
     pub(crate) cursor: u64,
@@ -157,6 +173,9 @@ pub struct SyncPullRequestV6 {
     pub(crate) batch_size: u32,
     pub(crate) sync_v5_settings: SyncApiSettings,
     pub(crate) is_initialised: bool,
+    /// Site syncs transactional records with omSupply central, older sites don't send this field
+    #[serde(default)]
+    pub(crate) transactional_sync_v6: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -175,6 +194,10 @@ pub struct SiteStatusRequestV6 {
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct SiteStatusV6 {
     pub(crate) is_integrating: bool,
+    /// Central server accepts files uploaded in chunks (`SyncUploadFileRequestV6::chunk`), older
+    /// central servers don't send this field and expect whole file in one request
+    #[serde(default)]
+    pub(crate) supports_file_chunks: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -190,22 +213,45 @@ pub struct SyncDownloadFileRequestV6 {
 pub struct SyncUploadFileRequestV6 {
     pub file_id: String,
     pub sync_v5_settings: SyncApiSettings,
+    // Missing when whole file is uploaded in one request (older remote sites)
+    #[serde(default)]
+    pub chunk: Option<SyncUploadFileChunkV6>,
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone)]
+pub struct SyncUploadFileChunkV6 {
+    // Position of the chunk in the file
+    pub offset: u64,
+    pub total_bytes: u64,
+    // Hex encoded sha256 of the whole file, checked once the last chunk is received
+    pub sha256: String,
+}
+
+#[derive(Deserialize, Debug, Serialize, PartialEq)]
+pub struct SyncUploadFileSuccessV6 {
+    // Bytes received by central server, upload is resumed from here
+    pub received_bytes: u64,
 }
 
 #[derive(Deserialize, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum SyncUploadFileResponseV6 {
-    Data(()),
+    // None for whole file uploads, serialised as `null` to match response expected by older remote sites
+    Data(Option<SyncUploadFileSuccessV6>),
     Error(SyncParsedErrorV6),
 }
 
+/// Response header with hex encoded sha256 of the whole file, sent with sync file downloads
+pub const SYNC_FILE_SHA256_HEADER: &str = "x-file-sha256";
+
 async fn response_or_err<T: DeserializeOwned>(
     result: Result<Response, reqwest::Error>,
+    traffic: &SyncTraffic,
 ) -> Result<T, SyncApiErrorVariantV6> {
     let response = match result {
         Ok(result) => result,
         Err(error) => {
-            if error.is_connect() {
+            if error.is_connect() || error.is_timeout() {
                 return Err(SyncApiErrorVariantV6::ConnectionError(error));
             } else {
                 return Err(SyncApiErrorVariantV6::Other(error.into()));
@@ -214,17 +260,13 @@ async fn response_or_err<T: DeserializeOwned>(
     };
 
     // Not checking for status, expecting 200 only, even if there is error
-    let response_text = response
-        .text()
-        .await
-        .map_err(ParsingResponseError::CannotGetTextResponse)?;
+    let body = traffic.response_body(response).await?;
 
-    let result = serde_json::from_str(&response_text).map_err(|source| {
-        ParsingResponseError::ParseError {
+    let result =
+        serde_json::from_slice(&body).map_err(|source| ParsingResponseError::ParseError {
             source,
-            response_text,
-        }
-    })?;
+            response_text: String::from_utf8_lossy(&body).to_string(),
+        })?;
 
     Ok(result)
 }
@@ -2,19 +2,22 @@ use super::*;
 use repository::SyncFileReferenceRow;
 use reqwest::multipart;
 use reqwest::Client;
-use std::fs::File;
-use std::io::Read;
 
 impl SyncApiV6 {
+    /// Uploads one chunk of a file, returns number of bytes central server has received so far.
+    /// Whole file is uploaded when `chunk` is None (central server doesn't support chunks), in
+    /// which case central server responds with no data
     pub async fn upload_file(
         &self,
         sync_file_reference_row: &SyncFileReferenceRow,
         file_name: &str,
-        file_handle: File,
-    ) -> Result<(), SyncApiErrorV6> {
+        chunk: Option<SyncUploadFileChunkV6>,
+        chunk_bytes: Vec<u8>,
+    ) -> Result<Option<SyncUploadFileSuccessV6>, SyncApiErrorV6> {
         let Self {
             sync_v5_settings,
             url,
+            ..
         } = self;
 
         let route = "upload_file";
@@ -33,16 +36,17 @@ impl SyncApiV6 {
         let json_request = SyncUploadFileRequestV6 {
             file_id: sync_file_reference_row.id.clone(),
             sync_v5_settings: sync_v5_settings.clone(),
+            chunk,
         };
 
         let request = client.put(url.clone()).multipart(
-            to_reqwest_multipart(&json_request, file_name, file_handle)
+            to_reqwest_multipart(&json_request, file_name, chunk_bytes)
                 .map_err(|e| error_with_url(e.into()))?,
         );
 
         let result = request.send().await;
 
-        let error = match response_or_err(result).await {
+        let error = match response_or_err(result, &self.traffic).await {
             Ok(SyncUploadFileResponseV6::Data(data)) => return Ok(data),
             Ok(SyncUploadFileResponseV6::Error(error)) => error.into(),
             Err(error) => error.into(),
@@ -58,14 +62,9 @@ impl SyncApiV6 {
 fn to_reqwest_multipart(
     json_reqwest: &SyncUploadFileRequestV6,
     file_name: &str,
-    mut file_handle: File,
+    chunk_bytes: Vec<u8>,
 ) -> anyhow::Result<multipart::Form> {
-    let mut file_bytes = Vec::new();
-
-    // Read the file into memory (ideally could be a stream or something, and upload the file in chunk so we can stop quickly when sync starts/stops/pauses)
-    file_handle.read_to_end(&mut file_bytes)?;
-
-    let file_part = multipart::Part::bytes(file_bytes).file_name(file_name.to_string());
+    let file_part = multipart::Part::bytes(chunk_bytes).file_name(file_name.to_string());
 
     let json_part =
         multipart::Part::text(serde_json::to_string(json_reqwest)?).mime_str("application/json")?;
@@ -9,9 +9,9 @@ use crate::{
 };
 
 use super::{
-    api::{ParsingSyncRecordError, SyncApiSettings},
+    api::{ParsingSyncRecordError, SyncApiSettings, SyncTraffic},
     api_v6::{SyncApiErrorV6, SyncApiV6, SyncApiV6CreatingError},
-    get_sync_push_changelogs_filter,
+    get_central_push_changelogs_filter, is_transactional_sync_v6,
     sync_status::logger::{SyncLogger, SyncLoggerError},
     translations::{
         translate_changelogs_to_sync_records, PushTranslationError, ToSyncRecordTranslationType,
@@ -67,9 +67,10 @@ impl SynchroniserV6 {
     pub(crate) fn new(
         url: &str,
         sync_v5_settings: &SyncApiSettings,
+        traffic: &SyncTraffic,
     ) -> Result<Self, SyncApiV6CreatingError> {
         Ok(Self {
-            sync_api_v6: SyncApiV6::new(url, sync_v5_settings)?,
+            sync_api_v6: SyncApiV6::new(url, sync_v5_settings)?.with_traffic(traffic),
         })
     }
 
@@ -93,6 +94,7 @@ impl SynchroniserV6 {
         logger: &mut SyncLogger<'a>,
     ) -> Result<(), CentralPullErrorV6> {
         let cursor_controller = CursorController::new(KeyType::SyncPullCursorV6);
+        let transactional_sync_v6 = is_transactional_sync_v6(connection)?;
         // TODO protection from infinite loop
         loop {
             let cursor = cursor_controller.get(&connection)?;
@@ -104,13 +106,16 @@ impl SynchroniserV6 {
                 is_last_batch,
             } = self
                 .sync_api_v6
-                .pull(cursor, batch_size, is_initialised)
+                .pull(cursor, batch_size, is_initialised, transactional_sync_v6)
                 .await?;
 
             logger.progress(SyncStepProgress::PullCentralV6, total_records)?;
 
             for SyncRecordV6 { cursor, record } in records {
-                let buffer_row = record.to_buffer_row(None)?;
+                let buffer_row = SyncBufferRow {
+                    store_id: record.store_id(),
+                    ..record.to_buffer_row(None)?
+                };
 
                 insert_one_and_update_cursor(
                     connection,
@@ -137,7 +142,7 @@ impl SynchroniserV6 {
         logger: &mut SyncLogger<'a>,
     ) -> Result<(), RemotePushErrorV6> {
         let changelog_repo = ChangelogRepository::new(connection);
-        let change_log_filter = get_sync_push_changelogs_filter(connection)?;
+        let change_log_filter = get_central_push_changelogs_filter(connection, true)?;
         let cursor_controller = CursorController::new(KeyType::SyncPushCursorV6);
 
         loop {
@@ -0,0 +1,478 @@
+use chrono::Utc;
+use repository::{
+    ChangelogRepository, ChangelogTableName, KeyType, NameRowRepository, PaginationOption,
+    RepositoryError, StorageConnection, SyncAction, SyncBufferRow, SyncBufferRowRepository,
+    SyncConflictFilter, SyncConflictRepository, SyncConflictResolution, SyncConflictRow,
+    SyncConflictRowRepository, SyncConflictSort,
+};
+use thiserror::Error;
+use util::uuid::uuid;
+
+use crate::{
+    cursor_controller::CursorController, get_default_pagination, i64_to_u32, i64_to_u64,
+    service_provider::ServiceContext, ListError, ListResult,
+};
+
+use super::{
+    sync_buffer::SyncBuffer, translation_and_integration::TranslationAndIntegration,
+    translations::all_translators,
+};
+
+pub const MAX_LIMIT: u32 = 1000;
+pub const MIN_LIMIT: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum ResolveSyncConflictError {
+    #[error("Sync conflict {0} does not exist")]
+    ConflictDoesNotExist(String),
+    #[error("Sync conflict {0} is already resolved")]
+    ConflictAlreadyResolved(String),
+    #[error("Central record failed to integrate: {0}")]
+    IntegrationError(String),
+    #[error("Database error")]
+    DatabaseError(#[from] RepositoryError),
+}
+
+/// Central records that are also edited locally and pushed to legacy central server (v5). Only
+/// tables whose changelog distinguishes sync updates from local changes (`is_sync_update`) can be
+/// checked for conflicts.
+/// Items are not checked: the item table has no changelog and is never pushed, and item
+/// visibility (master list) is only edited centrally. Pack variants (edited with
+/// `MutateItemNamesCodesAndUnits`) are maintained on omSupply central server and only pulled by
+/// remote sites, so a local edit could not be kept with `KeepLocal`
+fn conflict_changelog_table(table_name: &str) -> Option<ChangelogTableName> {
+    match table_name {
+        "name" => Some(ChangelogTableName::Name),
+        _ => None,
+    }
+}
+
+/// Re-saves the local record, new changelog makes sure local change is pushed again
+/// (central server may have applied its own version)
+fn touch_local_record(
+    connection: &StorageConnection,
+    changelog_table_name: &ChangelogTableName,
+    record_id: &str,
+) -> Result<(), RepositoryError> {
+    match changelog_table_name {
+        ChangelogTableName::Name => {
+            let repository = NameRowRepository::new(connection);
+            if let Some(name) = repository.find_one_by_id(record_id)? {
+                repository.upsert_one(&name)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Called at the start of sync, before pushing. Central records pulled in this sync may have been
+/// changed on central server before it received the local changes pushed in this sync, so
+/// conflicts are checked against the push cursor from before the push
+pub(crate) fn save_conflict_push_cursor(
+    connection: &StorageConnection,
+) -> Result<(), RepositoryError> {
+    let push_cursor = CursorController::new(KeyType::RemoteSyncPushCursor).get(connection)?;
+    CursorController::new(KeyType::SyncConflictPushCursor).update(connection, push_cursor)
+}
+
+/// Records a conflict when a central record would overwrite local changes, i.e. when the
+/// latest change of the local record was not made by sync and was not pushed before this sync
+/// (changes pushed in previous syncs were acknowledged by central server, so the central record
+/// is newer).
+/// Returns true if the central record conflicts and should not be integrated.
+/// A newer central record replaces the central record of an unresolved conflict
+pub(crate) fn record_conflict(
+    connection: &StorageConnection,
+    sync_record: &SyncBufferRow,
+) -> Result<bool, RepositoryError> {
+    if sync_record.source_site_id.is_some() || sync_record.action == SyncAction::Merge {
+        return Ok(false);
+    }
+    let Some(changelog_table_name) = conflict_changelog_table(&sync_record.table_name) else {
+        return Ok(false);
+    };
+    let latest_change = ChangelogRepository::new(connection)
+        .find_latest_for_record(&changelog_table_name, &sync_record.record_id)?;
+    let local_changelog_cursor = match latest_change {
+        Some(changelog) if !changelog.is_sync_update => changelog.cursor,
+        _ => return Ok(false),
+    };
+    let push_cursor = CursorController::new(KeyType::SyncConflictPushCursor).get(connection)?;
+    if i64_to_u64(local_changelog_cursor) < push_cursor {
+        return Ok(false);
+    }
+
+    let repository = SyncConflictRowRepository::new(connection);
+    let existing =
+        repository.find_unresolved_for_record(&sync_record.table_name, &sync_record.record_id)?;
+    repository.upsert_one(&SyncConflictRow {
+        id: existing.map(|conflict| conflict.id).unwrap_or_else(uuid),
+        table_name: sync_record.table_name.clone(),
+        record_id: sync_record.record_id.clone(),
+        action: sync_record.action.clone(),
+        remote_data: sync_record.data.clone(),
+        local_changelog_cursor,
+        detected_datetime: Utc::now().naive_utc(),
+        resolved_datetime: None,
+        resolution: None,
+    })?;
+
+    log::warn!(
+        "Sync conflict, central {} record {} not integrated",
+        sync_record.table_name,
+        sync_record.record_id
+    );
+    Ok(true)
+}
+
+pub fn get_sync_conflicts(
+    ctx: &ServiceContext,
+    pagination: Option<PaginationOption>,
+    filter: Option<SyncConflictFilter>,
+    sort: Option<SyncConflictSort>,
+) -> Result<ListResult<SyncConflictRow>, ListError> {
+    let pagination = get_default_pagination(pagination, MAX_LIMIT, MIN_LIMIT)?;
+    let repository = SyncConflictRepository::new(&ctx.connection);
+
+    Ok(ListResult {
+        rows: repository.query(pagination, filter.clone(), sort)?,
+        count: i64_to_u32(repository.count(filter)?),
+    })
+}
+
+/// `KeepLocal` re-saves the local record so that it's pushed again, `AcceptRemote` integrates
+/// the central record over the local record
+pub fn resolve_sync_conflict(
+    ctx: &ServiceContext,
+    id: &str,
+    resolution: SyncConflictResolution,
+) -> Result<SyncConflictRow, ResolveSyncConflictError> {
+    ctx.connection
+        .transaction_sync(|connection| {
+            let repository = SyncConflictRowRepository::new(connection);
+            let conflict = repository
+                .find_one_by_id(id)?
+                .ok_or_else(|| ResolveSyncConflictError::ConflictDoesNotExist(id.to_string()))?;
+            if conflict.resolved_datetime.is_some() {
+                return Err(ResolveSyncConflictError::ConflictAlreadyResolved(
+                    id.to_string(),
+                ));
+            }
+
+            match resolution {
+                SyncConflictResolution::KeepLocal => {
+                    if let Some(changelog_table_name) =
+                        conflict_changelog_table(&conflict.table_name)
+                    {
+                        touch_local_record(connection, &changelog_table_name, &conflict.record_id)?;
+                    }
+                }
+                SyncConflictResolution::AcceptRemote => {
+                    integrate_remote_record(connection, &conflict)?
+                }
+            }
+
+            let conflict = SyncConflictRow {
+                resolved_datetime: Some(Utc::now().naive_utc()),
+                resolution: Some(resolution),
+                ..conflict
+            };
+            repository.upsert_one(&conflict)?;
+
+            Ok(conflict)
+        })
+        .map_err(|error| error.to_inner_error())
+}
+
+fn integrate_remote_record(
+    connection: &StorageConnection,
+    conflict: &SyncConflictRow,
+) -> Result<(), ResolveSyncConflictError> {
+    let sync_record = SyncBufferRow {
+        record_id: conflict.record_id.clone(),
+        received_datetime: Utc::now().naive_utc(),
+        table_name: conflict.table_name.clone(),
+        action: conflict.action.clone(),
+        data: conflict.remote_data.clone(),
+        ..Default::default()
+    };
+
+    let sync_buffer = SyncBuffer::new(connection);
+    TranslationAndIntegration::new(connection, &sync_buffer)
+        .without_conflict_detection()
+        .translate_and_integrate_sync_records(
+            vec![sync_record.clone()],
+            &all_translators(),
+            None,
+        )?;
+
+    let integrated =
+        SyncBufferRowRepository::new(connection).find_one_by_record_id(&sync_record.record_id)?;
+    match integrated.and_then(|row| row.integration_error) {
+        Some(error) => Err(ResolveSyncConflictError::IntegrationError(error)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use repository::{
+        mock::MockDataInserts, test_db::setup_all, ChangelogRepository, ChangelogTableName,
+        DatetimeFilter, KeyType, KeyValueStoreRepository, NameRow, NameRowRepository,
+        StorageConnection, SyncConflictFilter, SyncConflictResolution,
+    };
+    use serde_json::json;
+
+    use crate::{
+        cursor_controller::CursorController,
+        service_provider::ServiceProvider,
+        sync::{
+            api::SyncApiV5,
+            central_data_synchroniser::CentralDataSynchroniser,
+            remote_data_synchroniser::RemoteDataSynchroniser,
+            store_backfill::integrate_prioritised,
+            sync_buffer::SyncBuffer,
+            sync_status::logger::SyncLogger,
+            test::{
+                check_integrated, test_data::name::test_pull_upsert_records, TestSyncIncomingRecord,
+            },
+            translation_and_integration::TranslationAndIntegration,
+            translations::{all_translators, PullTranslateResult},
+        },
+    };
+
+    use super::{
+        get_sync_conflicts, resolve_sync_conflict, save_conflict_push_cursor,
+        ResolveSyncConflictError,
+    };
+
+    #[actix_rt::test]
+    async fn test_sync_conflicts() {
+        let (_, connection, connection_manager, _) =
+            setup_all("test_sync_conflicts", MockDataInserts::none()).await;
+        let service_provider = ServiceProvider::new(connection_manager, "app_data");
+        let context = service_provider.basic_context().unwrap();
+
+        let TestSyncIncomingRecord {
+            translated_record,
+            sync_buffer_row: sync_record,
+            ..
+        } = test_pull_upsert_records().remove(0);
+
+        // Local change of the name
+        let name_repository = NameRowRepository::new(&connection);
+        let local_name = NameRow {
+            id: sync_record.record_id.clone(),
+            name: "Local name".to_string(),
+            ..Default::default()
+        };
+        name_repository.upsert_one(&local_name).unwrap();
+
+        let integrate = |sync_record| {
+            let sync_buffer = SyncBuffer::new(&connection);
+            TranslationAndIntegration::new(&connection, &sync_buffer)
+                .translate_and_integrate_sync_records(vec![sync_record], &all_translators(), None)
+                .unwrap();
+        };
+
+        // Central record conflicts, local name is kept
+        integrate(sync_record.clone());
+        assert_eq!(
+            name_repository
+                .find_one_by_id(&sync_record.record_id)
+                .unwrap()
+                .map(|name| name.name),
+            Some("Local name".to_string())
+        );
+        let unresolved = || {
+            get_sync_conflicts(
+                &context,
+                None,
+                Some(SyncConflictFilter::new().resolved_datetime(DatetimeFilter::is_null(true))),
+                None,
+            )
+            .unwrap()
+        };
+        assert_eq!(unresolved().count, 1);
+
+        // Newer central record updates existing conflict
+        integrate(sync_record.clone());
+        let conflict = unresolved().rows.pop().unwrap();
+        assert_eq!(unresolved().count, 1);
+
+        // Keep local, local name is changed again (to be pushed)
+        let conflict =
+            resolve_sync_conflict(&context, &conflict.id, SyncConflictResolution::KeepLocal)
+                .unwrap();
+        assert_eq!(conflict.resolution, Some(SyncConflictResolution::KeepLocal));
+        let latest_change = ChangelogRepository::new(&connection)
+            .find_latest_for_record(&ChangelogTableName::Name, &sync_record.record_id)
+            .unwrap()
+            .unwrap();
+        assert!(latest_change.cursor > conflict.local_changelog_cursor);
+        assert!(!latest_change.is_sync_update);
+
+        assert!(matches!(
+            resolve_sync_conflict(&context, &conflict.id, SyncConflictResolution::KeepLocal),
+            Err(ResolveSyncConflictError::ConflictAlreadyResolved(_))
+        ));
+        assert!(matches!(
+            resolve_sync_conflict(&context, "invalid", SyncConflictResolution::KeepLocal),
+            Err(ResolveSyncConflictError::ConflictDoesNotExist(_))
+        ));
+
+        // Accept remote, central name overwrites local name
+        integrate(sync_record.clone());
+        let conflict = unresolved().rows.pop().unwrap();
+        resolve_sync_conflict(&context, &conflict.id, SyncConflictResolution::AcceptRemote)
+            .unwrap();
+        match translated_record {
+            PullTranslateResult::IntegrationOperations(operations) => {
+                check_integrated(&connection, operations)
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(unresolved().count, 0);
+
+        // No local changes since, central record is integrated
+        integrate(sync_record.clone());
+        assert_eq!(unresolved().count, 0);
+
+        // Local change that was already pushed (before push cursor), central record is newer
+        name_repository.upsert_one(&local_name).unwrap();
+        let latest_cursor = ChangelogRepository::new(&connection)
+            .latest_cursor()
+            .unwrap();
+        CursorController::new(KeyType::RemoteSyncPushCursor)
+            .update(&connection, latest_cursor + 1)
+            .unwrap();
+        save_conflict_push_cursor(&connection).unwrap();
+        integrate(sync_record.clone());
+        assert_eq!(unresolved().count, 0);
+    }
+
+    /// Steps of `Synchroniser::sync_inner` that conflict detection relies on
+    async fn sync_cycle(
+        connection: &StorageConnection,
+        remote: &RemoteDataSynchroniser,
+        central: &CentralDataSynchroniser,
+    ) {
+        let mut logger = SyncLogger::start(connection).unwrap();
+        save_conflict_push_cursor(connection).unwrap();
+        remote.push(connection, 500, &mut logger).await.unwrap();
+        central.pull(connection, 500, &mut logger).await.unwrap();
+        integrate_prioritised(connection, true, &[], None).unwrap();
+    }
+
+    /// Local change is pushed and a central change made before the push is pulled in the same
+    /// sync
+    #[actix_rt::test]
+    async fn test_sync_conflicts_sync_cycle() {
+        let (_, connection, connection_manager, _) =
+            setup_all("test_sync_conflicts_sync_cycle", MockDataInserts::none()).await;
+        let service_provider = ServiceProvider::new(connection_manager, "app_data");
+        let context = service_provider.basic_context().unwrap();
+        KeyValueStoreRepository::new(&connection)
+            .set_i32(KeyType::SettingsSyncSiteId, Some(1))
+            .unwrap();
+
+        let TestSyncIncomingRecord {
+            sync_buffer_row: sync_record,
+            ..
+        } = test_pull_upsert_records().remove(0);
+        let name_repository = NameRowRepository::new(&connection);
+        name_repository
+            .upsert_one(&NameRow {
+                id: sync_record.record_id.clone(),
+                name: "Local name".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let mock_server = MockServer::start();
+        let push_mock = mock_server.mock(|when, then| {
+            when.method(POST).path("/sync/v5/queued_records");
+            then.status(200)
+                .json_body(json!({ "integrationStarted": true }));
+        });
+        let central_record = |cursor: u64| {
+            json!({
+                "ID": cursor,
+                "tableName": sync_record.table_name,
+                "recordId": sync_record.record_id,
+                "action": "update",
+                "recordData": serde_json::from_str::<serde_json::Value>(&sync_record.data).unwrap(),
+            })
+        };
+        let mut pull_mock = mock_server.mock(|when, then| {
+            when.method(GET)
+                .path("/sync/v5/central_records")
+                .query_param("cursor", "0");
+            then.status(200)
+                .json_body(json!({ "maxCursor": 1, "data": [central_record(1)] }));
+        });
+        let mut pull_end_mock = mock_server.mock(|when, then| {
+            when.method(GET)
+                .path("/sync/v5/central_records")
+                .query_param("cursor", "1");
+            then.status(200)
+                .json_body(json!({ "maxCursor": 1, "data": [] }));
+        });
+
+        let sync_api_v5 = SyncApiV5::new_test(&mock_server.base_url(), "site", "", "hardware_id");
+        let remote = RemoteDataSynchroniser {
+            sync_api_v5: sync_api_v5.clone(),
+        };
+        let central = CentralDataSynchroniser { sync_api_v5 };
+        let unresolved_count = || {
+            get_sync_conflicts(
+                &context,
+                None,
+                Some(SyncConflictFilter::new().resolved_datetime(DatetimeFilter::is_null(true))),
+                None,
+            )
+            .unwrap()
+            .count
+        };
+        let name = || {
+            name_repository
+                .find_one_by_id(&sync_record.record_id)
+                .unwrap()
+                .unwrap()
+                .name
+        };
+
+        // Local change is pushed in this sync, central record was changed before central
+        // server received it
+        sync_cycle(&connection, &remote, &central).await;
+        assert!(push_mock.hits() > 0);
+        assert_eq!(unresolved_count(), 1);
+        assert_eq!(name(), "Local name");
+
+        // Central record changed after the local change was pushed, it's integrated
+        pull_mock.delete();
+        mock_server.mock(|when, then| {
+            when.method(GET)
+                .path("/sync/v5/central_records")
+                .query_param("cursor", "1");
+            then.status(200)
+                .json_body(json!({ "maxCursor": 2, "data": [central_record(2)] }));
+        });
+        mock_server.mock(|when, then| {
+            when.method(GET)
+                .path("/sync/v5/central_records")
+                .query_param("cursor", "2");
+            then.status(200)
+                .json_body(json!({ "maxCursor": 2, "data": [] }));
+        });
+        pull_end_mock.delete();
+        sync_cycle(&connection, &remote, &central).await;
+        assert_eq!(unresolved_count(), 1);
+        assert_ne!(name(), "Local name");
+    }
+}
@@ -1,5 +1,7 @@
 use chrono::{Duration, Utc};
 use std::cmp;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
 use std::sync::Arc;
 use thiserror::Error;
 use util::format_error;
@@ -11,13 +13,14 @@ use repository::{
     RepositoryError,
 };
 
-use crate::static_files::{StaticFile, StaticFileCategory};
+use crate::static_files::{file_sha256, StaticFile, StaticFileCategory};
 use crate::sync::api::SyncApiV5;
 use crate::sync::api_v6::SyncApiV6;
 use crate::sync::settings::SYNC_VERSION;
+use crate::{i32_to_u64, u64_to_i32};
 use crate::{service_provider::ServiceProvider, static_files::StaticFileService};
 
-use super::api_v6::{SyncApiErrorV6, SyncApiV6CreatingError};
+use super::api_v6::{SyncApiErrorV6, SyncApiV6CreatingError, SyncUploadFileChunkV6};
 use super::settings::SyncSettings;
 use super::{
     api::SyncApiV5CreatingError,
@@ -27,6 +30,7 @@ use super::{
 pub static MAX_UPLOAD_ATTEMPTS: i32 = 7 * 24; // 7 days * 24 hours Retry sending for up to for 1 week before giving up
 pub static RETRY_DELAY_MINUTES: i64 = 15; // Doubles each retry until MAX_RETRY_DELAY_MINUTES
 pub static MAX_RETRY_DELAY_MINUTES: i64 = 60; // 1 hour
+pub static FILE_CHUNK_SIZE: u64 = 512 * 1024; // Uploaded per request, and how often download progress is saved
 
 #[derive(Debug, Error)]
 pub(crate) enum FileSyncError {
@@ -54,6 +58,8 @@ pub enum DownloadFileError {
     SyncApiV6CreatingError(#[from] SyncApiV6CreatingError),
     #[error(transparent)]
     SyncApiV5CreatingError(#[from] SyncApiV5CreatingError),
+    #[error("File system error")]
+    FileSystemError(#[from] anyhow::Error),
 }
 
 pub struct FileSynchroniser {
@@ -93,18 +99,52 @@ impl FileSynchroniser {
             .find_one_by_id(&file_id)?
             .ok_or(Error::FileDoesNotExist(file_id.to_string()))?;
 
+        // Resume from partially downloaded file
+        let mut offset = self
+            .static_file_service
+            .partial_sync_file_length(&sync_file_ref)?;
+        if offset >= i32_to_u64(sync_file_ref.total_bytes) {
+            // Already complete but not moved, or from a different version of the file
+            offset = 0;
+        }
+
+        let mut saved_bytes = offset;
         let download_result = self
             .sync_api_v6
-            .download_file(&self.static_file_service, &sync_file_ref)
+            .download_file(
+                &self.static_file_service,
+                &sync_file_ref,
+                offset,
+                |downloaded_bytes| {
+                    if downloaded_bytes < saved_bytes + FILE_CHUNK_SIZE {
+                        return;
+                    }
+                    saved_bytes = downloaded_bytes;
+                    let progress = SyncFileReferenceRow {
+                        downloaded_bytes: u64_to_i32(downloaded_bytes),
+                        status: SyncFileStatus::InProgress,
+                        ..sync_file_ref.clone()
+                    };
+                    if let Err(error) = sync_file_repo.update_status(&progress) {
+                        log::error!("Failed to save file download progress: {}", error);
+                    }
+                },
+            )
             .await;
 
         let file_row_update = match &download_result {
             Ok(_) => SyncFileReferenceRow {
                 downloaded_bytes: sync_file_ref.total_bytes,
                 status: SyncFileStatus::Done,
+                error: None,
                 ..sync_file_ref.clone()
             },
             Err(error) => SyncFileReferenceRow {
+                downloaded_bytes: u64_to_i32(
+                    self.static_file_service
+                        .partial_sync_file_length(&sync_file_ref)
+                        .unwrap_or_default(),
+                ),
                 status: SyncFileStatus::Error,
                 error: Some(format_error(&error)),
                 ..sync_file_ref.clone()
@@ -121,8 +161,9 @@ impl FileSynchroniser {
 
         // Find any files that need to be uploaded
         // Pick a file to upload
-        // Upload a file (In future this could be a chunk of data, instead of a whole file)
-        // Update the file record with the progress
+        // Upload the file a chunk at a time, continuing from the last chunk received by central
+        // (or whole file if central server doesn't support chunks)
+        // Update the file record with the progress after each chunk
         // Yield to the runtime to check if we've received a pause signal
 
         // Get any files that need to be sent to central server
@@ -135,10 +176,11 @@ impl FileSynchroniser {
         };
 
         // update the database to say we're uploading the file
-        sync_file_repo.update_status(&SyncFileReferenceRow {
+        let mut sync_file_reference = SyncFileReferenceRow {
             status: SyncFileStatus::InProgress,
             ..sync_file_reference.clone()
-        })?;
+        };
+        sync_file_repo.update_status(&sync_file_reference)?;
 
         let file_category = StaticFileCategory::SyncFile(
             sync_file_reference.table_name.to_owned(),
@@ -150,18 +192,67 @@ impl FileSynchroniser {
             .find_file(&sync_file_reference.id, file_category)?
             .ok_or(FileSyncError::FileNotFound(sync_file_reference.id.clone()))?;
 
-        let file_handle = std::fs::File::open(file.path.clone())?;
-
-        let upload_result = self
-            .sync_api_v6
-            .upload_file(&sync_file_reference, &file.name, file_handle)
-            .await;
+        let file_path = Path::new(&file.path);
+        let total_bytes = std::fs::metadata(file_path)?.len();
+
+        let upload_result = match self.sync_api_v6.get_site_status().await {
+            Ok(status) if status.supports_file_chunks => {
+                let sha256 = file_sha256(file_path)?;
+                // Central server responds with where to continue from, if it doesn't have the chunk before offset
+                let mut offset =
+                    cmp::min(i32_to_u64(sync_file_reference.uploaded_bytes), total_bytes);
+
+                loop {
+                    let chunk = SyncUploadFileChunkV6 {
+                        offset,
+                        total_bytes,
+                        sha256: sha256.clone(),
+                    };
+                    let result = self
+                        .sync_api_v6
+                        .upload_file(
+                            &sync_file_reference,
+                            &file.name,
+                            Some(chunk),
+                            read_chunk(file_path, offset)?,
+                        )
+                        .await;
+
+                    let received_bytes = match result {
+                        // Chunks are always answered with received bytes
+                        Ok(success) => success.map(|s| s.received_bytes).unwrap_or(total_bytes),
+                        Err(error) => break Err(error),
+                    };
+                    if received_bytes >= total_bytes {
+                        break Ok(());
+                    }
+
+                    sync_file_reference.uploaded_bytes = u64_to_i32(received_bytes);
+                    sync_file_repo.update_status(&sync_file_reference)?;
+                    offset = received_bytes;
+
+                    tokio::task::yield_now().await;
+                }
+            }
+            // Older central server, upload whole file in one request
+            Ok(_) => self
+                .sync_api_v6
+                .upload_file(
+                    &sync_file_reference,
+                    &file.name,
+                    None,
+                    std::fs::read(file_path)?,
+                )
+                .await
+                .map(|_| ()),
+            Err(error) => Err(error),
+        };
 
         let Err(error) = upload_result
         // On Success
         else {
             sync_file_repo.update_status(&SyncFileReferenceRow {
-                uploaded_bytes: sync_file_reference.total_bytes, // We always upload the whole file in one go
+                uploaded_bytes: sync_file_reference.total_bytes,
                 status: SyncFileStatus::Done,
                 error: None,
                 ..sync_file_reference.clone()
@@ -216,3 +307,12 @@ impl FileSynchroniser {
         Err(error.into())
     }
 }
+
+fn read_chunk(file_path: &Path, offset: u64) -> std::io::Result<Vec<u8>> {
+    let mut file_handle = std::fs::File::open(file_path)?;
+    file_handle.seek(SeekFrom::Start(offset))?;
+
+    let mut chunk = Vec::new();
+    file_handle.take(FILE_CHUNK_SIZE).read_to_end(&mut chunk)?;
+    Ok(chunk)
+}
@@ -0,0 +1,316 @@
+use chrono::Utc;
+use repository::{
+    EqualFilter, PaginationOption, RepositoryError, StorageConnection, SyncAction,
+    SyncBufferFilter, SyncBufferRepository, SyncBufferRow, SyncBufferRowRepository,
+};
+use thiserror::Error;
+
+use crate::{
+    get_default_pagination, i64_to_u32, service_provider::ServiceContext, usize_to_u32, ListError,
+    ListResult,
+};
+
+use super::{
+    sync_buffer::SyncBuffer,
+    translation_and_integration::TranslationAndIntegration,
+    translations::{all_translators, pull_integration_order},
+};
+
+pub const MAX_LIMIT: u32 = 1000;
+pub const MIN_LIMIT: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum IntegrationErrorsError {
+    #[error("Sync buffer record {0} does not exist")]
+    RecordDoesNotExist(String),
+    #[error("Sync buffer record {0} has no integration error")]
+    RecordHasNoIntegrationError(String),
+    #[error("Database error")]
+    DatabaseError(#[from] RepositoryError),
+}
+
+#[derive(Debug, PartialEq, Default)]
+pub struct ReplayIntegrationResult {
+    pub integrated_count: u32,
+    pub error_count: u32,
+}
+
+/// Sync buffer records that failed translation or integration, including ignored records unless
+/// filtered by `ignored_datetime`
+pub fn get_integration_errors(
+    ctx: &ServiceContext,
+    pagination: Option<PaginationOption>,
+    filter: Option<SyncBufferFilter>,
+) -> Result<ListResult<SyncBufferRow>, ListError> {
+    let pagination = get_default_pagination(pagination, MAX_LIMIT, MIN_LIMIT)?;
+    let filter = filter
+        .unwrap_or_default()
+        .integration_error(EqualFilter::is_null(false));
+    let repository = SyncBufferRepository::new(&ctx.connection);
+
+    Ok(ListResult {
+        rows: repository.query_paginated(pagination, Some(filter.clone()))?,
+        count: i64_to_u32(repository.count(Some(filter))?),
+    })
+}
+
+/// Translates and integrates failed records again (e.g. after the data or translator has been
+/// fixed), in the same order as sync integration. Ignored records are no longer ignored once replayed
+pub fn replay_integration_errors(
+    ctx: &ServiceContext,
+    record_ids: &[String],
+) -> Result<ReplayIntegrationResult, IntegrationErrorsError> {
+    let result = ctx
+        .connection
+        .transaction_sync(|connection| {
+            let translators = all_translators();
+            let table_order = pull_integration_order(&translators);
+
+            let mut rows = get_failed_records(connection, record_ids)?;
+            for row in rows.iter_mut() {
+                row.ignored_datetime = None;
+            }
+            sort_in_integration_order(&mut rows, &table_order);
+
+            let sync_buffer = SyncBuffer::new(connection);
+            TranslationAndIntegration::new(connection, &sync_buffer)
+                .translate_and_integrate_sync_records(rows, &translators, None)?;
+
+            let rows = find_records(connection, record_ids)?;
+            let error_count = rows
+                .iter()
+                .filter(|row| row.integration_error.is_some())
+                .count();
+
+            Ok(ReplayIntegrationResult {
+                integrated_count: usize_to_u32(rows.len() - error_count),
+                error_count: usize_to_u32(error_count),
+            })
+        })
+        .map_err(|error| error.to_inner_error())?;
+
+    if result.integrated_count > 0 {
+        ctx.processors_trigger
+            .trigger_requisition_transfer_processors();
+        ctx.processors_trigger.trigger_invoice_transfer_processors();
+        ctx.processors_trigger.trigger_audit_log_processor();
+    }
+
+    Ok(result)
+}
+
+/// Marks failed records as ignored, the integration error is kept for reference.
+/// Ignoring only hides records from integration error listings filtered by `ignored_datetime`,
+/// the records are still not integrated and can be replayed by id.
+/// Returns number of records ignored
+pub fn ignore_integration_errors(
+    ctx: &ServiceContext,
+    record_ids: &[String],
+) -> Result<u32, IntegrationErrorsError> {
+    ctx.connection
+        .transaction_sync(|connection| {
+            let rows = get_failed_records(connection, record_ids)?;
+            let repository = SyncBufferRowRepository::new(connection);
+            let ignored_datetime = Some(Utc::now().naive_utc());
+
+            for row in rows.iter() {
+                repository.upsert_one(&SyncBufferRow {
+                    ignored_datetime,
+                    ..row.clone()
+                })?;
+            }
+
+            Ok(usize_to_u32(rows.len()))
+        })
+        .map_err(|error| error.to_inner_error())
+}
+
+fn find_records(
+    connection: &StorageConnection,
+    record_ids: &[String],
+) -> Result<Vec<SyncBufferRow>, RepositoryError> {
+    SyncBufferRepository::new(connection).query_by_filter(
+        SyncBufferFilter::new().record_id(EqualFilter::equal_any(record_ids.to_vec())),
+    )
+}
+
+fn get_failed_records(
+    connection: &StorageConnection,
+    record_ids: &[String],
+) -> Result<Vec<SyncBufferRow>, IntegrationErrorsError> {
+    let rows = find_records(connection, record_ids)?;
+
+    for record_id in record_ids {
+        match rows.iter().find(|row| &row.record_id == record_id) {
+            None => {
+                return Err(IntegrationErrorsError::RecordDoesNotExist(
+                    record_id.clone(),
+                ))
+            }
+            Some(SyncBufferRow {
+                integration_error: None,
+                ..
+            }) => {
+                return Err(IntegrationErrorsError::RecordHasNoIntegrationError(
+                    record_id.clone(),
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Upserts in referential constraint order, then deletes in reverse order, then merges
+/// (same as `integrate_and_translate_sync_buffer`)
+fn sort_in_integration_order(rows: &mut [SyncBufferRow], table_order: &[&str]) {
+    rows.sort_by_key(|row| {
+        // Tables without translator are last, they will fail again
+        let table_index = table_order
+            .iter()
+            .position(|table_name| *table_name == row.table_name)
+            .unwrap_or(table_order.len()) as i64;
+
+        match row.action {
+            SyncAction::Upsert => (0, table_index),
+            SyncAction::Delete => (1, -table_index),
+            SyncAction::Merge => (2, table_index),
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use repository::{
+        mock::{MockData, MockDataInserts},
+        test_db::setup_all_with_data,
+        DatetimeFilter, StringFilter, SyncBufferFilter, SyncBufferRow, SyncBufferRowRepository,
+        UnitRowRepository,
+    };
+    use util::{inline_init, Defaults};
+
+    use crate::service_provider::ServiceProvider;
+
+    use super::{
+        get_integration_errors, ignore_integration_errors, replay_integration_errors,
+        IntegrationErrorsError, ReplayIntegrationResult,
+    };
+
+    fn fixed_unit() -> SyncBufferRow {
+        inline_init(|r: &mut SyncBufferRow| {
+            r.record_id = "fixed_unit".to_string();
+            r.table_name = "unit".to_string();
+            r.received_datetime = Defaults::naive_date_time();
+            r.integration_datetime = Some(Defaults::naive_date_time());
+            r.integration_error = Some("Previous error".to_string());
+            r.data = r#"{
+                "ID": "fixed_unit",
+                "units": "Units",
+                "comment": "",
+                "order_number": 0
+            }"#
+            .to_string();
+        })
+    }
+
+    fn unknown_table() -> SyncBufferRow {
+        inline_init(|r: &mut SyncBufferRow| {
+            r.record_id = "unknown_table".to_string();
+            r.table_name = "unknown_table".to_string();
+            r.received_datetime = Defaults::naive_date_time();
+            r.integration_datetime = Some(Defaults::naive_date_time());
+            r.integration_error = Some("Translator for record not found".to_string());
+            r.data = "{}".to_string();
+        })
+    }
+
+    fn integrated() -> SyncBufferRow {
+        inline_init(|r: &mut SyncBufferRow| {
+            r.record_id = "integrated".to_string();
+            r.table_name = "unit".to_string();
+            r.received_datetime = Defaults::naive_date_time();
+            r.integration_datetime = Some(Defaults::naive_date_time());
+        })
+    }
+
+    #[actix_rt::test]
+    async fn integration_errors() {
+        let (_, connection, connection_manager, _) = setup_all_with_data(
+            "integration_errors",
+            MockDataInserts::none(),
+            inline_init(|r: &mut MockData| {
+                r.sync_buffer_rows = vec![fixed_unit(), unknown_table(), integrated()];
+            }),
+        )
+        .await;
+        let service_provider = ServiceProvider::new(connection_manager, "app_data");
+        let ctx = service_provider.basic_context().unwrap();
+
+        let errors = get_integration_errors(&ctx, None, None).unwrap();
+        assert_eq!(errors.count, 2);
+
+        let errors = get_integration_errors(
+            &ctx,
+            None,
+            Some(
+                SyncBufferFilter::new().integration_error_message(StringFilter::like("not found")),
+            ),
+        )
+        .unwrap();
+        assert_eq!(errors.rows, vec![unknown_table()]);
+
+        assert!(matches!(
+            replay_integration_errors(&ctx, &["integrated".to_string()]),
+            Err(IntegrationErrorsError::RecordHasNoIntegrationError(_))
+        ));
+        assert!(matches!(
+            ignore_integration_errors(&ctx, &["missing".to_string()]),
+            Err(IntegrationErrorsError::RecordDoesNotExist(_))
+        ));
+
+        // Ignore
+        assert_eq!(
+            ignore_integration_errors(&ctx, &["unknown_table".to_string()]).unwrap(),
+            1
+        );
+        let errors = get_integration_errors(
+            &ctx,
+            None,
+            Some(SyncBufferFilter::new().ignored_datetime(DatetimeFilter::is_null(true))),
+        )
+        .unwrap();
+        assert_eq!(errors.rows, vec![fixed_unit()]);
+
+        // Replay
+        let result = replay_integration_errors(
+            &ctx,
+            &["fixed_unit".to_string(), "unknown_table".to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            ReplayIntegrationResult {
+                integrated_count: 1,
+                error_count: 1
+            }
+        );
+
+        assert!(UnitRowRepository::new(&connection)
+            .find_one_by_id_option("fixed_unit")
+            .unwrap()
+            .is_some());
+        let fixed_unit = SyncBufferRowRepository::new(&connection)
+            .find_one_by_record_id("fixed_unit")
+            .unwrap()
+            .unwrap();
+        assert_eq!(fixed_unit.integration_error, None);
+        // Replayed record is no longer ignored
+        let unknown_table = SyncBufferRowRepository::new(&connection)
+            .find_one_by_record_id("unknown_table")
+            .unwrap()
+            .unwrap();
+        assert_eq!(unknown_table.ignored_datetime, None);
+        assert!(unknown_table.integration_error.is_some());
+    }
+}
@@ -0,0 +1,123 @@
+mod routes;
+mod state;
+mod templates;
+
+pub use self::routes::*;
+pub use self::state::MockSite;
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use repository::{RepositoryError, SyncBufferRowRepository};
+use thiserror::Error;
+use util::{hash::sha256, uuid::uuid};
+
+use crate::service_provider::ServiceProvider;
+
+use self::state::MockCentralState;
+use super::{api::CommonSyncRecord, synchroniser::integrate_and_translate_sync_buffer};
+
+#[derive(Debug, Error)]
+pub enum MockCentralError {
+    #[error("Records should be an object of table names and arrays of records (or ids): {0}")]
+    InvalidRecords(#[from] serde_json::Error),
+    #[error("Could not parse central record")]
+    ParsingRecordError(String),
+    #[error("Database error")]
+    DatabaseError(#[from] RepositoryError),
+}
+
+/// Stands in for mSupply central server in sync tests, serving v5 sync api (including the
+/// test endpoints used by integration tests) and v6 sync api to multiple remote sites.
+/// Legacy central and remote data is kept in memory, central data is also integrated into the
+/// open mSupply database of `service_provider`, which serves v6 sync (like an open mSupply
+/// central server would).
+/// Transfer records pushed by a site are dispatched to the site of the other party store
+#[derive(Clone)]
+pub struct MockCentral {
+    state: Arc<Mutex<MockCentralState>>,
+    service_provider: Arc<ServiceProvider>,
+}
+
+impl MockCentral {
+    pub fn new(service_provider: Arc<ServiceProvider>) -> Self {
+        MockCentral {
+            state: Default::default(),
+            service_provider,
+        }
+    }
+
+    /// Creates a site with one store, site password is `password` (site name is used
+    /// if not provided)
+    pub fn create_site(
+        &self,
+        name: Option<&str>,
+        password: Option<&str>,
+        visible_name_ids: Vec<String>,
+    ) -> Result<MockSite, MockCentralError> {
+        let name = name.map(str::to_string).unwrap_or_else(|| {
+            let suffix: String = uuid().chars().take(8).collect();
+            format!("site_{}", suffix)
+        });
+        let password_sha256 = sha256(password.unwrap_or(&name));
+
+        let (site, records) =
+            self.state
+                .lock()
+                .unwrap()
+                .add_site(&name, &password_sha256, visible_name_ids);
+        self.integrate(records)?;
+
+        Ok(site)
+    }
+
+    /// Upserts central records, `records` is an object of table names and arrays of records,
+    /// i.e. `{ "item": [{ "ID": "item_a", "code": "A" }] }`
+    pub fn upsert_records(&self, records: serde_json::Value) -> Result<(), MockCentralError> {
+        let records: BTreeMap<String, Vec<serde_json::Value>> = serde_json::from_value(records)?;
+
+        let mut upserted = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            for (table_name, records) in records {
+                upserted.extend(state.upsert_central(&table_name, records));
+            }
+        }
+
+        self.integrate(upserted)
+    }
+
+    /// Deletes central records, `record_ids` is an object of table names and arrays of ids,
+    /// i.e. `{ "item": ["item_a"] }`
+    pub fn delete_records(&self, record_ids: serde_json::Value) -> Result<(), MockCentralError> {
+        let record_ids: BTreeMap<String, Vec<String>> = serde_json::from_value(record_ids)?;
+
+        let mut deleted = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            for (table_name, record_ids) in record_ids {
+                deleted.extend(state.delete_central(&table_name, record_ids));
+            }
+        }
+
+        self.integrate(deleted)
+    }
+
+    /// Central records are integrated into open mSupply database, for v6 sync
+    fn integrate(&self, records: Vec<CommonSyncRecord>) -> Result<(), MockCentralError> {
+        let ctx = self.service_provider.basic_context()?;
+        let repository = SyncBufferRowRepository::new(&ctx.connection);
+
+        for record in records {
+            let row = record
+                .to_buffer_row(None)
+                .map_err(|error| MockCentralError::ParsingRecordError(error.to_string()))?;
+            repository.upsert_one(&row)?;
+        }
+
+        integrate_and_translate_sync_buffer(&ctx.connection, true, None, None)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,379 @@
+use std::sync::MutexGuard;
+
+use actix_web::{
+    dev::Server,
+    error::{ErrorBadRequest, InternalError},
+    get,
+    http::header::{AUTHORIZATION, CONTENT_ENCODING},
+    post,
+    web::{self, Data},
+    App, HttpRequest, HttpResponse, HttpServer,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+
+use crate::sync::{
+    api::{
+        ParsedError, RemotePushResponseV5, RemoteSyncBatchV5, RemoteSyncPullAcknowledgementV5,
+        SiteStatusCodeV5, SiteStatusV5, SyncApiSettings, SyncEncoding, SyncErrorCodeV5,
+    },
+    api_v6::{
+        SiteStatusRequestV6, SiteStatusResponseV6, SyncParsedErrorV6, SyncPullRequestV6,
+        SyncPullResponseV6, SyncPushRequestV6, SyncPushResponseV6,
+    },
+    sync_on_central::{pull_for_site, push_for_site, site_status_for_site},
+};
+
+use super::{state::MockAuthError, MockCentral, MockCentralState};
+
+/// Starts mock central server on localhost `port` (0 for any free port), returns the port
+/// and the server, which should be awaited or spawned
+pub fn start_mock_central(mock_central: MockCentral, port: u16) -> std::io::Result<(u16, Server)> {
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(Data::new(mock_central.clone()))
+            .configure(config_mock_central)
+    })
+    .workers(2)
+    .bind(("127.0.0.1", port))?;
+
+    let port = server
+        .addrs()
+        .first()
+        .map(|address| address.port())
+        .unwrap_or(port);
+
+    Ok((port, server.run()))
+}
+
+pub fn config_mock_central(cfg: &mut web::ServiceConfig) {
+    cfg.service(site_info)
+        .service(site_status)
+        .service(initialise)
+        .service(central_records)
+        .service(queued_records)
+        .service(acknowledged_records)
+        .service(push_queued_records)
+        .service(test_upsert)
+        .service(test_delete)
+        .service(test_create_site)
+        .service(
+            web::scope("central/sync")
+                .service(pull_v6)
+                .service(push_v6)
+                .service(site_status_v6),
+        );
+}
+
+impl MockCentral {
+    fn state(&self) -> MutexGuard<MockCentralState> {
+        self.state.lock().unwrap()
+    }
+
+    /// v6 requests carry v5 credentials of the site
+    fn site_id_v6(&self, settings: &SyncApiSettings) -> Result<i32, SyncParsedErrorV6> {
+        self.state()
+            .authenticate(&settings.username, &settings.password_sha256)
+            .map_err(|error| SyncParsedErrorV6::LegacyServerError(parsed_error(error)))
+    }
+}
+
+fn parsed_error(error: MockAuthError) -> ParsedError {
+    let (code, message) = match error {
+        MockAuthError::SiteNameNotFound => (SyncErrorCodeV5::SiteNameNotFound, "Site not found"),
+        MockAuthError::IncorrectPassword => (
+            SyncErrorCodeV5::SiteIncorrectPassword,
+            "Site password is incorrect",
+        ),
+    };
+
+    ParsedError {
+        code,
+        message: message.to_string(),
+        data: None,
+    }
+}
+
+fn basic_auth(request: &HttpRequest) -> Option<(String, String)> {
+    let value = request.headers().get(AUTHORIZATION)?.to_str().ok()?;
+    let decoded = STANDARD.decode(value.strip_prefix("Basic ")?).ok()?;
+    let credentials = String::from_utf8(decoded).ok()?;
+    let (username, password) = credentials.split_once(':')?;
+
+    Some((username.to_string(), password.to_string()))
+}
+
+/// Site id of the site in basic auth credentials, responds with mSupply error otherwise
+fn authenticate(request: &HttpRequest, mock_central: &MockCentral) -> actix_web::Result<i32> {
+    let (username, password_sha256) = basic_auth(request).unwrap_or_default();
+
+    mock_central
+        .state()
+        .authenticate(&username, &password_sha256)
+        .map_err(|error| {
+            let response = HttpResponse::Unauthorized().json(json!({
+                "error": parsed_error(error)
+            }));
+            InternalError::from_response("Site authentication failed", response).into()
+        })
+}
+
+/// Request bodies can be compressed (v6 request bodies always are)
+async fn json_body<T: DeserializeOwned>(
+    request: &HttpRequest,
+    payload: web::Payload,
+) -> actix_web::Result<T> {
+    let body = payload.to_bytes().await?;
+    let encoding = request
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .and_then(SyncEncoding::from_name);
+
+    let body = match encoding {
+        Some(encoding) => encoding.decode(&body).map_err(ErrorBadRequest)?,
+        None => body.to_vec(),
+    };
+
+    serde_json::from_slice(&body).map_err(ErrorBadRequest)
+}
+
+#[get("/sync/v5/site")]
+async fn site_info(
+    request: HttpRequest,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    let site_id = authenticate(&request, &mock_central)?;
+    // Mock central server also serves v6 sync
+    let central_server_url = {
+        let connection_info = request.connection_info();
+        format!("{}://{}/", connection_info.scheme(), connection_info.host())
+    };
+
+    Ok(HttpResponse::Ok().json(mock_central.state().site_info(site_id, &central_server_url)))
+}
+
+#[get("/sync/v5/site_status")]
+async fn site_status(
+    request: HttpRequest,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    authenticate(&request, &mock_central)?;
+
+    // Records are integrated (queued) as they are pushed
+    Ok(HttpResponse::Ok().json(SiteStatusV5 {
+        code: SiteStatusCodeV5::Idle,
+        message: "Idle".to_string(),
+        data: None,
+    }))
+}
+
+#[post("/sync/v5/initialise")]
+async fn initialise(
+    request: HttpRequest,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    let site_id = authenticate(&request, &mock_central)?;
+
+    Ok(HttpResponse::Ok().json(mock_central.state().initialise(site_id)))
+}
+
+#[derive(Deserialize)]
+struct CentralRecordsQuery {
+    cursor: u64,
+    limit: u32,
+}
+
+#[get("/sync/v5/central_records")]
+async fn central_records(
+    request: HttpRequest,
+    query: web::Query<CentralRecordsQuery>,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    authenticate(&request, &mock_central)?;
+
+    Ok(HttpResponse::Ok().json(
+        mock_central
+            .state()
+            .central_batch(query.cursor, query.limit),
+    ))
+}
+
+#[derive(Deserialize)]
+struct QueuedRecordsQuery {
+    limit: u32,
+}
+
+#[get("/sync/v5/queued_records")]
+async fn queued_records(
+    request: HttpRequest,
+    query: web::Query<QueuedRecordsQuery>,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    let site_id = authenticate(&request, &mock_central)?;
+
+    Ok(HttpResponse::Ok().json(mock_central.state().queued_batch(site_id, query.limit)))
+}
+
+#[post("/sync/v5/acknowledged_records")]
+async fn acknowledged_records(
+    request: HttpRequest,
+    payload: web::Payload,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    let site_id = authenticate(&request, &mock_central)?;
+    let RemoteSyncPullAcknowledgementV5 { sync_ids } = json_body(&request, payload).await?;
+
+    mock_central.state().acknowledge(site_id, &sync_ids);
+    Ok(HttpResponse::Ok().json(json!({})))
+}
+
+#[post("/sync/v5/queued_records")]
+async fn push_queued_records(
+    request: HttpRequest,
+    payload: web::Payload,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    let site_id = authenticate(&request, &mock_central)?;
+    let RemoteSyncBatchV5 { data, .. } = json_body(&request, payload).await?;
+
+    mock_central.state().push(site_id, data);
+    Ok(HttpResponse::Ok().json(RemotePushResponseV5 {
+        integration_started: true,
+    }))
+}
+
+#[post("/sync/v5/test/upsert")]
+async fn test_upsert(
+    request: HttpRequest,
+    payload: web::Payload,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    authenticate(&request, &mock_central)?;
+    let records = json_body(&request, payload).await?;
+
+    mock_central
+        .upsert_records(records)
+        .map_err(ErrorBadRequest)?;
+    Ok(HttpResponse::Ok().json(json!({})))
+}
+
+#[post("/sync/v5/test/delete")]
+async fn test_delete(
+    request: HttpRequest,
+    payload: web::Payload,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    authenticate(&request, &mock_central)?;
+    let record_ids = json_body(&request, payload).await?;
+
+    mock_central
+        .delete_records(record_ids)
+        .map_err(ErrorBadRequest)?;
+    Ok(HttpResponse::Ok().json(json!({})))
+}
+
+#[derive(Deserialize)]
+struct CreateSiteInput {
+    #[serde(rename = "visibleNameIds", default)]
+    visible_name_ids: Vec<String>,
+}
+
+#[post("/sync/v5/test/create_site")]
+async fn test_create_site(
+    request: HttpRequest,
+    payload: web::Payload,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    authenticate(&request, &mock_central)?;
+    let CreateSiteInput { visible_name_ids } = json_body(&request, payload).await?;
+
+    let site = mock_central
+        .create_site(None, None, visible_name_ids)
+        .map_err(ErrorBadRequest)?;
+    Ok(HttpResponse::Ok().json(json!({
+        "site": {
+            "ID": site.id,
+            "site_ID": site.site_id,
+            "name": site.name,
+            "password": site.password_sha256,
+        },
+        "store": {
+            "ID": site.store_id,
+            "name_ID": site.name_id,
+        }
+    })))
+}
+
+#[post("/pull")]
+async fn pull_v6(
+    request: HttpRequest,
+    payload: web::Payload,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    let SyncPullRequestV6 {
+        cursor,
+        batch_size,
+        sync_v5_settings,
+        is_initialised,
+        transactional_sync_v6,
+    } = json_body(&request, payload).await?;
+
+    let result = mock_central
+        .site_id_v6(&sync_v5_settings)
+        .and_then(|site_id| {
+            pull_for_site(
+                &mock_central.service_provider,
+                site_id,
+                cursor,
+                batch_size,
+                is_initialised,
+                transactional_sync_v6,
+            )
+        });
+    let response = match result {
+        Ok(batch) => SyncPullResponseV6::Data(batch),
+        Err(error) => SyncPullResponseV6::Error(error),
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[post("/push")]
+async fn push_v6(
+    request: HttpRequest,
+    payload: web::Payload,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    let SyncPushRequestV6 {
+        batch,
+        sync_v5_settings,
+    } = json_body(&request, payload).await?;
+
+    let result = mock_central
+        .site_id_v6(&sync_v5_settings)
+        .and_then(|site_id| push_for_site(mock_central.service_provider.clone(), site_id, batch));
+    let response = match result {
+        Ok(result) => SyncPushResponseV6::Data(result),
+        Err(error) => SyncPushResponseV6::Error(error),
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[post("/site_status")]
+async fn site_status_v6(
+    request: HttpRequest,
+    payload: web::Payload,
+    mock_central: Data<MockCentral>,
+) -> actix_web::Result<HttpResponse> {
+    let SiteStatusRequestV6 { sync_v5_settings } = json_body(&request, payload).await?;
+
+    let response = match mock_central.site_id_v6(&sync_v5_settings) {
+        Ok(site_id) => SiteStatusResponseV6::Data(site_status_for_site(site_id)),
+        Err(error) => SiteStatusResponseV6::Error(error),
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
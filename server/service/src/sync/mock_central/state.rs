@@ -0,0 +1,460 @@
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+use util::uuid::uuid;
+
+use crate::sync::api::{
+    CentralSyncBatchV5, CentralSyncRecordV5, CommonSyncRecord, InitialisationStatus,
+    RemoteSyncBatchV5, RemoteSyncRecordV5, SiteInfoV5, SyncAction,
+};
+
+use super::templates::with_defaults;
+
+/// Site id of the mock central server itself, remote sites start from the next id
+const CENTRAL_SITE_ID: i32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockSite {
+    pub id: String,
+    pub site_id: i32,
+    pub name: String,
+    pub password_sha256: String,
+    pub store_id: String,
+    pub name_id: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub(super) enum MockAuthError {
+    SiteNameNotFound,
+    IncorrectPassword,
+}
+
+struct SiteState {
+    site: MockSite,
+    is_initialised: bool,
+    queue: Vec<RemoteSyncRecordV5>,
+}
+
+struct RemoteRecord {
+    record: CommonSyncRecord,
+    /// Site that pushed the record
+    source_site_id: i32,
+    /// Site of the other party of a transfer, record is dispatched to this site
+    destination_site_id: Option<i32>,
+}
+
+/// Legacy mSupply central server state, kept in memory
+#[derive(Default)]
+pub(super) struct MockCentralState {
+    sites: Vec<SiteState>,
+    central_log: Vec<CentralSyncRecordV5>,
+    /// Latest version of central records, by table name and record id
+    central_records: BTreeMap<(String, String), Value>,
+    /// Records pushed by remote sites, in the order they were first received
+    remote_records: Vec<RemoteRecord>,
+    next_sync_id: u64,
+}
+
+impl MockCentralState {
+    /// Adds site with a store (and a name for the store) that is visible to `visible_name_ids`,
+    /// returns central records that were added
+    pub(super) fn add_site(
+        &mut self,
+        name: &str,
+        password_sha256: &str,
+        visible_name_ids: Vec<String>,
+    ) -> (MockSite, Vec<CommonSyncRecord>) {
+        let site_id = CENTRAL_SITE_ID + 1 + self.sites.len() as i32;
+        let site = MockSite {
+            id: uuid(),
+            site_id,
+            name: name.to_string(),
+            password_sha256: password_sha256.to_string(),
+            store_id: uuid(),
+            name_id: uuid(),
+        };
+        let code = format!("S{}", site_id);
+
+        let mut records = self.upsert_central(
+            "name",
+            vec![json!({
+                "ID": site.name_id,
+                "name": name,
+                "code": code,
+                "type": "store",
+            })],
+        );
+        records.extend(self.upsert_central(
+            "store",
+            vec![json!({
+                "ID": site.store_id,
+                "name": name,
+                "code": code,
+                "name_ID": site.name_id,
+                "sync_id_remote_site": site_id,
+            })],
+        ));
+        let name_store_joins = visible_name_ids
+            .into_iter()
+            .map(|name_id| {
+                json!({
+                    "ID": uuid(),
+                    "name_ID": name_id,
+                    "store_ID": site.store_id,
+                })
+            })
+            .collect();
+        records.extend(self.upsert_central("name_store_join", name_store_joins));
+
+        self.sites.push(SiteState {
+            site: site.clone(),
+            is_initialised: false,
+            queue: Vec::new(),
+        });
+
+        (site, records)
+    }
+
+    pub(super) fn authenticate(
+        &self,
+        username: &str,
+        password_sha256: &str,
+    ) -> Result<i32, MockAuthError> {
+        let site = self
+            .sites
+            .iter()
+            .find(|state| state.site.name == username)
+            .ok_or(MockAuthError::SiteNameNotFound)?;
+
+        if site.site.password_sha256 != password_sha256 {
+            return Err(MockAuthError::IncorrectPassword);
+        }
+
+        Ok(site.site.site_id)
+    }
+
+    pub(super) fn site_info(&self, site_id: i32, central_server_url: &str) -> SiteInfoV5 {
+        let site = self.site(site_id);
+
+        SiteInfoV5 {
+            id: site.site.id.clone(),
+            site_id,
+            initialisation_status: match site.is_initialised {
+                true => InitialisationStatus::Completed,
+                false => InitialisationStatus::New,
+            },
+            central_server_url: central_server_url.to_string(),
+            is_central_server: false,
+        }
+    }
+
+    /// Records are upserted with default values for missing fields (for known tables)
+    pub(super) fn upsert_central(
+        &mut self,
+        table_name: &str,
+        records: Vec<Value>,
+    ) -> Vec<CommonSyncRecord> {
+        records
+            .into_iter()
+            .map(|record| {
+                let record = with_defaults(table_name, record);
+                let record_id = record["ID"].as_str().unwrap_or_default().to_string();
+                let key = (table_name.to_string(), record_id.clone());
+                let action = match self.central_records.contains_key(&key) {
+                    true => SyncAction::Update,
+                    false => SyncAction::Insert,
+                };
+                self.central_records.insert(key, record.clone());
+
+                self.log_central(CommonSyncRecord {
+                    table_name: table_name.to_string(),
+                    record_id,
+                    action,
+                    record_data: record,
+                })
+            })
+            .collect()
+    }
+
+    pub(super) fn delete_central(
+        &mut self,
+        table_name: &str,
+        record_ids: Vec<String>,
+    ) -> Vec<CommonSyncRecord> {
+        record_ids
+            .into_iter()
+            .map(|record_id| {
+                self.central_records
+                    .remove(&(table_name.to_string(), record_id.clone()));
+
+                self.log_central(CommonSyncRecord {
+                    table_name: table_name.to_string(),
+                    record_id,
+                    action: SyncAction::Delete,
+                    record_data: json!({}),
+                })
+            })
+            .collect()
+    }
+
+    fn log_central(&mut self, record: CommonSyncRecord) -> CommonSyncRecord {
+        self.central_log.push(CentralSyncRecordV5 {
+            cursor: self.central_log.len() as u64 + 1,
+            record: record.clone(),
+        });
+        record
+    }
+
+    /// Central records after `cursor`
+    pub(super) fn central_batch(&self, cursor: u64, limit: u32) -> CentralSyncBatchV5 {
+        CentralSyncBatchV5 {
+            max_cursor: self.central_log.len() as u64,
+            data: self
+                .central_log
+                .iter()
+                .filter(|record| record.cursor > cursor)
+                .take(limit as usize)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Queues all records of the site (and records dispatched to the site) for remote pull
+    pub(super) fn initialise(&mut self, site_id: i32) -> RemoteSyncBatchV5 {
+        let records: Vec<CommonSyncRecord> = self
+            .remote_records
+            .iter()
+            .filter(|remote| {
+                remote.source_site_id == site_id || remote.destination_site_id == Some(site_id)
+            })
+            .map(|remote| remote.record.clone())
+            .collect();
+
+        let site = self.site_mut(site_id);
+        site.queue.clear();
+        site.is_initialised = true;
+        for record in records {
+            self.enqueue(site_id, record);
+        }
+
+        RemoteSyncBatchV5 {
+            queue_length: self.site(site_id).queue.len() as u64,
+            data: Vec::new(),
+        }
+    }
+
+    pub(super) fn queued_batch(&self, site_id: i32, limit: u32) -> RemoteSyncBatchV5 {
+        let queue = &self.site(site_id).queue;
+
+        RemoteSyncBatchV5 {
+            queue_length: queue.len() as u64,
+            data: queue
+                .iter()
+                .take(limit as usize)
+                .map(|record| RemoteSyncRecordV5 {
+                    sync_id: record.sync_id.clone(),
+                    record: record.record.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    pub(super) fn acknowledge(&mut self, site_id: i32, sync_ids: &[String]) {
+        self.site_mut(site_id)
+            .queue
+            .retain(|record| !sync_ids.contains(&record.sync_id));
+    }
+
+    /// Stores pushed records and dispatches transfer records to the site of the other party
+    pub(super) fn push(&mut self, site_id: i32, records: Vec<RemoteSyncRecordV5>) {
+        for RemoteSyncRecordV5 { record, .. } in records {
+            let existing = self.remote_records.iter().position(|remote| {
+                remote.record.table_name == record.table_name
+                    && remote.record.record_id == record.record_id
+            });
+            let destination_site_id = match (&record.action, existing) {
+                (SyncAction::Delete, Some(index)) => self.remote_records[index].destination_site_id,
+                _ => self.destination_site_id(&record),
+            }
+            .filter(|destination_site_id| *destination_site_id != site_id);
+
+            if let Some(destination_site_id) = destination_site_id {
+                self.enqueue(destination_site_id, record.clone());
+            }
+
+            let remote = RemoteRecord {
+                record,
+                source_site_id: site_id,
+                destination_site_id,
+            };
+            match (&remote.record.action, existing) {
+                (SyncAction::Delete, Some(index)) => {
+                    self.remote_records.remove(index);
+                }
+                (SyncAction::Delete, None) => {}
+                (_, Some(index)) => self.remote_records[index] = remote,
+                (_, None) => self.remote_records.push(remote),
+            }
+        }
+    }
+
+    /// Transfer records go to the site of the other party store (`name_ID`), lines go
+    /// where their requisition or invoice went
+    fn destination_site_id(&self, record: &CommonSyncRecord) -> Option<i32> {
+        let data = &record.record_data;
+
+        match record.table_name.as_str() {
+            "requisition" | "transact" => self.site_id_for_name(data["name_ID"].as_str()?),
+            "requisition_line" => {
+                self.remote_destination("requisition", data["requisition_ID"].as_str()?)
+            }
+            "trans_line" => self.remote_destination("transact", data["transaction_ID"].as_str()?),
+            _ => None,
+        }
+    }
+
+    fn site_id_for_name(&self, name_id: &str) -> Option<i32> {
+        self.central_records
+            .iter()
+            .find(|((table_name, _), store)| table_name == "store" && store["name_ID"] == name_id)
+            .and_then(|(_, store)| store["sync_id_remote_site"].as_i64())
+            .map(|site_id| site_id as i32)
+    }
+
+    fn remote_destination(&self, table_name: &str, record_id: &str) -> Option<i32> {
+        self.remote_records
+            .iter()
+            .find(|remote| {
+                remote.record.table_name == table_name && remote.record.record_id == record_id
+            })
+            .and_then(|remote| remote.destination_site_id)
+    }
+
+    fn enqueue(&mut self, site_id: i32, record: CommonSyncRecord) {
+        self.next_sync_id += 1;
+        let sync_id = self.next_sync_id.to_string();
+        self.site_mut(site_id)
+            .queue
+            .push(RemoteSyncRecordV5 { sync_id, record });
+    }
+
+    /// Sites are only looked up by id after authentication
+    fn site(&self, site_id: i32) -> &SiteState {
+        self.sites
+            .iter()
+            .find(|state| state.site.site_id == site_id)
+            .expect("Mock site should exist")
+    }
+
+    fn site_mut(&mut self, site_id: i32) -> &mut SiteState {
+        self.sites
+            .iter_mut()
+            .find(|state| state.site.site_id == site_id)
+            .expect("Mock site should exist")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::sync::api::{CommonSyncRecord, RemoteSyncRecordV5, SyncAction};
+
+    use super::{MockAuthError, MockCentralState};
+
+    fn pushed(
+        table_name: &str,
+        record_id: &str,
+        record_data: serde_json::Value,
+    ) -> RemoteSyncRecordV5 {
+        RemoteSyncRecordV5 {
+            sync_id: "1".to_string(),
+            record: CommonSyncRecord {
+                table_name: table_name.to_string(),
+                record_id: record_id.to_string(),
+                action: SyncAction::Insert,
+                record_data,
+            },
+        }
+    }
+
+    fn queued_record_ids(state: &MockCentralState, site_id: i32) -> Vec<String> {
+        state
+            .queued_batch(site_id, 100)
+            .data
+            .into_iter()
+            .map(|record| record.record.record_id)
+            .collect()
+    }
+
+    #[test]
+    fn mock_central_state() {
+        let mut state = MockCentralState::default();
+        let (site_a, records) = state.add_site("site_a", "hash_a", vec![]);
+        let (site_b, _) = state.add_site("site_b", "hash_b", vec![site_a.name_id.clone()]);
+        // name, store
+        assert_eq!(records.len(), 2);
+        assert_eq!(site_b.site_id, site_a.site_id + 1);
+
+        assert_eq!(state.authenticate("site_a", "hash_a"), Ok(site_a.site_id));
+        assert_eq!(
+            state.authenticate("site_a", "hash_b"),
+            Err(MockAuthError::IncorrectPassword)
+        );
+        assert_eq!(
+            state.authenticate("site_c", "hash_a"),
+            Err(MockAuthError::SiteNameNotFound)
+        );
+
+        // Central records, name + store for each site and a name_store_join
+        let batch = state.central_batch(0, 3);
+        assert_eq!(batch.max_cursor, 5);
+        assert_eq!(batch.data.len(), 3);
+        assert_eq!(state.central_batch(3, 10).data.len(), 2);
+        assert_eq!(state.central_batch(5, 10).data.len(), 0);
+
+        // Transfer and its lines are dispatched to the other party
+        state.push(
+            site_a.site_id,
+            vec![
+                pushed(
+                    "requisition",
+                    "requisition_a",
+                    json!({"store_ID": site_a.store_id, "name_ID": site_b.name_id}),
+                ),
+                pushed(
+                    "requisition_line",
+                    "line_a",
+                    json!({"requisition_ID": "requisition_a"}),
+                ),
+                pushed(
+                    "location",
+                    "location_a",
+                    json!({"store_ID": site_a.store_id}),
+                ),
+            ],
+        );
+        assert_eq!(
+            queued_record_ids(&state, site_b.site_id),
+            vec!["requisition_a", "line_a"]
+        );
+        assert!(queued_record_ids(&state, site_a.site_id).is_empty());
+
+        let sync_ids: Vec<String> = state
+            .queued_batch(site_b.site_id, 1)
+            .data
+            .into_iter()
+            .map(|record| record.sync_id)
+            .collect();
+        state.acknowledge(site_b.site_id, &sync_ids);
+        assert_eq!(queued_record_ids(&state, site_b.site_id), vec!["line_a"]);
+
+        // Initialisation queues own records and records dispatched to the site
+        assert_eq!(state.initialise(site_a.site_id).queue_length, 3);
+        assert_eq!(
+            queued_record_ids(&state, site_a.site_id),
+            vec!["requisition_a", "line_a", "location_a"]
+        );
+        assert_eq!(state.initialise(site_b.site_id).queue_length, 2);
+    }
+}
@@ -0,0 +1,96 @@
+use serde_json::{json, Value};
+
+/// Default field values for central records upserted through the test endpoint, mSupply fills
+/// missing fields of test records with defaults of the 4D table (only fields required by
+/// translators are listed here, records of other tables should be complete)
+fn template(table_name: &str) -> Option<Value> {
+    let template = match table_name {
+        "name" => json!({
+            "name": "",
+            "code": "",
+            "type": "facility",
+            "customer": true,
+            "supplier": true,
+            "supplying_store_id": "",
+            "first": "",
+            "last": "",
+            "female": false,
+            "date_of_birth": "0000-00-00",
+            "phone": "",
+            "charge code": "",
+            "comment": "",
+            "country": "",
+            "bill_address1": "",
+            "bill_address2": "",
+            "email": "",
+            "url": "",
+            "manufacturer": false,
+            "donor": false,
+            "hold": false,
+            "created_date": "0000-00-00",
+            "national_health_number": "",
+            "isDeceased": false,
+            "om_created_datetime": "",
+            "om_gender": "",
+            "om_date_of_death": "",
+            "custom_data": null
+        }),
+        "store" => json!({
+            "name": "",
+            "code": "",
+            "name_ID": "",
+            "sync_id_remote_site": 1,
+            "logo": "",
+            "store_mode": "store",
+            "created_date": "0000-00-00"
+        }),
+        "name_store_join" => json!({
+            "inactive": false,
+            "spare_Category_ID": 0,
+            "spare_Category_optional2_id": 0,
+            "spare_Category_optional_id": 0
+        }),
+        "item" => json!({
+            "item_name": "",
+            "code": "",
+            "unit_ID": "",
+            "type_of": "general",
+            "default_pack_size": 1
+        }),
+        _ => return None,
+    };
+
+    Some(template)
+}
+
+/// Record with missing fields set to table defaults
+pub(super) fn with_defaults(table_name: &str, record: Value) -> Value {
+    let (Some(Value::Object(mut result)), Value::Object(fields)) = (template(table_name), &record)
+    else {
+        return record;
+    };
+
+    for (key, value) in fields {
+        result.insert(key.clone(), value.clone());
+    }
+
+    Value::Object(result)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::with_defaults;
+
+    #[test]
+    fn mock_central_record_defaults() {
+        let item = with_defaults("item", json!({"ID": "item_a", "code": "A"}));
+        assert_eq!(item["ID"], json!("item_a"));
+        assert_eq!(item["code"], json!("A"));
+        assert_eq!(item["type_of"], json!("general"));
+
+        let unit = json!({"ID": "unit_a"});
+        assert_eq!(with_defaults("unit", unit.clone()), unit);
+    }
+}
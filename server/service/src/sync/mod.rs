@@ -1,18 +1,26 @@
 #[cfg(test)]
 pub(crate) mod test;
 
+pub mod adaptive_sync;
 pub mod api;
 pub mod api_v6;
 pub(crate) mod central_data_synchroniser;
 pub(crate) mod central_data_synchroniser_v6;
+pub mod conflicts;
 pub mod file_sync_driver;
 pub mod file_synchroniser;
 mod integrate_document;
+pub mod integration_errors;
+#[cfg(any(test, feature = "mock-central"))]
+pub mod mock_central;
+pub mod peer_sync;
 pub(crate) mod remote_data_synchroniser;
 pub mod settings;
 pub mod site_info;
+pub mod store_backfill;
 mod sync_buffer;
 pub mod sync_on_central;
+pub mod sync_package;
 pub(crate) mod sync_serde;
 pub mod sync_status;
 pub mod sync_user;
@@ -26,8 +34,8 @@ use std::sync::RwLock;
 
 use log::info;
 use repository::{
-    ChangelogFilter, EqualFilter, KeyValueStoreRepository, RepositoryError, StorageConnection,
-    Store, StoreFilter, StoreRepository,
+    ChangelogFilter, ChangelogTableName, EqualFilter, KeyType, KeyValueStoreRepository,
+    RepositoryError, StorageConnection, Store, StoreFilter, StoreRepository,
 };
 
 use thiserror::Error;
@@ -53,6 +61,37 @@ pub(crate) fn get_sync_push_changelogs_filter(
     ))
 }
 
+/// Site syncs transactional records with omSupply central, see `SyncSettings::transactional_sync_v6`
+pub(crate) fn is_transactional_sync_v6(
+    connection: &StorageConnection,
+) -> Result<bool, RepositoryError> {
+    Ok(KeyValueStoreRepository::new(connection)
+        .get_bool(KeyType::SettingsSyncTransactionalV6)?
+        .unwrap_or(false))
+}
+
+/// Push filter for legacy central or omSupply central, transactional records are only pushed to
+/// one of them depending on `SyncSettings::transactional_sync_v6`
+pub(crate) fn get_central_push_changelogs_filter(
+    connection: &StorageConnection,
+    is_omsupply_central: bool,
+) -> Result<Option<ChangelogFilter>, GetActiveStoresOnSiteError> {
+    let filter = get_sync_push_changelogs_filter(connection)?;
+    let transactional_sync_v6 =
+        is_transactional_sync_v6(connection).map_err(GetActiveStoresOnSiteError::DatabaseError)?;
+
+    if transactional_sync_v6 == is_omsupply_central {
+        return Ok(filter);
+    }
+
+    Ok(filter.map(|filter| {
+        filter.table_name(EqualFilter {
+            not_equal_all: Some(ChangelogTableName::transactional_sync_tables()),
+            ..Default::default()
+        })
+    }))
+}
+
 #[derive(Error, Debug)]
 pub(crate) enum GetActiveStoresOnSiteError {
     #[error("Database error while getting active store on site")]
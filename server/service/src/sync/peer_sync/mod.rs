@@ -0,0 +1,501 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use repository::{
+    ChangelogFilter, ChangelogRepository, ChangelogTableName, EqualFilter, KeyType,
+    KeyValueStoreRepository, RepositoryError, StorageConnection, StoreFilter, StoreRepository,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use util::uuid::uuid;
+
+use crate::{
+    service_provider::ServiceProvider,
+    sync::{
+        api::ParsingSyncRecordError,
+        api_v6::{SyncBatchV6, SyncRecordV6},
+        is_initialised,
+        settings::PeerSyncSettings,
+        translations::{
+            translate_changelogs_to_sync_records, PushTranslationError, ToSyncRecordTranslationType,
+        },
+        ActiveStoresOnSite, GetActiveStoresOnSiteError,
+    },
+};
+
+mod pinned_certificate;
+mod synchroniser;
+pub use synchroniser::{PeerSyncDriver, Peers};
+
+/// Version of the peer sync message format
+const PEER_SYNC_VERSION: u32 = 2;
+/// Route of peer pull requests, relative to server url
+pub const PEER_SYNC_PULL_ROUTE: &str = "peer/sync/pull";
+/// Route of the signed certificate fingerprint of a peer, relative to server url
+pub const PEER_SYNC_CERTIFICATE_ROUTE: &str = "peer/sync/certificate";
+/// Messages with a timestamp further than this from the current time are rejected
+/// (clocks of servers in a facility are expected to be roughly in sync)
+const MAX_MESSAGE_AGE_SECONDS: i64 = 5 * 60;
+
+/// LAN peer sync, for transfers between sites in one facility while central server is unreachable.
+///
+/// * Peers are found with DNS-SD discovery (or configured `peer_urls`)
+/// * A site pulls transfer records (invoices and requisitions with their lines) that a peer created
+/// for the stores of the site, as omSupply (v6) records, the same records are later also received
+/// from central server (upserted by id, so there is no duplication)
+/// * Records are integrated with `source_site_id` of the peer, they are not pushed to central from
+/// the receiving site, and transfer processors create the other side of the transfer as usual
+///
+/// Messages are json, signed with HMAC-SHA256 using the facility `secret` as the key. Requests
+/// have a nonce and timestamp and are only answered once, responses echo the nonce, requesting
+/// site and cursor. The https certificate of the peer is pinned to the fingerprint the peer
+/// signed (`PeerCertificate`), so messages can't be read by other devices on the network
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PeerSyncMessage {
+    version: u32,
+    /// Hex encoded HMAC of `message`
+    signature: String,
+    /// Json of request or response, signed as is
+    message: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PeerPullRequest {
+    /// Site of the requesting peer
+    pub(crate) site_id: i32,
+    /// Pull cursors of the requesting peer by peer site id, peer uses the cursor for its own site
+    /// (the requesting peer doesn't know which site is behind a discovered url)
+    pub(crate) cursors: Vec<(i32, u64)>,
+    pub(crate) batch_size: u32,
+    pub(crate) nonce: String,
+    /// Unix timestamp (seconds)
+    pub(crate) timestamp: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PeerPullResponse {
+    /// Site of the responding peer
+    pub(crate) site_id: i32,
+    /// `PeerPullRequest.site_id` of the request
+    pub(crate) requesting_site_id: i32,
+    /// `PeerPullRequest.nonce` of the request
+    pub(crate) nonce: String,
+    /// Cursor of the request the batch starts from
+    pub(crate) cursor: u64,
+    /// Unix timestamp (seconds)
+    pub(crate) timestamp: i64,
+    pub(crate) batch: SyncBatchV6,
+}
+
+/// Fingerprint of the https certificate of a peer, served unauthenticated (the fingerprint is
+/// signed) and pinned by the requesting peer before pull requests are sent
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PeerCertificate {
+    /// Hex encoded SHA-256 of the certificate (DER)
+    pub(crate) fingerprint: String,
+}
+
+/// Nonces of peer pull requests received within `MAX_MESSAGE_AGE_SECONDS`, each request is only
+/// answered once
+#[derive(Debug, Default)]
+pub struct PeerSyncNonces(Mutex<HashMap<String, i64>>);
+
+impl PeerSyncNonces {
+    /// False if the nonce was already used, expired nonces are removed
+    fn use_nonce(&self, nonce: &str, timestamp: i64, now: i64) -> bool {
+        let mut nonces = self.0.lock().unwrap();
+        nonces.retain(|_, timestamp| now - *timestamp <= MAX_MESSAGE_AGE_SECONDS);
+        nonces.insert(nonce.to_string(), timestamp).is_none()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum PeerSyncError {
+    #[error("Peer sync is not configured")]
+    NotConfigured,
+    #[error("Site is not initialised")]
+    NotInitialised,
+    #[error("Site id is not set in database")]
+    SiteIdNotSet,
+    #[error("Invalid peer sync message")]
+    InvalidMessage(#[source] anyhow::Error),
+    #[error("Unsupported peer sync version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Peer sync signature doesn't match, peer is configured with a different secret")]
+    InvalidSignature,
+    #[error("Peer sync message timestamp {0} is too far from the current time")]
+    ExpiredMessage(i64),
+    #[error("Peer sync request was already answered")]
+    ReplayedMessage,
+    #[error("Peer sync response doesn't match the request: {0}")]
+    UnexpectedResponse(String),
+    #[error("Peer certificate can't be used")]
+    CertificateError(#[source] anyhow::Error),
+    #[error("Request to peer failed")]
+    RequestError(#[from] reqwest::Error),
+    #[error(transparent)]
+    DatabaseError(#[from] RepositoryError),
+    #[error(transparent)]
+    GetActiveStoresOnSiteError(#[from] GetActiveStoresOnSiteError),
+    #[error(transparent)]
+    PushTranslationError(#[from] PushTranslationError),
+    #[error(transparent)]
+    ParsingSyncRecordError(#[from] ParsingSyncRecordError),
+}
+
+/// Answers pull request of a peer, `body` and result are signed `PeerSyncMessage` json
+pub fn serve_peer_pull(
+    service_provider: &ServiceProvider,
+    settings: Option<&PeerSyncSettings>,
+    body: &str,
+) -> Result<String, PeerSyncError> {
+    let settings = settings.ok_or(PeerSyncError::NotConfigured)?;
+    if !is_initialised(service_provider) {
+        return Err(PeerSyncError::NotInitialised);
+    }
+    let request: PeerPullRequest = verify(&settings.secret, body)?;
+    let now = Utc::now().timestamp();
+    check_timestamp(request.timestamp, now)?;
+    if !service_provider
+        .peer_sync_nonces
+        .use_nonce(&request.nonce, request.timestamp, now)
+    {
+        return Err(PeerSyncError::ReplayedMessage);
+    }
+
+    let ctx = service_provider.basic_context()?;
+    let site_id = site_id(&ctx.connection)?;
+    let cursor = request
+        .cursors
+        .iter()
+        .find(|(peer_site_id, _)| *peer_site_id == site_id)
+        .map(|(_, cursor)| *cursor)
+        .unwrap_or(0);
+
+    let batch = pull_for_peer(&ctx.connection, request.site_id, cursor, request.batch_size)?;
+    log::info!(
+        "Sending {} transfer records to peer site {}",
+        batch.records.len(),
+        request.site_id
+    );
+
+    sign(
+        &settings.secret,
+        &PeerPullResponse {
+            site_id,
+            requesting_site_id: request.site_id,
+            nonce: request.nonce,
+            cursor,
+            timestamp: Utc::now().timestamp(),
+            batch,
+        },
+    )
+}
+
+/// Signed fingerprint of the https certificate (DER) of this server
+pub fn serve_peer_certificate(
+    settings: Option<&PeerSyncSettings>,
+    certificate_der: &[u8],
+) -> Result<String, PeerSyncError> {
+    let settings = settings.ok_or(PeerSyncError::NotConfigured)?;
+    sign(
+        &settings.secret,
+        &PeerCertificate {
+            fingerprint: certificate_fingerprint(certificate_der),
+        },
+    )
+}
+
+pub(crate) fn certificate_fingerprint(certificate_der: &[u8]) -> String {
+    hex::encode(Sha256::digest(certificate_der))
+}
+
+fn check_timestamp(timestamp: i64, now: i64) -> Result<(), PeerSyncError> {
+    match (now - timestamp).abs() <= MAX_MESSAGE_AGE_SECONDS {
+        true => Ok(()),
+        false => Err(PeerSyncError::ExpiredMessage(timestamp)),
+    }
+}
+
+impl PeerPullRequest {
+    pub(crate) fn new(site_id: i32, cursors: &HashMap<i32, u64>, batch_size: u32) -> Self {
+        Self {
+            site_id,
+            cursors: cursors.clone().into_iter().collect(),
+            batch_size,
+            nonce: uuid(),
+            timestamp: Utc::now().timestamp(),
+        }
+    }
+
+    /// Response must answer this request, for the cursor of the responding site, and must not
+    /// move the cursor back
+    pub(crate) fn check_response(&self, response: &PeerPullResponse) -> Result<(), PeerSyncError> {
+        let unexpected = |message: String| Err(PeerSyncError::UnexpectedResponse(message));
+        if response.nonce != self.nonce {
+            return unexpected("nonce".to_string());
+        }
+        if response.requesting_site_id != self.site_id {
+            return unexpected(format!("requesting site {}", response.requesting_site_id));
+        }
+        let cursor = self
+            .cursors
+            .iter()
+            .find(|(peer_site_id, _)| *peer_site_id == response.site_id)
+            .map(|(_, cursor)| *cursor)
+            .unwrap_or(0);
+        if response.cursor != cursor {
+            return unexpected(format!("cursor {}, expected {}", response.cursor, cursor));
+        }
+        let end_cursor = response.batch.end_cursor;
+        if end_cursor + 1 < cursor || (!response.batch.records.is_empty() && end_cursor < cursor) {
+            return unexpected(format!(
+                "end cursor {} is older than cursor {}",
+                end_cursor, cursor
+            ));
+        }
+        check_timestamp(response.timestamp, Utc::now().timestamp())
+    }
+}
+
+/// Transfer records created on this site for stores of `peer_site_id`
+pub(crate) fn pull_for_peer(
+    connection: &StorageConnection,
+    peer_site_id: i32,
+    cursor: u64,
+    batch_size: u32,
+) -> Result<SyncBatchV6, PeerSyncError> {
+    let peer_name_ids = StoreRepository::new(connection)
+        .query_by_filter(StoreFilter::new().site_id(EqualFilter::equal_to_i32(peer_site_id)))?
+        .into_iter()
+        .map(|store| store.name_row.id)
+        .collect();
+
+    let filter = ChangelogFilter::new()
+        .table_name(EqualFilter {
+            equal_any: Some(ChangelogTableName::transfer_sync_tables()),
+            ..Default::default()
+        })
+        .store_id(EqualFilter::equal_any(
+            ActiveStoresOnSite::get(connection)?.store_ids(),
+        ))
+        .name_id(EqualFilter::equal_any(peer_name_ids))
+        // Records received from other sites are sent by their own site
+        .is_sync_update(EqualFilter::equal_or_null_bool(false));
+
+    let changelog_repo = ChangelogRepository::new(connection);
+    let changelogs = changelog_repo.changelogs(cursor, batch_size, Some(filter.clone()))?;
+    let total_records = changelog_repo.count(cursor, Some(filter))?;
+    let end_cursor = changelogs
+        .last()
+        .map(|log| log.cursor as u64)
+        .unwrap_or(changelog_repo.latest_cursor()?);
+
+    let records = translate_changelogs_to_sync_records(
+        connection,
+        changelogs,
+        ToSyncRecordTranslationType::PushToOmSupplyCentral,
+    )?
+    .into_iter()
+    .map(SyncRecordV6::from)
+    .collect();
+
+    Ok(SyncBatchV6 {
+        end_cursor,
+        total_records,
+        records,
+        is_last_batch: total_records <= batch_size as u64,
+    })
+}
+
+fn site_id(connection: &StorageConnection) -> Result<i32, PeerSyncError> {
+    KeyValueStoreRepository::new(connection)
+        .get_i32(KeyType::SettingsSyncSiteId)?
+        .ok_or(PeerSyncError::SiteIdNotSet)
+}
+
+fn signature(secret: &str, message: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(message.as_bytes());
+    mac
+}
+
+fn sign<T: Serialize>(secret: &str, message: &T) -> Result<String, PeerSyncError> {
+    let message =
+        serde_json::to_string(message).map_err(|e| PeerSyncError::InvalidMessage(e.into()))?;
+    let signed = PeerSyncMessage {
+        version: PEER_SYNC_VERSION,
+        signature: hex::encode(signature(secret, &message).finalize().into_bytes()),
+        message,
+    };
+
+    serde_json::to_string(&signed).map_err(|e| PeerSyncError::InvalidMessage(e.into()))
+}
+
+fn verify<T: DeserializeOwned>(secret: &str, body: &str) -> Result<T, PeerSyncError> {
+    let signed: PeerSyncMessage =
+        serde_json::from_str(body).map_err(|e| PeerSyncError::InvalidMessage(e.into()))?;
+    if signed.version != PEER_SYNC_VERSION {
+        return Err(PeerSyncError::UnsupportedVersion(signed.version));
+    }
+
+    let signature_bytes =
+        hex::decode(&signed.signature).map_err(|_| PeerSyncError::InvalidSignature)?;
+    signature(secret, &signed.message)
+        .verify_slice(&signature_bytes)
+        .map_err(|_| PeerSyncError::InvalidSignature)?;
+
+    serde_json::from_str(&signed.message).map_err(|e| PeerSyncError::InvalidMessage(e.into()))
+}
+
+#[cfg(test)]
+mod test {
+    use repository::{
+        mock::{mock_store_a, mock_store_b, MockData, MockDataInserts},
+        test_db::setup_all_with_data,
+        InvoiceRow, InvoiceRowRepository, KeyValueStoreRow,
+    };
+    use util::inline_init;
+
+    use super::*;
+    use crate::sync::api::{CommonSyncRecord, SyncAction};
+
+    #[actix_rt::test]
+    async fn test_pull_for_peer() {
+        let (_, connection, _, _) = setup_all_with_data(
+            "test_pull_for_peer",
+            MockDataInserts::all(),
+            inline_init(|r: &mut MockData| {
+                r.key_value_store_rows = vec![inline_init(|r: &mut KeyValueStoreRow| {
+                    r.id = KeyType::SettingsSyncSiteId;
+                    r.value_int = Some(mock_store_a().site_id);
+                })]
+            }),
+        )
+        .await;
+
+        // Outbound shipment from store a (this site) to store b (peer site)
+        let invoice = inline_init(|r: &mut InvoiceRow| {
+            r.id = "peer_transfer_invoice".to_string();
+            r.store_id = mock_store_a().id;
+            r.name_link_id = mock_store_b().name_id;
+        });
+        InvoiceRowRepository::new(&connection)
+            .upsert_one(&invoice)
+            .unwrap();
+
+        let pulled_invoice_ids = |peer_site_id: i32| -> Vec<String> {
+            pull_for_peer(&connection, peer_site_id, 0, 100000)
+                .unwrap()
+                .records
+                .into_iter()
+                .filter(|r| r.record.table_name == "om_invoice")
+                .map(|r| r.record.record_id)
+                .collect()
+        };
+
+        assert!(pulled_invoice_ids(mock_store_b().site_id).contains(&invoice.id));
+        assert!(!pulled_invoice_ids(3).contains(&invoice.id));
+    }
+
+    #[test]
+    fn test_peer_sync_signature() {
+        let request = PeerPullRequest::new(1, &HashMap::from([(2, 10)]), 500);
+        let signed = sign("facility_secret", &request).unwrap();
+
+        assert_eq!(
+            verify::<PeerPullRequest>("facility_secret", &signed).unwrap(),
+            request
+        );
+        assert!(matches!(
+            verify::<PeerPullRequest>("other_secret", &signed),
+            Err(PeerSyncError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_peer_sync_replay() {
+        let nonces = PeerSyncNonces::default();
+        let now = Utc::now().timestamp();
+        assert!(nonces.use_nonce("nonce", now, now));
+        assert!(!nonces.use_nonce("nonce", now, now + 10));
+        // Expired nonces are removed, the request itself is rejected by its timestamp
+        assert!(nonces.use_nonce("nonce", now, now + MAX_MESSAGE_AGE_SECONDS + 1));
+
+        assert!(check_timestamp(now - 10, now).is_ok());
+        assert!(matches!(
+            check_timestamp(now - MAX_MESSAGE_AGE_SECONDS - 1, now),
+            Err(PeerSyncError::ExpiredMessage(_))
+        ));
+    }
+
+    #[test]
+    fn test_peer_pull_check_response() {
+        let request = PeerPullRequest::new(1, &HashMap::from([(2, 10)]), 500);
+        let response = |batch: SyncBatchV6| PeerPullResponse {
+            site_id: 2,
+            requesting_site_id: 1,
+            nonce: request.nonce.clone(),
+            cursor: 10,
+            timestamp: Utc::now().timestamp(),
+            batch,
+        };
+        let batch = |end_cursor: u64, records: Vec<SyncRecordV6>| SyncBatchV6 {
+            end_cursor,
+            records,
+            ..Default::default()
+        };
+        let record = || SyncRecordV6 {
+            cursor: 12,
+            record: CommonSyncRecord {
+                table_name: "invoice".to_string(),
+                record_id: "invoice".to_string(),
+                action: SyncAction::Update,
+                record_data: serde_json::Value::Null,
+            },
+        };
+
+        assert!(request
+            .check_response(&response(batch(12, vec![record()])))
+            .is_ok());
+        // Nothing new
+        assert!(request
+            .check_response(&response(batch(9, Vec::new())))
+            .is_ok());
+
+        let unexpected = |response: PeerPullResponse| {
+            matches!(
+                request.check_response(&response),
+                Err(PeerSyncError::UnexpectedResponse(_))
+            )
+        };
+        assert!(unexpected(PeerPullResponse {
+            nonce: "replayed".to_string(),
+            ..response(batch(12, vec![record()]))
+        }));
+        assert!(unexpected(PeerPullResponse {
+            requesting_site_id: 3,
+            ..response(batch(12, vec![record()]))
+        }));
+        assert!(unexpected(PeerPullResponse {
+            cursor: 0,
+            ..response(batch(12, vec![record()]))
+        }));
+        assert!(unexpected(response(batch(9, vec![record()]))));
+        assert!(unexpected(response(batch(5, Vec::new()))));
+        assert!(matches!(
+            request.check_response(&PeerPullResponse {
+                timestamp: 0,
+                ..response(batch(12, vec![record()]))
+            }),
+            Err(PeerSyncError::ExpiredMessage(0))
+        ));
+    }
+}
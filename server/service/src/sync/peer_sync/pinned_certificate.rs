@@ -0,0 +1,89 @@
+use std::{sync::Arc, time::Duration};
+
+use reqwest::Client;
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    ClientConfig, DigitallySignedStruct, Error, SignatureScheme,
+};
+
+use super::certificate_fingerprint;
+
+/// Accepts only the certificate with `fingerprint` (peers on LAN use self signed certificates,
+/// the fingerprint is signed by the peer)
+#[derive(Debug)]
+struct PinnedCertificateVerifier {
+    fingerprint: String,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertificateVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        match certificate_fingerprint(end_entity.as_ref()) == self.fingerprint {
+            true => Ok(ServerCertVerified::assertion()),
+            false => Err(Error::General(
+                "Peer certificate doesn't match the signed fingerprint".to_string(),
+            )),
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// Client that only connects to servers with the certificate `fingerprint`
+pub(super) fn pinned_client(fingerprint: String, timeout: Duration) -> anyhow::Result<Client> {
+    let provider = Arc::new(ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinnedCertificateVerifier {
+            fingerprint,
+            provider,
+        }))
+        .with_no_client_auth();
+
+    Ok(Client::builder()
+        .use_preconfigured_tls(config)
+        .timeout(timeout)
+        .build()?)
+}
@@ -0,0 +1,447 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use repository::{
+    KeyType, KeyValueStoreRepository, StorageConnection, SyncBufferRow, SyncBufferRowRepository,
+};
+use reqwest::{header::CONTENT_TYPE, Client, Url};
+use util::format_error;
+
+use crate::{
+    service_provider::ServiceProvider,
+    sync::{
+        api_v6::{SyncBatchV6, SyncRecordV6},
+        is_initialised,
+        settings::PeerSyncSettings,
+        synchroniser::integrate_and_translate_sync_buffer,
+    },
+};
+
+use super::{
+    pinned_certificate::pinned_client, sign, site_id, verify, PeerCertificate, PeerPullRequest,
+    PeerPullResponse, PeerSyncError, PEER_SYNC_CERTIFICATE_ROUTE, PEER_SYNC_PULL_ROUTE,
+};
+
+const PEER_SYNC_BATCH_SIZE: u32 = 500;
+const PEER_SYNC_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Urls of peers found with DNS-SD discovery, by hardware id
+#[derive(Clone, Default)]
+pub struct Peers {
+    urls: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl Peers {
+    pub fn set(&self, hardware_id: &str, url: &str) {
+        self.urls
+            .write()
+            .unwrap()
+            .insert(hardware_id.to_string(), url.to_string());
+    }
+
+    pub fn remove(&self, hardware_id: &str) {
+        self.urls.write().unwrap().remove(hardware_id);
+    }
+
+    fn urls(&self) -> Vec<String> {
+        self.urls.read().unwrap().values().cloned().collect()
+    }
+}
+
+/// Pulls transfer records from discovered and configured peers every
+/// `PeerSyncSettings.interval_seconds` (only when initialised)
+pub struct PeerSyncDriver {
+    settings: PeerSyncSettings,
+    peers: Peers,
+}
+
+impl PeerSyncDriver {
+    pub fn new(settings: PeerSyncSettings, peers: Peers) -> Self {
+        Self { settings, peers }
+    }
+
+    pub async fn run(self, service_provider: Arc<ServiceProvider>) {
+        loop {
+            tokio::time::sleep(Duration::from_secs(self.settings.interval_seconds)).await;
+
+            if !is_initialised(&service_provider) {
+                continue;
+            }
+
+            let mut urls = self.peers.urls();
+            urls.extend(self.settings.peer_urls.iter().cloned());
+            urls.sort();
+            urls.dedup();
+
+            let mut number_of_records = 0;
+            for url in urls {
+                match sync_with_peer(&service_provider, &self.settings.secret, &url).await {
+                    Ok(count) => number_of_records += count,
+                    // Peers are often unreachable (e.g. device turned off), sync continues with others
+                    Err(error) => {
+                        log::warn!("Peer sync with {} failed: {}", url, format_error(&error))
+                    }
+                }
+            }
+
+            if number_of_records > 0 {
+                let processors_trigger = &service_provider.processors_trigger;
+                processors_trigger.trigger_requisition_transfer_processors();
+                processors_trigger.trigger_invoice_transfer_processors();
+            }
+        }
+    }
+}
+
+/// Pulls and integrates transfer records for this site from peer at `url`, returns number of
+/// pulled records
+pub(crate) async fn sync_with_peer(
+    service_provider: &ServiceProvider,
+    secret: &str,
+    url: &str,
+) -> Result<usize, PeerSyncError> {
+    let url = Url::parse(url).map_err(|e| PeerSyncError::InvalidMessage(e.into()))?;
+    let client = client(&url, secret).await?;
+    let url = url
+        .join(PEER_SYNC_PULL_ROUTE)
+        .map_err(|e| PeerSyncError::InvalidMessage(e.into()))?;
+    let ctx = service_provider.basic_context()?;
+    let site_id = site_id(&ctx.connection)?;
+    let mut cursors = get_cursors(&ctx.connection)?;
+
+    let mut peer_site_id = None;
+    let mut number_of_records = 0;
+    loop {
+        let request = PeerPullRequest::new(site_id, &cursors, PEER_SYNC_BATCH_SIZE);
+        let response = post(&client, &url, secret, &request).await?;
+        request.check_response(&response)?;
+        let PeerPullResponse {
+            site_id: responding_site_id,
+            batch:
+                SyncBatchV6 {
+                    end_cursor,
+                    records,
+                    is_last_batch,
+                    ..
+                },
+            ..
+        } = response;
+
+        if responding_site_id == site_id {
+            // Discovered ourselves (e.g. via another network interface)
+            return Ok(0);
+        }
+        peer_site_id = Some(responding_site_id);
+        number_of_records += records.len();
+
+        let buffer_rows = records
+            .into_iter()
+            .map(|SyncRecordV6 { record, .. }| {
+                Ok(SyncBufferRow {
+                    store_id: record.store_id(),
+                    ..record.to_buffer_row(Some(responding_site_id))?
+                })
+            })
+            .collect::<Result<Vec<_>, PeerSyncError>>()?;
+        SyncBufferRowRepository::new(&ctx.connection).upsert_many(&buffer_rows)?;
+
+        cursors.insert(responding_site_id, end_cursor + 1);
+        if is_last_batch {
+            break;
+        }
+    }
+
+    // Integrate once all batches are received (lines can be in an earlier batch than their
+    // parents), cursor is only saved after integration
+    if let Some(peer_site_id) = peer_site_id {
+        integrate_and_translate_sync_buffer(&ctx.connection, true, None, Some(peer_site_id))?;
+        set_cursors(&ctx.connection, &cursors)?;
+    }
+    log::info!(
+        "Pulled {} transfer records from peer {}",
+        number_of_records,
+        url
+    );
+
+    Ok(number_of_records)
+}
+
+/// Client for the peer at `url`, https peers must use the certificate they signed the fingerprint
+/// of (http is only used by peers in development mode)
+async fn client(url: &Url, secret: &str) -> Result<Client, PeerSyncError> {
+    if url.scheme() != "https" {
+        return Ok(Client::builder()
+            .timeout(PEER_SYNC_REQUEST_TIMEOUT)
+            .build()?);
+    }
+
+    let certificate_url = url
+        .join(PEER_SYNC_CERTIFICATE_ROUTE)
+        .map_err(|e| PeerSyncError::InvalidMessage(e.into()))?;
+    let response = Client::builder()
+        // Peers on LAN use self signed certificates, the fingerprint is authenticated by signature
+        .danger_accept_invalid_certs(true)
+        .timeout(PEER_SYNC_REQUEST_TIMEOUT)
+        .build()?
+        .get(certificate_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let PeerCertificate { fingerprint } = verify(secret, &response)?;
+
+    pinned_client(fingerprint, PEER_SYNC_REQUEST_TIMEOUT).map_err(PeerSyncError::CertificateError)
+}
+
+async fn post(
+    client: &Client,
+    url: &Url,
+    secret: &str,
+    request: &PeerPullRequest,
+) -> Result<PeerPullResponse, PeerSyncError> {
+    let response = client
+        .post(url.clone())
+        .header(CONTENT_TYPE, "application/json")
+        .body(sign(secret, request)?)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    verify(secret, &response)
+}
+
+fn get_cursors(connection: &StorageConnection) -> Result<HashMap<i32, u64>, PeerSyncError> {
+    let cursors = KeyValueStoreRepository::new(connection).get_string(KeyType::PeerSyncCursors)?;
+
+    Ok(cursors
+        .and_then(|cursors| serde_json::from_str::<Vec<(i32, u64)>>(&cursors).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .collect())
+}
+
+fn set_cursors(
+    connection: &StorageConnection,
+    cursors: &HashMap<i32, u64>,
+) -> Result<(), PeerSyncError> {
+    let cursors: Vec<(i32, u64)> = cursors.clone().into_iter().collect();
+    let cursors =
+        serde_json::to_string(&cursors).map_err(|e| PeerSyncError::InvalidMessage(e.into()))?;
+    KeyValueStoreRepository::new(connection).set_string(KeyType::PeerSyncCursors, Some(cursors))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use chrono::Utc;
+    use repository::{
+        mock::{mock_item_a, mock_store_a, mock_store_b, MockDataInserts},
+        test_db::setup_all,
+        EqualFilter, InvoiceFilter, InvoiceLineFilter, InvoiceLineRepository, InvoiceLineRow,
+        InvoiceLineRowRepository, InvoiceLineType, InvoiceRepository, InvoiceRow,
+        InvoiceRowRepository, InvoiceStatus, InvoiceType, SyncLogRow, SyncLogRowRepository,
+    };
+    use util::inline_init;
+
+    use crate::{
+        processors::transfer::invoice::process_invoice_transfers,
+        settings_service::{SettingsService, SettingsServiceTrait},
+        sync::{
+            peer_sync::{pull_for_peer, serve_peer_pull},
+            settings::SyncSettings,
+        },
+    };
+
+    use super::*;
+
+    const SECRET: &str = "facility_secret";
+
+    async fn setup_site(db_name: &str, site_id: i32) -> (Arc<ServiceProvider>, StorageConnection) {
+        let (_, connection, connection_manager, _) = setup_all(
+            db_name,
+            MockDataInserts::none()
+                .names()
+                .stores()
+                .items()
+                .units()
+                .currencies(),
+        )
+        .await;
+        let service_provider = Arc::new(ServiceProvider::new(connection_manager, "app_data"));
+        let ctx = service_provider.basic_context().unwrap();
+
+        SettingsService
+            .update_sync_settings(
+                &ctx,
+                &SyncSettings {
+                    url: "http://central".to_string(),
+                    username: db_name.to_string(),
+                    password_sha256: "password_sha256".to_string(),
+                    interval_seconds: 60,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        KeyValueStoreRepository::new(&connection)
+            .set_i32(KeyType::SettingsSyncSiteId, Some(site_id))
+            .unwrap();
+        SyncLogRowRepository::new(&connection)
+            .upsert_one(&SyncLogRow {
+                id: "initialised".to_string(),
+                finished_datetime: Some(Utc::now().naive_utc()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        (service_provider, connection)
+    }
+
+    async fn peer_pull(
+        body: String,
+        service_provider: web::Data<ServiceProvider>,
+        settings: web::Data<PeerSyncSettings>,
+    ) -> HttpResponse {
+        match serve_peer_pull(&service_provider, Some(settings.get_ref()), &body) {
+            Ok(response) => HttpResponse::Ok().body(response),
+            Err(error) => HttpResponse::InternalServerError().body(format_error(&error)),
+        }
+    }
+
+    fn inbound_shipments(connection: &StorageConnection, outbound_id: &str) -> Vec<String> {
+        InvoiceRepository::new(connection)
+            .query_by_filter(InvoiceFilter::new_match_linked_invoice_id(outbound_id))
+            .unwrap()
+            .into_iter()
+            .map(|invoice| invoice.invoice_row.id)
+            .collect()
+    }
+
+    /// Site a creates a shipment for store b, site b pulls it from site a (peer), integrates it
+    /// and its transfer processor creates the inbound shipment. The same shipment later arriving
+    /// from central must not create another inbound shipment
+    #[actix_rt::test]
+    async fn test_sync_with_peer_transfer() {
+        let (service_provider_a, connection_a) =
+            setup_site("test_sync_with_peer_transfer_a", mock_store_a().site_id).await;
+        let (service_provider_b, connection_b) =
+            setup_site("test_sync_with_peer_transfer_b", mock_store_b().site_id).await;
+
+        let outbound_shipment = inline_init(|r: &mut InvoiceRow| {
+            r.id = "peer_outbound_shipment".to_string();
+            r.store_id = mock_store_a().id;
+            r.name_link_id = mock_store_b().name_id;
+            r.r#type = InvoiceType::OutboundShipment;
+            r.status = InvoiceStatus::Picked;
+            r.created_datetime = Utc::now().naive_utc();
+            r.picked_datetime = Some(Utc::now().naive_utc());
+        });
+        InvoiceRowRepository::new(&connection_a)
+            .upsert_one(&outbound_shipment)
+            .unwrap();
+        InvoiceLineRowRepository::new(&connection_a)
+            .upsert_one(&inline_init(|r: &mut InvoiceLineRow| {
+                r.id = "peer_outbound_shipment_line".to_string();
+                r.invoice_id = outbound_shipment.id.clone();
+                r.item_link_id = mock_item_a().id;
+                r.r#type = InvoiceLineType::StockOut;
+                r.pack_size = 1;
+                r.number_of_packs = 10.0;
+            }))
+            .unwrap();
+
+        // Serve peer pull of site a
+        let settings = PeerSyncSettings {
+            secret: SECRET.to_string(),
+            peer_urls: Vec::new(),
+            interval_seconds: 60,
+        };
+        let server_service_provider = service_provider_a.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::from(server_service_provider.clone()))
+                .app_data(web::Data::new(settings.clone()))
+                .route(
+                    &format!("/{}", PEER_SYNC_PULL_ROUTE),
+                    web::post().to(peer_pull),
+                )
+        })
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("http://{}/", server.addrs()[0]);
+        let server = server.run();
+        let server_handle = server.handle();
+        actix_rt::spawn(server);
+
+        // PULL -> INTEGRATE
+        let number_of_records = sync_with_peer(&service_provider_b, SECRET, &url)
+            .await
+            .unwrap();
+        assert_eq!(number_of_records, 2);
+        assert_eq!(
+            InvoiceRowRepository::new(&connection_b)
+                .find_one_by_id_option(&outbound_shipment.id)
+                .unwrap()
+                .map(|r| r.status),
+            Some(InvoiceStatus::Picked)
+        );
+        // Cursor is saved, nothing new to pull
+        assert_eq!(
+            sync_with_peer(&service_provider_b, SECRET, &url)
+                .await
+                .unwrap(),
+            0
+        );
+        server_handle.stop(true).await;
+
+        // TRANSFER PROCESSOR
+        process_invoice_transfers(&service_provider_b).unwrap();
+        let inbound_shipment_ids = inbound_shipments(&connection_b, &outbound_shipment.id);
+        assert_eq!(inbound_shipment_ids.len(), 1);
+        let inbound_shipment = InvoiceRowRepository::new(&connection_b)
+            .find_one_by_id(&inbound_shipment_ids[0])
+            .unwrap();
+        assert_eq!(inbound_shipment.store_id, mock_store_b().id);
+        assert_eq!(inbound_shipment.r#type, InvoiceType::InboundShipment);
+        assert_eq!(
+            InvoiceLineRepository::new(&connection_b)
+                .query_by_filter(
+                    InvoiceLineFilter::new()
+                        .invoice_id(EqualFilter::equal_to(&inbound_shipment.id))
+                )
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Same shipment arriving from central (no source site)
+        let central_buffer_rows: Vec<SyncBufferRow> =
+            pull_for_peer(&connection_a, mock_store_b().site_id, 0, 1000)
+                .unwrap()
+                .records
+                .into_iter()
+                .map(|SyncRecordV6 { record, .. }| SyncBufferRow {
+                    store_id: record.store_id(),
+                    ..record.to_buffer_row(None).unwrap()
+                })
+                .collect();
+        SyncBufferRowRepository::new(&connection_b)
+            .upsert_many(&central_buffer_rows)
+            .unwrap();
+        integrate_and_translate_sync_buffer(&connection_b, true, None, None).unwrap();
+
+        process_invoice_transfers(&service_provider_b).unwrap();
+        assert_eq!(
+            inbound_shipments(&connection_b, &outbound_shipment.id),
+            inbound_shipment_ids
+        );
+    }
+}
@@ -3,7 +3,7 @@ use std::time::{Duration, SystemTime};
 use crate::{
     cursor_controller::CursorController,
     sync::{
-        get_sync_push_changelogs_filter, sync_status::logger::SyncStepProgress,
+        get_central_push_changelogs_filter, sync_status::logger::SyncStepProgress,
         GetActiveStoresOnSiteError,
     },
 };
@@ -167,7 +167,7 @@ impl RemoteDataSynchroniser {
         logger: &mut SyncLogger<'a>,
     ) -> Result<(), RemotePushError> {
         let changelog_repo = ChangelogRepository::new(connection);
-        let change_log_filter = get_sync_push_changelogs_filter(connection)?;
+        let change_log_filter = get_central_push_changelogs_filter(connection, false)?;
         let cursor_controller = CursorController::new(KeyType::RemoteSyncPushCursor);
 
         loop {
@@ -13,6 +13,32 @@ pub struct SyncSettings {
     // Number of records to pull or push in one API call
     #[serde(default)]
     pub batch_size: BatchSize,
+    /// Stores to make operational first during initialisation, remote records of other stores
+    /// on the site are integrated (backfilled) store by store, in the background after following
+    /// syncs
+    #[serde(default)]
+    pub priority_store_ids: Vec<String>,
+    /// Sync invoices, stock lines, stocktakes and requisitions with omSupply central (v6) instead
+    /// of legacy central (v5). Sites that transfer with each other should use the same setting
+    #[serde(default)]
+    pub transactional_sync_v6: bool,
+}
+
+/// Transfers (requisitions and shipments) are exchanged directly between servers of one facility
+/// when central server is unreachable, see `peer_sync`
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct PeerSyncSettings {
+    /// Shared by all servers of the facility, peer sync messages are signed with it
+    pub secret: String,
+    /// Peers that are not found with DNS-SD discovery, e.g. `https://192.168.1.20:8000`
+    #[serde(default)]
+    pub peer_urls: Vec<String>,
+    #[serde(default = "default_peer_sync_interval_seconds")]
+    pub interval_seconds: u64,
+}
+
+fn default_peer_sync_interval_seconds() -> u64 {
+    60
 }
 
 #[derive(Clone, Debug, PartialEq, Deserialize)]
@@ -0,0 +1,369 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use log::{error, info};
+use repository::{
+    DatetimeFilter, EqualFilter, KeyType, KeyValueStoreRepository, RepositoryError,
+    StorageConnection, SyncBufferFilter, SyncBufferRepository, SyncBufferRowRepository,
+};
+use thiserror::Error;
+use util::format_error;
+
+use crate::service_provider::ServiceProvider;
+
+use super::{
+    sync_status::logger::SyncLogger, synchroniser::integrate_and_translate_sync_buffer_for_stores,
+    translation_and_integration::TranslationAndIntegrationResults, ActiveStoresOnSite,
+    GetActiveStoresOnSiteError,
+};
+
+/// Backfill doesn't start on another store after this time, remaining stores are backfilled
+/// in the next sync
+const BACKFILL_TIME_LIMIT: Duration = Duration::from_secs(5 * 60);
+
+/// Only one backfill runs at a time, sync doesn't wait for backfill to finish
+static BACKFILL_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Legacy line tables don't have store_ID: (line table, parent field, parent table)
+const LINE_PARENTS: [(&str, &str, &str); 6] = [
+    ("trans_line", "transaction_ID", "transact"),
+    ("requisition_line", "requisition_ID", "requisition"),
+    ("Stock_take_lines", "stock_take_ID", "Stock_take"),
+    ("om_invoice_line", "invoice_id", "om_invoice"),
+    ("om_requisition_line", "requisition_id", "om_requisition"),
+    ("om_stocktake_line", "stocktake_id", "om_stocktake"),
+];
+
+#[derive(Error, Debug)]
+pub(crate) enum StoreBackfillError {
+    #[error("Database error during store backfill")]
+    DatabaseError(#[from] RepositoryError),
+    #[error("Problem getting active stores on site during store backfill")]
+    GetActiveStoresOnSiteError(#[from] GetActiveStoresOnSiteError),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoreSyncProgress {
+    pub store_id: String,
+    pub store_name: String,
+    /// Remote records of the store that were pulled from central server
+    pub total: u64,
+    /// Pulled records that are not integrated yet
+    pub remaining: u64,
+    /// Store is not operational until its records are backfilled
+    pub is_backfill_pending: bool,
+}
+
+/// Store ids are stored comma separated
+pub(crate) fn parse_store_ids(store_ids: &str) -> Vec<String> {
+    store_ids
+        .split(',')
+        .filter(|store_id| !store_id.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Stores waiting for backfill, in backfill order
+pub fn backfill_store_ids(connection: &StorageConnection) -> Result<Vec<String>, RepositoryError> {
+    let store_ids =
+        KeyValueStoreRepository::new(connection).get_string(KeyType::SyncBackfillStoreIds)?;
+
+    Ok(store_ids
+        .map(|store_ids| parse_store_ids(&store_ids))
+        .unwrap_or_default())
+}
+
+fn set_backfill_store_ids(
+    connection: &StorageConnection,
+    store_ids: &[String],
+) -> Result<(), RepositoryError> {
+    KeyValueStoreRepository::new(connection)
+        .set_string(KeyType::SyncBackfillStoreIds, Some(store_ids.join(",")))
+}
+
+/// Lines get the store of their (not yet integrated) parent record
+fn assign_line_store_ids(connection: &StorageConnection) -> Result<(), RepositoryError> {
+    let query_repository = SyncBufferRepository::new(connection);
+    let row_repository = SyncBufferRowRepository::new(connection);
+    let not_integrated = || {
+        SyncBufferFilter::new()
+            .integration_datetime(DatetimeFilter::is_null(true))
+            .source_site_id(EqualFilter::i32_is_null(true))
+    };
+
+    for (line_table, parent_field, parent_table) in LINE_PARENTS {
+        let lines = query_repository.query_by_filter(
+            not_integrated()
+                .table_name(EqualFilter::equal_to(line_table))
+                .store_id(EqualFilter::is_null(true)),
+        )?;
+        if lines.is_empty() {
+            continue;
+        }
+
+        let parent_store_ids: HashMap<String, String> = query_repository
+            .query_by_filter(
+                not_integrated()
+                    .table_name(EqualFilter::equal_to(parent_table))
+                    .store_id(EqualFilter::is_null(false)),
+            )?
+            .into_iter()
+            .filter_map(|parent| Some((parent.record_id, parent.store_id?)))
+            .collect();
+
+        for mut line in lines {
+            let data: serde_json::Value = serde_json::from_str(&line.data).unwrap_or_default();
+            let Some(store_id) = data[parent_field]
+                .as_str()
+                .and_then(|parent_id| parent_store_ids.get(parent_id))
+            else {
+                continue;
+            };
+
+            line.store_id = Some(store_id.clone());
+            row_repository.upsert_one(&line)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Translates and integrates sync buffer, records of stores waiting for backfill are skipped.
+/// During initialisation with `priority_store_ids`, records without store (i.e. central data)
+/// are integrated first so that stores on this site are known, site stores that are not
+/// prioritised are then queued for backfill
+pub(crate) fn integrate_prioritised<'a>(
+    connection: &StorageConnection,
+    is_initialised: bool,
+    priority_store_ids: &[String],
+    mut logger: Option<&mut SyncLogger<'a>>,
+) -> Result<
+    (
+        TranslationAndIntegrationResults,
+        TranslationAndIntegrationResults,
+        TranslationAndIntegrationResults,
+    ),
+    StoreBackfillError,
+> {
+    let mut store_ids_to_backfill = backfill_store_ids(connection)?;
+
+    if !is_initialised && !priority_store_ids.is_empty() {
+        assign_line_store_ids(connection)?;
+        integrate_and_translate_sync_buffer_for_stores(
+            connection,
+            false,
+            logger.as_deref_mut(),
+            None,
+            Some(EqualFilter::is_null(true)),
+        )?;
+
+        store_ids_to_backfill = ActiveStoresOnSite::get(connection)?
+            .store_ids()
+            .into_iter()
+            .filter(|store_id| !priority_store_ids.contains(store_id))
+            .collect();
+        info!("Stores queued for backfill {:?}", store_ids_to_backfill);
+        set_backfill_store_ids(connection, &store_ids_to_backfill)?;
+    } else if !store_ids_to_backfill.is_empty() {
+        assign_line_store_ids(connection)?;
+    }
+
+    let store_id = match store_ids_to_backfill.is_empty() {
+        true => None,
+        false => {
+            let store_ids = SyncBufferRepository::new(connection)
+                .store_ids(Some(
+                    SyncBufferFilter::new().integration_datetime(DatetimeFilter::is_null(true)),
+                ))?
+                .into_iter()
+                .filter(|store_id| !store_ids_to_backfill.contains(store_id))
+                .collect();
+            Some(EqualFilter::equal_any_or_null(store_ids))
+        }
+    };
+
+    Ok(integrate_and_translate_sync_buffer_for_stores(
+        connection,
+        is_initialised,
+        logger,
+        None,
+        store_id,
+    )?)
+}
+
+/// Runs `backfill_stores` as a blocking task, outside of sync. Transfer processors are triggered
+/// once backfill is done
+pub(crate) fn spawn_backfill_stores(service_provider: Arc<ServiceProvider>) {
+    if BACKFILL_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let result = service_provider
+            .basic_context()
+            .map_err(StoreBackfillError::from)
+            .and_then(|ctx| {
+                backfill_stores(&ctx.connection)?;
+                ctx.processors_trigger
+                    .trigger_requisition_transfer_processors();
+                ctx.processors_trigger.trigger_invoice_transfer_processors();
+                ctx.processors_trigger.trigger_audit_log_processor();
+                Ok(())
+            });
+        if let Err(error) = result {
+            error!("Store backfill failed: {}", format_error(&error));
+        }
+        BACKFILL_IN_PROGRESS.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Integrates records of stores waiting for backfill, one store (and transaction) at a time
+pub(crate) fn backfill_stores(connection: &StorageConnection) -> Result<(), StoreBackfillError> {
+    let mut store_ids = backfill_store_ids(connection)?;
+    let start = Instant::now();
+
+    while let Some(store_id) = store_ids.first().cloned() {
+        if start.elapsed() >= BACKFILL_TIME_LIMIT {
+            info!("{} stores left to backfill", store_ids.len());
+            break;
+        }
+
+        info!("Backfilling store {}", store_id);
+        let (upserts, deletes, merges) = integrate_and_translate_sync_buffer_for_stores(
+            connection,
+            true,
+            None,
+            None,
+            Some(EqualFilter::equal_to(&store_id)),
+        )?;
+        info!(
+            "Store {} backfill result: {:?} {:?} {:?}",
+            store_id, upserts, deletes, merges
+        );
+
+        store_ids.remove(0);
+        set_backfill_store_ids(connection, &store_ids)?;
+    }
+
+    Ok(())
+}
+
+/// Integration progress of remote records for each store on this site
+pub fn store_sync_progress(
+    connection: &StorageConnection,
+) -> Result<Vec<StoreSyncProgress>, RepositoryError> {
+    let stores = match ActiveStoresOnSite::get(connection) {
+        Ok(active_stores) => active_stores.stores,
+        Err(GetActiveStoresOnSiteError::SiteIdNotSet) => return Ok(Vec::new()),
+        Err(GetActiveStoresOnSiteError::DatabaseError(error)) => return Err(error),
+    };
+    let store_ids_to_backfill = backfill_store_ids(connection)?;
+    let repository = SyncBufferRepository::new(connection);
+
+    stores
+        .into_iter()
+        .map(|store| {
+            let store_id = store.store_row.id;
+            let filter = SyncBufferFilter::new()
+                .store_id(EqualFilter::equal_to(&store_id))
+                .source_site_id(EqualFilter::i32_is_null(true));
+            let total = repository.count(Some(filter.clone()))?;
+            let remaining = repository.count(Some(
+                filter.integration_datetime(DatetimeFilter::is_null(true)),
+            ))?;
+
+            Ok(StoreSyncProgress {
+                is_backfill_pending: store_ids_to_backfill.contains(&store_id),
+                store_id,
+                store_name: store.name_row.name,
+                total: total as u64,
+                remaining: remaining as u64,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use repository::{mock::MockDataInserts, test_db, SyncAction, SyncBufferRow};
+    use serde_json::json;
+    use util::inline_init;
+
+    fn buffer_row(record_id: &str, table_name: &str, data: serde_json::Value) -> SyncBufferRow {
+        inline_init(|r: &mut SyncBufferRow| {
+            r.record_id = record_id.to_string();
+            r.table_name = table_name.to_string();
+            r.action = SyncAction::Upsert;
+            r.data = data.to_string();
+        })
+    }
+
+    #[actix_rt::test]
+    async fn test_assign_line_store_ids() {
+        let (_, connection, _, _) =
+            test_db::setup_all("test_assign_line_store_ids", MockDataInserts::none()).await;
+
+        let transact = SyncBufferRow {
+            store_id: Some("store_a".to_string()),
+            ..buffer_row("transact", "transact", json!({ "store_ID": "store_a" }))
+        };
+        let trans_line = buffer_row(
+            "trans_line",
+            "trans_line",
+            json!({ "transaction_ID": "transact" }),
+        );
+        let orphan_line = buffer_row(
+            "orphan_line",
+            "trans_line",
+            json!({ "transaction_ID": "integrated_transact" }),
+        );
+        let requisition_line = buffer_row(
+            "requisition_line",
+            "requisition_line",
+            json!({ "requisition_ID": "transact" }),
+        );
+
+        let repository = SyncBufferRowRepository::new(&connection);
+        for row in [&transact, &trans_line, &orphan_line, &requisition_line] {
+            repository.upsert_one(row).unwrap();
+        }
+
+        assign_line_store_ids(&connection).unwrap();
+
+        let store_id = |record_id: &str| {
+            repository
+                .find_one_by_record_id(record_id)
+                .unwrap()
+                .unwrap()
+                .store_id
+        };
+        assert_eq!(store_id("trans_line"), Some("store_a".to_string()));
+        // Parent is not in sync buffer
+        assert_eq!(store_id("orphan_line"), None);
+        // Parent is not a requisition
+        assert_eq!(store_id("requisition_line"), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_backfill_store_ids() {
+        let (_, connection, _, _) =
+            test_db::setup_all("test_backfill_store_ids", MockDataInserts::none()).await;
+
+        assert_eq!(backfill_store_ids(&connection), Ok(Vec::new()));
+
+        let store_ids = vec!["store_a".to_string(), "store_b".to_string()];
+        set_backfill_store_ids(&connection, &store_ids).unwrap();
+        assert_eq!(backfill_store_ids(&connection), Ok(store_ids));
+
+        // Nothing to integrate, all stores are removed from backfill
+        backfill_stores(&connection).unwrap();
+        assert_eq!(backfill_store_ids(&connection), Ok(Vec::new()));
+    }
+}
@@ -8,6 +8,7 @@ use util::inline_edit;
 pub(crate) struct SyncBuffer<'a> {
     query_repository: SyncBufferRepository<'a>,
     row_repository: SyncBufferRowRepository<'a>,
+    store_id: Option<EqualFilter<String>>,
 }
 
 impl<'a> SyncBuffer<'a> {
@@ -15,9 +16,16 @@ impl<'a> SyncBuffer<'a> {
         SyncBuffer {
             query_repository: SyncBufferRepository::new(connection),
             row_repository: SyncBufferRowRepository::new(connection),
+            store_id: None,
         }
     }
 
+    /// Only records matching `store_id` filter are returned by `get_ordered_sync_buffer_records`
+    pub(crate) fn store_id(mut self, filter: EqualFilter<String>) -> Self {
+        self.store_id = Some(filter);
+        self
+    }
+
     pub(crate) fn record_successful_integration(
         &self,
         row: &SyncBufferRow,
@@ -59,16 +67,19 @@ impl<'a> SyncBuffer<'a> {
         let mut result = Vec::new();
 
         for legacy_table_name in order {
-            let mut rows = self.query_repository.query_by_filter(
-                SyncBufferFilter::new()
-                    .table_name(EqualFilter::equal_to(legacy_table_name))
-                    .action(action.equal_to())
-                    .integration_datetime(DatetimeFilter::is_null(true))
-                    .source_site_id(match source_site_id {
-                        Some(site_id) => EqualFilter::equal_to_i32(site_id),
-                        None => EqualFilter::i32_is_null(true),
-                    }),
-            )?;
+            let mut filter = SyncBufferFilter::new()
+                .table_name(EqualFilter::equal_to(legacy_table_name))
+                .action(action.equal_to())
+                .integration_datetime(DatetimeFilter::is_null(true))
+                .source_site_id(match source_site_id {
+                    Some(site_id) => EqualFilter::equal_to_i32(site_id),
+                    None => EqualFilter::i32_is_null(true),
+                });
+            if let Some(store_id) = &self.store_id {
+                filter = filter.store_id(store_id.clone());
+            }
+
+            let mut rows = self.query_repository.query_by_filter(filter)?;
             result.append(&mut rows);
         }
 
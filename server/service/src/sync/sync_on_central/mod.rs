@@ -13,18 +13,24 @@ use util::format_error;
 use crate::{
     service_provider::ServiceProvider,
     settings::Settings,
-    static_files::{StaticFile, StaticFileCategory, StaticFileService},
+    static_files::{
+        file_sha256, PartialSyncFileError, StaticFile, StaticFileCategory, StaticFileService,
+    },
     sync::{
-        api::SyncApiV5, api_v6::SiteStatusV6, synchroniser::integrate_and_translate_sync_buffer,
-        translations::ToSyncRecordTranslationType, CentralServerConfig,
+        api::{SyncApiSettings, SyncApiV5},
+        api_v6::SiteStatusV6,
+        synchroniser::integrate_and_translate_sync_buffer,
+        translations::ToSyncRecordTranslationType,
+        CentralServerConfig,
     },
+    u64_to_i32,
 };
 
 use super::{
     api_v6::{
         SiteStatusRequestV6, SyncBatchV6, SyncDownloadFileRequestV6, SyncParsedErrorV6,
         SyncPullRequestV6, SyncPushRequestV6, SyncPushSuccessV6, SyncRecordV6,
-        SyncUploadFileRequestV6,
+        SyncUploadFileChunkV6, SyncUploadFileRequestV6, SyncUploadFileSuccessV6,
     },
     translations::translate_changelogs_to_sync_records,
 };
@@ -37,22 +43,34 @@ pub async fn pull(
         batch_size,
         sync_v5_settings,
         is_initialised,
+        transactional_sync_v6,
     }: SyncPullRequestV6,
 ) -> Result<SyncBatchV6, SyncParsedErrorV6> {
-    use SyncParsedErrorV6 as Error;
+    let site_id = validate_site(sync_v5_settings).await?;
 
-    if !CentralServerConfig::is_central_server() {
-        return Err(Error::NotACentralServer);
-    }
-    // Check credentials again mSupply central server
-    let response = SyncApiV5::new(sync_v5_settings)
-        .map_err(|e| Error::OtherServerError(format_error(&e)))?
-        .get_site_info()
-        .await
-        .map_err(Error::from)?;
+    pull_for_site(
+        service_provider,
+        site_id,
+        cursor,
+        batch_size,
+        is_initialised,
+        transactional_sync_v6,
+    )
+}
+
+/// Records for a site whose credentials were already checked
+pub(crate) fn pull_for_site(
+    service_provider: &ServiceProvider,
+    site_id: i32,
+    cursor: u64,
+    batch_size: u32,
+    is_initialised: bool,
+    transactional_sync_v6: bool,
+) -> Result<SyncBatchV6, SyncParsedErrorV6> {
+    use SyncParsedErrorV6 as Error;
 
     // Site should retry if we are currently integrating records for this site
-    if is_integrating(response.site_id) {
+    if is_integrating(site_id) {
         return Err(Error::IntegrationInProgress);
     }
 
@@ -63,13 +81,15 @@ pub async fn pull(
     let changelogs = changelog_repo.outgoing_sync_records_from_central(
         cursor,
         batch_size,
-        response.site_id,
+        site_id,
         is_initialised,
+        transactional_sync_v6,
     )?;
     let total_records = changelog_repo.count_outgoing_sync_records_from_central(
         cursor,
-        response.site_id,
+        site_id,
         is_initialised,
+        transactional_sync_v6,
     )?;
     let max_cursor = changelog_repo.latest_cursor()?;
 
@@ -88,11 +108,7 @@ pub async fn pull(
     .map(SyncRecordV6::from)
     .collect();
 
-    log::info!(
-        "Sending {} records to site {}",
-        records.len(),
-        response.site_id
-    );
+    log::info!("Sending {} records to site {}", records.len(), site_id);
     log::debug!("Sending records as central server: {:#?}", records);
 
     let is_last_batch = total_records <= batch_size as u64;
@@ -113,20 +129,21 @@ pub async fn push(
         sync_v5_settings,
     }: SyncPushRequestV6,
 ) -> Result<SyncPushSuccessV6, SyncParsedErrorV6> {
-    use SyncParsedErrorV6 as Error;
+    let site_id = validate_site(sync_v5_settings).await?;
 
-    if !CentralServerConfig::is_central_server() {
-        return Err(Error::NotACentralServer);
-    }
-    // Check credentials again mSupply central server
-    let response = SyncApiV5::new(sync_v5_settings)
-        .map_err(|e| Error::OtherServerError(format_error(&e)))?
-        .get_site_info()
-        .await
-        .map_err(Error::from)?;
+    push_for_site(service_provider, site_id, batch)
+}
+
+/// Receive records from a site whose credentials were already checked
+pub(crate) fn push_for_site(
+    service_provider: Arc<ServiceProvider>,
+    site_id: i32,
+    batch: SyncBatchV6,
+) -> Result<SyncPushSuccessV6, SyncParsedErrorV6> {
+    use SyncParsedErrorV6 as Error;
 
     // Site should retry if we are currently integrating records for this site
-    if is_integrating(response.site_id) {
+    if is_integrating(site_id) {
         return Err(Error::IntegrationInProgress);
     }
 
@@ -134,7 +151,7 @@ pub async fn push(
         "Receiving {}/{} records from site {}",
         batch.records.len(),
         batch.total_records,
-        response.site_id
+        site_id
     );
     log::debug!("Receiving records as central server: {:#?}", batch);
 
@@ -149,13 +166,13 @@ pub async fn push(
 
     let records_in_this_batch = records.len() as u64;
     for SyncRecordV6 { record, .. } in records {
-        let buffer_row = record.to_buffer_row(Some(response.site_id))?;
+        let buffer_row = record.to_buffer_row(Some(site_id))?;
 
         repo.upsert_one(&buffer_row)?;
     }
 
     if is_last_batch {
-        spawn_integration(service_provider, response.site_id);
+        spawn_integration(service_provider, site_id);
     }
 
     Ok(SyncPushSuccessV6 {
@@ -166,21 +183,34 @@ pub async fn push(
 pub async fn get_site_status(
     SiteStatusRequestV6 { sync_v5_settings }: SiteStatusRequestV6,
 ) -> Result<SiteStatusV6, SyncParsedErrorV6> {
+    let site_id = validate_site(sync_v5_settings).await?;
+
+    Ok(site_status_for_site(site_id))
+}
+
+pub(crate) fn site_status_for_site(site_id: i32) -> SiteStatusV6 {
+    SiteStatusV6 {
+        is_integrating: is_integrating(site_id),
+        supports_file_chunks: true,
+    }
+}
+
+/// Checks that this is a central server and site credentials against mSupply central server,
+/// returns site id
+async fn validate_site(sync_v5_settings: SyncApiSettings) -> Result<i32, SyncParsedErrorV6> {
     use SyncParsedErrorV6 as Error;
 
     if !CentralServerConfig::is_central_server() {
         return Err(Error::NotACentralServer);
     }
-
+    // Check credentials again mSupply central server
     let response = SyncApiV5::new(sync_v5_settings)
         .map_err(|e| Error::OtherServerError(format_error(&e)))?
         .get_site_info()
         .await
         .map_err(Error::from)?;
 
-    let is_integrating = is_integrating(response.site_id);
-
-    Ok(SiteStatusV6 { is_integrating })
+    Ok(response.site_id)
 }
 
 fn spawn_integration(service_provider: Arc<ServiceProvider>, site_id: i32) -> () {
@@ -208,7 +238,8 @@ fn spawn_integration(service_provider: Arc<ServiceProvider>, site_id: i32) -> ()
     });
 }
 
-/// Send a file to a remote open-mSupply Server
+/// Send a file to a remote open-mSupply Server, also returns sha256 of the file so that remote
+/// site can check the file once all (range) requests are complete
 pub async fn download_file(
     settings: &Settings,
     SyncDownloadFileRequestV6 {
@@ -217,7 +248,7 @@ pub async fn download_file(
         record_id,
         sync_v5_settings,
     }: SyncDownloadFileRequestV6,
-) -> Result<(actix_files::NamedFile, StaticFile), SyncParsedErrorV6> {
+) -> Result<(actix_files::NamedFile, StaticFile, /* sha256 */ String), SyncParsedErrorV6> {
     use SyncParsedErrorV6 as Error;
 
     log::info!(
@@ -247,20 +278,22 @@ pub async fn download_file(
 
     let named_file =
         actix_files::NamedFile::open(&file_description.path).map_err(|e| Error::from_error(&e))?;
-    Ok((named_file, file_description))
+    let sha256 = file_sha256(named_file.path()).map_err(|e| Error::from_error(&e))?;
+    Ok((named_file, file_description, sha256))
 }
 
 /// Accept a file from a remote open-mSupply Server
-/// This is the endpoint that the remote server will call to upload a file
+/// This is the endpoint that the remote server will call to upload a file, either whole or in chunks
 pub async fn upload_file(
     settings: &Settings,
     service_provider: &ServiceProvider,
     SyncUploadFileRequestV6 {
         file_id,
         sync_v5_settings,
+        chunk,
     }: SyncUploadFileRequestV6,
     file_part: TempFile,
-) -> Result<(), SyncParsedErrorV6> {
+) -> Result<Option<SyncUploadFileSuccessV6>, SyncParsedErrorV6> {
     use SyncParsedErrorV6 as Error;
 
     log::info!("Receiving a file via sync : {}", file_id);
@@ -283,23 +316,66 @@ pub async fn upload_file(
         .find_one_by_id(&file_id)?
         .ok_or(Error::SyncFileNotFound(file_id.clone()))?;
 
-    file_service.move_temp_file(
-        file_part,
-        &StaticFileCategory::SyncFile(
-            sync_file_reference.table_name.clone(),
-            sync_file_reference.record_id.clone(),
-        ),
-        Some(file_id),
+    let Some(SyncUploadFileChunkV6 {
+        offset,
+        total_bytes,
+        sha256,
+    }) = chunk
+    else {
+        // Whole file in one request
+        file_service.move_temp_file(
+            file_part,
+            &StaticFileCategory::SyncFile(
+                sync_file_reference.table_name.clone(),
+                sync_file_reference.record_id.clone(),
+            ),
+            Some(file_id),
+        )?;
+
+        repo.upsert_one(&SyncFileReferenceRow {
+            uploaded_bytes: sync_file_reference.total_bytes,
+            ..sync_file_reference
+        })?;
+
+        return Ok(None);
+    };
+
+    let received_bytes = file_service.append_partial_sync_file(
+        &sync_file_reference,
+        offset,
+        total_bytes,
+        file_part.file.path(),
     )?;
 
+    if received_bytes < total_bytes {
+        // Progress is local information, no changelog
+        repo.update_status(&SyncFileReferenceRow {
+            uploaded_bytes: u64_to_i32(received_bytes),
+            ..sync_file_reference
+        })?;
+        return Ok(Some(SyncUploadFileSuccessV6 { received_bytes }));
+    }
+
+    match file_service.complete_partial_sync_file(&sync_file_reference, Some(&sha256)) {
+        Ok(_) => {}
+        Err(PartialSyncFileError::HashMismatch { expected, actual }) => {
+            log::error!(
+                "Hash mismatch for uploaded file {}, expected {}, got {}",
+                file_id,
+                expected,
+                actual
+            );
+            return Err(Error::SyncFileHashMismatch(file_id));
+        }
+        Err(error) => return Err(Error::from_error(&error)),
+    }
+
     repo.upsert_one(&SyncFileReferenceRow {
-        // Do we really need to store this ?
-        // I can see total bytes could be useful, but uploaded ?
         uploaded_bytes: sync_file_reference.total_bytes,
         ..sync_file_reference
     })?;
 
-    Ok(())
+    Ok(Some(SyncUploadFileSuccessV6 { received_bytes }))
 }
 
 static SITES_BEING_INTEGRATED: RwLock<Vec<i32>> = RwLock::new(vec![]);
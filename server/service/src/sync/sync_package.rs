@@ -0,0 +1,910 @@
+use std::io::Read;
+
+use chrono::{NaiveDateTime, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use hmac::{Hmac, Mac};
+use repository::{
+    ChangelogRepository, KeyType, KeyValueStoreRepository, RepositoryError, StorageConnection,
+    SyncBufferRow, SyncBufferRowRepository,
+};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+use util::{format_error, hash::sha256};
+
+use crate::{
+    app_data::AppDataServiceTrait,
+    cursor_controller::CursorController,
+    service_provider::{ServiceContext, ServiceProvider},
+    settings_service::{SettingsService, SettingsServiceTrait},
+    sync::{
+        api::{
+            CentralSyncBatchV5, CentralSyncRecordV5, ParsingSyncRecordError, RemoteSyncBatchV5,
+            RemoteSyncRecordV5, SyncApiError, SyncApiSettings, SyncApiV5,
+        },
+        get_sync_push_changelogs_filter,
+        settings::SYNC_VERSION,
+        sync_status::status::{SyncStatusService, SyncStatusTrait},
+        synchroniser::integrate_and_translate_sync_buffer,
+        translations::{
+            translate_changelogs_to_sync_records, PushTranslationError, ToSyncRecordTranslationType,
+        },
+        CentralServerConfig, GetActiveStoresOnSiteError,
+    },
+};
+
+/// Version of the package file format
+const SYNC_PACKAGE_VERSION: u32 = 1;
+/// Larger backlogs are exported in multiple packages, the next package continues from the
+/// last acknowledged cursor
+const MAX_RECORDS_PER_PACKAGE: u32 = 50_000;
+const CENTRAL_RECORDS_BATCH_SIZE: u32 = 1000;
+
+/// Offline ("sneakernet") sync, for sites without connectivity to central server.
+///
+/// * Remote site exports push package with changelogs from `RemoteSyncPushCursor` onward
+/// * omSupply central server pushes the records to mSupply central server on behalf of the site
+/// (with the site password entered on central server) and produces central package, with remote and
+/// central data for the site and acknowledgement of the push package
+/// * Remote site imports central package, the push cursor is only advanced when acknowledgement is
+/// received (until then the same records are exported again). Remote records of the central
+/// package are acknowledged on mSupply central server with the next push package
+///
+/// Packages are gzip compressed json, signed with HMAC-SHA256 using site password hash as the key
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncPackageFile {
+    version: u32,
+    /// Hex encoded HMAC of `package`
+    signature: String,
+    /// Json of `SyncPackage`, signed as is
+    package: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SyncPackage {
+    pub(crate) id: String,
+    pub(crate) site_id: i32,
+    pub(crate) sync_version: u32,
+    pub(crate) created_datetime: NaiveDateTime,
+    pub(crate) content: SyncPackageContent,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(crate) enum SyncPackageContent {
+    /// Produced by remote site
+    #[serde(rename_all = "camelCase")]
+    Push {
+        /// First changelog cursor included in the package
+        start_cursor: u64,
+        /// Last changelog cursor included in the package
+        end_cursor: u64,
+        /// Changelogs not included in this package (exceeding `MAX_RECORDS_PER_PACKAGE`)
+        remaining: u64,
+        /// Sequence of last imported central package
+        acknowledged_sequence: u64,
+        /// Sync ids of the remote records of the last imported central package
+        acknowledged_sync_ids: Vec<String>,
+        /// Sync username and hardware id of the site, used by central server to sync on behalf of
+        /// the site
+        username: String,
+        site_uuid: String,
+        /// `CentralSyncPullCursor` of the site
+        central_pull_cursor: u64,
+        records: Vec<RemoteSyncRecordV5>,
+    },
+    /// Produced by central server
+    #[serde(rename_all = "camelCase")]
+    Central {
+        /// Increases with every central package for the site, older packages are rejected
+        sequence: u64,
+        /// `end_cursor` of the push package that was integrated on central server
+        acknowledged_push_end_cursor: Option<u64>,
+        /// Remote records queued for the site
+        records: Vec<RemoteSyncRecordV5>,
+        /// Remote records not included in this package
+        remaining: u64,
+        /// Central records after the site's `central_pull_cursor`
+        central_records: Vec<CentralSyncRecordV5>,
+        /// `CentralSyncPullCursor` of the site after importing `central_records`
+        central_pull_cursor: u64,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ExportSyncPackage {
+    pub file_name: String,
+    pub data: Vec<u8>,
+    pub number_of_records: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ImportSyncPackageResult {
+    pub number_of_records: usize,
+    pub push_cursor_advanced: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum SyncPackageError {
+    #[error("Site is not initialised")]
+    NotInitialised,
+    #[error("Sync settings are not set")]
+    SyncSettingsNotSet,
+    #[error("Site id is not set in database")]
+    SiteIdNotSet,
+    #[error("Invalid sync package file")]
+    InvalidFile(#[source] anyhow::Error),
+    #[error("Unsupported sync package version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Sync package signature doesn't match, package was modified or is for another site")]
+    InvalidSignature,
+    #[error("Sync package is for site {package_site_id}, this is site {site_id}")]
+    WrongSite { package_site_id: i32, site_id: i32 },
+    #[error("Expected central package, got push package")]
+    NotACentralPackage,
+    #[error("Expected push package, got central package")]
+    NotAPushPackage,
+    #[error("Central packages can only be exported on the central server")]
+    NotACentralServer,
+    #[error("Could not get hardware id")]
+    HardwareIdError(#[source] std::io::Error),
+    #[error("Could not create sync api: {0}")]
+    SyncApiCreatingError(String),
+    #[error(transparent)]
+    SyncApiError(#[from] SyncApiError),
+    #[error("Central package {sequence} was already imported (last imported {last_imported})")]
+    AlreadyImported { sequence: u64, last_imported: u64 },
+    #[error("Could not translate changelogs: {0}")]
+    PushTranslationError(String),
+    #[error("Could not parse record: {0}")]
+    ParsingRecordError(String),
+    #[error("Database error")]
+    DatabaseError(#[from] RepositoryError),
+}
+
+impl SyncPackageError {
+    fn from_active_stores_error(error: GetActiveStoresOnSiteError) -> Self {
+        match error {
+            GetActiveStoresOnSiteError::DatabaseError(error) => Self::DatabaseError(error),
+            GetActiveStoresOnSiteError::SiteIdNotSet => Self::SiteIdNotSet,
+        }
+    }
+}
+
+impl SyncPackage {
+    fn new(site_id: i32, content: SyncPackageContent) -> Self {
+        SyncPackage {
+            id: util::uuid::uuid(),
+            site_id,
+            sync_version: SYNC_VERSION,
+            created_datetime: Utc::now().naive_utc(),
+            content,
+        }
+    }
+
+    pub(crate) fn to_file(&self, password_sha256: &str) -> Result<Vec<u8>, anyhow::Error> {
+        let package = serde_json::to_string(self)?;
+        let file = SyncPackageFile {
+            version: SYNC_PACKAGE_VERSION,
+            signature: hex::encode(signature(password_sha256, &package).finalize().into_bytes()),
+            package,
+        };
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        serde_json::to_writer(&mut encoder, &file)?;
+        Ok(encoder.finish()?)
+    }
+
+    pub(crate) fn from_file(data: &[u8], password_sha256: &str) -> Result<Self, SyncPackageError> {
+        let mut json = Vec::new();
+        GzDecoder::new(data)
+            .read_to_end(&mut json)
+            .map_err(|error| SyncPackageError::InvalidFile(error.into()))?;
+        let file: SyncPackageFile = serde_json::from_slice(&json)
+            .map_err(|error| SyncPackageError::InvalidFile(error.into()))?;
+
+        if file.version != SYNC_PACKAGE_VERSION {
+            return Err(SyncPackageError::UnsupportedVersion(file.version));
+        }
+
+        let signature_bytes =
+            hex::decode(&file.signature).map_err(|_| SyncPackageError::InvalidSignature)?;
+        signature(password_sha256, &file.package)
+            .verify_slice(&signature_bytes)
+            .map_err(|_| SyncPackageError::InvalidSignature)?;
+
+        serde_json::from_str(&file.package)
+            .map_err(|error| SyncPackageError::InvalidFile(error.into()))
+    }
+}
+
+fn signature(password_sha256: &str, package: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(password_sha256.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(package.as_bytes());
+    mac
+}
+
+struct SiteCredentials {
+    site_id: i32,
+    username: String,
+    password_sha256: String,
+}
+
+fn site_credentials(ctx: &ServiceContext) -> Result<SiteCredentials, SyncPackageError> {
+    if !SyncStatusService.is_initialised(ctx)? {
+        return Err(SyncPackageError::NotInitialised);
+    }
+    let sync_settings = SettingsService
+        .sync_settings(ctx)?
+        .ok_or(SyncPackageError::SyncSettingsNotSet)?;
+    let site_id = KeyValueStoreRepository::new(&ctx.connection)
+        .get_i32(KeyType::SettingsSyncSiteId)?
+        .ok_or(SyncPackageError::SiteIdNotSet)?;
+
+    Ok(SiteCredentials {
+        site_id,
+        username: sync_settings.username,
+        password_sha256: sync_settings.password_sha256,
+    })
+}
+
+/// Exports changelogs that have not been acknowledged by central server. Cursors are not
+/// changed, exporting again produces a package with the same (and any new) records
+pub fn export_sync_package(
+    ctx: &ServiceContext,
+    service_provider: &ServiceProvider,
+) -> Result<ExportSyncPackage, SyncPackageError> {
+    let SiteCredentials {
+        site_id,
+        username,
+        password_sha256,
+    } = site_credentials(ctx)?;
+    let site_uuid = service_provider
+        .app_data_service
+        .get_hardware_id()
+        .map_err(SyncPackageError::HardwareIdError)?;
+    let connection = &ctx.connection;
+
+    let changelog_repo = ChangelogRepository::new(connection);
+    let change_log_filter = get_sync_push_changelogs_filter(connection)
+        .map_err(SyncPackageError::from_active_stores_error)?;
+    let start_cursor = CursorController::new(KeyType::RemoteSyncPushCursor).get(connection)?;
+    let acknowledged_sequence =
+        CursorController::new(KeyType::SyncPackageCentralSequence).get(connection)?;
+    let acknowledged_sync_ids = acknowledged_sync_ids(connection)?;
+    let central_pull_cursor =
+        CursorController::new(KeyType::CentralSyncPullCursor).get(connection)?;
+
+    let changelogs = changelog_repo.changelogs(
+        start_cursor,
+        MAX_RECORDS_PER_PACKAGE,
+        change_log_filter.clone(),
+    )?;
+    let total = changelog_repo.count(start_cursor, change_log_filter)?;
+    let end_cursor = changelogs
+        .last()
+        .map(|log| log.cursor as u64)
+        // Empty package still acknowledges central package
+        .unwrap_or(start_cursor.saturating_sub(1));
+    let remaining = total.saturating_sub(changelogs.len() as u64);
+
+    let records: Vec<RemoteSyncRecordV5> = translate_changelogs_to_sync_records(
+        connection,
+        changelogs,
+        ToSyncRecordTranslationType::PushToLegacyCentral,
+    )
+    .map_err(|error: PushTranslationError| {
+        SyncPackageError::PushTranslationError(format_error(&error))
+    })?
+    .into_iter()
+    .map(RemoteSyncRecordV5::from)
+    .collect();
+    let number_of_records = records.len();
+
+    log::info!(
+        "Exporting sync package with {} records (cursor {} to {}, {} remaining)",
+        number_of_records,
+        start_cursor,
+        end_cursor,
+        remaining
+    );
+
+    let package = SyncPackage::new(
+        site_id,
+        SyncPackageContent::Push {
+            start_cursor,
+            end_cursor,
+            remaining,
+            acknowledged_sequence,
+            acknowledged_sync_ids,
+            username,
+            site_uuid,
+            central_pull_cursor,
+            records,
+        },
+    );
+
+    Ok(ExportSyncPackage {
+        file_name: format!(
+            "sync_package_site_{}_{}.json.gz",
+            site_id,
+            package.created_datetime.format("%Y%m%d_%H%M%S")
+        ),
+        data: package
+            .to_file(&password_sha256)
+            .map_err(SyncPackageError::InvalidFile)?,
+        number_of_records,
+    })
+}
+
+fn acknowledged_sync_ids(connection: &StorageConnection) -> Result<Vec<String>, RepositoryError> {
+    let sync_ids = KeyValueStoreRepository::new(connection)
+        .get_string(KeyType::SyncPackageAcknowledgedSyncIds)?
+        .and_then(|sync_ids| serde_json::from_str(&sync_ids).ok())
+        .unwrap_or_default();
+    Ok(sync_ids)
+}
+
+/// Central side of offline sync, run on the omSupply central server. Records of the site's push
+/// package are pushed to mSupply central server with the site credentials, and the records for the
+/// site are exported as central package
+pub async fn export_central_sync_package(
+    ctx: &ServiceContext,
+    service_provider: &ServiceProvider,
+    push_package: &[u8],
+    site_password: &str,
+) -> Result<ExportSyncPackage, SyncPackageError> {
+    if !CentralServerConfig::is_central_server() {
+        return Err(SyncPackageError::NotACentralServer);
+    }
+    let sync_settings = SettingsService
+        .sync_settings(ctx)?
+        .ok_or(SyncPackageError::SyncSettingsNotSet)?;
+    let central_api_settings =
+        SyncApiV5::new_settings(&sync_settings, service_provider, SYNC_VERSION)
+            .map_err(|error| SyncPackageError::SyncApiCreatingError(format_error(&error)))?;
+
+    create_central_package(central_api_settings, push_package, &sha256(site_password)).await
+}
+
+/// # Arguments
+///
+/// * `central_api_settings` - Settings to reach mSupply central server, the credentials are
+/// replaced with the site's credentials
+async fn create_central_package(
+    central_api_settings: SyncApiSettings,
+    push_package: &[u8],
+    password_sha256: &str,
+) -> Result<ExportSyncPackage, SyncPackageError> {
+    use SyncPackageError as Error;
+
+    let package = SyncPackage::from_file(push_package, password_sha256)?;
+    let site_id = package.site_id;
+    let SyncPackageContent::Push {
+        end_cursor,
+        remaining,
+        acknowledged_sequence,
+        acknowledged_sync_ids,
+        username,
+        site_uuid,
+        central_pull_cursor,
+        records,
+        ..
+    } = package.content
+    else {
+        return Err(Error::NotAPushPackage);
+    };
+
+    let api = SyncApiV5::new(SyncApiSettings {
+        username,
+        password_sha256: password_sha256.to_string(),
+        site_uuid,
+        ..central_api_settings
+    })
+    .map_err(|error| Error::SyncApiCreatingError(format_error(&error)))?;
+
+    let site_info = api.get_site_info().await?;
+    if site_info.site_id != site_id {
+        return Err(Error::WrongSite {
+            package_site_id: site_id,
+            site_id: site_info.site_id,
+        });
+    }
+
+    // Remote records of the previous central package were imported by the site
+    if !acknowledged_sync_ids.is_empty() {
+        api.post_acknowledged_records(acknowledged_sync_ids).await?;
+    }
+    let number_of_pushed_records = records.len();
+    if !records.is_empty() {
+        api.post_queued_records(remaining, records).await?;
+    }
+
+    // Not acknowledged until the site imported them, the same records are returned until then
+    let RemoteSyncBatchV5 {
+        queue_length,
+        data: remote_records,
+    } = api.get_queued_records(MAX_RECORDS_PER_PACKAGE).await?;
+
+    let mut central_records = Vec::new();
+    let mut central_cursor = central_pull_cursor;
+    while central_records.len() < MAX_RECORDS_PER_PACKAGE as usize {
+        let CentralSyncBatchV5 { max_cursor, data } = api
+            .get_central_records(central_cursor, CENTRAL_RECORDS_BATCH_SIZE)
+            .await?;
+        match (data.last(), central_cursor < max_cursor) {
+            (Some(last), _) => central_cursor = last.cursor,
+            // Batch can be empty before reaching max cursor, same as `CentralDataSynchroniser`
+            (None, true) => central_cursor += 1,
+            (None, false) => break,
+        }
+        central_records.extend(data);
+    }
+
+    let number_of_records = remote_records.len() + central_records.len();
+    log::info!(
+        "Exporting central sync package for site {} with {} remote and {} central records, pushed {} records",
+        site_id,
+        remote_records.len(),
+        central_records.len(),
+        number_of_pushed_records
+    );
+
+    let package = SyncPackage::new(
+        site_id,
+        SyncPackageContent::Central {
+            sequence: acknowledged_sequence + 1,
+            acknowledged_push_end_cursor: Some(end_cursor),
+            remaining: queue_length.saturating_sub(remote_records.len() as u64),
+            records: remote_records,
+            central_records,
+            central_pull_cursor: central_cursor,
+        },
+    );
+
+    Ok(ExportSyncPackage {
+        file_name: format!(
+            "sync_package_central_site_{}_{}.json.gz",
+            site_id,
+            package.created_datetime.format("%Y%m%d_%H%M%S")
+        ),
+        data: package
+            .to_file(password_sha256)
+            .map_err(SyncPackageError::InvalidFile)?,
+        number_of_records,
+    })
+}
+
+/// Imports central package into sync buffer and integrates it. Push cursor is advanced to
+/// acknowledged push package
+pub fn import_sync_package(
+    ctx: &ServiceContext,
+    data: &[u8],
+) -> Result<ImportSyncPackageResult, SyncPackageError> {
+    let SiteCredentials {
+        site_id,
+        password_sha256,
+        ..
+    } = site_credentials(ctx)?;
+    let connection = &ctx.connection;
+
+    let package = SyncPackage::from_file(data, &password_sha256)?;
+    if package.site_id != site_id {
+        return Err(SyncPackageError::WrongSite {
+            package_site_id: package.site_id,
+            site_id,
+        });
+    }
+    let SyncPackageContent::Central {
+        sequence,
+        acknowledged_push_end_cursor,
+        records,
+        central_records,
+        central_pull_cursor,
+        ..
+    } = package.content
+    else {
+        return Err(SyncPackageError::NotACentralPackage);
+    };
+
+    let sequence_controller = CursorController::new(KeyType::SyncPackageCentralSequence);
+    let push_cursor_controller = CursorController::new(KeyType::RemoteSyncPushCursor);
+    let central_cursor_controller = CursorController::new(KeyType::CentralSyncPullCursor);
+
+    let sync_ids = records
+        .iter()
+        .map(|record| record.sync_id.clone())
+        .collect::<Vec<String>>();
+    let buffer_rows = records
+        .into_iter()
+        .map(|record| record.record)
+        .chain(central_records.into_iter().map(|record| record.record))
+        .map(|record| record.to_buffer_row(None))
+        .collect::<Result<Vec<SyncBufferRow>, ParsingSyncRecordError>>()
+        .map_err(|error| SyncPackageError::ParsingRecordError(format_error(&error)))?;
+    let number_of_records = buffer_rows.len();
+
+    let push_cursor_advanced =
+        connection
+            .transaction_sync(|connection| {
+                let last_imported = sequence_controller.get(connection)?;
+                if sequence <= last_imported {
+                    return Err(SyncPackageError::AlreadyImported {
+                        sequence,
+                        last_imported,
+                    });
+                }
+
+                SyncBufferRowRepository::new(connection).upsert_many(&buffer_rows)?;
+                sequence_controller.update(connection, sequence)?;
+                // Acknowledged on central server with the next push package
+                KeyValueStoreRepository::new(connection).set_string(
+                    KeyType::SyncPackageAcknowledgedSyncIds,
+                    Some(serde_json::to_string(&sync_ids).map_err(|error| {
+                        SyncPackageError::ParsingRecordError(format_error(&error))
+                    })?),
+                )?;
+                if central_pull_cursor > central_cursor_controller.get(connection)? {
+                    central_cursor_controller.update(connection, central_pull_cursor)?;
+                }
+
+                let Some(acknowledged_cursor) = acknowledged_push_end_cursor else {
+                    return Ok(false);
+                };
+                // Acknowledgement of an older package doesn't move the cursor back
+                if acknowledged_cursor < push_cursor_controller.get(connection)? {
+                    return Ok(false);
+                }
+                push_cursor_controller.update(connection, acknowledged_cursor + 1)?;
+                Ok(true)
+            })
+            .map_err(|error| error.to_inner_error())?;
+
+    log::info!(
+        "Imported sync package {} with {} records, integrating",
+        sequence,
+        number_of_records
+    );
+    let (upserts, deletes, merges) =
+        integrate_and_translate_sync_buffer(connection, true, None, None)?;
+    log::info!("Upsert Integration result: {:?}", upserts);
+    log::info!("Delete Integration result: {:?}", deletes);
+    log::info!("Merge Integration result: {:?}", merges);
+
+    ctx.processors_trigger
+        .trigger_requisition_transfer_processors();
+    ctx.processors_trigger.trigger_invoice_transfer_processors();
+    ctx.processors_trigger.trigger_audit_log_processor();
+
+    Ok(ImportSyncPackageResult {
+        number_of_records,
+        push_cursor_advanced,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use repository::{
+        mock::{mock_store_a, MockDataInserts},
+        test_db::setup_all,
+        KeyType, KeyValueStoreRepository, LocationRow, LocationRowRepository, SyncBufferRow,
+        SyncBufferRowRepository,
+    };
+
+    use crate::{
+        cursor_controller::CursorController,
+        service_provider::ServiceProvider,
+        settings_service::{SettingsService, SettingsServiceTrait},
+        sync::{
+            api::{CentralSyncRecordV5, CommonSyncRecord, RemoteSyncRecordV5, SyncApiSettings},
+            settings::SyncSettings,
+        },
+    };
+    use httpmock::{
+        Method::{GET, POST},
+        MockServer,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn sync_package_export_import() {
+        let (_, connection, connection_manager, _) = setup_all(
+            "sync_package_export_import",
+            MockDataInserts::none().names().stores(),
+        )
+        .await;
+        let service_provider = ServiceProvider::new(connection_manager, "app_data");
+        let ctx = service_provider.basic_context().unwrap();
+
+        // Not initialised
+        assert!(matches!(
+            export_sync_package(&ctx, &service_provider),
+            Err(SyncPackageError::NotInitialised)
+        ));
+
+        let password_sha256 = "site_password_sha256".to_string();
+        SettingsService
+            .update_sync_settings(
+                &ctx,
+                &SyncSettings {
+                    url: "http://central".to_string(),
+                    username: "site".to_string(),
+                    password_sha256: password_sha256.clone(),
+                    interval_seconds: 60,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        KeyValueStoreRepository::new(&connection)
+            .set_i32(KeyType::SettingsSyncSiteId, Some(mock_store_a().site_id))
+            .unwrap();
+        repository::SyncLogRowRepository::new(&connection)
+            .upsert_one(&repository::SyncLogRow {
+                id: "initialised".to_string(),
+                finished_datetime: Some(Utc::now().naive_utc()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        LocationRowRepository::new(&connection)
+            .upsert_one(&LocationRow {
+                id: "sync_package_location".to_string(),
+                name: "location".to_string(),
+                code: "code".to_string(),
+                on_hold: false,
+                store_id: mock_store_a().id,
+            })
+            .unwrap();
+
+        // EXPORT
+        let export = export_sync_package(&ctx, &service_provider).unwrap();
+        let package = SyncPackage::from_file(&export.data, &password_sha256).unwrap();
+        let SyncPackageContent::Push {
+            start_cursor,
+            end_cursor,
+            records,
+            ..
+        } = package.content
+        else {
+            panic!("Expected push package");
+        };
+        assert_eq!(start_cursor, 0);
+        assert!(records
+            .iter()
+            .any(|record| record.record.record_id == "sync_package_location"));
+        assert_eq!(records.len(), export.number_of_records);
+
+        // Signed with another key
+        assert!(matches!(
+            SyncPackage::from_file(&export.data, "other_password"),
+            Err(SyncPackageError::InvalidSignature)
+        ));
+        // Push package can't be imported
+        assert!(matches!(
+            import_sync_package(&ctx, &export.data),
+            Err(SyncPackageError::NotACentralPackage)
+        ));
+
+        // IMPORT
+        let central_package = |sequence, site_id| {
+            SyncPackage::new(
+                site_id,
+                SyncPackageContent::Central {
+                    sequence,
+                    acknowledged_push_end_cursor: Some(end_cursor),
+                    records: vec![RemoteSyncRecordV5 {
+                        sync_id: "1".to_string(),
+                        record: CommonSyncRecord::test(),
+                    }],
+                    remaining: 0,
+                    central_records: vec![CentralSyncRecordV5 {
+                        cursor: 5,
+                        record: CommonSyncRecord {
+                            record_id: "central_test".to_string(),
+                            ..CommonSyncRecord::test()
+                        },
+                    }],
+                    central_pull_cursor: 5,
+                },
+            )
+            .to_file(&password_sha256)
+            .unwrap()
+        };
+
+        assert!(matches!(
+            import_sync_package(&ctx, &central_package(1, 1)),
+            Err(SyncPackageError::WrongSite { .. })
+        ));
+
+        let result =
+            import_sync_package(&ctx, &central_package(1, mock_store_a().site_id)).unwrap();
+        assert_eq!(
+            result,
+            ImportSyncPackageResult {
+                number_of_records: 2,
+                push_cursor_advanced: true
+            }
+        );
+        assert_eq!(
+            CursorController::new(KeyType::RemoteSyncPushCursor)
+                .get(&connection)
+                .unwrap(),
+            end_cursor + 1
+        );
+        assert_eq!(
+            CursorController::new(KeyType::CentralSyncPullCursor)
+                .get(&connection)
+                .unwrap(),
+            5
+        );
+        let buffer_row: Option<SyncBufferRow> = SyncBufferRowRepository::new(&connection)
+            .find_one_by_record_id(&CommonSyncRecord::test().record_id)
+            .unwrap();
+        assert!(buffer_row.is_some());
+        let buffer_row: Option<SyncBufferRow> = SyncBufferRowRepository::new(&connection)
+            .find_one_by_record_id("central_test")
+            .unwrap();
+        assert!(buffer_row.is_some());
+
+        // Same package can't be imported twice
+        assert!(matches!(
+            import_sync_package(&ctx, &central_package(1, mock_store_a().site_id)),
+            Err(SyncPackageError::AlreadyImported { .. })
+        ));
+
+        // Acknowledged records are not exported again, central sequence is acknowledged
+        let export = export_sync_package(&ctx, &service_provider).unwrap();
+        let package = SyncPackage::from_file(&export.data, &password_sha256).unwrap();
+        let SyncPackageContent::Push {
+            start_cursor,
+            acknowledged_sequence,
+            acknowledged_sync_ids,
+            username,
+            central_pull_cursor,
+            records,
+            ..
+        } = package.content
+        else {
+            panic!("Expected push package");
+        };
+        assert_eq!(start_cursor, end_cursor + 1);
+        assert_eq!(acknowledged_sequence, 1);
+        assert_eq!(acknowledged_sync_ids, vec!["1".to_string()]);
+        assert_eq!(username, "site");
+        assert_eq!(central_pull_cursor, 5);
+        assert!(records.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn sync_package_create_central_package() {
+        let mock_server = MockServer::start();
+        let password_sha256 = sha256("site_password");
+        let central_api_settings = SyncApiSettings {
+            server_url: mock_server.base_url(),
+            username: "central".to_string(),
+            password_sha256: sha256("central_password"),
+            site_uuid: "central_hardware_id".to_string(),
+            app_version: "1.0".to_string(),
+            app_name: "test".to_string(),
+            sync_version: SYNC_VERSION.to_string(),
+        };
+
+        let push_package = |site_id| {
+            SyncPackage::new(
+                site_id,
+                SyncPackageContent::Push {
+                    start_cursor: 0,
+                    end_cursor: 20,
+                    remaining: 0,
+                    acknowledged_sequence: 3,
+                    acknowledged_sync_ids: vec!["ack".to_string()],
+                    username: "site".to_string(),
+                    site_uuid: "site_hardware_id".to_string(),
+                    central_pull_cursor: 10,
+                    records: vec![RemoteSyncRecordV5 {
+                        sync_id: "push".to_string(),
+                        record: CommonSyncRecord::test(),
+                    }],
+                },
+            )
+            .to_file(&password_sha256)
+            .unwrap()
+        };
+
+        let site_info_mock = mock_server.mock(|when, then| {
+            when.method(GET)
+                .path("/sync/v5/site")
+                .header("msupply-site-uuid", "site_hardware_id");
+            then.status(200).json_body(json!({
+                "id": "site",
+                "siteId": 5,
+                "initialisationStatus": "completed",
+                "isOmSupplyCentralServer": false,
+                "omSupplyCentralServerUrl": ""
+            }));
+        });
+        let acknowledged_mock = mock_server.mock(|when, then| {
+            when.method(POST)
+                .path("/sync/v5/acknowledged_records")
+                .json_body(json!({ "syncIDs": ["ack"] }));
+            then.status(200).body("");
+        });
+        let push_mock = mock_server.mock(|when, then| {
+            when.method(POST).path("/sync/v5/queued_records");
+            then.status(200)
+                .json_body(json!({ "integrationStarted": true }));
+        });
+        mock_server.mock(|when, then| {
+            when.method(GET).path("/sync/v5/queued_records");
+            then.status(200).json_body(json!({
+                "queueLength": 3,
+                "data": [
+                    { "syncOutId": "remote", "tableName": "test", "recordId": "remote", "action": "delete" }
+                ]
+            }));
+        });
+        mock_server.mock(|when, then| {
+            when.method(GET)
+                .path("/sync/v5/central_records")
+                .query_param("cursor", "10");
+            then.status(200).json_body(json!({
+                "maxCursor": 12,
+                "data": [
+                    { "ID": 12, "tableName": "test", "recordId": "central", "action": "delete" }
+                ]
+            }));
+        });
+        mock_server.mock(|when, then| {
+            when.method(GET)
+                .path("/sync/v5/central_records")
+                .query_param("cursor", "12");
+            then.status(200)
+                .json_body(json!({ "maxCursor": 12, "data": [] }));
+        });
+
+        // Package of another site
+        assert!(matches!(
+            create_central_package(
+                central_api_settings.clone(),
+                &push_package(6),
+                &password_sha256
+            )
+            .await,
+            Err(SyncPackageError::WrongSite { .. })
+        ));
+
+        let export =
+            create_central_package(central_api_settings, &push_package(5), &password_sha256)
+                .await
+                .unwrap();
+        site_info_mock.assert_hits(2);
+        acknowledged_mock.assert();
+        push_mock.assert();
+
+        assert_eq!(export.number_of_records, 2);
+        let package = SyncPackage::from_file(&export.data, &password_sha256).unwrap();
+        assert_eq!(package.site_id, 5);
+        let SyncPackageContent::Central {
+            sequence,
+            acknowledged_push_end_cursor,
+            records,
+            remaining,
+            central_records,
+            central_pull_cursor,
+        } = package.content
+        else {
+            panic!("Expected central package");
+        };
+        assert_eq!(sequence, 4);
+        assert_eq!(acknowledged_push_end_cursor, Some(20));
+        assert_eq!(records[0].sync_id, "remote");
+        assert_eq!(remaining, 2);
+        assert_eq!(central_records[0].record.record_id, "central");
+        assert_eq!(central_pull_cursor, 12);
+    }
+}
@@ -6,7 +6,7 @@ use thiserror::Error;
 use util::format_error;
 
 use crate::sync::{
-    api::{SyncApiErrorVariantV5, SyncErrorCodeV5},
+    api::{SyncApiErrorVariantV5, SyncErrorCodeV5, SyncTrafficCount},
     api_v6::{SyncApiErrorVariantV6, SyncApiV6CreatingError, SyncParsedErrorV6},
     central_data_synchroniser::CentralPullError,
     central_data_synchroniser_v6::{
@@ -19,6 +19,7 @@ use crate::sync::{
 };
 
 use super::SyncLogError;
+use crate::{log_context::LogContext, u64_to_i64};
 
 #[derive(Debug)]
 pub(crate) enum SyncStep {
@@ -63,12 +64,15 @@ impl SyncLoggerError {
 
 impl<'a> SyncLogger<'a> {
     pub fn start(connection: &'a StorageConnection) -> Result<SyncLogger, SyncLoggerError> {
-        info!("Sync started");
         let row = SyncLogRow {
             id: util::uuid::uuid(),
             started_datetime: chrono::Utc::now().naive_utc(),
             ..Default::default()
         };
+        // Log entries of the sync run can be matched with the sync log
+        LogContext::update(|context| context.correlation_id = row.id.clone());
+
+        info!("Sync started");
 
         let sync_log_repo = SyncLogRowRepository::new(connection);
         sync_log_repo.upsert_one(&row)?;
@@ -87,6 +91,7 @@ impl<'a> SyncLogger<'a> {
     }
 
     pub(crate) fn start_step(&mut self, step: SyncStep) -> Result<(), SyncLoggerError> {
+        LogContext::update(|context| context.step = Some(format!("{:?}", step)));
         info!("Sync step started {:?}", step);
         self.row = match step {
             SyncStep::PrepareInitial => SyncLogRow {
@@ -298,6 +303,51 @@ impl<'a> SyncLogger<'a> {
         self.sync_log_repo.upsert_one(&self.row)?;
         Ok(())
     }
+
+    /// Records bytes transferred by sync api requests during the step, steps that don't
+    /// call sync api are ignored
+    pub(crate) fn transferred_bytes(
+        &mut self,
+        step: SyncStep,
+        traffic: SyncTrafficCount,
+    ) -> Result<(), SyncLoggerError> {
+        let SyncTrafficCount {
+            transferred_bytes,
+            uncompressed_bytes,
+        } = traffic;
+        info!(
+            "Transferred ({}) bytes, ({}) bytes uncompressed",
+            transferred_bytes, uncompressed_bytes
+        );
+        let transferred_bytes = Some(u64_to_i64(transferred_bytes));
+
+        self.row = match step {
+            SyncStep::PrepareInitial | SyncStep::Integrate => return Ok(()),
+            SyncStep::Push => SyncLogRow {
+                push_transferred_bytes: transferred_bytes,
+                ..self.row.clone()
+            },
+            SyncStep::PullCentral => SyncLogRow {
+                pull_central_transferred_bytes: transferred_bytes,
+                ..self.row.clone()
+            },
+            SyncStep::PullRemote => SyncLogRow {
+                pull_remote_transferred_bytes: transferred_bytes,
+                ..self.row.clone()
+            },
+            SyncStep::PullCentralV6 => SyncLogRow {
+                pull_v6_transferred_bytes: transferred_bytes,
+                ..self.row.clone()
+            },
+            SyncStep::PushCentralV6 => SyncLogRow {
+                push_v6_transferred_bytes: transferred_bytes,
+                ..self.row.clone()
+            },
+        };
+
+        self.sync_log_repo.upsert_one(&self.row)?;
+        Ok(())
+    }
 }
 
 impl SyncLogError {
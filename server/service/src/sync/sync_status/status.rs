@@ -7,7 +7,7 @@ use util::Defaults;
 
 use crate::{
     cursor_controller::CursorController,
-    i32_to_u32,
+    i32_to_u32, i64_to_u64,
     service_provider::ServiceContext,
     settings_service::{SettingsService, SettingsServiceTrait},
     sync::{get_sync_push_changelogs_filter, GetActiveStoresOnSiteError},
@@ -28,6 +28,8 @@ pub struct SyncStatusWithProgress {
     pub finished: Option<NaiveDateTime>,
     pub total: Option<u32>,
     pub done: Option<u32>,
+    /// Request and response body bytes sent over the network, not recorded for integration
+    pub transferred_bytes: Option<u64>,
 }
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -78,6 +80,11 @@ impl FullSyncStatus {
             push_v6_progress_done,
             integration_progress_total,
             integration_progress_done,
+            push_transferred_bytes,
+            pull_central_transferred_bytes,
+            pull_remote_transferred_bytes,
+            pull_v6_transferred_bytes,
+            push_v6_transferred_bytes,
         } = sync_log_row;
         let error = SyncLogError::from_sync_log_row(&sync_log_row);
 
@@ -97,36 +104,42 @@ impl FullSyncStatus {
                 finished: integration_finished_datetime,
                 total: integration_progress_total.map(i32_to_u32),
                 done: integration_progress_done.map(i32_to_u32),
+                transferred_bytes: None,
             }),
             pull_central: pull_central_started_datetime.map(|started| SyncStatusWithProgress {
                 started,
                 finished: pull_central_finished_datetime,
                 total: pull_central_progress_total.map(i32_to_u32),
                 done: pull_central_progress_done.map(i32_to_u32),
+                transferred_bytes: pull_central_transferred_bytes.map(i64_to_u64),
             }),
             pull_remote: pull_remote_started_datetime.map(|started| SyncStatusWithProgress {
                 started,
                 finished: pull_remote_finished_datetime,
                 total: pull_remote_progress_total.map(i32_to_u32),
                 done: pull_remote_progress_done.map(i32_to_u32),
+                transferred_bytes: pull_remote_transferred_bytes.map(i64_to_u64),
             }),
             push: push_started_datetime.map(|started| SyncStatusWithProgress {
                 started,
                 finished: push_finished_datetime,
                 total: push_progress_total.map(i32_to_u32),
                 done: push_progress_done.map(i32_to_u32),
+                transferred_bytes: push_transferred_bytes.map(i64_to_u64),
             }),
             pull_v6: pull_v6_started_datetime.map(|started| SyncStatusWithProgress {
                 started,
                 finished: pull_v6_finished_datetime,
                 total: pull_v6_progress_total.map(i32_to_u32),
                 done: pull_v6_progress_done.map(i32_to_u32),
+                transferred_bytes: pull_v6_transferred_bytes.map(i64_to_u64),
             }),
             push_v6: push_v6_started_datetime.map(|started| SyncStatusWithProgress {
                 started,
                 finished: push_v6_finished_datetime,
                 total: push_v6_progress_total.map(i32_to_u32),
                 done: push_v6_progress_done.map(i32_to_u32),
+                transferred_bytes: push_v6_transferred_bytes.map(i64_to_u64),
             }),
         }
     }
@@ -575,6 +575,8 @@ async fn run_server_and_sync(
             remote_push: 1,
             central_pull: 1,
         },
+        priority_store_ids: Vec::new(),
+        transactional_sync_v6: false,
     };
 
     let synchroniser =
@@ -628,6 +630,7 @@ async fn empty_v6_server(port: u16) -> Server {
     async fn empty_status_response() -> impl Responder {
         web::Json(SiteStatusResponseV6::Data(SiteStatusV6 {
             is_integrating: false,
+            supports_file_chunks: true,
         }))
     }
     HttpServer::new(move || {
@@ -1,26 +1,30 @@
 use crate::{
+    log_context::LogContext,
     service_provider::{ServiceContext, ServiceProvider},
     sync::{sync_status::logger::SyncStep, CentralServerConfig},
 };
 use log::warn;
-use repository::{RepositoryError, StorageConnection, SyncAction};
+use repository::{EqualFilter, RepositoryError, StorageConnection, SyncAction};
 
 use std::sync::Arc;
 use thiserror::Error;
 use util::format_error;
 
 use super::{
-    api::{SyncApiError, SyncApiSettings, SyncApiV5},
+    adaptive_sync::SyncBatch,
+    api::{SyncApiError, SyncApiSettings, SyncApiV5, SyncTraffic},
     api_v6::SyncApiV6CreatingError,
     central_data_synchroniser::{CentralDataSynchroniser, CentralPullError},
     central_data_synchroniser_v6::{
         CentralPullErrorV6, RemotePushErrorV6, SynchroniserV6, WaitForSyncOperationErrorV6,
     },
+    conflicts::save_conflict_push_cursor,
     remote_data_synchroniser::{
         PostInitialisationError, RemoteDataSynchroniser, RemotePullError, RemotePushError,
         WaitForSyncOperationError,
     },
     settings::{SyncSettings, SYNC_VERSION},
+    store_backfill::{backfill_store_ids, integrate_prioritised, spawn_backfill_stores},
     sync_buffer::SyncBuffer,
     sync_status::logger::{SyncLogger, SyncLoggerError},
     translation_and_integration::{TranslationAndIntegration, TranslationAndIntegrationResults},
@@ -29,12 +33,17 @@ use super::{
 
 const INTEGRATION_POLL_PERIOD_SECONDS: u64 = 1;
 const INTEGRATION_TIMEOUT_SECONDS: u64 = 30;
+/// v6 records can be large (e.g. with files), v6 pull batches are at most this size even when
+/// central pull batch size is larger
+const MAX_V6_PULL_BATCH_SIZE: u32 = 20;
 pub struct Synchroniser {
     settings: SyncSettings,
     service_provider: Arc<ServiceProvider>,
     central: CentralDataSynchroniser,
     sync_v5_settings: SyncApiSettings,
     remote: RemoteDataSynchroniser,
+    // Shared by v5 and v6 sync apis, collected for each sync step
+    traffic: SyncTraffic,
 }
 
 #[derive(Error)]
@@ -69,6 +78,32 @@ pub(crate) enum SyncError {
     IntegrationError(RepositoryError),
 }
 
+impl SyncError {
+    /// Batch that timed out or was too large, its batch size should be reduced
+    fn oversized_batch(&self) -> Option<SyncBatch> {
+        let (batch, is_oversized) = match self {
+            SyncError::CentralPullError(CentralPullError::SyncApiError(error)) => {
+                (SyncBatch::CentralPull, error.is_oversized_batch())
+            }
+            SyncError::CentralPullErrorV6(CentralPullErrorV6::SyncApiError(error)) => {
+                (SyncBatch::CentralPull, error.is_oversized_batch())
+            }
+            SyncError::RemotePullError(RemotePullError::SyncApiError(error)) => {
+                (SyncBatch::RemotePull, error.is_oversized_batch())
+            }
+            SyncError::RemotePushError(RemotePushError::SyncApiError(error)) => {
+                (SyncBatch::RemotePush, error.is_oversized_batch())
+            }
+            SyncError::RemotePushErrorV6(RemotePushErrorV6::SyncApiError(error)) => {
+                (SyncBatch::RemotePush, error.is_oversized_batch())
+            }
+            _ => return None,
+        };
+
+        is_oversized.then_some(batch)
+    }
+}
+
 // For unwrap and expect debug implementation is used
 impl std::fmt::Debug for SyncError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -116,7 +151,8 @@ impl Synchroniser {
         sync_version: u32,
     ) -> anyhow::Result<Self> {
         let sync_v5_settings = SyncApiV5::new_settings(&settings, &service_provider, sync_version)?;
-        let sync_api_v5 = SyncApiV5::new(sync_v5_settings.clone())?;
+        let traffic = SyncTraffic::default();
+        let sync_api_v5 = SyncApiV5::new(sync_v5_settings.clone())?.with_traffic(&traffic);
         Ok(Synchroniser {
             remote: RemoteDataSynchroniser {
                 sync_api_v5: sync_api_v5.clone(),
@@ -125,14 +161,21 @@ impl Synchroniser {
             service_provider,
             central: CentralDataSynchroniser { sync_api_v5 },
             sync_v5_settings,
+            traffic,
         })
     }
 
     pub(crate) async fn sync(&self) -> Result<(), SyncError> {
+        // Log entries of this sync run (and processors triggered by it) share a correlation id
+        LogContext::new("sync").scope(self.sync_logged()).await
+    }
+
+    async fn sync_logged(&self) -> Result<(), SyncError> {
         let ctx = self.service_provider.basic_context()?;
         let mut logger = SyncLogger::start(&ctx.connection)?;
 
         let sync_result = self.sync_inner(&mut logger, &ctx).await;
+        self.adapt_to_result(&sync_result);
 
         if let Err(error) = &sync_result {
             logger.error(error)?;
@@ -143,13 +186,33 @@ impl Synchroniser {
         Ok(())
     }
 
+    /// Batch sizes and retry interval used by the next sync
+    fn adapt_to_result(&self, sync_result: &Result<(), SyncError>) {
+        let SyncSettings {
+            batch_size,
+            interval_seconds,
+            ..
+        } = &self.settings;
+        let adaptive_sync = &self.service_provider.adaptive_sync;
+
+        match sync_result {
+            Ok(_) => adaptive_sync.sync_succeeded(batch_size),
+            Err(error) => {
+                adaptive_sync.sync_failed(batch_size, error.oversized_batch(), *interval_seconds)
+            }
+        }
+    }
+
     /// Sync must not be called concurrently (e.g. sync cursors are fetched/updated without DB tx)
     async fn sync_inner<'a>(
         &self,
         logger: &mut SyncLogger<'a>,
         ctx: &'a ServiceContext,
     ) -> Result<(), SyncError> {
-        let batch_size = &self.settings.batch_size;
+        let batch_size = &self
+            .service_provider
+            .adaptive_sync
+            .batch_size(&self.settings.batch_size);
         let sync_status_service = &self.service_provider.sync_status_service;
 
         if self.service_provider.settings.is_sync_disabled(&ctx)? {
@@ -186,11 +249,17 @@ impl Synchroniser {
             CentralServerConfig::NotConfigured => return Err(SyncError::V6NotConfigured),
             CentralServerConfig::IsCentralServer => None,
             CentralServerConfig::CentralServerUrl(url) => {
-                let v6_sync = SynchroniserV6::new(&url, &self.sync_v5_settings)?;
+                let v6_sync = SynchroniserV6::new(&url, &self.sync_v5_settings, &self.traffic)?;
                 Some(v6_sync)
             }
         };
 
+        // Traffic is recorded per step, from here on
+        self.traffic.take();
+
+        // Local changes that are pushed from here on can conflict with pulled central records
+        save_conflict_push_cursor(&ctx.connection)?;
+
         // PUSH V6
         logger.start_step(SyncStep::PushCentralV6)?;
         if let (true, Some(v6_sync)) = (is_initialised, &v6_sync) {
@@ -205,6 +274,7 @@ impl Synchroniser {
                 )
                 .await?;
         }
+        logger.transferred_bytes(SyncStep::PushCentralV6, self.traffic.take())?;
         logger.done_step(SyncStep::PushCentralV6)?;
 
         // PUSH
@@ -221,6 +291,7 @@ impl Synchroniser {
                 )
                 .await?;
         }
+        logger.transferred_bytes(SyncStep::Push, self.traffic.take())?;
         logger.done_step(SyncStep::Push)?;
 
         // PULL CENTRAL
@@ -228,6 +299,7 @@ impl Synchroniser {
         self.central
             .pull(&ctx.connection, batch_size.central_pull, logger)
             .await?;
+        logger.transferred_bytes(SyncStep::PullCentral, self.traffic.take())?;
         logger.done_step(SyncStep::PullCentral)?;
 
         // PULL REMOTE
@@ -236,6 +308,7 @@ impl Synchroniser {
             .pull(&ctx.connection, batch_size.remote_pull, logger)
             .await?;
 
+        logger.transferred_bytes(SyncStep::PullRemote, self.traffic.take())?;
         logger.done_step(SyncStep::PullRemote)?;
 
         // PULL V6
@@ -243,26 +316,31 @@ impl Synchroniser {
             logger.start_step(SyncStep::PullCentralV6)?;
 
             v6_sync
-                .pull(&ctx.connection, 20, is_initialised, logger)
+                .pull(
+                    &ctx.connection,
+                    batch_size.central_pull.min(MAX_V6_PULL_BATCH_SIZE),
+                    is_initialised,
+                    logger,
+                )
                 .await?;
 
+            logger.transferred_bytes(SyncStep::PullCentralV6, self.traffic.take())?;
             logger.done_step(SyncStep::PullCentralV6)?;
         }
 
         // INTEGRATE RECORDS
         logger.start_step(SyncStep::Integrate)?;
 
-        let (upserts, deletes, merges) = integrate_and_translate_sync_buffer(
+        let (upserts, deletes, merges) = integrate_prioritised(
             &ctx.connection,
             is_initialised,
+            &self.settings.priority_store_ids,
             // Only pass in logger during initialisation
             match is_initialised {
-                false => Some(logger),
+                false => Some(&mut *logger),
                 true => None,
             },
-            None,
-        )
-        .map_err(SyncError::IntegrationError)?;
+        )?;
 
         warn!("Upsert Integration result: {:?}", upserts);
         warn!("Delete Integration result: {:?}", deletes);
@@ -281,6 +359,13 @@ impl Synchroniser {
         ctx.processors_trigger
             .trigger_requisition_transfer_processors();
         ctx.processors_trigger.trigger_invoice_transfer_processors();
+        ctx.processors_trigger.trigger_audit_log_processor();
+
+        // Stores queued for backfill during initialisation are integrated after it, in the
+        // background so that sync isn't held up
+        if is_initialised && !backfill_store_ids(&ctx.connection)?.is_empty() {
+            spawn_backfill_stores(self.service_provider.clone());
+        }
 
         Ok(())
     }
@@ -299,6 +384,30 @@ pub fn integrate_and_translate_sync_buffer<'a>(
         TranslationAndIntegrationResults,
     ),
     RepositoryError,
+> {
+    integrate_and_translate_sync_buffer_for_stores(
+        connection,
+        execute_in_transaction,
+        logger,
+        source_site_id,
+        None,
+    )
+}
+
+/// Translation And Integration of sync buffer records matching `store_id` filter (see store_backfill)
+pub(crate) fn integrate_and_translate_sync_buffer_for_stores<'a>(
+    connection: &StorageConnection,
+    execute_in_transaction: bool,
+    logger: Option<&mut SyncLogger<'a>>,
+    source_site_id: Option<i32>,
+    store_id: Option<EqualFilter<String>>,
+) -> Result<
+    (
+        TranslationAndIntegrationResults,
+        TranslationAndIntegrationResults,
+        TranslationAndIntegrationResults,
+    ),
+    RepositoryError,
 > {
     // Integration is done inside a transaction, to make sure all records are available at the same time
     // and maintain logical data integrity. During initialisation nested transactions cause significant
@@ -320,7 +429,10 @@ pub fn integrate_and_translate_sync_buffer<'a>(
         let translators = all_translators();
         let table_order = pull_integration_order(&translators);
 
-        let sync_buffer = SyncBuffer::new(connection);
+        let sync_buffer = match &store_id {
+            Some(store_id) => SyncBuffer::new(connection).store_id(store_id.clone()),
+            None => SyncBuffer::new(connection),
+        };
         let translation_and_integration = TranslationAndIntegration::new(connection, &sync_buffer);
 
         // Translate and integrate upserts (ordered by referential database constraints)
@@ -384,9 +496,13 @@ pub fn integrate_and_translate_sync_buffer<'a>(
 
 #[cfg(test)]
 mod tests {
+    use httpmock::{Method::POST, MockServer};
     use repository::mock::MockDataInserts;
+    use reqwest::Url;
     use util::{assert_matches, inline_init};
 
+    use crate::sync::api_v6::{SyncApiErrorV6, SyncApiErrorVariantV6};
+
     use crate::test_helpers::{setup_all_and_service_provider, ServiceTestContext};
 
     use super::*;
@@ -416,4 +532,37 @@ mod tests {
 
         assert_matches!(s.sync().await, Ok(_));
     }
+
+    #[actix_rt::test]
+    async fn test_oversized_batch_v6() {
+        let mock_server = MockServer::start();
+        mock_server.mock(|when, then| {
+            when.method(POST).path("/central/sync/pull");
+            then.status(200).delay(std::time::Duration::from_secs(1));
+        });
+        let url = Url::parse(&mock_server.url("/central/sync/pull")).unwrap();
+
+        let timeout = reqwest::Client::new()
+            .post(url.clone())
+            .timeout(std::time::Duration::from_millis(10))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(timeout.is_timeout());
+        let error =
+            SyncError::CentralPullErrorV6(CentralPullErrorV6::SyncApiError(SyncApiErrorV6 {
+                source: SyncApiErrorVariantV6::ConnectionError(timeout),
+                url: url.clone(),
+                route: "pull".to_string(),
+            }));
+        assert_eq!(error.oversized_batch(), Some(SyncBatch::CentralPull));
+
+        let error =
+            SyncError::CentralPullErrorV6(CentralPullErrorV6::SyncApiError(SyncApiErrorV6 {
+                source: SyncApiErrorVariantV6::Other(anyhow::anyhow!("not a timeout")),
+                url,
+                route: "pull".to_string(),
+            }));
+        assert_eq!(error.oversized_batch(), None);
+    }
 }
@@ -24,6 +24,7 @@ pub struct SyncTrigger {
 /// Used to 'drive' synchronisation, it's tasks:
 /// * Expose channel for manually triggering sync
 /// * Trigger sync every SyncSettings.interval_seconds (only when initialised)
+/// * After failed sync, retry with exponential backoff instead (see `AdaptiveSync`)
 impl SynchroniserDriver {
     pub fn init(file_sync_trigger: FileSyncTrigger) -> (SyncTrigger, SynchroniserDriver) {
         // We use a single-element channel so that we can only have one sync pending at a time.
@@ -49,7 +50,7 @@ impl SynchroniserDriver {
     /// Operations:
     /// * Try to sync if already initialise or if `force_run`
     /// * In loop
-    ///    * If initialised await for manual trigger OR interval sec timeout
+    ///    * If initialised await for manual trigger OR interval sec timeout (retry interval after failed sync)
     ///    * If not initialised await only for manual trigger
     ///    * do sync if any of the above were triggered
     pub async fn run(mut self, service_provider: Arc<ServiceProvider>, force_run: bool) {
@@ -63,11 +64,13 @@ impl SynchroniserDriver {
                 tokio::select! {
                     // Wait for trigger
                     Some(_) = self.receiver.recv() => {},
-                    // OR wait for SyncSettings.interval_seconds
+                    // OR wait for SyncSettings.interval_seconds (or retry interval after failed sync)
                     _ = async {
                         // Need to get interval_seconds from database on every iteration, since it could have been updated
-                        let sync_settings = get_sync_settings(&service_provider);
-                        let duration = Duration::from_secs(sync_settings.interval_seconds);
+                        let duration = service_provider.adaptive_sync.retry_in().unwrap_or_else(|| {
+                            let sync_settings = get_sync_settings(&service_provider);
+                            Duration::from_secs(sync_settings.interval_seconds)
+                        });
                         tokio::time::sleep(duration).await;
                      } => {},
                     else => break,
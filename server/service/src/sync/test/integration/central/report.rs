@@ -13,53 +13,73 @@ pub(crate) struct ReportTester;
 impl SyncRecordTester for ReportTester {
     fn test_step_data(&self, _: &NewSiteProperties) -> Vec<TestStepData> {
         let mut result = Vec::new();
+        // synced report definitions are validated
+        let template = json!({
+            "index": { "template": "template.html", "query": "query" },
+            "entries": {
+                "template.html": {
+                    "type": "TeraTemplate",
+                    "data": { "output": "Html", "template": "{{ data.id }}" }
+                },
+                "query": { "type": "DefaultQuery", "data": "Invoice" }
+            }
+        })
+        .to_string();
         // STEP 1 - insert
         let report_row1 = ReportRow {
             id: uuid(),
             name: uuid(),
             r#type: ReportType::OmSupply,
-            template: "".to_string(),
+            template: template.clone(),
             context: ContextType::InboundShipment,
             comment: Some(uuid()),
             sub_context: None,
             argument_schema_id: None,
+            version: None,
         };
         let report_json1 = json!({
             "ID": report_row1.id,
             "report_name":  report_row1.name,
             "editor": "omsupply",
             "context": "Supplier Invoice",
+            "template": template,
             "Comment": report_row1.comment.as_ref().unwrap()
         });
 
         let report_row2 = inline_init(|r: &mut ReportRow| {
             r.id = uuid();
+            r.template = template.clone();
             r.context = ContextType::OutboundShipment
         });
         let report_json2 = json!({
             "ID": report_row2.id,
             "editor": "omsupply",
             "context": "Customer Invoice",
+            "template": template,
         });
 
         let report_row3 = inline_init(|r: &mut ReportRow| {
             r.id = uuid();
+            r.template = template.clone();
             r.context = ContextType::Requisition
         });
         let report_json3 = json!({
             "ID": report_row3.id,
             "editor": "omsupply",
             "context": "Requisition",
+            "template": template,
         });
 
         let report_row4 = inline_init(|r: &mut ReportRow| {
             r.id = uuid();
+            r.template = template.clone();
             r.context = ContextType::Stocktake
         });
         let report_json4 = json!({
             "ID": report_row4.id,
             "editor": "omsupply",
             "context": "Stock Take",
+            "template": template,
         });
 
         // TODO Resource ? There is not translations for it
@@ -69,7 +69,6 @@ impl SyncApiV5 {
             .await?;
 
         let site_response = to_json::<CreateSyncSiteResponse>(response)
-            .await
             .map_err(|error| self.api_error(route, error.into()))?;
 
         let check_site_api = SyncApiV5 {
@@ -152,6 +151,8 @@ impl ConfigureCentralServer {
                 // fresh data file has 230 central change logs
                 // and a small number makes integration tests super slow
                 batch_size: Default::default(),
+                priority_store_ids: Vec::new(),
+                transactional_sync_v6: false,
             },
             new_site_properties,
         })
@@ -0,0 +1,155 @@
+use std::{sync::Arc, time::Duration};
+
+use repository::{mock::MockDataInserts, ItemRow, StorageConnection, StoreRowRepository};
+use serde_json::json;
+use util::{inline_init, uuid::uuid};
+
+use crate::{
+    processors::transfer::requisition::test::RequisitionTransferTester,
+    service_provider::ServiceProvider,
+    sync::{
+        mock_central::{start_mock_central, MockCentral, MockSite},
+        settings::SyncSettings,
+        synchroniser::Synchroniser,
+    },
+    test_helpers::{setup_all_and_service_provider, ServiceTestContext},
+};
+
+struct MockSiteContext {
+    connection: StorageConnection,
+    service_provider: Arc<ServiceProvider>,
+    synchroniser: Synchroniser,
+}
+
+async fn init_site(
+    url: &str,
+    site: &MockSite,
+    identifier: &str,
+) -> (MockSiteContext, tokio::task::JoinHandle<()>) {
+    let ServiceTestContext {
+        connection,
+        service_provider,
+        processors_task,
+        service_context,
+        ..
+    } = setup_all_and_service_provider(identifier, MockDataInserts::none()).await;
+
+    let sync_settings = SyncSettings {
+        url: url.to_string(),
+        username: site.name.clone(),
+        password_sha256: site.password_sha256.clone(),
+        interval_seconds: 10000000,
+        batch_size: Default::default(),
+        priority_store_ids: Vec::new(),
+        transactional_sync_v6: false,
+    };
+
+    service_provider
+        .site_info_service
+        .request_and_set_site_info(&service_provider, &sync_settings)
+        .await
+        .unwrap();
+    service_provider
+        .settings
+        .update_sync_settings(&service_context, &sync_settings)
+        .unwrap();
+
+    let synchroniser = Synchroniser::new(sync_settings, service_provider.clone().into()).unwrap();
+
+    (
+        MockSiteContext {
+            connection,
+            service_provider,
+            synchroniser,
+        },
+        processors_task,
+    )
+}
+
+async fn sync_and_delay(site_1: &MockSiteContext, site_2: &MockSiteContext) {
+    site_1.synchroniser.sync().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    site_2.synchroniser.sync().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+}
+
+#[actix_rt::test]
+async fn mock_central_requisition_transfer() {
+    let ServiceTestContext {
+        service_provider: central_service_provider,
+        ..
+    } = setup_all_and_service_provider("mock_central_transfer", MockDataInserts::none()).await;
+
+    let mock_central = MockCentral::new(central_service_provider);
+    let request_site = mock_central.create_site(None, None, vec![]).unwrap();
+    let response_site = mock_central
+        .create_site(None, None, vec![request_site.name_id.clone()])
+        .unwrap();
+
+    let item1 = inline_init(|r: &mut ItemRow| {
+        r.id = uuid();
+    });
+    let item2 = inline_init(|r: &mut ItemRow| {
+        r.id = uuid();
+    });
+    mock_central
+        .upsert_records(json!({
+            "name_store_join": [
+                {"ID": uuid(), "name_ID": response_site.name_id, "store_ID": request_site.store_id},
+                {"ID": uuid(), "name_ID": request_site.name_id, "store_ID": response_site.store_id}
+            ],
+            "item": [
+                {"ID": item1.id},
+                {"ID": item2.id}
+            ]
+        }))
+        .unwrap();
+
+    let (port, server) = start_mock_central(mock_central, 0).unwrap();
+    actix_rt::spawn(server);
+    let url = format!("http://127.0.0.1:{}", port);
+
+    let (request_context, request_processors_task) =
+        init_site(&url, &request_site, "mock_central_transfer_request").await;
+    let (response_context, response_processors_task) =
+        init_site(&url, &response_site, "mock_central_transfer_response").await;
+
+    let test = async move {
+        request_context.synchroniser.sync().await.unwrap();
+        response_context.synchroniser.sync().await.unwrap();
+
+        let store_repository = StoreRowRepository::new(&request_context.connection);
+        let request_store = store_repository
+            .find_one_by_id(&request_site.store_id)
+            .unwrap()
+            .unwrap();
+        let response_store = store_repository
+            .find_one_by_id(&response_site.store_id)
+            .unwrap()
+            .unwrap();
+
+        let mut tester =
+            RequisitionTransferTester::new(&request_store, &response_store, &item1, &item2);
+
+        tester.insert_request_requisition(&request_context.connection);
+        sync_and_delay(&request_context, &response_context).await;
+        tester.check_response_requisition_not_created(&response_context.connection);
+
+        tester.update_request_requisition_to_sent(&request_context.service_provider);
+        sync_and_delay(&request_context, &response_context).await;
+        tester.check_response_requisition_created(&response_context.connection);
+
+        sync_and_delay(&response_context, &request_context).await;
+        tester.check_request_requisition_was_linked(&request_context.connection);
+
+        tester.update_response_requisition_to_finalised(&response_context.service_provider);
+        sync_and_delay(&response_context, &request_context).await;
+        tester.check_request_requisition_status_updated(&request_context.connection);
+    };
+
+    tokio::select! {
+        Err(err) = request_processors_task => unreachable!("{}", err),
+        Err(err) = response_processors_task => unreachable!("{}", err),
+        _ = test => (),
+    };
+}
@@ -1,6 +1,7 @@
 #[cfg(feature = "integration_test")]
 mod integration;
 pub(crate) mod merge_helpers;
+mod mock_central;
 mod pull_and_push;
 pub(crate) mod test_data;
 
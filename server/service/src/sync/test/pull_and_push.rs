@@ -12,8 +12,10 @@ use crate::sync::{
 };
 use repository::{
     mock::{mock_store_b, MockData, MockDataInserts},
-    test_db, ChangelogRepository, KeyType, KeyValueStoreRow, SyncBufferRow,
-    SyncBufferRowRepository,
+    test_db, ChangelogRepository, ChangelogRow, ChangelogTableName, InvoiceLineRowRepository,
+    InvoiceRowRepository, KeyType, KeyValueStoreRow, RequisitionLineRowRepository,
+    RequisitionRowRepository, StockLineRowRepository, StocktakeLineRowRepository,
+    StocktakeRowRepository, StorageConnection, SyncBufferRow, SyncBufferRowRepository,
 };
 use util::inline_init;
 
@@ -23,6 +25,7 @@ use super::{
         get_all_pull_delete_central_test_records, get_all_pull_delete_remote_test_records,
         get_all_pull_upsert_central_test_records, get_all_pull_upsert_remote_test_records,
     },
+    TestSyncIncomingRecord,
 };
 
 #[actix_rt::test]
@@ -83,6 +86,10 @@ async fn test_sync_pull_and_push() {
     let changelogs = ChangelogRepository::new(&connection)
         .changelogs(push_cursor, 100000, None /*change_log_filter*/)
         .unwrap();
+    // Transactional records are only pulled from omSupply central when a site uses transactional
+    // v6 sync, om_* translation of the same fixtures is checked in test_sync_transactional_v6_parity
+    // and test_sync_transactional_v6_round_trip
+    let (_, central_changelogs) = split_transactional(changelogs.clone());
     // Translate
     let mut translated = vec![
         translate_changelogs_to_sync_records(
@@ -93,7 +100,7 @@ async fn test_sync_pull_and_push() {
         .unwrap(),
         translate_changelogs_to_sync_records(
             &connection,
-            changelogs.clone(),
+            central_changelogs,
             ToSyncRecordTranslationType::PullFromOmSupplyCentral,
         )
         .unwrap(),
@@ -151,3 +158,289 @@ async fn test_sync_pull_and_push() {
     // PUSH DELETE
     // TODO
 }
+
+fn split_transactional(changelogs: Vec<ChangelogRow>) -> (Vec<ChangelogRow>, Vec<ChangelogRow>) {
+    let transactional_tables = ChangelogTableName::transactional_sync_tables();
+    changelogs
+        .into_iter()
+        .partition(|changelog| transactional_tables.contains(&changelog.table_name))
+}
+
+fn translate_transactional_v6(connection: &StorageConnection, cursor: u64) -> Vec<PushSyncRecord> {
+    let changelogs = ChangelogRepository::new(connection)
+        .changelogs(cursor, 100000, None)
+        .unwrap();
+    let (transactional_changelogs, _) = split_transactional(changelogs);
+
+    let mut translated = translate_changelogs_to_sync_records(
+        connection,
+        transactional_changelogs,
+        ToSyncRecordTranslationType::PushToOmSupplyCentral,
+    )
+    .unwrap();
+    translated.sort_by(|a, b| {
+        (&a.record.table_name, &a.record.record_id)
+            .cmp(&(&b.record.table_name, &b.record.record_id))
+    });
+    translated
+}
+
+/// Transactional records pushed to omSupply central should integrate on another site and push
+/// the same data again
+#[actix_rt::test]
+async fn test_sync_transactional_v6_round_trip() {
+    let (_, connection, _, _) = test_db::setup_all_with_data(
+        "test_sync_transactional_v6_round_trip",
+        MockDataInserts::all(),
+        inline_init(|r: &mut MockData| {
+            r.key_value_store_rows = vec![inline_init(|r: &mut KeyValueStoreRow| {
+                r.id = KeyType::SettingsSyncSiteId;
+                r.value_int = Some(mock_store_b().site_id);
+            })]
+        }),
+    )
+    .await;
+
+    let push_cursor = ChangelogRepository::new(&connection)
+        .latest_cursor()
+        .unwrap()
+        + 1;
+
+    let test_records = vec![
+        get_all_pull_upsert_central_test_records(),
+        get_all_pull_upsert_remote_test_records(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    insert_all_extra_data(&test_records, &connection).await;
+    SyncBufferRowRepository::new(&connection)
+        .upsert_many(&extract_sync_buffer_rows(&test_records))
+        .unwrap();
+    integrate_and_translate_sync_buffer(&connection, true, None, None).unwrap();
+
+    let pushed = translate_transactional_v6(&connection, push_cursor);
+    assert!(pushed
+        .iter()
+        .any(|r| r.record.table_name == "om_invoice_line"));
+
+    // Integrate pushed records as if pulled from omSupply central
+    let round_trip_cursor = ChangelogRepository::new(&connection)
+        .latest_cursor()
+        .unwrap()
+        + 1;
+    let buffer_rows: Vec<SyncBufferRow> = pushed
+        .iter()
+        .map(|r| r.record.clone().to_buffer_row(None).unwrap())
+        .collect();
+    SyncBufferRowRepository::new(&connection)
+        .upsert_many(&buffer_rows)
+        .unwrap();
+    integrate_and_translate_sync_buffer(&connection, true, None, None).unwrap();
+
+    assert_no_om_integration_errors(&connection);
+
+    let round_trip = translate_transactional_v6(&connection, round_trip_cursor);
+    assert_eq!(
+        round_trip.iter().map(|r| &r.record).collect::<Vec<_>>(),
+        pushed.iter().map(|r| &r.record).collect::<Vec<_>>()
+    );
+}
+
+fn assert_no_om_integration_errors(connection: &StorageConnection) {
+    let integration_errors: Vec<SyncBufferRow> = SyncBufferRowRepository::new(connection)
+        .get_all()
+        .unwrap()
+        .into_iter()
+        .filter(|r| r.table_name.starts_with("om_") && r.integration_error.is_some())
+        .collect();
+    assert_eq!(integration_errors, Vec::new());
+}
+
+/// Row must exist, so that missing rows on both sites don't pass as equal
+fn assert_same_row<T: PartialEq + std::fmt::Debug>(
+    table_name: &str,
+    id: &str,
+    legacy_row: Option<T>,
+    om_row: Option<T>,
+) {
+    assert!(
+        legacy_row.is_some(),
+        "{} {} was not integrated by legacy translators",
+        table_name,
+        id
+    );
+    assert_eq!(legacy_row, om_row, "{} {}", table_name, id);
+}
+
+/// Legacy tables of the records synced as om_* tables with transactional v6 sync
+const LEGACY_TRANSACTIONAL_TABLES: [&str; 7] = [
+    "transact",
+    "trans_line",
+    "item_line",
+    "Stock_take",
+    "Stock_take_lines",
+    "requisition",
+    "requisition_line",
+];
+
+async fn setup_transactional_v6(db_name: &str) -> StorageConnection {
+    let (_, connection, _, _) = test_db::setup_all_with_data(
+        db_name,
+        MockDataInserts::all(),
+        inline_init(|r: &mut MockData| {
+            r.key_value_store_rows = vec![inline_init(|r: &mut KeyValueStoreRow| {
+                r.id = KeyType::SettingsSyncSiteId;
+                r.value_int = Some(mock_store_b().site_id);
+            })]
+        }),
+    )
+    .await;
+    connection
+}
+
+async fn integrate_test_records(
+    connection: &StorageConnection,
+    test_records: &Vec<TestSyncIncomingRecord>,
+) {
+    insert_all_extra_data(test_records, connection).await;
+    SyncBufferRowRepository::new(connection)
+        .upsert_many(&extract_sync_buffer_rows(test_records))
+        .unwrap();
+    integrate_and_translate_sync_buffer(connection, true, None, None).unwrap();
+}
+
+/// Invoice, stock line, stocktake and requisition fixtures integrated through legacy translators
+/// and through om_* translators (on another site) should result in the same rows
+#[actix_rt::test]
+async fn test_sync_transactional_v6_parity() {
+    let legacy_connection =
+        setup_transactional_v6("test_sync_transactional_v6_parity_legacy").await;
+    let om_connection = setup_transactional_v6("test_sync_transactional_v6_parity_om").await;
+
+    let test_records: Vec<TestSyncIncomingRecord> = vec![
+        get_all_pull_upsert_central_test_records(),
+        get_all_pull_upsert_remote_test_records(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    // Legacy translators
+    let push_cursor = ChangelogRepository::new(&legacy_connection)
+        .latest_cursor()
+        .unwrap()
+        + 1;
+    integrate_test_records(&legacy_connection, &test_records).await;
+    let om_records = translate_transactional_v6(&legacy_connection, push_cursor);
+    for table_name in [
+        "om_invoice",
+        "om_invoice_line",
+        "om_stock_line",
+        "om_stocktake",
+        "om_stocktake_line",
+        "om_requisition",
+        "om_requisition_line",
+    ] {
+        assert!(
+            om_records.iter().any(|r| r.record.table_name == table_name),
+            "No {} records",
+            table_name
+        );
+    }
+
+    // om_* translators, with the same non transactional records
+    let (legacy_transactional_records, other_records): (Vec<_>, Vec<_>) =
+        test_records.into_iter().partition(|r| {
+            LEGACY_TRANSACTIONAL_TABLES.contains(&r.sync_buffer_row.table_name.as_str())
+        });
+    insert_all_extra_data(&legacy_transactional_records, &om_connection).await;
+    integrate_test_records(&om_connection, &other_records).await;
+    let buffer_rows: Vec<SyncBufferRow> = om_records
+        .iter()
+        .map(|r| r.record.clone().to_buffer_row(None).unwrap())
+        .collect();
+    SyncBufferRowRepository::new(&om_connection)
+        .upsert_many(&buffer_rows)
+        .unwrap();
+    integrate_and_translate_sync_buffer(&om_connection, true, None, None).unwrap();
+    assert_no_om_integration_errors(&om_connection);
+
+    for om_record in om_records {
+        let id = &om_record.record.record_id;
+        let table_name = om_record.record.table_name.as_str();
+        match table_name {
+            "om_invoice" => assert_same_row(
+                table_name,
+                id,
+                InvoiceRowRepository::new(&legacy_connection)
+                    .find_one_by_id_option(id)
+                    .unwrap(),
+                InvoiceRowRepository::new(&om_connection)
+                    .find_one_by_id_option(id)
+                    .unwrap(),
+            ),
+            "om_invoice_line" => assert_same_row(
+                table_name,
+                id,
+                InvoiceLineRowRepository::new(&legacy_connection)
+                    .find_one_by_id_option(id)
+                    .unwrap(),
+                InvoiceLineRowRepository::new(&om_connection)
+                    .find_one_by_id_option(id)
+                    .unwrap(),
+            ),
+            "om_stock_line" => assert_same_row(
+                table_name,
+                id,
+                StockLineRowRepository::new(&legacy_connection)
+                    .find_one_by_id_option(id)
+                    .unwrap(),
+                StockLineRowRepository::new(&om_connection)
+                    .find_one_by_id_option(id)
+                    .unwrap(),
+            ),
+            "om_stocktake" => assert_same_row(
+                table_name,
+                id,
+                StocktakeRowRepository::new(&legacy_connection)
+                    .find_one_by_id(id)
+                    .unwrap(),
+                StocktakeRowRepository::new(&om_connection)
+                    .find_one_by_id(id)
+                    .unwrap(),
+            ),
+            "om_stocktake_line" => assert_same_row(
+                table_name,
+                id,
+                StocktakeLineRowRepository::new(&legacy_connection)
+                    .find_one_by_id(id)
+                    .unwrap(),
+                StocktakeLineRowRepository::new(&om_connection)
+                    .find_one_by_id(id)
+                    .unwrap(),
+            ),
+            "om_requisition" => assert_same_row(
+                table_name,
+                id,
+                RequisitionRowRepository::new(&legacy_connection)
+                    .find_one_by_id(id)
+                    .unwrap(),
+                RequisitionRowRepository::new(&om_connection)
+                    .find_one_by_id(id)
+                    .unwrap(),
+            ),
+            "om_requisition_line" => assert_same_row(
+                table_name,
+                id,
+                RequisitionLineRowRepository::new(&legacy_connection)
+                    .find_one_by_id(id)
+                    .unwrap(),
+                RequisitionLineRowRepository::new(&om_connection)
+                    .find_one_by_id(id)
+                    .unwrap(),
+            ),
+            _ => panic!("Unexpected table {}", table_name),
+        }
+    }
+}
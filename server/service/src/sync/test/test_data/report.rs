@@ -3,6 +3,8 @@ use repository::{ContextType, ReportRow, ReportRowDelete, ReportType};
 
 const TABLE_NAME: &str = "report";
 
+const REPORT_1_TEMPLATE: &str = r#"{"index":{"template":"template.html","query":"query"},"version":"1.0.0","entries":{"template.html":{"type":"TeraTemplate","data":{"output":"Html","template":"{{data.stocktake.id}}"}},"query":{"type":"DefaultQuery","data":"Stocktake"}}}"#;
+
 const REPORT_1: (&str, &str) = (
     "76B6C424E1935C4DAF36A7A8F451FE72",
     r#"{
@@ -20,7 +22,7 @@ const REPORT_1: (&str, &str) = (
         "editor": "omsupply",
         "orientation": "",
         "disabled": false,
-        "template": "template data",
+        "template": "{\"index\":{\"template\":\"template.html\",\"query\":\"query\"},\"version\":\"1.0.0\",\"entries\":{\"template.html\":{\"type\":\"TeraTemplate\",\"data\":{\"output\":\"Html\",\"template\":\"{{data.stocktake.id}}\"}},\"query\":{\"type\":\"DefaultQuery\",\"data\":\"Stocktake\"}}}",
         "sub_context": "",
         "form_schema_ID": ""
     }"#,
@@ -34,11 +36,12 @@ pub(crate) fn test_pull_upsert_records() -> Vec<TestSyncIncomingRecord> {
             id: REPORT_1.0.to_string(),
             name: "Test".to_string(),
             r#type: ReportType::OmSupply,
-            template: "template data".to_string(),
+            template: REPORT_1_TEMPLATE.to_string(),
             context: ContextType::Stocktake,
             comment: Some("Test comment".to_string()),
             sub_context: None,
             argument_schema_id: None,
+            version: Some("1.0.0".to_string()),
         },
     )]
 }
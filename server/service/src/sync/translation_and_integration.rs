@@ -1,5 +1,6 @@
 use super::sync_status::logger::{SyncLogger, SyncLoggerError, SyncStepProgress};
 use super::{
+    conflicts::record_conflict,
     sync_buffer::SyncBuffer,
     translations::{IntegrationOperation, PullTranslateResult, SyncTranslation, SyncTranslators},
 };
@@ -13,12 +14,14 @@ static PROGRESS_STEP_LEN: usize = 100;
 pub(crate) struct TranslationAndIntegration<'a> {
     connection: &'a StorageConnection,
     sync_buffer: &'a SyncBuffer<'a>,
+    detect_conflicts: bool,
 }
 
 #[derive(Default, Debug)]
 pub(crate) struct TranslationAndIntegrationResult {
     pub(crate) integrated_count: u32,
     pub(crate) errors_count: u32,
+    pub(crate) conflicts_count: u32,
 }
 type TableName = String;
 #[derive(Default, Debug)]
@@ -32,9 +35,17 @@ impl<'a> TranslationAndIntegration<'a> {
         TranslationAndIntegration {
             connection,
             sync_buffer,
+            detect_conflicts: true,
         }
     }
 
+    /// Central records overwrite local changes, used when a conflict is resolved in favour of
+    /// the central record
+    pub(crate) fn without_conflict_detection(mut self) -> Self {
+        self.detect_conflicts = false;
+        self
+    }
+
     // Go through each translator, adding translations to result, if no translators matched return None
     fn translate_sync_record(
         &self,
@@ -107,6 +118,14 @@ impl<'a> TranslationAndIntegration<'a> {
         };
 
         for (number_of_records_integrated, sync_record) in sync_records.into_iter().enumerate() {
+            // Record is kept in sync_conflict instead of overwriting local changes
+            if self.detect_conflicts && record_conflict(self.connection, &sync_record)? {
+                self.sync_buffer
+                    .record_successful_integration(&sync_record)?;
+                result.insert_conflict(&sync_record.table_name);
+                continue;
+            }
+
             let translation_result = match self.translate_sync_record(&sync_record, translators) {
                 Ok(translation_result) => translation_result,
                 // Record error in sync buffer and in result, continue to next sync_record
@@ -236,6 +255,11 @@ impl TranslationAndIntegrationResults {
         let entry = self.0.entry(table_name.to_owned()).or_default();
         entry.integrated_count += 1;
     }
+
+    fn insert_conflict(&mut self, table_name: &str) {
+        let entry = self.0.entry(table_name.to_owned()).or_default();
+        entry.conflicts_count += 1;
+    }
 }
 
 #[cfg(test)]
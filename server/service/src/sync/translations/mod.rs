@@ -28,6 +28,13 @@ pub(crate) mod name;
 pub(crate) mod name_store_join;
 pub(crate) mod name_tag;
 pub(crate) mod name_tag_join;
+pub(crate) mod om_invoice;
+pub(crate) mod om_invoice_line;
+pub(crate) mod om_requisition;
+pub(crate) mod om_requisition_line;
+pub(crate) mod om_stock_line;
+pub(crate) mod om_stocktake;
+pub(crate) mod om_stocktake_line;
 pub(crate) mod pack_variant;
 pub(crate) mod period;
 pub(crate) mod period_schedule;
@@ -98,6 +105,14 @@ pub(crate) fn all_translators() -> SyncTranslators {
         user_permission::boxed(),
         document::boxed(),
         currency::boxed(),
+        // Transactional, synced with omSupply central when transactional v6 sync is on
+        om_stock_line::boxed(),
+        om_invoice::boxed(),
+        om_invoice_line::boxed(),
+        om_stocktake::boxed(),
+        om_stocktake_line::boxed(),
+        om_requisition::boxed(),
+        om_requisition_line::boxed(),
         // Cold chain
         sensor::boxed(),
         temperature_breach::boxed(),
@@ -0,0 +1,116 @@
+use repository::{
+    ChangelogRow, ChangelogTableName, EqualFilter, Invoice, InvoiceFilter, InvoiceRepository,
+    InvoiceRow, InvoiceRowDelete, StorageConnection, SyncBufferRow,
+};
+
+use crate::sync::translations::{
+    clinician::ClinicianTranslation, currency::CurrencyTranslation, name::NameTranslation,
+    store::StoreTranslation,
+};
+
+use super::{
+    PullTranslateResult, PushTranslateResult, SyncTranslation, ToSyncRecordTranslationType,
+};
+
+// Needs to be added to all_translators()
+#[deny(dead_code)]
+pub(crate) fn boxed() -> Box<dyn SyncTranslation> {
+    Box::new(OmInvoiceTranslation)
+}
+
+/// Invoice synced with omSupply central (v6), see `transactional_sync_v6` setting
+pub(crate) struct OmInvoiceTranslation;
+
+impl SyncTranslation for OmInvoiceTranslation {
+    fn table_name(&self) -> &'static str {
+        "om_invoice"
+    }
+
+    fn pull_dependencies(&self) -> Vec<&'static str> {
+        vec![
+            NameTranslation.table_name(),
+            StoreTranslation.table_name(),
+            ClinicianTranslation.table_name(),
+            CurrencyTranslation.table_name(),
+        ]
+    }
+
+    fn try_translate_from_upsert_sync_record(
+        &self,
+        _: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        Ok(PullTranslateResult::upsert(serde_json::from_str::<
+            InvoiceRow,
+        >(&sync_record.data)?))
+    }
+
+    fn try_translate_from_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        Ok(PullTranslateResult::delete(InvoiceRowDelete(
+            sync_record.record_id.clone(),
+        )))
+    }
+
+    fn change_log_type(&self) -> Option<ChangelogTableName> {
+        Some(ChangelogTableName::Invoice)
+    }
+
+    fn should_translate_to_sync_record(
+        &self,
+        row: &ChangelogRow,
+        r#type: &ToSyncRecordTranslationType,
+    ) -> bool {
+        match r#type {
+            ToSyncRecordTranslationType::PullFromOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            ToSyncRecordTranslationType::PushToOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            _ => false,
+        }
+    }
+
+    fn try_translate_to_upsert_sync_record(
+        &self,
+        connection: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        let Invoice {
+            invoice_row,
+            name_row,
+            clinician_row,
+            ..
+        } = InvoiceRepository::new(connection)
+            .query_one(InvoiceFilter::new().id(EqualFilter::equal_to(&changelog.record_id)))?
+            .ok_or(anyhow::Error::msg(format!(
+                "Invoice row ({}) not found",
+                changelog.record_id
+            )))?;
+
+        // Links are sent as the (merged) record they point to
+        let row = InvoiceRow {
+            name_link_id: name_row.id,
+            clinician_link_id: clinician_row.map(|clinician| clinician.id),
+            ..invoice_row
+        };
+
+        Ok(PushTranslateResult::upsert(
+            changelog,
+            self.table_name(),
+            serde_json::to_value(&row)?,
+        ))
+    }
+
+    fn try_translate_to_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        Ok(PushTranslateResult::delete(changelog, self.table_name()))
+    }
+}
@@ -0,0 +1,134 @@
+use repository::{
+    ChangelogRow, ChangelogTableName, EqualFilter, InvoiceLine, InvoiceLineFilter,
+    InvoiceLineRepository, InvoiceLineRow, InvoiceLineRowDelete, StorageConnection, SyncBufferRow,
+};
+
+use crate::sync::translations::{
+    currency::CurrencyTranslation, item::ItemTranslation, location::LocationTranslation,
+    om_invoice::OmInvoiceTranslation, om_stock_line::OmStockLineTranslation,
+    reason::ReasonTranslation,
+};
+
+use super::{
+    is_active_record_on_site, utils::clear_invalid_location_id, ActiveRecordCheck,
+    PullTranslateResult, PushTranslateResult, SyncTranslation, ToSyncRecordTranslationType,
+};
+
+// Needs to be added to all_translators()
+#[deny(dead_code)]
+pub(crate) fn boxed() -> Box<dyn SyncTranslation> {
+    Box::new(OmInvoiceLineTranslation)
+}
+
+/// Invoice line synced with omSupply central (v6), see `transactional_sync_v6` setting
+pub(crate) struct OmInvoiceLineTranslation;
+
+impl SyncTranslation for OmInvoiceLineTranslation {
+    fn table_name(&self) -> &'static str {
+        "om_invoice_line"
+    }
+
+    fn pull_dependencies(&self) -> Vec<&'static str> {
+        vec![
+            OmInvoiceTranslation.table_name(),
+            ItemTranslation.table_name(),
+            OmStockLineTranslation.table_name(),
+            LocationTranslation.table_name(),
+            ReasonTranslation.table_name(),
+            CurrencyTranslation.table_name(),
+        ]
+    }
+
+    fn try_translate_from_upsert_sync_record(
+        &self,
+        connection: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        let row = serde_json::from_str::<InvoiceLineRow>(&sync_record.data)?;
+
+        // Lines of transfers from another site refer to stock lines and locations of that site
+        let is_record_active_on_site = is_active_record_on_site(
+            connection,
+            ActiveRecordCheck::InvoiceLine {
+                invoice_id: row.invoice_id.clone(),
+            },
+        )?;
+        let stock_line_id = match is_record_active_on_site {
+            true => row.stock_line_id,
+            false => None,
+        };
+        let location_id = clear_invalid_location_id(connection, row.location_id)?;
+
+        Ok(PullTranslateResult::upsert(InvoiceLineRow {
+            stock_line_id,
+            location_id,
+            ..row
+        }))
+    }
+
+    fn try_translate_from_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        Ok(PullTranslateResult::delete(InvoiceLineRowDelete(
+            sync_record.record_id.clone(),
+        )))
+    }
+
+    fn change_log_type(&self) -> Option<ChangelogTableName> {
+        Some(ChangelogTableName::InvoiceLine)
+    }
+
+    fn should_translate_to_sync_record(
+        &self,
+        row: &ChangelogRow,
+        r#type: &ToSyncRecordTranslationType,
+    ) -> bool {
+        match r#type {
+            ToSyncRecordTranslationType::PullFromOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            ToSyncRecordTranslationType::PushToOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            _ => false,
+        }
+    }
+
+    fn try_translate_to_upsert_sync_record(
+        &self,
+        connection: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        let InvoiceLine {
+            invoice_line_row,
+            item_row,
+            ..
+        } = InvoiceLineRepository::new(connection)
+            .query_one(InvoiceLineFilter::new().id(EqualFilter::equal_to(&changelog.record_id)))?
+            .ok_or(anyhow::Error::msg(format!(
+                "Invoice line row ({}) not found",
+                changelog.record_id
+            )))?;
+
+        let row = InvoiceLineRow {
+            item_link_id: item_row.id,
+            ..invoice_line_row
+        };
+
+        Ok(PushTranslateResult::upsert(
+            changelog,
+            self.table_name(),
+            serde_json::to_value(&row)?,
+        ))
+    }
+
+    fn try_translate_to_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        Ok(PushTranslateResult::delete(changelog, self.table_name()))
+    }
+}
@@ -0,0 +1,113 @@
+use repository::{
+    ChangelogRow, ChangelogTableName, EqualFilter, Requisition, RequisitionFilter,
+    RequisitionRepository, RequisitionRow, RequisitionRowDelete, StorageConnection, SyncBufferRow,
+};
+
+use crate::sync::translations::{
+    master_list::MasterListTranslation, name::NameTranslation, period::PeriodTranslation,
+    store::StoreTranslation,
+};
+
+use super::{
+    PullTranslateResult, PushTranslateResult, SyncTranslation, ToSyncRecordTranslationType,
+};
+
+// Needs to be added to all_translators()
+#[deny(dead_code)]
+pub(crate) fn boxed() -> Box<dyn SyncTranslation> {
+    Box::new(OmRequisitionTranslation)
+}
+
+/// Requisition synced with omSupply central (v6), see `transactional_sync_v6` setting
+pub(crate) struct OmRequisitionTranslation;
+
+impl SyncTranslation for OmRequisitionTranslation {
+    fn table_name(&self) -> &'static str {
+        "om_requisition"
+    }
+
+    fn pull_dependencies(&self) -> Vec<&'static str> {
+        vec![
+            NameTranslation.table_name(),
+            StoreTranslation.table_name(),
+            PeriodTranslation.table_name(),
+            MasterListTranslation.table_name(),
+        ]
+    }
+
+    fn try_translate_from_upsert_sync_record(
+        &self,
+        _: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        Ok(PullTranslateResult::upsert(serde_json::from_str::<
+            RequisitionRow,
+        >(&sync_record.data)?))
+    }
+
+    fn try_translate_from_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        Ok(PullTranslateResult::delete(RequisitionRowDelete(
+            sync_record.record_id.clone(),
+        )))
+    }
+
+    fn change_log_type(&self) -> Option<ChangelogTableName> {
+        Some(ChangelogTableName::Requisition)
+    }
+
+    fn should_translate_to_sync_record(
+        &self,
+        row: &ChangelogRow,
+        r#type: &ToSyncRecordTranslationType,
+    ) -> bool {
+        match r#type {
+            ToSyncRecordTranslationType::PullFromOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            ToSyncRecordTranslationType::PushToOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            _ => false,
+        }
+    }
+
+    fn try_translate_to_upsert_sync_record(
+        &self,
+        connection: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        let Requisition {
+            requisition_row,
+            name_row,
+            ..
+        } = RequisitionRepository::new(connection)
+            .query_one(RequisitionFilter::new().id(EqualFilter::equal_to(&changelog.record_id)))?
+            .ok_or(anyhow::Error::msg(format!(
+                "Requisition row ({}) not found",
+                changelog.record_id
+            )))?;
+
+        let row = RequisitionRow {
+            name_link_id: name_row.id,
+            ..requisition_row
+        };
+
+        Ok(PushTranslateResult::upsert(
+            changelog,
+            self.table_name(),
+            serde_json::to_value(&row)?,
+        ))
+    }
+
+    fn try_translate_to_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        Ok(PushTranslateResult::delete(changelog, self.table_name()))
+    }
+}
@@ -0,0 +1,113 @@
+use repository::{
+    ChangelogRow, ChangelogTableName, ItemLinkRowRepository, RequisitionLineRow,
+    RequisitionLineRowDelete, RequisitionLineRowRepository, StorageConnection, SyncBufferRow,
+};
+
+use crate::sync::translations::{item::ItemTranslation, om_requisition::OmRequisitionTranslation};
+
+use super::{
+    PullTranslateResult, PushTranslateResult, SyncTranslation, ToSyncRecordTranslationType,
+};
+
+// Needs to be added to all_translators()
+#[deny(dead_code)]
+pub(crate) fn boxed() -> Box<dyn SyncTranslation> {
+    Box::new(OmRequisitionLineTranslation)
+}
+
+/// Requisition line synced with omSupply central (v6), see `transactional_sync_v6` setting
+pub(crate) struct OmRequisitionLineTranslation;
+
+impl SyncTranslation for OmRequisitionLineTranslation {
+    fn table_name(&self) -> &'static str {
+        "om_requisition_line"
+    }
+
+    fn pull_dependencies(&self) -> Vec<&'static str> {
+        vec![
+            OmRequisitionTranslation.table_name(),
+            ItemTranslation.table_name(),
+        ]
+    }
+
+    fn try_translate_from_upsert_sync_record(
+        &self,
+        _: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        Ok(PullTranslateResult::upsert(serde_json::from_str::<
+            RequisitionLineRow,
+        >(&sync_record.data)?))
+    }
+
+    fn try_translate_from_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        Ok(PullTranslateResult::delete(RequisitionLineRowDelete(
+            sync_record.record_id.clone(),
+        )))
+    }
+
+    fn change_log_type(&self) -> Option<ChangelogTableName> {
+        Some(ChangelogTableName::RequisitionLine)
+    }
+
+    fn should_translate_to_sync_record(
+        &self,
+        row: &ChangelogRow,
+        r#type: &ToSyncRecordTranslationType,
+    ) -> bool {
+        match r#type {
+            ToSyncRecordTranslationType::PullFromOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            ToSyncRecordTranslationType::PushToOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            _ => false,
+        }
+    }
+
+    fn try_translate_to_upsert_sync_record(
+        &self,
+        connection: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        let requisition_line = RequisitionLineRowRepository::new(connection)
+            .find_one_by_id(&changelog.record_id)?
+            .ok_or(anyhow::Error::msg(format!(
+                "Requisition line row ({}) not found",
+                changelog.record_id
+            )))?;
+
+        let item_id = ItemLinkRowRepository::new(connection)
+            .find_one_by_id(&requisition_line.item_link_id)?
+            .ok_or(anyhow::anyhow!(
+                "Item link ({}) not found in requisition line ({})",
+                requisition_line.item_link_id,
+                requisition_line.id
+            ))?
+            .item_id;
+
+        let row = RequisitionLineRow {
+            item_link_id: item_id,
+            ..requisition_line
+        };
+
+        Ok(PushTranslateResult::upsert(
+            changelog,
+            self.table_name(),
+            serde_json::to_value(&row)?,
+        ))
+    }
+
+    fn try_translate_to_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        Ok(PushTranslateResult::delete(changelog, self.table_name()))
+    }
+}
@@ -0,0 +1,128 @@
+use repository::{
+    ChangelogRow, ChangelogTableName, EqualFilter, StockLine, StockLineFilter, StockLineRepository,
+    StockLineRow, StockLineRowDelete, StorageConnection, SyncBufferRow,
+};
+
+use crate::sync::translations::{
+    barcode::BarcodeTranslation, item::ItemTranslation, location::LocationTranslation,
+    name::NameTranslation, store::StoreTranslation,
+};
+
+use super::{
+    utils::{clear_invalid_barcode_id, clear_invalid_location_id},
+    PullTranslateResult, PushTranslateResult, SyncTranslation, ToSyncRecordTranslationType,
+};
+
+// Needs to be added to all_translators()
+#[deny(dead_code)]
+pub(crate) fn boxed() -> Box<dyn SyncTranslation> {
+    Box::new(OmStockLineTranslation)
+}
+
+/// Stock line synced with omSupply central (v6), see `transactional_sync_v6` setting
+pub(crate) struct OmStockLineTranslation;
+
+impl SyncTranslation for OmStockLineTranslation {
+    fn table_name(&self) -> &'static str {
+        "om_stock_line"
+    }
+
+    fn pull_dependencies(&self) -> Vec<&'static str> {
+        vec![
+            ItemTranslation.table_name(),
+            NameTranslation.table_name(),
+            StoreTranslation.table_name(),
+            LocationTranslation.table_name(),
+            BarcodeTranslation.table_name(),
+        ]
+    }
+
+    fn try_translate_from_upsert_sync_record(
+        &self,
+        connection: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        let row = serde_json::from_str::<StockLineRow>(&sync_record.data)?;
+
+        let barcode_id = clear_invalid_barcode_id(connection, row.barcode_id)?;
+        let location_id = clear_invalid_location_id(connection, row.location_id)?;
+
+        Ok(PullTranslateResult::upsert(StockLineRow {
+            barcode_id,
+            location_id,
+            ..row
+        }))
+    }
+
+    fn try_translate_from_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        Ok(PullTranslateResult::delete(StockLineRowDelete(
+            sync_record.record_id.clone(),
+        )))
+    }
+
+    fn change_log_type(&self) -> Option<ChangelogTableName> {
+        Some(ChangelogTableName::StockLine)
+    }
+
+    fn should_translate_to_sync_record(
+        &self,
+        row: &ChangelogRow,
+        r#type: &ToSyncRecordTranslationType,
+    ) -> bool {
+        match r#type {
+            ToSyncRecordTranslationType::PullFromOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            ToSyncRecordTranslationType::PushToOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            _ => false,
+        }
+    }
+
+    fn try_translate_to_upsert_sync_record(
+        &self,
+        connection: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        let StockLine {
+            stock_line_row,
+            item_row,
+            supplier_name_row,
+            ..
+        } = StockLineRepository::new(connection)
+            .query_by_filter(
+                StockLineFilter::new().id(EqualFilter::equal_to(&changelog.record_id)),
+                None,
+            )?
+            .pop()
+            .ok_or(anyhow::Error::msg(format!(
+                "Stock line row ({}) not found",
+                changelog.record_id
+            )))?;
+
+        let row = StockLineRow {
+            item_link_id: item_row.id,
+            supplier_link_id: supplier_name_row.map(|supplier| supplier.id),
+            ..stock_line_row
+        };
+
+        Ok(PushTranslateResult::upsert(
+            changelog,
+            self.table_name(),
+            serde_json::to_value(&row)?,
+        ))
+    }
+
+    fn try_translate_to_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        Ok(PushTranslateResult::delete(changelog, self.table_name()))
+    }
+}
@@ -0,0 +1,120 @@
+use repository::{
+    ChangelogRow, ChangelogTableName, EqualFilter, StocktakeLine, StocktakeLineFilter,
+    StocktakeLineRepository, StocktakeLineRow, StocktakeLineRowDelete, StorageConnection,
+    SyncBufferRow,
+};
+
+use crate::sync::translations::{
+    item::ItemTranslation, location::LocationTranslation, om_stock_line::OmStockLineTranslation,
+    om_stocktake::OmStocktakeTranslation, reason::ReasonTranslation,
+};
+
+use super::{
+    utils::clear_invalid_location_id, PullTranslateResult, PushTranslateResult, SyncTranslation,
+    ToSyncRecordTranslationType,
+};
+
+// Needs to be added to all_translators()
+#[deny(dead_code)]
+pub(crate) fn boxed() -> Box<dyn SyncTranslation> {
+    Box::new(OmStocktakeLineTranslation)
+}
+
+/// Stocktake line synced with omSupply central (v6), see `transactional_sync_v6` setting
+pub(crate) struct OmStocktakeLineTranslation;
+
+impl SyncTranslation for OmStocktakeLineTranslation {
+    fn table_name(&self) -> &'static str {
+        "om_stocktake_line"
+    }
+
+    fn pull_dependencies(&self) -> Vec<&'static str> {
+        vec![
+            OmStocktakeTranslation.table_name(),
+            OmStockLineTranslation.table_name(),
+            ItemTranslation.table_name(),
+            LocationTranslation.table_name(),
+            ReasonTranslation.table_name(),
+        ]
+    }
+
+    fn try_translate_from_upsert_sync_record(
+        &self,
+        connection: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        let row = serde_json::from_str::<StocktakeLineRow>(&sync_record.data)?;
+        let location_id = clear_invalid_location_id(connection, row.location_id)?;
+
+        Ok(PullTranslateResult::upsert(StocktakeLineRow {
+            location_id,
+            ..row
+        }))
+    }
+
+    fn try_translate_from_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        sync_record: &SyncBufferRow,
+    ) -> Result<PullTranslateResult, anyhow::Error> {
+        Ok(PullTranslateResult::delete(StocktakeLineRowDelete(
+            sync_record.record_id.clone(),
+        )))
+    }
+
+    fn change_log_type(&self) -> Option<ChangelogTableName> {
+        Some(ChangelogTableName::StocktakeLine)
+    }
+
+    fn should_translate_to_sync_record(
+        &self,
+        row: &ChangelogRow,
+        r#type: &ToSyncRecordTranslationType,
+    ) -> bool {
+        match r#type {
+            ToSyncRecordTranslationType::PullFromOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            ToSyncRecordTranslationType::PushToOmSupplyCentral => {
+                self.change_log_type().as_ref() == Some(&row.table_name)
+            }
+            _ => false,
+        }
+    }
+
+    fn try_translate_to_upsert_sync_record(
+        &self,
+        connection: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        let StocktakeLine { line, item, .. } = StocktakeLineRepository::new(connection)
+            .query_by_filter(
+                StocktakeLineFilter::new().id(EqualFilter::equal_to(&changelog.record_id)),
+                None,
+            )?
+            .pop()
+            .ok_or(anyhow::Error::msg(format!(
+                "Stocktake line row ({}) not found",
+                changelog.record_id
+            )))?;
+
+        let row = StocktakeLineRow {
+            item_link_id: item.id,
+            ..line
+        };
+
+        Ok(PushTranslateResult::upsert(
+            changelog,
+            self.table_name(),
+            serde_json::to_value(&row)?,
+        ))
+    }
+
+    fn try_translate_to_delete_sync_record(
+        &self,
+        _: &StorageConnection,
+        changelog: &ChangelogRow,
+    ) -> Result<PushTranslateResult, anyhow::Error> {
+        Ok(PushTranslateResult::delete(changelog, self.table_name()))
+    }
+}
@@ -1,8 +1,17 @@
-use crate::sync::{
-    sync_serde::empty_str_as_option_string, translations::form_schema::FormSchemaTranslation,
+use std::cmp::Ordering;
+
+use crate::{
+    report::{
+        definition::ReportDefinition,
+        validation::{compare_versions, validate_report, ReportValidationError},
+    },
+    sync::{
+        sync_serde::empty_str_as_option_string, translations::form_schema::FormSchemaTranslation,
+    },
 };
 use repository::{
-    ContextType, ReportRow, ReportRowDelete, ReportType, StorageConnection, SyncBufferRow,
+    ContextType, ReportRow, ReportRowDelete, ReportRowRepository, ReportType, RepositoryError,
+    StorageConnection, SyncBufferRow, SyncBufferRowRepository,
 };
 
 use serde::{Deserialize, Serialize};
@@ -77,7 +86,7 @@ impl SyncTranslation for ReportTranslation {
 
     fn try_translate_from_upsert_sync_record(
         &self,
-        _: &StorageConnection,
+        connection: &StorageConnection,
         sync_record: &SyncBufferRow,
     ) -> Result<PullTranslateResult, anyhow::Error> {
         let LegacyReportRow {
@@ -110,7 +119,7 @@ impl SyncTranslation for ReportTranslation {
             }
         };
 
-        let result = ReportRow {
+        let mut result = ReportRow {
             id,
             name: report_name,
             r#type,
@@ -119,8 +128,28 @@ impl SyncTranslation for ReportTranslation {
             comment,
             sub_context,
             argument_schema_id,
+            version: None,
         };
 
+        // An invalid report becomes an integration error and the installed report is kept
+        let errors = validation_errors(connection, &result)?;
+        if !errors.is_empty() {
+            return Err(anyhow::anyhow!("Invalid report: {}", errors.join("; ")));
+        }
+        result.version = serde_json::from_str::<ReportDefinition>(&result.template)?.version;
+
+        // Only a report that is older than the installed one is skipped, when either version is
+        // missing or invalid central stays authoritative
+        if let Some(installed) = ReportRowRepository::new(connection).find_one_by_id(&result.id)? {
+            if compare_versions(&result.version, &installed.version) == Some(Ordering::Less) {
+                return Ok(PullTranslateResult::Ignored(format!(
+                    "Report version {} is older than the installed version {}",
+                    result.version.unwrap_or_default(),
+                    installed.version.unwrap_or_default()
+                )));
+            }
+        }
+
         Ok(PullTranslateResult::upsert(result))
     }
 
@@ -135,6 +164,29 @@ impl SyncTranslation for ReportTranslation {
     }
 }
 
+/// Validation errors of the report, references to reports that are still waiting in the sync
+/// buffer are not errors since they are resolved once the referenced report is integrated
+fn validation_errors(
+    connection: &StorageConnection,
+    report: &ReportRow,
+) -> Result<Vec<String>, RepositoryError> {
+    let sync_buffer = SyncBufferRowRepository::new(connection);
+    let mut errors = Vec::new();
+    for error in validate_report(connection, report)? {
+        if let ReportValidationError::ReferenceNotFound { source, .. } = &error {
+            let is_pending = sync_buffer
+                .find_one_by_record_id(source)?
+                .map(|row| row.table_name == "report" && row.integration_datetime.is_none())
+                .unwrap_or(false);
+            if is_pending {
+                continue;
+            }
+        }
+        errors.push(error.to_string());
+    }
+    Ok(errors)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -166,4 +218,104 @@ mod tests {
             assert_eq!(translation_result, record.translated_record);
         }
     }
+
+    #[actix_rt::test]
+    async fn test_report_translation_validation() {
+        use serde_json::json;
+        let translator = ReportTranslation {};
+
+        let (_, connection, _, _) = setup_all(
+            "test_report_translation_validation",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let definition = |version: Option<&str>, template: &str| {
+            json!({
+                "index": { "template": "template.html", "query": "query" },
+                "version": version,
+                "entries": {
+                    "template.html": {
+                        "type": "TeraTemplate",
+                        "data": { "output": "Html", "template": template }
+                    },
+                    "query": { "type": "DefaultQuery", "data": "Stocktake" }
+                }
+            })
+            .to_string()
+        };
+        let record = |template: &str| SyncBufferRow {
+            record_id: "report".to_string(),
+            table_name: "report".to_string(),
+            data: json!({
+                "ID": "report",
+                "report_name": "Report",
+                "editor": "omsupply",
+                "context": "Stock Take",
+                "template": template,
+                "Comment": "",
+                "sub_context": "",
+                "form_schema_ID": ""
+            })
+            .to_string(),
+            ..Default::default()
+        };
+        let installed = ReportRow {
+            id: "report".to_string(),
+            name: "Report".to_string(),
+            template: definition(Some("1.1.0"), "{{ data.id }}"),
+            context: ContextType::Stocktake,
+            version: Some("1.1.0".to_string()),
+            ..Default::default()
+        };
+        ReportRowRepository::new(&connection)
+            .upsert_one(&installed)
+            .unwrap();
+
+        // broken template
+        let result = translator.try_translate_from_upsert_sync_record(
+            &connection,
+            &record(&definition(Some("1.2.0"), "{{ data.id ")),
+        );
+        assert!(result.is_err());
+
+        // older version
+        let result = translator
+            .try_translate_from_upsert_sync_record(
+                &connection,
+                &record(&definition(Some("1.0.0"), "{{ data.name }}")),
+            )
+            .unwrap();
+        assert!(matches!(result, PullTranslateResult::Ignored(_)));
+
+        // unversioned, can't be compared with the installed version
+        let template = definition(None, "{{ data.name }}");
+        let result = translator
+            .try_translate_from_upsert_sync_record(&connection, &record(&template))
+            .unwrap();
+        assert_eq!(
+            result,
+            PullTranslateResult::upsert(ReportRow {
+                template,
+                comment: None,
+                version: None,
+                ..installed.clone()
+            })
+        );
+
+        // newer version
+        let template = definition(Some("1.2.0"), "{{ data.name }}");
+        let result = translator
+            .try_translate_from_upsert_sync_record(&connection, &record(&template))
+            .unwrap();
+        assert_eq!(
+            result,
+            PullTranslateResult::upsert(ReportRow {
+                template,
+                comment: None,
+                version: Some("1.2.0".to_string()),
+                ..installed
+            })
+        );
+    }
 }
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use actix_rt::task::JoinHandle;
 use repository::{
+    database_settings::DatabaseSettings,
     mock::{MockData, MockDataInserts},
     test_db::setup_all_with_data,
     StorageConnection, StorageConnectionManager,
@@ -28,17 +29,8 @@ pub(crate) struct ServiceTestContext {
     pub(crate) service_context: ServiceContext,
 }
 
-// TODO use this method in service tests
-pub(crate) async fn setup_all_with_data_and_service_provider(
-    db_name: &str,
-    inserts: MockDataInserts,
-    extra_mock_data: MockData,
-) -> ServiceTestContext {
-    let (_, connection, connection_manager, db_settings) =
-        setup_all_with_data(db_name, inserts, extra_mock_data).await;
-
-    let (processors_trigger, processors) = Processors::init();
-    let (file_sync_trigger, _) = FileSyncDriver::init(&Settings {
+pub(crate) fn test_settings(database: DatabaseSettings) -> Settings {
+    Settings {
         server: ServerSettings {
             port: 0,
             danger_allow_http: false,
@@ -46,11 +38,32 @@ pub(crate) async fn setup_all_with_data_and_service_provider(
             cors_origins: vec![],
             base_dir: None,
             machine_uid: None,
+            request_limits: Default::default(),
+            certificates: None,
+            metrics: None,
+            pdf_renderer: Default::default(),
+            report_cache: None,
         },
-        database: db_settings,
+        database,
         sync: None,
         logging: None,
-    });
+        peer_sync: None,
+        mail: None,
+        receipt_printer: None,
+    }
+}
+
+// TODO use this method in service tests
+pub(crate) async fn setup_all_with_data_and_service_provider(
+    db_name: &str,
+    inserts: MockDataInserts,
+    extra_mock_data: MockData,
+) -> ServiceTestContext {
+    let (_, connection, connection_manager, db_settings) =
+        setup_all_with_data(db_name, inserts, extra_mock_data).await;
+
+    let (processors_trigger, processors) = Processors::init();
+    let (file_sync_trigger, _) = FileSyncDriver::init(&test_settings(db_settings));
     let (sync_trigger, _) = SynchroniserDriver::init(file_sync_trigger);
     let (site_is_initialise_trigger, _) = SiteIsInitialisedCallback::init();
 